@@ -164,6 +164,19 @@ impl ActivityIndicator {
     }
 
     fn content_to_render(&mut self, cx: &mut ViewContext<Self>) -> Content {
+        // Show the progress of any large files being loaded.
+        if let Some((path, progress)) = self.project.read(cx).loading_files(cx).next() {
+            return Content {
+                icon: Some(DOWNLOAD_ICON),
+                message: format!(
+                    "Loading {} ({}%)",
+                    path.path.display(),
+                    progress.loaded * 100 / progress.total.max(1)
+                ),
+                on_click: None,
+            };
+        }
+
         // Show any language server has pending activity.
         let mut pending_work = self.pending_language_server_work(cx);
         if let Some(PendingWork {
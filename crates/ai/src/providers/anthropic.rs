@@ -0,0 +1,394 @@
+use anyhow::{anyhow, Result};
+use futures::{
+    future::{self, BoxFuture},
+    io::BufReader,
+    stream::BoxStream,
+    AsyncBufReadExt, AsyncReadExt, FutureExt, Stream, StreamExt,
+};
+use gpui::{AppContext, BackgroundExecutor};
+use isahc::{http::StatusCode, Request, RequestExt};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{env, io, sync::Arc};
+use util::ResultExt;
+
+use crate::{
+    auth::{CredentialProvider, ProviderCredential},
+    completion::{CompletionProvider, CompletionRequest},
+    models::LanguageModel,
+    providers::open_ai::{OpenAiLanguageModel, OpenAiRequest, RequestMessage, Role},
+};
+
+const ANTHROPIC_API_VERSION: &'static str = "2023-06-01";
+/// The API requires a limit on the tokens generated, which the requests the
+/// assistant builds don't set.
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// A request to the Messages API.
+#[derive(Debug, Serialize)]
+pub struct AnthropicRequest {
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    pub messages: Vec<RequestMessage>,
+    pub max_tokens: u32,
+    pub stream: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+    pub temperature: f32,
+}
+
+impl AnthropicRequest {
+    /// Translates a request in OpenAI's format. System messages become the system
+    /// prompt, and consecutive messages with the same role are merged, as the API
+    /// only accepts alternating user and assistant messages.
+    pub fn from_open_ai(request: OpenAiRequest) -> Self {
+        let mut system = None::<String>;
+        let mut messages = Vec::<RequestMessage>::new();
+        for message in request.messages {
+            if message.content.is_empty() {
+                continue;
+            }
+            if message.role == Role::System {
+                let system = system.get_or_insert_with(String::new);
+                if !system.is_empty() {
+                    system.push_str("\n\n");
+                }
+                system.push_str(&message.content);
+            } else if let Some(last_message) = messages
+                .last_mut()
+                .filter(|last_message| last_message.role == message.role)
+            {
+                last_message.content.push_str("\n\n");
+                last_message.content.push_str(&message.content);
+            } else {
+                messages.push(message);
+            }
+        }
+
+        Self {
+            model: request.model,
+            system,
+            messages,
+            max_tokens: DEFAULT_MAX_TOKENS,
+            stream: request.stream,
+            stop_sequences: request.stop,
+            // The API accepts temperatures between 0 and 1.
+            temperature: request.temperature.clamp(0., 1.),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnthropicEvent {
+    ContentBlockDelta {
+        delta: AnthropicDelta,
+    },
+    MessageStop,
+    Error {
+        error: AnthropicError,
+    },
+    /// An event that doesn't carry completion text, like `message_start` or `ping`.
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AnthropicDelta {
+    pub text: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AnthropicError {
+    pub message: String,
+}
+
+pub async fn stream_completion(
+    api_url: String,
+    api_key: String,
+    request: AnthropicRequest,
+) -> Result<impl Stream<Item = Result<AnthropicEvent>>> {
+    let mut response = Request::post(format!("{api_url}/messages"))
+        .header("Content-Type", "application/json")
+        .header("X-Api-Key", api_key)
+        .header("Anthropic-Version", ANTHROPIC_API_VERSION)
+        .body(serde_json::to_string(&request)?)?
+        .send_async()
+        .await?;
+
+    if response.status() == StatusCode::OK {
+        fn parse_line(line: Result<String, io::Error>) -> Result<Option<AnthropicEvent>> {
+            if let Some(data) = line?.strip_prefix("data: ") {
+                Ok(Some(serde_json::from_str(data)?))
+            } else {
+                Ok(None)
+            }
+        }
+
+        let lines = BufReader::new(response.into_body()).lines();
+        Ok(lines
+            .filter_map(|line| future::ready(parse_line(line).transpose()))
+            .take_while(|event| future::ready(!matches!(event, Ok(AnthropicEvent::MessageStop)))))
+    } else {
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).await?;
+
+        #[derive(Deserialize)]
+        struct AnthropicResponse {
+            error: AnthropicError,
+        }
+
+        match serde_json::from_str::<AnthropicResponse>(&body) {
+            Ok(response) if !response.error.message.is_empty() => Err(anyhow!(
+                "Failed to connect to Anthropic API: {}",
+                response.error.message,
+            )),
+            _ => Err(anyhow!(
+                "Failed to connect to Anthropic API: {} {}",
+                response.status(),
+                body,
+            )),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AnthropicCompletionProvider {
+    api_url: String,
+    model: OpenAiLanguageModel,
+    credential: Arc<RwLock<ProviderCredential>>,
+}
+
+impl AnthropicCompletionProvider {
+    pub async fn new(
+        api_url: String,
+        model_name: String,
+        capacity: usize,
+        executor: BackgroundExecutor,
+    ) -> Self {
+        let model = executor
+            .spawn(async move { OpenAiLanguageModel::load_with_capacity(&model_name, capacity) })
+            .await;
+        let credential = Arc::new(RwLock::new(ProviderCredential::NoCredentials));
+        Self {
+            api_url,
+            model,
+            credential,
+        }
+    }
+}
+
+impl CredentialProvider for AnthropicCompletionProvider {
+    fn has_credentials(&self) -> bool {
+        match *self.credential.read() {
+            ProviderCredential::Credentials { .. } => true,
+            _ => false,
+        }
+    }
+
+    fn retrieve_credentials(&self, cx: &mut AppContext) -> BoxFuture<ProviderCredential> {
+        let existing_credential = self.credential.read().clone();
+        let retrieved_credential = match existing_credential {
+            ProviderCredential::Credentials { .. } => {
+                return async move { existing_credential }.boxed()
+            }
+            _ => {
+                if let Some(api_key) = env::var("ANTHROPIC_API_KEY").log_err() {
+                    async move { ProviderCredential::Credentials { api_key } }.boxed()
+                } else {
+                    let credentials = cx.read_credentials(&self.api_url);
+                    async move {
+                        if let Some(Some((_, api_key))) = credentials.await.log_err() {
+                            if let Some(api_key) = String::from_utf8(api_key).log_err() {
+                                ProviderCredential::Credentials { api_key }
+                            } else {
+                                ProviderCredential::NoCredentials
+                            }
+                        } else {
+                            ProviderCredential::NoCredentials
+                        }
+                    }
+                    .boxed()
+                }
+            }
+        };
+
+        async move {
+            let retrieved_credential = retrieved_credential.await;
+            *self.credential.write() = retrieved_credential.clone();
+            retrieved_credential
+        }
+        .boxed()
+    }
+
+    fn save_credentials(
+        &self,
+        cx: &mut AppContext,
+        credential: ProviderCredential,
+    ) -> BoxFuture<()> {
+        *self.credential.write() = credential.clone();
+        let write_credentials = match credential {
+            ProviderCredential::Credentials { api_key } => {
+                Some(cx.write_credentials(&self.api_url, "Bearer", api_key.as_bytes()))
+            }
+            _ => None,
+        };
+
+        async move {
+            if let Some(write_credentials) = write_credentials {
+                write_credentials.await.log_err();
+            }
+        }
+        .boxed()
+    }
+
+    fn delete_credentials(&self, cx: &mut AppContext) -> BoxFuture<()> {
+        *self.credential.write() = ProviderCredential::NoCredentials;
+        let delete_credentials = cx.delete_credentials(&self.api_url);
+        async move {
+            delete_credentials.await.log_err();
+        }
+        .boxed()
+    }
+}
+
+impl CompletionProvider for AnthropicCompletionProvider {
+    fn base_model(&self) -> Box<dyn LanguageModel> {
+        let model: Box<dyn LanguageModel> = Box::new(self.model.clone());
+        model
+    }
+    fn complete(
+        &self,
+        prompt: Box<dyn CompletionRequest>,
+    ) -> BoxFuture<'static, Result<BoxStream<'static, Result<String>>>> {
+        let credential = self.credential.read().clone();
+        let api_url = self.api_url.clone();
+        async move {
+            let ProviderCredential::Credentials { api_key } = credential else {
+                return Err(anyhow!("no credentials provider for completion"));
+            };
+            let request = serde_json::from_str::<OpenAiRequest>(&prompt.data()?)?;
+            let events =
+                stream_completion(api_url, api_key, AnthropicRequest::from_open_ai(request))
+                    .await?;
+            let stream = events
+                .filter_map(|event| async move {
+                    match event {
+                        Ok(AnthropicEvent::ContentBlockDelta { delta }) => delta.text.map(Ok),
+                        Ok(AnthropicEvent::Error { error }) => Some(Err(anyhow!(error.message))),
+                        Ok(_) => None,
+                        Err(error) => Some(Err(error)),
+                    }
+                })
+                .boxed();
+            Ok(stream)
+        }
+        .boxed()
+    }
+    fn box_clone(&self) -> Box<dyn CompletionProvider> {
+        Box::new((*self).clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::FakeHttpServer;
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_translate_open_ai_request() {
+        let request = AnthropicRequest::from_open_ai(OpenAiRequest {
+            model: "claude-3-haiku-20240307".into(),
+            messages: vec![
+                message(Role::System, "Be brief."),
+                message(Role::User, "Hi"),
+                message(Role::User, "there"),
+                message(Role::Assistant, ""),
+                message(Role::System, "Use Rust."),
+            ],
+            stream: true,
+            stop: vec!["|END|>".into()],
+            temperature: 1.5,
+        });
+        assert_eq!(request.system.as_deref(), Some("Be brief.\n\nUse Rust."));
+        assert_eq!(request.messages, [message(Role::User, "Hi\n\nthere")]);
+        assert_eq!(request.stop_sequences, ["|END|>"]);
+        assert_eq!(request.temperature, 1.);
+    }
+
+    #[test]
+    fn test_stream_completion() {
+        let server = FakeHttpServer::serve(
+            200,
+            "text/event-stream",
+            concat!(
+                "event: message_start\n",
+                "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\"}}\n\n",
+                "event: content_block_delta\n",
+                "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello\"}}\n\n",
+                "event: ping\n",
+                "data: {\"type\":\"ping\"}\n\n",
+                "event: content_block_delta\n",
+                "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\", world\"}}\n\n",
+                "event: message_stop\n",
+                "data: {\"type\":\"message_stop\"}\n\n",
+            ),
+        );
+        let provider = AnthropicCompletionProvider {
+            api_url: server.url().into(),
+            model: OpenAiLanguageModel::load_with_capacity("claude-3-haiku-20240307", 200000),
+            credential: Arc::new(RwLock::new(ProviderCredential::Credentials {
+                api_key: "key".into(),
+            })),
+        };
+        let completion: Result<Vec<String>> = block_on(async {
+            let stream = provider.complete(request("Hi")).await?;
+            stream.collect::<Vec<_>>().await.into_iter().collect()
+        });
+        assert_eq!(completion.unwrap(), ["Hello", ", world"]);
+
+        let request = server.request();
+        assert!(request.head.starts_with("POST /messages "));
+        assert!(request.head.to_lowercase().contains("x-api-key: key"));
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["messages"][0]["content"], "Hi");
+        assert_eq!(body["max_tokens"], DEFAULT_MAX_TOKENS);
+    }
+
+    #[test]
+    fn test_stream_completion_error() {
+        let server = FakeHttpServer::serve(
+            401,
+            "application/json",
+            r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#,
+        );
+        let result = block_on(stream_completion(
+            server.url().into(),
+            "key".into(),
+            AnthropicRequest::from_open_ai(OpenAiRequest::default()),
+        ));
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Failed to connect to Anthropic API: invalid x-api-key"
+        );
+    }
+
+    fn message(role: Role, content: &str) -> RequestMessage {
+        RequestMessage {
+            role,
+            content: content.into(),
+        }
+    }
+
+    fn request(content: &str) -> Box<dyn CompletionRequest> {
+        Box::new(OpenAiRequest {
+            model: "claude-3-haiku-20240307".into(),
+            messages: vec![message(Role::User, content)],
+            stream: true,
+            stop: Vec::new(),
+            temperature: 1.,
+        })
+    }
+}
@@ -0,0 +1,247 @@
+use anyhow::{anyhow, Result};
+use futures::{
+    future::{self, BoxFuture},
+    io::BufReader,
+    stream::BoxStream,
+    AsyncBufReadExt, AsyncReadExt, FutureExt, Stream, StreamExt,
+};
+use gpui::{AppContext, BackgroundExecutor};
+use isahc::{http::StatusCode, Request, RequestExt};
+use serde::{Deserialize, Serialize};
+use std::io;
+
+use crate::{
+    auth::{CredentialProvider, ProviderCredential},
+    completion::{CompletionProvider, CompletionRequest},
+    models::LanguageModel,
+    providers::open_ai::{OpenAiLanguageModel, OpenAiRequest, RequestMessage, ResponseMessage},
+};
+
+/// A request to a local Ollama server's chat API.
+#[derive(Debug, Serialize)]
+pub struct OllamaRequest {
+    pub model: String,
+    pub messages: Vec<RequestMessage>,
+    pub stream: bool,
+    pub options: OllamaOptions,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OllamaOptions {
+    pub temperature: f32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+}
+
+impl From<OpenAiRequest> for OllamaRequest {
+    fn from(request: OpenAiRequest) -> Self {
+        Self {
+            model: request.model,
+            messages: request.messages,
+            stream: request.stream,
+            options: OllamaOptions {
+                temperature: request.temperature,
+                stop: request.stop,
+            },
+        }
+    }
+}
+
+/// A chunk of a streamed response, sent as a line of JSON.
+#[derive(Deserialize, Debug)]
+pub struct OllamaResponse {
+    pub message: Option<ResponseMessage>,
+    #[serde(default)]
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+pub async fn stream_completion(
+    api_url: String,
+    request: OllamaRequest,
+) -> Result<impl Stream<Item = Result<OllamaResponse>>> {
+    let mut response = Request::post(format!("{api_url}/api/chat"))
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&request)?)?
+        .send_async()
+        .await?;
+
+    if response.status() == StatusCode::OK {
+        fn parse_line(line: Result<String, io::Error>) -> Result<Option<OllamaResponse>> {
+            let line = line?;
+            if line.trim().is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(serde_json::from_str(&line)?))
+            }
+        }
+
+        let lines = BufReader::new(response.into_body()).lines();
+        let mut done = false;
+        Ok(lines
+            .filter_map(|line| future::ready(parse_line(line).transpose()))
+            .take_while(move |response| {
+                let was_done = done;
+                done = response.as_ref().map_or(false, |response| response.done);
+                future::ready(!was_done)
+            }))
+    } else {
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).await?;
+
+        match serde_json::from_str::<OllamaResponse>(&body) {
+            Ok(OllamaResponse {
+                error: Some(error), ..
+            }) => Err(anyhow!("Failed to connect to Ollama: {error}")),
+            _ => Err(anyhow!(
+                "Failed to connect to Ollama: {} {}",
+                response.status(),
+                body,
+            )),
+        }
+    }
+}
+
+/// Completes with models served by a local Ollama server, which doesn't need
+/// credentials.
+#[derive(Clone)]
+pub struct OllamaCompletionProvider {
+    api_url: String,
+    model: OpenAiLanguageModel,
+}
+
+impl OllamaCompletionProvider {
+    pub async fn new(
+        api_url: String,
+        model_name: String,
+        capacity: usize,
+        executor: BackgroundExecutor,
+    ) -> Self {
+        let model = executor
+            .spawn(async move { OpenAiLanguageModel::load_with_capacity(&model_name, capacity) })
+            .await;
+        Self { api_url, model }
+    }
+}
+
+impl CredentialProvider for OllamaCompletionProvider {
+    fn has_credentials(&self) -> bool {
+        true
+    }
+
+    fn retrieve_credentials(&self, _cx: &mut AppContext) -> BoxFuture<ProviderCredential> {
+        async { ProviderCredential::NotNeeded }.boxed()
+    }
+
+    fn save_credentials(
+        &self,
+        _cx: &mut AppContext,
+        _credential: ProviderCredential,
+    ) -> BoxFuture<()> {
+        async {}.boxed()
+    }
+
+    fn delete_credentials(&self, _cx: &mut AppContext) -> BoxFuture<()> {
+        async {}.boxed()
+    }
+}
+
+impl CompletionProvider for OllamaCompletionProvider {
+    fn base_model(&self) -> Box<dyn LanguageModel> {
+        let model: Box<dyn LanguageModel> = Box::new(self.model.clone());
+        model
+    }
+    fn complete(
+        &self,
+        prompt: Box<dyn CompletionRequest>,
+    ) -> BoxFuture<'static, Result<BoxStream<'static, Result<String>>>> {
+        let api_url = self.api_url.clone();
+        async move {
+            let request = serde_json::from_str::<OpenAiRequest>(&prompt.data()?)?;
+            let responses = stream_completion(api_url, request.into()).await?;
+            let stream = responses
+                .filter_map(|response| async move {
+                    match response {
+                        Ok(OllamaResponse {
+                            error: Some(error), ..
+                        }) => Some(Err(anyhow!(error))),
+                        Ok(response) => response
+                            .message?
+                            .content
+                            .filter(|content| !content.is_empty())
+                            .map(Ok),
+                        Err(error) => Some(Err(error)),
+                    }
+                })
+                .boxed();
+            Ok(stream)
+        }
+        .boxed()
+    }
+    fn box_clone(&self) -> Box<dyn CompletionProvider> {
+        Box::new((*self).clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{providers::open_ai::Role, test::FakeHttpServer};
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_stream_completion() {
+        let server = FakeHttpServer::serve(
+            200,
+            "application/x-ndjson",
+            concat!(
+                "{\"model\":\"llama2\",\"message\":{\"role\":\"assistant\",\"content\":\"fn\"},\"done\":false}\n",
+                "{\"model\":\"llama2\",\"message\":{\"role\":\"assistant\",\"content\":\" main\"},\"done\":false}\n",
+                "{\"model\":\"llama2\",\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"eval_count\":2}\n",
+            ),
+        );
+        let provider = OllamaCompletionProvider {
+            api_url: server.url().into(),
+            model: OpenAiLanguageModel::load_with_capacity("llama2", 4096),
+        };
+        let completion: Result<Vec<String>> = block_on(async {
+            let stream = provider
+                .complete(Box::new(OpenAiRequest {
+                    model: "llama2".into(),
+                    messages: vec![RequestMessage {
+                        role: Role::User,
+                        content: "Write a main function".into(),
+                    }],
+                    stream: true,
+                    stop: vec!["|END|>".into()],
+                    temperature: 0.5,
+                }))
+                .await?;
+            stream.collect::<Vec<_>>().await.into_iter().collect()
+        });
+        assert_eq!(completion.unwrap(), ["fn", " main"]);
+
+        let request = server.request();
+        assert!(request.head.starts_with("POST /api/chat "));
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["model"], "llama2");
+        assert_eq!(body["options"]["stop"][0], "|END|>");
+    }
+
+    #[test]
+    fn test_stream_completion_error() {
+        let server = FakeHttpServer::serve(
+            404,
+            "application/json",
+            r#"{"error":"model 'llama9' not found, try pulling it first"}"#,
+        );
+        let result = block_on(stream_completion(
+            server.url().into(),
+            OpenAiRequest::default().into(),
+        ));
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Failed to connect to Ollama: model 'llama9' not found, try pulling it first"
+        );
+    }
+}
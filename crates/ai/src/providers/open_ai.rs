@@ -1,7 +1,9 @@
+pub mod compatible;
 pub mod completion;
 pub mod embedding;
 pub mod model;
 
+pub use compatible::*;
 pub use completion::*;
 pub use embedding::*;
 pub use model::OpenAiLanguageModel;
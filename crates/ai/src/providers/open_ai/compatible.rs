@@ -0,0 +1,220 @@
+use anyhow::Result;
+use futures::{future::BoxFuture, stream::BoxStream, FutureExt, StreamExt};
+use gpui::{AppContext, BackgroundExecutor};
+use parking_lot::RwLock;
+use std::sync::Arc;
+use util::ResultExt;
+
+use crate::{
+    auth::{CredentialProvider, ProviderCredential},
+    completion::{CompletionProvider, CompletionRequest},
+    models::LanguageModel,
+    providers::open_ai::{stream_completion, OpenAiLanguageModel},
+};
+
+/// Completes with a server that implements OpenAI's chat completions API, like a
+/// local inference server, serving models that the user declares. An API key is
+/// only sent once the user enters one, as such servers often don't need it.
+#[derive(Clone)]
+pub struct OpenAiCompatibleCompletionProvider {
+    api_url: String,
+    model: OpenAiLanguageModel,
+    credential: Arc<RwLock<ProviderCredential>>,
+    executor: BackgroundExecutor,
+}
+
+impl OpenAiCompatibleCompletionProvider {
+    pub async fn new(
+        api_url: String,
+        model_name: String,
+        capacity: usize,
+        executor: BackgroundExecutor,
+    ) -> Self {
+        let model = executor
+            .spawn(async move { OpenAiLanguageModel::load_with_capacity(&model_name, capacity) })
+            .await;
+        let credential = Arc::new(RwLock::new(ProviderCredential::NotNeeded));
+        Self {
+            api_url,
+            model,
+            credential,
+            executor,
+        }
+    }
+}
+
+impl CredentialProvider for OpenAiCompatibleCompletionProvider {
+    fn has_credentials(&self) -> bool {
+        match *self.credential.read() {
+            ProviderCredential::Credentials { .. } | ProviderCredential::NotNeeded => true,
+            ProviderCredential::NoCredentials => false,
+        }
+    }
+
+    fn retrieve_credentials(&self, cx: &mut AppContext) -> BoxFuture<ProviderCredential> {
+        let existing_credential = self.credential.read().clone();
+        if let ProviderCredential::Credentials { .. } = existing_credential {
+            return async move { existing_credential }.boxed();
+        }
+
+        let credentials = cx.read_credentials(&self.api_url);
+        async move {
+            let retrieved_credential = match credentials.await.log_err() {
+                Some(Some((_, api_key))) => match String::from_utf8(api_key).log_err() {
+                    Some(api_key) => ProviderCredential::Credentials { api_key },
+                    None => ProviderCredential::NotNeeded,
+                },
+                _ => ProviderCredential::NotNeeded,
+            };
+            *self.credential.write() = retrieved_credential.clone();
+            retrieved_credential
+        }
+        .boxed()
+    }
+
+    fn save_credentials(
+        &self,
+        cx: &mut AppContext,
+        credential: ProviderCredential,
+    ) -> BoxFuture<()> {
+        *self.credential.write() = credential.clone();
+        let write_credentials = match credential {
+            ProviderCredential::Credentials { api_key } => {
+                Some(cx.write_credentials(&self.api_url, "Bearer", api_key.as_bytes()))
+            }
+            _ => None,
+        };
+
+        async move {
+            if let Some(write_credentials) = write_credentials {
+                write_credentials.await.log_err();
+            }
+        }
+        .boxed()
+    }
+
+    fn delete_credentials(&self, cx: &mut AppContext) -> BoxFuture<()> {
+        *self.credential.write() = ProviderCredential::NotNeeded;
+        let delete_credentials = cx.delete_credentials(&self.api_url);
+        async move {
+            delete_credentials.await.log_err();
+        }
+        .boxed()
+    }
+}
+
+impl CompletionProvider for OpenAiCompatibleCompletionProvider {
+    fn base_model(&self) -> Box<dyn LanguageModel> {
+        let model: Box<dyn LanguageModel> = Box::new(self.model.clone());
+        model
+    }
+    fn complete(
+        &self,
+        prompt: Box<dyn CompletionRequest>,
+    ) -> BoxFuture<'static, Result<BoxStream<'static, Result<String>>>> {
+        let credential = self.credential.read().clone();
+        let api_url = self.api_url.clone();
+        let request = stream_completion(api_url, credential, self.executor.clone(), prompt);
+        async move {
+            let response = request.await?;
+            let stream = response
+                .filter_map(|response| async move {
+                    match response {
+                        Ok(mut response) => Some(Ok(response.choices.pop()?.delta.content?)),
+                        Err(error) => Some(Err(error)),
+                    }
+                })
+                .boxed();
+            Ok(stream)
+        }
+        .boxed()
+    }
+    fn box_clone(&self) -> Box<dyn CompletionProvider> {
+        Box::new((*self).clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        providers::open_ai::{OpenAiRequest, RequestMessage, Role},
+        test::FakeHttpServer,
+    };
+    use gpui::TestAppContext;
+
+    #[gpui::test]
+    async fn test_stream_completion(cx: &mut TestAppContext) {
+        // The server's response is read on real threads.
+        cx.executor().allow_parking();
+        let server = FakeHttpServer::serve(
+            200,
+            "text/event-stream",
+            concat!(
+                "data: {\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"mistral\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Hello\"},\"finish_reason\":null}]}\n\n",
+                "data: {\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"mistral\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\" there\"},\"finish_reason\":\"stop\"}]}\n\n",
+                "data: [DONE]\n\n",
+            ),
+        );
+        let provider = OpenAiCompatibleCompletionProvider::new(
+            server.url().into(),
+            "mistral".into(),
+            32768,
+            cx.executor(),
+        )
+        .await;
+        assert!(provider.has_credentials());
+        assert_eq!(provider.base_model().capacity().unwrap(), 32768);
+
+        let stream = provider
+            .complete(Box::new(OpenAiRequest {
+                model: "mistral".into(),
+                messages: vec![RequestMessage {
+                    role: Role::User,
+                    content: "Hi".into(),
+                }],
+                stream: true,
+                stop: Vec::new(),
+                temperature: 1.,
+            }))
+            .await
+            .unwrap();
+        let completion = stream.collect::<Vec<_>>().await;
+        let completion = completion.into_iter().collect::<Result<Vec<_>>>();
+        assert_eq!(completion.unwrap(), ["Hello", " there"]);
+
+        // Without a key, none is sent.
+        let request = server.request();
+        assert!(request.head.starts_with("POST /chat/completions "));
+        assert!(!request.head.to_lowercase().contains("authorization"));
+    }
+
+    #[gpui::test]
+    async fn test_reset_key(cx: &mut TestAppContext) {
+        let provider = OpenAiCompatibleCompletionProvider::new(
+            "http://localhost:8080/v1".into(),
+            "mistral".into(),
+            32768,
+            cx.executor(),
+        )
+        .await;
+        cx.update(|cx| {
+            provider.save_credentials(
+                cx,
+                ProviderCredential::Credentials {
+                    api_key: "secret".into(),
+                },
+            )
+        })
+        .await;
+        assert!(provider.has_credentials());
+
+        // Without a key, the server is used as if it never needed one.
+        cx.update(|cx| provider.delete_credentials(cx)).await;
+        assert!(provider.has_credentials());
+        assert!(matches!(
+            *provider.credential.read(),
+            ProviderCredential::NotNeeded
+        ));
+    }
+}
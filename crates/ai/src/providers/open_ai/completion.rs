@@ -57,7 +57,9 @@ pub struct RequestMessage {
     pub content: String,
 }
 
-#[derive(Debug, Default, Serialize)]
+/// A chat completion request. Providers whose APIs have another format
+/// translate it from this one.
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct OpenAiRequest {
     pub model: String,
     pub messages: Vec<RequestMessage>,
@@ -109,8 +111,10 @@ pub async fn stream_completion(
     request: Box<dyn CompletionRequest>,
 ) -> Result<impl Stream<Item = Result<OpenAiResponseStreamEvent>>> {
     let api_key = match credential {
-        ProviderCredential::Credentials { api_key } => api_key,
-        _ => {
+        ProviderCredential::Credentials { api_key } => Some(api_key),
+        // Servers that are compatible with OpenAI's API may not need a key.
+        ProviderCredential::NotNeeded => None,
+        ProviderCredential::NoCredentials => {
             return Err(anyhow!("no credentials provider for completion"));
         }
     };
@@ -118,12 +122,12 @@ pub async fn stream_completion(
     let (tx, rx) = futures::channel::mpsc::unbounded::<Result<OpenAiResponseStreamEvent>>();
 
     let json_data = request.data()?;
-    let mut response = Request::post(format!("{api_url}/chat/completions"))
-        .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .body(json_data)?
-        .send_async()
-        .await?;
+    let mut request = Request::post(format!("{api_url}/chat/completions"))
+        .header("Content-Type", "application/json");
+    if let Some(api_key) = api_key {
+        request = request.header("Authorization", format!("Bearer {}", api_key));
+    }
+    let mut response = request.body(json_data)?.send_async().await?;
 
     let status = response.status();
     if status == StatusCode::OK {
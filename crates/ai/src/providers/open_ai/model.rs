@@ -9,6 +9,7 @@ use super::OPEN_AI_BPE_TOKENIZER;
 pub struct OpenAiLanguageModel {
     name: String,
     bpe: Option<CoreBPE>,
+    capacity: Option<usize>,
 }
 
 impl OpenAiLanguageModel {
@@ -18,6 +19,16 @@ impl OpenAiLanguageModel {
         OpenAiLanguageModel {
             name: model_name.to_string(),
             bpe: Some(bpe),
+            capacity: None,
+        }
+    }
+
+    /// Loads a model that OpenAI's tokenizer doesn't know the context size of, such
+    /// as one served by another provider. Its tokens are counted approximately.
+    pub fn load_with_capacity(model_name: &str, capacity: usize) -> Self {
+        OpenAiLanguageModel {
+            capacity: Some(capacity),
+            ..Self::load(model_name)
         }
     }
 }
@@ -54,6 +65,9 @@ impl LanguageModel for OpenAiLanguageModel {
         }
     }
     fn capacity(&self) -> anyhow::Result<usize> {
-        anyhow::Ok(tiktoken_rs::model::get_context_size(&self.name))
+        anyhow::Ok(
+            self.capacity
+                .unwrap_or_else(|| tiktoken_rs::model::get_context_size(&self.name)),
+        )
     }
 }
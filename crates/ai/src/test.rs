@@ -1,5 +1,8 @@
 use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
     sync::atomic::{self, AtomicUsize, Ordering},
+    thread,
     time::Instant,
 };
 
@@ -211,3 +214,75 @@ impl CompletionProvider for FakeCompletionProvider {
         Box::new((*self).clone())
     }
 }
+
+/// A local HTTP server that answers a single request with a canned response,
+/// to test how providers stream completions without reaching their APIs.
+pub struct FakeHttpServer {
+    url: String,
+    request: thread::JoinHandle<FakeHttpRequest>,
+}
+
+/// The request a [`FakeHttpServer`] received.
+pub struct FakeHttpRequest {
+    /// The request line and headers.
+    pub head: String,
+    pub body: String,
+}
+
+impl FakeHttpServer {
+    pub fn serve(status: u16, content_type: &str, body: &str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let response = format!(
+            "HTTP/1.1 {status} {}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            if status == 200 { "OK" } else { "Error" },
+            body.len(),
+        );
+        let request = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = read_request(&mut stream);
+            stream.write_all(response.as_bytes()).unwrap();
+            request
+        });
+        Self { url, request }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Waits for the request to be received.
+    pub fn request(self) -> FakeHttpRequest {
+        self.request.join().unwrap()
+    }
+}
+
+fn read_request(stream: &mut TcpStream) -> FakeHttpRequest {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    let head_len = loop {
+        if let Some(ix) = request.windows(4).position(|bytes| bytes == b"\r\n\r\n") {
+            break ix + 4;
+        }
+        let len = stream.read(&mut buffer).unwrap();
+        assert!(len > 0, "connection closed before the request's headers");
+        request.extend_from_slice(&buffer[..len]);
+    };
+
+    let head = String::from_utf8(request[..head_len].to_vec()).unwrap();
+    let content_len = head
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse::<usize>().unwrap())
+        })
+        .unwrap_or(0);
+    while request.len() < head_len + content_len {
+        let len = stream.read(&mut buffer).unwrap();
+        assert!(len > 0, "connection closed before the request's body");
+        request.extend_from_slice(&buffer[..len]);
+    }
+    let body = String::from_utf8(request[head_len..].to_vec()).unwrap();
+    FakeHttpRequest { head, body }
+}
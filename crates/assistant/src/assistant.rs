@@ -2,17 +2,25 @@ pub mod assistant_panel;
 pub mod assistant_settings;
 mod codegen;
 mod prompts;
+mod slash_command;
 mod streaming_diff;
 
-use ai::providers::open_ai::Role;
+use ai::{
+    completion::CompletionProvider,
+    providers::{
+        anthropic::AnthropicCompletionProvider,
+        ollama::OllamaCompletionProvider,
+        open_ai::{OpenAiCompatibleCompletionProvider, OpenAiCompletionProvider, Role},
+    },
+};
 use anyhow::Result;
 pub use assistant_panel::AssistantPanel;
-use assistant_settings::OpenAiModel;
+use assistant_settings::{AssistantProvider, AssistantProviderKind};
 use chrono::{DateTime, Local};
 use collections::HashMap;
 use fs::Fs;
 use futures::StreamExt;
-use gpui::{actions, AppContext, SharedString};
+use gpui::{actions, AppContext, BackgroundExecutor, SharedString};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::{cmp::Reverse, ffi::OsStr, path::PathBuf, sync::Arc};
@@ -69,11 +77,14 @@ struct SavedConversation {
     message_metadata: HashMap<MessageId, MessageMetadata>,
     summary: String,
     api_url: Option<String>,
-    model: OpenAiModel,
+    /// Conversations saved before there were other providers are OpenAI's.
+    #[serde(default)]
+    provider: AssistantProviderKind,
+    model: String,
 }
 
 impl SavedConversation {
-    const VERSION: &'static str = "0.1.0";
+    const VERSION: &'static str = "0.2.0";
 }
 
 struct SavedConversationMetadata {
@@ -117,6 +128,37 @@ impl SavedConversationMetadata {
     }
 }
 
+async fn build_completion_provider(
+    provider: AssistantProvider,
+    executor: BackgroundExecutor,
+) -> Arc<dyn CompletionProvider> {
+    let AssistantProvider {
+        kind,
+        api_url,
+        model,
+    } = provider;
+    match kind {
+        AssistantProviderKind::OpenAi => {
+            Arc::new(OpenAiCompletionProvider::new(api_url, model.name, executor).await)
+        }
+        AssistantProviderKind::Anthropic => Arc::new(
+            AnthropicCompletionProvider::new(api_url, model.name, model.max_tokens, executor).await,
+        ),
+        AssistantProviderKind::Ollama => Arc::new(
+            OllamaCompletionProvider::new(api_url, model.name, model.max_tokens, executor).await,
+        ),
+        AssistantProviderKind::OpenAiCompatible => Arc::new(
+            OpenAiCompatibleCompletionProvider::new(
+                api_url,
+                model.name,
+                model.max_tokens,
+                executor,
+            )
+            .await,
+        ),
+    }
+}
+
 pub fn init(cx: &mut AppContext) {
     assistant_panel::init(cx);
 }
@@ -1,17 +1,21 @@
 use crate::{
-    assistant_settings::{AssistantDockPosition, AssistantSettings, OpenAiModel},
+    assistant_settings::{
+        AssistantDockPosition, AssistantProvider, AssistantProviderKind, AssistantSettings,
+        AvailableModel,
+    },
+    build_completion_provider,
     codegen::{self, Codegen, CodegenKind},
     prompts::generate_content_prompt,
+    slash_command::{self, ContextOutput, ContextSource, SlashCommand},
     Assist, CycleMessageRole, InlineAssist, MessageId, MessageMetadata, MessageStatus,
     NewConversation, QuoteSelection, ResetKey, Role, SavedConversation, SavedConversationMetadata,
     SavedMessage, Split, ToggleFocus, ToggleIncludeConversation, ToggleRetrieveContext,
 };
 use ai::prompts::repository_context::PromptCodeSnippet;
-use ai::providers::open_ai::OPEN_AI_API_URL;
 use ai::{
     auth::ProviderCredential,
     completion::{CompletionProvider, CompletionRequest},
-    providers::open_ai::{OpenAiCompletionProvider, OpenAiRequest, RequestMessage},
+    providers::open_ai::{OpenAiRequest, RequestMessage},
 };
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Local};
@@ -36,11 +40,14 @@ use gpui::{
     StatefulInteractiveElement, Styled, Subscription, Task, TextStyle, UniformListScrollHandle,
     View, ViewContext, VisualContext, WeakModel, WeakView, WhiteSpace, WindowContext,
 };
-use language::{language_settings::SoftWrap, Buffer, BufferId, LanguageRegistry, ToOffset as _};
+use language::{
+    language_settings::SoftWrap, Buffer, BufferId, LanguageRegistry, OffsetRangeExt, ToOffset as _,
+};
+use parking_lot::RwLock;
 use project::Project;
 use search::{buffer_search::DivRegistrar, BufferSearchBar};
 use semantic_index::{SemanticIndex, SemanticIndexStatus};
-use settings::Settings;
+use settings::{Settings, SettingsStore};
 use std::{
     cell::Cell,
     cmp,
@@ -94,7 +101,9 @@ pub struct AssistantPanel {
     zoomed: bool,
     focus_handle: FocusHandle,
     toolbar: View<Toolbar>,
+    provider: AssistantProvider,
     completion_provider: Arc<dyn CompletionProvider>,
+    _build_completion_provider: Task<()>,
     api_key_editor: Option<View<Editor>>,
     languages: Arc<LanguageRegistry>,
     fs: Arc<dyn Fs>,
@@ -122,22 +131,9 @@ impl AssistantPanel {
                 .await
                 .log_err()
                 .unwrap_or_default();
-            let (api_url, model_name) = cx
-                .update(|cx| {
-                    let settings = AssistantSettings::get_global(cx);
-                    (
-                        settings.openai_api_url.clone(),
-                        settings.default_open_ai_model.full_name().to_string(),
-                    )
-                })
-                .log_err()
-                .unwrap();
-            let completion_provider = OpenAiCompletionProvider::new(
-                api_url,
-                model_name,
-                cx.background_executor().clone(),
-            )
-            .await;
+            let provider = cx.update(|cx| AssistantSettings::get_global(cx).default_provider())?;
+            let completion_provider =
+                build_completion_provider(provider.clone(), cx.background_executor().clone()).await;
 
             // TODO: deserialize state.
             let workspace_handle = workspace.clone();
@@ -175,6 +171,8 @@ impl AssistantPanel {
                     let focus_handle = cx.focus_handle();
                     cx.on_focus_in(&focus_handle, Self::focus_in).detach();
                     cx.on_focus_out(&focus_handle, Self::focus_out).detach();
+                    cx.observe_global::<SettingsStore>(Self::settings_changed)
+                        .detach();
 
                     Self {
                         workspace: workspace_handle,
@@ -186,7 +184,9 @@ impl AssistantPanel {
                         zoomed: false,
                         focus_handle,
                         toolbar,
-                        completion_provider: Arc::new(completion_provider),
+                        provider,
+                        completion_provider,
+                        _build_completion_provider: Task::ready(()),
                         api_key_editor: None,
                         languages: workspace.app_state().languages.clone(),
                         fs: workspace.app_state().fs.clone(),
@@ -224,6 +224,34 @@ impl AssistantPanel {
         }
     }
 
+    /// Switches to the provider and model the settings now start conversations
+    /// with, once its completion provider is built. Existing conversations keep
+    /// theirs.
+    fn settings_changed(&mut self, cx: &mut ViewContext<Self>) {
+        let provider = AssistantSettings::get_global(cx).default_provider();
+        if provider == self.provider {
+            // Drop the provider of settings that were since reverted.
+            self._build_completion_provider = Task::ready(());
+            return;
+        }
+        let executor = cx.background_executor().clone();
+        self._build_completion_provider = cx.spawn(|this, mut cx| async move {
+            let completion_provider = build_completion_provider(provider.clone(), executor).await;
+            this.update(&mut cx, |this, cx| {
+                // The provider and its completion provider are swapped together, so
+                // that requests never name a model of another provider.
+                this.provider = provider;
+                this.completion_provider = completion_provider;
+                this.api_key_editor.take();
+                if this.focus_handle.contains_focused(cx) {
+                    this.set_active(true, cx);
+                }
+                cx.notify();
+            })
+            .ok();
+        });
+    }
+
     fn focus_out(&mut self, cx: &mut ViewContext<Self>) {
         self.toolbar
             .update(cx, |toolbar, cx| toolbar.focus_changed(false, cx));
@@ -693,10 +721,15 @@ impl AssistantPanel {
             Task::ready(Ok(Vec::new()))
         };
 
-        let mut model = AssistantSettings::get_global(cx)
-            .default_open_ai_model
-            .clone();
-        let model_name = model.full_name();
+        let mut model = self.provider.model.clone();
+        if let Some(conversation) = conversation.as_ref() {
+            // The conversation may have been started with another provider.
+            let conversation = conversation.read(cx);
+            if conversation.provider.kind == self.provider.kind {
+                model = conversation.provider.model.clone();
+            }
+        }
+        let prompt_model = model.clone();
 
         let prompt = cx.background_executor().spawn(async move {
             let snippets = snippets.await?;
@@ -708,7 +741,8 @@ impl AssistantPanel {
                 buffer,
                 range,
                 snippets,
-                model_name,
+                &prompt_model.name,
+                prompt_model.max_tokens,
                 project_name,
             )
         });
@@ -722,7 +756,6 @@ impl AssistantPanel {
                     .messages(cx)
                     .map(|message| message.to_open_ai_message(buffer)),
             );
-            model = conversation.model.clone();
         }
 
         cx.spawn(|_, mut cx| async move {
@@ -735,7 +768,7 @@ impl AssistantPanel {
             });
 
             let request = Box::new(OpenAiRequest {
-                model: model.full_name().into(),
+                model: model.name,
                 messages,
                 stream: true,
                 stop: vec!["|END|>".to_string()],
@@ -801,6 +834,7 @@ impl AssistantPanel {
     fn new_conversation(&mut self, cx: &mut ViewContext<Self>) -> View<ConversationEditor> {
         let editor = cx.new_view(|cx| {
             ConversationEditor::new(
+                self.provider.clone(),
                 self.completion_provider.clone(),
                 self.languages.clone(),
                 self.fs.clone(),
@@ -891,8 +925,11 @@ impl AssistantPanel {
             cx.update(|cx| completion_provider.delete_credentials(cx))?
                 .await;
             this.update(&mut cx, |this, cx| {
-                this.build_api_key_editor(cx);
-                this.focus_handle.focus(cx);
+                // Some providers don't need a key.
+                if !this.has_credentials() {
+                    this.build_api_key_editor(cx);
+                    this.focus_handle.focus(cx);
+                }
                 cx.notify();
             })
         })
@@ -1169,7 +1206,7 @@ fn build_api_key_editor(cx: &mut WindowContext) -> View<Editor> {
 impl Render for AssistantPanel {
     fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         if let Some(api_key_editor) = self.api_key_editor.clone() {
-            const INSTRUCTIONS: [&'static str; 6] = [
+            const OPEN_AI_INSTRUCTIONS: &[&'static str] = &[
                 "To use the assistant panel or inline assistant, you need to add your OpenAI API key.",
                 " - You can create an API key at: platform.openai.com/api-keys",
                 " - Make sure your OpenAI account has credits",
@@ -1177,6 +1214,22 @@ impl Render for AssistantPanel {
                 " ",
                 "Paste your OpenAI API key and press Enter to use the assistant:"
             ];
+            const ANTHROPIC_INSTRUCTIONS: &[&'static str] = &[
+                "To use the assistant panel or inline assistant, you need to add your Anthropic API key.",
+                " - You can create an API key at: console.anthropic.com/settings/keys",
+                " ",
+                "Paste your Anthropic API key and press Enter to use the assistant:"
+            ];
+            const API_KEY_INSTRUCTIONS: &[&'static str] = &[
+                "Paste the API key for the server in your assistant settings and press Enter to use the assistant:"
+            ];
+            let instructions = match self.provider.kind {
+                AssistantProviderKind::OpenAi => OPEN_AI_INSTRUCTIONS,
+                AssistantProviderKind::Anthropic => ANTHROPIC_INSTRUCTIONS,
+                AssistantProviderKind::Ollama | AssistantProviderKind::OpenAiCompatible => {
+                    API_KEY_INSTRUCTIONS
+                }
+            };
 
             v_flex()
                 .p_4()
@@ -1184,7 +1237,9 @@ impl Render for AssistantPanel {
                 .on_action(cx.listener(AssistantPanel::save_credentials))
                 .track_focus(&self.focus_handle)
                 .children(
-                    INSTRUCTIONS.map(|instruction| Label::new(instruction).size(LabelSize::Small)),
+                    instructions
+                        .iter()
+                        .map(|instruction| Label::new(*instruction).size(LabelSize::Small)),
                 )
                 .child(
                     h_flex()
@@ -1419,8 +1474,7 @@ struct Conversation {
     pending_summary: Task<Option<()>>,
     completion_count: usize,
     pending_completions: Vec<PendingCompletion>,
-    model: OpenAiModel,
-    api_url: Option<String>,
+    provider: AssistantProvider,
     token_count: Option<usize>,
     max_token_count: usize,
     pending_token_count: Task<Option<()>>,
@@ -1433,9 +1487,12 @@ struct Conversation {
 impl EventEmitter<ConversationEvent> for Conversation {}
 
 impl Conversation {
+    /// Starts a conversation with the given provider, requesting completions from
+    /// the completion provider built for it.
     fn new(
         language_registry: Arc<LanguageRegistry>,
         cx: &mut ModelContext<Self>,
+        provider: AssistantProvider,
         completion_provider: Arc<dyn CompletionProvider>,
     ) -> Self {
         let markdown = language_registry.language_for_name("Markdown");
@@ -1453,10 +1510,6 @@ impl Conversation {
             buffer
         });
 
-        let settings = AssistantSettings::get_global(cx);
-        let model = settings.default_open_ai_model.clone();
-        let api_url = settings.openai_api_url.clone();
-
         let mut this = Self {
             id: Some(Uuid::new_v4().to_string()),
             message_anchors: Default::default(),
@@ -1467,10 +1520,9 @@ impl Conversation {
             completion_count: Default::default(),
             pending_completions: Default::default(),
             token_count: None,
-            max_token_count: tiktoken_rs::model::get_context_size(&model.full_name()),
+            max_token_count: provider.model.max_tokens,
             pending_token_count: Task::ready(None),
-            api_url: Some(api_url),
-            model: model.clone(),
+            provider,
             _subscriptions: vec![cx.subscribe(&buffer, Self::handle_buffer_event)],
             pending_save: Task::ready(Ok(())),
             path: None,
@@ -1514,8 +1566,9 @@ impl Conversation {
                 .as_ref()
                 .map(|summary| summary.text.clone())
                 .unwrap_or_default(),
-            model: self.model.clone(),
-            api_url: self.api_url.clone(),
+            api_url: Some(self.provider.api_url.clone()),
+            provider: self.provider.kind,
+            model: self.provider.model.name.clone(),
         }
     }
 
@@ -1529,18 +1582,19 @@ impl Conversation {
             Some(id) => Some(id),
             None => Some(Uuid::new_v4().to_string()),
         };
-        let model = saved_conversation.model;
-        let api_url = saved_conversation.api_url;
-        let completion_provider: Arc<dyn CompletionProvider> = Arc::new(
-            OpenAiCompletionProvider::new(
-                api_url
-                    .clone()
-                    .unwrap_or_else(|| OPEN_AI_API_URL.to_string()),
-                model.full_name().into(),
-                cx.background_executor().clone(),
-            )
-            .await,
-        );
+        let provider = cx.update(|cx| {
+            let settings = AssistantSettings::get_global(cx);
+            let kind = saved_conversation.provider;
+            AssistantProvider {
+                kind,
+                api_url: saved_conversation
+                    .api_url
+                    .unwrap_or_else(|| settings.api_url(kind).into()),
+                model: settings.model(kind, &saved_conversation.model),
+            }
+        })?;
+        let completion_provider =
+            build_completion_provider(provider.clone(), cx.background_executor().clone()).await;
         cx.update(|cx| completion_provider.retrieve_credentials(cx))?
             .await;
 
@@ -1586,10 +1640,9 @@ impl Conversation {
                 completion_count: Default::default(),
                 pending_completions: Default::default(),
                 token_count: None,
-                max_token_count: tiktoken_rs::model::get_context_size(&model.full_name()),
+                max_token_count: provider.model.max_tokens,
                 pending_token_count: Task::ready(None),
-                api_url,
-                model,
+                provider,
                 _subscriptions: vec![cx.subscribe(&buffer, Self::handle_buffer_event)],
                 pending_save: Task::ready(Ok(())),
                 path: Some(path),
@@ -1638,7 +1691,7 @@ impl Conversation {
                 })
             })
             .collect::<Vec<_>>();
-        let model = self.model.clone();
+        let model = self.provider.model.clone();
         self.pending_token_count = cx.spawn(|this, mut cx| {
             async move {
                 cx.background_executor()
@@ -1647,13 +1700,14 @@ impl Conversation {
                 let token_count = cx
                     .background_executor()
                     .spawn(async move {
-                        tiktoken_rs::num_tokens_from_messages(&model.full_name(), &messages)
+                        // Other providers' models are counted approximately, like GPT-4's.
+                        tiktoken_rs::num_tokens_from_messages(&model.name, &messages)
+                            .or_else(|_| tiktoken_rs::num_tokens_from_messages("gpt-4", &messages))
                     })
                     .await?;
 
                 this.update(&mut cx, |this, cx| {
-                    this.max_token_count =
-                        tiktoken_rs::model::get_context_size(&this.model.full_name());
+                    this.max_token_count = this.provider.model.max_tokens;
                     this.token_count = Some(token_count);
                     cx.notify()
                 })?;
@@ -1667,8 +1721,21 @@ impl Conversation {
         Some(self.max_token_count as isize - self.token_count? as isize)
     }
 
-    fn set_model(&mut self, model: OpenAiModel, cx: &mut ModelContext<Self>) {
-        self.model = model;
+    /// Switches to the next of the models the provider serves, starting over
+    /// from the first one after the last one or a model it no longer serves.
+    fn cycle_model(&mut self, cx: &mut ModelContext<Self>) {
+        let models = AssistantSettings::get_global(cx).models(self.provider.kind);
+        let next_ix = models
+            .iter()
+            .position(|model| model.name == self.provider.model.name)
+            .map_or(0, |ix| ix + 1);
+        if let Some(new_model) = models.get(next_ix % models.len().max(1)) {
+            self.set_model(new_model.clone(), cx);
+        }
+    }
+
+    fn set_model(&mut self, model: AvailableModel, cx: &mut ModelContext<Self>) {
+        self.provider.model = model;
         self.count_remaining_tokens(cx);
         cx.notify();
     }
@@ -1722,7 +1789,7 @@ impl Conversation {
             }
 
             let request: Box<dyn CompletionRequest> = Box::new(OpenAiRequest {
-                model: self.model.full_name().to_string(),
+                model: self.provider.model.name.clone(),
                 messages: self
                     .messages(cx)
                     .filter(|message| matches!(message.status, MessageStatus::Done))
@@ -2005,7 +2072,7 @@ impl Conversation {
                         .into(),
                 }));
             let request: Box<dyn CompletionRequest> = Box::new(OpenAiRequest {
-                model: self.model.full_name().to_string(),
+                model: self.provider.model.name.clone(),
                 messages: messages.collect(),
                 stream: true,
                 stop: vec![],
@@ -2187,6 +2254,60 @@ struct ScrollPosition {
     cursor: Anchor,
 }
 
+/// A section of the conversation that a slash command expanded into. Its text
+/// is replaced whenever the data it was read from changes.
+struct ContextSection {
+    command: SlashCommand,
+    source: Option<ContextSource>,
+    output_range: Range<language::Anchor>,
+    /// Whether the output was inserted, after which an empty range means that
+    /// the user undid or deleted it.
+    is_expanded: bool,
+    _project_subscription: Option<Subscription>,
+    _buffer_subscriptions: Vec<Subscription>,
+    _expand: Task<Option<()>>,
+}
+
+struct ConversationEditorCompletionProvider(WeakView<ConversationEditor>);
+
+impl editor::CompletionProvider for ConversationEditorCompletionProvider {
+    fn completions(
+        &self,
+        buffer: &Model<Buffer>,
+        buffer_position: language::Anchor,
+        cx: &mut ViewContext<Editor>,
+    ) -> Task<Result<Vec<language::Completion>>> {
+        let Some(handle) = self.0.upgrade() else {
+            return Task::ready(Ok(Vec::new()));
+        };
+        let project = handle
+            .read(cx)
+            .workspace
+            .upgrade()
+            .map(|workspace| workspace.read(cx).project().clone());
+        slash_command::completions(project, buffer, buffer_position, cx)
+    }
+
+    fn resolve_completions(
+        &self,
+        _completion_indices: Vec<usize>,
+        _completions: Arc<RwLock<Box<[language::Completion]>>>,
+        _cx: &mut ViewContext<Editor>,
+    ) -> Task<Result<bool>> {
+        Task::ready(Ok(false))
+    }
+
+    fn apply_additional_edits_for_completion(
+        &self,
+        _buffer: Model<Buffer>,
+        _completion: language::Completion,
+        _push_to_history: bool,
+        _cx: &mut ViewContext<Editor>,
+    ) -> Task<Result<Option<language::Transaction>>> {
+        Task::ready(Ok(None))
+    }
+}
+
 struct ConversationEditor {
     conversation: Model<Conversation>,
     fs: Arc<dyn Fs>,
@@ -2194,19 +2315,26 @@ struct ConversationEditor {
     editor: View<Editor>,
     blocks: HashSet<BlockId>,
     scroll_position: Option<ScrollPosition>,
+    context_sections: HashMap<usize, ContextSection>,
+    next_context_section_id: usize,
+    /// Where the sections the user undid started, so that their commands aren't
+    /// expanded again.
+    removed_context_sections: Vec<language::Anchor>,
     _subscriptions: Vec<Subscription>,
 }
 
 impl ConversationEditor {
     fn new(
+        provider: AssistantProvider,
         completion_provider: Arc<dyn CompletionProvider>,
         language_registry: Arc<LanguageRegistry>,
         fs: Arc<dyn Fs>,
         workspace: WeakView<Workspace>,
         cx: &mut ViewContext<Self>,
     ) -> Self {
-        let conversation =
-            cx.new_model(|cx| Conversation::new(language_registry, cx, completion_provider));
+        let conversation = cx.new_model(|cx| {
+            Conversation::new(language_registry, cx, provider, completion_provider)
+        });
         Self::for_conversation(conversation, fs, workspace, cx)
     }
 
@@ -2216,13 +2344,20 @@ impl ConversationEditor {
         workspace: WeakView<Workspace>,
         cx: &mut ViewContext<Self>,
     ) -> Self {
+        let this = cx.view().downgrade();
         let editor = cx.new_view(|cx| {
             let mut editor = Editor::for_buffer(conversation.read(cx).buffer.clone(), None, cx);
             editor.set_soft_wrap_mode(SoftWrap::EditorWidth, cx);
             editor.set_show_gutter(false, cx);
             editor.set_show_wrap_guides(false, cx);
+            editor.set_completion_provider(Box::new(ConversationEditorCompletionProvider(this)));
             editor
         });
+        conversation.update(cx, |conversation, cx| {
+            conversation.buffer.update(cx, |buffer, cx| {
+                buffer.set_completion_triggers(vec!["/".into()], cx)
+            })
+        });
 
         let _subscriptions = vec![
             cx.observe(&conversation, |_, _, cx| cx.notify()),
@@ -2235,6 +2370,9 @@ impl ConversationEditor {
             editor,
             blocks: Default::default(),
             scroll_position: None,
+            context_sections: Default::default(),
+            next_context_section_id: 0,
+            removed_context_sections: Vec::new(),
             fs,
             workspace,
             _subscriptions,
@@ -2366,10 +2504,231 @@ impl ConversationEditor {
             EditorEvent::SelectionsChanged { .. } => {
                 self.scroll_position = self.cursor_scroll_position(cx);
             }
+            EditorEvent::Edited => {
+                self.remove_collapsed_context_sections(cx);
+                self.expand_slash_command(cx);
+            }
             _ => {}
         }
     }
 
+    /// Expands the slash command on the line above the cursor, once the user
+    /// has pressed enter after it.
+    fn expand_slash_command(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let cursor = self.editor.read(cx).selections.newest::<usize>(cx).head();
+        let buffer = self.conversation.read(cx).buffer.read(cx).snapshot();
+        let cursor_point = buffer.offset_to_point(cursor);
+        if cursor_point.column != 0 || cursor_point.row == 0 {
+            return;
+        }
+
+        let command_row = cursor_point.row - 1;
+        let command_start = buffer.point_to_offset(language::Point::new(command_row, 0));
+        let command_line = buffer
+            .text_for_range(command_start..cursor - 1)
+            .collect::<String>();
+        let Some(command) = SlashCommand::parse(&command_line) else {
+            return;
+        };
+        if self.context_sections.values().any(|section| {
+            let output_range = section.output_range.to_offset(&buffer);
+            output_range.start == cursor
+                || (output_range.start <= command_start && command_start < output_range.end)
+        }) || self
+            .removed_context_sections
+            .iter()
+            .any(|start| start.to_offset(&buffer) == cursor)
+        {
+            return;
+        }
+
+        let id = post_inc(&mut self.next_context_section_id);
+        let anchor = buffer.anchor_before(cursor);
+        let resolve = command.resolve(&workspace, cx);
+        let expand = cx.spawn(|this, mut cx| {
+            async move {
+                let source = resolve.await;
+                this.update(&mut cx, |this, cx| match source {
+                    Ok(source) => this.set_context_source(id, source, cx),
+                    Err(error) => {
+                        this.context_sections.remove(&id);
+                        this.show_context_error(id, error, cx);
+                    }
+                })
+            }
+            .log_err()
+        });
+        self.context_sections.insert(
+            id,
+            ContextSection {
+                command,
+                source: None,
+                output_range: anchor..anchor,
+                is_expanded: false,
+                _project_subscription: None,
+                _buffer_subscriptions: Vec::new(),
+                _expand: expand,
+            },
+        );
+    }
+
+    /// Stops tracking the sections whose output the user undid or deleted, so
+    /// that it isn't inserted again when its data changes.
+    fn remove_collapsed_context_sections(&mut self, cx: &mut ViewContext<Self>) {
+        let buffer = self.conversation.read(cx).buffer.read(cx).snapshot();
+        self.context_sections.retain(|_, section| {
+            let is_collapsed =
+                section.is_expanded && section.output_range.to_offset(&buffer).is_empty();
+            if is_collapsed {
+                self.removed_context_sections
+                    .push(section.output_range.start);
+            }
+            !is_collapsed
+        });
+    }
+
+    fn set_context_source(&mut self, id: usize, source: ContextSource, cx: &mut ViewContext<Self>) {
+        let Some(section) = self.context_sections.get_mut(&id) else {
+            return;
+        };
+        section._project_subscription = source.project().map(|project| {
+            let source = source.clone();
+            cx.subscribe(project, move |this, _, event, cx| {
+                if source.is_invalidated_by_project_event(event) {
+                    this.refresh_context_section(id, true, cx);
+                }
+            })
+        });
+        section.source = Some(source);
+        self.refresh_context_section(id, false, cx);
+    }
+
+    fn refresh_context_section(&mut self, id: usize, debounce: bool, cx: &mut ViewContext<Self>) {
+        let Some(section) = self.context_sections.get_mut(&id) else {
+            return;
+        };
+        let Some(source) = section.source.clone() else {
+            return;
+        };
+        section._expand = cx.spawn(|this, mut cx| {
+            async move {
+                if debounce {
+                    cx.background_executor()
+                        .timer(Duration::from_millis(300))
+                        .await;
+                }
+                let output = this.update(&mut cx, |_, cx| source.render(cx))?.await;
+                this.update(&mut cx, |this, cx| match output {
+                    Ok(output) => this.replace_context_section(id, output, cx),
+                    Err(error) => this.show_context_error(id, error, cx),
+                })
+            }
+            .log_err()
+        });
+    }
+
+    /// Replaces the section's text with the given output, keeping it folded
+    /// unless the user unfolded it.
+    fn replace_context_section(
+        &mut self,
+        id: usize,
+        output: ContextOutput,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(section) = self.context_sections.get_mut(&id) else {
+            return;
+        };
+        let Some(source) = section.source.clone() else {
+            return;
+        };
+        let buffer = self.conversation.read(cx).buffer.clone();
+        let snapshot = buffer.read(cx).snapshot();
+        let output_range = section.output_range.to_offset(&snapshot);
+
+        // Stop tracking sections whose command the user edited or removed.
+        let command_row = snapshot.offset_to_point(output_range.start).row;
+        let command_line = (command_row > 0).then(|| {
+            let row = command_row - 1;
+            snapshot
+                .text_for_range(
+                    language::Point::new(row, 0)..language::Point::new(row, snapshot.line_len(row)),
+                )
+                .collect::<String>()
+        });
+        if command_line
+            .and_then(|line| SlashCommand::parse(&line))
+            .as_ref()
+            != Some(&section.command)
+        {
+            self.context_sections.remove(&id);
+            return;
+        }
+
+        section._buffer_subscriptions = output
+            .buffers
+            .iter()
+            .map(|output_buffer| {
+                let source = source.clone();
+                cx.subscribe(output_buffer, move |this, _, event, cx| {
+                    if source.is_invalidated_by_buffer_event(event) {
+                        this.refresh_context_section(id, true, cx);
+                    }
+                })
+            })
+            .collect();
+
+        let old_text = snapshot
+            .text_for_range(output_range.clone())
+            .collect::<String>();
+        if old_text == output.text {
+            return;
+        }
+
+        let is_new = output_range.is_empty();
+        let start = output_range.start;
+        let end = start + output.text.len();
+        // The fold keeps the command line visible and hides the section below it.
+        let fold_range = start - 1..end - 1;
+        self.editor.update(cx, |editor, cx| {
+            let is_folded = is_new
+                || editor
+                    .snapshot(cx)
+                    .display_snapshot
+                    .intersects_fold(output_range.start);
+            let move_cursor =
+                is_new && editor.selections.newest::<usize>(cx).head() == output_range.start;
+
+            editor.unfold_ranges([start - 1..output_range.end], true, false, cx);
+            buffer.update(cx, |buffer, cx| {
+                buffer.edit([(output_range.clone(), output.text)], None, cx)
+            });
+            if is_folded {
+                editor.fold_ranges([fold_range], false, cx);
+            }
+            if move_cursor {
+                editor
+                    .change_selections(None, cx, |selections| selections.select_ranges([end..end]));
+            }
+        });
+
+        let snapshot = buffer.read(cx).snapshot();
+        if let Some(section) = self.context_sections.get_mut(&id) {
+            section.output_range = snapshot.anchor_after(start)..snapshot.anchor_before(end);
+            section.is_expanded = true;
+        }
+    }
+
+    fn show_context_error(&self, id: usize, error: anyhow::Error, cx: &mut ViewContext<Self>) {
+        if let Some(workspace) = self.workspace.upgrade() {
+            workspace.update(cx, |workspace, cx| {
+                workspace.show_toast(Toast::new(id, error.to_string()), cx)
+            });
+        }
+    }
+
     fn cursor_scroll_position(&self, cx: &mut ViewContext<Self>) -> Option<ScrollPosition> {
         self.editor.update(cx, |editor, cx| {
             let snapshot = editor.snapshot(cx);
@@ -2594,11 +2953,10 @@ impl ConversationEditor {
         });
     }
 
+    /// Switches to the next of the models the conversation's provider serves.
     fn cycle_model(&mut self, cx: &mut ViewContext<Self>) {
-        self.conversation.update(cx, |conversation, cx| {
-            let new_model = conversation.model.cycle();
-            conversation.set_model(new_model, cx);
-        });
+        self.conversation
+            .update(cx, |conversation, cx| conversation.cycle_model(cx));
     }
 
     fn title(&self, cx: &AppContext) -> String {
@@ -2613,7 +2971,12 @@ impl ConversationEditor {
     fn render_current_model(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         Button::new(
             "current_model",
-            self.conversation.read(cx).model.short_name(),
+            self.conversation
+                .read(cx)
+                .provider
+                .model
+                .display_name()
+                .to_string(),
         )
         .style(ButtonStyle::Filled)
         .tooltip(move |cx| Tooltip::text("Change Model", cx))
@@ -3244,10 +3607,12 @@ fn merge_ranges(ranges: &mut Vec<Range<Anchor>>, buffer: &MultiBufferSnapshot) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::MessageId;
+    use crate::{assistant_settings::ProviderSettingsContent, MessageId};
     use ai::test::FakeCompletionProvider;
-    use gpui::{AppContext, TestAppContext};
+    use gpui::{AppContext, TestAppContext, VisualTestContext, WindowHandle};
+    use serde_json::json;
     use settings::SettingsStore;
+    use workspace::AppState;
 
     #[gpui::test]
     fn test_inserting_and_removing_messages(cx: &mut AppContext) {
@@ -3257,7 +3622,10 @@ mod tests {
         let registry = Arc::new(LanguageRegistry::test());
 
         let completion_provider = Arc::new(FakeCompletionProvider::new());
-        let conversation = cx.new_model(|cx| Conversation::new(registry, cx, completion_provider));
+        let conversation = cx.new_model(|cx| {
+            let provider = AssistantSettings::get_global(cx).default_provider();
+            Conversation::new(registry, cx, provider, completion_provider)
+        });
         let buffer = conversation.read(cx).buffer.clone();
 
         let message_1 = conversation.read(cx).message_anchors[0].clone();
@@ -3388,7 +3756,10 @@ mod tests {
         let registry = Arc::new(LanguageRegistry::test());
         let completion_provider = Arc::new(FakeCompletionProvider::new());
 
-        let conversation = cx.new_model(|cx| Conversation::new(registry, cx, completion_provider));
+        let conversation = cx.new_model(|cx| {
+            let provider = AssistantSettings::get_global(cx).default_provider();
+            Conversation::new(registry, cx, provider, completion_provider)
+        });
         let buffer = conversation.read(cx).buffer.clone();
 
         let message_1 = conversation.read(cx).message_anchors[0].clone();
@@ -3486,7 +3857,10 @@ mod tests {
         init(cx);
         let registry = Arc::new(LanguageRegistry::test());
         let completion_provider = Arc::new(FakeCompletionProvider::new());
-        let conversation = cx.new_model(|cx| Conversation::new(registry, cx, completion_provider));
+        let conversation = cx.new_model(|cx| {
+            let provider = AssistantSettings::get_global(cx).default_provider();
+            Conversation::new(registry, cx, provider, completion_provider)
+        });
         let buffer = conversation.read(cx).buffer.clone();
 
         let message_1 = conversation.read(cx).message_anchors[0].clone();
@@ -3570,8 +3944,10 @@ mod tests {
         cx.update(init);
         let registry = Arc::new(LanguageRegistry::test());
         let completion_provider = Arc::new(FakeCompletionProvider::new());
-        let conversation =
-            cx.new_model(|cx| Conversation::new(registry.clone(), cx, completion_provider));
+        let conversation = cx.new_model(|cx| {
+            let provider = AssistantSettings::get_global(cx).default_provider();
+            Conversation::new(registry.clone(), cx, provider, completion_provider)
+        });
         let buffer = conversation.read_with(cx, |conversation, _| conversation.buffer.clone());
         let message_0 =
             conversation.read_with(cx, |conversation, _| conversation.message_anchors[0].id);
@@ -3629,6 +4005,261 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_deserializing_conversation_without_provider(cx: &mut TestAppContext) {
+        let settings_store = cx.update(SettingsStore::test);
+        cx.set_global(settings_store);
+        cx.update(init);
+        let registry = Arc::new(LanguageRegistry::test());
+
+        // Conversations saved by version 0.1.0 were all completed by OpenAI.
+        let saved_conversation = serde_json::from_str::<SavedConversation>(
+            r#"{
+                "id": "1",
+                "zed": "conversation",
+                "version": "0.1.0",
+                "text": "Hello\n",
+                "messages": [{ "id": 0, "start": 0 }],
+                "message_metadata": {
+                    "0": {
+                        "role": "user",
+                        "sent_at": "2024-01-01T00:00:00+00:00",
+                        "status": "Done"
+                    }
+                },
+                "summary": "Greeting",
+                "api_url": "https://api.openai.com/v1",
+                "model": "gpt-4-0613"
+            }"#,
+        )
+        .unwrap();
+        let conversation = Conversation::deserialize(
+            saved_conversation,
+            Default::default(),
+            registry,
+            &mut cx.to_async(),
+        )
+        .await
+        .unwrap();
+        conversation.read_with(cx, |conversation, cx| {
+            assert_eq!(conversation.provider.kind, AssistantProviderKind::OpenAi);
+            assert_eq!(conversation.provider.api_url, "https://api.openai.com/v1");
+            assert_eq!(conversation.provider.model.name, "gpt-4-0613");
+            assert_eq!(conversation.provider.model.display_name(), "gpt-4");
+            assert_eq!(conversation.buffer.read(cx).text(), "Hello\n");
+        });
+        assert_eq!(
+            cx.read(|cx| messages(&conversation, cx)),
+            [(MessageId(0), Role::User, 0..6)]
+        );
+    }
+
+    #[gpui::test]
+    fn test_cycling_models(cx: &mut AppContext) {
+        let settings_store = SettingsStore::test(cx);
+        cx.set_global(settings_store);
+        init(cx);
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings::<AssistantSettings>(cx, |settings| {
+                settings.provider = Some(AssistantProviderKind::Ollama);
+                settings.ollama = Some(ProviderSettingsContent {
+                    default_model: Some("mistral".into()),
+                    available_models: Some(vec![
+                        AvailableModel {
+                            name: "llama2".into(),
+                            display_name: None,
+                            max_tokens: 4096,
+                        },
+                        AvailableModel {
+                            name: "mistral".into(),
+                            display_name: Some("Mistral 7B".into()),
+                            max_tokens: 32768,
+                        },
+                    ]),
+                    ..Default::default()
+                });
+            });
+        });
+        let registry = Arc::new(LanguageRegistry::test());
+        let completion_provider = Arc::new(FakeCompletionProvider::new());
+        let conversation = cx.new_model(|cx| {
+            let provider = AssistantSettings::get_global(cx).default_provider();
+            Conversation::new(registry, cx, provider, completion_provider)
+        });
+        let model_name = |cx: &AppContext| conversation.read(cx).provider.model.name.clone();
+        assert_eq!(model_name(cx), "mistral");
+
+        // After the last model, cycling starts over from the first one.
+        conversation.update(cx, |conversation, cx| conversation.cycle_model(cx));
+        assert_eq!(model_name(cx), "llama2");
+        conversation.update(cx, |conversation, cx| conversation.cycle_model(cx));
+        assert_eq!(model_name(cx), "mistral");
+        assert_eq!(conversation.read(cx).provider.model.max_tokens, 32768);
+
+        // A model the provider no longer serves is followed by the first one.
+        conversation.update(cx, |conversation, cx| {
+            conversation.set_model(
+                AssistantSettings::get_global(cx).model(AssistantProviderKind::Ollama, "phi"),
+                cx,
+            )
+        });
+        assert_eq!(model_name(cx), "phi");
+        conversation.update(cx, |conversation, cx| conversation.cycle_model(cx));
+        assert_eq!(model_name(cx), "llama2");
+    }
+
+    #[gpui::test]
+    async fn test_slash_command_expansion(cx: &mut TestAppContext) {
+        let (workspace, source_buffer) = open_workspace_with_file(cx).await;
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let (conversation_editor, editor, buffer) = new_conversation_editor(workspace, cx);
+        let is_folded = |cx: &mut VisualTestContext| {
+            editor.update(cx, |editor, cx| {
+                editor
+                    .snapshot(cx)
+                    .display_snapshot
+                    .intersects_fold("/tab\n".len())
+            })
+        };
+
+        // The command expands once enter is pressed after it, and its section is
+        // folded below it.
+        editor.update(cx, |editor, cx| {
+            editor.handle_input("/tab", cx);
+            editor.newline(&editor::actions::Newline, cx);
+        });
+        buffer.update(cx, |buffer, _| buffer.finalize_last_transaction());
+        cx.run_until_parked();
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.text()),
+            "/tab\n```dir/one.rs\nconst ONE: usize = 1;\n```\n"
+        );
+        assert!(is_folded(cx));
+        editor.update(cx, |editor, cx| {
+            assert_eq!(
+                editor.selections.newest::<usize>(cx).head(),
+                buffer.read(cx).len()
+            );
+        });
+
+        // The section follows the edits of the buffer it was read from.
+        source_buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "// one\n")], None, cx));
+        cx.executor().advance_clock(Duration::from_millis(300));
+        cx.run_until_parked();
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.text()),
+            "/tab\n```dir/one.rs\n// one\nconst ONE: usize = 1;\n```\n"
+        );
+        assert!(is_folded(cx));
+
+        // Once the command line is edited, the section isn't updated anymore.
+        buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "see ")], None, cx));
+        source_buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "// two\n")], None, cx));
+        cx.executor().advance_clock(Duration::from_millis(300));
+        cx.run_until_parked();
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.text()),
+            "see /tab\n```dir/one.rs\n// one\nconst ONE: usize = 1;\n```\n"
+        );
+        conversation_editor.read_with(cx, |conversation_editor, _| {
+            assert!(conversation_editor.context_sections.is_empty())
+        });
+    }
+
+    #[gpui::test]
+    async fn test_undoing_slash_command_expansion(cx: &mut TestAppContext) {
+        let (workspace, source_buffer) = open_workspace_with_file(cx).await;
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let (conversation_editor, editor, buffer) = new_conversation_editor(workspace, cx);
+
+        editor.update(cx, |editor, cx| {
+            editor.handle_input("/tab", cx);
+            editor.newline(&editor::actions::Newline, cx);
+        });
+        buffer.update(cx, |buffer, _| buffer.finalize_last_transaction());
+        cx.run_until_parked();
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.text()),
+            "/tab\n```dir/one.rs\nconst ONE: usize = 1;\n```\n"
+        );
+
+        // Neither undoing the expansion nor editing the buffer it was read from
+        // afterwards expands the command again.
+        editor.update(cx, |editor, cx| editor.undo(&editor::actions::Undo, cx));
+        cx.run_until_parked();
+        assert_eq!(buffer.read_with(cx, |buffer, _| buffer.text()), "/tab\n");
+        conversation_editor.read_with(cx, |conversation_editor, _| {
+            assert!(conversation_editor.context_sections.is_empty())
+        });
+        source_buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "// one\n")], None, cx));
+        cx.executor().advance_clock(Duration::from_millis(300));
+        cx.run_until_parked();
+        assert_eq!(buffer.read_with(cx, |buffer, _| buffer.text()), "/tab\n");
+    }
+
+    /// Opens a project with one file, and the file in an editor.
+    async fn open_workspace_with_file(
+        cx: &mut TestAppContext,
+    ) -> (WindowHandle<Workspace>, Model<Buffer>) {
+        let app_state = cx.update(|cx| {
+            let app_state = AppState::test(cx);
+            language::init(cx);
+            editor::init(cx);
+            Project::init_settings(cx);
+            init(cx);
+            app_state
+        });
+        app_state
+            .fs
+            .as_fake()
+            .insert_tree("/dir", json!({ "one.rs": "const ONE: usize = 1;\n" }))
+            .await;
+        let project = Project::test(app_state.fs.clone(), ["/dir".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+
+        let worktree_id = project.read_with(cx, |project, cx| {
+            project.worktrees().next().unwrap().read(cx).id()
+        });
+        let source_editor = workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_path((worktree_id, "one.rs"), None, true, cx)
+            })
+            .unwrap()
+            .await
+            .unwrap()
+            .downcast::<Editor>()
+            .unwrap();
+        let source_buffer = source_editor.read_with(cx, |editor, cx| {
+            editor.buffer().read(cx).as_singleton().unwrap()
+        });
+        (workspace, source_buffer)
+    }
+
+    fn new_conversation_editor(
+        workspace: WindowHandle<Workspace>,
+        cx: &mut VisualTestContext,
+    ) -> (View<ConversationEditor>, View<Editor>, Model<Buffer>) {
+        let workspace = workspace.root_view(cx).unwrap();
+        let fs = workspace.read_with(cx, |workspace, _| workspace.app_state().fs.clone());
+        let conversation_editor = cx.new_view(|cx| {
+            ConversationEditor::new(
+                AssistantSettings::get_global(cx).default_provider(),
+                Arc::new(FakeCompletionProvider::new()),
+                Arc::new(LanguageRegistry::test()),
+                fs,
+                workspace.downgrade(),
+                cx,
+            )
+        });
+        let (editor, buffer) = conversation_editor.read_with(cx, |conversation_editor, cx| {
+            (
+                conversation_editor.editor.clone(),
+                conversation_editor.conversation.read(cx).buffer.clone(),
+            )
+        });
+        (conversation_editor, editor, buffer)
+    }
+
     fn messages(
         conversation: &Model<Conversation>,
         cx: &AppContext,
@@ -3654,9 +4285,7 @@ fn report_assistant_event(
     let client = workspace.read(cx).project().read(cx).client();
     let telemetry = client.telemetry();
 
-    let model = AssistantSettings::get_global(cx)
-        .default_open_ai_model
-        .clone();
+    let model = AssistantSettings::get_global(cx).default_provider().model;
 
-    telemetry.report_assistant_event(conversation_id, assistant_kind, model.full_name())
+    telemetry.report_assistant_event(conversation_id, assistant_kind, model.name)
 }
@@ -4,6 +4,9 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
 
+/// The context size assumed for models that aren't declared in the settings.
+const DEFAULT_MAX_TOKENS: usize = 4096;
+
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub enum OpenAiModel {
     #[serde(rename = "gpt-3.5-turbo-0613")]
@@ -31,15 +34,73 @@ impl OpenAiModel {
         }
     }
 
-    pub fn cycle(&self) -> Self {
-        match self {
-            OpenAiModel::ThreePointFiveTurbo => OpenAiModel::Four,
-            OpenAiModel::Four => OpenAiModel::FourTurbo,
-            OpenAiModel::FourTurbo => OpenAiModel::ThreePointFiveTurbo,
+    pub fn available_model(&self) -> AvailableModel {
+        AvailableModel {
+            name: self.full_name().into(),
+            display_name: Some(self.short_name().into()),
+            max_tokens: tiktoken_rs::model::get_context_size(self.full_name()),
         }
     }
 }
 
+/// The service that completes conversations and inline assists.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub enum AssistantProviderKind {
+    #[default]
+    #[serde(rename = "openai")]
+    OpenAi,
+    #[serde(rename = "anthropic")]
+    Anthropic,
+    #[serde(rename = "ollama")]
+    Ollama,
+    /// A server that implements OpenAI's chat completions API.
+    #[serde(rename = "openai_compatible")]
+    OpenAiCompatible,
+}
+
+/// A model that a provider serves.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct AvailableModel {
+    /// The name of the model in the provider's API.
+    pub name: String,
+    /// The name to show for the model, if it's not the model's name.
+    pub display_name: Option<String>,
+    /// The number of tokens that fit in the model's context.
+    pub max_tokens: usize,
+}
+
+impl AvailableModel {
+    pub fn display_name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// A provider and one of its models.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssistantProvider {
+    pub kind: AssistantProviderKind,
+    pub api_url: String,
+    pub model: AvailableModel,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProviderSettings {
+    pub api_url: String,
+    pub default_model: String,
+    pub available_models: Vec<AvailableModel>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct ProviderSettingsContent {
+    /// The base URL of the provider's API.
+    pub api_url: Option<String>,
+    /// The model to use when starting new conversations.
+    pub default_model: Option<String>,
+    /// The models the provider serves, which the model button in a conversation
+    /// cycles through.
+    pub available_models: Option<Vec<AvailableModel>>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AssistantDockPosition {
@@ -56,6 +117,70 @@ pub struct AssistantSettings {
     pub default_height: Pixels,
     pub default_open_ai_model: OpenAiModel,
     pub openai_api_url: String,
+    pub provider: AssistantProviderKind,
+    pub anthropic: ProviderSettings,
+    pub ollama: ProviderSettings,
+    pub openai_compatible: ProviderSettings,
+}
+
+impl AssistantSettings {
+    /// The provider and model to start conversations with.
+    pub fn default_provider(&self) -> AssistantProvider {
+        let model = match self.provider_settings(self.provider) {
+            Some(settings) => self.model(self.provider, &settings.default_model),
+            None => self.default_open_ai_model.available_model(),
+        };
+        AssistantProvider {
+            kind: self.provider,
+            api_url: self.api_url(self.provider).into(),
+            model,
+        }
+    }
+
+    pub fn api_url(&self, kind: AssistantProviderKind) -> &str {
+        match self.provider_settings(kind) {
+            Some(settings) => &settings.api_url,
+            None => &self.openai_api_url,
+        }
+    }
+
+    pub fn models(&self, kind: AssistantProviderKind) -> Vec<AvailableModel> {
+        match self.provider_settings(kind) {
+            Some(settings) => settings.available_models.clone(),
+            None => [
+                OpenAiModel::ThreePointFiveTurbo,
+                OpenAiModel::Four,
+                OpenAiModel::FourTurbo,
+            ]
+            .iter()
+            .map(OpenAiModel::available_model)
+            .collect(),
+        }
+    }
+
+    /// Looks up one of the provider's models, assuming a small context for models
+    /// that aren't declared in the settings.
+    pub fn model(&self, kind: AssistantProviderKind, name: &str) -> AvailableModel {
+        self.models(kind)
+            .into_iter()
+            .find(|model| model.name == name)
+            .unwrap_or_else(|| AvailableModel {
+                name: name.into(),
+                display_name: None,
+                max_tokens: DEFAULT_MAX_TOKENS,
+            })
+    }
+
+    /// The settings of providers other than OpenAI, whose settings are top-level
+    /// as they predate the other providers.
+    fn provider_settings(&self, kind: AssistantProviderKind) -> Option<&ProviderSettings> {
+        match kind {
+            AssistantProviderKind::OpenAi => None,
+            AssistantProviderKind::Anthropic => Some(&self.anthropic),
+            AssistantProviderKind::Ollama => Some(&self.ollama),
+            AssistantProviderKind::OpenAiCompatible => Some(&self.openai_compatible),
+        }
+    }
 }
 
 /// Assistant panel settings
@@ -85,6 +210,16 @@ pub struct AssistantSettingsContent {
     ///
     /// Default: https://api.openai.com/v1
     pub openai_api_url: Option<String>,
+    /// The provider to complete new conversations and inline assists with.
+    ///
+    /// Default: openai
+    pub provider: Option<AssistantProviderKind>,
+    /// Settings for Anthropic's API.
+    pub anthropic: Option<ProviderSettingsContent>,
+    /// Settings for a local Ollama server.
+    pub ollama: Option<ProviderSettingsContent>,
+    /// Settings for a server that implements OpenAI's chat completions API.
+    pub openai_compatible: Option<ProviderSettingsContent>,
 }
 
 impl Settings for AssistantSettings {
@@ -100,3 +235,87 @@ impl Settings for AssistantSettings {
         Self::load_via_json_merge(default_value, user_values)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::AppContext;
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    fn test_default_provider(cx: &mut AppContext) {
+        let store = SettingsStore::test(cx);
+        cx.set_global(store);
+        AssistantSettings::register(cx);
+
+        // OpenAI's settings are the top-level ones.
+        let provider = AssistantSettings::get_global(cx).default_provider();
+        assert_eq!(provider.kind, AssistantProviderKind::OpenAi);
+        assert_eq!(provider.api_url, "https://api.openai.com/v1");
+        assert_eq!(provider.model, OpenAiModel::FourTurbo.available_model());
+
+        update_settings(cx, |settings| {
+            settings.provider = Some(AssistantProviderKind::Anthropic);
+        });
+        let provider = AssistantSettings::get_global(cx).default_provider();
+        assert_eq!(provider.kind, AssistantProviderKind::Anthropic);
+        assert_eq!(provider.api_url, "https://api.anthropic.com/v1");
+        assert_eq!(provider.model.name, "claude-3-opus-20240229");
+        assert_eq!(provider.model.display_name(), "claude-3-opus");
+        assert_eq!(provider.model.max_tokens, 200000);
+
+        // A default model that isn't declared is assumed to have a small context.
+        update_settings(cx, |settings| {
+            settings.provider = Some(AssistantProviderKind::Ollama);
+            settings.ollama = Some(ProviderSettingsContent {
+                default_model: Some("mistral".into()),
+                ..Default::default()
+            });
+        });
+        let provider = AssistantSettings::get_global(cx).default_provider();
+        assert_eq!(provider.kind, AssistantProviderKind::Ollama);
+        assert_eq!(provider.api_url, "http://localhost:11434");
+        assert_eq!(
+            provider.model,
+            AvailableModel {
+                name: "mistral".into(),
+                display_name: None,
+                max_tokens: DEFAULT_MAX_TOKENS,
+            }
+        );
+        assert_eq!(provider.model.display_name(), "mistral");
+    }
+
+    #[gpui::test]
+    fn test_model(cx: &mut AppContext) {
+        let store = SettingsStore::test(cx);
+        cx.set_global(store);
+        AssistantSettings::register(cx);
+
+        let settings = AssistantSettings::get_global(cx);
+        assert_eq!(
+            settings.model(AssistantProviderKind::OpenAi, "gpt-4-0613"),
+            OpenAiModel::Four.available_model()
+        );
+        assert_eq!(
+            settings
+                .model(AssistantProviderKind::Ollama, "llama2")
+                .max_tokens,
+            4096
+        );
+        assert_eq!(
+            settings.model(AssistantProviderKind::OpenAiCompatible, "phi"),
+            AvailableModel {
+                name: "phi".into(),
+                display_name: None,
+                max_tokens: DEFAULT_MAX_TOKENS,
+            }
+        );
+    }
+
+    fn update_settings(cx: &mut AppContext, update: impl FnOnce(&mut AssistantSettingsContent)) {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings::<AssistantSettings>(cx, update);
+        });
+    }
+}
@@ -128,10 +128,14 @@ pub fn generate_content_prompt(
     range: Range<usize>,
     search_results: Vec<PromptCodeSnippet>,
     model: &str,
+    model_capacity: usize,
     project_name: Option<String>,
 ) -> anyhow::Result<String> {
     // Using new Prompt Templates
-    let openai_model: Arc<dyn LanguageModel> = Arc::new(OpenAiLanguageModel::load(model));
+    let openai_model: Arc<dyn LanguageModel> = Arc::new(OpenAiLanguageModel::load_with_capacity(
+        model,
+        model_capacity,
+    ));
     let lang_name = if let Some(language_name) = language_name {
         Some(language_name.to_string())
     } else {
@@ -0,0 +1,909 @@
+use anyhow::{anyhow, Result};
+use editor::Editor;
+use fs::repository::GitFileStatus;
+use futures::StreamExt;
+use fuzzy::StringMatchCandidate;
+use git::diff::BufferDiff;
+use gpui::{AppContext, Model, Task, View};
+use language::{
+    Buffer, BufferSnapshot, CodeLabel, Completion, DiagnosticSeverity, Documentation,
+    LanguageServerId, OffsetRangeExt, Point, ToOffset, ToPoint,
+};
+use project::{search::SearchQuery, PathMatchCandidateSet, Project, ProjectPath};
+use std::{
+    fmt::Write,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+use workspace::Workspace;
+
+const MAX_SEARCH_RESULTS: usize = 100;
+const MAX_PATH_COMPLETIONS: usize = 20;
+
+/// A `/command` typed on its own line of a conversation, which expands into a
+/// section of context from the project.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SlashCommand {
+    File(String),
+    Tab,
+    Selection,
+    Diagnostics,
+    Symbols,
+    Search(String),
+    GitDiff,
+}
+
+impl SlashCommand {
+    /// The name, argument and description of each command, in the order they
+    /// are offered as completions.
+    pub const ALL: &'static [(&'static str, Option<&'static str>, &'static str)] = &[
+        ("file", Some("<path>"), "Insert a file from the project"),
+        ("tab", None, "Insert the active tab"),
+        ("selection", None, "Insert the active editor's selection"),
+        (
+            "diagnostics",
+            None,
+            "Insert the project's errors and warnings",
+        ),
+        ("symbols", None, "Insert the active tab's outline"),
+        ("search", Some("<query>"), "Insert project search results"),
+        ("git-diff", None, "Insert the project's uncommitted changes"),
+    ];
+
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end().strip_prefix('/')?;
+        let (name, argument) = match line.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (line, ""),
+        };
+        match (name, argument) {
+            ("file", path) if !path.is_empty() => Some(Self::File(path.into())),
+            ("tab", "") => Some(Self::Tab),
+            ("selection", "") => Some(Self::Selection),
+            ("diagnostics", "") => Some(Self::Diagnostics),
+            ("symbols", "") => Some(Self::Symbols),
+            ("search", query) if !query.is_empty() => Some(Self::Search(query.into())),
+            ("git-diff", "") => Some(Self::GitDiff),
+            _ => None,
+        }
+    }
+
+    /// Finds what the command refers to. Commands that refer to the active
+    /// editor keep referring to the same buffer after the user switches tabs.
+    pub fn resolve(
+        &self,
+        workspace: &View<Workspace>,
+        cx: &mut AppContext,
+    ) -> Task<Result<ContextSource>> {
+        let project = workspace.read(cx).project().clone();
+        let active_editor = workspace
+            .read(cx)
+            .active_item(cx)
+            .and_then(|item| item.act_as::<Editor>(cx));
+        let active_buffer = active_editor
+            .as_ref()
+            .and_then(|editor| editor.read(cx).buffer().read(cx).as_singleton());
+
+        let source = match self {
+            Self::File(path) => {
+                let Some(project_path) = project_path_for(project.read(cx), path, cx) else {
+                    return Task::ready(Err(anyhow!("no file named {path:?} in the project")));
+                };
+                let open_buffer =
+                    project.update(cx, |project, cx| project.open_buffer(project_path, cx));
+                return cx
+                    .foreground_executor()
+                    .spawn(async move { Ok(ContextSource::Buffer(open_buffer.await?)) });
+            }
+            Self::Tab => active_buffer
+                .map(ContextSource::Buffer)
+                .ok_or_else(|| anyhow!("/tab needs an active editor with a single file")),
+            Self::Selection => active_editor
+                .zip(active_buffer)
+                .map(|(editor, buffer)| {
+                    // Offsets in a singleton multi-buffer are offsets in its buffer.
+                    let range = editor.read(cx).selections.newest::<usize>(cx).range();
+                    let range = buffer.read(cx).anchor_before(range.start)
+                        ..buffer.read(cx).anchor_after(range.end);
+                    ContextSource::Selection { buffer, range }
+                })
+                .ok_or_else(|| anyhow!("/selection needs an active editor with a single file")),
+            Self::Symbols => active_buffer
+                .map(ContextSource::Symbols)
+                .ok_or_else(|| anyhow!("/symbols needs an active editor with a single file")),
+            Self::Diagnostics => Ok(ContextSource::Diagnostics(project)),
+            Self::Search(query) => Ok(ContextSource::Search {
+                project,
+                query: query.clone(),
+            }),
+            Self::GitDiff => Ok(ContextSource::GitDiff(project)),
+        };
+        Task::ready(source)
+    }
+}
+
+/// The data a slash command expanded from, kept so that its section can be
+/// expanded again when the data changes.
+#[derive(Clone)]
+pub enum ContextSource {
+    Buffer(Model<Buffer>),
+    Selection {
+        buffer: Model<Buffer>,
+        range: Range<language::Anchor>,
+    },
+    Symbols(Model<Buffer>),
+    Diagnostics(Model<Project>),
+    Search {
+        project: Model<Project>,
+        query: String,
+    },
+    GitDiff(Model<Project>),
+}
+
+/// The text of an expanded section, along with the buffers it was read from.
+pub struct ContextOutput {
+    pub text: String,
+    pub buffers: Vec<Model<Buffer>>,
+}
+
+impl ContextSource {
+    pub fn project(&self) -> Option<&Model<Project>> {
+        match self {
+            Self::Diagnostics(project) | Self::Search { project, .. } | Self::GitDiff(project) => {
+                Some(project)
+            }
+            Self::Buffer(_) | Self::Selection { .. } | Self::Symbols(_) => None,
+        }
+    }
+
+    pub fn is_invalidated_by_project_event(&self, event: &project::Event) -> bool {
+        match self {
+            Self::Diagnostics(_) => matches!(
+                event,
+                project::Event::DiagnosticsUpdated { .. }
+                    | project::Event::DiskBasedDiagnosticsFinished { .. }
+            ),
+            Self::Search { .. } | Self::GitDiff(_) => {
+                matches!(event, project::Event::WorktreeUpdatedEntries(..))
+            }
+            Self::Buffer(_) | Self::Selection { .. } | Self::Symbols(_) => false,
+        }
+    }
+
+    pub fn is_invalidated_by_buffer_event(&self, event: &language::Event) -> bool {
+        match self {
+            Self::Buffer(_) | Self::Selection { .. } | Self::Search { .. } => {
+                matches!(event, language::Event::Edited | language::Event::Reloaded)
+            }
+            Self::Symbols(_) => matches!(
+                event,
+                language::Event::Reparsed | language::Event::LanguageChanged
+            ),
+            Self::GitDiff(_) => matches!(
+                event,
+                language::Event::Edited
+                    | language::Event::Reloaded
+                    | language::Event::DiffBaseChanged
+            ),
+            Self::Diagnostics(_) => false,
+        }
+    }
+
+    /// Produces the section's text: a fenced block that ends with a newline.
+    pub fn render(&self, cx: &mut AppContext) -> Task<Result<ContextOutput>> {
+        match self {
+            Self::Buffer(buffer) => {
+                let snapshot = buffer.read(cx).snapshot();
+                let path = buffer_path(buffer, cx);
+                let text = fenced(&path, &snapshot.text());
+                Task::ready(Ok(ContextOutput {
+                    text,
+                    buffers: vec![buffer.clone()],
+                }))
+            }
+            Self::Selection { buffer, range } => {
+                let snapshot = buffer.read(cx).snapshot();
+                let rows = range.to_point(&snapshot);
+                let path = format!(
+                    "{}:{}-{}",
+                    buffer_path(buffer, cx),
+                    rows.start.row + 1,
+                    rows.end.row + 1
+                );
+                let text = snapshot.text_for_range(range.clone()).collect::<String>();
+                let text = fenced(&path, &text);
+                Task::ready(Ok(ContextOutput {
+                    text,
+                    buffers: vec![buffer.clone()],
+                }))
+            }
+            Self::Symbols(buffer) => {
+                let snapshot = buffer.read(cx).snapshot();
+                let mut symbols = String::new();
+                if let Some(outline) = snapshot.outline(None) {
+                    for item in outline.items {
+                        let row = item.range.start.to_point(&snapshot).row;
+                        writeln!(
+                            symbols,
+                            "{}{} (line {})",
+                            "  ".repeat(item.depth),
+                            item.text,
+                            row + 1
+                        )
+                        .unwrap();
+                    }
+                }
+                let path = format!("{} symbols", buffer_path(buffer, cx));
+                Task::ready(Ok(ContextOutput {
+                    text: fenced(&path, &symbols),
+                    buffers: vec![buffer.clone()],
+                }))
+            }
+            Self::Diagnostics(project) => render_diagnostics(project.clone(), cx),
+            Self::Search { project, query } => render_search(project.clone(), query, cx),
+            Self::GitDiff(project) => render_git_diff(project.clone(), cx),
+        }
+    }
+}
+
+fn render_diagnostics(project: Model<Project>, cx: &mut AppContext) -> Task<Result<ContextOutput>> {
+    let mut paths = project
+        .read(cx)
+        .diagnostic_summaries(false, cx)
+        .filter(|(_, _, summary)| summary.error_count + summary.warning_count > 0)
+        .map(|(path, _, _)| path)
+        .collect::<Vec<_>>();
+    paths.dedup();
+
+    cx.spawn(|mut cx| async move {
+        let mut diagnostics = String::new();
+        let mut buffers = Vec::new();
+        for path in paths {
+            let buffer = project
+                .update(&mut cx, |project, cx| project.open_buffer(path, cx))?
+                .await?;
+            buffer.read_with(&cx, |buffer, cx| {
+                let snapshot = buffer.snapshot();
+                let path = snapshot
+                    .file()
+                    .map(|file| file.full_path(cx))
+                    .unwrap_or_default();
+                for entry in snapshot.diagnostics_in_range::<_, Point>(0..snapshot.len(), false) {
+                    if !entry.diagnostic.is_primary {
+                        continue;
+                    }
+                    let severity = match entry.diagnostic.severity {
+                        DiagnosticSeverity::ERROR => "error",
+                        DiagnosticSeverity::WARNING => "warning",
+                        _ => continue,
+                    };
+                    writeln!(
+                        diagnostics,
+                        "{}:{}:{}: {severity}: {}",
+                        path.display(),
+                        entry.range.start.row + 1,
+                        entry.range.start.column + 1,
+                        entry.diagnostic.message
+                    )
+                    .unwrap();
+                }
+            })?;
+            buffers.push(buffer);
+        }
+
+        Ok(ContextOutput {
+            text: fenced("diagnostics", &diagnostics),
+            buffers,
+        })
+    })
+}
+
+fn render_search(
+    project: Model<Project>,
+    query: &str,
+    cx: &mut AppContext,
+) -> Task<Result<ContextOutput>> {
+    let search_query = match SearchQuery::text(query, false, false, false, Vec::new(), Vec::new()) {
+        Ok(search_query) => search_query,
+        Err(error) => return Task::ready(Err(error)),
+    };
+    let mut results = project.update(cx, |project, cx| project.search(search_query, cx));
+    let info = format!("search {query}");
+
+    cx.spawn(|cx| async move {
+        let mut matches = Vec::new();
+        let mut buffers = Vec::new();
+        while let Some((buffer, ranges)) = results.next().await {
+            buffer.read_with(&cx, |buffer, cx| {
+                let snapshot = buffer.snapshot();
+                let path = snapshot
+                    .file()
+                    .map(|file| file.full_path(cx))
+                    .unwrap_or_default();
+                let mut last_row = None;
+                for range in ranges {
+                    let row = range.start.to_point(&snapshot).row;
+                    if last_row.replace(row) != Some(row) {
+                        let line = line_text(&snapshot, row);
+                        matches.push((path.clone(), row, line.trim().to_string()));
+                    }
+                }
+            })?;
+            buffers.push(buffer);
+            if matches.len() >= MAX_SEARCH_RESULTS {
+                break;
+            }
+        }
+
+        // Buffers are searched concurrently, so sort the results to keep the
+        // section stable when it's expanded again.
+        matches.sort();
+        let mut text = String::new();
+        for (path, row, line) in matches.into_iter().take(MAX_SEARCH_RESULTS) {
+            writeln!(text, "{}:{}: {}", path.display(), row + 1, line).unwrap();
+        }
+        Ok(ContextOutput {
+            text: fenced(&info, &text),
+            buffers,
+        })
+    })
+}
+
+fn render_git_diff(project: Model<Project>, cx: &mut AppContext) -> Task<Result<ContextOutput>> {
+    let mut paths = Vec::new();
+    for worktree in project.read(cx).visible_worktrees(cx) {
+        let worktree = worktree.read(cx);
+        for entry in worktree.entries(false) {
+            if entry.is_file()
+                && matches!(
+                    entry.git_status,
+                    Some(GitFileStatus::Added | GitFileStatus::Modified)
+                )
+            {
+                paths.push(ProjectPath {
+                    worktree_id: worktree.id(),
+                    path: entry.path.clone(),
+                });
+            }
+        }
+    }
+
+    cx.spawn(|mut cx| async move {
+        let mut diff = String::new();
+        let mut buffers = Vec::new();
+        for path in paths {
+            let buffer = project
+                .update(&mut cx, |project, cx| project.open_buffer(path, cx))?
+                .await?;
+            let (path, diff_base, snapshot) = buffer.read_with(&cx, |buffer, cx| {
+                let path = buffer
+                    .file()
+                    .map(|file| file.full_path(cx))
+                    .unwrap_or_default();
+                let diff_base = buffer.diff_base().unwrap_or_default().to_string();
+                (path, diff_base, buffer.snapshot())
+            })?;
+            let file_diff = cx
+                .background_executor()
+                .spawn(async move { unified_diff(&path, &diff_base, &snapshot).await })
+                .await;
+            diff.push_str(&file_diff);
+            buffers.push(buffer);
+        }
+
+        Ok(ContextOutput {
+            text: fenced("diff", &diff),
+            buffers,
+        })
+    })
+}
+
+async fn unified_diff(path: &Path, diff_base: &str, snapshot: &BufferSnapshot) -> String {
+    let mut buffer_diff = BufferDiff::new();
+    buffer_diff.update(diff_base, snapshot).await;
+
+    let mut diff = String::new();
+    let hunks = buffer_diff
+        .hunks_intersecting_range(language::Anchor::MIN..language::Anchor::MAX, snapshot);
+    for hunk in hunks {
+        if diff.is_empty() {
+            writeln!(diff, "--- a/{}", path.display()).unwrap();
+            writeln!(diff, "+++ b/{}", path.display()).unwrap();
+        }
+
+        let old_text = &diff_base[hunk.diff_base_byte_range.clone()];
+        let old_start = diff_base[..hunk.diff_base_byte_range.start]
+            .matches('\n')
+            .count();
+        writeln!(
+            diff,
+            "@@ -{},{} +{},{} @@",
+            old_start + 1,
+            old_text.lines().count(),
+            hunk.buffer_range.start + 1,
+            hunk.buffer_range.len()
+        )
+        .unwrap();
+        for line in old_text.lines() {
+            writeln!(diff, "-{line}").unwrap();
+        }
+        for row in hunk.buffer_range {
+            writeln!(diff, "+{}", line_text(snapshot, row)).unwrap();
+        }
+    }
+    diff
+}
+
+/// Completes command names after a `/` at the start of a line, and project
+/// paths after `/file `.
+pub fn completions(
+    project: Option<Model<Project>>,
+    buffer: &Model<Buffer>,
+    position: language::Anchor,
+    cx: &mut AppContext,
+) -> Task<Result<Vec<Completion>>> {
+    let snapshot = buffer.read(cx).snapshot();
+    let position = position.to_point(&snapshot);
+    let line_start = Point::new(position.row, 0);
+    let line = snapshot
+        .text_for_range(line_start..position)
+        .collect::<String>();
+    let Some(command) = line.strip_prefix('/') else {
+        return Task::ready(Ok(Vec::new()));
+    };
+
+    let end = snapshot.anchor_after(position);
+    match command.split_once(' ') {
+        None => {
+            let start = snapshot.anchor_before(line_start.to_offset(&snapshot) + 1);
+            let candidates = SlashCommand::ALL
+                .iter()
+                .enumerate()
+                .map(|(id, (name, _, _))| StringMatchCandidate {
+                    id,
+                    string: name.to_string(),
+                    char_bag: name.chars().collect(),
+                })
+                .collect::<Vec<_>>();
+            let query = command.to_string();
+            let executor = cx.background_executor().clone();
+            cx.background_executor().spawn(async move {
+                let matches = fuzzy::match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    SlashCommand::ALL.len(),
+                    &Default::default(),
+                    executor,
+                )
+                .await;
+                Ok(matches
+                    .into_iter()
+                    .map(|mat| {
+                        let (name, argument, description) = SlashCommand::ALL[mat.candidate_id];
+                        let new_text = if argument.is_some() {
+                            format!("{name} ")
+                        } else {
+                            name.to_string()
+                        };
+                        let label = format!("/{name} {}", argument.unwrap_or_default());
+                        Completion {
+                            old_range: start..end,
+                            new_text,
+                            label: CodeLabel {
+                                filter_range: 1..name.len() + 1,
+                                text: label.trim_end().to_string(),
+                                runs: Vec::new(),
+                            },
+                            documentation: Some(Documentation::SingleLine(description.into())),
+                            server_id: LanguageServerId(0),
+                            lsp_completion: Default::default(),
+                        }
+                    })
+                    .collect())
+            })
+        }
+        Some(("file", query)) => {
+            let Some(project) = project else {
+                return Task::ready(Ok(Vec::new()));
+            };
+            let start = snapshot.anchor_before(position.to_offset(&snapshot) - query.len());
+            let worktrees = project.read(cx).visible_worktrees(cx).collect::<Vec<_>>();
+            let include_root_name = worktrees.len() > 1;
+            let candidate_sets = worktrees
+                .into_iter()
+                .map(|worktree| PathMatchCandidateSet {
+                    snapshot: worktree.read(cx).snapshot(),
+                    include_ignored: false,
+                    include_root_name,
+                })
+                .collect::<Vec<_>>();
+            let query = query.to_string();
+            let executor = cx.background_executor().clone();
+            cx.background_executor().spawn(async move {
+                let cancel_flag = AtomicBool::new(false);
+                let matches = fuzzy::match_path_sets(
+                    &candidate_sets,
+                    &query,
+                    None,
+                    false,
+                    MAX_PATH_COMPLETIONS,
+                    &cancel_flag,
+                    executor,
+                )
+                .await;
+                Ok(matches
+                    .into_iter()
+                    .map(|mat| {
+                        let path = format!("{}{}", mat.path_prefix, mat.path.to_string_lossy());
+                        Completion {
+                            old_range: start..end,
+                            new_text: path.clone(),
+                            label: CodeLabel {
+                                filter_range: 0..path.len(),
+                                text: path,
+                                runs: Vec::new(),
+                            },
+                            documentation: None,
+                            server_id: LanguageServerId(0),
+                            lsp_completion: Default::default(),
+                        }
+                    })
+                    .collect())
+            })
+        }
+        Some(_) => Task::ready(Ok(Vec::new())),
+    }
+}
+
+/// Finds a file by its path relative to a worktree, optionally prefixed by the
+/// worktree's name, as `/file` completions are when several are open.
+fn project_path_for(project: &Project, path: &str, cx: &AppContext) -> Option<ProjectPath> {
+    let path = Path::new(path);
+    project.visible_worktrees(cx).find_map(|worktree| {
+        let worktree = worktree.read(cx);
+        let relative_paths = path
+            .strip_prefix(worktree.root_name())
+            .ok()
+            .into_iter()
+            .chain([path]);
+        for relative_path in relative_paths {
+            if worktree
+                .entry_for_path(relative_path)
+                .map_or(false, |entry| entry.is_file())
+            {
+                return Some(ProjectPath {
+                    worktree_id: worktree.id(),
+                    path: relative_path.into(),
+                });
+            }
+        }
+        None
+    })
+}
+
+fn buffer_path(buffer: &Model<Buffer>, cx: &AppContext) -> String {
+    buffer
+        .read(cx)
+        .file()
+        .map(|file| file.full_path(cx))
+        .unwrap_or_else(|| PathBuf::from("untitled"))
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn line_text(snapshot: &BufferSnapshot, row: u32) -> String {
+    snapshot
+        .text_for_range(Point::new(row, 0)..Point::new(row, snapshot.line_len(row)))
+        .collect()
+}
+
+fn fenced(info: &str, text: &str) -> String {
+    let mut fenced = format!("```{info}\n{text}");
+    if !fenced.ends_with('\n') {
+        fenced.push('\n');
+    }
+    fenced.push_str("```\n");
+    fenced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::FakeFs;
+    use gpui::{Context, TestAppContext};
+    use language::{
+        BufferId, Diagnostic, DiagnosticEntry, Language, LanguageConfig, LanguageMatcher,
+        PointUtf16, Unclipped,
+    };
+    use serde_json::json;
+    use settings::SettingsStore;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            SlashCommand::parse("/file src/main.rs "),
+            Some(SlashCommand::File("src/main.rs".into()))
+        );
+        assert_eq!(
+            SlashCommand::parse("/search  fn main"),
+            Some(SlashCommand::Search("fn main".into()))
+        );
+        assert_eq!(
+            SlashCommand::parse("/git-diff"),
+            Some(SlashCommand::GitDiff)
+        );
+        assert_eq!(SlashCommand::parse("/file"), None);
+        assert_eq!(SlashCommand::parse("/tab main.rs"), None);
+        assert_eq!(SlashCommand::parse("/unknown"), None);
+        assert_eq!(SlashCommand::parse("see /tab"), None);
+    }
+
+    #[gpui::test]
+    async fn test_render(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            language::init(cx);
+            Project::init_settings(cx);
+        });
+
+        let fs = FakeFs::new(cx.background_executor.clone());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                ".git": {},
+                "one.rs": "const ONE: usize = 1;\n",
+                "two.rs": "const TWO: usize = one::ONE + one::ONE;\n",
+            }),
+        )
+        .await;
+        fs.set_index_for_repo(
+            Path::new("/dir/.git"),
+            &[(Path::new("two.rs"), "const TWO: usize = 2;\n".into())],
+        );
+        fs.set_status_for_repo_via_git_operation(
+            Path::new("/dir/.git"),
+            &[(Path::new("two.rs"), GitFileStatus::Modified)],
+        );
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        cx.executor().run_until_parked();
+
+        let project_path = cx
+            .update(|cx| project_path_for(project.read(cx), "dir/one.rs", cx))
+            .unwrap();
+        let buffer = project
+            .update(cx, |project, cx| project.open_buffer(project_path, cx))
+            .await
+            .unwrap();
+        let output = cx
+            .update(|cx| ContextSource::Buffer(buffer).render(cx))
+            .await
+            .unwrap();
+        assert_eq!(output.text, "```dir/one.rs\nconst ONE: usize = 1;\n```\n");
+
+        let output = cx
+            .update(|cx| {
+                ContextSource::Search {
+                    project: project.clone(),
+                    query: "one::ONE".into(),
+                }
+                .render(cx)
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            output.text,
+            "```search one::ONE\ndir/two.rs:1: const TWO: usize = one::ONE + one::ONE;\n```\n"
+        );
+
+        let output = cx
+            .update(|cx| ContextSource::GitDiff(project.clone()).render(cx))
+            .await
+            .unwrap();
+        assert_eq!(
+            output.text,
+            concat!(
+                "```diff\n",
+                "--- a/dir/two.rs\n",
+                "+++ b/dir/two.rs\n",
+                "@@ -1,1 +1,1 @@\n",
+                "-const TWO: usize = 2;\n",
+                "+const TWO: usize = one::ONE + one::ONE;\n",
+                "```\n",
+            )
+        );
+    }
+
+    #[gpui::test]
+    async fn test_render_diagnostics(cx: &mut TestAppContext) {
+        let project = test_project(cx).await;
+        project
+            .update(cx, |project, cx| {
+                let diagnostic =
+                    |range: Range<PointUtf16>, severity, message: &str| DiagnosticEntry {
+                        range: Unclipped(range.start)..Unclipped(range.end),
+                        diagnostic: Diagnostic {
+                            message: message.into(),
+                            severity,
+                            is_primary: true,
+                            ..Default::default()
+                        },
+                    };
+                project.update_diagnostic_entries(
+                    LanguageServerId(0),
+                    PathBuf::from("/dir/one.rs"),
+                    None,
+                    vec![
+                        diagnostic(
+                            PointUtf16::new(0, 6)..PointUtf16::new(0, 9),
+                            DiagnosticSeverity::WARNING,
+                            "constant is never used",
+                        ),
+                        diagnostic(
+                            PointUtf16::new(0, 13)..PointUtf16::new(0, 18),
+                            DiagnosticSeverity::HINT,
+                            "consider using u32",
+                        ),
+                        diagnostic(
+                            PointUtf16::new(0, 19)..PointUtf16::new(0, 22),
+                            DiagnosticSeverity::ERROR,
+                            "mismatched types",
+                        ),
+                    ],
+                    cx,
+                )
+            })
+            .unwrap();
+
+        // Only errors and warnings are listed.
+        let output = cx
+            .update(|cx| ContextSource::Diagnostics(project.clone()).render(cx))
+            .await
+            .unwrap();
+        assert_eq!(
+            output.text,
+            concat!(
+                "```diagnostics\n",
+                "dir/one.rs:1:7: warning: constant is never used\n",
+                "dir/one.rs:1:20: error: mismatched types\n",
+                "```\n",
+            )
+        );
+        assert_eq!(output.buffers.len(), 1);
+    }
+
+    #[gpui::test]
+    async fn test_render_symbols_and_selection(cx: &mut TestAppContext) {
+        let project = test_project(cx).await;
+        let project_path = cx
+            .update(|cx| project_path_for(project.read(cx), "point.rs", cx))
+            .unwrap();
+        let buffer = project
+            .update(cx, |project, cx| project.open_buffer(project_path, cx))
+            .await
+            .unwrap();
+        buffer.update(cx, |buffer, cx| {
+            buffer.set_language(Some(Arc::new(rust_lang())), cx)
+        });
+        cx.executor().run_until_parked();
+
+        let output = cx
+            .update(|cx| ContextSource::Symbols(buffer.clone()).render(cx))
+            .await
+            .unwrap();
+        assert_eq!(
+            output.text,
+            concat!(
+                "```dir/point.rs symbols\n",
+                "struct Point (line 1)\n",
+                "impl Point (line 3)\n",
+                "  fn new (line 4)\n",
+                "```\n",
+            )
+        );
+
+        let range = buffer.read_with(cx, |buffer, _| {
+            buffer.anchor_before(Point::new(3, 4))..buffer.anchor_after(Point::new(5, 5))
+        });
+        let output = cx
+            .update(|cx| ContextSource::Selection { buffer, range }.render(cx))
+            .await
+            .unwrap();
+        assert_eq!(
+            output.text,
+            "```dir/point.rs:4-6\nfn new() -> Self {\n        Point\n    }\n```\n"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_completions(cx: &mut TestAppContext) {
+        let project = test_project(cx).await;
+        let labels = |text: &str, project: Option<Model<Project>>, cx: &mut TestAppContext| {
+            let buffer = cx.new_model(|cx| {
+                Buffer::new(0, BufferId::new(cx.entity_id().as_u64()).unwrap(), text)
+            });
+            let completions = cx.update(|cx| {
+                let position = buffer.read(cx).anchor_after(text.len());
+                completions(project, &buffer, position, cx)
+            });
+            async move {
+                completions
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|completion| (completion.label.text, completion.new_text))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            labels("/ta", None, cx).await,
+            [("/tab".to_string(), "tab".to_string())]
+        );
+        assert_eq!(
+            labels("/fi", None, cx).await,
+            [("/file <path>".to_string(), "file ".to_string())]
+        );
+        assert_eq!(
+            labels("/file one", Some(project.clone()), cx).await,
+            [("one.rs".to_string(), "one.rs".to_string())]
+        );
+        assert!(labels("/search one", Some(project.clone()), cx)
+            .await
+            .is_empty());
+        assert!(labels("see /ta", None, cx).await.is_empty());
+    }
+
+    async fn test_project(cx: &mut TestAppContext) -> Model<Project> {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            language::init(cx);
+            Project::init_settings(cx);
+        });
+
+        let fs = FakeFs::new(cx.background_executor.clone());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "one.rs": "const ONE: usize = 'a';\n",
+                "point.rs": "struct Point;\n\nimpl Point {\n    fn new() -> Self {\n        Point\n    }\n}\n",
+            }),
+        )
+        .await;
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        cx.executor().run_until_parked();
+        project
+    }
+
+    fn rust_lang() -> Language {
+        Language::new(
+            LanguageConfig {
+                name: "Rust".into(),
+                matcher: LanguageMatcher {
+                    path_suffixes: vec!["rs".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            Some(tree_sitter_rust::language()),
+        )
+        .with_outline_query(
+            r#"
+            (struct_item
+                "struct" @context
+                name: (_) @name) @item
+            (impl_item
+                "impl" @context
+                type: (_) @name) @item
+            (function_item
+                "fn" @context
+                name: (_) @name) @item
+            "#,
+        )
+        .unwrap()
+    }
+}
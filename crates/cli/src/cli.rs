@@ -14,7 +14,16 @@ pub enum CliRequest {
     //
     // Since Zed CLI has to be installed separately, there can be situations when old CLI is
     // querying new Zed editors, support both formats by using `String` here and parsing it on Zed side later.
-    Open { paths: Vec<String>, wait: bool },
+    Open {
+        paths: Vec<String>,
+        wait: bool,
+    },
+    /// Compares two files side by side, the paths being absolute.
+    Diff {
+        old_path: String,
+        new_path: String,
+        wait: bool,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -2,7 +2,7 @@
 
 use anyhow::{anyhow, Context, Result};
 use clap::Parser;
-use cli::{CliRequest, CliResponse};
+use cli::{ipc, CliRequest, CliResponse};
 use serde::Deserialize;
 use std::{
     ffi::OsStr,
@@ -24,6 +24,14 @@ struct Args {
     /// Non-existing paths and directories will ignore `:line:row` suffix.
     #[clap(value_parser = parse_path_with_position)]
     paths_with_position: Vec<PathLikeWithPosition<PathBuf>>,
+    /// Compare two files instead of opening paths.
+    #[clap(
+        long,
+        number_of_values = 2,
+        value_names = &["OLD_PATH", "NEW_PATH"],
+        conflicts_with = "paths-with-position"
+    )]
+    diff: Vec<PathBuf>,
     /// Print Zed's version and the app path.
     #[clap(short, long)]
     version: bool,
@@ -56,6 +64,22 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    if let [old_path, new_path] = args.diff.as_slice() {
+        let canonicalize = |path: &PathBuf| {
+            fs::canonicalize(path)
+                .map(|path| path.display().to_string())
+                .with_context(|| format!("path {path:?} canonicalization"))
+        };
+        let request = CliRequest::Diff {
+            old_path: canonicalize(old_path)?,
+            new_path: canonicalize(new_path)?,
+            wait: args.wait,
+        };
+        let (tx, rx) = bundle.launch()?;
+        tx.send(request)?;
+        return handle_responses(rx);
+    }
+
     for path in args
         .paths_with_position
         .iter()
@@ -83,6 +107,10 @@ fn main() -> Result<()> {
         wait: args.wait,
     })?;
 
+    handle_responses(rx)
+}
+
+fn handle_responses(rx: ipc::IpcReceiver<CliResponse>) -> Result<()> {
     while let Ok(response) = rx.recv() {
         match response {
             CliResponse::Ping => {}
@@ -128,7 +156,7 @@ fn locate_bundle() -> Result<PathBuf> {
 mod linux {
     use std::path::Path;
 
-    use cli::{CliRequest, CliResponse};
+    use cli::{ipc, CliRequest, CliResponse};
     use ipc_channel::ipc::{IpcReceiver, IpcSender};
 
     use crate::{Bundle, InfoPlist};
@@ -519,7 +519,9 @@ impl Client {
             Status::Connected { .. } => {
                 state._reconnect_task = None;
             }
-            Status::ConnectionLost => {
+            // Connections that weren't authenticated can't be re-established by
+            // signing in again.
+            Status::ConnectionLost if state.credentials.is_some() => {
                 let this = self.clone();
                 let reconnect_interval = state.reconnect_interval;
                 state._reconnect_task = Some(cx.spawn(move |cx| async move {
@@ -844,6 +846,21 @@ impl Client {
         }
     }
 
+    /// Connects over a connection that was established without authenticating, such as
+    /// to a headless project server started over SSH, which says hello like the server.
+    pub async fn connect_over(
+        self: &Arc<Self>,
+        connection: Connection,
+        cx: &AsyncAppContext,
+    ) -> Result<()> {
+        self.set_status(Status::Connecting, cx);
+        let result = self.set_connection(connection, cx).await;
+        if result.is_err() {
+            self.set_status(Status::ConnectionError, cx);
+        }
+        result
+    }
+
     async fn set_connection(
         self: &Arc<Self>,
         conn: Connection,
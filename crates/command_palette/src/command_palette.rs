@@ -35,20 +35,25 @@ pub struct CommandPalette {
 
 impl CommandPalette {
     fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
-        workspace.register_action(|workspace, _: &Toggle, cx| {
-            let Some(previous_focus_handle) = cx.focused() else {
-                return;
-            };
-            let telemetry = workspace.client().telemetry().clone();
-            workspace.toggle_modal(cx, move |cx| {
-                CommandPalette::new(previous_focus_handle, telemetry, cx)
-            });
+        workspace.register_action(|workspace, _: &Toggle, cx| Self::toggle(workspace, "", cx));
+    }
+
+    /// Toggles the command palette, seeding its query with the given text.
+    pub fn toggle(workspace: &mut Workspace, query: &str, cx: &mut ViewContext<Workspace>) {
+        let Some(previous_focus_handle) = cx.focused() else {
+            return;
+        };
+        let telemetry = workspace.client().telemetry().clone();
+        let query = query.to_string();
+        workspace.toggle_modal(cx, move |cx| {
+            CommandPalette::new(previous_focus_handle, telemetry, query, cx)
         });
     }
 
     fn new(
         previous_focus_handle: FocusHandle,
         telemetry: Arc<Telemetry>,
+        query: String,
         cx: &mut ViewContext<Self>,
     ) -> Self {
         let filter = cx.try_global::<CommandPaletteFilter>();
@@ -80,7 +85,13 @@ impl CommandPalette {
             previous_focus_handle,
         );
 
-        let picker = cx.new_view(|cx| Picker::new(delegate, cx));
+        let picker = cx.new_view(|cx| {
+            let picker = Picker::new(delegate, cx);
+            if !query.is_empty() {
+                picker.set_query(query, cx);
+            }
+            picker
+        });
         Self { picker }
     }
 }
@@ -0,0 +1,867 @@
+//! A client for the [Debug Adapter Protocol](https://microsoft.github.io/debug-adapter-protocol/),
+//! talking to debug adapters over their stdio or over TCP.
+
+mod types;
+
+pub use types::*;
+
+use anyhow::{anyhow, Context, Result};
+use collections::HashMap;
+use futures::{channel::oneshot, io::BufWriter, AsyncRead, AsyncWrite};
+use gpui::{AsyncAppContext, Task};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use smol::{
+    channel,
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    process::{self, Child},
+};
+use std::{
+    ffi::OsString,
+    fmt,
+    future::Future,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::Stdio,
+    str,
+    sync::{
+        atomic::{AtomicU64, Ordering::SeqCst},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use util::{ResultExt, TryFutureExt};
+
+const HEADER_DELIMITER: &[u8; 4] = b"\r\n\r\n";
+const CONTENT_LEN_HEADER: &str = "Content-Length: ";
+/// How long to keep trying to connect to a debug adapter that was just started, while
+/// it opens its port.
+const TCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const TCP_CONNECT_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+type EventHandler = Box<dyn Send + FnMut(Value, AsyncAppContext)>;
+type RequestHandler = Box<dyn Send + FnMut(u64, Value, AsyncAppContext)>;
+type ResponseHandler = Box<dyn Send + FnOnce(Result<Value>)>;
+
+/// Represents a launchable debug adapter.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DebugAdapterBinary {
+    pub path: PathBuf,
+    pub arguments: Vec<OsString>,
+}
+
+/// A running debug adapter.
+pub struct DebugAdapterClient {
+    next_seq: Arc<AtomicU64>,
+    outbound_tx: channel::Sender<String>,
+    capabilities: Capabilities,
+    event_handlers: Arc<Mutex<HashMap<&'static str, EventHandler>>>,
+    request_handlers: Arc<Mutex<HashMap<&'static str, RequestHandler>>>,
+    response_handlers: Arc<Mutex<Option<HashMap<u64, ResponseHandler>>>>,
+    #[allow(clippy::type_complexity)]
+    io_tasks: Mutex<Option<(Task<Option<()>>, Task<Option<()>>)>>,
+    _adapter: Option<Mutex<Child>>,
+}
+
+/// A protocol message, sent in either direction.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Message {
+    Request(RequestMessage),
+    Response(ResponseMessage),
+    Event(EventMessage),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RequestMessage {
+    seq: u64,
+    command: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    arguments: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResponseMessage {
+    seq: u64,
+    request_seq: u64,
+    success: bool,
+    command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    body: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EventMessage {
+    seq: u64,
+    event: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    body: Value,
+}
+
+impl ResponseMessage {
+    /// The error described by a failed response, preferring the detailed message in
+    /// its body over its short `message`.
+    fn error(&self) -> anyhow::Error {
+        let details = self
+            .body
+            .get("error")
+            .and_then(|error| error.get("format"))
+            .and_then(Value::as_str);
+        match (details, &self.message) {
+            (Some(details), _) => anyhow!("{details}"),
+            (None, Some(message)) => anyhow!("{message}"),
+            (None, None) => anyhow!("{} request failed", self.command),
+        }
+    }
+}
+
+/// Handle to an event or reverse request handler, which unregisters it when dropped.
+#[must_use = "Dropping the subscription unregisters the handler; call `detach` to keep it"]
+pub enum Subscription {
+    Event {
+        event: &'static str,
+        event_handlers: Option<Arc<Mutex<HashMap<&'static str, EventHandler>>>>,
+    },
+    Request {
+        command: &'static str,
+        request_handlers: Option<Arc<Mutex<HashMap<&'static str, RequestHandler>>>>,
+    },
+}
+
+impl DebugAdapterClient {
+    /// Starts a debug adapter and talks to it over its stdio.
+    pub fn stdio(
+        binary: DebugAdapterBinary,
+        working_dir: &Path,
+        cx: AsyncAppContext,
+    ) -> Result<Self> {
+        let mut adapter = process::Command::new(&binary.path)
+            .current_dir(working_dir)
+            .args(binary.arguments)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("failed to start debug adapter {:?}", binary.path))?;
+
+        let stdin = adapter.stdin.take().unwrap();
+        let stdout = adapter.stdout.take().unwrap();
+        let stderr = adapter.stderr.take().unwrap();
+        Ok(Self::new_internal(
+            stdin,
+            stdout,
+            Some(stderr),
+            Some(adapter),
+            cx,
+        ))
+    }
+
+    /// Connects to a debug adapter listening on the given address, starting it first
+    /// when a binary is given.
+    pub async fn tcp(
+        binary: Option<DebugAdapterBinary>,
+        address: SocketAddr,
+        working_dir: &Path,
+        cx: AsyncAppContext,
+    ) -> Result<Self> {
+        let mut adapter = binary
+            .map(|binary| {
+                process::Command::new(&binary.path)
+                    .current_dir(working_dir)
+                    .args(binary.arguments)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .with_context(|| format!("failed to start debug adapter {:?}", binary.path))
+            })
+            .transpose()?;
+
+        let started_at = Instant::now();
+        let stream = loop {
+            match TcpStream::connect(address).await {
+                Ok(stream) => break stream,
+                Err(_) if started_at.elapsed() < TCP_CONNECT_TIMEOUT => {
+                    cx.background_executor()
+                        .timer(TCP_CONNECT_RETRY_INTERVAL)
+                        .await;
+                }
+                Err(error) => {
+                    return Err(error).with_context(|| {
+                        format!("failed to connect to debug adapter at {address}")
+                    })
+                }
+            }
+        };
+
+        let stderr = adapter.as_mut().and_then(|adapter| adapter.stderr.take());
+        Ok(Self::new_internal(
+            stream.clone(),
+            stream,
+            stderr,
+            adapter,
+            cx,
+        ))
+    }
+
+    fn new_internal<Writer, Reader, Stderr>(
+        writer: Writer,
+        reader: Reader,
+        stderr: Option<Stderr>,
+        adapter: Option<Child>,
+        cx: AsyncAppContext,
+    ) -> Self
+    where
+        Writer: AsyncWrite + Unpin + Send + 'static,
+        Reader: AsyncRead + Unpin + Send + 'static,
+        Stderr: AsyncRead + Unpin + Send + 'static,
+    {
+        let (outbound_tx, outbound_rx) = channel::unbounded::<String>();
+        let next_seq = Arc::new(AtomicU64::new(1));
+        let event_handlers = Arc::new(Mutex::new(HashMap::<_, EventHandler>::default()));
+        let request_handlers = Arc::new(Mutex::new(HashMap::<_, RequestHandler>::default()));
+        let response_handlers =
+            Arc::new(Mutex::new(Some(HashMap::<_, ResponseHandler>::default())));
+
+        let reader_task = cx.spawn({
+            let event_handlers = event_handlers.clone();
+            let request_handlers = request_handlers.clone();
+            let response_handlers = response_handlers.clone();
+            let outbound_tx = outbound_tx.clone();
+            let next_seq = next_seq.clone();
+            move |cx| {
+                Self::handle_input(
+                    reader,
+                    event_handlers,
+                    request_handlers,
+                    response_handlers,
+                    outbound_tx,
+                    next_seq,
+                    cx,
+                )
+                .log_err()
+            }
+        });
+        let stderr_task = stderr
+            .map(|stderr| cx.spawn(|_| Self::handle_stderr(stderr).log_err()))
+            .unwrap_or_else(|| Task::Ready(Some(None)));
+        let input_task = cx.spawn(|_| async move {
+            let (reader, stderr) = futures::join!(reader_task, stderr_task);
+            reader.or(stderr)
+        });
+        let output_task = cx
+            .background_executor()
+            .spawn(Self::handle_output(writer, outbound_rx).log_err());
+
+        Self {
+            next_seq,
+            outbound_tx,
+            capabilities: Default::default(),
+            event_handlers,
+            request_handlers,
+            response_handlers,
+            io_tasks: Mutex::new(Some((input_task, output_task))),
+            _adapter: adapter.map(Mutex::new),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_input<Reader>(
+        reader: Reader,
+        event_handlers: Arc<Mutex<HashMap<&'static str, EventHandler>>>,
+        request_handlers: Arc<Mutex<HashMap<&'static str, RequestHandler>>>,
+        response_handlers: Arc<Mutex<Option<HashMap<u64, ResponseHandler>>>>,
+        outbound_tx: channel::Sender<String>,
+        next_seq: Arc<AtomicU64>,
+        cx: AsyncAppContext,
+    ) -> Result<()>
+    where
+        Reader: AsyncRead + Unpin + Send + 'static,
+    {
+        let mut reader = BufReader::new(reader);
+        let _clear_response_handlers = util::defer({
+            let response_handlers = response_handlers.clone();
+            move || {
+                response_handlers.lock().take();
+            }
+        });
+        let mut buffer = Vec::new();
+        loop {
+            read_message(&mut reader, &mut buffer).await?;
+            match serde_json::from_slice::<Message>(&buffer) {
+                Ok(Message::Event(event)) => {
+                    if let Some(handler) = event_handlers.lock().get_mut(event.event.as_str()) {
+                        handler(event.body, cx.clone());
+                    } else {
+                        log::debug!("unhandled debug adapter event {}", event.event);
+                    }
+                }
+                Ok(Message::Request(request)) => {
+                    if let Some(handler) = request_handlers.lock().get_mut(request.command.as_str())
+                    {
+                        handler(request.seq, request.arguments, cx.clone());
+                    } else {
+                        send_response(
+                            &outbound_tx,
+                            &next_seq,
+                            request.seq,
+                            &request.command,
+                            Err(anyhow!("unsupported request {}", request.command)),
+                        );
+                    }
+                }
+                Ok(Message::Response(response)) => {
+                    let handler = response_handlers
+                        .lock()
+                        .as_mut()
+                        .and_then(|handlers| handlers.remove(&response.request_seq));
+                    if let Some(handler) = handler {
+                        if response.success {
+                            handler(Ok(response.body));
+                        } else {
+                            handler(Err(response.error()));
+                        }
+                    }
+                }
+                Err(error) => {
+                    log::warn!(
+                        "failed to deserialize debug adapter message: {error:?}, message: {:?}",
+                        String::from_utf8_lossy(&buffer)
+                    );
+                }
+            }
+
+            // Don't starve the main thread when receiving lots of messages at once.
+            smol::future::yield_now().await;
+        }
+    }
+
+    async fn handle_stderr<Stderr>(stderr: Stderr) -> Result<()>
+    where
+        Stderr: AsyncRead + Unpin + Send + 'static,
+    {
+        let mut stderr = BufReader::new(stderr);
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            if stderr.read_until(b'\n', &mut buffer).await? == 0 {
+                return Ok(());
+            }
+            log::debug!(
+                "debug adapter stderr: {}",
+                String::from_utf8_lossy(&buffer).trim_end()
+            );
+        }
+    }
+
+    async fn handle_output<Writer>(
+        writer: Writer,
+        outbound_rx: channel::Receiver<String>,
+    ) -> Result<()>
+    where
+        Writer: AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut writer = BufWriter::new(writer);
+        while let Ok(message) = outbound_rx.recv().await {
+            log::trace!("outgoing debug adapter message: {message}");
+            write_message(&mut writer, &message).await?;
+        }
+        drop(writer);
+        Ok(())
+    }
+
+    /// Initializes the debug adapter, recording the capabilities it reports.
+    pub async fn initialize(mut self, adapter_id: &str) -> Result<Arc<Self>> {
+        let capabilities = self
+            .request::<requests::Initialize>(InitializeRequestArguments {
+                client_id: Some("zed".to_string()),
+                client_name: Some("Zed".to_string()),
+                adapter_id: adapter_id.to_string(),
+                lines_start_at1: true,
+                columns_start_at1: true,
+                path_format: Some("path".to_string()),
+                supports_variable_type: true,
+            })
+            .await?;
+        self.capabilities = capabilities.unwrap_or_default();
+        Ok(Arc::new(self))
+    }
+
+    /// The capabilities reported by the debug adapter when it was initialized.
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// Sends a request to the debug adapter, resolving to its response.
+    pub fn request<R: Request>(
+        &self,
+        arguments: R::Arguments,
+    ) -> impl 'static + Future<Output = Result<R::Response>> {
+        let seq = self.next_seq.fetch_add(1, SeqCst);
+        let (tx, rx) = oneshot::channel();
+        let send = serde_json::to_value(arguments)
+            .and_then(|arguments| {
+                serde_json::to_string(&Message::Request(RequestMessage {
+                    seq,
+                    command: R::COMMAND.to_string(),
+                    arguments,
+                }))
+            })
+            .with_context(|| format!("failed to serialize {} request", R::COMMAND))
+            .and_then(|message| {
+                self.response_handlers
+                    .lock()
+                    .as_mut()
+                    .ok_or_else(|| anyhow!("debug adapter shut down"))?
+                    .insert(
+                        seq,
+                        Box::new(move |response| {
+                            tx.send(response).ok();
+                        }),
+                    );
+                self.outbound_tx
+                    .try_send(message)
+                    .context("failed to write to the debug adapter")
+            });
+
+        async move {
+            send?;
+            let body = rx.await.map_err(|_| anyhow!("debug adapter shut down"))??;
+            serde_json::from_value(body)
+                .with_context(|| format!("failed to deserialize {} response", R::COMMAND))
+        }
+    }
+
+    /// Registers a handler for an event of the debug adapter.
+    ///
+    /// Only one handler can be registered for each event at a time.
+    pub fn on_event<E, F>(&self, mut f: F) -> Subscription
+    where
+        E: Event,
+        F: 'static + Send + FnMut(E::Body, AsyncAppContext),
+    {
+        let prev_handler = self.event_handlers.lock().insert(
+            E::EVENT,
+            Box::new(move |body, cx| match serde_json::from_value(body) {
+                Ok(body) => f(body, cx),
+                Err(error) => {
+                    log::error!("invalid body of debug adapter event {}: {error}", E::EVENT)
+                }
+            }),
+        );
+        assert!(
+            prev_handler.is_none(),
+            "registered multiple handlers for the same debug adapter event"
+        );
+        Subscription::Event {
+            event: E::EVENT,
+            event_handlers: Some(self.event_handlers.clone()),
+        }
+    }
+
+    /// Registers a handler for a reverse request, sent by the debug adapter to the client.
+    pub fn on_request<R, F, Fut>(&self, mut f: F) -> Subscription
+    where
+        R: Request,
+        F: 'static + Send + FnMut(R::Arguments, AsyncAppContext) -> Fut,
+        Fut: 'static + Future<Output = Result<R::Response>>,
+    {
+        let outbound_tx = self.outbound_tx.clone();
+        let next_seq = self.next_seq.clone();
+        let prev_handler = self.request_handlers.lock().insert(
+            R::COMMAND,
+            Box::new(move |request_seq, arguments, cx| {
+                let response =
+                    serde_json::from_value(arguments).map(|arguments| f(arguments, cx.clone()));
+                let outbound_tx = outbound_tx.clone();
+                let next_seq = next_seq.clone();
+                cx.spawn(|_| async move {
+                    let result = match response {
+                        Ok(response) => response.await.and_then(|response| {
+                            serde_json::to_value(response).map_err(Into::into)
+                        }),
+                        Err(error) => Err(error.into()),
+                    };
+                    send_response(&outbound_tx, &next_seq, request_seq, R::COMMAND, result);
+                })
+                .detach();
+            }),
+        );
+        assert!(
+            prev_handler.is_none(),
+            "registered multiple handlers for the same debug adapter request"
+        );
+        Subscription::Request {
+            command: R::COMMAND,
+            request_handlers: Some(self.request_handlers.clone()),
+        }
+    }
+}
+
+impl fmt::Debug for DebugAdapterClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DebugAdapterClient")
+            .field("capabilities", &self.capabilities)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Subscription {
+    /// Detaching a subscription handle prevents it from unregistering the handler on drop.
+    pub fn detach(&mut self) {
+        match self {
+            Subscription::Event { event_handlers, .. } => *event_handlers = None,
+            Subscription::Request {
+                request_handlers, ..
+            } => *request_handlers = None,
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        match self {
+            Subscription::Event {
+                event,
+                event_handlers,
+            } => {
+                if let Some(handlers) = event_handlers {
+                    handlers.lock().remove(event);
+                }
+            }
+            Subscription::Request {
+                command,
+                request_handlers,
+            } => {
+                if let Some(handlers) = request_handlers {
+                    handlers.lock().remove(command);
+                }
+            }
+        }
+    }
+}
+
+/// Reads the content of the next message into the buffer.
+async fn read_message<Reader>(reader: &mut BufReader<Reader>, buffer: &mut Vec<u8>) -> Result<()>
+where
+    Reader: AsyncRead + Unpin,
+{
+    buffer.clear();
+    while !buffer.ends_with(HEADER_DELIMITER) {
+        if reader.read_until(b'\n', buffer).await? == 0 {
+            return Err(anyhow!("debug adapter closed the connection"));
+        }
+    }
+
+    let headers = str::from_utf8(buffer)?;
+    let message_len: usize = headers
+        .split("\r\n")
+        .find_map(|header| header.strip_prefix(CONTENT_LEN_HEADER))
+        .ok_or_else(|| anyhow!("missing {CONTENT_LEN_HEADER:?} header in {headers:?}"))?
+        .trim()
+        .parse()?;
+
+    buffer.resize(message_len, 0);
+    reader.read_exact(buffer).await?;
+    log::trace!(
+        "incoming debug adapter message: {}",
+        String::from_utf8_lossy(buffer)
+    );
+    Ok(())
+}
+
+async fn write_message<Writer>(writer: &mut Writer, message: &str) -> Result<()>
+where
+    Writer: AsyncWrite + Unpin,
+{
+    writer
+        .write_all(format!("{CONTENT_LEN_HEADER}{}\r\n\r\n", message.len()).as_bytes())
+        .await?;
+    writer.write_all(message.as_bytes()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+fn send_response(
+    outbound_tx: &channel::Sender<String>,
+    next_seq: &AtomicU64,
+    request_seq: u64,
+    command: &str,
+    result: Result<Value>,
+) {
+    let (success, message, body) = match result {
+        Ok(body) => (true, None, body),
+        Err(error) => (false, Some(error.to_string()), Value::Null),
+    };
+    let response = Message::Response(ResponseMessage {
+        seq: next_seq.fetch_add(1, SeqCst),
+        request_seq,
+        success,
+        command: command.to_string(),
+        message,
+        body,
+    });
+    if let Some(response) = serde_json::to_string(&response).log_err() {
+        outbound_tx.try_send(response).ok();
+    }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+type FakeRequestHandler = Box<dyn Send + FnMut(Value) -> Result<Value>>;
+
+/// Mock debug adapter for use in tests.
+#[cfg(any(test, feature = "test-support"))]
+pub struct FakeDebugAdapter {
+    next_seq: Arc<AtomicU64>,
+    outbound_tx: channel::Sender<String>,
+    request_handlers: Arc<Mutex<HashMap<&'static str, FakeRequestHandler>>>,
+    requests_rx: channel::Receiver<(String, Value)>,
+    _io_tasks: (Task<Option<()>>, Task<Option<()>>),
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl FakeDebugAdapter {
+    /// Creates a client connected to a fake debug adapter, which answers the requests
+    /// it has a handler for and fails the others. It handles `initialize` by default.
+    pub fn new(cx: AsyncAppContext) -> (DebugAdapterClient, Self) {
+        let (client_writer, adapter_reader) = async_pipe::pipe();
+        let (adapter_writer, client_reader) = async_pipe::pipe();
+        let client = DebugAdapterClient::new_internal(
+            client_writer,
+            client_reader,
+            None::<async_pipe::PipeReader>,
+            None,
+            cx.clone(),
+        );
+
+        let next_seq = Arc::new(AtomicU64::new(1));
+        let (outbound_tx, outbound_rx) = channel::unbounded::<String>();
+        let (requests_tx, requests_rx) = channel::unbounded();
+        let request_handlers = Arc::new(Mutex::new(HashMap::<_, FakeRequestHandler>::default()));
+        let input_task = cx.background_executor().spawn(
+            Self::handle_input(
+                adapter_reader,
+                request_handlers.clone(),
+                requests_tx,
+                outbound_tx.clone(),
+                next_seq.clone(),
+            )
+            .log_err(),
+        );
+        let output_task = cx
+            .background_executor()
+            .spawn(DebugAdapterClient::handle_output(adapter_writer, outbound_rx).log_err());
+
+        let fake = Self {
+            next_seq,
+            outbound_tx,
+            request_handlers,
+            requests_rx,
+            _io_tasks: (input_task, output_task),
+        };
+        fake.handle_request::<requests::Initialize, _>(|_| {
+            Ok(Some(Capabilities {
+                supports_configuration_done_request: Some(true),
+                ..Default::default()
+            }))
+        });
+        (client, fake)
+    }
+
+    async fn handle_input(
+        reader: async_pipe::PipeReader,
+        request_handlers: Arc<Mutex<HashMap<&'static str, FakeRequestHandler>>>,
+        requests_tx: channel::Sender<(String, Value)>,
+        outbound_tx: channel::Sender<String>,
+        next_seq: Arc<AtomicU64>,
+    ) -> Result<()> {
+        let mut reader = BufReader::new(reader);
+        let mut buffer = Vec::new();
+        loop {
+            read_message(&mut reader, &mut buffer).await?;
+            let Message::Request(request) = serde_json::from_slice(&buffer)? else {
+                continue;
+            };
+            requests_tx
+                .try_send((request.command.clone(), request.arguments.clone()))
+                .ok();
+            let result = match request_handlers.lock().get_mut(request.command.as_str()) {
+                Some(handler) => handler(request.arguments),
+                None => Err(anyhow!("unhandled request {}", request.command)),
+            };
+            send_response(
+                &outbound_tx,
+                &next_seq,
+                request.seq,
+                &request.command,
+                result,
+            );
+        }
+    }
+
+    /// Registers a handler for a kind of request, replacing the previous one.
+    pub fn handle_request<R, F>(&self, mut handler: F)
+    where
+        R: Request,
+        F: 'static + Send + FnMut(R::Arguments) -> Result<R::Response>,
+    {
+        self.request_handlers.lock().insert(
+            R::COMMAND,
+            Box::new(move |arguments| {
+                let response = handler(serde_json::from_value(arguments)?)?;
+                Ok(serde_json::to_value(response)?)
+            }),
+        );
+    }
+
+    /// Sends an event to the client.
+    pub fn send_event<E: Event>(&self, body: E::Body) {
+        let event = Message::Event(EventMessage {
+            seq: self.next_seq.fetch_add(1, SeqCst),
+            event: E::EVENT.to_string(),
+            body: serde_json::to_value(body).unwrap(),
+        });
+        self.outbound_tx
+            .try_send(serde_json::to_string(&event).unwrap())
+            .ok();
+    }
+
+    /// Waits for the client to send a request of the given kind, skipping the requests
+    /// of other kinds.
+    pub async fn receive_request<R: Request>(&self) -> R::Arguments {
+        loop {
+            let (command, arguments) = self
+                .requests_rx
+                .recv()
+                .await
+                .expect("debug adapter client was dropped");
+            if command == R::COMMAND {
+                return serde_json::from_value(arguments).unwrap();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+
+    #[ctor::ctor]
+    fn init_logger() {
+        if std::env::var("RUST_LOG").is_ok() {
+            env_logger::init();
+        }
+    }
+
+    #[gpui::test]
+    async fn test_fake(cx: &mut TestAppContext) {
+        let (client, fake) = FakeDebugAdapter::new(cx.to_async());
+        let (stopped_tx, stopped_rx) = channel::unbounded();
+        client
+            .on_event::<events::Stopped, _>(move |body, _| {
+                stopped_tx.try_send(body).unwrap();
+            })
+            .detach();
+        let client = client.initialize("fake").await.unwrap();
+        assert_eq!(
+            client.capabilities().supports_configuration_done_request,
+            Some(true)
+        );
+
+        fake.handle_request::<requests::SetBreakpoints, _>(|arguments| {
+            Ok(SetBreakpointsResponse {
+                breakpoints: arguments
+                    .breakpoints
+                    .iter()
+                    .map(|breakpoint| Breakpoint {
+                        id: None,
+                        verified: true,
+                        message: None,
+                        line: Some(breakpoint.line),
+                    })
+                    .collect(),
+            })
+        });
+        let response = client
+            .request::<requests::SetBreakpoints>(SetBreakpointsArguments {
+                source: Source {
+                    name: None,
+                    path: Some("/project/src/main.rs".into()),
+                },
+                breakpoints: vec![SourceBreakpoint {
+                    line: 3,
+                    condition: None,
+                }],
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.breakpoints[0].line, Some(3));
+        let arguments = fake.receive_request::<requests::SetBreakpoints>().await;
+        assert_eq!(
+            arguments.source.path.as_deref(),
+            Some("/project/src/main.rs")
+        );
+
+        let error = client.request::<requests::Threads>(None).await.unwrap_err();
+        assert_eq!(error.to_string(), "unhandled request threads");
+
+        fake.send_event::<events::Stopped>(StoppedEventBody {
+            reason: "breakpoint".into(),
+            description: None,
+            thread_id: Some(1),
+            all_threads_stopped: Some(true),
+        });
+        assert_eq!(stopped_rx.recv().await.unwrap().thread_id, Some(1));
+    }
+
+    #[test]
+    fn test_message_framing() {
+        smol::block_on(test_message_framing_async());
+    }
+
+    async fn test_message_framing_async() {
+        let mut encoded = Vec::new();
+        write_message(
+            &mut encoded,
+            r#"{"seq":1,"type":"event","event":"initialized"}"#,
+        )
+        .await
+        .unwrap();
+        write_message(
+            &mut encoded,
+            r#"{"seq":2,"type":"request","command":"threads"}"#,
+        )
+        .await
+        .unwrap();
+
+        let mut reader = BufReader::new(encoded.as_slice());
+        let mut buffer = Vec::new();
+        read_message(&mut reader, &mut buffer).await.unwrap();
+        let Message::Event(event) = serde_json::from_slice(&buffer).unwrap() else {
+            panic!("expected an event");
+        };
+        assert_eq!(event.event, "initialized");
+        assert_eq!(event.body, Value::Null);
+
+        read_message(&mut reader, &mut buffer).await.unwrap();
+        let Message::Request(request) = serde_json::from_slice(&buffer).unwrap() else {
+            panic!("expected a request");
+        };
+        assert_eq!(request.seq, 2);
+        assert_eq!(request.command, "threads");
+        assert_eq!(
+            serde_json::to_string(&Message::Request(request)).unwrap(),
+            r#"{"type":"request","seq":2,"command":"threads"}"#
+        );
+
+        assert!(read_message(&mut reader, &mut buffer).await.is_err());
+    }
+}
@@ -0,0 +1,352 @@
+//! Messages of the [Debug Adapter Protocol](https://microsoft.github.io/debug-adapter-protocol/specification).
+//!
+//! Only the parts of the protocol used by the debugger are modeled here. Optional fields
+//! which aren't used are omitted, since serde ignores unknown fields.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+/// A request sent from the client to the debug adapter, or from the debug adapter to
+/// the client for reverse requests.
+pub trait Request {
+    type Arguments: 'static + Serialize + DeserializeOwned + Send;
+    type Response: 'static + Serialize + DeserializeOwned + Send;
+    const COMMAND: &'static str;
+}
+
+/// An event sent from the debug adapter to the client.
+pub trait Event {
+    type Body: 'static + Serialize + DeserializeOwned + Send;
+    const EVENT: &'static str;
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitializeRequestArguments {
+    #[serde(rename = "clientID")]
+    pub client_id: Option<String>,
+    pub client_name: Option<String>,
+    #[serde(rename = "adapterID")]
+    pub adapter_id: String,
+    pub lines_start_at1: bool,
+    pub columns_start_at1: bool,
+    pub path_format: Option<String>,
+    pub supports_variable_type: bool,
+}
+
+/// The features supported by a debug adapter.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Capabilities {
+    pub supports_configuration_done_request: Option<bool>,
+    pub supports_conditional_breakpoints: Option<bool>,
+    pub supports_evaluate_for_hovers: Option<bool>,
+    pub supports_terminate_request: Option<bool>,
+}
+
+/// A source file, identified by its path.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Source {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// A breakpoint as set by the client. Lines start at 1.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceBreakpoint {
+    pub line: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+}
+
+/// A breakpoint as reported by the debug adapter.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Breakpoint {
+    #[serde(default)]
+    pub id: Option<u64>,
+    pub verified: bool,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub line: Option<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetBreakpointsArguments {
+    pub source: Source,
+    pub breakpoints: Vec<SourceBreakpoint>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetBreakpointsResponse {
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Thread {
+    pub id: u64,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadsResponse {
+    pub threads: Vec<Thread>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackTraceArguments {
+    pub thread_id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_frame: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub levels: Option<u64>,
+}
+
+/// A frame of a thread's stack. Lines and columns start at 1.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackFrame {
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub source: Option<Source>,
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackTraceResponse {
+    pub stack_frames: Vec<StackFrame>,
+    #[serde(default)]
+    pub total_frames: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopesArguments {
+    pub frame_id: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scope {
+    pub name: String,
+    pub variables_reference: u64,
+    #[serde(default)]
+    pub expensive: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopesResponse {
+    pub scopes: Vec<Scope>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariablesArguments {
+    pub variables_reference: u64,
+}
+
+/// A variable, whose children can be fetched when its `variables_reference` isn't zero.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Variable {
+    pub name: String,
+    pub value: String,
+    #[serde(default, rename = "type")]
+    pub type_: Option<String>,
+    #[serde(default)]
+    pub variables_reference: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariablesResponse {
+    pub variables: Vec<Variable>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EvaluateContext {
+    Watch,
+    Repl,
+    Hover,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateArguments {
+    pub expression: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<EvaluateContext>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateResponse {
+    pub result: String,
+    #[serde(default, rename = "type")]
+    pub type_: Option<String>,
+    #[serde(default)]
+    pub variables_reference: u64,
+}
+
+/// The arguments of the requests resuming or stepping a thread.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadArguments {
+    pub thread_id: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContinueResponse {
+    #[serde(default)]
+    pub all_threads_continued: Option<bool>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisconnectArguments {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminate_debuggee: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoppedEventBody {
+    pub reason: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub thread_id: Option<u64>,
+    #[serde(default)]
+    pub all_threads_stopped: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContinuedEventBody {
+    pub thread_id: u64,
+    #[serde(default)]
+    pub all_threads_continued: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExitedEventBody {
+    pub exit_code: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputEventBody {
+    #[serde(default)]
+    pub category: Option<String>,
+    pub output: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadEventBody {
+    pub reason: String,
+    pub thread_id: u64,
+}
+
+pub mod requests {
+    use super::*;
+
+    macro_rules! request {
+        ($name:ident, $command:literal, $arguments:ty, $response:ty) => {
+            pub enum $name {}
+
+            impl Request for $name {
+                type Arguments = $arguments;
+                type Response = $response;
+                const COMMAND: &'static str = $command;
+            }
+        };
+    }
+
+    request!(
+        Initialize,
+        "initialize",
+        InitializeRequestArguments,
+        Option<Capabilities>
+    );
+    // The arguments of `launch` and `attach` are specific to each debug adapter.
+    request!(Launch, "launch", Value, Option<Value>);
+    request!(Attach, "attach", Value, Option<Value>);
+    request!(
+        ConfigurationDone,
+        "configurationDone",
+        Option<Value>,
+        Option<Value>
+    );
+    request!(
+        SetBreakpoints,
+        "setBreakpoints",
+        SetBreakpointsArguments,
+        SetBreakpointsResponse
+    );
+    request!(Threads, "threads", Option<Value>, ThreadsResponse);
+    request!(
+        StackTrace,
+        "stackTrace",
+        StackTraceArguments,
+        StackTraceResponse
+    );
+    request!(Scopes, "scopes", ScopesArguments, ScopesResponse);
+    request!(
+        Variables,
+        "variables",
+        VariablesArguments,
+        VariablesResponse
+    );
+    request!(Evaluate, "evaluate", EvaluateArguments, EvaluateResponse);
+    request!(Continue, "continue", ThreadArguments, ContinueResponse);
+    request!(Next, "next", ThreadArguments, Option<Value>);
+    request!(StepIn, "stepIn", ThreadArguments, Option<Value>);
+    request!(StepOut, "stepOut", ThreadArguments, Option<Value>);
+    request!(Pause, "pause", ThreadArguments, Option<Value>);
+    request!(Disconnect, "disconnect", DisconnectArguments, Option<Value>);
+}
+
+pub mod events {
+    use super::*;
+
+    macro_rules! event {
+        ($name:ident, $event:literal, $body:ty) => {
+            pub enum $name {}
+
+            impl Event for $name {
+                type Body = $body;
+                const EVENT: &'static str = $event;
+            }
+        };
+    }
+
+    event!(Initialized, "initialized", Option<Value>);
+    event!(Stopped, "stopped", StoppedEventBody);
+    event!(Continued, "continued", ContinuedEventBody);
+    event!(Exited, "exited", ExitedEventBody);
+    event!(Terminated, "terminated", Option<Value>);
+    event!(Output, "output", OutputEventBody);
+    event!(Thread, "thread", ThreadEventBody);
+}
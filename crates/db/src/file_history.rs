@@ -0,0 +1,192 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use sqlez_macros::sql;
+
+use crate::{define_connection, query};
+
+define_connection!(pub static ref FILE_HISTORY: FileHistoryStore<()> =
+    &[sql!(
+        CREATE TABLE IF NOT EXISTS file_snapshots(
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path BLOB NOT NULL,
+            timestamp INTEGER NOT NULL,
+            content TEXT NOT NULL,
+            deleted INTEGER NOT NULL
+        ) STRICT;
+        CREATE INDEX IF NOT EXISTS file_snapshots_path ON file_snapshots(path);
+    )];
+);
+
+/// A version of a file stored in the local history.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileSnapshot {
+    pub id: i64,
+    pub timestamp: SystemTime,
+    /// Whether the snapshot was taken right before the file was deleted.
+    pub deleted: bool,
+}
+
+/// How many snapshots are kept for each file.
+#[derive(Clone, Copy, Debug)]
+pub struct SnapshotRetention {
+    pub max_snapshots: usize,
+    pub max_age: Duration,
+}
+
+impl FileHistoryStore {
+    query! {
+        fn snapshot_rows(path: &Path) -> Result<Vec<(i64, i64, bool)>> {
+            SELECT id, timestamp, deleted FROM file_snapshots
+            WHERE path = ?
+            ORDER BY id DESC
+        }
+    }
+
+    query! {
+        pub fn snapshot_content(id: i64) -> Result<Option<String>> {
+            SELECT content FROM file_snapshots WHERE id = ?
+        }
+    }
+
+    query! {
+        fn latest_snapshot(path: &Path) -> Result<Option<(String, bool)>> {
+            SELECT content, deleted FROM file_snapshots
+            WHERE path = ?
+            ORDER BY id DESC
+            LIMIT 1
+        }
+    }
+
+    // Returns the files whose most recent snapshot was taken when they were deleted.
+    query! {
+        pub fn deleted_files() -> Result<Vec<PathBuf>> {
+            SELECT path FROM file_snapshots AS snapshot
+            WHERE deleted AND id = (
+                SELECT MAX(id) FROM file_snapshots WHERE path = snapshot.path
+            )
+            ORDER BY timestamp DESC
+        }
+    }
+
+    query! {
+        async fn insert_snapshot(path: PathBuf, timestamp: i64, content: String, deleted: bool) -> Result<()> {
+            INSERT INTO file_snapshots
+                (path, timestamp, content, deleted)
+            VALUES
+                (?1, ?2, ?3, ?4)
+        }
+    }
+
+    query! {
+        async fn prune_snapshots(path: PathBuf, max_snapshots: i64, min_timestamp: i64) -> Result<()> {
+            DELETE FROM file_snapshots
+            WHERE path = ?1 AND (
+                timestamp < ?3 OR id NOT IN (
+                    SELECT id FROM file_snapshots
+                    WHERE path = ?1
+                    ORDER BY id DESC
+                    LIMIT ?2
+                )
+            )
+        }
+    }
+
+    /// Returns the snapshots of the file at the given path, most recent first.
+    pub fn snapshots(&self, path: &Path) -> Result<Vec<FileSnapshot>> {
+        Ok(self
+            .snapshot_rows(path)?
+            .into_iter()
+            .map(|(id, timestamp, deleted)| FileSnapshot {
+                id,
+                timestamp: UNIX_EPOCH + Duration::from_millis(timestamp.max(0) as u64),
+                deleted,
+            })
+            .collect())
+    }
+
+    /// Stores a new snapshot of a file, unless its contents didn't change since the last
+    /// one, and drops the snapshots that fall outside the retention limits.
+    pub async fn save_snapshot(
+        &self,
+        path: PathBuf,
+        content: String,
+        deleted: bool,
+        retention: SnapshotRetention,
+    ) -> Result<()> {
+        if let Some((latest_content, latest_deleted)) = self.latest_snapshot(&path)? {
+            if latest_content == content && latest_deleted == deleted {
+                return Ok(());
+            }
+        }
+
+        let now = SystemTime::now();
+        self.insert_snapshot(path.clone(), unix_millis(now), content, deleted)
+            .await?;
+        let min_timestamp = now.checked_sub(retention.max_age).map_or(0, unix_millis);
+        self.prune_snapshots(path, retention.max_snapshots as i64, min_timestamp)
+            .await
+    }
+}
+
+fn unix_millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[gpui::test]
+    async fn test_file_history() {
+        let db = FileHistoryStore(crate::open_test_db("test_file_history").await);
+        let retention = SnapshotRetention {
+            max_snapshots: 2,
+            max_age: Duration::from_secs(60 * 60),
+        };
+        let path = PathBuf::from("/root/a.txt");
+
+        assert!(db.snapshots(&path).unwrap().is_empty());
+
+        db.save_snapshot(path.clone(), "one".into(), false, retention)
+            .await
+            .unwrap();
+        // Saving the same contents again doesn't create a snapshot.
+        db.save_snapshot(path.clone(), "one".into(), false, retention)
+            .await
+            .unwrap();
+        assert_eq!(db.snapshots(&path).unwrap().len(), 1);
+
+        db.save_snapshot(path.clone(), "two".into(), false, retention)
+            .await
+            .unwrap();
+        db.save_snapshot(path.clone(), "three".into(), false, retention)
+            .await
+            .unwrap();
+        let snapshots = db.snapshots(&path).unwrap();
+        assert_eq!(
+            snapshots
+                .iter()
+                .map(|snapshot| db.snapshot_content(snapshot.id).unwrap().unwrap())
+                .collect::<Vec<_>>(),
+            ["three", "two"]
+        );
+        assert_eq!(db.deleted_files().unwrap(), Vec::<PathBuf>::new());
+
+        db.save_snapshot(path.clone(), "three".into(), true, retention)
+            .await
+            .unwrap();
+        assert!(db.snapshots(&path).unwrap()[0].deleted);
+        assert_eq!(db.deleted_files().unwrap(), [path.clone()]);
+
+        // Saving the file again means it's no longer deleted.
+        db.save_snapshot(path.clone(), "four".into(), false, retention)
+            .await
+            .unwrap();
+        assert_eq!(db.deleted_files().unwrap(), Vec::<PathBuf>::new());
+    }
+}
@@ -0,0 +1,657 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context as _, Result};
+use editor::{Bias, Editor};
+use fs::Fs;
+use gpui::{
+    div, px, Action, AppContext, AsyncWindowContext, EventEmitter, FocusHandle, FocusableView,
+    InteractiveElement, IntoElement, Model, ModelContext, ParentElement, Pixels, Render,
+    SharedString, Styled, Subscription, Task, View, ViewContext, VisualContext, WeakView,
+    WindowContext,
+};
+use language::Point;
+use project::Project;
+use settings::{Settings, SettingsStore};
+use ui::{prelude::*, IconButton, IconName, ListHeader, ListItem, Tooltip};
+use util::paths::HOME;
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    Toast, Workspace,
+};
+
+use crate::{
+    debugger_settings::DebuggerDockPosition, modal::ConfigurationsModal, Continue,
+    DebugConfiguration, DebugSession, DebuggerSettings, Pause, SessionEvent, SessionStatus, Start,
+    StepInto, StepOut, StepOver, Stop, ToggleFocus,
+};
+
+const NO_CONFIGURATIONS_TOAST_ID: usize = 0x6d2a9e41;
+
+/// Highlights the line of the selected stack frame.
+enum DebugCurrentLine {}
+
+/// Shows the threads, stack frames, variables and watch expressions of the debug session.
+pub struct DebuggerPanel {
+    workspace: WeakView<Workspace>,
+    fs: Arc<dyn Fs>,
+    focus_handle: FocusHandle,
+    session: Option<Model<DebugSession>>,
+    /// The label of the configuration being started, and the task starting it.
+    starting: Option<(String, Task<()>)>,
+    watch_editor: View<Editor>,
+    /// The editor showing the line of the selected stack frame.
+    highlighted_editor: Option<WeakView<Editor>>,
+    width: Option<Pixels>,
+    height: Option<Pixels>,
+    _session_subscriptions: Vec<Subscription>,
+}
+
+impl DebuggerPanel {
+    pub async fn load(
+        workspace: WeakView<Workspace>,
+        mut cx: AsyncWindowContext,
+    ) -> Result<View<Self>> {
+        workspace.update(&mut cx, |workspace, cx| {
+            cx.new_view(|cx| Self::new(workspace, cx))
+        })
+    }
+
+    fn new(workspace: &Workspace, cx: &mut ViewContext<Self>) -> Self {
+        let watch_editor = cx.new_view(|cx| {
+            let mut editor = Editor::single_line(cx);
+            editor.set_placeholder_text("Add a watch expression…", cx);
+            editor
+        });
+        let mut old_dock_position = DebuggerSettings::get_global(cx).dock;
+        cx.observe_global::<SettingsStore>(move |_, cx| {
+            let new_dock_position = DebuggerSettings::get_global(cx).dock;
+            if new_dock_position != old_dock_position {
+                old_dock_position = new_dock_position;
+                cx.emit(PanelEvent::ChangePosition);
+            }
+        })
+        .detach();
+
+        Self {
+            workspace: workspace.weak_handle(),
+            fs: workspace.app_state().fs.clone(),
+            focus_handle: cx.focus_handle(),
+            session: None,
+            starting: None,
+            watch_editor,
+            highlighted_editor: None,
+            width: None,
+            height: None,
+            _session_subscriptions: Vec::new(),
+        }
+    }
+
+    /// Whether a debug session is running or being started.
+    pub fn is_debugging(&self, cx: &AppContext) -> bool {
+        self.starting.is_some()
+            || self.session.as_ref().map_or(false, |session| {
+                session.read(cx).status() != SessionStatus::Exited
+            })
+    }
+
+    pub fn session(&self) -> Option<&Model<DebugSession>> {
+        self.session.as_ref()
+    }
+
+    /// Starts the only debug configuration of the project, or lets the user pick one
+    /// when there are several.
+    pub fn pick_configuration(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+        let mut configurations = debug_configurations(workspace, cx);
+        if configurations.len() > 1 {
+            let workspace_handle = cx.view().downgrade();
+            workspace.toggle_modal(cx, |cx| {
+                ConfigurationsModal::new(workspace_handle, configurations, cx)
+            });
+        } else if let Some((configuration, cwd)) = configurations.pop() {
+            Self::start_session(workspace, configuration, cwd, cx);
+        } else {
+            workspace.show_toast(
+                Toast::new(
+                    NO_CONFIGURATIONS_TOAST_ID,
+                    "No debug configurations are defined in the `debugger.configurations` setting",
+                ),
+                cx,
+            );
+        }
+    }
+
+    /// Starts a debug session in the panel, replacing the previous one.
+    pub(crate) fn start_session(
+        workspace: &mut Workspace,
+        configuration: DebugConfiguration,
+        cwd: PathBuf,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let Some(panel) = workspace.panel::<Self>(cx) else {
+            return;
+        };
+        let project = workspace.project().clone();
+        panel.update(cx, |panel, cx| panel.start(configuration, cwd, project, cx));
+        workspace.open_panel::<Self>(cx);
+    }
+
+    fn start(
+        &mut self,
+        configuration: DebugConfiguration,
+        cwd: PathBuf,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let Some(session) = self.session.take() {
+            session
+                .update(cx, |session, cx| session.stop(cx))
+                .detach_and_log_err(cx);
+        }
+        self._session_subscriptions.clear();
+        self.clear_current_line(cx);
+
+        let label = configuration.label.clone();
+        let start = DebugSession::start(configuration, cwd, project, cx);
+        let task = cx.spawn(|this, mut cx| async move {
+            let result = start.await;
+            this.update(&mut cx, |this, cx| {
+                this.starting = None;
+                match result {
+                    Ok(session) => this.set_session(session, cx),
+                    Err(error) => {
+                        this.workspace
+                            .update(cx, |workspace, cx| workspace.show_error(&error, cx))
+                            .ok();
+                    }
+                }
+                cx.notify();
+            })
+            .ok();
+        });
+        self.starting = Some((label, task));
+        cx.notify();
+    }
+
+    fn set_session(&mut self, session: Model<DebugSession>, cx: &mut ViewContext<Self>) {
+        self._session_subscriptions = vec![
+            cx.observe(&session, |_, _, cx| cx.notify()),
+            cx.subscribe(&session, |this, _, event, cx| match event {
+                SessionEvent::StackFrameChanged => this.show_current_line(cx),
+                SessionEvent::Exited => this.clear_current_line(cx),
+            }),
+        ];
+        self.session = Some(session);
+        // The program may have stopped before the panel subscribed to the session.
+        self.show_current_line(cx);
+    }
+
+    /// Applies an operation to the debug session of the workspace, if there is one.
+    pub(crate) fn update_session(
+        workspace: &mut Workspace,
+        cx: &mut ViewContext<Workspace>,
+        f: impl FnOnce(&mut DebugSession, &mut ModelContext<DebugSession>) -> Task<Result<()>>,
+    ) {
+        let Some(session) = workspace
+            .panel::<Self>(cx)
+            .and_then(|panel| panel.read(cx).session.clone())
+        else {
+            return;
+        };
+        session.update(cx, f).detach_and_log_err(cx);
+    }
+
+    /// Opens the file of the selected stack frame, highlighting its current line.
+    fn show_current_line(&mut self, cx: &mut ViewContext<Self>) {
+        let location = self.session.as_ref().and_then(|session| {
+            let frame = session.read(cx).selected_frame()?;
+            let path = PathBuf::from(frame.source.as_ref()?.path.as_ref()?);
+            Some((path, frame.line.saturating_sub(1)))
+        });
+        let Some((path, row)) = location else {
+            self.clear_current_line(cx);
+            return;
+        };
+        let Ok(open) = self
+            .workspace
+            .update(cx, |workspace, cx| workspace.open_abs_path(path, true, cx))
+        else {
+            return;
+        };
+
+        cx.spawn(|this, mut cx| async move {
+            let editor = open
+                .await?
+                .downcast::<Editor>()
+                .context("the stack frame's file isn't shown in an editor")?;
+            this.update(&mut cx, |this, cx| {
+                this.clear_current_line(cx);
+                editor.update(cx, |editor, cx| {
+                    let snapshot = editor.buffer().read(cx).snapshot(cx);
+                    let start = snapshot.clip_point(Point::new(row, 0), Bias::Left);
+                    let end = snapshot.clip_point(Point::new(row + 1, 0), Bias::Left);
+                    let range = snapshot.anchor_before(start)..snapshot.anchor_before(end);
+                    editor.highlight_background::<DebugCurrentLine>(
+                        vec![range.clone()],
+                        |colors| colors.editor_highlighted_line_background,
+                        cx,
+                    );
+                    editor.change_selections(
+                        Some(editor::scroll::Autoscroll::center()),
+                        cx,
+                        |selections| selections.select_ranges([range.start..range.start]),
+                    );
+                });
+                this.highlighted_editor = Some(editor.downgrade());
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn clear_current_line(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(editor) = self
+            .highlighted_editor
+            .take()
+            .and_then(|editor| editor.upgrade())
+        {
+            editor.update(cx, |editor, cx| {
+                editor.clear_background_highlights::<DebugCurrentLine>(cx);
+            });
+        }
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, cx: &mut ViewContext<Self>) {
+        let Some(session) = self.session.clone() else {
+            return;
+        };
+        let expression = self.watch_editor.read(cx).text(cx);
+        let expression = expression.trim();
+        if expression.is_empty() {
+            return;
+        }
+        let expression = expression.to_string();
+        session.update(cx, |session, cx| session.add_watch(expression, cx));
+        self.watch_editor
+            .update(cx, |editor, cx| editor.set_text("", cx));
+    }
+
+    fn render_toolbar(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let status = self
+            .session
+            .as_ref()
+            .map(|session| session.read(cx).status());
+        let title: SharedString = match (&self.starting, &self.session) {
+            (Some((label, _)), _) => format!("Starting {label}…").into(),
+            (None, Some(session)) => {
+                let session = session.read(cx);
+                let status = match session.status() {
+                    SessionStatus::Starting => "starting",
+                    SessionStatus::Running => "running",
+                    SessionStatus::Stopped => "paused",
+                    SessionStatus::Exited => "exited",
+                };
+                format!("{} ({status})", session.configuration().label).into()
+            }
+            (None, None) => "No debug session".into(),
+        };
+        let stopped = status == Some(SessionStatus::Stopped);
+        let running = status == Some(SessionStatus::Running);
+        let active = stopped || running || status == Some(SessionStatus::Starting);
+
+        let button = |id: &'static str,
+                      icon: IconName,
+                      label: &'static str,
+                      action: Box<dyn Action>,
+                      enabled: bool| {
+            let tooltip_action = action.boxed_clone();
+            IconButton::new(id, icon)
+                .icon_size(IconSize::Small)
+                .disabled(!enabled)
+                .on_click(move |_, cx| cx.dispatch_action(action.boxed_clone()))
+                .tooltip(move |cx| Tooltip::for_action(label, &*tooltip_action, cx))
+        };
+
+        h_flex()
+            .px_2()
+            .py_1()
+            .gap_1()
+            .justify_between()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(Label::new(title).size(LabelSize::Small))
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(button(
+                        "debugger-start",
+                        IconName::Debug,
+                        "Start Debugging",
+                        Box::new(Start),
+                        !active && self.starting.is_none(),
+                    ))
+                    .child(if running {
+                        button(
+                            "debugger-pause",
+                            IconName::DebugPause,
+                            "Pause",
+                            Box::new(Pause),
+                            true,
+                        )
+                    } else {
+                        button(
+                            "debugger-continue",
+                            IconName::Play,
+                            "Continue",
+                            Box::new(Continue),
+                            stopped,
+                        )
+                    })
+                    .child(button(
+                        "debugger-step-over",
+                        IconName::DebugStepOver,
+                        "Step Over",
+                        Box::new(StepOver),
+                        stopped,
+                    ))
+                    .child(button(
+                        "debugger-step-into",
+                        IconName::DebugStepInto,
+                        "Step Into",
+                        Box::new(StepInto),
+                        stopped,
+                    ))
+                    .child(button(
+                        "debugger-step-out",
+                        IconName::DebugStepOut,
+                        "Step Out",
+                        Box::new(StepOut),
+                        stopped,
+                    ))
+                    .child(button(
+                        "debugger-stop",
+                        IconName::DebugStop,
+                        "Stop",
+                        Box::new(Stop),
+                        active,
+                    )),
+            )
+    }
+
+    fn render_threads_and_frames(
+        &self,
+        session: &Model<DebugSession>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let state = session.read(cx);
+        let threads = state.threads().iter().map(|thread| {
+            let thread_id = thread.id;
+            let session = session.clone();
+            ListItem::new(("debugger-thread", thread_id as usize))
+                .inset(true)
+                .selected(state.selected_thread_id() == Some(thread_id))
+                .child(Label::new(thread.name.clone()).size(LabelSize::Small))
+                .on_click(move |_, cx| {
+                    session.update(cx, |session, cx| session.select_thread(thread_id, cx));
+                })
+        });
+        let frames = state.stack_frames().iter().enumerate().map(|(ix, frame)| {
+            let session = session.clone();
+            let location = frame
+                .source
+                .as_ref()
+                .and_then(|source| {
+                    let name = source.name.clone().or_else(|| {
+                        let path = Path::new(source.path.as_ref()?);
+                        Some(path.file_name()?.to_string_lossy().to_string())
+                    })?;
+                    Some(format!("{name}:{}", frame.line))
+                })
+                .unwrap_or_default();
+            ListItem::new(("debugger-stack-frame", ix))
+                .inset(true)
+                .selected(state.selected_frame_ix() == Some(ix))
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .child(Label::new(frame.name.clone()).size(LabelSize::Small))
+                        .child(
+                            Label::new(location)
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        ),
+                )
+                .on_click(move |_, cx| {
+                    session.update(cx, |session, cx| session.select_frame(ix, cx));
+                })
+        });
+
+        v_flex()
+            .id("debugger-threads-and-frames")
+            .flex_1()
+            .h_full()
+            .overflow_y_scroll()
+            .child(ListHeader::new("Threads"))
+            .children(threads)
+            .child(ListHeader::new("Call Stack"))
+            .children(frames)
+    }
+
+    fn render_variables(
+        &self,
+        session: &Model<DebugSession>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let variables = session
+            .read(cx)
+            .variables()
+            .iter()
+            .enumerate()
+            .map(|(ix, variable)| {
+                let session = session.clone();
+                let toggle = (variable.variables_reference != 0).then_some(variable.expanded);
+                ListItem::new(("debugger-variable", ix))
+                    .inset(true)
+                    .indent_level(variable.depth)
+                    .indent_step_size(px(12.))
+                    .toggle(toggle)
+                    .on_toggle({
+                        let session = session.clone();
+                        move |_, cx| {
+                            session.update(cx, |session, cx| session.toggle_variable(ix, cx));
+                        }
+                    })
+                    .on_click(move |_, cx| {
+                        session.update(cx, |session, cx| session.toggle_variable(ix, cx));
+                    })
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(Label::new(variable.name.clone()).size(LabelSize::Small))
+                            .child(
+                                Label::new(variable.value.clone())
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            ),
+                    )
+            });
+
+        v_flex()
+            .id("debugger-variables")
+            .flex_1()
+            .h_full()
+            .overflow_y_scroll()
+            .child(ListHeader::new("Variables"))
+            .children(variables)
+    }
+
+    fn render_watches(
+        &self,
+        session: &Model<DebugSession>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let watches = session
+            .read(cx)
+            .watches()
+            .iter()
+            .enumerate()
+            .map(|(ix, watch)| {
+                let session = session.clone();
+                let (value, color) = match &watch.value {
+                    Some(Ok(value)) => (value.clone(), Color::Muted),
+                    Some(Err(error)) => (error.clone(), Color::Error),
+                    None => (String::new(), Color::Muted),
+                };
+                ListItem::new(("debugger-watch", ix))
+                    .inset(true)
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(Label::new(watch.expression.clone()).size(LabelSize::Small))
+                            .child(Label::new(value).size(LabelSize::Small).color(color)),
+                    )
+                    .end_hover_slot(
+                        IconButton::new(("debugger-remove-watch", ix), IconName::Close)
+                            .icon_size(IconSize::XSmall)
+                            .on_click(move |_, cx| {
+                                session.update(cx, |session, cx| session.remove_watch(ix, cx));
+                            })
+                            .tooltip(|cx| Tooltip::text("Remove Watch", cx)),
+                    )
+            });
+
+        v_flex()
+            .id("debugger-watches")
+            .flex_1()
+            .h_full()
+            .overflow_y_scroll()
+            .child(ListHeader::new("Watch"))
+            .children(watches)
+            .child(div().px_2().py_1().child(self.watch_editor.clone()))
+    }
+}
+
+/// The debug configurations of the worktree of the active item, or of the first
+/// worktree, along with the directory to start their debug adapters in.
+fn debug_configurations(
+    workspace: &Workspace,
+    cx: &WindowContext,
+) -> Vec<(DebugConfiguration, PathBuf)> {
+    let project = workspace.project().read(cx);
+    let worktree = workspace
+        .active_item(cx)
+        .and_then(|item| item.project_path(cx))
+        .and_then(|project_path| project.worktree_for_id(project_path.worktree_id, cx))
+        .or_else(|| project.visible_worktrees(cx).next());
+    let (settings, cwd) = match worktree {
+        Some(worktree) => {
+            let worktree = worktree.read(cx);
+            (
+                DebuggerSettings::get(Some((worktree.id().to_usize(), Path::new(""))), cx),
+                worktree.abs_path().to_path_buf(),
+            )
+        }
+        None => (DebuggerSettings::get_global(cx), HOME.clone()),
+    };
+    settings
+        .configurations
+        .iter()
+        .map(|configuration| (configuration.clone(), cwd.clone()))
+        .collect()
+}
+
+impl EventEmitter<PanelEvent> for DebuggerPanel {}
+
+impl Render for DebuggerPanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let content = match self.session.clone() {
+            Some(session) => h_flex()
+                .size_full()
+                .items_start()
+                .child(self.render_threads_and_frames(&session, cx))
+                .child(self.render_variables(&session, cx))
+                .child(self.render_watches(&session, cx))
+                .into_any_element(),
+            None => v_flex()
+                .size_full()
+                .items_center()
+                .justify_center()
+                .child(Label::new("Start a debug session to inspect a program").color(Color::Muted))
+                .into_any_element(),
+        };
+
+        v_flex()
+            .key_context("DebuggerPanel")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::confirm))
+            .size_full()
+            .child(self.render_toolbar(cx))
+            .child(content)
+    }
+}
+
+impl FocusableView for DebuggerPanel {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Panel for DebuggerPanel {
+    fn persistent_name() -> &'static str {
+        "DebuggerPanel"
+    }
+
+    fn position(&self, cx: &WindowContext) -> DockPosition {
+        match DebuggerSettings::get_global(cx).dock {
+            DebuggerDockPosition::Left => DockPosition::Left,
+            DebuggerDockPosition::Bottom => DockPosition::Bottom,
+            DebuggerDockPosition::Right => DockPosition::Right,
+        }
+    }
+
+    fn position_is_valid(&self, _: DockPosition) -> bool {
+        true
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        settings::update_settings_file::<DebuggerSettings>(self.fs.clone(), cx, move |settings| {
+            let dock = match position {
+                DockPosition::Left => DebuggerDockPosition::Left,
+                DockPosition::Bottom => DebuggerDockPosition::Bottom,
+                DockPosition::Right => DebuggerDockPosition::Right,
+            };
+            settings.dock = Some(dock);
+        });
+    }
+
+    fn size(&self, cx: &WindowContext) -> Pixels {
+        let settings = DebuggerSettings::get_global(cx);
+        match self.position(cx) {
+            DockPosition::Left | DockPosition::Right => {
+                self.width.unwrap_or(settings.default_width)
+            }
+            DockPosition::Bottom => self.height.unwrap_or(settings.default_height),
+        }
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        match self.position(cx) {
+            DockPosition::Left | DockPosition::Right => self.width = size,
+            DockPosition::Bottom => self.height = size,
+        }
+        cx.notify();
+    }
+
+    fn icon(&self, _: &WindowContext) -> Option<IconName> {
+        Some(IconName::Debug)
+    }
+
+    fn icon_tooltip(&self, _: &WindowContext) -> Option<&'static str> {
+        Some("Debugger Panel")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+}
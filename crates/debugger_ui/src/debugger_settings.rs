@@ -0,0 +1,122 @@
+use gpui::{AppContext, Pixels};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use settings::Settings;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DebuggerDockPosition {
+    Left,
+    Bottom,
+    Right,
+}
+
+/// How to talk to a debug adapter.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DebugTransport {
+    /// Over the stdio of the debug adapter, started from `command`.
+    #[default]
+    Stdio,
+    /// Over a TCP connection to a debug adapter listening on the given port. When a
+    /// `command` is given, the debug adapter is started before connecting to it.
+    Tcp {
+        #[serde(default = "default_tcp_host")]
+        host: String,
+        port: u16,
+    },
+}
+
+fn default_tcp_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+/// Whether the debug adapter starts the program to debug, or attaches to a running one.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DebugRequestKind {
+    #[default]
+    Launch,
+    Attach,
+}
+
+/// A way of starting a debug session, as defined in the `debugger.configurations` setting.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct DebugConfiguration {
+    /// Human readable name of the configuration to display in the UI.
+    pub label: String,
+    /// Executable starting the debug adapter.
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Arguments to the debug adapter executable.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Identifier of the debug adapter, such as `lldb` or `debugpy`.
+    /// Defaults to the name of the command.
+    #[serde(default)]
+    pub adapter_id: Option<String>,
+    #[serde(default)]
+    pub transport: DebugTransport,
+    #[serde(default)]
+    pub request: DebugRequestKind,
+    /// Arguments of the `launch` or `attach` request, which are specific to each
+    /// debug adapter.
+    #[serde(default)]
+    pub arguments: serde_json::Value,
+}
+
+impl DebugConfiguration {
+    pub fn adapter_id(&self) -> String {
+        self.adapter_id
+            .clone()
+            .or_else(|| {
+                let command = self.command.as_ref()?;
+                let name = std::path::Path::new(command).file_stem()?;
+                Some(name.to_string_lossy().to_string())
+            })
+            .unwrap_or_else(|| self.label.clone())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DebuggerSettings {
+    pub dock: DebuggerDockPosition,
+    pub default_width: Pixels,
+    pub default_height: Pixels,
+    pub configurations: Vec<DebugConfiguration>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct DebuggerSettingsContent {
+    /// Where to dock the debugger panel.
+    ///
+    /// Default: bottom
+    pub dock: Option<DebuggerDockPosition>,
+    /// Default width when the debugger panel is docked to the left or right.
+    ///
+    /// Default: 480
+    pub default_width: Option<f32>,
+    /// Default height when the debugger panel is docked to the bottom.
+    ///
+    /// Default: 320
+    pub default_height: Option<f32>,
+    /// The ways of starting a debug session. Configurations defined in a project's
+    /// `.zed/settings.json` replace the ones defined in the user settings.
+    ///
+    /// Default: []
+    pub configurations: Option<Vec<DebugConfiguration>>,
+}
+
+impl Settings for DebuggerSettings {
+    const KEY: Option<&'static str> = Some("debugger");
+
+    type FileContent = DebuggerSettingsContent;
+
+    fn load(
+        default_value: &Self::FileContent,
+        user_values: &[&Self::FileContent],
+        _: &mut AppContext,
+    ) -> anyhow::Result<Self> {
+        Self::load_via_json_merge(default_value, user_values)
+    }
+}
@@ -0,0 +1,78 @@
+mod debugger_panel;
+mod debugger_settings;
+mod modal;
+mod session;
+
+use gpui::{actions, AppContext, ViewContext};
+use settings::Settings;
+use workspace::Workspace;
+
+pub use debugger_panel::DebuggerPanel;
+pub use debugger_settings::{
+    DebugConfiguration, DebugRequestKind, DebugTransport, DebuggerSettings,
+};
+pub use session::{DebugSession, SessionEvent, SessionStatus, VariableEntry, Watch};
+
+actions!(
+    debugger,
+    [
+        Start,
+        Continue,
+        Pause,
+        StepOver,
+        StepInto,
+        StepOut,
+        Stop,
+        ToggleFocus
+    ]
+);
+
+pub fn init(cx: &mut AppContext) {
+    DebuggerSettings::register(cx);
+    cx.observe_new_views(
+        |workspace: &mut Workspace, _: &mut ViewContext<Workspace>| {
+            workspace
+                .register_action(|workspace, _: &ToggleFocus, cx| {
+                    workspace.toggle_panel_focus::<DebuggerPanel>(cx);
+                })
+                .register_action(|workspace, _: &Start, cx| {
+                    DebuggerPanel::pick_configuration(workspace, cx);
+                })
+                .register_action(|workspace, _: &Continue, cx| {
+                    // Continuing without a running session starts one, as in other editors.
+                    let has_session = workspace
+                        .panel::<DebuggerPanel>(cx)
+                        .map_or(false, |panel| panel.read(cx).is_debugging(cx));
+                    if has_session {
+                        DebuggerPanel::update_session(workspace, cx, |session, cx| {
+                            session.continue_thread(cx)
+                        });
+                    } else {
+                        DebuggerPanel::pick_configuration(workspace, cx);
+                    }
+                })
+                .register_action(|workspace, _: &Pause, cx| {
+                    DebuggerPanel::update_session(workspace, cx, |session, cx| session.pause(cx));
+                })
+                .register_action(|workspace, _: &StepOver, cx| {
+                    DebuggerPanel::update_session(workspace, cx, |session, cx| {
+                        session.step_over(cx)
+                    });
+                })
+                .register_action(|workspace, _: &StepInto, cx| {
+                    DebuggerPanel::update_session(workspace, cx, |session, cx| {
+                        session.step_into(cx)
+                    });
+                })
+                .register_action(|workspace, _: &StepOut, cx| {
+                    DebuggerPanel::update_session(workspace, cx, |session, cx| {
+                        session.step_out(cx)
+                    });
+                })
+                .register_action(|workspace, _: &Stop, cx| {
+                    DebuggerPanel::update_session(workspace, cx, |session, cx| session.stop(cx));
+                });
+        },
+    )
+    .detach();
+}
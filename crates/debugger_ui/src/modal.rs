@@ -0,0 +1,168 @@
+use std::{path::PathBuf, sync::Arc};
+
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    rems, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, ParentElement,
+    Render, Styled, Task, View, ViewContext, VisualContext, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+use crate::{DebugConfiguration, DebuggerPanel};
+
+/// Lists the debug configurations to start a session with.
+pub struct ConfigurationsModal {
+    picker: View<Picker<ConfigurationsModalDelegate>>,
+}
+
+impl ConfigurationsModal {
+    pub fn new(
+        workspace: WeakView<Workspace>,
+        configurations: Vec<(DebugConfiguration, PathBuf)>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let delegate = ConfigurationsModalDelegate {
+            modal: cx.view().downgrade(),
+            workspace,
+            configurations,
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        let picker = cx.new_view(|cx| Picker::new(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for ConfigurationsModal {
+    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for ConfigurationsModal {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for ConfigurationsModal {}
+impl ModalView for ConfigurationsModal {}
+
+pub struct ConfigurationsModalDelegate {
+    modal: WeakView<ConfigurationsModal>,
+    workspace: WeakView<Workspace>,
+    /// The configurations, along with the directory to start their debug adapter in.
+    configurations: Vec<(DebugConfiguration, PathBuf)>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl PickerDelegate for ConfigurationsModalDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self) -> Arc<str> {
+        "Select a debug configuration...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self
+            .configurations
+            .iter()
+            .enumerate()
+            .map(|(ix, (configuration, _))| {
+                StringMatchCandidate::new(ix, configuration.label.clone())
+            })
+            .collect::<Vec<_>>();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let (configuration, cwd) = self.configurations[mat.candidate_id].clone();
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    DebuggerPanel::start_session(workspace, configuration, cwd, cx);
+                })
+                .log_err();
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.modal
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let (configuration, _) = &self.configurations[mat.candidate_id];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                ))
+                .end_slot(
+                    Label::new(configuration.adapter_id())
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                ),
+        )
+    }
+}
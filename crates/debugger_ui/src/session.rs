@@ -0,0 +1,847 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Context as _, Result};
+use dap::{
+    events, requests, ContinuedEventBody, DebugAdapterBinary, DebugAdapterClient,
+    DisconnectArguments, EvaluateArguments, EvaluateContext, OutputEventBody, ScopesArguments,
+    SetBreakpointsArguments, Source, SourceBreakpoint, StackFrame, StackTraceArguments,
+    StoppedEventBody, Thread, ThreadArguments, VariablesArguments,
+};
+use futures::{
+    channel::mpsc::{self, UnboundedReceiver},
+    future::join_all,
+    StreamExt,
+};
+use gpui::{AppContext, AsyncAppContext, Context, EventEmitter, Model, ModelContext, Task};
+use project::{breakpoint_store, Project};
+use util::ResultExt;
+
+use crate::debugger_settings::{DebugConfiguration, DebugRequestKind, DebugTransport};
+
+/// The state of the program being debugged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SessionStatus {
+    Starting,
+    Running,
+    Stopped,
+    Exited,
+}
+
+/// A line of the variables tree, which is kept flattened in display order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariableEntry {
+    pub depth: usize,
+    pub name: String,
+    pub value: String,
+    /// The reference to fetch the children of the variable with, or 0 when it has none.
+    pub variables_reference: u64,
+    pub expanded: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Watch {
+    pub expression: String,
+    /// The value of the expression in the selected stack frame, or the reason it
+    /// couldn't be evaluated.
+    pub value: Option<Result<String, String>>,
+}
+
+pub enum SessionEvent {
+    /// The selected stack frame changed, or the program resumed and there is none.
+    StackFrameChanged,
+    Exited,
+}
+
+/// The events of the debug adapter, forwarded to the session.
+enum AdapterEvent {
+    Initialized,
+    Stopped(StoppedEventBody),
+    Continued(ContinuedEventBody),
+    Exited,
+    Output(OutputEventBody),
+}
+
+/// A running debug adapter, along with the state of the program it debugs.
+pub struct DebugSession {
+    configuration: DebugConfiguration,
+    client: Arc<DebugAdapterClient>,
+    project: Model<Project>,
+    status: SessionStatus,
+    configured: bool,
+    threads: Vec<Thread>,
+    selected_thread_id: Option<u64>,
+    stack_frames: Vec<StackFrame>,
+    selected_frame_ix: Option<usize>,
+    variables: Vec<VariableEntry>,
+    watches: Vec<Watch>,
+    output: String,
+    _maintain_events: Task<()>,
+    _subscriptions: Vec<gpui::Subscription>,
+}
+
+impl EventEmitter<SessionEvent> for DebugSession {}
+
+impl DebugSession {
+    /// Starts the debug adapter of the configuration in the given directory, and
+    /// launches or attaches to the program to debug.
+    pub fn start(
+        configuration: DebugConfiguration,
+        cwd: PathBuf,
+        project: Model<Project>,
+        cx: &mut AppContext,
+    ) -> Task<Result<Model<Self>>> {
+        cx.spawn(|cx| async move {
+            let client = connect(&configuration, &cwd, cx.clone()).await?;
+            Self::initialize(configuration, client, project, cx).await
+        })
+    }
+
+    /// Initializes a debug session over a client of a started debug adapter.
+    pub async fn initialize(
+        configuration: DebugConfiguration,
+        client: DebugAdapterClient,
+        project: Model<Project>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Model<Self>> {
+        let (events_tx, events_rx) = mpsc::unbounded();
+        // The adapter may send events as soon as it is initialized, so the handlers
+        // forward them to the session which doesn't exist yet.
+        client
+            .on_event::<events::Initialized, _>({
+                let events_tx = events_tx.clone();
+                move |_, _| {
+                    events_tx.unbounded_send(AdapterEvent::Initialized).ok();
+                }
+            })
+            .detach();
+        client
+            .on_event::<events::Stopped, _>({
+                let events_tx = events_tx.clone();
+                move |body, _| {
+                    events_tx.unbounded_send(AdapterEvent::Stopped(body)).ok();
+                }
+            })
+            .detach();
+        client
+            .on_event::<events::Continued, _>({
+                let events_tx = events_tx.clone();
+                move |body, _| {
+                    events_tx.unbounded_send(AdapterEvent::Continued(body)).ok();
+                }
+            })
+            .detach();
+        client
+            .on_event::<events::Exited, _>({
+                let events_tx = events_tx.clone();
+                move |_, _| {
+                    events_tx.unbounded_send(AdapterEvent::Exited).ok();
+                }
+            })
+            .detach();
+        client
+            .on_event::<events::Terminated, _>({
+                let events_tx = events_tx.clone();
+                move |_, _| {
+                    events_tx.unbounded_send(AdapterEvent::Exited).ok();
+                }
+            })
+            .detach();
+        client
+            .on_event::<events::Output, _>(move |body, _| {
+                events_tx.unbounded_send(AdapterEvent::Output(body)).ok();
+            })
+            .detach();
+
+        let client = client.initialize(&configuration.adapter_id()).await?;
+        let session = cx.new_model(|cx| {
+            Self::new(
+                configuration.clone(),
+                client.clone(),
+                project,
+                events_rx,
+                cx,
+            )
+        })?;
+
+        let arguments = configuration.arguments.clone();
+        match configuration.request {
+            DebugRequestKind::Launch => client.request::<requests::Launch>(arguments).await?,
+            DebugRequestKind::Attach => client.request::<requests::Attach>(arguments).await?,
+        };
+        session.update(&mut cx, |session, cx| {
+            if session.status == SessionStatus::Starting {
+                session.status = SessionStatus::Running;
+                cx.notify();
+            }
+        })?;
+        Ok(session)
+    }
+
+    fn new(
+        configuration: DebugConfiguration,
+        client: Arc<DebugAdapterClient>,
+        project: Model<Project>,
+        mut events_rx: UnboundedReceiver<AdapterEvent>,
+        cx: &mut ModelContext<Self>,
+    ) -> Self {
+        let breakpoint_store = project.read(cx).breakpoint_store().clone();
+        let subscription = cx.subscribe(&breakpoint_store, |this, _, event, cx| {
+            let breakpoint_store::Event::BreakpointsChanged { abs_path } = event;
+            if this.configured && this.status != SessionStatus::Exited {
+                this.send_breakpoints(abs_path, cx).detach_and_log_err(cx);
+            }
+        });
+        let maintain_events = cx.spawn(|this, mut cx| async move {
+            while let Some(event) = events_rx.next().await {
+                if this
+                    .update(&mut cx, |this, cx| this.handle_adapter_event(event, cx))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            configuration,
+            client,
+            project,
+            status: SessionStatus::Starting,
+            configured: false,
+            threads: Vec::new(),
+            selected_thread_id: None,
+            stack_frames: Vec::new(),
+            selected_frame_ix: None,
+            variables: Vec::new(),
+            watches: Vec::new(),
+            output: String::new(),
+            _maintain_events: maintain_events,
+            _subscriptions: vec![subscription],
+        }
+    }
+
+    pub fn configuration(&self) -> &DebugConfiguration {
+        &self.configuration
+    }
+
+    pub fn status(&self) -> SessionStatus {
+        self.status
+    }
+
+    pub fn threads(&self) -> &[Thread] {
+        &self.threads
+    }
+
+    pub fn selected_thread_id(&self) -> Option<u64> {
+        self.selected_thread_id
+    }
+
+    pub fn stack_frames(&self) -> &[StackFrame] {
+        &self.stack_frames
+    }
+
+    pub fn selected_frame_ix(&self) -> Option<usize> {
+        self.selected_frame_ix
+    }
+
+    pub fn selected_frame(&self) -> Option<&StackFrame> {
+        self.stack_frames.get(self.selected_frame_ix?)
+    }
+
+    pub fn variables(&self) -> &[VariableEntry] {
+        &self.variables
+    }
+
+    pub fn watches(&self) -> &[Watch] {
+        &self.watches
+    }
+
+    /// What the program and the debug adapter printed.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    fn handle_adapter_event(&mut self, event: AdapterEvent, cx: &mut ModelContext<Self>) {
+        match event {
+            AdapterEvent::Initialized => self.configure(cx),
+            AdapterEvent::Stopped(body) => {
+                self.status = SessionStatus::Stopped;
+                if body.thread_id.is_some() {
+                    self.selected_thread_id = body.thread_id;
+                }
+                self.refresh_threads(cx);
+            }
+            AdapterEvent::Continued(body) => {
+                if body.all_threads_continued.unwrap_or(true)
+                    || self.selected_thread_id == Some(body.thread_id)
+                {
+                    self.resumed(cx);
+                }
+            }
+            AdapterEvent::Exited => {
+                if self.status != SessionStatus::Exited {
+                    self.status = SessionStatus::Exited;
+                    self.clear_stack(cx);
+                    cx.emit(SessionEvent::Exited);
+                }
+            }
+            AdapterEvent::Output(body) => {
+                if body.category.as_deref() != Some("telemetry") {
+                    self.output.push_str(&body.output);
+                }
+            }
+        }
+        cx.notify();
+    }
+
+    /// Sends the breakpoints of the project to the debug adapter, then tells it that
+    /// the configuration is done.
+    fn configure(&mut self, cx: &mut ModelContext<Self>) {
+        self.configured = true;
+        let breakpoint_store = self.project.read(cx).breakpoint_store().read(cx);
+        let requests = breakpoint_store
+            .paths()
+            .map(|path| self.send_breakpoints(path, cx))
+            .collect::<Vec<_>>();
+        let client = self.client.clone();
+        cx.background_executor()
+            .spawn(async move {
+                for result in join_all(requests).await {
+                    result.log_err();
+                }
+                if client
+                    .capabilities()
+                    .supports_configuration_done_request
+                    .unwrap_or(false)
+                {
+                    client
+                        .request::<requests::ConfigurationDone>(None)
+                        .await
+                        .log_err();
+                }
+            })
+            .detach();
+    }
+
+    fn send_breakpoints(&self, abs_path: &Path, cx: &AppContext) -> Task<Result<()>> {
+        let breakpoints = self
+            .project
+            .read(cx)
+            .breakpoint_store()
+            .read(cx)
+            .breakpoints_for_path(abs_path, cx)
+            .map(|row| SourceBreakpoint {
+                line: row + 1,
+                condition: None,
+            })
+            .collect();
+        let request = self
+            .client
+            .request::<requests::SetBreakpoints>(SetBreakpointsArguments {
+                source: Source {
+                    name: abs_path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string()),
+                    path: Some(abs_path.to_string_lossy().to_string()),
+                },
+                breakpoints,
+            });
+        cx.background_executor().spawn(async move {
+            request.await?;
+            Ok(())
+        })
+    }
+
+    fn refresh_threads(&mut self, cx: &mut ModelContext<Self>) {
+        let threads = self.client.request::<requests::Threads>(None);
+        cx.spawn(|this, mut cx| async move {
+            let threads = threads.await?.threads;
+            this.update(&mut cx, |this, cx| {
+                if this
+                    .selected_thread_id
+                    .map_or(true, |id| !threads.iter().any(|thread| thread.id == id))
+                {
+                    this.selected_thread_id = threads.first().map(|thread| thread.id);
+                }
+                this.threads = threads;
+                if let Some(thread_id) = this.selected_thread_id {
+                    this.select_thread(thread_id, cx);
+                }
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Shows the stack of the given thread, selecting its topmost frame.
+    pub fn select_thread(&mut self, thread_id: u64, cx: &mut ModelContext<Self>) {
+        self.selected_thread_id = Some(thread_id);
+        if self.status != SessionStatus::Stopped {
+            return;
+        }
+
+        let stack_trace = self
+            .client
+            .request::<requests::StackTrace>(StackTraceArguments {
+                thread_id,
+                start_frame: None,
+                levels: None,
+            });
+        cx.spawn(|this, mut cx| async move {
+            let stack_frames = stack_trace.await?.stack_frames;
+            this.update(&mut cx, |this, cx| {
+                if this.selected_thread_id != Some(thread_id) {
+                    return;
+                }
+                let has_frames = !stack_frames.is_empty();
+                this.stack_frames = stack_frames;
+                this.selected_frame_ix = None;
+                if has_frames {
+                    this.select_frame(0, cx);
+                } else {
+                    this.variables.clear();
+                    cx.emit(SessionEvent::StackFrameChanged);
+                }
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
+        cx.notify();
+    }
+
+    /// Shows the variables of the given stack frame and evaluates the watches in it.
+    pub fn select_frame(&mut self, ix: usize, cx: &mut ModelContext<Self>) {
+        let Some(frame) = self.stack_frames.get(ix) else {
+            return;
+        };
+        let frame_id = frame.id;
+        self.selected_frame_ix = Some(ix);
+        self.variables.clear();
+        cx.emit(SessionEvent::StackFrameChanged);
+
+        let scopes = self
+            .client
+            .request::<requests::Scopes>(ScopesArguments { frame_id });
+        cx.spawn(|this, mut cx| async move {
+            let scopes = scopes.await?.scopes;
+            this.update(&mut cx, |this, cx| {
+                if this.selected_frame().map(|frame| frame.id) != Some(frame_id) {
+                    return;
+                }
+                this.variables = scopes
+                    .into_iter()
+                    .map(|scope| VariableEntry {
+                        depth: 0,
+                        name: scope.name,
+                        value: String::new(),
+                        variables_reference: scope.variables_reference,
+                        expanded: false,
+                    })
+                    .collect();
+                // Show the local variables, which are usually in the first scope.
+                if !this.variables.is_empty() {
+                    this.toggle_variable(0, cx);
+                }
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
+
+        self.evaluate_watches(cx);
+        cx.notify();
+    }
+
+    /// Expands or collapses the children of a variable.
+    pub fn toggle_variable(&mut self, ix: usize, cx: &mut ModelContext<Self>) {
+        let Some(entry) = self.variables.get_mut(ix) else {
+            return;
+        };
+        if entry.variables_reference == 0 {
+            return;
+        }
+
+        if entry.expanded {
+            entry.expanded = false;
+            let depth = entry.depth;
+            let end = self.variables[ix + 1..]
+                .iter()
+                .position(|child| child.depth <= depth)
+                .map_or(self.variables.len(), |len| ix + 1 + len);
+            self.variables.drain(ix + 1..end);
+            cx.notify();
+            return;
+        }
+
+        entry.expanded = true;
+        let variables_reference = entry.variables_reference;
+        let depth = entry.depth + 1;
+        let variables = self
+            .client
+            .request::<requests::Variables>(VariablesArguments {
+                variables_reference,
+            });
+        cx.spawn(|this, mut cx| async move {
+            let variables = variables.await?.variables;
+            this.update(&mut cx, |this, cx| {
+                // The tree may have changed while the variables were fetched.
+                let Some(ix) = this.variables.iter().position(|entry| {
+                    entry.variables_reference == variables_reference && entry.depth + 1 == depth
+                }) else {
+                    return;
+                };
+                if !this.variables[ix].expanded {
+                    return;
+                }
+                this.variables.splice(
+                    ix + 1..ix + 1,
+                    variables.into_iter().map(|variable| VariableEntry {
+                        depth,
+                        name: variable.name,
+                        value: variable.value,
+                        variables_reference: variable.variables_reference,
+                        expanded: false,
+                    }),
+                );
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
+        cx.notify();
+    }
+
+    pub fn add_watch(&mut self, expression: String, cx: &mut ModelContext<Self>) {
+        self.watches.push(Watch {
+            expression,
+            value: None,
+        });
+        self.evaluate_watches(cx);
+    }
+
+    pub fn remove_watch(&mut self, ix: usize, cx: &mut ModelContext<Self>) {
+        if ix < self.watches.len() {
+            self.watches.remove(ix);
+            cx.notify();
+        }
+    }
+
+    fn evaluate_watches(&mut self, cx: &mut ModelContext<Self>) {
+        let frame_id = self.selected_frame().map(|frame| frame.id);
+        for watch in &mut self.watches {
+            watch.value = None;
+        }
+        if frame_id.is_none() {
+            cx.notify();
+            return;
+        }
+
+        let evaluations = self
+            .watches
+            .iter()
+            .map(|watch| {
+                let expression = watch.expression.clone();
+                let evaluation = self
+                    .client
+                    .request::<requests::Evaluate>(EvaluateArguments {
+                        expression: expression.clone(),
+                        frame_id,
+                        context: Some(EvaluateContext::Watch),
+                    });
+                async move { (expression, evaluation.await) }
+            })
+            .collect::<Vec<_>>();
+        cx.spawn(|this, mut cx| async move {
+            let results = join_all(evaluations).await;
+            this.update(&mut cx, |this, cx| {
+                if this.selected_frame().map(|frame| frame.id) != frame_id {
+                    return;
+                }
+                for (expression, result) in results {
+                    for watch in &mut this.watches {
+                        if watch.expression == expression {
+                            watch.value = Some(match &result {
+                                Ok(response) => Ok(response.result.clone()),
+                                Err(error) => Err(error.to_string()),
+                            });
+                        }
+                    }
+                }
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+        cx.notify();
+    }
+
+    fn current_thread_id(&self) -> Result<u64> {
+        self.selected_thread_id
+            .or_else(|| self.threads.first().map(|thread| thread.id))
+            .ok_or_else(|| anyhow!("no thread to control"))
+    }
+
+    fn resumed(&mut self, cx: &mut ModelContext<Self>) {
+        if self.status == SessionStatus::Stopped {
+            self.status = SessionStatus::Running;
+            self.clear_stack(cx);
+            cx.notify();
+        }
+    }
+
+    fn clear_stack(&mut self, cx: &mut ModelContext<Self>) {
+        self.stack_frames.clear();
+        self.selected_frame_ix = None;
+        self.variables.clear();
+        for watch in &mut self.watches {
+            watch.value = None;
+        }
+        cx.emit(SessionEvent::StackFrameChanged);
+    }
+
+    /// Sends a request resuming the stopped program.
+    fn resume<R>(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>>
+    where
+        R: dap::Request<Arguments = ThreadArguments>,
+    {
+        if self.status != SessionStatus::Stopped {
+            return Task::ready(Ok(()));
+        }
+        let thread_id = match self.current_thread_id() {
+            Ok(thread_id) => thread_id,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let request = self.client.request::<R>(ThreadArguments { thread_id });
+        self.resumed(cx);
+        cx.background_executor().spawn(async move {
+            request.await?;
+            Ok(())
+        })
+    }
+
+    pub fn continue_thread(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.resume::<requests::Continue>(cx)
+    }
+
+    pub fn step_over(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.resume::<requests::Next>(cx)
+    }
+
+    pub fn step_into(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.resume::<requests::StepIn>(cx)
+    }
+
+    pub fn step_out(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.resume::<requests::StepOut>(cx)
+    }
+
+    pub fn pause(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        if self.status != SessionStatus::Running {
+            return Task::ready(Ok(()));
+        }
+        let thread_id = match self.current_thread_id() {
+            Ok(thread_id) => thread_id,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let request = self
+            .client
+            .request::<requests::Pause>(ThreadArguments { thread_id });
+        cx.background_executor().spawn(async move {
+            request.await?;
+            Ok(())
+        })
+    }
+
+    /// Disconnects from the debug adapter, terminating the program when it was launched.
+    pub fn stop(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        if self.status == SessionStatus::Exited {
+            return Task::ready(Ok(()));
+        }
+        let request = self
+            .client
+            .request::<requests::Disconnect>(DisconnectArguments {
+                restart: None,
+                terminate_debuggee: Some(self.configuration.request == DebugRequestKind::Launch),
+            });
+        self.status = SessionStatus::Exited;
+        self.clear_stack(cx);
+        cx.emit(SessionEvent::Exited);
+        cx.notify();
+        cx.background_executor().spawn(async move {
+            request.await?;
+            Ok(())
+        })
+    }
+}
+
+/// Starts the debug adapter of a configuration, or connects to it.
+async fn connect(
+    configuration: &DebugConfiguration,
+    cwd: &Path,
+    cx: AsyncAppContext,
+) -> Result<DebugAdapterClient> {
+    let binary = configuration
+        .command
+        .as_ref()
+        .map(|command| DebugAdapterBinary {
+            path: command.into(),
+            arguments: configuration.args.iter().map(Into::into).collect(),
+        });
+    match &configuration.transport {
+        DebugTransport::Stdio => {
+            let binary = binary.with_context(|| {
+                format!(
+                    "debug configuration {:?} has no command to start",
+                    configuration.label
+                )
+            })?;
+            DebugAdapterClient::stdio(binary, cwd, cx)
+        }
+        DebugTransport::Tcp { host, port } => {
+            let address = smol::net::resolve((host.as_str(), *port))
+                .await?
+                .into_iter()
+                .next()
+                .with_context(|| format!("failed to resolve {host}"))?;
+            DebugAdapterClient::tcp(binary, address, cwd, cx).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dap::{
+        Breakpoint, FakeDebugAdapter, Scope, ScopesResponse, SetBreakpointsResponse,
+        StackTraceResponse, ThreadsResponse, Variable, VariablesResponse,
+    };
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+    use serde_json::json;
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    async fn test_debug_session(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            Project::init_settings(cx);
+        });
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/project", json!({ "main.rs": "fn main() {}\n" }))
+            .await;
+        let project = Project::test(fs, ["/project".as_ref()], cx).await;
+        let main_rs: Arc<Path> = Path::new("/project/main.rs").into();
+        project.update(cx, |project, cx| {
+            project
+                .breakpoint_store()
+                .update(cx, |store, cx| store.toggle(main_rs.clone(), 0, cx));
+        });
+
+        let (client, fake) = FakeDebugAdapter::new(cx.to_async());
+        fake.handle_request::<requests::Launch, _>(|_| Ok(None));
+        fake.handle_request::<requests::ConfigurationDone, _>(|_| Ok(None));
+        fake.handle_request::<requests::SetBreakpoints, _>(|arguments| {
+            Ok(SetBreakpointsResponse {
+                breakpoints: arguments
+                    .breakpoints
+                    .iter()
+                    .map(|breakpoint| Breakpoint {
+                        id: None,
+                        verified: true,
+                        message: None,
+                        line: Some(breakpoint.line),
+                    })
+                    .collect(),
+            })
+        });
+        fake.handle_request::<requests::Threads, _>(|_| {
+            Ok(ThreadsResponse {
+                threads: vec![Thread {
+                    id: 1,
+                    name: "main".into(),
+                }],
+            })
+        });
+        fake.handle_request::<requests::StackTrace, _>(|_| {
+            Ok(StackTraceResponse {
+                stack_frames: vec![StackFrame {
+                    id: 10,
+                    name: "main".into(),
+                    source: Some(Source {
+                        name: Some("main.rs".into()),
+                        path: Some("/project/main.rs".into()),
+                    }),
+                    line: 1,
+                    column: 1,
+                }],
+            })
+        });
+        fake.handle_request::<requests::Scopes, _>(|_| {
+            Ok(ScopesResponse {
+                scopes: vec![Scope {
+                    name: "Locals".into(),
+                    variables_reference: 20,
+                    expensive: false,
+                }],
+            })
+        });
+        fake.handle_request::<requests::Variables, _>(|_| {
+            Ok(VariablesResponse {
+                variables: vec![Variable {
+                    name: "count".into(),
+                    value: "3".into(),
+                    type_: None,
+                    variables_reference: 0,
+                }],
+            })
+        });
+
+        let configuration = DebugConfiguration {
+            label: "Debug main".into(),
+            ..Default::default()
+        };
+        let session = DebugSession::initialize(configuration, client, project, cx.to_async())
+            .await
+            .unwrap();
+        assert_eq!(
+            session.read_with(cx, |session, _| session.status()),
+            SessionStatus::Running
+        );
+
+        fake.send_event::<events::Initialized>(None);
+        let arguments = fake.receive_request::<requests::SetBreakpoints>().await;
+        assert_eq!(arguments.source.path.as_deref(), Some("/project/main.rs"));
+        assert_eq!(
+            arguments.breakpoints,
+            [SourceBreakpoint {
+                line: 1,
+                condition: None,
+            }]
+        );
+        fake.receive_request::<requests::ConfigurationDone>().await;
+
+        fake.send_event::<events::Stopped>(StoppedEventBody {
+            reason: "breakpoint".into(),
+            description: None,
+            thread_id: Some(1),
+            all_threads_stopped: Some(true),
+        });
+        fake.receive_request::<requests::Variables>().await;
+        cx.run_until_parked();
+        session.read_with(cx, |session, _| {
+            assert_eq!(session.status(), SessionStatus::Stopped);
+            assert_eq!(session.selected_thread_id(), Some(1));
+            assert_eq!(session.selected_frame().map(|frame| frame.id), Some(10));
+            assert_eq!(
+                session
+                    .variables()
+                    .iter()
+                    .map(|entry| (entry.depth, entry.name.as_str(), entry.value.as_str()))
+                    .collect::<Vec<_>>(),
+                [(0, "Locals", ""), (1, "count", "3")]
+            );
+        });
+    }
+}
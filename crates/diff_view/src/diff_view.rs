@@ -0,0 +1,937 @@
+mod text_diff;
+
+use std::{mem, ops::Range, path::PathBuf, sync::Arc};
+
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashSet;
+use editor::{
+    display_map::{BlockContext, BlockDisposition, BlockId, BlockProperties, BlockStyle},
+    Editor, EditorEvent, RenderBlock,
+};
+use gpui::{
+    actions, impl_actions, AnyElement, AppContext, EventEmitter, FocusHandle, FocusableView,
+    HighlightStyle, Hsla, Model, PathPromptOptions, Render, SharedString, StyledText, Subscription,
+    Task, View, ViewContext, VisualContext, WindowContext,
+};
+use language::{language_settings::SoftWrap, Buffer};
+use project::{Project, ProjectPath};
+use serde::Deserialize;
+use text::{BufferId, Point};
+use ui::prelude::*;
+use workspace::{
+    item::{Item, ItemEvent},
+    Workspace,
+};
+
+pub use text_diff::{diff_hunks, DiffHunk};
+
+actions!(
+    diff_view,
+    [
+        DiffWithSaved,
+        DiffWithHead,
+        DiffWithPreviousEditor,
+        DiffFiles,
+        ToggleDiffMode
+    ]
+);
+
+/// Compares the active file with its contents at a git revision.
+#[derive(Clone, Default, Deserialize, PartialEq)]
+pub struct DiffWithRevision {
+    pub revision: String,
+}
+
+impl_actions!(diff_view, [DiffWithRevision]);
+
+/// Deleted hunks longer than this are cut short in unified diffs, blocks being at most
+/// this many lines high.
+const MAX_DELETED_LINES: usize = u8::MAX as usize;
+
+enum DiffLine {}
+enum DiffWord {}
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(
+        |workspace: &mut Workspace, _: &mut ViewContext<Workspace>| {
+            workspace.register_action(DiffView::diff_with_saved);
+            workspace.register_action(|workspace, _: &DiffWithHead, cx| {
+                DiffView::diff_with_revision(workspace, "HEAD".into(), cx)
+            });
+            workspace.register_action(|workspace, action: &DiffWithRevision, cx| {
+                DiffView::diff_with_revision(workspace, action.revision.clone(), cx)
+            });
+            workspace.register_action(DiffView::diff_with_previous_editor);
+            workspace.register_action(DiffView::diff_files);
+        },
+    )
+    .detach();
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiffMode {
+    /// The old and the new text in editors next to each other, scrolled together.
+    #[default]
+    SideBySide,
+    /// The new text in a single editor, with the deleted lines shown above the
+    /// lines that replaced them.
+    Unified,
+}
+
+/// Where the old text of a diff comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum OldSource {
+    /// A buffer of its own, which can be edited like the new one.
+    Buffer,
+    /// The saved contents of the new buffer's file, which follow the file when the
+    /// buffer is saved or reloaded.
+    Saved,
+    /// The contents of the new buffer's file at a git revision.
+    Revision(String),
+}
+
+/// Compares two texts, highlighting the lines and the words within them that differ.
+pub struct DiffView {
+    old_buffer: Model<Buffer>,
+    new_buffer: Model<Buffer>,
+    old_source: OldSource,
+    old_title: SharedString,
+    new_title: SharedString,
+    old_editor: View<Editor>,
+    new_editor: View<Editor>,
+    mode: DiffMode,
+    hunks: Vec<DiffHunk>,
+    old_blocks: HashSet<BlockId>,
+    new_blocks: HashSet<BlockId>,
+    _diff_task: Task<()>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl DiffView {
+    fn diff_with_saved(
+        workspace: &mut Workspace,
+        _: &DiffWithSaved,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let Some(buffer) = active_buffer(workspace, cx) else {
+            return;
+        };
+        let Some(file) = buffer.read(cx).file().and_then(|file| file.as_local()) else {
+            return;
+        };
+        let abs_path = file.abs_path(cx);
+        let title = format!("{} (saved)", file.file_name(cx).to_string_lossy());
+        let fs = workspace.project().read(cx).fs().clone();
+        cx.spawn(|workspace, mut cx| async move {
+            let saved_text = fs.load(&abs_path).await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                Self::open_with_text(
+                    workspace,
+                    saved_text,
+                    OldSource::Saved,
+                    title.into(),
+                    buffer,
+                    cx,
+                );
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn diff_with_revision(
+        workspace: &mut Workspace,
+        revision: String,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let Some(buffer) = active_buffer(workspace, cx) else {
+            return;
+        };
+        let Some(file) = buffer.read(cx).file() else {
+            return;
+        };
+        let project_path = ProjectPath {
+            worktree_id: file.worktree_id(),
+            path: file.path().clone(),
+        };
+        let title = format!("{} ({revision})", file.file_name(cx).to_string_lossy());
+        let load_text =
+            workspace
+                .project()
+                .read(cx)
+                .load_revision_text(&project_path, revision.clone(), cx);
+        cx.spawn(|workspace, mut cx| async move {
+            // Files added since the revision are compared with nothing.
+            let text = load_text.await?.unwrap_or_default();
+            workspace.update(&mut cx, |workspace, cx| {
+                Self::open_with_text(
+                    workspace,
+                    text,
+                    OldSource::Revision(revision),
+                    title.into(),
+                    buffer,
+                    cx,
+                );
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Compares the editor that was active before the active one with it.
+    fn diff_with_previous_editor(
+        workspace: &mut Workspace,
+        _: &DiffWithPreviousEditor,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let Some(new_buffer) = active_buffer(workspace, cx) else {
+            return;
+        };
+        let pane = workspace.active_pane().read(cx);
+        let old_buffer = pane
+            .activation_history()
+            .iter()
+            .rev()
+            .filter_map(|item_id| {
+                let item = pane.items().find(|item| item.item_id() == *item_id)?;
+                let editor = item.downcast::<Editor>()?;
+                let buffer = editor.read(cx).buffer().read(cx).as_singleton();
+                buffer
+            })
+            .find(|buffer| *buffer != new_buffer);
+        if let Some(old_buffer) = old_buffer {
+            Self::open(workspace, old_buffer, new_buffer, cx);
+        }
+    }
+
+    fn diff_files(workspace: &mut Workspace, _: &DiffFiles, cx: &mut ViewContext<Workspace>) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: true,
+        });
+        cx.spawn(|workspace, mut cx| async move {
+            let Some(paths) = paths.await? else {
+                return Ok(());
+            };
+            let [old_path, new_path] = paths.as_slice() else {
+                return Err(anyhow!("select two files to compare"));
+            };
+            workspace
+                .update(&mut cx, |workspace, cx| {
+                    Self::open_files(workspace, old_path.clone(), new_path.clone(), cx)
+                })?
+                .await?;
+            Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Opens the files at the given paths and compares them.
+    pub fn open_files(
+        workspace: &mut Workspace,
+        old_path: PathBuf,
+        new_path: PathBuf,
+        cx: &mut ViewContext<Workspace>,
+    ) -> Task<Result<View<Self>>> {
+        let project = workspace.project().clone();
+        let old_buffer = project.update(cx, |project, cx| project.open_local_buffer(&old_path, cx));
+        let new_buffer = project.update(cx, |project, cx| project.open_local_buffer(&new_path, cx));
+        cx.spawn(|workspace, mut cx| async move {
+            let old_buffer = old_buffer
+                .await
+                .with_context(|| format!("failed to open {old_path:?}"))?;
+            let new_buffer = new_buffer
+                .await
+                .with_context(|| format!("failed to open {new_path:?}"))?;
+            workspace.update(&mut cx, |workspace, cx| {
+                Self::open(workspace, old_buffer, new_buffer, cx)
+            })
+        })
+    }
+
+    /// Compares two buffers, both of which can be edited in the diff.
+    pub fn open(
+        workspace: &mut Workspace,
+        old_buffer: Model<Buffer>,
+        new_buffer: Model<Buffer>,
+        cx: &mut ViewContext<Workspace>,
+    ) -> View<Self> {
+        let old_title = buffer_title(&old_buffer, cx);
+        Self::add_to_workspace(
+            workspace,
+            old_buffer,
+            OldSource::Buffer,
+            old_title,
+            new_buffer,
+            cx,
+        )
+    }
+
+    fn open_with_text(
+        workspace: &mut Workspace,
+        old_text: String,
+        old_source: OldSource,
+        old_title: SharedString,
+        new_buffer: Model<Buffer>,
+        cx: &mut ViewContext<Workspace>,
+    ) -> View<Self> {
+        let language = new_buffer.read(cx).language().cloned();
+        let old_buffer = cx.new_model(|cx| {
+            let mut buffer =
+                Buffer::new(0, BufferId::new(cx.entity_id().as_u64()).unwrap(), old_text);
+            buffer.set_language(language, cx);
+            buffer
+        });
+        Self::add_to_workspace(workspace, old_buffer, old_source, old_title, new_buffer, cx)
+    }
+
+    fn add_to_workspace(
+        workspace: &mut Workspace,
+        old_buffer: Model<Buffer>,
+        old_source: OldSource,
+        old_title: SharedString,
+        new_buffer: Model<Buffer>,
+        cx: &mut ViewContext<Workspace>,
+    ) -> View<Self> {
+        let existing = workspace.items_of_type::<Self>(cx).find(|view| {
+            let view = view.read(cx);
+            view.new_buffer == new_buffer
+                && view.old_source == old_source
+                && (old_source != OldSource::Buffer || view.old_buffer == old_buffer)
+        });
+        if let Some(existing) = existing {
+            workspace.activate_item(&existing, cx);
+            return existing;
+        }
+
+        let project = workspace.project().clone();
+        let view =
+            cx.new_view(|cx| Self::new(project, old_buffer, old_source, old_title, new_buffer, cx));
+        workspace.add_item(Box::new(view.clone()), cx);
+        view
+    }
+
+    fn new(
+        project: Model<Project>,
+        old_buffer: Model<Buffer>,
+        old_source: OldSource,
+        old_title: SharedString,
+        new_buffer: Model<Buffer>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let old_editor = cx.new_view(|cx| {
+            let mut editor = if old_source == OldSource::Buffer {
+                Editor::for_buffer(old_buffer.clone(), Some(project.clone()), cx)
+            } else {
+                let mut editor = Editor::for_buffer(old_buffer.clone(), None, cx);
+                editor.set_read_only(true);
+                editor
+            };
+            // Wrapped lines would throw the two sides out of alignment.
+            editor.set_soft_wrap_mode(SoftWrap::None, cx);
+            editor
+        });
+        let new_editor = cx.new_view(|cx| {
+            let mut editor = Editor::for_buffer(new_buffer.clone(), Some(project.clone()), cx);
+            editor.set_soft_wrap_mode(SoftWrap::None, cx);
+            editor
+        });
+        let subscriptions = vec![
+            cx.subscribe(&old_buffer, Self::handle_buffer_event),
+            cx.subscribe(&new_buffer, Self::handle_buffer_event),
+            cx.subscribe(&old_editor, Self::handle_editor_event),
+            cx.subscribe(&new_editor, Self::handle_editor_event),
+        ];
+
+        let mut this = Self {
+            new_title: buffer_title(&new_buffer, cx),
+            old_buffer,
+            new_buffer,
+            old_source,
+            old_title,
+            old_editor,
+            new_editor,
+            mode: DiffMode::default(),
+            hunks: Vec::new(),
+            old_blocks: HashSet::default(),
+            new_blocks: HashSet::default(),
+            _diff_task: Task::ready(()),
+            _subscriptions: subscriptions,
+        };
+        this.update_diff(cx);
+        this
+    }
+
+    pub fn mode(&self) -> DiffMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: DiffMode, cx: &mut ViewContext<Self>) {
+        if self.mode != mode {
+            self.mode = mode;
+            self.apply_diff(cx);
+            if self.mode == DiffMode::SideBySide {
+                let new_editor = self.new_editor.clone();
+                self.sync_scroll(&new_editor, cx);
+            }
+            cx.notify();
+        }
+    }
+
+    pub fn hunks(&self) -> &[DiffHunk] {
+        &self.hunks
+    }
+
+    pub fn old_editor(&self) -> &View<Editor> {
+        &self.old_editor
+    }
+
+    pub fn new_editor(&self) -> &View<Editor> {
+        &self.new_editor
+    }
+
+    fn toggle_mode(&mut self, _: &ToggleDiffMode, cx: &mut ViewContext<Self>) {
+        let mode = match self.mode {
+            DiffMode::SideBySide => DiffMode::Unified,
+            DiffMode::Unified => DiffMode::SideBySide,
+        };
+        self.set_mode(mode, cx);
+    }
+
+    fn handle_buffer_event(
+        &mut self,
+        buffer: Model<Buffer>,
+        event: &language::Event,
+        cx: &mut ViewContext<Self>,
+    ) {
+        match event {
+            language::Event::Saved | language::Event::Reloaded
+                if buffer == self.new_buffer && self.old_source == OldSource::Saved =>
+            {
+                let saved_text = self.new_buffer.read(cx).text();
+                self.old_buffer
+                    .update(cx, |old_buffer, cx| old_buffer.set_text(saved_text, cx));
+            }
+            language::Event::Edited | language::Event::Reloaded => self.update_diff(cx),
+            language::Event::DirtyChanged | language::Event::FileHandleChanged => {
+                self.new_title = buffer_title(&self.new_buffer, cx);
+                if self.old_source == OldSource::Buffer {
+                    self.old_title = buffer_title(&self.old_buffer, cx);
+                }
+                cx.emit(ItemEvent::UpdateTab);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_editor_event(
+        &mut self,
+        editor: View<Editor>,
+        event: &EditorEvent,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let EditorEvent::ScrollPositionChanged { .. } = event {
+            self.sync_scroll(&editor, cx);
+        }
+    }
+
+    /// Scrolls the other side to the same row, the sides being aligned by blocks.
+    fn sync_scroll(&mut self, source: &View<Editor>, cx: &mut ViewContext<Self>) {
+        if self.mode != DiffMode::SideBySide {
+            return;
+        }
+        let target = if *source == self.old_editor {
+            &self.new_editor
+        } else {
+            &self.old_editor
+        };
+        let position = source.update(cx, |editor, cx| editor.scroll_position(cx));
+        target.update(cx, |editor, cx| {
+            if editor.scroll_position(cx) != position {
+                editor.set_scroll_position(position, cx);
+            }
+        });
+    }
+
+    fn update_diff(&mut self, cx: &mut ViewContext<Self>) {
+        let old_text = self.old_buffer.read(cx).text();
+        let new_snapshot = self.new_buffer.read(cx).text_snapshot();
+        // Replacing the task cancels the computation for outdated contents.
+        self._diff_task = cx.spawn(|this, mut cx| async move {
+            let hunks = cx
+                .background_executor()
+                .spawn(async move { diff_hunks(&old_text, &new_snapshot).await })
+                .await;
+            this.update(&mut cx, |this, cx| {
+                this.hunks = hunks;
+                this.apply_diff(cx);
+                cx.notify();
+            })
+            .ok();
+        });
+    }
+
+    fn apply_diff(&mut self, cx: &mut ViewContext<Self>) {
+        let status = cx.theme().status();
+        let (deleted, created) = (status.deleted_background, status.created_background);
+
+        highlight_changes(
+            &self.old_editor,
+            self.hunks.iter().flat_map(DiffHunk::old_lines),
+            self.hunks.iter().flat_map(|hunk| hunk.old_words.clone()),
+            deleted,
+            cx,
+        );
+        highlight_changes(
+            &self.new_editor,
+            self.hunks.iter().flat_map(DiffHunk::new_lines),
+            self.hunks.iter().flat_map(|hunk| hunk.new_words.clone()),
+            created,
+            cx,
+        );
+
+        let (old_blocks, new_blocks) = match self.mode {
+            DiffMode::SideBySide => self.padding_blocks(),
+            DiffMode::Unified => (Vec::new(), self.deleted_blocks(deleted, cx)),
+        };
+        let old_block_ids = mem::take(&mut self.old_blocks);
+        self.old_blocks = replace_blocks(&self.old_editor, old_block_ids, old_blocks, cx);
+        let new_block_ids = mem::take(&mut self.new_blocks);
+        self.new_blocks = replace_blocks(&self.new_editor, new_block_ids, new_blocks, cx);
+    }
+
+    /// Blocks filling in for the lines that the other side has more of in each hunk,
+    /// which keep the sides aligned.
+    fn padding_blocks(&self) -> (Vec<DiffBlock>, Vec<DiffBlock>) {
+        let mut old_blocks = Vec::new();
+        let mut new_blocks = Vec::new();
+        for hunk in &self.hunks {
+            let old_len = hunk.old_rows.len();
+            let new_len = hunk.new_rows.len();
+            let (blocks, row, height) = if old_len < new_len {
+                (&mut old_blocks, hunk.old_rows.end, new_len - old_len)
+            } else {
+                (&mut new_blocks, hunk.new_rows.end, old_len - new_len)
+            };
+            let mut height = height;
+            while height > 0 {
+                let block_height = height.min(u8::MAX as usize);
+                blocks.push(DiffBlock {
+                    row,
+                    height: block_height as u8,
+                    render: Arc::new(|cx: &mut BlockContext| {
+                        div()
+                            .size_full()
+                            .bg(cx.theme().colors().editor_subheader_background)
+                            .into_any_element()
+                    }),
+                });
+                height -= block_height;
+            }
+        }
+        (old_blocks, new_blocks)
+    }
+
+    /// Blocks showing the deleted lines of each hunk above the lines that replaced them.
+    fn deleted_blocks(&self, color: Hsla, cx: &AppContext) -> Vec<DiffBlock> {
+        let old_text = self.old_buffer.read(cx).text();
+        self.hunks
+            .iter()
+            .filter(|hunk| !hunk.old_range.is_empty())
+            .map(|hunk| {
+                let mut lines = Vec::new();
+                let mut line_start = hunk.old_range.start;
+                for line in old_text[hunk.old_range.clone()].lines() {
+                    let line_range = line_start..line_start + line.len();
+                    let words = hunk
+                        .old_words
+                        .iter()
+                        .filter(|word| word.start < line_range.end && word.end > line_range.start)
+                        .map(|word| {
+                            word.start.max(line_range.start) - line_start
+                                ..word.end.min(line_range.end) - line_start
+                        })
+                        .collect::<Vec<_>>();
+                    lines.push((SharedString::from(line.to_string()), words));
+                    line_start = line_range.end + 1;
+                }
+
+                let hidden_lines = lines.len().saturating_sub(MAX_DELETED_LINES);
+                if hidden_lines > 0 {
+                    lines.truncate(MAX_DELETED_LINES - 1);
+                }
+                let height = lines.len() + usize::from(hidden_lines > 0);
+                DiffBlock {
+                    row: hunk.new_rows.start,
+                    height: height as u8,
+                    render: render_deleted_lines(lines, hidden_lines + 1, color),
+                }
+            })
+            .collect()
+    }
+}
+
+/// A block inserted in one of the editors, above the given row or below the last one.
+struct DiffBlock {
+    row: u32,
+    height: u8,
+    render: RenderBlock,
+}
+
+fn replace_blocks(
+    editor: &View<Editor>,
+    old_block_ids: HashSet<BlockId>,
+    blocks: Vec<DiffBlock>,
+    cx: &mut WindowContext,
+) -> HashSet<BlockId> {
+    editor.update(cx, |editor, cx| {
+        editor.remove_blocks(old_block_ids, None, cx);
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let max_row = snapshot.max_point().row;
+        let blocks = blocks.into_iter().map(|block| {
+            let (row, disposition) = if block.row > max_row {
+                (max_row, BlockDisposition::Below)
+            } else {
+                (block.row, BlockDisposition::Above)
+            };
+            BlockProperties {
+                position: snapshot.anchor_before(Point::new(row, 0)),
+                height: block.height,
+                style: BlockStyle::Flex,
+                render: block.render,
+                disposition,
+            }
+        });
+        editor.insert_blocks(blocks, None, cx).into_iter().collect()
+    })
+}
+
+/// Highlights the changed lines of an editor faintly, and the changed words within
+/// them prominently.
+fn highlight_changes(
+    editor: &View<Editor>,
+    lines: impl Iterator<Item = Range<usize>>,
+    words: impl Iterator<Item = Range<usize>>,
+    color: Hsla,
+    cx: &mut WindowContext,
+) {
+    editor.update(cx, |editor, cx| {
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let to_anchors = |range: Range<usize>| {
+            snapshot.anchor_after(range.start)..snapshot.anchor_before(range.end)
+        };
+        let lines = lines.map(&to_anchors).collect();
+        let words = words.map(&to_anchors).collect();
+        editor.highlight_text::<DiffLine>(lines, line_highlight(color), cx);
+        editor.highlight_text::<DiffWord>(
+            words,
+            HighlightStyle {
+                background_color: Some(color),
+                ..Default::default()
+            },
+            cx,
+        );
+    });
+}
+
+fn line_highlight(mut color: Hsla) -> HighlightStyle {
+    color.fade_out(0.6);
+    HighlightStyle {
+        background_color: Some(color),
+        ..Default::default()
+    }
+}
+
+/// Renders deleted lines with their changed words, followed by the number of lines
+/// that were left out, if any, when `more_lines` is greater than one.
+fn render_deleted_lines(
+    lines: Vec<(SharedString, Vec<Range<usize>>)>,
+    more_lines: usize,
+    color: Hsla,
+) -> RenderBlock {
+    Arc::new(move |cx: &mut BlockContext| {
+        let text_style = cx.editor_style.text.clone();
+        let line_height = cx.line_height;
+        let text_x = cx.anchor_x;
+        let line_background = line_highlight(color).background_color;
+        let word_highlight = HighlightStyle {
+            background_color: Some(color),
+            ..Default::default()
+        };
+        let row = || {
+            h_flex()
+                .w_full()
+                .h(line_height)
+                .pl(text_x)
+                .when_some(line_background, |row, background| row.bg(background))
+        };
+
+        v_flex()
+            .w_full()
+            .children(lines.iter().map(|(text, words)| {
+                row().child(StyledText::new(text.clone()).with_highlights(
+                    &text_style,
+                    words.iter().map(|word| (word.clone(), word_highlight)),
+                ))
+            }))
+            .when(more_lines > 1, |lines| {
+                lines.child(
+                    row().child(
+                        Label::new(format!("{} more deleted lines", more_lines - 1))
+                            .color(Color::Muted),
+                    ),
+                )
+            })
+            .into_any_element()
+    })
+}
+
+fn active_buffer(workspace: &Workspace, cx: &AppContext) -> Option<Model<Buffer>> {
+    let editor = workspace.active_item_as::<Editor>(cx)?;
+    let buffer = editor.read(cx).buffer().read(cx).as_singleton();
+    buffer
+}
+
+fn buffer_title(buffer: &Model<Buffer>, cx: &AppContext) -> SharedString {
+    let buffer = buffer.read(cx);
+    let name = buffer.file().map_or_else(
+        || "untitled".to_string(),
+        |file| file.file_name(cx).to_string_lossy().to_string(),
+    );
+    if buffer.is_dirty() {
+        format!("{name} (unsaved)").into()
+    } else {
+        name.into()
+    }
+}
+
+fn file_path(buffer: &Model<Buffer>, cx: &AppContext) -> Option<PathBuf> {
+    let file = buffer.read(cx).file()?;
+    Some(
+        file.as_local()
+            .map_or_else(|| file.full_path(cx), |file| file.abs_path(cx)),
+    )
+}
+
+impl EventEmitter<ItemEvent> for DiffView {}
+
+impl FocusableView for DiffView {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.new_editor.focus_handle(cx)
+    }
+}
+
+impl Item for DiffView {
+    type Event = ItemEvent;
+
+    fn tab_content(&self, _: Option<usize>, selected: bool, _: &WindowContext) -> AnyElement {
+        let color = if selected {
+            Color::Default
+        } else {
+            Color::Muted
+        };
+        h_flex()
+            .gap_2()
+            .child(Icon::new(IconName::Split).color(color))
+            .child(Label::new(format!("{} ↔ {}", self.old_title, self.new_title)).color(color))
+            .into_any_element()
+    }
+
+    fn tab_tooltip_text(&self, cx: &AppContext) -> Option<SharedString> {
+        let old_path = match &self.old_source {
+            OldSource::Buffer => file_path(&self.old_buffer, cx)?
+                .to_string_lossy()
+                .to_string(),
+            OldSource::Saved | OldSource::Revision(_) => self.old_title.to_string(),
+        };
+        let new_path = file_path(&self.new_buffer, cx)?;
+        Some(format!("{old_path} ↔ {}", new_path.to_string_lossy()).into())
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("diff view")
+    }
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+
+    fn is_dirty(&self, cx: &AppContext) -> bool {
+        self.edited_buffers(cx).next().is_some()
+    }
+
+    fn can_save(&self, cx: &AppContext) -> bool {
+        self.new_buffer.read(cx).file().is_some()
+    }
+
+    fn save(&mut self, project: Model<Project>, cx: &mut ViewContext<Self>) -> Task<Result<()>> {
+        let buffers = self.edited_buffers(cx).collect::<HashSet<_>>();
+        project.update(cx, |project, cx| project.save_buffers(buffers, cx))
+    }
+
+    fn deactivated(&mut self, cx: &mut ViewContext<Self>) {
+        self.new_editor
+            .update(cx, |editor, cx| editor.deactivated(cx));
+    }
+}
+
+impl DiffView {
+    /// The buffers of the diff that have unsaved edits, the old one only being saved
+    /// when it belongs to a file of its own.
+    fn edited_buffers<'a>(
+        &'a self,
+        cx: &'a AppContext,
+    ) -> impl 'a + Iterator<Item = Model<Buffer>> {
+        let old_buffer = (self.old_source == OldSource::Buffer).then(|| self.old_buffer.clone());
+        [Some(self.new_buffer.clone()), old_buffer]
+            .into_iter()
+            .flatten()
+            .filter(move |buffer| {
+                let buffer = buffer.read(cx);
+                buffer.is_dirty() && buffer.file().is_some()
+            })
+    }
+}
+
+impl Render for DiffView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let header = |title: SharedString| {
+            h_flex()
+                .px_2()
+                .py_1()
+                .border_b_1()
+                .border_color(cx.theme().colors().border)
+                .child(Label::new(title).color(Color::Muted))
+        };
+        let mode_label = match self.mode {
+            DiffMode::SideBySide => "Unified",
+            DiffMode::Unified => "Side by Side",
+        };
+        let toggle_mode = Button::new("toggle-diff-mode", mode_label)
+            .on_click(cx.listener(|this, _, cx| this.toggle_mode(&ToggleDiffMode, cx)));
+
+        let content = match self.mode {
+            DiffMode::SideBySide => h_flex()
+                .flex_1()
+                .size_full()
+                .child(
+                    v_flex()
+                        .flex_1()
+                        .h_full()
+                        .border_r_1()
+                        .border_color(cx.theme().colors().border)
+                        .child(header(self.old_title.clone()))
+                        .child(div().flex_1().child(self.old_editor.clone())),
+                )
+                .child(
+                    v_flex()
+                        .flex_1()
+                        .h_full()
+                        .child(
+                            header(self.new_title.clone())
+                                .justify_between()
+                                .child(toggle_mode),
+                        )
+                        .child(div().flex_1().child(self.new_editor.clone())),
+                ),
+            DiffMode::Unified => h_flex().flex_1().size_full().child(
+                v_flex()
+                    .flex_1()
+                    .h_full()
+                    .child(
+                        header(format!("{} ↔ {}", self.old_title, self.new_title).into())
+                            .justify_between()
+                            .child(toggle_mode),
+                    )
+                    .child(div().flex_1().child(self.new_editor.clone())),
+            ),
+        };
+
+        v_flex()
+            .key_context("DiffView")
+            .on_action(cx.listener(Self::toggle_mode))
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    async fn test_side_by_side_and_unified_diff(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/root",
+            json!({
+                "old.txt": "one\ntwo\nthree\nfour\n",
+                "new.txt": "one\n2\nthree\nfour\nfive\nsix\n",
+            }),
+        )
+        .await;
+        let project = Project::test(fs, ["/root".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+
+        let diff_view = workspace
+            .update(cx, |workspace, cx| {
+                DiffView::open_files(
+                    workspace,
+                    PathBuf::from("/root/old.txt"),
+                    PathBuf::from("/root/new.txt"),
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+        cx.run_until_parked();
+
+        diff_view.update(cx, |diff_view, cx| {
+            let rows = diff_view
+                .hunks()
+                .iter()
+                .map(|hunk| (hunk.old_rows.clone(), hunk.new_rows.clone()))
+                .collect::<Vec<_>>();
+            assert_eq!(rows, [(1..2, 1..2), (4..4, 4..6)]);
+            assert_eq!(diff_view.old_blocks.len(), 1);
+            assert!(diff_view.new_blocks.is_empty());
+            assert_eq!(diff_view.new_title.as_ref(), "new.txt");
+
+            diff_view.set_mode(DiffMode::Unified, cx);
+            assert!(diff_view.old_blocks.is_empty());
+            assert_eq!(diff_view.new_blocks.len(), 1);
+        });
+
+        // Editing either side updates the diff.
+        diff_view.update(cx, |diff_view, cx| {
+            diff_view.new_editor().update(cx, |editor, cx| {
+                editor.buffer().update(cx, |buffer, cx| {
+                    buffer.edit([(Point::new(4, 0)..Point::new(6, 0), "")], None, cx)
+                })
+            });
+        });
+        cx.run_until_parked();
+        diff_view.update(cx, |diff_view, cx| {
+            assert_eq!(diff_view.hunks().len(), 1);
+            assert!(diff_view.is_dirty(cx));
+            assert_eq!(diff_view.new_title.as_ref(), "new.txt (unsaved)");
+        });
+    }
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            language::init(cx);
+            Project::init_settings(cx);
+            workspace::init_settings(cx);
+            editor::init(cx);
+        });
+    }
+}
@@ -0,0 +1,290 @@
+use std::ops::Range;
+
+use git::diff::BufferDiff;
+use similar::{Algorithm, DiffTag};
+use text::{BufferSnapshot, Point};
+
+/// Hunks longer than this aren't diffed word by word, as the result wouldn't be
+/// readable and would take long to compute.
+const MAX_WORD_DIFF_LEN: usize = 8 * 1024;
+
+/// A change between the old and the new text, with the words that changed within it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffHunk {
+    pub old_rows: Range<u32>,
+    pub new_rows: Range<u32>,
+    /// The bytes of the old text in the hunk's rows.
+    pub old_range: Range<usize>,
+    /// The bytes of the new text in the hunk's rows.
+    pub new_range: Range<usize>,
+    /// The bytes of the old text that were removed or replaced within the hunk.
+    pub old_words: Vec<Range<usize>>,
+    /// The bytes of the new text that were inserted or replaced within the hunk.
+    pub new_words: Vec<Range<usize>>,
+}
+
+impl DiffHunk {
+    /// The parts of the hunk's old text that aren't changed words, which are
+    /// highlighted less prominently.
+    pub fn old_lines(&self) -> Vec<Range<usize>> {
+        subtract_ranges(self.old_range.clone(), &self.old_words)
+    }
+
+    /// The parts of the hunk's new text that aren't changed words.
+    pub fn new_lines(&self) -> Vec<Range<usize>> {
+        subtract_ranges(self.new_range.clone(), &self.new_words)
+    }
+}
+
+/// Computes the line-level hunks between an old text and a buffer, along with the
+/// words that changed within the hunks that modify lines.
+pub async fn diff_hunks(old_text: &str, new: &BufferSnapshot) -> Vec<DiffHunk> {
+    let mut buffer_diff = BufferDiff::new();
+    buffer_diff.update(old_text, new).await;
+
+    let new_text = new.text();
+    let mut old_row = 0;
+    let mut old_offset = 0;
+    let mut hunks = Vec::new();
+    for hunk in buffer_diff.hunks_intersecting_range(text::Anchor::MIN..text::Anchor::MAX, new) {
+        let old_range = hunk.diff_base_byte_range.clone();
+        old_row += old_text[old_offset..old_range.start].matches('\n').count() as u32;
+        old_offset = old_range.start;
+        let old_rows = old_row..old_row + line_count(&old_text[old_range.clone()]);
+
+        let new_rows = hunk.buffer_range.clone();
+        let new_range = row_range_to_offsets(new, new_rows.clone());
+
+        let (old_words, new_words) = if !old_range.is_empty()
+            && !new_range.is_empty()
+            && old_range.len() <= MAX_WORD_DIFF_LEN
+            && new_range.len() <= MAX_WORD_DIFF_LEN
+        {
+            word_diff(
+                &old_text[old_range.clone()],
+                old_range.start,
+                &new_text[new_range.clone()],
+                new_range.start,
+            )
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        hunks.push(DiffHunk {
+            old_rows,
+            new_rows,
+            old_range,
+            new_range,
+            old_words,
+            new_words,
+        });
+    }
+    hunks
+}
+
+fn line_count(text: &str) -> u32 {
+    let newlines = text.matches('\n').count() as u32;
+    if text.is_empty() || text.ends_with('\n') {
+        newlines
+    } else {
+        newlines + 1
+    }
+}
+
+fn row_range_to_offsets(buffer: &BufferSnapshot, rows: Range<u32>) -> Range<usize> {
+    let offset_for_row = |row: u32| {
+        if row > buffer.max_point().row {
+            buffer.len()
+        } else {
+            buffer.point_to_offset(Point::new(row, 0))
+        }
+    };
+    offset_for_row(rows.start)..offset_for_row(rows.end)
+}
+
+/// Diffs two texts token by token, returning the byte ranges of the changed tokens
+/// in each, shifted by the given offsets. Adjacent changes are merged.
+fn word_diff(
+    old: &str,
+    old_offset: usize,
+    new: &str,
+    new_offset: usize,
+) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let old_tokens = tokenize(old);
+    let new_tokens = tokenize(new);
+    let old_words = old_tokens
+        .iter()
+        .map(|range| &old[range.clone()])
+        .collect::<Vec<_>>();
+    let new_words = new_tokens
+        .iter()
+        .map(|range| &new[range.clone()])
+        .collect::<Vec<_>>();
+
+    let mut old_changes = Vec::new();
+    let mut new_changes = Vec::new();
+    for op in similar::capture_diff_slices(Algorithm::Myers, &old_words, &new_words) {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        if tag == DiffTag::Equal {
+            continue;
+        }
+        push_token_range(&mut old_changes, &old_tokens, old_range, old_offset);
+        push_token_range(&mut new_changes, &new_tokens, new_range, new_offset);
+    }
+    (old_changes, new_changes)
+}
+
+fn push_token_range(
+    ranges: &mut Vec<Range<usize>>,
+    tokens: &[Range<usize>],
+    token_range: Range<usize>,
+    offset: usize,
+) {
+    if token_range.is_empty() {
+        return;
+    }
+    let start = tokens[token_range.start].start + offset;
+    let end = tokens[token_range.end - 1].end + offset;
+    match ranges.last_mut() {
+        Some(last) if last.end == start => last.end = end,
+        _ => ranges.push(start..end),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Word,
+    Whitespace,
+    Other,
+}
+
+/// Splits text into words, runs of whitespace other than newlines, and single
+/// characters of any other kind.
+fn tokenize(text: &str) -> Vec<Range<usize>> {
+    let kind = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            TokenKind::Word
+        } else if c.is_whitespace() && c != '\n' {
+            TokenKind::Whitespace
+        } else {
+            TokenKind::Other
+        }
+    };
+
+    let mut tokens: Vec<Range<usize>> = Vec::new();
+    let mut last_kind = None;
+    for (offset, c) in text.char_indices() {
+        let kind = kind(c);
+        let end = offset + c.len_utf8();
+        match tokens.last_mut() {
+            Some(token) if last_kind == Some(kind) && kind != TokenKind::Other => token.end = end,
+            _ => tokens.push(offset..end),
+        }
+        last_kind = Some(kind);
+    }
+    tokens
+}
+
+fn subtract_ranges(range: Range<usize>, holes: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = range.start;
+    for hole in holes {
+        if hole.start > start {
+            ranges.push(start..hole.start.min(range.end));
+        }
+        start = start.max(hole.end);
+    }
+    if start < range.end {
+        ranges.push(start..range.end);
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use text::{Buffer, BufferId};
+    use unindent::Unindent as _;
+
+    #[test]
+    fn test_diff_hunks() {
+        let old_text = "
+            fn main() {
+                let count = 1;
+                println!(\"{count}\");
+            }
+            // removed
+        "
+        .unindent();
+        let new_text = "
+            // added
+            fn main() {
+                let total = 1;
+                println!(\"{total}\");
+            }
+        "
+        .unindent();
+        let buffer = Buffer::new(0, BufferId::new(1).unwrap(), new_text.clone());
+        let hunks = smol::block_on(diff_hunks(&old_text, &buffer.snapshot()));
+
+        let texts = hunks
+            .iter()
+            .map(|hunk| {
+                (
+                    hunk.old_rows.clone(),
+                    hunk.new_rows.clone(),
+                    &old_text[hunk.old_range.clone()],
+                    &new_text[hunk.new_range.clone()],
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            [
+                (0..0, 0..1, "", "// added\n"),
+                (
+                    1..3,
+                    2..4,
+                    "    let count = 1;\n    println!(\"{count}\");\n",
+                    "    let total = 1;\n    println!(\"{total}\");\n"
+                ),
+                (4..5, 5..5, "// removed\n", ""),
+            ]
+        );
+
+        let modified = &hunks[1];
+        let words = |text: &str, ranges: &[Range<usize>]| {
+            ranges
+                .iter()
+                .map(|range| text[range.clone()].to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(words(&old_text, &modified.old_words), ["count", "count"]);
+        assert_eq!(words(&new_text, &modified.new_words), ["total", "total"]);
+        assert_eq!(
+            words(&new_text, &modified.new_lines()),
+            ["    let ", " = 1;\n    println!(\"{", "}\");\n"]
+        );
+        assert!(hunks[0].old_words.is_empty() && hunks[0].new_words.is_empty());
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let text = "let x_1 =  foo(bar);\n";
+        let tokens = tokenize(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            ["let", " ", "x_1", " ", "=", "  ", "foo", "(", "bar", ")", ";", "\n"]
+        );
+    }
+
+    #[test]
+    fn test_subtract_ranges() {
+        assert_eq!(subtract_ranges(0..10, &[2..4, 4..6, 8..10]), [0..2, 6..8]);
+        assert_eq!(subtract_ranges(0..10, &[]), [0..10]);
+        assert_eq!(subtract_ranges(0..4, &[0..4]), Vec::<Range<usize>>::new());
+    }
+}
@@ -43,6 +43,13 @@ pub struct ToggleCodeActions {
     pub deployed_from_indicator: bool,
 }
 
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct SpawnRunnable {
+    /// The row of the runnable to spawn, defaults to the row of the newest selection.
+    #[serde(default)]
+    pub row: Option<u32>,
+}
+
 #[derive(PartialEq, Clone, Deserialize, Default)]
 pub struct ConfirmCompletion {
     #[serde(default)]
@@ -104,6 +111,7 @@ impl_actions!(
         MovePageDown,
         SelectToEndOfLine,
         ToggleCodeActions,
+        SpawnRunnable,
         ConfirmCompletion,
         ConfirmCodeAction,
         ToggleComments,
@@ -119,6 +127,9 @@ impl_actions!(
 gpui::actions!(
     editor,
     [
+        AcceptConflictBoth,
+        AcceptConflictOurs,
+        AcceptConflictTheirs,
         AddSelectionAbove,
         AddSelectionBelow,
         Backspace,
@@ -157,10 +168,12 @@ gpui::actions!(
         Fold,
         FoldSelectedRanges,
         Format,
+        GoToConflict,
         GoToDefinition,
         GoToDefinitionSplit,
         GoToDiagnostic,
         GoToHunk,
+        GoToPrevConflict,
         GoToPrevDiagnostic,
         GoToPrevHunk,
         GoToTypeDefinition,
@@ -234,6 +247,7 @@ gpui::actions!(
         SplitSelectionIntoLines,
         Tab,
         TabPrev,
+        ToggleBreakpoint,
         ToggleInlayHints,
         ToggleSoftWrap,
         Transpose,
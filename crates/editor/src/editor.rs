@@ -25,6 +25,7 @@ mod highlight_matching_bracket;
 mod hover_links;
 mod hover_popover;
 pub mod items;
+mod large_file;
 mod mouse_context_menu;
 pub mod movement;
 mod persistence;
@@ -54,6 +55,7 @@ use element::LineWithInvisibles;
 pub use element::{Cursor, EditorElement, HighlightedRange, HighlightedRangeLine};
 use futures::FutureExt;
 use fuzzy::{StringMatch, StringMatchCandidate};
+pub use git::conflicts::EditorConflict;
 use git::diff_hunk_to_display;
 use gpui::{
     div, impl_actions, point, prelude::*, px, relative, rems, size, uniform_list, Action,
@@ -72,9 +74,9 @@ use itertools::Itertools;
 use language::{char_kind, CharKind};
 use language::{
     language_settings::{self, all_language_settings, InlayHintSettings},
-    markdown, point_from_lsp, AutoindentMode, BracketPair, Buffer, Capability, CodeAction,
-    CodeLabel, Completion, CursorShape, Diagnostic, Documentation, IndentKind, IndentSize,
-    Language, OffsetRangeExt, Point, Selection, SelectionGoal, TransactionId,
+    markdown, point_from_lsp, AutoindentMode, BracketPair, Buffer, BufferSnapshot, Capability,
+    CodeAction, CodeLabel, Completion, CursorShape, Diagnostic, Documentation, IndentKind,
+    IndentSize, Language, OffsetRangeExt, Point, Selection, SelectionGoal, TransactionId,
 };
 
 use hover_links::{HoverLink, HoveredLinkState, InlayHighlight};
@@ -88,7 +90,10 @@ pub use multi_buffer::{
 };
 use ordered_float::OrderedFloat;
 use parking_lot::{Mutex, RwLock};
-use project::{FormatTrigger, Location, Project, ProjectPath, ProjectTransaction};
+use project::{
+    breakpoint_store::Event as BreakpointStoreEvent, FormatTrigger, Location, Project, ProjectPath,
+    ProjectTransaction,
+};
 use rand::prelude::*;
 use rpc::proto::*;
 use scroll::{Autoscroll, OngoingScroll, ScrollAnchor, ScrollManager, ScrollbarAutoHide};
@@ -121,7 +126,10 @@ use ui::{
 };
 use util::{maybe, post_inc, RangeExt, ResultExt, TryFutureExt};
 use workspace::Toast;
-use workspace::{searchable::SearchEvent, ItemNavHistory, Pane, SplitDirection, ViewId, Workspace};
+use workspace::{
+    item::PreviewTabsSettings, searchable::SearchEvent, ItemNavHistory, Pane, SplitDirection,
+    ViewId, Workspace,
+};
 
 use crate::hover_links::find_url;
 
@@ -135,6 +143,8 @@ pub(crate) const CURSORS_VISIBLE_FOR: Duration = Duration::from_millis(2000);
 pub const CODE_ACTIONS_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(250);
 #[doc(hidden)]
 pub const DOCUMENT_HIGHLIGHTS_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(75);
+#[doc(hidden)]
+pub const CONFLICTS_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(150);
 
 pub(crate) const FORMAT_TIMEOUT: Duration = Duration::from_secs(2);
 
@@ -399,12 +409,15 @@ pub struct Editor {
     available_code_actions: Option<(Model<Buffer>, Arc<[CodeAction]>)>,
     code_actions_task: Option<Task<()>>,
     document_highlights_task: Option<Task<()>>,
+    merge_conflicts: git::conflicts::MergeConflicts,
+    runnable_rows: Option<RunnableRows>,
     pending_rename: Option<RenameState>,
     searchable: bool,
     cursor_shape: CursorShape,
     collapse_matches: bool,
     autoindent_mode: Option<AutoindentMode>,
     workspace: Option<(WeakView<Workspace>, i64)>,
+    unsaved_backup_task: Option<Task<()>>,
     keymap_context_layers: BTreeMap<TypeId, KeyContext>,
     input_enabled: bool,
     use_modal_editing: bool,
@@ -1320,6 +1333,15 @@ impl CopilotState {
     }
 }
 
+/// The buffer rows of the runnables in the edited buffer, for a version of its text and
+/// syntax tree.
+#[derive(Debug)]
+struct RunnableRows {
+    version: clock::Global,
+    parse_count: usize,
+    rows: Vec<u32>,
+}
+
 #[derive(Debug)]
 struct ActiveDiagnosticGroup {
     primary_range: Range<Anchor>,
@@ -1454,8 +1476,13 @@ impl Editor {
 
         let blink_manager = cx.new_model(|cx| BlinkManager::new(CURSOR_BLINK_INTERVAL, cx));
 
-        let soft_wrap_mode_override =
-            (mode == EditorMode::SingleLine).then(|| language_settings::SoftWrap::None);
+        // Soft wrapping a large file would have to lay out every one of its lines.
+        let is_large_file = buffer
+            .read(cx)
+            .as_singleton()
+            .map_or(false, |buffer| buffer.read(cx).is_large_file());
+        let soft_wrap_mode_override = (mode == EditorMode::SingleLine || is_large_file)
+            .then(|| language_settings::SoftWrap::None);
 
         let mut project_subscriptions = Vec::new();
         if mode == EditorMode::Full {
@@ -1470,6 +1497,16 @@ impl Editor {
                         editor.refresh_inlay_hints(InlayHintRefreshReason::RefreshRequested, cx);
                     };
                 }));
+                let breakpoint_store = project.read(cx).breakpoint_store().clone();
+                project_subscriptions.push(cx.subscribe(
+                    &breakpoint_store,
+                    |editor, _, event, cx| {
+                        let BreakpointStoreEvent::BreakpointsChanged { abs_path } = event;
+                        if editor.breakpoint_path(cx).as_ref() == Some(abs_path) {
+                            cx.notify();
+                        }
+                    },
+                ));
             }
         }
 
@@ -1524,12 +1561,15 @@ impl Editor {
             available_code_actions: Default::default(),
             code_actions_task: Default::default(),
             document_highlights_task: Default::default(),
+            merge_conflicts: Default::default(),
+            runnable_rows: None,
             pending_rename: Default::default(),
             searchable: true,
             cursor_shape: Default::default(),
             autoindent_mode: Some(AutoindentMode::EachLine),
             collapse_matches: false,
             workspace: None,
+            unsaved_backup_task: None,
             keymap_context_layers: Default::default(),
             input_enabled: true,
             use_modal_editing: mode == EditorMode::Full,
@@ -1575,6 +1615,8 @@ impl Editor {
 
         this.end_selection(cx);
         this.scroll_manager.show_scrollbar(cx);
+        this.refresh_conflicts(cx);
+        this.insert_large_file_banner(cx);
 
         if mode == EditorMode::Full {
             let should_auto_hide_scrollbars = cx.should_auto_hide_scrollbars();
@@ -3016,7 +3058,7 @@ impl Editor {
     }
 
     fn refresh_inlay_hints(&mut self, reason: InlayHintRefreshReason, cx: &mut ViewContext<Self>) {
-        if self.project.is_none() || self.mode != EditorMode::Full {
+        if self.project.is_none() || self.mode != EditorMode::Full || self.is_large_file(cx) {
             return;
         }
 
@@ -4074,6 +4116,42 @@ impl Editor {
         }
     }
 
+    /// The buffer rows of the runnables in the given snapshot of the edited buffer, queried
+    /// again only when the buffer or its syntax tree changes.
+    pub(crate) fn runnable_rows(&mut self, buffer_snapshot: &BufferSnapshot) -> &[u32] {
+        let is_stale = self.runnable_rows.as_ref().map_or(true, |runnable_rows| {
+            runnable_rows.parse_count != buffer_snapshot.parse_count()
+                || &runnable_rows.version != buffer_snapshot.version()
+        });
+        if is_stale {
+            let mut rows = buffer_snapshot
+                .runnables_in_range(0..buffer_snapshot.len())
+                .into_iter()
+                .map(|runnable| buffer_snapshot.offset_to_point(runnable.range.start).row)
+                .collect::<Vec<_>>();
+            rows.sort_unstable();
+            rows.dedup();
+            self.runnable_rows = Some(RunnableRows {
+                version: buffer_snapshot.version().clone(),
+                parse_count: buffer_snapshot.parse_count(),
+                rows,
+            });
+        }
+        self.runnable_rows
+            .as_ref()
+            .map_or(&[], |runnable_rows| &runnable_rows.rows)
+    }
+
+    pub fn render_run_indicator(&self, row: u32, cx: &mut ViewContext<Self>) -> IconButton {
+        IconButton::new(("run_indicator", row as usize), ui::IconName::Play)
+            .icon_size(IconSize::XSmall)
+            .icon_color(Color::Muted)
+            .on_click(cx.listener(move |editor, _e, cx| {
+                editor.focus(cx);
+                cx.dispatch_action(Box::new(SpawnRunnable { row: Some(row) }));
+            }))
+    }
+
     pub fn render_fold_indicators(
         &self,
         fold_data: Vec<Option<(FoldStatus, u32, bool)>>,
@@ -7433,7 +7511,12 @@ impl Editor {
                                         if split {
                                             workspace.split_project_item(target.buffer.clone(), cx)
                                         } else {
-                                            workspace.open_project_item(target.buffer.clone(), cx)
+                                            workspace.open_project_item(
+                                                target.buffer.clone(),
+                                                PreviewTabsSettings::get_global(cx)
+                                                    .enable_preview_from_code_navigation,
+                                                cx,
+                                            )
                                         }
                                     });
                                 target_editor.update(cx, |target_editor, cx| {
@@ -8447,6 +8530,51 @@ impl Editor {
         cx.notify();
     }
 
+    /// The path of the file edited in a singleton editor, which its breakpoints are keyed by.
+    fn breakpoint_path(&self, cx: &AppContext) -> Option<Arc<Path>> {
+        let buffer = self.buffer.read(cx).as_singleton()?;
+        let file = buffer.read(cx).file()?.as_local()?;
+        Some(file.abs_path(cx).into())
+    }
+
+    /// The buffer rows of the breakpoints set in the edited file.
+    pub fn breakpoint_rows(&self, cx: &AppContext) -> Vec<u32> {
+        let (Some(project), Some(abs_path)) = (self.project.as_ref(), self.breakpoint_path(cx))
+        else {
+            return Vec::new();
+        };
+        project
+            .read(cx)
+            .breakpoint_store()
+            .read(cx)
+            .breakpoints_for_path(&abs_path, cx)
+            .collect()
+    }
+
+    pub fn toggle_breakpoint(&mut self, _: &ToggleBreakpoint, cx: &mut ViewContext<Self>) {
+        let row = self.selections.newest::<Point>(cx).head().row;
+        self.toggle_breakpoint_at_row(row, cx);
+    }
+
+    /// Toggles a breakpoint on the given buffer row, returning whether the edited file
+    /// supports breakpoints.
+    pub(crate) fn toggle_breakpoint_at_row(
+        &mut self,
+        row: u32,
+        cx: &mut ViewContext<Self>,
+    ) -> bool {
+        let (Some(project), Some(abs_path)) = (self.project.clone(), self.breakpoint_path(cx))
+        else {
+            return false;
+        };
+        project.update(cx, |project, cx| {
+            project
+                .breakpoint_store()
+                .update(cx, |store, cx| store.toggle(abs_path, row, cx))
+        });
+        true
+    }
+
     pub fn set_show_gutter(&mut self, show_gutter: bool, cx: &mut ViewContext<Self>) {
         self.show_gutter = show_gutter;
         cx.notify();
@@ -8884,6 +9012,7 @@ impl Editor {
             } => {
                 self.refresh_active_diagnostics(cx);
                 self.refresh_code_actions(cx);
+                self.refresh_conflicts(cx);
                 if self.has_active_copilot_suggestion(cx) {
                     self.update_visible_copilot_suggestion(cx);
                 }
@@ -8934,9 +9063,11 @@ impl Editor {
                     excerpts: excerpts.clone(),
                 });
                 self.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx);
+                self.refresh_conflicts(cx);
             }
             multi_buffer::Event::ExcerptsRemoved { ids } => {
                 self.refresh_inlay_hints(InlayHintRefreshReason::ExcerptsRemoved(ids.clone()), cx);
+                self.refresh_conflicts(cx);
                 cx.emit(EditorEvent::ExcerptsRemoved { ids: ids.clone() })
             }
             multi_buffer::Event::Reparsed => cx.emit(EditorEvent::Reparsed),
@@ -9024,7 +9155,7 @@ impl Editor {
                 pane.update(cx, |pane, _| pane.disable_history());
 
                 for (buffer, ranges) in new_selections_by_buffer.into_iter() {
-                    let editor = workspace.open_project_item::<Self>(buffer, cx);
+                    let editor = workspace.open_project_item::<Self>(buffer, false, cx);
                     editor.update(cx, |editor, cx| {
                         editor.change_selections(Some(Autoscroll::newest()), cx, |s| {
                             s.select_ranges(ranges);
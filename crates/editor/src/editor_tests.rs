@@ -7338,6 +7338,86 @@ fn test_split_words() {
     assert_eq!(split("helloworld"), &["helloworld"]);
 }
 
+#[gpui::test]
+async fn test_merge_conflicts(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state(
+        &r#"ˇone
+        <<<<<<< HEAD
+        two
+        =======
+        2
+        >>>>>>> feature
+        three
+        <<<<<<< HEAD
+        four
+        =======
+        4
+        >>>>>>> feature
+        "#
+        .unindent(),
+    );
+    cx.executor().advance_clock(CONFLICTS_DEBOUNCE_TIMEOUT);
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| {
+        assert_eq!(editor.conflicts().len(), 2);
+        editor.go_to_conflict(&GoToConflict, cx);
+    });
+    cx.assert_editor_state(
+        &r#"one
+        ˇ<<<<<<< HEAD
+        two
+        =======
+        2
+        >>>>>>> feature
+        three
+        <<<<<<< HEAD
+        four
+        =======
+        4
+        >>>>>>> feature
+        "#
+        .unindent(),
+    );
+
+    cx.update_editor(|editor, cx| editor.accept_conflict_theirs(&AcceptConflictTheirs, cx));
+    cx.executor().advance_clock(CONFLICTS_DEBOUNCE_TIMEOUT);
+    cx.run_until_parked();
+    cx.assert_editor_state(
+        &r#"one
+        ˇ2
+        three
+        <<<<<<< HEAD
+        four
+        =======
+        4
+        >>>>>>> feature
+        "#
+        .unindent(),
+    );
+
+    // Going back wraps around to the last conflict.
+    cx.update_editor(|editor, cx| {
+        assert_eq!(editor.conflicts().len(), 1);
+        editor.go_to_prev_conflict(&GoToPrevConflict, cx);
+        editor.accept_conflict_both(&AcceptConflictBoth, cx);
+    });
+    cx.executor().advance_clock(CONFLICTS_DEBOUNCE_TIMEOUT);
+    cx.run_until_parked();
+    cx.assert_editor_state(
+        &r#"one
+        2
+        three
+        ˇfour
+        4
+        "#
+        .unindent(),
+    );
+    cx.update_editor(|editor, _| assert!(editor.conflicts().is_empty()));
+}
+
 #[gpui::test]
 async fn test_move_to_enclosing_bracket(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -258,6 +258,11 @@ impl EditorElement {
         register_action(view, cx, Editor::go_to_prev_diagnostic);
         register_action(view, cx, Editor::go_to_hunk);
         register_action(view, cx, Editor::go_to_prev_hunk);
+        register_action(view, cx, Editor::go_to_conflict);
+        register_action(view, cx, Editor::go_to_prev_conflict);
+        register_action(view, cx, Editor::accept_conflict_ours);
+        register_action(view, cx, Editor::accept_conflict_theirs);
+        register_action(view, cx, Editor::accept_conflict_both);
         register_action(view, cx, Editor::go_to_definition);
         register_action(view, cx, Editor::go_to_definition_split);
         register_action(view, cx, Editor::go_to_type_definition);
@@ -272,6 +277,7 @@ impl EditorElement {
         register_action(view, cx, Editor::toggle_code_actions);
         register_action(view, cx, Editor::open_excerpts);
         register_action(view, cx, Editor::toggle_soft_wrap);
+        register_action(view, cx, Editor::toggle_breakpoint);
         register_action(view, cx, Editor::toggle_inlay_hints);
         register_action(view, cx, hover_popover::hover);
         register_action(view, cx, Editor::reveal_in_finder);
@@ -394,6 +400,7 @@ impl EditorElement {
         position_map: &PositionMap,
         text_bounds: Bounds<Pixels>,
         gutter_bounds: Bounds<Pixels>,
+        gutter_padding: Pixels,
         stacking_order: &StackingOrder,
         cx: &mut ViewContext<Editor>,
     ) {
@@ -412,6 +419,17 @@ impl EditorElement {
         }
 
         let point_for_position = position_map.point_for_position(text_bounds, event.position);
+        // Clicking the space left of the line numbers toggles a breakpoint.
+        if event.position.x < gutter_bounds.origin.x + gutter_padding {
+            let row = point_for_position
+                .previous_valid
+                .to_point(&position_map.snapshot.display_snapshot)
+                .row;
+            if editor.toggle_breakpoint_at_row(row, cx) {
+                cx.stop_propagation();
+                return;
+            }
+        }
         let position = point_for_position.previous_valid;
         if modifiers.shift && modifiers.alt {
             editor.select(
@@ -735,6 +753,26 @@ impl EditorElement {
             }
         }
 
+        let breakpoint_diameter = line_height * 0.5;
+        for row in &layout.breakpoint_rows {
+            let origin = bounds.origin
+                + point(
+                    (layout.gutter_padding - breakpoint_diameter) / 2.,
+                    *row as f32 * line_height - scroll_top
+                        + (line_height - breakpoint_diameter) / 2.,
+                );
+            cx.paint_quad(quad(
+                Bounds {
+                    origin,
+                    size: size(breakpoint_diameter, breakpoint_diameter),
+                },
+                Corners::all(breakpoint_diameter / 2.),
+                cx.theme().status().error,
+                Edges::default(),
+                transparent_black(),
+            ));
+        }
+
         cx.with_z_index(1, |cx| {
             for (ix, fold_indicator) in layout.fold_indicators.drain(..).enumerate() {
                 if let Some(fold_indicator) = fold_indicator {
@@ -775,6 +813,23 @@ impl EditorElement {
 
                 button.draw(bounds.origin + point(x, y), available_space, cx);
             }
+
+            for indicator in layout.run_indicators.drain(..) {
+                let mut button = indicator.button.into_any_element();
+                let available_space = size(
+                    AvailableSpace::MinContent,
+                    AvailableSpace::Definite(line_height),
+                );
+                let indicator_size = button.measure(available_space, cx);
+
+                let mut x = Pixels::ZERO;
+                let mut y = indicator.row as f32 * line_height - scroll_top;
+                // Center indicator.
+                x += ((layout.gutter_padding + layout.gutter_margin) - indicator_size.width) / 2.;
+                y += (line_height - indicator_size.height) / 2.;
+
+                button.draw(bounds.origin + point(x, y), available_space, cx);
+            }
         });
     }
 
@@ -1754,6 +1809,44 @@ impl EditorElement {
             .collect()
     }
 
+    /// Returns the display rows showing runnables, along with the buffer row of each.
+    fn layout_run_indicators(
+        &self,
+        display_rows: Range<u32>,
+        runnable_rows: &[u32],
+        snapshot: &EditorSnapshot,
+    ) -> Vec<(u32, u32)> {
+        let buffer_start = DisplayPoint::new(display_rows.start, 0).to_point(snapshot);
+        let buffer_end = DisplayPoint::new(display_rows.end, 0).to_point(snapshot);
+        runnable_rows
+            .iter()
+            .filter(|buffer_row| (buffer_start.row..=buffer_end.row).contains(buffer_row))
+            .map(|&buffer_row| {
+                let display_row = Point::new(buffer_row, 0).to_display_point(snapshot).row();
+                (display_row, buffer_row)
+            })
+            .filter(|(display_row, _)| display_rows.contains(display_row))
+            .dedup_by(|(a, _), (b, _)| a == b)
+            .collect()
+    }
+
+    /// Returns the display rows showing the breakpoints of the edited file.
+    fn layout_breakpoints(
+        &self,
+        display_rows: Range<u32>,
+        breakpoint_rows: Vec<u32>,
+        snapshot: &EditorSnapshot,
+    ) -> Vec<u32> {
+        let max_row = snapshot.buffer_snapshot.max_point().row;
+        breakpoint_rows
+            .into_iter()
+            .filter(|row| *row <= max_row)
+            .map(|row| Point::new(row, 0).to_display_point(snapshot).row())
+            .filter(|display_row| display_rows.contains(display_row))
+            .dedup()
+            .collect()
+    }
+
     fn calculate_relative_line_numbers(
         &self,
         snapshot: &EditorSnapshot,
@@ -2279,6 +2372,32 @@ impl EditorElement {
                 }
             }
 
+            let run_indicators = if let Some((_, _, buffer_snapshot)) =
+                snapshot.buffer_snapshot.as_singleton()
+            {
+                let runnable_rows = editor.runnable_rows(buffer_snapshot);
+                self.layout_run_indicators(start_row..end_row, runnable_rows, &snapshot)
+                    .into_iter()
+                    .filter(|(display_row, _)| {
+                        code_actions_indicator
+                            .as_ref()
+                            .map_or(true, |indicator| indicator.row != *display_row)
+                    })
+                    .map(|(display_row, buffer_row)| RunIndicator {
+                        row: display_row,
+                        button: editor.render_run_indicator(buffer_row, cx),
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            let breakpoint_rows = if is_singleton {
+                self.layout_breakpoints(start_row..end_row, editor.breakpoint_rows(cx), &snapshot)
+            } else {
+                Vec::new()
+            };
+
             let visible_rows = start_row..start_row + line_layouts.len() as u32;
             let max_size = size(
                 (120. * em_width) // Default size
@@ -2385,6 +2504,8 @@ impl EditorElement {
                 selections,
                 context_menu,
                 code_actions_indicator,
+                run_indicators,
+                breakpoint_rows,
                 fold_indicators,
                 tab_invisible,
                 space_invisible,
@@ -2717,6 +2838,7 @@ impl EditorElement {
             let editor = self.editor.clone();
             let stacking_order = cx.stacking_order().clone();
             let interactive_bounds = interactive_bounds.clone();
+            let gutter_padding = layout.gutter_padding;
 
             move |event: &MouseDownEvent, phase, cx| {
                 if phase == DispatchPhase::Bubble
@@ -2730,6 +2852,7 @@ impl EditorElement {
                                 &position_map,
                                 text_bounds,
                                 gutter_bounds,
+                                gutter_padding,
                                 &stacking_order,
                                 cx,
                             );
@@ -3172,6 +3295,8 @@ pub struct LayoutState {
     max_row: u32,
     context_menu: Option<(DisplayPoint, AnyElement)>,
     code_actions_indicator: Option<CodeActionsIndicator>,
+    run_indicators: Vec<RunIndicator>,
+    breakpoint_rows: Vec<u32>,
     hover_popovers: Option<(DisplayPoint, Vec<AnyElement>)>,
     fold_indicators: Vec<Option<IconButton>>,
     tab_invisible: ShapedLine,
@@ -3189,6 +3314,11 @@ struct CodeActionsIndicator {
     button: IconButton,
 }
 
+struct RunIndicator {
+    row: u32,
+    button: IconButton,
+}
+
 struct PositionMap {
     size: Size<Pixels>,
     line_height: Pixels,
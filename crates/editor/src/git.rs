@@ -1,3 +1,4 @@
+pub mod conflicts;
 pub mod permalink;
 
 use std::ops::Range;
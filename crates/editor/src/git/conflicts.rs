@@ -0,0 +1,365 @@
+//! Highlighting and resolution of the regions left with conflict markers by git.
+
+use std::{ops::Range, sync::Arc};
+
+use ::git::conflict::{parse_conflicts, Conflict, Resolution};
+use collections::{HashMap, HashSet};
+use gpui::{HighlightStyle, Hsla, Task, ViewContext};
+use multi_buffer::{Anchor, MultiBufferSnapshot, ToOffset};
+use text::{BufferId, OffsetRangeExt};
+use ui::{prelude::*, ButtonStyle, Tooltip};
+
+use crate::{
+    display_map::{BlockContext, BlockDisposition, BlockId, BlockProperties, BlockStyle},
+    scroll::Autoscroll,
+    AcceptConflictBoth, AcceptConflictOurs, AcceptConflictTheirs, Editor, EditorMode, GoToConflict,
+    GoToPrevConflict, CONFLICTS_DEBOUNCE_TIMEOUT,
+};
+
+enum ConflictMarker {}
+enum ConflictOurs {}
+enum ConflictBase {}
+enum ConflictTheirs {}
+
+/// The conflicts of an editor's buffers, with the blocks offering to resolve them.
+#[derive(Default)]
+pub(crate) struct MergeConflicts {
+    conflicts: Vec<EditorConflict>,
+    blocks: HashSet<BlockId>,
+    parsed: ParsedConflicts,
+    refresh_task: Option<Task<()>>,
+}
+
+/// The conflicts parsed in each buffer, along with the version they were parsed at.
+type ParsedConflicts = HashMap<BufferId, (clock::Global, Arc<[Conflict]>)>;
+
+/// A [`Conflict`] of one of the editor's excerpts, anchored in the multibuffer.
+#[derive(Clone, Debug)]
+pub struct EditorConflict {
+    pub range: Range<Anchor>,
+    pub ours: Range<Anchor>,
+    pub base: Option<Range<Anchor>>,
+    pub theirs: Range<Anchor>,
+    pub ours_label: SharedString,
+    pub theirs_label: SharedString,
+}
+
+impl EditorConflict {
+    fn new(
+        conflict: &Conflict,
+        excerpt_id: multi_buffer::ExcerptId,
+        snapshot: &MultiBufferSnapshot,
+    ) -> Self {
+        let anchor_range = |range: &Range<text::Anchor>| {
+            snapshot.anchor_in_excerpt(excerpt_id, range.start)
+                ..snapshot.anchor_in_excerpt(excerpt_id, range.end)
+        };
+        Self {
+            range: anchor_range(&conflict.range),
+            ours: anchor_range(&conflict.ours),
+            base: conflict.base.as_ref().map(anchor_range),
+            theirs: anchor_range(&conflict.theirs),
+            ours_label: conflict.ours_label.clone().into(),
+            theirs_label: conflict.theirs_label.clone().into(),
+        }
+    }
+
+    fn resolved_text(&self, resolution: Resolution, snapshot: &MultiBufferSnapshot) -> String {
+        let text =
+            |range: &Range<Anchor>| snapshot.text_for_range(range.clone()).collect::<String>();
+        match resolution {
+            Resolution::Ours => text(&self.ours),
+            Resolution::Theirs => text(&self.theirs),
+            Resolution::Both => text(&self.ours) + &text(&self.theirs),
+        }
+    }
+
+    /// The ranges of the marker lines, which are all the lines that aren't ours, theirs
+    /// or the base.
+    fn marker_ranges(&self) -> Vec<Range<Anchor>> {
+        let mut ranges = vec![self.range.start..self.ours.start];
+        match &self.base {
+            Some(base) => {
+                ranges.push(self.ours.end..base.start);
+                ranges.push(base.end..self.theirs.start);
+            }
+            None => ranges.push(self.ours.end..self.theirs.start),
+        }
+        ranges.push(self.theirs.end..self.range.end);
+        ranges
+    }
+}
+
+/// Finds the conflicts shown in each excerpt, parsing only the buffers that changed
+/// since they were last parsed. Returns the conflicts parsed in each buffer.
+fn find_conflicts(
+    snapshot: &MultiBufferSnapshot,
+    mut old_parsed: ParsedConflicts,
+) -> (Vec<EditorConflict>, ParsedConflicts) {
+    let mut parsed = HashMap::default();
+    let mut conflicts = Vec::new();
+    for (excerpt_id, buffer, excerpt_range) in snapshot.excerpts() {
+        let excerpt_range = excerpt_range.context.to_offset(buffer);
+        let (_, buffer_conflicts) = parsed.entry(buffer.remote_id()).or_insert_with(|| {
+            old_parsed
+                .remove(&buffer.remote_id())
+                .filter(|(version, _)| version == buffer.version())
+                .unwrap_or_else(|| (buffer.version().clone(), parse_conflicts(buffer).into()))
+        });
+        conflicts.extend(
+            buffer_conflicts
+                .iter()
+                .filter(|conflict| {
+                    let range = conflict.range.to_offset(buffer);
+                    excerpt_range.start <= range.start && range.end <= excerpt_range.end
+                })
+                .map(|conflict| EditorConflict::new(conflict, excerpt_id, snapshot)),
+        );
+    }
+    (conflicts, parsed)
+}
+
+impl Editor {
+    pub fn conflicts(&self) -> &[EditorConflict] {
+        &self.merge_conflicts.conflicts
+    }
+
+    /// Parses the conflicts of the editor's buffers in the background, once they stop
+    /// changing.
+    pub(crate) fn refresh_conflicts(&mut self, cx: &mut ViewContext<Self>) {
+        if self.mode != EditorMode::Full || self.is_large_file(cx) {
+            return;
+        }
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let parsed = self.merge_conflicts.parsed.clone();
+        self.merge_conflicts.refresh_task = Some(cx.spawn(|this, mut cx| async move {
+            cx.background_executor()
+                .timer(CONFLICTS_DEBOUNCE_TIMEOUT)
+                .await;
+            let (conflicts, parsed) = cx
+                .background_executor()
+                .spawn(async move { find_conflicts(&snapshot, parsed) })
+                .await;
+            this.update(&mut cx, |this, cx| {
+                this.merge_conflicts.parsed = parsed;
+                this.set_conflicts(conflicts, cx);
+            })
+            .ok();
+        }));
+    }
+
+    fn set_conflicts(&mut self, conflicts: Vec<EditorConflict>, cx: &mut ViewContext<Self>) {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let status = cx.theme().status().clone();
+        let background = |mut color: Hsla| {
+            color.fade_out(0.5);
+            HighlightStyle {
+                background_color: Some(color),
+                ..Default::default()
+            }
+        };
+        self.highlight_text::<ConflictMarker>(
+            conflicts
+                .iter()
+                .flat_map(EditorConflict::marker_ranges)
+                .collect(),
+            background(status.conflict_background),
+            cx,
+        );
+        self.highlight_text::<ConflictOurs>(
+            conflicts
+                .iter()
+                .map(|conflict| conflict.ours.clone())
+                .collect(),
+            background(status.created_background),
+            cx,
+        );
+        self.highlight_text::<ConflictBase>(
+            conflicts
+                .iter()
+                .filter_map(|conflict| conflict.base.clone())
+                .collect(),
+            background(status.hidden_background),
+            cx,
+        );
+        self.highlight_text::<ConflictTheirs>(
+            conflicts
+                .iter()
+                .map(|conflict| conflict.theirs.clone())
+                .collect(),
+            background(status.info_background),
+            cx,
+        );
+
+        // Edits within conflicts keep the blocks where they are, only changes to the
+        // conflicts themselves replacing them.
+        let key = |conflicts: &[EditorConflict]| {
+            conflicts
+                .iter()
+                .map(|conflict| {
+                    (
+                        conflict.range.start.to_offset(&snapshot),
+                        conflict.ours_label.clone(),
+                        conflict.theirs_label.clone(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        if key(&conflicts) != key(&self.merge_conflicts.conflicts) {
+            let old_blocks = std::mem::take(&mut self.merge_conflicts.blocks);
+            self.remove_blocks(old_blocks, None, cx);
+            let editor = cx.view().downgrade();
+            let blocks = conflicts.iter().map(|conflict| BlockProperties {
+                position: conflict.range.start,
+                height: 1,
+                style: BlockStyle::Fixed,
+                render: render_conflict_actions(editor.clone(), conflict.clone()),
+                disposition: BlockDisposition::Above,
+            });
+            self.merge_conflicts.blocks =
+                self.insert_blocks(blocks, None, cx).into_iter().collect();
+        }
+        self.merge_conflicts.conflicts = conflicts;
+        cx.notify();
+    }
+
+    /// Resolves the conflicts containing a selection, or the one starting at the given
+    /// position.
+    fn resolve_conflicts(
+        &mut self,
+        position: Option<Anchor>,
+        resolution: Resolution,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let selections = self.selections.all::<usize>(cx);
+        let edits = self
+            .merge_conflicts
+            .conflicts
+            .iter()
+            .filter(|conflict| {
+                let range = conflict.range.to_offset(&snapshot);
+                match position {
+                    Some(position) => range.start == position.to_offset(&snapshot),
+                    None => selections.iter().any(|selection| {
+                        range.start <= selection.end && selection.start < range.end
+                    }),
+                }
+            })
+            .map(|conflict| {
+                (
+                    conflict.range.clone(),
+                    conflict.resolved_text(resolution, &snapshot),
+                )
+            })
+            .collect::<Vec<_>>();
+        if !edits.is_empty() {
+            self.transact(cx, |this, cx| this.edit(edits, cx));
+        }
+    }
+
+    pub fn accept_conflict_ours(&mut self, _: &AcceptConflictOurs, cx: &mut ViewContext<Self>) {
+        self.resolve_conflicts(None, Resolution::Ours, cx);
+    }
+
+    pub fn accept_conflict_theirs(&mut self, _: &AcceptConflictTheirs, cx: &mut ViewContext<Self>) {
+        self.resolve_conflicts(None, Resolution::Theirs, cx);
+    }
+
+    pub fn accept_conflict_both(&mut self, _: &AcceptConflictBoth, cx: &mut ViewContext<Self>) {
+        self.resolve_conflicts(None, Resolution::Both, cx);
+    }
+
+    pub fn go_to_conflict(&mut self, _: &GoToConflict, cx: &mut ViewContext<Self>) {
+        self.go_to_conflict_in_direction(false, cx);
+    }
+
+    pub fn go_to_prev_conflict(&mut self, _: &GoToPrevConflict, cx: &mut ViewContext<Self>) {
+        self.go_to_conflict_in_direction(true, cx);
+    }
+
+    /// Moves the cursor to the start of the next or previous conflict, wrapping around.
+    fn go_to_conflict_in_direction(&mut self, reversed: bool, cx: &mut ViewContext<Self>) {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let head = self.selections.newest::<usize>(cx).head();
+        let starts = self
+            .merge_conflicts
+            .conflicts
+            .iter()
+            .map(|conflict| conflict.range.start.to_offset(&snapshot))
+            .collect::<Vec<_>>();
+        let target = if reversed {
+            starts
+                .iter()
+                .rev()
+                .find(|start| **start < head)
+                .or(starts.last())
+        } else {
+            starts
+                .iter()
+                .find(|start| **start > head)
+                .or(starts.first())
+        };
+        if let Some(&target) = target {
+            self.change_selections(Some(Autoscroll::fit()), cx, |selections| {
+                selections.select_ranges([target..target])
+            });
+        }
+    }
+}
+
+fn render_conflict_actions(
+    editor: gpui::WeakView<Editor>,
+    conflict: EditorConflict,
+) -> Arc<dyn Fn(&mut BlockContext) -> AnyElement> {
+    Arc::new(move |cx: &mut BlockContext| {
+        let button = |id: &'static str, label: SharedString, resolution: Resolution| {
+            let editor = editor.clone();
+            let position = conflict.range.start;
+            Button::new((id, cx.block_id), label)
+                .style(ButtonStyle::Subtle)
+                .label_size(LabelSize::Small)
+                .on_click(move |_, cx| {
+                    editor
+                        .update(cx, |editor, cx| {
+                            editor.resolve_conflicts(Some(position), resolution, cx)
+                        })
+                        .ok();
+                })
+        };
+        let label = |prefix: &str, name: &SharedString| -> SharedString {
+            if name.is_empty() {
+                prefix.to_string().into()
+            } else {
+                format!("{prefix} ({name})").into()
+            }
+        };
+
+        h_flex()
+            .id(cx.block_id)
+            .h(cx.line_height)
+            .pl(cx.anchor_x)
+            .gap_1()
+            .child(
+                button(
+                    "accept-ours",
+                    label("Accept Ours", &conflict.ours_label),
+                    Resolution::Ours,
+                )
+                .tooltip(|cx| Tooltip::for_action("Accept Ours", &AcceptConflictOurs, cx)),
+            )
+            .child(
+                button(
+                    "accept-theirs",
+                    label("Accept Theirs", &conflict.theirs_label),
+                    Resolution::Theirs,
+                )
+                .tooltip(|cx| Tooltip::for_action("Accept Theirs", &AcceptConflictTheirs, cx)),
+            )
+            .child(
+                button("accept-both", "Accept Both".into(), Resolution::Both)
+                    .tooltip(|cx| Tooltip::for_action("Accept Both", &AcceptConflictBoth, cx)),
+            )
+            .into_any_element()
+    })
+}
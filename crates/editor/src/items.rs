@@ -1,9 +1,9 @@
 use crate::{
-    editor_settings::SeedQuerySetting, persistence::DB, scroll::ScrollAnchor, Anchor, Autoscroll,
-    Editor, EditorEvent, EditorSettings, ExcerptId, ExcerptRange, MultiBuffer, MultiBufferSnapshot,
-    NavigationData, ToPoint as _,
+    editor_settings::SeedQuerySetting, persistence::DB, scroll::ScrollAnchor, Anchor,
+    AnchorRangeExt as _, Autoscroll, Editor, EditorEvent, EditorSettings, ExcerptId, ExcerptRange,
+    MultiBuffer, MultiBufferSnapshot, NavigationData, ToPoint as _,
 };
-use anyhow::{anyhow, Context as _, Result};
+use anyhow::{Context as _, Result};
 use collections::HashSet;
 use futures::future::try_join_all;
 use gpui::{
@@ -12,23 +12,25 @@ use gpui::{
     Subscription, Task, View, ViewContext, VisualContext, WeakView, WindowContext,
 };
 use language::{
-    proto::serialize_anchor as serialize_text_anchor, Bias, Buffer, CharKind, OffsetRangeExt,
-    Point, SelectionGoal,
+    proto::{serialize_anchor as serialize_text_anchor, serialize_fingerprint},
+    Bias, Buffer, CharKind, OffsetRangeExt, Point, SelectionGoal,
 };
 use project::repository::GitFileStatus;
 use project::{search::SearchQuery, FormatTrigger, Item as _, Project, ProjectPath};
 use rpc::proto::{self, update_view, PeerId};
 use settings::Settings;
-use workspace::item::ItemSettings;
+use workspace::{item::ItemSettings, WorkspaceSettings};
 
 use std::fmt::Write;
 use std::{
+    any::TypeId,
     borrow::Cow,
     cmp::{self, Ordering},
     iter,
     ops::Range,
     path::{Path, PathBuf},
     sync::Arc,
+    time::{Duration, SystemTime},
 };
 use text::{BufferId, Selection};
 use theme::Theme;
@@ -40,7 +42,7 @@ use workspace::{
 };
 use workspace::{
     item::{FollowableItem, Item, ItemEvent, ItemHandle, ProjectItem},
-    searchable::{Direction, SearchEvent, SearchableItem, SearchableItemHandle},
+    searchable::{Direction, SearchEvent, SearchOptions, SearchableItem, SearchableItemHandle},
     ItemId, ItemNavHistory, Pane, ToolbarItemLocation, ViewId, Workspace, WorkspaceId,
 };
 
@@ -685,6 +687,23 @@ impl Item for Editor {
         self.hide_hovered_link(cx);
     }
 
+    fn closed(&mut self, cx: &mut ViewContext<Self>) {
+        // A closed editor is never deserialized again, so its backup would be
+        // orphaned, and its changes were either saved or discarded.
+        self.unsaved_backup_task.take();
+        if let Some((_, workspace_id)) = self.workspace.as_ref() {
+            let workspace_id = *workspace_id;
+            let item_id = cx.view().item_id().as_u64() as ItemId;
+            cx.background_executor()
+                .spawn(async move {
+                    DB.delete_unsaved_buffer(item_id, workspace_id)
+                        .await
+                        .log_err();
+                })
+                .detach();
+        }
+    }
+
     fn is_dirty(&self, cx: &AppContext) -> bool {
         self.buffer().read(cx).read(cx).is_dirty()
     }
@@ -713,25 +732,38 @@ impl Item for Editor {
 
             if buffers.len() == 1 {
                 project
-                    .update(&mut cx, |project, cx| project.save_buffers(buffers, cx))?
+                    .update(&mut cx, |project, cx| {
+                        project.save_buffers(buffers.clone(), cx)
+                    })?
                     .await?;
+                cx.update(|cx| {
+                    for buffer in &buffers {
+                        save_undo_history(buffer, cx);
+                    }
+                })?;
             } else {
                 // For multi-buffers, only save those ones that contain changes. For clean buffers
                 // we simulate saving by calling `Buffer::did_save`, so that language servers or
                 // other downstream listeners of save events get notified.
-                let (dirty_buffers, clean_buffers) = buffers.into_iter().partition(|buffer| {
-                    buffer
-                        .update(&mut cx, |buffer, _| {
-                            buffer.is_dirty() || buffer.has_conflict()
-                        })
-                        .unwrap_or(false)
-                });
+                let (dirty_buffers, clean_buffers): (HashSet<_>, HashSet<_>) =
+                    buffers.into_iter().partition(|buffer| {
+                        buffer
+                            .update(&mut cx, |buffer, _| {
+                                buffer.is_dirty() || buffer.has_conflict()
+                            })
+                            .unwrap_or(false)
+                    });
 
                 project
                     .update(&mut cx, |project, cx| {
-                        project.save_buffers(dirty_buffers, cx)
+                        project.save_buffers(dirty_buffers.clone(), cx)
                     })?
                     .await?;
+                cx.update(|cx| {
+                    for buffer in &dirty_buffers {
+                        save_undo_history(buffer, cx);
+                    }
+                })?;
                 for buffer in clean_buffers {
                     buffer
                         .update(&mut cx, |buffer, cx| {
@@ -793,6 +825,11 @@ impl Item for Editor {
         })
     }
 
+    fn backup_unsaved_changes(&mut self, cx: &mut ViewContext<Self>) -> Option<Task<Result<()>>> {
+        self.unsaved_backup_task.take();
+        self.backup_unsaved_buffer(None, cx)
+    }
+
     fn as_searchable(&self, handle: &View<Self>) -> Option<Box<dyn SearchableItemHandle>> {
         Some(Box::new(handle.clone()))
     }
@@ -866,16 +903,21 @@ impl Item for Editor {
 
         if let Some(buffer) = self.buffer().read(cx).as_singleton() {
             serialize(buffer.clone(), workspace_id, item_id, cx);
+            self.schedule_unsaved_backup(cx);
 
             cx.subscribe(&buffer, |this, buffer, event, cx| {
                 if let Some((_, workspace_id)) = this.workspace.as_ref() {
-                    if let language::Event::FileHandleChanged = event {
-                        serialize(
+                    match event {
+                        language::Event::FileHandleChanged => serialize(
                             buffer,
                             *workspace_id,
                             cx.view().item_id().as_u64() as ItemId,
                             cx,
-                        );
+                        ),
+                        language::Event::Edited
+                        | language::Event::DirtyChanged
+                        | language::Event::LanguageChanged => this.schedule_unsaved_backup(cx),
+                        _ => {}
                     }
                 }
             })
@@ -928,11 +970,20 @@ impl Item for Editor {
         item_id: ItemId,
         cx: &mut ViewContext<Pane>,
     ) -> Task<Result<View<Self>>> {
+        let unsaved_buffer = if WorkspaceSettings::get_global(cx).restore_unsaved_buffers {
+            DB.get_unsaved_buffer(item_id, workspace_id)
+                .log_err()
+                .flatten()
+        } else {
+            None
+        };
         let project_item: Result<_> = project.update(cx, |project, cx| {
             // Look up the path with this key associated, create a self with that path
-            let path = DB
-                .get_path(item_id, workspace_id)?
-                .context("No path stored for this editor")?;
+            let Some(path) = DB.get_path(item_id, workspace_id)? else {
+                // Untitled buffers are only restored if they had unsaved contents.
+                anyhow::ensure!(unsaved_buffer.is_some(), "No path stored for this editor");
+                return Ok(Task::ready(project.create_buffer("", None, cx)));
+            };
 
             let (worktree, path) = project
                 .find_local_worktree(&path, cx)
@@ -942,16 +993,24 @@ impl Item for Editor {
                 path: path.into(),
             };
 
-            Ok(project.open_path(project_path, cx))
+            Ok(project.open_buffer(project_path, cx))
         });
 
         project_item
             .map(|project_item| {
                 cx.spawn(|pane, mut cx| async move {
-                    let (_, project_item) = project_item.await?;
-                    let buffer = project_item
-                        .downcast::<Buffer>()
-                        .map_err(|_| anyhow!("Project item at stored path was not a buffer"))?;
+                    let buffer = project_item.await?;
+                    if let Some((contents, language, mtime_seconds, mtime_nanos)) = unsaved_buffer {
+                        let mtime = mtime_seconds.zip(mtime_nanos).and_then(|(seconds, nanos)| {
+                            let since_epoch =
+                                Duration::new(seconds.try_into().ok()?, nanos.try_into().ok()?);
+                            SystemTime::UNIX_EPOCH.checked_add(since_epoch)
+                        });
+                        restore_unsaved_buffer(
+                            &project, &buffer, contents, language, mtime, &mut cx,
+                        )
+                        .await?;
+                    }
                     Ok(pane.update(&mut cx, |_, cx| {
                         cx.new_view(|cx| {
                             let mut editor = Editor::for_buffer(buffer, Some(project), cx);
@@ -974,20 +1033,245 @@ impl ProjectItem for Editor {
         buffer: Model<Buffer>,
         cx: &mut ViewContext<Self>,
     ) -> Self {
+        restore_undo_history(&buffer, cx);
         Self::for_buffer(buffer, Some(project), cx)
     }
 }
 
+/// How long an editor's buffer goes without edits before its unsaved changes
+/// are backed up.
+const UNSAVED_BACKUP_DEBOUNCE: Duration = Duration::from_secs(1);
+
+impl Editor {
+    /// Backs up the unsaved changes of the editor's buffer once it stops being
+    /// edited, or removes the backup once the buffer is saved.
+    fn schedule_unsaved_backup(&mut self, cx: &mut ViewContext<Self>) {
+        self.unsaved_backup_task = self
+            .backup_unsaved_buffer(Some(UNSAVED_BACKUP_DEBOUNCE), cx)
+            .map(|backup| {
+                cx.background_executor().spawn(async move {
+                    backup.await.log_err();
+                })
+            });
+    }
+
+    fn backup_unsaved_buffer(
+        &self,
+        delay: Option<Duration>,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<Task<Result<()>>> {
+        if !WorkspaceSettings::get_global(cx).restore_unsaved_buffers {
+            return None;
+        }
+        let (_, workspace_id) = self.workspace.as_ref()?;
+        let workspace_id = *workspace_id;
+        let item_id = cx.view().item_id().as_u64() as ItemId;
+        if !self.project.as_ref()?.read(cx).is_local() {
+            return None;
+        }
+        let buffer = self.buffer().read(cx).as_singleton()?;
+        let buffer = buffer.read(cx);
+        if buffer.is_large_file() {
+            return None;
+        }
+
+        let unsaved = buffer.is_dirty().then(|| {
+            let mtime = buffer
+                .file()
+                .and_then(|_| {
+                    buffer
+                        .saved_mtime()
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .ok()
+                })
+                .map(|mtime| (mtime.as_secs() as i64, mtime.subsec_nanos() as i64));
+            (
+                buffer.text_snapshot(),
+                buffer
+                    .language()
+                    .map(|language| language.name().to_string()),
+                mtime,
+            )
+        });
+        let executor = cx.background_executor().clone();
+        Some(cx.background_executor().spawn(async move {
+            if let Some(delay) = delay {
+                executor.timer(delay).await;
+            }
+            match unsaved {
+                Some((snapshot, language, mtime)) => {
+                    DB.save_unsaved_buffer(
+                        item_id,
+                        workspace_id,
+                        snapshot.text(),
+                        language,
+                        mtime.map(|(seconds, _)| seconds),
+                        mtime.map(|(_, nanos)| nanos),
+                    )
+                    .await
+                }
+                None => DB.delete_unsaved_buffer(item_id, workspace_id).await,
+            }
+        }))
+    }
+}
+
+/// Replaces the contents of a deserialized editor's buffer with the unsaved
+/// contents it had, leaving the buffer dirty.
+///
+/// The contents aren't restored if the buffer's file was modified on disk
+/// after they were backed up, since they'd silently revert that change.
+async fn restore_unsaved_buffer(
+    project: &Model<Project>,
+    buffer: &Model<Buffer>,
+    contents: String,
+    language: Option<String>,
+    backup_mtime: Option<SystemTime>,
+    cx: &mut AsyncWindowContext,
+) -> Result<()> {
+    let (is_untitled, saved_mtime) = buffer.read_with(cx, |buffer, _| {
+        (buffer.file().is_none(), buffer.saved_mtime())
+    })?;
+    if !is_untitled && backup_mtime != Some(saved_mtime) {
+        log::warn!("not restoring unsaved changes of a file that changed on disk");
+        return Ok(());
+    }
+    if let Some(language) = language.filter(|_| is_untitled) {
+        let language = project
+            .read_with(cx, |project, _| {
+                project.languages().language_for_name(&language)
+            })?
+            .await
+            .log_err();
+        if let Some(language) = language {
+            project.update(cx, |project, cx| {
+                project.set_language_for_buffer(buffer, language, cx)
+            })?;
+        }
+    }
+    buffer.update(cx, |buffer, cx| {
+        if buffer.text() != contents {
+            buffer.set_text(contents, cx);
+        }
+    })
+}
+
+fn save_undo_history(buffer: &Model<Buffer>, cx: &mut AppContext) {
+    let buffer = buffer.read(cx);
+    let Some(file) = buffer.file().and_then(|file| file.as_local()) else {
+        return;
+    };
+    // Large files' histories aren't kept, as hashing them blocks saving.
+    if buffer.is_large_file() || buffer.undo_tree().is_empty() {
+        return;
+    }
+
+    let path = file.abs_path(cx);
+    let content_hash = serialize_fingerprint(buffer.as_rope().fingerprint());
+    let undo_tree = buffer.undo_tree().clone();
+    cx.background_executor()
+        .spawn(async move {
+            DB.save_undo_tree(path, content_hash, undo_tree.serialize())
+                .await
+                .log_err()
+        })
+        .detach();
+}
+
+/// Restores the undo history that was saved the last time the buffer's file was edited,
+/// provided the file hasn't changed since.
+fn restore_undo_history(buffer: &Model<Buffer>, cx: &mut AppContext) {
+    let buffer_ref = buffer.read(cx);
+    let Some(file) = buffer_ref.file().and_then(|file| file.as_local()) else {
+        return;
+    };
+    if buffer_ref.is_large_file() || !buffer_ref.undo_tree().is_empty() || buffer_ref.is_dirty() {
+        return;
+    }
+
+    let path = file.abs_path(cx);
+    let fingerprint = buffer_ref.as_rope().fingerprint();
+    let content_hash = serialize_fingerprint(fingerprint);
+    let buffer = buffer.downgrade();
+    cx.spawn(|mut cx| async move {
+        let undo_tree = cx
+            .background_executor()
+            .spawn(async move { DB.get_undo_tree(&path, &content_hash) })
+            .await?;
+        if let Some(undo_tree) = undo_tree {
+            buffer.update(&mut cx, |buffer, cx| {
+                if buffer.undo_tree().is_empty() && buffer.as_rope().fingerprint() == fingerprint {
+                    buffer.restore_undo_tree(undo_tree, cx)
+                } else {
+                    Ok(())
+                }
+            })??;
+        }
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
 impl EventEmitter<SearchEvent> for Editor {}
 
 pub(crate) enum BufferSearchHighlights {}
+pub(crate) enum SearchWithinRange {}
+
 impl SearchableItem for Editor {
     type Match = Range<Anchor>;
 
+    fn supported_options() -> SearchOptions {
+        SearchOptions {
+            case: true,
+            word: true,
+            regex: true,
+            replacement: true,
+            selection: true,
+        }
+    }
+
     fn clear_matches(&mut self, cx: &mut ViewContext<Self>) {
         self.clear_background_highlights::<BufferSearchHighlights>(cx);
     }
 
+    fn toggle_filtered_search_ranges(&mut self, enabled: bool, cx: &mut ViewContext<Self>) {
+        if !enabled {
+            self.clear_background_highlights::<SearchWithinRange>(cx);
+            return;
+        }
+
+        let buffer = self.buffer().read(cx).snapshot(cx);
+        let ranges = self
+            .selections
+            .all_adjusted(cx)
+            .into_iter()
+            .filter(|selection| !selection.is_empty())
+            .map(|selection| {
+                buffer.anchor_before(selection.start)..buffer.anchor_after(selection.end)
+            })
+            .collect::<Vec<_>>();
+        if ranges.is_empty() {
+            self.clear_background_highlights::<SearchWithinRange>(cx);
+        } else {
+            self.highlight_background::<SearchWithinRange>(
+                ranges,
+                |theme| theme.editor_search_scope_background,
+                cx,
+            );
+        }
+    }
+
+    fn has_filtered_search_ranges(&mut self, cx: &mut ViewContext<Self>) -> bool {
+        let buffer = self.buffer().read(cx).snapshot(cx);
+        self.background_highlights
+            .get(&TypeId::of::<SearchWithinRange>())
+            .map_or(false, |(_, ranges)| {
+                ranges
+                    .iter()
+                    .any(|range| !range.to_offset(&buffer).is_empty())
+            })
+    }
+
     fn update_matches(&mut self, matches: Vec<Range<Anchor>>, cx: &mut ViewContext<Self>) {
         self.highlight_background::<BufferSearchHighlights>(
             matches,
@@ -1120,18 +1404,34 @@ impl SearchableItem for Editor {
         cx: &mut ViewContext<Self>,
     ) -> Task<Vec<Range<Anchor>>> {
         let buffer = self.buffer().read(cx).snapshot(cx);
+        let search_within_ranges = self
+            .background_highlights
+            .get(&TypeId::of::<SearchWithinRange>())
+            .map(|(_, ranges)| {
+                ranges
+                    .iter()
+                    .map(|range| range.to_offset(&buffer))
+                    .collect::<Vec<_>>()
+            })
+            .filter(|ranges| !ranges.is_empty());
         cx.background_executor().spawn(async move {
             let mut ranges = Vec::new();
             if let Some((_, _, excerpt_buffer)) = buffer.as_singleton() {
-                ranges.extend(
-                    query
-                        .search(excerpt_buffer, None)
-                        .await
-                        .into_iter()
-                        .map(|range| {
-                            buffer.anchor_after(range.start)..buffer.anchor_before(range.end)
-                        }),
-                );
+                let search_ranges = search_within_ranges
+                    .clone()
+                    .unwrap_or_else(|| vec![0..buffer.len()]);
+                for search_range in search_ranges {
+                    ranges.extend(
+                        query
+                            .search(excerpt_buffer, Some(search_range.clone()))
+                            .await
+                            .into_iter()
+                            .map(|range| {
+                                buffer.anchor_after(search_range.start + range.start)
+                                    ..buffer.anchor_before(search_range.start + range.end)
+                            }),
+                    );
+                }
             } else {
                 for excerpt in buffer.excerpt_boundaries_in_range(0..buffer.len()) {
                     let excerpt_range = excerpt.range.context.to_offset(&excerpt.buffer);
@@ -1152,6 +1452,14 @@ impl SearchableItem for Editor {
                             }),
                     );
                 }
+                if let Some(search_within_ranges) = search_within_ranges.as_ref() {
+                    ranges.retain(|range| {
+                        let range = range.to_offset(&buffer);
+                        search_within_ranges.iter().any(|search_range| {
+                            search_range.start <= range.start && range.end <= search_range.end
+                        })
+                    });
+                }
             }
             ranges
         })
@@ -0,0 +1,60 @@
+//! The banner shown at the top of editors for files opened in large file mode.
+
+use std::sync::Arc;
+
+use gpui::{AppContext, ViewContext};
+use multi_buffer::Anchor;
+use ui::{prelude::*, Icon, IconName, IconSize};
+
+use crate::{
+    display_map::{BlockContext, BlockDisposition, BlockProperties, BlockStyle},
+    Editor, EditorMode,
+};
+
+const LARGE_FILE_MESSAGE: &str = "This file is large, so syntax highlighting, language servers, \
+    soft wrap, inlay hints and git diffs are turned off.";
+
+impl Editor {
+    /// Whether the editor shows a single buffer that was opened in large file mode.
+    pub fn is_large_file(&self, cx: &AppContext) -> bool {
+        self.buffer
+            .read(cx)
+            .as_singleton()
+            .map_or(false, |buffer| buffer.read(cx).is_large_file())
+    }
+
+    /// Explains which features are off above the first line of a large file.
+    pub(crate) fn insert_large_file_banner(&mut self, cx: &mut ViewContext<Self>) {
+        if self.mode != EditorMode::Full || !self.is_large_file(cx) {
+            return;
+        }
+        self.insert_blocks(
+            [BlockProperties {
+                position: Anchor::min(),
+                height: 1,
+                style: BlockStyle::Sticky,
+                render: Arc::new(|cx: &mut BlockContext| {
+                    h_flex()
+                        .id(cx.block_id)
+                        .h(cx.line_height)
+                        .pl(cx.anchor_x)
+                        .gap_1()
+                        .child(
+                            Icon::new(IconName::ExclamationTriangle)
+                                .size(IconSize::Small)
+                                .color(Color::Warning),
+                        )
+                        .child(
+                            Label::new(LARGE_FILE_MESSAGE)
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                        .into_any_element()
+                }),
+                disposition: BlockDisposition::Above,
+            }],
+            None,
+            cx,
+        );
+    }
+}
@@ -1,10 +1,17 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use anyhow::Result;
 use db::sqlez_macros::sql;
 use db::{define_connection, query};
+use serde::{Deserialize, Serialize};
+use text::{SerializedUndoTree, SerializedUndoTreeNode, UndoTreeEdit};
 
 use workspace::{ItemId, WorkspaceDb, WorkspaceId};
 
+/// How many files' undo trees are kept.
+const MAX_UNDO_HISTORIES: usize = 500;
+
 define_connection!(
     // Current schema shape using pseudo-rust syntax:
     // editors(
@@ -15,6 +22,22 @@ define_connection!(
     //   scroll_vertical_offset: f32,
     //   scroll_horizontal_offset: f32,
     // )
+    //
+    // undo_histories(
+    //   path: PathBuf,
+    //   content_hash: String,
+    //   history: String, // JSON encoded UndoHistory
+    //   saved_at: i64, // milliseconds since the epoch
+    // )
+    //
+    // unsaved_buffers(
+    //   item_id: usize,
+    //   workspace_id: usize,
+    //   contents: String,
+    //   language: Option<String>,
+    //   mtime_seconds: Option<i64>,
+    //   mtime_nanos: Option<i64>,
+    // )
     pub static ref DB: EditorDb<WorkspaceDb> =
         &[sql! (
             CREATE TABLE editors(
@@ -31,6 +54,32 @@ define_connection!(
             ALTER TABLE editors ADD COLUMN scroll_top_row INTEGER NOT NULL DEFAULT 0;
             ALTER TABLE editors ADD COLUMN scroll_horizontal_offset REAL NOT NULL DEFAULT 0;
             ALTER TABLE editors ADD COLUMN scroll_vertical_offset REAL NOT NULL DEFAULT 0;
+        ),
+        sql! (
+            CREATE TABLE undo_histories(
+                path BLOB NOT NULL PRIMARY KEY,
+                content_hash TEXT NOT NULL,
+                history TEXT NOT NULL
+            ) STRICT;
+        ),
+        sql! (
+            CREATE TABLE unsaved_buffers(
+                item_id INTEGER NOT NULL,
+                workspace_id INTEGER NOT NULL,
+                contents TEXT NOT NULL,
+                language TEXT,
+                PRIMARY KEY(item_id, workspace_id),
+                FOREIGN KEY(workspace_id) REFERENCES workspaces(workspace_id)
+                ON DELETE CASCADE
+                ON UPDATE CASCADE
+            ) STRICT;
+        ),
+        sql! (
+            ALTER TABLE unsaved_buffers ADD COLUMN mtime_seconds INTEGER;
+            ALTER TABLE unsaved_buffers ADD COLUMN mtime_nanos INTEGER;
+        ),
+        sql! (
+            ALTER TABLE undo_histories ADD COLUMN saved_at INTEGER NOT NULL DEFAULT 0;
         )];
 );
 
@@ -80,4 +129,148 @@ impl EditorDb {
             WHERE item_id = ?1 AND workspace_id = ?2
         }
     }
+    // Returns the contents and language name of the editor's buffer, if it had
+    // unsaved changes, along with the mtime its file had when they were backed up
+    query! {
+        pub fn get_unsaved_buffer(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<(String, Option<String>, Option<i64>, Option<i64>)>> {
+            SELECT contents, language, mtime_seconds, mtime_nanos FROM unsaved_buffers
+            WHERE item_id = ? AND workspace_id = ?
+        }
+    }
+
+    query! {
+        pub async fn save_unsaved_buffer(
+            item_id: ItemId,
+            workspace_id: WorkspaceId,
+            contents: String,
+            language: Option<String>,
+            mtime_seconds: Option<i64>,
+            mtime_nanos: Option<i64>
+        ) -> Result<()> {
+            INSERT OR REPLACE INTO unsaved_buffers
+                (item_id, workspace_id, contents, language, mtime_seconds, mtime_nanos)
+            VALUES
+                (?1, ?2, ?3, ?4, ?5, ?6)
+        }
+    }
+
+    query! {
+        pub async fn delete_unsaved_buffer(item_id: ItemId, workspace_id: WorkspaceId) -> Result<()> {
+            DELETE FROM unsaved_buffers
+            WHERE item_id = ? AND workspace_id = ?
+        }
+    }
+
+    query! {
+        fn get_undo_history(path: &Path, content_hash: &str) -> Result<Option<String>> {
+            SELECT history FROM undo_histories
+            WHERE path = ? AND content_hash = ?
+        }
+    }
+
+    query! {
+        async fn save_undo_history(path: PathBuf, content_hash: String, history: String, saved_at: i64) -> Result<()> {
+            INSERT OR REPLACE INTO undo_histories
+                (path, content_hash, history, saved_at)
+            VALUES
+                (?1, ?2, ?3, ?4)
+        }
+    }
+
+    query! {
+        async fn prune_undo_histories(max_histories: i64) -> Result<()> {
+            DELETE FROM undo_histories
+            WHERE path NOT IN (
+                SELECT path FROM undo_histories
+                ORDER BY saved_at DESC
+                LIMIT ?
+            )
+        }
+    }
+
+    /// Returns the undo tree saved for the file at the given path, if the file still has
+    /// the contents it had when the tree was saved.
+    pub fn get_undo_tree(
+        &self,
+        path: &Path,
+        content_hash: &str,
+    ) -> Result<Option<SerializedUndoTree>> {
+        let Some(history) = self.get_undo_history(path, content_hash)? else {
+            return Ok(None);
+        };
+        let history: UndoHistory = serde_json::from_str(&history)?;
+        Ok(Some(history.into()))
+    }
+
+    /// Saves the undo tree of the file at the given path, dropping the trees of
+    /// the files that were saved least recently beyond [`MAX_UNDO_HISTORIES`].
+    pub async fn save_undo_tree(
+        &self,
+        path: PathBuf,
+        content_hash: String,
+        undo_tree: SerializedUndoTree,
+    ) -> Result<()> {
+        let history = serde_json::to_string(&UndoHistory::from(undo_tree))?;
+        let saved_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis() as i64);
+        self.save_undo_history(path, content_hash, history, saved_at)
+            .await?;
+        self.prune_undo_histories(MAX_UNDO_HISTORIES as i64).await
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct UndoHistory {
+    nodes: Vec<UndoHistoryNode>,
+    current: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct UndoHistoryNode {
+    parent: Option<usize>,
+    offset: usize,
+    old_text: String,
+    new_text: String,
+    timestamp: SystemTime,
+}
+
+impl From<SerializedUndoTree> for UndoHistory {
+    fn from(tree: SerializedUndoTree) -> Self {
+        Self {
+            nodes: tree
+                .nodes
+                .into_iter()
+                .map(|node| UndoHistoryNode {
+                    parent: node.parent,
+                    offset: node.edit.range.start,
+                    old_text: node.edit.old_text,
+                    new_text: node.edit.new_text,
+                    timestamp: node.timestamp,
+                })
+                .collect(),
+            current: tree.current,
+        }
+    }
+}
+
+impl From<UndoHistory> for SerializedUndoTree {
+    fn from(history: UndoHistory) -> Self {
+        Self {
+            nodes: history
+                .nodes
+                .into_iter()
+                .map(|node| SerializedUndoTreeNode {
+                    parent: node.parent,
+                    edit: UndoTreeEdit {
+                        range: node.offset..node.offset + node.old_text.len(),
+                        old_text: node.old_text,
+                        new_text: node.new_text,
+                    },
+                    timestamp: node.timestamp,
+                })
+                .collect(),
+            current: history.current,
+        }
+    }
 }
@@ -0,0 +1,215 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use db::file_history::FILE_HISTORY;
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, ParentElement, Render,
+    Styled, Task, View, ViewContext, VisualContext, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+use crate::{FileHistoryView, OpenDeletedFileHistory};
+
+/// Lists the files of the project that were deleted while their history was recorded,
+/// so that their history can be opened and their contents restored.
+pub struct DeletedFiles {
+    picker: View<Picker<DeletedFilesDelegate>>,
+}
+
+impl DeletedFiles {
+    pub fn toggle(
+        workspace: &mut Workspace,
+        _: &OpenDeletedFileHistory,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let project = workspace.project().read(cx);
+        if !project.is_local() {
+            return;
+        }
+
+        let roots = project
+            .visible_worktrees(cx)
+            .map(|worktree| worktree.read(cx).abs_path())
+            .collect::<Vec<_>>();
+        let workspace_handle = cx.view().downgrade();
+        workspace.toggle_modal(cx, |cx| Self::new(workspace_handle, roots, cx));
+    }
+
+    fn new(
+        workspace: WeakView<Workspace>,
+        roots: Vec<Arc<Path>>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let delegate = DeletedFilesDelegate {
+            deleted_files: cx.view().downgrade(),
+            workspace,
+            paths: Vec::new(),
+            candidates: Vec::new(),
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        let picker = cx.new_view(|cx| Picker::new(delegate, cx));
+
+        let load_paths = cx
+            .background_executor()
+            .spawn(async move { FILE_HISTORY.deleted_files() });
+        let picker_handle = picker.downgrade();
+        cx.spawn(|_, mut cx| async move {
+            let paths = load_paths.await?;
+            picker_handle.update(&mut cx, |picker, cx| {
+                picker.delegate.set_paths(paths, &roots);
+                picker.refresh(cx);
+            })
+        })
+        .detach_and_log_err(cx);
+
+        Self { picker }
+    }
+}
+
+impl Render for DeletedFiles {
+    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for DeletedFiles {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for DeletedFiles {}
+impl ModalView for DeletedFiles {}
+
+pub struct DeletedFilesDelegate {
+    deleted_files: WeakView<DeletedFiles>,
+    workspace: WeakView<Workspace>,
+    paths: Vec<PathBuf>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl DeletedFilesDelegate {
+    /// Keeps the deleted files that belong to one of the project's worktrees, labeled
+    /// relative to it.
+    fn set_paths(&mut self, paths: Vec<PathBuf>, roots: &[Arc<Path>]) {
+        self.paths.clear();
+        self.candidates.clear();
+        for path in paths {
+            let Some(root) = roots.iter().find(|root| path.starts_with(root)) else {
+                continue;
+            };
+            let root_name = root.file_name().map(Path::new).unwrap_or(Path::new(""));
+            let label = root_name
+                .join(path.strip_prefix(root).unwrap())
+                .to_string_lossy()
+                .to_string();
+            self.candidates
+                .push(StringMatchCandidate::new(self.paths.len(), label));
+            self.paths.push(path);
+        }
+    }
+}
+
+impl PickerDelegate for DeletedFilesDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self) -> Arc<str> {
+        "Search deleted files...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let abs_path = self.paths[mat.candidate_id].clone();
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    FileHistoryView::open(workspace, abs_path, cx)
+                })
+                .log_err();
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.deleted_files
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                )),
+        )
+    }
+}
@@ -0,0 +1,333 @@
+mod deleted_files;
+mod file_history_view;
+
+use std::{path::PathBuf, time::Duration};
+
+use db::file_history::{SnapshotRetention, FILE_HISTORY};
+use editor::{Editor, EditorEvent, EditorMode};
+use gpui::{actions, AppContext, Model, Task, ViewContext};
+use language::Buffer;
+use project::{Project, ProjectEntryId};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use settings::Settings;
+use util::ResultExt;
+use workspace::Workspace;
+
+pub use deleted_files::DeletedFiles;
+pub use file_history_view::FileHistoryView;
+
+/// Files larger than this aren't stored in the local history.
+const MAX_SNAPSHOT_LEN: u64 = 2 * 1024 * 1024;
+/// How much of a deleted directory's contents is read before deleting it.
+const MAX_DELETED_SNAPSHOTS_LEN: u64 = 64 * 1024 * 1024;
+
+actions!(
+    file_history,
+    [OpenFileHistory, OpenDeletedFileHistory, RestoreSnapshot]
+);
+
+#[derive(Deserialize, Debug)]
+pub struct FileHistorySettings {
+    pub enabled: bool,
+    pub max_snapshots_per_file: usize,
+    pub max_age_days: u64,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct FileHistorySettingsContent {
+    /// Whether to keep a snapshot of files each time they are saved or deleted.
+    ///
+    /// Default: true
+    pub enabled: Option<bool>,
+    /// How many snapshots to keep for each file.
+    ///
+    /// Default: 50
+    pub max_snapshots_per_file: Option<usize>,
+    /// How many days to keep snapshots for.
+    ///
+    /// Default: 30
+    pub max_age_days: Option<u64>,
+}
+
+impl Settings for FileHistorySettings {
+    const KEY: Option<&'static str> = Some("file_history");
+
+    type FileContent = FileHistorySettingsContent;
+
+    fn load(
+        default_value: &Self::FileContent,
+        user_values: &[&Self::FileContent],
+        _: &mut AppContext,
+    ) -> anyhow::Result<Self> {
+        Self::load_via_json_merge(default_value, user_values)
+    }
+}
+
+impl FileHistorySettings {
+    fn retention(&self) -> SnapshotRetention {
+        SnapshotRetention {
+            max_snapshots: self.max_snapshots_per_file,
+            max_age: Duration::from_secs(self.max_age_days * 24 * 60 * 60),
+        }
+    }
+}
+
+pub fn init_settings(cx: &mut AppContext) {
+    FileHistorySettings::register(cx);
+}
+
+pub fn init(cx: &mut AppContext) {
+    init_settings(cx);
+
+    cx.observe_new_views(|editor: &mut Editor, cx: &mut ViewContext<Editor>| {
+        if editor.mode() != EditorMode::Full {
+            return;
+        }
+        let view = cx.view().clone();
+        cx.subscribe(&view, |editor, _, event, cx| {
+            if let EditorEvent::Saved = event {
+                let buffers = editor.buffer().read(cx).all_buffers();
+                snapshot_saved_buffers(buffers, cx);
+            }
+        })
+        .detach();
+    })
+    .detach();
+
+    cx.observe_new_views(
+        |workspace: &mut Workspace, _: &mut ViewContext<Workspace>| {
+            workspace.register_action(FileHistoryView::deploy);
+            workspace.register_action(DeletedFiles::toggle);
+        },
+    )
+    .detach();
+}
+
+/// Stores the contents of the given buffers in the local history, for those that were
+/// saved to a local file.
+fn snapshot_saved_buffers(buffers: impl IntoIterator<Item = Model<Buffer>>, cx: &AppContext) {
+    let settings = FileHistorySettings::get_global(cx);
+    if !settings.enabled {
+        return;
+    }
+
+    let retention = settings.retention();
+    for buffer in buffers {
+        let buffer = buffer.read(cx);
+        if buffer.is_dirty() || buffer.is_large_file() || buffer.len() as u64 > MAX_SNAPSHOT_LEN {
+            continue;
+        }
+        let Some(file) = buffer.file().and_then(|file| file.as_local()) else {
+            continue;
+        };
+
+        let path = file.abs_path(cx);
+        let content = buffer.text();
+        cx.background_executor()
+            .spawn(async move {
+                FILE_HISTORY
+                    .save_snapshot(path, content, false, retention)
+                    .await
+                    .log_err()
+            })
+            .detach();
+    }
+}
+
+/// Stores the last contents of the files about to be deleted with the given entries,
+/// including the files contained in deleted directories, except for ignored and large
+/// ones. The returned task completes once the files are read, and they are stored in
+/// the background.
+pub fn snapshot_deleted_entries(
+    project: &Model<Project>,
+    entry_ids: impl IntoIterator<Item = ProjectEntryId>,
+    cx: &AppContext,
+) -> Task<()> {
+    let settings = FileHistorySettings::get_global(cx);
+    let project = project.read(cx);
+    if !settings.enabled || !project.is_local() {
+        return Task::ready(());
+    }
+
+    let mut paths = Vec::<PathBuf>::new();
+    for entry_id in entry_ids {
+        let Some(worktree) = project.worktree_for_entry(entry_id, cx) else {
+            continue;
+        };
+        let worktree = worktree.read(cx);
+        let Some(entry) = worktree.entry_for_id(entry_id) else {
+            continue;
+        };
+        if entry.is_ignored {
+            continue;
+        }
+        if entry.is_file() {
+            paths.extend(worktree.absolutize(&entry.path).log_err());
+        } else {
+            paths.extend(
+                worktree
+                    .descendent_entries(false, false, &entry.path)
+                    .filter_map(|entry| worktree.absolutize(&entry.path).log_err()),
+            );
+        }
+    }
+
+    let fs = project.fs().clone();
+    let retention = settings.retention();
+    let executor = cx.background_executor().clone();
+    cx.background_executor().spawn(async move {
+        let mut snapshots = Vec::new();
+        let mut total_len = 0;
+        for path in paths {
+            let Some(metadata) = fs.metadata(&path).await.ok().flatten() else {
+                continue;
+            };
+            if metadata.is_dir || metadata.len > MAX_SNAPSHOT_LEN {
+                continue;
+            }
+            total_len += metadata.len;
+            if total_len > MAX_DELETED_SNAPSHOTS_LEN {
+                break;
+            }
+            // Files that can't be loaded as text, such as binary files, aren't stored.
+            if let Ok(content) = fs.load(&path).await {
+                snapshots.push((path, content));
+            }
+        }
+
+        executor
+            .spawn(async move {
+                for (path, content) in snapshots {
+                    FILE_HISTORY
+                        .save_snapshot(path, content, true, retention)
+                        .await
+                        .log_err();
+                }
+            })
+            .detach();
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+    use std::path::Path;
+    use workspace::item::Item;
+
+    #[gpui::test]
+    async fn test_snapshot_on_save(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/saved", json!({ "a.txt": "one\n" })).await;
+        let project = Project::test(fs, ["/saved".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let worktree_id = project.update(cx, |project, cx| {
+            project.worktrees().next().unwrap().read(cx).id()
+        });
+        let editor = workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_path((worktree_id, "a.txt"), None, true, cx)
+            })
+            .await
+            .unwrap()
+            .downcast::<Editor>()
+            .unwrap();
+
+        // Edits are only stored once they're saved.
+        let path = Path::new("/saved/a.txt");
+        editor.update(cx, |editor, cx| editor.set_text("two\n", cx));
+        cx.run_until_parked();
+        assert!(FILE_HISTORY.snapshots(path).unwrap().is_empty());
+
+        editor
+            .update(cx, |editor, cx| Item::save(editor, project.clone(), cx))
+            .await
+            .unwrap();
+        assert_eq!(wait_for_snapshots(path, cx), [("two\n".to_string(), false)]);
+    }
+
+    #[gpui::test]
+    async fn test_snapshot_on_delete(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/deleted",
+            json!({
+                ".git": {},
+                ".gitignore": "ignored.txt\n",
+                "dir": {
+                    "a.txt": "a\n",
+                    "ignored.txt": "ignored\n",
+                    "nested": { "b.txt": "b\n" },
+                },
+                "c.txt": "c\n",
+            }),
+        )
+        .await;
+        let project = Project::test(fs, ["/deleted".as_ref()], cx).await;
+        cx.run_until_parked();
+        let entry_id = project.update(cx, |project, cx| {
+            let worktree = project.worktrees().next().unwrap();
+            worktree.read(cx).entry_for_path("dir").unwrap().id
+        });
+
+        // The files in a deleted directory are stored, except for ignored ones.
+        cx.update(|cx| snapshot_deleted_entries(&project, [entry_id], cx))
+            .await;
+        assert_eq!(
+            wait_for_snapshots(Path::new("/deleted/dir/nested/b.txt"), cx),
+            [("b\n".to_string(), true)]
+        );
+        assert_eq!(
+            wait_for_snapshots(Path::new("/deleted/dir/a.txt"), cx),
+            [("a\n".to_string(), true)]
+        );
+        assert!(FILE_HISTORY
+            .snapshots(Path::new("/deleted/dir/ignored.txt"))
+            .unwrap()
+            .is_empty());
+        assert!(FILE_HISTORY
+            .snapshots(Path::new("/deleted/c.txt"))
+            .unwrap()
+            .is_empty());
+    }
+
+    /// Waits for the snapshots of the file to be stored in the background, returning
+    /// their contents and whether they were taken on deletion, newest first.
+    fn wait_for_snapshots(path: &Path, cx: &mut TestAppContext) -> Vec<(String, bool)> {
+        for _ in 0..100 {
+            cx.run_until_parked();
+            let snapshots = FILE_HISTORY.snapshots(path).unwrap();
+            if !snapshots.is_empty() {
+                return snapshots
+                    .into_iter()
+                    .map(|snapshot| {
+                        let content = FILE_HISTORY.snapshot_content(snapshot.id).unwrap();
+                        (content.unwrap(), snapshot.deleted)
+                    })
+                    .collect();
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("no snapshots of {path:?} were stored");
+    }
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            language::init(cx);
+            Project::init_settings(cx);
+            workspace::init_settings(cx);
+            editor::init(cx);
+            init(cx);
+        });
+    }
+}
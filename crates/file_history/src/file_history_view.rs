@@ -0,0 +1,474 @@
+use std::{ops::Range, path::PathBuf, time::Duration};
+
+use anyhow::{anyhow, Context as _, Result};
+use db::file_history::{FileSnapshot, FILE_HISTORY};
+use editor::Editor;
+use git::diff::BufferDiff;
+use gpui::{
+    uniform_list, AnyElement, AppContext, EventEmitter, FocusHandle, FocusableView, HighlightStyle,
+    InteractiveElement, IntoElement, Model, ParentElement, Render, SharedString, Styled,
+    Subscription, Task, UniformListScrollHandle, View, ViewContext, VisualContext, WindowContext,
+};
+use language::Buffer;
+use project::Project;
+use text::{BufferId, Point};
+use ui::{
+    prelude::*,
+    utils::{DateTimeType, FormatDistance},
+    ListItem, ListItemSpacing, Tooltip,
+};
+use util::ResultExt;
+use workspace::{
+    item::{Item, ItemEvent},
+    Workspace,
+};
+
+use crate::{OpenFileHistory, RestoreSnapshot};
+
+/// Snapshots are written in the background after a file is saved, so the list is
+/// reloaded a bit later to include them.
+const RELOAD_AFTER_SAVE_DELAY: Duration = Duration::from_millis(250);
+
+enum DiffAddition {}
+enum DiffDeletion {}
+
+/// Lists the snapshots stored in the local history for a file, and shows how the
+/// selected snapshot differs from the file's current contents.
+pub struct FileHistoryView {
+    project: Model<Project>,
+    abs_path: PathBuf,
+    /// The file's current contents, if the file still exists.
+    buffer: Option<Model<Buffer>>,
+    snapshots: Vec<FileSnapshot>,
+    selected_ix: usize,
+    diff_editor: View<Editor>,
+    focus_handle: FocusHandle,
+    list_scroll_handle: UniformListScrollHandle,
+    _load_task: Task<Result<()>>,
+    _diff_task: Task<Result<()>>,
+    _buffer_subscription: Option<Subscription>,
+}
+
+impl FileHistoryView {
+    pub fn deploy(workspace: &mut Workspace, _: &OpenFileHistory, cx: &mut ViewContext<Workspace>) {
+        let Some(abs_path) = workspace.active_item_as::<Editor>(cx).and_then(|editor| {
+            let buffer = editor.read(cx).buffer().read(cx).as_singleton()?;
+            let file = buffer.read(cx).file()?.as_local()?;
+            Some(file.abs_path(cx))
+        }) else {
+            return;
+        };
+        Self::open(workspace, abs_path, cx);
+    }
+
+    pub fn open(workspace: &mut Workspace, abs_path: PathBuf, cx: &mut ViewContext<Workspace>) {
+        let existing = workspace
+            .items_of_type::<Self>(cx)
+            .find(|view| view.read(cx).abs_path == abs_path);
+        if let Some(existing) = existing {
+            workspace.activate_item(&existing, cx);
+            return;
+        }
+
+        let project = workspace.project().clone();
+        let view = cx.new_view(|cx| Self::new(project, abs_path, cx));
+        workspace.add_item(Box::new(view), cx);
+    }
+
+    fn new(project: Model<Project>, abs_path: PathBuf, cx: &mut ViewContext<Self>) -> Self {
+        let diff_editor = cx.new_view(|cx| {
+            let buffer = cx.new_model(|cx| {
+                Buffer::new(0, BufferId::new(cx.entity_id().as_u64()).unwrap(), "")
+            });
+            let mut editor = Editor::for_buffer(buffer, None, cx);
+            editor.set_read_only(true);
+            editor
+        });
+
+        let mut this = Self {
+            project,
+            abs_path,
+            buffer: None,
+            snapshots: Vec::new(),
+            selected_ix: 0,
+            diff_editor,
+            focus_handle: cx.focus_handle(),
+            list_scroll_handle: UniformListScrollHandle::new(),
+            _load_task: Task::ready(Ok(())),
+            _diff_task: Task::ready(Ok(())),
+            _buffer_subscription: None,
+        };
+        this.reload(Duration::ZERO, cx);
+        this
+    }
+
+    fn reload(&mut self, delay: Duration, cx: &mut ViewContext<Self>) {
+        let abs_path = self.abs_path.clone();
+        let fs = self.project.read(cx).fs().clone();
+        let project = self.project.clone();
+        self._load_task = cx.spawn(|this, mut cx| async move {
+            cx.background_executor().timer(delay).await;
+            let snapshots = cx
+                .background_executor()
+                .spawn({
+                    let abs_path = abs_path.clone();
+                    async move { FILE_HISTORY.snapshots(&abs_path) }
+                })
+                .await?;
+            let buffer = if fs.is_file(&abs_path).await {
+                let buffer = project
+                    .update(&mut cx, |project, cx| {
+                        project.open_local_buffer(&abs_path, cx)
+                    })?
+                    .await?;
+                Some(buffer)
+            } else {
+                None
+            };
+
+            this.update(&mut cx, |this, cx| {
+                this.snapshots = snapshots;
+                this.selected_ix = this.selected_ix.min(this.snapshots.len().saturating_sub(1));
+                this.set_buffer(buffer, cx);
+                this.update_diff(cx);
+                cx.notify();
+            })
+        });
+    }
+
+    fn set_buffer(&mut self, buffer: Option<Model<Buffer>>, cx: &mut ViewContext<Self>) {
+        if self.buffer == buffer {
+            return;
+        }
+
+        self._buffer_subscription = buffer.as_ref().map(|buffer| {
+            cx.subscribe(buffer, |this, _, event, cx| match event {
+                language::Event::Edited | language::Event::Reloaded => this.update_diff(cx),
+                language::Event::Saved => this.reload(RELOAD_AFTER_SAVE_DELAY, cx),
+                _ => {}
+            })
+        });
+        if let Some(buffer) = &buffer {
+            let language = buffer.read(cx).language().cloned();
+            if let Some(diff_buffer) = self.diff_editor.read(cx).buffer().read(cx).as_singleton() {
+                diff_buffer.update(cx, |diff_buffer, cx| diff_buffer.set_language(language, cx));
+            }
+        }
+        self.buffer = buffer;
+    }
+
+    fn select(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        if ix < self.snapshots.len() && ix != self.selected_ix {
+            self.selected_ix = ix;
+            self.list_scroll_handle.scroll_to_item(ix);
+            self.update_diff(cx);
+            cx.notify();
+        }
+    }
+
+    fn select_next(&mut self, _: &menu::SelectNext, cx: &mut ViewContext<Self>) {
+        self.select(self.selected_ix + 1, cx);
+    }
+
+    fn select_prev(&mut self, _: &menu::SelectPrev, cx: &mut ViewContext<Self>) {
+        if let Some(ix) = self.selected_ix.checked_sub(1) {
+            self.select(ix, cx);
+        }
+    }
+
+    fn update_diff(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(snapshot) = self.snapshots.get(self.selected_ix) else {
+            self.diff_editor
+                .update(cx, |editor, cx| editor.set_text("", cx));
+            return;
+        };
+
+        let snapshot_id = snapshot.id;
+        let current_text = self
+            .buffer
+            .as_ref()
+            .map(|buffer| buffer.read(cx).text_snapshot());
+        self._diff_task = cx.spawn(|this, mut cx| async move {
+            let diff = cx
+                .background_executor()
+                .spawn(async move {
+                    let snapshot_text = FILE_HISTORY
+                        .snapshot_content(snapshot_id)?
+                        .ok_or_else(|| anyhow!("snapshot {snapshot_id} not found"))?;
+                    let current_text = current_text.unwrap_or_else(|| {
+                        text::Buffer::new(0, BufferId::new(1).unwrap(), String::new()).snapshot()
+                    });
+                    anyhow::Ok(UnifiedDiff::new(&snapshot_text, &current_text).await)
+                })
+                .await?;
+
+            this.update(&mut cx, |this, cx| {
+                this.diff_editor.update(cx, |editor, cx| {
+                    editor.set_text(diff.text, cx);
+                    let buffer = editor.buffer().read(cx).snapshot(cx);
+                    let anchor_ranges = |rows: &[Range<u32>]| {
+                        rows.iter()
+                            .map(|rows| {
+                                buffer.anchor_before(Point::new(rows.start, 0))
+                                    ..buffer.anchor_before(Point::new(rows.end, 0))
+                            })
+                            .collect::<Vec<_>>()
+                    };
+                    let added_ranges = anchor_ranges(&diff.added_rows);
+                    let deleted_ranges = anchor_ranges(&diff.deleted_rows);
+                    let status = cx.theme().status();
+                    let added_style = HighlightStyle {
+                        background_color: Some(status.created_background),
+                        ..Default::default()
+                    };
+                    let deleted_style = HighlightStyle {
+                        background_color: Some(status.deleted_background),
+                        ..Default::default()
+                    };
+                    editor.highlight_text::<DiffAddition>(added_ranges, added_style, cx);
+                    editor.highlight_text::<DiffDeletion>(deleted_ranges, deleted_style, cx);
+                });
+            })
+        });
+    }
+
+    fn restore_snapshot(&mut self, _: &RestoreSnapshot, cx: &mut ViewContext<Self>) {
+        let Some(snapshot) = self.snapshots.get(self.selected_ix) else {
+            return;
+        };
+
+        let snapshot_id = snapshot.id;
+        let abs_path = self.abs_path.clone();
+        let buffer = self.buffer.clone();
+        let project = self.project.clone();
+        let fs = project.read(cx).fs().clone();
+        cx.spawn(|this, mut cx| async move {
+            let content = cx
+                .background_executor()
+                .spawn(async move { FILE_HISTORY.snapshot_content(snapshot_id) })
+                .await?
+                .with_context(|| format!("snapshot {snapshot_id} not found"))?;
+
+            if let Some(buffer) = buffer {
+                let diff = buffer
+                    .update(&mut cx, |buffer, cx| buffer.diff(content, cx))?
+                    .await;
+                buffer.update(&mut cx, |buffer, cx| {
+                    buffer.finalize_last_transaction();
+                    buffer.apply_diff(diff, cx);
+                    buffer.finalize_last_transaction();
+                })?;
+                project
+                    .update(&mut cx, |project, cx| project.save_buffer(buffer, cx))?
+                    .await?;
+            } else {
+                if let Some(parent) = abs_path.parent() {
+                    fs.create_dir(parent).await?;
+                }
+                fs.atomic_write(abs_path, content).await?;
+            }
+
+            this.update(&mut cx, |this, cx| this.reload(Duration::ZERO, cx))
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn file_name(&self) -> SharedString {
+        self.abs_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.abs_path.to_string_lossy().to_string())
+            .into()
+    }
+
+    fn render_snapshot(&self, ix: usize, cx: &mut ViewContext<Self>) -> ListItem {
+        let snapshot = &self.snapshots[ix];
+        let age = FormatDistance::from_now(DateTimeType::Local(snapshot.timestamp.into()))
+            .hide_prefix(true)
+            .add_suffix(true)
+            .to_string();
+
+        ListItem::new(ix)
+            .inset(true)
+            .spacing(ListItemSpacing::Sparse)
+            .selected(ix == self.selected_ix)
+            .on_click(cx.listener(move |this, _, cx| this.select(ix, cx)))
+            .child(Label::new(age))
+            .end_slot(
+                snapshot
+                    .deleted
+                    .then(|| Label::new("deleted").color(Color::Muted)),
+            )
+    }
+}
+
+/// The contents of a snapshot and of the current file, interleaved line by line.
+struct UnifiedDiff {
+    text: String,
+    added_rows: Vec<Range<u32>>,
+    deleted_rows: Vec<Range<u32>>,
+}
+
+impl UnifiedDiff {
+    async fn new(old_text: &str, new_text: &text::BufferSnapshot) -> Self {
+        let mut buffer_diff = BufferDiff::new();
+        buffer_diff.update(old_text, new_text).await;
+
+        let mut diff = Self {
+            text: String::new(),
+            added_rows: Vec::new(),
+            deleted_rows: Vec::new(),
+        };
+        let mut new_row = 0;
+        for hunk in
+            buffer_diff.hunks_intersecting_range(text::Anchor::MIN..text::Anchor::MAX, new_text)
+        {
+            diff.push_lines(new_text, new_row..hunk.buffer_range.start);
+
+            let start_row = diff.row();
+            diff.push_text(&old_text[hunk.diff_base_byte_range.clone()]);
+            diff.deleted_rows.push(start_row..diff.row());
+
+            let start_row = diff.row();
+            diff.push_lines(new_text, hunk.buffer_range.clone());
+            diff.added_rows.push(start_row..diff.row());
+
+            new_row = hunk.buffer_range.end;
+        }
+        diff.push_lines(new_text, new_row..new_text.max_point().row + 1);
+        diff
+    }
+
+    fn row(&self) -> u32 {
+        self.text.matches('\n').count() as u32
+    }
+
+    fn push_lines(&mut self, buffer: &text::BufferSnapshot, rows: Range<u32>) {
+        if rows.is_empty() {
+            return;
+        }
+        let start = Point::new(rows.start, 0);
+        let end = if rows.end > buffer.max_point().row {
+            buffer.max_point()
+        } else {
+            Point::new(rows.end, 0)
+        };
+        let text = buffer.text_for_range(start..end).collect::<String>();
+        self.push_text(&text);
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+        self.text.push_str(text);
+    }
+}
+
+impl FocusableView for FileHistoryView {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl EventEmitter<ItemEvent> for FileHistoryView {}
+
+impl Item for FileHistoryView {
+    type Event = ItemEvent;
+
+    fn tab_content(&self, _: Option<usize>, selected: bool, _: &WindowContext) -> AnyElement {
+        h_flex()
+            .gap_2()
+            .child(Icon::new(IconName::ArrowCircle).color(if selected {
+                Color::Default
+            } else {
+                Color::Muted
+            }))
+            .child(
+                Label::new(format!("History: {}", self.file_name())).color(if selected {
+                    Color::Default
+                } else {
+                    Color::Muted
+                }),
+            )
+            .into_any_element()
+    }
+
+    fn tab_tooltip_text(&self, _: &AppContext) -> Option<SharedString> {
+        Some(self.abs_path.to_string_lossy().to_string().into())
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("file history")
+    }
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+}
+
+impl Render for FileHistoryView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let snapshot_list = if self.snapshots.is_empty() {
+            div()
+                .p_2()
+                .child(Label::new("No snapshots").color(Color::Muted))
+                .into_any_element()
+        } else {
+            uniform_list(
+                cx.view().clone(),
+                "file-history-snapshots",
+                self.snapshots.len(),
+                |this, range, cx| {
+                    range
+                        .map(|ix| this.render_snapshot(ix, cx))
+                        .collect::<Vec<_>>()
+                },
+            )
+            .size_full()
+            .track_scroll(self.list_scroll_handle.clone())
+            .into_any_element()
+        };
+
+        h_flex()
+            .key_context("FileHistory")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::select_next))
+            .on_action(cx.listener(Self::select_prev))
+            .on_action(cx.listener(Self::restore_snapshot))
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(
+                v_flex()
+                    .w(rems(16.))
+                    .h_full()
+                    .border_r_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(
+                        h_flex()
+                            .p_2()
+                            .justify_between()
+                            .child(Label::new(self.file_name()))
+                            .child(
+                                Button::new("restore-snapshot", "Restore")
+                                    .disabled(self.snapshots.is_empty())
+                                    .tooltip(|cx| {
+                                        Tooltip::for_action(
+                                            "Restore the selected snapshot",
+                                            &RestoreSnapshot,
+                                            cx,
+                                        )
+                                    })
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.restore_snapshot(&RestoreSnapshot, cx)
+                                    })),
+                            ),
+                    )
+                    .child(snapshot_list),
+            )
+            .child(div().flex_1().h_full().child(self.diff_editor.clone()))
+    }
+}
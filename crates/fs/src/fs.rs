@@ -47,6 +47,14 @@ pub trait Fs: Send + Sync {
     async fn remove_file(&self, path: &Path, options: RemoveOptions) -> Result<()>;
     async fn open_sync(&self, path: &Path) -> Result<Box<dyn io::Read>>;
     async fn load(&self, path: &Path) -> Result<String>;
+    async fn load_bytes(&self, path: &Path) -> Result<Vec<u8>>;
+    /// Reads the file in chunks of at most `chunk_size` bytes, so that large
+    /// files can be consumed without holding all of their bytes at once.
+    async fn load_chunks(
+        &self,
+        path: &Path,
+        chunk_size: usize,
+    ) -> Result<Pin<Box<dyn Send + Stream<Item = Result<Vec<u8>>>>>>;
     async fn atomic_write(&self, path: PathBuf, text: String) -> Result<()>;
     async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()>;
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf>;
@@ -101,6 +109,7 @@ pub struct Metadata {
     pub mtime: SystemTime,
     pub is_symlink: bool,
     pub is_dir: bool,
+    pub len: u64,
 }
 
 pub struct RealFs;
@@ -185,6 +194,28 @@ impl Fs for RealFs {
         Ok(text)
     }
 
+    async fn load_bytes(&self, path: &Path) -> Result<Vec<u8>> {
+        Ok(smol::fs::read(path).await?)
+    }
+
+    async fn load_chunks(
+        &self,
+        path: &Path,
+        chunk_size: usize,
+    ) -> Result<Pin<Box<dyn Send + Stream<Item = Result<Vec<u8>>>>>> {
+        let file = smol::fs::File::open(path).await?;
+        let chunks = futures::stream::try_unfold(file, move |mut file| async move {
+            let mut chunk = vec![0; chunk_size];
+            let len = file.read(&mut chunk).await?;
+            if len == 0 {
+                return Ok(None);
+            }
+            chunk.truncate(len);
+            Ok(Some((chunk, file)))
+        });
+        Ok(Box::pin(chunks))
+    }
+
     async fn atomic_write(&self, path: PathBuf, data: String) -> Result<()> {
         smol::unblock(move || {
             let mut tmp_file = NamedTempFile::new()?;
@@ -244,6 +275,7 @@ impl Fs for RealFs {
             mtime: metadata.modified().unwrap(),
             is_symlink,
             is_dir: metadata.file_type().is_dir(),
+            len: metadata.len(),
         }))
     }
 
@@ -413,7 +445,7 @@ enum FakeFsEntry {
     File {
         inode: u64,
         mtime: SystemTime,
-        content: String,
+        content: Vec<u8>,
     },
     Dir {
         inode: u64,
@@ -563,6 +595,11 @@ impl FakeFs {
         self.write_file_internal(path, content).unwrap()
     }
 
+    /// Inserts a file with contents that don't have to be valid UTF-8.
+    pub async fn insert_file_bytes(&self, path: impl AsRef<Path>, content: Vec<u8>) {
+        self.write_file_internal(path, content).unwrap()
+    }
+
     pub async fn insert_symlink(&self, path: impl AsRef<Path>, target: PathBuf) {
         let mut state = self.state.lock();
         let path = path.as_ref();
@@ -582,7 +619,11 @@ impl FakeFs {
         state.emit_event(&[path]);
     }
 
-    pub fn write_file_internal(&self, path: impl AsRef<Path>, content: String) -> Result<()> {
+    pub fn write_file_internal(
+        &self,
+        path: impl AsRef<Path>,
+        content: impl Into<Vec<u8>>,
+    ) -> Result<()> {
         let mut state = self.state.lock();
         let path = path.as_ref();
         let inode = state.next_inode;
@@ -592,7 +633,7 @@ impl FakeFs {
         let file = Arc::new(Mutex::new(FakeFsEntry::File {
             inode,
             mtime,
-            content,
+            content: content.into(),
         }));
         state.write_path(path, move |entry| {
             match entry {
@@ -695,6 +736,18 @@ impl FakeFs {
         });
     }
 
+    pub fn set_revision_for_repo(&self, dot_git: &Path, revision: &str, files: &[(&Path, String)]) {
+        self.with_git_state(dot_git, false, |state| {
+            state.revision_contents.insert(
+                revision.to_string(),
+                files
+                    .iter()
+                    .map(|(path, content)| (path.to_path_buf(), content.clone()))
+                    .collect(),
+            );
+        });
+    }
+
     pub fn set_status_for_repo_via_working_copy_change(
         &self,
         dot_git: &Path,
@@ -816,7 +869,7 @@ impl FakeFsEntry {
         matches!(self, Self::Symlink { .. })
     }
 
-    fn file_content(&self, path: &Path) -> Result<&String> {
+    fn file_content(&self, path: &Path) -> Result<&Vec<u8>> {
         if let Self::File { content, .. } = self {
             Ok(content)
         } else {
@@ -824,7 +877,7 @@ impl FakeFsEntry {
         }
     }
 
-    fn set_file_content(&mut self, path: &Path, new_content: String) -> Result<()> {
+    fn set_file_content(&mut self, path: &Path, new_content: Vec<u8>) -> Result<()> {
         if let Self::File { content, mtime, .. } = self {
             *mtime = SystemTime::now();
             *content = new_content;
@@ -893,7 +946,7 @@ impl Fs for FakeFs {
         let file = Arc::new(Mutex::new(FakeFsEntry::File {
             inode,
             mtime,
-            content: String::new(),
+            content: Vec::new(),
         }));
         state.write_path(path, |entry| {
             match entry {
@@ -984,7 +1037,7 @@ impl Fs for FakeFs {
                 e.insert(Arc::new(Mutex::new(FakeFsEntry::File {
                     inode,
                     mtime,
-                    content: String::new(),
+                    content: Vec::new(),
                 })))
                 .clone(),
             )),
@@ -1063,11 +1116,16 @@ impl Fs for FakeFs {
     }
 
     async fn open_sync(&self, path: &Path) -> Result<Box<dyn io::Read>> {
-        let text = self.load(path).await?;
-        Ok(Box::new(io::Cursor::new(text)))
+        let bytes = self.load_bytes(path).await?;
+        Ok(Box::new(io::Cursor::new(bytes)))
     }
 
     async fn load(&self, path: &Path) -> Result<String> {
+        let bytes = self.load_bytes(path).await?;
+        Ok(String::from_utf8(bytes)?)
+    }
+
+    async fn load_bytes(&self, path: &Path) -> Result<Vec<u8>> {
         let path = normalize_path(path);
         self.simulate_random_delay().await;
         let state = self.state.lock();
@@ -1076,6 +1134,19 @@ impl Fs for FakeFs {
         entry.file_content(&path).cloned()
     }
 
+    async fn load_chunks(
+        &self,
+        path: &Path,
+        chunk_size: usize,
+    ) -> Result<Pin<Box<dyn Send + Stream<Item = Result<Vec<u8>>>>>> {
+        let bytes = self.load_bytes(path).await?;
+        let chunks = bytes
+            .chunks(chunk_size)
+            .map(|chunk| Ok(chunk.to_vec()))
+            .collect::<Vec<_>>();
+        Ok(Box::pin(futures::stream::iter(chunks)))
+    }
+
     async fn atomic_write(&self, path: PathBuf, data: String) -> Result<()> {
         self.simulate_random_delay().await;
         let path = normalize_path(path.as_path());
@@ -1087,7 +1158,7 @@ impl Fs for FakeFs {
     async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()> {
         self.simulate_random_delay().await;
         let path = normalize_path(path);
-        let content = chunks(text, line_ending).collect();
+        let content = chunks(text, line_ending).collect::<String>();
         if let Some(path) = path.parent() {
             self.create_dir(path).await?;
         }
@@ -1134,17 +1205,23 @@ impl Fs for FakeFs {
 
             let entry = entry.lock();
             Ok(Some(match &*entry {
-                FakeFsEntry::File { inode, mtime, .. } => Metadata {
+                FakeFsEntry::File {
+                    inode,
+                    mtime,
+                    content,
+                } => Metadata {
                     inode: *inode,
                     mtime: *mtime,
                     is_dir: false,
                     is_symlink,
+                    len: content.len() as u64,
                 },
                 FakeFsEntry::Dir { inode, mtime, .. } => Metadata {
                     inode: *inode,
                     mtime: *mtime,
                     is_dir: true,
                     is_symlink,
+                    len: 0,
                 },
                 FakeFsEntry::Symlink { .. } => unreachable!(),
             }))
@@ -25,6 +25,14 @@ pub trait GitRepository: Send {
     fn reload_index(&self);
     fn load_index_text(&self, relative_file_path: &Path) -> Option<String>;
 
+    /// Loads the contents of a file at the given revision, such as `HEAD` or a commit
+    /// SHA, or `None` if the file doesn't exist at that revision.
+    fn load_revision_text(
+        &self,
+        relative_file_path: &Path,
+        revision: &str,
+    ) -> Result<Option<String>>;
+
     /// Returns the URL of the remote with the given name.
     fn remote_url(&self, name: &str) -> Option<String>;
     fn branch_name(&self) -> Option<String>;
@@ -92,6 +100,22 @@ impl GitRepository for LibGitRepository {
         None
     }
 
+    fn load_revision_text(
+        &self,
+        relative_file_path: &Path,
+        revision: &str,
+    ) -> Result<Option<String>> {
+        check_path_to_repo_path_errors(relative_file_path)?;
+        let tree = self.revparse_single(revision)?.peel_to_commit()?.tree()?;
+        let entry = match tree.get_path(relative_file_path) {
+            Ok(entry) => entry,
+            Err(error) if error.code() == git2::ErrorCode::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+        let content = self.find_blob(entry.id())?.content().to_owned();
+        Ok(Some(String::from_utf8(content)?))
+    }
+
     fn remote_url(&self, name: &str) -> Option<String> {
         let remote = self.find_remote(name).ok()?;
         remote.url().map(|url| url.to_string())
@@ -251,6 +275,7 @@ pub struct FakeGitRepository {
 #[derive(Debug, Clone, Default)]
 pub struct FakeGitRepositoryState {
     pub index_contents: HashMap<PathBuf, String>,
+    pub revision_contents: HashMap<String, HashMap<PathBuf, String>>,
     pub worktree_statuses: HashMap<RepoPath, GitFileStatus>,
     pub branch_name: Option<String>,
 }
@@ -269,6 +294,15 @@ impl GitRepository for FakeGitRepository {
         state.index_contents.get(path).cloned()
     }
 
+    fn load_revision_text(&self, path: &Path, revision: &str) -> Result<Option<String>> {
+        let state = self.state.lock();
+        let contents = state
+            .revision_contents
+            .get(revision)
+            .ok_or_else(|| anyhow::anyhow!("unknown revision {revision:?}"))?;
+        Ok(contents.get(path).cloned())
+    }
+
     fn remote_url(&self, _name: &str) -> Option<String> {
         None
     }
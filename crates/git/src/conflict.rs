@@ -0,0 +1,260 @@
+use std::ops::Range;
+use text::{Anchor, BufferSnapshot, Point};
+
+const OURS_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SEPARATOR_MARKER: &str = "=======";
+const THEIRS_MARKER: &str = ">>>>>>>";
+
+/// A region of a buffer left with conflict markers by a merge, a rebase or a cherry-pick.
+///
+/// ```text
+/// <<<<<<< HEAD
+/// ours
+/// ||||||| base
+/// base (only with the diff3 conflict style)
+/// =======
+/// theirs
+/// >>>>>>> branch
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    /// The whole region, from the start of the first marker line to the end of the last one.
+    pub range: Range<Anchor>,
+    /// The lines between the `<<<<<<<` marker and the next marker.
+    pub ours: Range<Anchor>,
+    /// The lines between the `|||||||` and `=======` markers, if any.
+    pub base: Option<Range<Anchor>>,
+    /// The lines between the `=======` and `>>>>>>>` markers.
+    pub theirs: Range<Anchor>,
+    /// The name following the `<<<<<<<` marker, such as `HEAD`.
+    pub ours_label: String,
+    /// The name following the `>>>>>>>` marker, such as the merged branch.
+    pub theirs_label: String,
+}
+
+/// How to resolve a conflict.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    Ours,
+    Theirs,
+    /// Our lines followed by theirs.
+    Both,
+}
+
+impl Conflict {
+    /// The text replacing the whole conflict region when resolving it.
+    pub fn resolved_text(&self, resolution: Resolution, buffer: &BufferSnapshot) -> String {
+        let text = |range: &Range<Anchor>| buffer.text_for_range(range.clone()).collect::<String>();
+        match resolution {
+            Resolution::Ours => text(&self.ours),
+            Resolution::Theirs => text(&self.theirs),
+            Resolution::Both => text(&self.ours) + &text(&self.theirs),
+        }
+    }
+}
+
+enum State {
+    Outside,
+    Ours {
+        start: usize,
+        label: String,
+        ours_start: usize,
+    },
+    Base {
+        start: usize,
+        label: String,
+        ours: Range<usize>,
+        base_start: usize,
+    },
+    Theirs {
+        start: usize,
+        label: String,
+        ours: Range<usize>,
+        base: Option<Range<usize>>,
+        theirs_start: usize,
+    },
+}
+
+/// Finds the conflict regions of a buffer. Markers that don't form a complete region are
+/// ignored, as they're more likely to be part of the text than left by git.
+pub fn parse_conflicts(buffer: &BufferSnapshot) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let mut state = State::Outside;
+    let max_row = buffer.max_point().row;
+    for row in 0..=max_row {
+        // Only the lines that may be markers are read, rather than copying the whole text.
+        let line_start = buffer.point_to_offset(Point::new(row, 0));
+        let may_be_marker = buffer
+            .chars_at(line_start)
+            .next()
+            .map_or(false, |c| matches!(c, '<' | '|' | '=' | '>'));
+        if !may_be_marker {
+            continue;
+        }
+        let line_end = if row < max_row {
+            buffer.point_to_offset(Point::new(row + 1, 0))
+        } else {
+            buffer.len()
+        };
+        let line = buffer
+            .text_for_range(line_start..line_start + buffer.line_len(row) as usize)
+            .collect::<String>();
+        let content = line.trim_end_matches('\r');
+        if let Some(label) = marker_label(content, OURS_MARKER) {
+            // A region that never ended is dropped in favor of the one starting here.
+            state = State::Ours {
+                start: line_start,
+                label,
+                ours_start: line_end,
+            };
+        } else {
+            state = match state {
+                State::Ours {
+                    start,
+                    label,
+                    ours_start,
+                } if marker_label(content, BASE_MARKER).is_some() => State::Base {
+                    start,
+                    label,
+                    ours: ours_start..line_start,
+                    base_start: line_end,
+                },
+                State::Ours {
+                    start,
+                    label,
+                    ours_start,
+                } if content == SEPARATOR_MARKER => State::Theirs {
+                    start,
+                    label,
+                    ours: ours_start..line_start,
+                    base: None,
+                    theirs_start: line_end,
+                },
+                State::Base {
+                    start,
+                    label,
+                    ours,
+                    base_start,
+                } if content == SEPARATOR_MARKER => State::Theirs {
+                    start,
+                    label,
+                    ours,
+                    base: Some(base_start..line_start),
+                    theirs_start: line_end,
+                },
+                State::Theirs {
+                    start,
+                    label,
+                    ours,
+                    base,
+                    theirs_start,
+                } => {
+                    if let Some(theirs_label) = marker_label(content, THEIRS_MARKER) {
+                        let anchor_range = |range: Range<usize>| {
+                            buffer.anchor_after(range.start)..buffer.anchor_before(range.end)
+                        };
+                        conflicts.push(Conflict {
+                            range: anchor_range(start..line_end),
+                            ours: anchor_range(ours),
+                            base: base.map(anchor_range),
+                            theirs: anchor_range(theirs_start..line_start),
+                            ours_label: label,
+                            theirs_label,
+                        });
+                        State::Outside
+                    } else {
+                        State::Theirs {
+                            start,
+                            label,
+                            ours,
+                            base,
+                            theirs_start,
+                        }
+                    }
+                }
+                state => state,
+            };
+        }
+    }
+    conflicts
+}
+
+/// Returns the label following a marker at the start of a line, which may be empty.
+fn marker_label(line: &str, marker: &str) -> Option<String> {
+    let rest = line.strip_prefix(marker)?;
+    if rest.is_empty() {
+        Some(String::new())
+    } else {
+        rest.strip_prefix(' ').map(|label| label.trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use text::{Buffer, BufferId};
+    use unindent::Unindent as _;
+
+    #[test]
+    fn test_parse_conflicts() {
+        let text = "
+            one
+            <<<<<<< HEAD
+            two
+            =======
+            2
+            >>>>>>> feature
+            three
+            <<<<<<< ours
+            four
+            ||||||| base
+            for
+            =======
+            >>>>>>> theirs
+            <<<<<<< unterminated
+            =======
+        "
+        .unindent();
+        let buffer = Buffer::new(0, BufferId::new(1).unwrap(), text);
+        let snapshot = buffer.snapshot();
+        let conflicts = parse_conflicts(&snapshot);
+        let text =
+            |range: &Range<Anchor>| snapshot.text_for_range(range.clone()).collect::<String>();
+
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(
+            text(&conflicts[0].range),
+            "<<<<<<< HEAD\ntwo\n=======\n2\n>>>>>>> feature\n"
+        );
+        assert_eq!(text(&conflicts[0].ours), "two\n");
+        assert_eq!(conflicts[0].base, None);
+        assert_eq!(text(&conflicts[0].theirs), "2\n");
+        assert_eq!(conflicts[0].ours_label, "HEAD");
+        assert_eq!(conflicts[0].theirs_label, "feature");
+
+        assert_eq!(text(&conflicts[1].ours), "four\n");
+        assert_eq!(text(conflicts[1].base.as_ref().unwrap()), "for\n");
+        assert_eq!(text(&conflicts[1].theirs), "");
+
+        assert_eq!(
+            conflicts[0].resolved_text(Resolution::Both, &snapshot),
+            "two\n2\n"
+        );
+        assert_eq!(
+            conflicts[1].resolved_text(Resolution::Theirs, &snapshot),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_marker_label() {
+        assert_eq!(
+            marker_label("<<<<<<< HEAD", OURS_MARKER),
+            Some("HEAD".into())
+        );
+        assert_eq!(marker_label("<<<<<<<", OURS_MARKER), Some("".into()));
+        assert_eq!(marker_label("<<<<<<<<", OURS_MARKER), None);
+        assert_eq!(marker_label("  <<<<<<<", OURS_MARKER), None);
+    }
+}
@@ -30,12 +30,13 @@ use util::{
 
 use crate::WindowAppearance;
 use crate::{
-    current_platform, image_cache::ImageCache, init_app_menus, Action, ActionRegistry, Any,
-    AnyView, AnyWindowHandle, AppMetadata, AssetSource, BackgroundExecutor, ClipboardItem, Context,
-    DispatchPhase, Entity, EventEmitter, ForegroundExecutor, Global, KeyBinding, Keymap, Keystroke,
-    LayoutId, Menu, PathPromptOptions, Pixels, Platform, PlatformDisplay, Point, Render,
-    SharedString, SubscriberSet, Subscription, SvgRenderer, Task, TextStyle, TextStyleRefinement,
-    TextSystem, View, ViewContext, Window, WindowContext, WindowHandle, WindowId,
+    current_headless_platform, current_platform, image_cache::ImageCache, init_app_menus, Action,
+    ActionRegistry, Any, AnyView, AnyWindowHandle, AppMetadata, AssetSource, BackgroundExecutor,
+    ClipboardItem, Context, DispatchPhase, Entity, EventEmitter, ForegroundExecutor, Global,
+    KeyBinding, Keymap, Keystroke, LayoutId, Menu, PathPromptOptions, Pixels, Platform,
+    PlatformDisplay, Point, Render, SharedString, SubscriberSet, Subscription, SvgRenderer, Task,
+    TextStyle, TextStyleRefinement, TextSystem, View, ViewContext, Window, WindowContext,
+    WindowHandle, WindowId,
 };
 
 mod async_context;
@@ -122,6 +123,16 @@ impl App {
         ))
     }
 
+    /// Builds an app that doesn't open windows, such as a server, which can run
+    /// without a display.
+    pub fn headless() -> Self {
+        Self(AppContext::new(
+            current_headless_platform(),
+            Arc::new(()),
+            http::client(),
+        ))
+    }
+
     /// Assign
     pub fn with_assets(self, asset_source: impl AssetSource) -> Self {
         let mut context_lock = self.0.borrow_mut();
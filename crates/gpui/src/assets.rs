@@ -52,6 +52,35 @@ impl ImageData {
         }
     }
 
+    /// Decode an image from the bytes of a raster format such as PNG, JPEG or GIF.
+    pub fn from_encoded(bytes: &[u8]) -> Result<Self> {
+        let format = image::guess_format(bytes)?;
+        let image = image::load_from_memory_with_format(bytes, format)?.into_bgra8();
+        Ok(Self::new(image))
+    }
+
+    /// Render an SVG document in full color, at `scale` times its intrinsic size.
+    pub fn from_svg(bytes: &[u8], scale: f32) -> Result<Self> {
+        let tree = usvg::Tree::from_data(bytes, &usvg::Options::default())?;
+        let svg_size = tree.svg_node().size;
+        let width = (svg_size.width() as f32 * scale).ceil().max(1.) as u32;
+        let height = (svg_size.height() as f32 * scale).ceil().max(1.) as u32;
+        let mut pixmap = tiny_skia::Pixmap::new(width, height)
+            .ok_or_else(|| anyhow!("can't render an SVG at {width}x{height}"))?;
+        resvg::render(&tree, usvg::FitTo::Width(width), pixmap.as_mut())
+            .ok_or_else(|| anyhow!("failed to render SVG"))?;
+
+        // The pixmap is premultiplied RGBA, while images are straight BGRA.
+        let mut data = Vec::with_capacity(pixmap.pixels().len() * 4);
+        for pixel in pixmap.pixels() {
+            let color = pixel.demultiply();
+            data.extend_from_slice(&[color.blue(), color.green(), color.red(), color.alpha()]);
+        }
+        let image = ImageBuffer::from_raw(width, height, data)
+            .ok_or_else(|| anyhow!("invalid SVG pixmap"))?;
+        Ok(Self::new(image))
+    }
+
     /// Convert this image into a byte slice.
     pub fn as_bytes(&self) -> &[u8] {
         &self.data
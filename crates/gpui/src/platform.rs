@@ -62,6 +62,15 @@ pub(crate) fn current_platform() -> Rc<dyn Platform> {
     Rc::new(LinuxPlatform::new())
 }
 
+#[cfg(target_os = "macos")]
+pub(crate) fn current_headless_platform() -> Rc<dyn Platform> {
+    Rc::new(MacPlatform::new())
+}
+#[cfg(target_os = "linux")]
+pub(crate) fn current_headless_platform() -> Rc<dyn Platform> {
+    Rc::new(LinuxPlatform::new_headless())
+}
+
 pub(crate) trait Platform: 'static {
     fn background_executor(&self) -> BackgroundExecutor;
     fn foreground_executor(&self) -> ForegroundExecutor;
@@ -1,6 +1,7 @@
 mod client;
 mod client_dispatcher;
 mod dispatcher;
+mod headless;
 mod platform;
 mod text_system;
 mod wayland;
@@ -0,0 +1,57 @@
+use std::rc::Rc;
+
+use crate::platform::linux::client::Client;
+use crate::platform::linux::client_dispatcher::ClientDispatcher;
+use crate::platform::{LinuxPlatformInner, PlatformWindow};
+use crate::{AnyWindowHandle, DisplayId, PlatformDisplay, WindowOptions};
+
+/// A client for apps without windows, such as servers, which only runs the tasks
+/// dispatched to the main thread.
+pub(crate) struct HeadlessClient {
+    platform_inner: Rc<LinuxPlatformInner>,
+}
+
+impl HeadlessClient {
+    pub(crate) fn new(inner: Rc<LinuxPlatformInner>) -> Self {
+        Self {
+            platform_inner: inner,
+        }
+    }
+}
+
+impl Client for HeadlessClient {
+    fn run(&self, on_finish_launching: Box<dyn FnOnce()>) {
+        on_finish_launching();
+        while !self.platform_inner.state.lock().quit_requested {
+            match self.platform_inner.main_receiver.recv() {
+                Ok(runnable) => {
+                    runnable.run();
+                }
+                Err(_) => break,
+            }
+        }
+
+        if let Some(ref mut fun) = self.platform_inner.callbacks.lock().quit {
+            fun();
+        }
+    }
+
+    fn displays(&self) -> Vec<Rc<dyn PlatformDisplay>> {
+        Vec::new()
+    }
+
+    fn display(&self, _: DisplayId) -> Option<Rc<dyn PlatformDisplay>> {
+        None
+    }
+
+    fn open_window(&self, _: AnyWindowHandle, _: WindowOptions) -> Box<dyn PlatformWindow> {
+        panic!("a headless app can't open windows")
+    }
+}
+
+/// Tasks dispatched to the main thread are received by the run loop itself.
+pub(crate) struct HeadlessClientDispatcher;
+
+impl ClientDispatcher for HeadlessClientDispatcher {
+    fn dispatch_on_main_thread(&self) {}
+}
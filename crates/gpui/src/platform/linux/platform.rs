@@ -18,6 +18,7 @@ use wayland_client::Connection;
 
 use crate::platform::linux::client::Client;
 use crate::platform::linux::client_dispatcher::ClientDispatcher;
+use crate::platform::linux::headless::{HeadlessClient, HeadlessClientDispatcher};
 use crate::platform::linux::wayland::{WaylandClient, WaylandClientDispatcher};
 use crate::platform::{X11Client, X11ClientDispatcher, XcbAtoms};
 use crate::{
@@ -83,6 +84,29 @@ impl LinuxPlatform {
         }
     }
 
+    /// Creates a platform without a display server, for apps that don't open windows.
+    pub(crate) fn new_headless() -> Self {
+        let (main_sender, main_receiver) = flume::unbounded::<Runnable>();
+        let client_dispatcher: Arc<dyn ClientDispatcher + Send + Sync> =
+            Arc::new(HeadlessClientDispatcher);
+        let dispatcher = Arc::new(LinuxDispatcher::new(main_sender, &client_dispatcher));
+        let inner = Rc::new(LinuxPlatformInner {
+            background_executor: BackgroundExecutor::new(dispatcher.clone()),
+            foreground_executor: ForegroundExecutor::new(dispatcher.clone()),
+            main_receiver,
+            text_system: Arc::new(LinuxTextSystem::new()),
+            callbacks: Mutex::new(Callbacks::default()),
+            state: Mutex::new(LinuxPlatformState {
+                quit_requested: false,
+            }),
+        });
+        let client = Rc::new(HeadlessClient::new(Rc::clone(&inner)));
+        Self {
+            client,
+            inner: Rc::clone(&inner),
+        }
+    }
+
     fn new_wayland(
         main_sender: Sender<Runnable>,
         main_receiver: Receiver<Runnable>,
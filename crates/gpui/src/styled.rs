@@ -1,7 +1,7 @@
 use crate::{
     self as gpui, hsla, point, px, relative, rems, AbsoluteLength, AlignItems, CursorStyle,
-    DefiniteLength, Fill, FlexDirection, FontWeight, Hsla, JustifyContent, Length, Position,
-    SharedString, StyleRefinement, Visibility, WhiteSpace,
+    DefiniteLength, Fill, FlexDirection, FontStyle, FontWeight, Hsla, JustifyContent, Length,
+    Position, SharedString, StyleRefinement, Visibility, WhiteSpace,
 };
 use crate::{BoxShadow, TextStyleRefinement};
 use smallvec::{smallvec, SmallVec};
@@ -529,6 +529,22 @@ pub trait Styled: Sized {
         self
     }
 
+    /// Set the font style of this element to italic, this value cascades to it's child elements.
+    fn italic(mut self) -> Self {
+        self.text_style()
+            .get_or_insert_with(Default::default)
+            .font_style = Some(FontStyle::Italic);
+        self
+    }
+
+    /// Set the font style of this element to normal (not italic), this value cascades to it's child elements.
+    fn not_italic(mut self) -> Self {
+        self.text_style()
+            .get_or_insert_with(Default::default)
+            .font_style = Some(FontStyle::Normal);
+        self
+    }
+
     /// Set the background color of this element, this value cascades to it's child elements.
     fn text_bg(mut self, bg: impl Into<Hsla>) -> Self {
         self.text_style()
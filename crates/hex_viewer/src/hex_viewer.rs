@@ -0,0 +1,612 @@
+use std::ops::Range;
+
+use editor::Editor;
+use gpui::{
+    actions, relative, uniform_list, AnyElement, AppContext, EntityId, EventEmitter, FocusHandle,
+    FocusableView, FontStyle, FontWeight, HighlightStyle, Hsla, Model, Render, SharedString,
+    StyledText, Subscription, TextStyle, UniformListScrollHandle, View, ViewContext, WindowContext,
+};
+use project::{BinaryItem, Project};
+use settings::Settings;
+use theme::ThemeSettings;
+use ui::{prelude::*, Tooltip};
+use workspace::item::{Item, ItemEvent, ProjectItem};
+
+actions!(
+    hex_viewer,
+    [GoToOffset, FindBytes, SelectNextMatch, SelectPrevMatch]
+);
+
+const BYTES_PER_ROW: usize = 16;
+
+pub fn init(cx: &mut AppContext) {
+    workspace::register_project_item::<HexView>(cx);
+}
+
+/// A read-only view of the bytes of a file that isn't valid UTF-8, with an offset
+/// column, the bytes in hexadecimal and their printable ASCII characters.
+pub struct HexView {
+    binary_item: Model<BinaryItem>,
+    focus_handle: FocusHandle,
+    scroll_handle: UniformListScrollHandle,
+    offset_editor: View<Editor>,
+    search_editor: View<Editor>,
+    pattern: Vec<u8>,
+    matches: Vec<usize>,
+    active_match: Option<usize>,
+    selected_offset: Option<usize>,
+    error: Option<SharedString>,
+    _observe_binary_item: Subscription,
+}
+
+impl HexView {
+    pub fn new(binary_item: Model<BinaryItem>, cx: &mut ViewContext<Self>) -> Self {
+        let offset_editor = cx.new_view(|cx| {
+            let mut editor = Editor::single_line(cx);
+            editor.set_placeholder_text("Go to offset, e.g. 0x1f0", cx);
+            editor
+        });
+        let search_editor = cx.new_view(|cx| {
+            let mut editor = Editor::single_line(cx);
+            editor.set_placeholder_text("Find bytes, e.g. 7f 45 4c 46 or \"ELF\"", cx);
+            editor
+        });
+        Self {
+            _observe_binary_item: cx.observe(&binary_item, |this, _, cx| {
+                this.update_matches(cx);
+                cx.notify();
+            }),
+            binary_item,
+            focus_handle: cx.focus_handle(),
+            scroll_handle: UniformListScrollHandle::new(),
+            offset_editor,
+            search_editor,
+            pattern: Vec::new(),
+            matches: Vec::new(),
+            active_match: None,
+            selected_offset: None,
+            error: None,
+        }
+    }
+
+    pub fn selected_offset(&self) -> Option<usize> {
+        self.selected_offset
+    }
+
+    /// The offsets at which the searched pattern was found.
+    pub fn matches(&self) -> &[usize] {
+        &self.matches
+    }
+
+    fn len(&self, cx: &AppContext) -> usize {
+        self.binary_item.read(cx).content().len()
+    }
+
+    fn select_offset(&mut self, offset: usize, cx: &mut ViewContext<Self>) {
+        self.selected_offset = Some(offset);
+        self.scroll_handle.scroll_to_item(offset / BYTES_PER_ROW);
+        cx.notify();
+    }
+
+    /// Moves to the offset typed in the offset editor.
+    pub fn go_to_offset(&mut self, text: &str, cx: &mut ViewContext<Self>) {
+        let len = self.len(cx);
+        match parse_offset(text) {
+            Some(offset) if offset < len => {
+                self.error = None;
+                self.select_offset(offset, cx);
+            }
+            Some(_) => self.set_error(format!("The file is only {len} bytes long"), cx),
+            None => self.set_error(format!("Invalid offset {text:?}"), cx),
+        }
+    }
+
+    /// Searches for the byte pattern typed in the search editor, selecting the first
+    /// match after the selected offset.
+    pub fn search(&mut self, text: &str, cx: &mut ViewContext<Self>) {
+        let Some(pattern) = parse_byte_pattern(text) else {
+            self.set_error(format!("Invalid byte pattern {text:?}"), cx);
+            return;
+        };
+        self.error = None;
+        self.pattern = pattern;
+        self.update_matches(cx);
+        if self.matches.is_empty() && !self.pattern.is_empty() {
+            self.set_error("No matches".to_string(), cx);
+            return;
+        }
+        let start = self.selected_offset.unwrap_or(0);
+        let ix = self.matches.partition_point(|offset| *offset < start);
+        self.activate_match(ix % self.matches.len().max(1), cx);
+    }
+
+    fn update_matches(&mut self, cx: &mut ViewContext<Self>) {
+        self.matches = find_pattern(self.binary_item.read(cx).content(), &self.pattern);
+        self.active_match = self.active_match.filter(|ix| *ix < self.matches.len());
+    }
+
+    fn activate_match(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        if let Some(&offset) = self.matches.get(ix) {
+            self.active_match = Some(ix);
+            self.select_offset(offset, cx);
+        }
+    }
+
+    fn set_error(&mut self, error: String, cx: &mut ViewContext<Self>) {
+        self.error = Some(error.into());
+        cx.notify();
+    }
+
+    fn focus_go_to_offset(&mut self, _: &GoToOffset, cx: &mut ViewContext<Self>) {
+        cx.focus_view(&self.offset_editor);
+    }
+
+    fn focus_find_bytes(&mut self, _: &FindBytes, cx: &mut ViewContext<Self>) {
+        cx.focus_view(&self.search_editor);
+    }
+
+    fn select_next_match(&mut self, _: &SelectNextMatch, cx: &mut ViewContext<Self>) {
+        if !self.matches.is_empty() {
+            let ix = self
+                .active_match
+                .map_or(0, |ix| (ix + 1) % self.matches.len());
+            self.activate_match(ix, cx);
+        }
+    }
+
+    fn select_prev_match(&mut self, _: &SelectPrevMatch, cx: &mut ViewContext<Self>) {
+        if !self.matches.is_empty() {
+            let ix = self
+                .active_match
+                .map_or(0, |ix| ix.checked_sub(1).unwrap_or(self.matches.len() - 1));
+            self.activate_match(ix, cx);
+        }
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, cx: &mut ViewContext<Self>) {
+        if self.offset_editor.focus_handle(cx).is_focused(cx) {
+            let text = self.offset_editor.read(cx).text(cx);
+            self.go_to_offset(&text, cx);
+        } else if self.search_editor.focus_handle(cx).is_focused(cx) {
+            let text = self.search_editor.read(cx).text(cx);
+            if parse_byte_pattern(&text).as_ref() == Some(&self.pattern) && self.error.is_none() {
+                self.select_next_match(&SelectNextMatch, cx);
+            } else {
+                self.search(&text, cx);
+            }
+        }
+    }
+
+    fn render_row(
+        &self,
+        row: usize,
+        text_style: &TextStyle,
+        cx: &ViewContext<Self>,
+    ) -> impl IntoElement {
+        let content = self.binary_item.read(cx).content();
+        let start = row * BYTES_PER_ROW;
+        let bytes = &content[start..(start + BYTES_PER_ROW).min(content.len())];
+        let colors = cx.theme().colors();
+        let highlight = |offset: usize| -> Option<Hsla> {
+            if self.selected_offset == Some(offset) {
+                return Some(colors.element_selected);
+            }
+            let ix = self.matches.partition_point(|start| *start <= offset);
+            let is_match = ix > 0 && offset < self.matches[ix - 1] + self.pattern.len();
+            is_match.then_some(colors.search_match_background)
+        };
+        let highlight = &highlight;
+        let highlights = |width: usize, stride: usize| {
+            (0..bytes.len()).filter_map(move |ix| {
+                let background_color = highlight(start + ix)?;
+                Some((
+                    ix * stride..ix * stride + width,
+                    HighlightStyle {
+                        background_color: Some(background_color),
+                        ..Default::default()
+                    },
+                ))
+            })
+        };
+
+        let (hex, ascii) = format_row(bytes);
+        h_flex()
+            .id(row)
+            .gap_4()
+            .px_2()
+            .child(
+                div()
+                    .text_color(colors.editor_line_number)
+                    .child(format!("{start:08x}")),
+            )
+            .child(StyledText::new(hex).with_highlights(text_style, highlights(2, 3)))
+            .child(StyledText::new(ascii).with_highlights(text_style, highlights(1, 1)))
+    }
+
+    fn render_header(&self, cx: &ViewContext<Self>) -> impl IntoElement {
+        let input = |editor: &View<Editor>| {
+            div()
+                .w(rems(16.))
+                .px_2()
+                .py_1()
+                .border_1()
+                .border_color(cx.theme().colors().border)
+                .rounded_md()
+                .child(editor.clone())
+        };
+        let match_count = if self.pattern.is_empty() {
+            None
+        } else {
+            Some(match self.active_match {
+                Some(ix) => format!("{} of {}", ix + 1, self.matches.len()),
+                None => format!("{} matches", self.matches.len()),
+            })
+        };
+
+        h_flex()
+            .gap_2()
+            .p_2()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(input(&self.offset_editor))
+            .child(input(&self.search_editor))
+            .child(
+                IconButton::new("select-prev-match", IconName::ChevronLeft)
+                    .disabled(self.matches.is_empty())
+                    .tooltip(|cx| Tooltip::for_action("Previous Match", &SelectPrevMatch, cx))
+                    .on_click(
+                        cx.listener(|this, _, cx| this.select_prev_match(&SelectPrevMatch, cx)),
+                    ),
+            )
+            .child(
+                IconButton::new("select-next-match", IconName::ChevronRight)
+                    .disabled(self.matches.is_empty())
+                    .tooltip(|cx| Tooltip::for_action("Next Match", &SelectNextMatch, cx))
+                    .on_click(
+                        cx.listener(|this, _, cx| this.select_next_match(&SelectNextMatch, cx)),
+                    ),
+            )
+            .children(match_count.map(|count| Label::new(count).color(Color::Muted)))
+            .children(
+                self.error
+                    .clone()
+                    .map(|error| Label::new(error).color(Color::Error)),
+            )
+            .child(div().flex_1())
+            .child(Label::new(format!("{} bytes", self.len(cx))).color(Color::Muted))
+    }
+}
+
+/// Parses a decimal offset, or a hexadecimal one prefixed with `0x`.
+pub fn parse_offset(text: &str) -> Option<usize> {
+    let text = text.trim().replace('_', "");
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// Parses bytes written in hexadecimal, optionally separated by whitespace, or an ASCII
+/// string between double quotes.
+pub fn parse_byte_pattern(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    if let Some(string) = text
+        .strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+    {
+        return Some(string.as_bytes().to_vec());
+    }
+
+    let digits = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let high = pair[0].to_digit(16)?;
+            let low = pair[1].to_digit(16)?;
+            Some((high * 16 + low) as u8)
+        })
+        .collect()
+}
+
+/// Returns the offsets of every occurrence of the pattern, including overlapping ones.
+pub fn find_pattern(content: &[u8], pattern: &[u8]) -> Vec<usize> {
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    content
+        .windows(pattern.len())
+        .enumerate()
+        .filter(|(_, window)| *window == pattern)
+        .map(|(offset, _)| offset)
+        .collect()
+}
+
+/// Formats a row of bytes as hexadecimal pairs and as ASCII, with non-printable bytes
+/// shown as dots. Each byte takes three characters of the former and one of the latter.
+fn format_row(bytes: &[u8]) -> (SharedString, SharedString) {
+    let mut hex = String::with_capacity(BYTES_PER_ROW * 3);
+    let mut ascii = String::with_capacity(BYTES_PER_ROW);
+    for (ix, byte) in bytes.iter().enumerate() {
+        if ix > 0 {
+            hex.push(' ');
+        }
+        hex.push_str(&format!("{byte:02x}"));
+        ascii.push(if byte.is_ascii_graphic() || *byte == b' ' {
+            *byte as char
+        } else {
+            '.'
+        });
+    }
+    // Pad short rows so that the ASCII column stays aligned.
+    let padding = (BYTES_PER_ROW - bytes.len()) * 3;
+    hex.extend(std::iter::repeat(' ').take(padding));
+    (hex.into(), ascii.into())
+}
+
+fn row_range(len: usize) -> Range<usize> {
+    0..(len + BYTES_PER_ROW - 1) / BYTES_PER_ROW
+}
+
+impl EventEmitter<ItemEvent> for HexView {}
+
+impl FocusableView for HexView {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Item for HexView {
+    type Event = ItemEvent;
+
+    fn tab_content(&self, _: Option<usize>, selected: bool, cx: &WindowContext) -> AnyElement {
+        let title = self
+            .binary_item
+            .read(cx)
+            .path()
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+        Label::new(title)
+            .color(if selected {
+                Color::Default
+            } else {
+                Color::Muted
+            })
+            .into_any_element()
+    }
+
+    fn tab_tooltip_text(&self, cx: &AppContext) -> Option<SharedString> {
+        Some(
+            self.binary_item
+                .read(cx)
+                .path()
+                .to_string_lossy()
+                .to_string()
+                .into(),
+        )
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("hex viewer")
+    }
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+
+    fn for_each_project_item(
+        &self,
+        cx: &AppContext,
+        f: &mut dyn FnMut(EntityId, &dyn project::Item),
+    ) {
+        f(self.binary_item.entity_id(), self.binary_item.read(cx))
+    }
+
+    fn is_singleton(&self, _: &AppContext) -> bool {
+        true
+    }
+
+    fn clone_on_split(
+        &self,
+        _: workspace::WorkspaceId,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<View<Self>> {
+        let binary_item = self.binary_item.clone();
+        Some(cx.new_view(|cx| Self::new(binary_item, cx)))
+    }
+}
+
+impl ProjectItem for HexView {
+    type Item = BinaryItem;
+
+    fn for_project_item(
+        _: Model<Project>,
+        binary_item: Model<BinaryItem>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        Self::new(binary_item, cx)
+    }
+}
+
+impl Render for HexView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let settings = ThemeSettings::get_global(cx);
+        let font_family = settings.buffer_font.family.clone();
+        let text_style = TextStyle {
+            color: cx.theme().colors().text,
+            font_family: settings.buffer_font.family.clone(),
+            font_features: settings.buffer_font.features,
+            font_size: settings.buffer_font_size(cx).into(),
+            font_weight: FontWeight::NORMAL,
+            font_style: FontStyle::Normal,
+            line_height: relative(1.3).into(),
+            ..Default::default()
+        };
+        let rows = row_range(self.len(cx));
+
+        let content = if rows.is_empty() {
+            div()
+                .p_2()
+                .child(Label::new("Empty file").color(Color::Muted))
+                .into_any_element()
+        } else {
+            uniform_list(
+                cx.view().clone(),
+                "hex-rows",
+                rows.len(),
+                move |this, range, cx| {
+                    range
+                        .map(|row| this.render_row(row, &text_style, cx))
+                        .collect::<Vec<_>>()
+                },
+            )
+            .size_full()
+            .track_scroll(self.scroll_handle.clone())
+            .into_any_element()
+        };
+
+        v_flex()
+            .key_context("HexView")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::focus_go_to_offset))
+            .on_action(cx.listener(Self::focus_find_bytes))
+            .on_action(cx.listener(Self::select_next_match))
+            .on_action(cx.listener(Self::select_prev_match))
+            .on_action(cx.listener(Self::confirm))
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .font(font_family)
+            .child(self.render_header(cx))
+            .child(div().flex_1().child(content))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use project::{FakeFs, ProjectPath};
+    use serde_json::json;
+    use settings::SettingsStore;
+    use std::path::Path;
+    use workspace::Workspace;
+
+    #[test]
+    fn test_parse_offset_and_pattern() {
+        assert_eq!(parse_offset("0x1f0"), Some(0x1f0));
+        assert_eq!(parse_offset(" 1_024 "), Some(1024));
+        assert_eq!(parse_offset("0xg"), None);
+        assert_eq!(
+            parse_byte_pattern("7f 45 4C46"),
+            Some(vec![0x7f, 0x45, 0x4c, 0x46])
+        );
+        assert_eq!(parse_byte_pattern("\"ELF\""), Some(b"ELF".to_vec()));
+        assert_eq!(parse_byte_pattern("7f 4"), None);
+        assert_eq!(find_pattern(b"aaab", b"aa"), vec![0, 1]);
+        assert_eq!(find_pattern(b"aaab", b""), Vec::<usize>::new());
+
+        let (hex, ascii) = format_row(b"A\x00 z");
+        assert_eq!(hex.trim_end(), "41 00 20 7a");
+        assert_eq!(hex.len(), BYTES_PER_ROW * 3 - 1);
+        assert_eq!(ascii.as_ref(), "A. z");
+    }
+
+    #[gpui::test]
+    async fn test_go_to_offset_and_search(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/root",
+            json!({ "data.bin": "0123456789abcdef0123456789abcdef" }),
+        )
+        .await;
+        let project = Project::test(fs, ["/root".as_ref()], cx).await;
+        let worktree_id = project.update(cx, |project, cx| {
+            project.worktrees().next().unwrap().read(cx).id()
+        });
+        let binary_item = project
+            .update(cx, |project, cx| {
+                project.open_binary(
+                    ProjectPath {
+                        worktree_id,
+                        path: Path::new("data.bin").into(),
+                    },
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+        let (hex_view, cx) = cx.add_window_view(|cx| HexView::new(binary_item, cx));
+
+        hex_view.update(cx, |hex_view, cx| {
+            hex_view.go_to_offset("0x11", cx);
+            assert_eq!(hex_view.selected_offset(), Some(17));
+            hex_view.go_to_offset("32", cx);
+            assert_eq!(hex_view.selected_offset(), Some(17));
+            assert!(hex_view.error.is_some());
+
+            // The search starts at the selected offset and wraps around.
+            hex_view.search("\"89\"", cx);
+            assert_eq!(hex_view.matches(), &[8, 24]);
+            assert_eq!(hex_view.selected_offset(), Some(24));
+            hex_view.select_next_match(&SelectNextMatch, cx);
+            assert_eq!(hex_view.selected_offset(), Some(8));
+            hex_view.search("61 62", cx);
+            assert_eq!(hex_view.matches(), &[10, 26]);
+            assert_eq!(hex_view.selected_offset(), Some(10));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_opening_non_utf8_files(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/root", json!({ "text.txt": "text" })).await;
+        fs.insert_file_bytes("/root/data.bin", vec![0x7f, b'E', b'L', b'F', 0xff, 0xfe])
+            .await;
+        let project = Project::test(fs, ["/root".as_ref()], cx).await;
+        let worktree_id = project.update(cx, |project, cx| {
+            project.worktrees().next().unwrap().read(cx).id()
+        });
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+
+        let item = workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_path((worktree_id, "data.bin"), None, true, cx)
+            })
+            .await
+            .unwrap();
+        let hex_view = item.downcast::<HexView>().expect("expected a hex view");
+        hex_view.update(cx, |hex_view, cx| {
+            assert_eq!(hex_view.len(cx), 6);
+            hex_view.search("ff fe", cx);
+            assert_eq!(hex_view.matches(), &[4]);
+        });
+
+        let item = workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_path((worktree_id, "text.txt"), None, true, cx)
+            })
+            .await
+            .unwrap();
+        assert!(item.downcast::<Editor>().is_some());
+    }
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            language::init(cx);
+            Project::init_settings(cx);
+            workspace::init_settings(cx);
+            editor::init(cx);
+            init(cx);
+        });
+    }
+}
@@ -0,0 +1,452 @@
+use std::sync::Arc;
+
+use editor::Editor;
+use gpui::{
+    actions, img, AnyElement, AppContext, EntityId, EventEmitter, FocusHandle, FocusableView,
+    ImageData, Model, Render, SharedString, Subscription, Task, View, ViewContext, WindowContext,
+};
+use human_bytes::human_bytes;
+use project::{ImageItem, Item as _, Project};
+use ui::prelude::*;
+use workspace::{
+    item::{Item, ItemEvent, ItemHandle, ProjectItem},
+    StatusItemView, Workspace,
+};
+
+actions!(
+    image_viewer,
+    [ZoomIn, ZoomOut, ResetZoom, FitToWindow, OpenAsText]
+);
+
+/// SVGs are rasterized at this multiple of their intrinsic size, so that they stay sharp
+/// on high density displays and when zoomed in a little.
+const SVG_SCALE: f32 = 2.;
+const ZOOM_STEP: f32 = 1.25;
+const MIN_ZOOM: f32 = 0.05;
+const MAX_ZOOM: f32 = 32.;
+
+pub fn init(cx: &mut AppContext) {
+    workspace::register_project_item::<ImageView>(cx);
+    cx.observe_new_views(
+        |workspace: &mut Workspace, _: &mut ViewContext<Workspace>| {
+            workspace.register_action(ImageView::open_as_text);
+        },
+    )
+    .detach();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Zoom {
+    /// Shrinks images larger than the pane to fit in it, showing others at their actual size.
+    Fit,
+    Scale(f32),
+}
+
+/// An image file of the project, decoded and shown in a pane.
+pub struct ImageView {
+    image_item: Model<ImageItem>,
+    is_svg: bool,
+    image: Option<Arc<ImageData>>,
+    error: Option<SharedString>,
+    zoom: Zoom,
+    focus_handle: FocusHandle,
+    decode_task: Option<Task<()>>,
+    _observe_image_item: Subscription,
+}
+
+impl ImageView {
+    pub fn new(image_item: Model<ImageItem>, cx: &mut ViewContext<Self>) -> Self {
+        let mut this = Self {
+            _observe_image_item: cx.observe(&image_item, |this, _, cx| this.decode(cx)),
+            is_svg: image_item.read(cx).is_svg(),
+            image_item,
+            image: None,
+            error: None,
+            zoom: Zoom::Fit,
+            focus_handle: cx.focus_handle(),
+            decode_task: None,
+        };
+        this.decode(cx);
+        this
+    }
+
+    pub fn image_item(&self) -> &Model<ImageItem> {
+        &self.image_item
+    }
+
+    pub fn zoom(&self) -> Zoom {
+        self.zoom
+    }
+
+    /// The width and height of the image in pixels, or of an SVG's view box.
+    pub fn dimensions(&self) -> Option<(u32, u32)> {
+        let size = self.image.as_ref()?.size();
+        let (width, height) = (i32::from(size.width) as u32, i32::from(size.height) as u32);
+        if self.is_svg {
+            Some((
+                (width as f32 / SVG_SCALE).round() as u32,
+                (height as f32 / SVG_SCALE).round() as u32,
+            ))
+        } else {
+            Some((width, height))
+        }
+    }
+
+    fn decode(&mut self, cx: &mut ViewContext<Self>) {
+        let content = self.image_item.read(cx).content().clone();
+        let is_svg = self.is_svg;
+        let decode = cx.background_executor().spawn(async move {
+            if is_svg {
+                ImageData::from_svg(&content, SVG_SCALE)
+            } else {
+                ImageData::from_encoded(&content)
+            }
+        });
+        self.decode_task = Some(cx.spawn(|this, mut cx| async move {
+            let result = decode.await;
+            this.update(&mut cx, |this, cx| {
+                match result {
+                    Ok(image) => {
+                        this.image = Some(Arc::new(image));
+                        this.error = None;
+                    }
+                    Err(error) => {
+                        this.image = None;
+                        this.error = Some(format!("Failed to decode image: {error:#}").into());
+                    }
+                }
+                cx.notify();
+            })
+            .ok();
+        }));
+    }
+
+    fn set_zoom(&mut self, zoom: Zoom, cx: &mut ViewContext<Self>) {
+        self.zoom = match zoom {
+            Zoom::Fit => Zoom::Fit,
+            Zoom::Scale(scale) => Zoom::Scale(scale.clamp(MIN_ZOOM, MAX_ZOOM)),
+        };
+        cx.notify();
+    }
+
+    fn scale(&self) -> f32 {
+        match self.zoom {
+            Zoom::Fit => 1.,
+            Zoom::Scale(scale) => scale,
+        }
+    }
+
+    fn zoom_in(&mut self, _: &ZoomIn, cx: &mut ViewContext<Self>) {
+        self.set_zoom(Zoom::Scale(self.scale() * ZOOM_STEP), cx);
+    }
+
+    fn zoom_out(&mut self, _: &ZoomOut, cx: &mut ViewContext<Self>) {
+        self.set_zoom(Zoom::Scale(self.scale() / ZOOM_STEP), cx);
+    }
+
+    fn reset_zoom(&mut self, _: &ResetZoom, cx: &mut ViewContext<Self>) {
+        self.set_zoom(Zoom::Scale(1.), cx);
+    }
+
+    fn fit_to_window(&mut self, _: &FitToWindow, cx: &mut ViewContext<Self>) {
+        self.set_zoom(Zoom::Fit, cx);
+    }
+
+    /// Reopens the active image in a text editor, for SVGs and for files that aren't
+    /// images despite their extension.
+    fn open_as_text(workspace: &mut Workspace, _: &OpenAsText, cx: &mut ViewContext<Workspace>) {
+        let Some(image_view) = workspace.active_item_as::<ImageView>(cx) else {
+            return;
+        };
+        let Some(project_path) = image_view.read(cx).image_item.read(cx).project_path(cx) else {
+            return;
+        };
+        let open_buffer = workspace
+            .project()
+            .update(cx, |project, cx| project.open_buffer(project_path, cx));
+        cx.spawn(|workspace, mut cx| async move {
+            let buffer = open_buffer.await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                workspace.open_project_item::<Editor>(buffer, false, cx);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+}
+
+impl EventEmitter<ItemEvent> for ImageView {}
+
+impl FocusableView for ImageView {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Item for ImageView {
+    type Event = ItemEvent;
+
+    fn tab_content(&self, _: Option<usize>, selected: bool, cx: &WindowContext) -> AnyElement {
+        let title = self
+            .image_item
+            .read(cx)
+            .path()
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+        Label::new(title)
+            .color(if selected {
+                Color::Default
+            } else {
+                Color::Muted
+            })
+            .into_any_element()
+    }
+
+    fn tab_tooltip_text(&self, cx: &AppContext) -> Option<SharedString> {
+        Some(
+            self.image_item
+                .read(cx)
+                .path()
+                .to_string_lossy()
+                .to_string()
+                .into(),
+        )
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("image viewer")
+    }
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+
+    fn for_each_project_item(
+        &self,
+        cx: &AppContext,
+        f: &mut dyn FnMut(EntityId, &dyn project::Item),
+    ) {
+        f(self.image_item.entity_id(), self.image_item.read(cx))
+    }
+
+    fn is_singleton(&self, _: &AppContext) -> bool {
+        true
+    }
+
+    fn clone_on_split(
+        &self,
+        _: workspace::WorkspaceId,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<View<Self>> {
+        let image_item = self.image_item.clone();
+        Some(cx.new_view(|cx| Self::new(image_item, cx)))
+    }
+}
+
+impl ProjectItem for ImageView {
+    type Item = ImageItem;
+
+    fn for_project_item(
+        _: Model<Project>,
+        image_item: Model<ImageItem>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        Self::new(image_item, cx)
+    }
+}
+
+impl Render for ImageView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let content = match (&self.image, &self.error) {
+            (Some(image), _) => {
+                let (width, height) = self.dimensions().unwrap_or_default();
+                let (width, height) = (width as f32, height as f32);
+                match self.zoom {
+                    Zoom::Fit => div()
+                        .size_full()
+                        .flex()
+                        .items_center()
+                        .justify_center()
+                        .child(
+                            img(image.clone())
+                                .w(px(width))
+                                .h(px(height))
+                                .max_w_full()
+                                .max_h_full(),
+                        ),
+                    Zoom::Scale(scale) => div()
+                        .id("image-scroll")
+                        .size_full()
+                        .overflow_scroll()
+                        .child(
+                            div()
+                                .min_w_full()
+                                .min_h_full()
+                                .flex()
+                                .items_center()
+                                .justify_center()
+                                .child(
+                                    img(image.clone())
+                                        .flex_none()
+                                        .w(px(width * scale))
+                                        .h(px(height * scale)),
+                                ),
+                        ),
+                }
+            }
+            (None, Some(error)) => div()
+                .size_full()
+                .flex()
+                .items_center()
+                .justify_center()
+                .child(Label::new(error.clone()).color(Color::Error)),
+            (None, None) => div(),
+        };
+
+        div()
+            .key_context("ImageViewer")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::zoom_in))
+            .on_action(cx.listener(Self::zoom_out))
+            .on_action(cx.listener(Self::reset_zoom))
+            .on_action(cx.listener(Self::fit_to_window))
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(content)
+    }
+}
+
+/// Shows the dimensions, file size and zoom of the active image in the status bar.
+pub struct ImageInfo {
+    image_view: Option<View<ImageView>>,
+    _observe_image_view: Option<Subscription>,
+}
+
+impl ImageInfo {
+    pub fn new() -> Self {
+        Self {
+            image_view: None,
+            _observe_image_view: None,
+        }
+    }
+}
+
+impl Default for ImageInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Render for ImageInfo {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        div().when_some(self.image_view.as_ref(), |el, image_view| {
+            let image_view = image_view.read(cx);
+            let mut parts = Vec::new();
+            if let Some((width, height)) = image_view.dimensions() {
+                parts.push(format!("{width} × {height}"));
+            }
+            let file_size = image_view.image_item.read(cx).content().len();
+            parts.push(human_bytes(file_size as f64));
+            parts.push(match image_view.zoom {
+                Zoom::Fit => "Fit".to_string(),
+                Zoom::Scale(scale) => format!("{:.0}%", scale * 100.),
+            });
+            el.child(Label::new(parts.join(" · ")).size(LabelSize::Small))
+        })
+    }
+}
+
+impl StatusItemView for ImageInfo {
+    fn set_active_pane_item(
+        &mut self,
+        active_pane_item: Option<&dyn ItemHandle>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.image_view = active_pane_item.and_then(|item| item.downcast::<ImageView>());
+        self._observe_image_view = self
+            .image_view
+            .as_ref()
+            .map(|image_view| cx.observe(image_view, |_, _, cx| cx.notify()));
+        cx.notify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+    use std::path::Path;
+
+    #[gpui::test]
+    async fn test_open_and_reload_image(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/root",
+            json!({
+                "icon.svg": svg(16, 8),
+                "notes.txt": "",
+            }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), ["/root".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+
+        let item = workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_abs_path("/root/icon.svg".into(), true, cx)
+            })
+            .await
+            .unwrap();
+        let image_view = item.downcast::<ImageView>().unwrap();
+        cx.run_until_parked();
+        image_view.update(cx, |image_view, cx| {
+            assert_eq!(image_view.dimensions(), Some((16, 8)));
+            image_view.zoom_in(&ZoomIn, cx);
+            assert_eq!(image_view.zoom(), Zoom::Scale(ZOOM_STEP));
+            image_view.fit_to_window(&FitToWindow, cx);
+            assert_eq!(image_view.zoom(), Zoom::Fit);
+        });
+
+        fs.insert_file("/root/icon.svg", svg(32, 24)).await;
+        cx.run_until_parked();
+        image_view.update(cx, |image_view, _| {
+            assert_eq!(image_view.dimensions(), Some((32, 24)));
+        });
+
+        // Images can still be edited as text.
+        workspace.update(cx, |workspace, cx| {
+            ImageView::open_as_text(workspace, &OpenAsText, cx)
+        });
+        cx.run_until_parked();
+        workspace.update(cx, |workspace, cx| {
+            let editor = workspace.active_item_as::<Editor>(cx).unwrap();
+            assert_eq!(editor.read(cx).text(cx), svg(32, 24));
+            assert_eq!(
+                editor.project_path(cx).unwrap().path.as_ref(),
+                Path::new("icon.svg")
+            );
+        });
+    }
+
+    fn svg(width: u32, height: u32) -> String {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}"><rect width="{width}" height="{height}" fill="red"/></svg>"#
+        )
+    }
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            language::init(cx);
+            Project::init_settings(cx);
+            workspace::init_settings(cx);
+            editor::init(cx);
+            init(cx);
+        });
+    }
+}
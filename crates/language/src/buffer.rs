@@ -13,7 +13,7 @@ use crate::{
         SyntaxLayer, SyntaxMap, SyntaxMapCapture, SyntaxMapCaptures, SyntaxMapMatches,
         SyntaxSnapshot, ToTreeSitterPoint,
     },
-    CodeLabel, LanguageScope, Outline,
+    CodeLabel, LanguageScope, Outline, RunnableKind,
 };
 use anyhow::{anyhow, Context, Result};
 pub use clock::ReplicaId;
@@ -110,6 +110,8 @@ pub struct Buffer {
     completion_triggers_timestamp: clock::Lamport,
     deferred_ops: OperationQueue<Operation>,
     capability: Capability,
+    /// Whether this buffer's file was too large to be parsed and diffed.
+    large_file: bool,
 }
 
 /// An immutable, cheaply cloneable representation of a fixed
@@ -277,6 +279,17 @@ pub struct CodeAction {
     pub lsp_action: lsp::CodeAction,
 }
 
+/// A test, entry point or benchmark detected by a language's `runnables.scm` query.
+#[derive(Clone, Debug)]
+pub struct Runnable {
+    /// What the runnable is.
+    pub kind: RunnableKind,
+    /// The range of the runnable's name.
+    pub range: Range<usize>,
+    /// The language the runnable is written in.
+    pub language: Arc<Language>,
+}
+
 /// An operation used to synchronize this buffer with its other replicas.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Operation {
@@ -698,6 +711,23 @@ impl Buffer {
             completion_triggers: Default::default(),
             completion_triggers_timestamp: Default::default(),
             deferred_ops: OperationQueue::new(),
+            large_file: false,
+        }
+    }
+
+    /// Whether this buffer's file is above the large file threshold, in which
+    /// case it is neither parsed nor diffed against its git index.
+    pub fn is_large_file(&self) -> bool {
+        self.large_file
+    }
+
+    /// Marks the buffer as holding a large file, dropping any syntax tree and
+    /// git diff it already computed.
+    pub fn set_large_file(&mut self, large_file: bool) {
+        self.large_file = large_file;
+        if large_file {
+            self.syntax_map.lock().clear();
+            self.git_diff = git::diff::BufferDiff::new();
         }
     }
 
@@ -800,6 +830,13 @@ impl Buffer {
         cx: &mut ModelContext<Self>,
     ) -> oneshot::Receiver<Option<Transaction>> {
         let (tx, rx) = futures::channel::oneshot::channel();
+        // Large files would be loaded whole and diffed against the buffer, so
+        // they keep their contents until they're reopened. Edits to them are
+        // still reported as conflicts with the file on disk.
+        if self.large_file {
+            tx.send(None).ok();
+            return rx;
+        }
         let prev_version = self.text.version();
         self.reload_task = Some(cx.spawn(|this, mut cx| async move {
             let Some((new_mtime, new_text)) = this.update(&mut cx, |this, cx| {
@@ -924,6 +961,9 @@ impl Buffer {
 
     /// Recomputes the Git diff status.
     pub fn git_diff_recalc(&mut self, cx: &mut ModelContext<Self>) -> Option<Task<()>> {
+        if self.large_file {
+            return None;
+        }
         let diff_base = self.diff_base.clone()?; // TODO: Make this an Arc
         let snapshot = self.snapshot();
 
@@ -1025,7 +1065,7 @@ impl Buffer {
     /// for the same buffer, we only initiate a new parse if we are not already
     /// parsing in the background.
     pub fn reparse(&mut self, cx: &mut ModelContext<Self>) {
-        if self.parsing_in_background {
+        if self.parsing_in_background || self.large_file {
             return;
         }
         let language = if let Some(language) = self.language.clone() {
@@ -2069,6 +2109,48 @@ impl Buffer {
         redone
     }
 
+    /// Moves the buffer to the given state of its undo tree, by applying the difference
+    /// as a new transaction.
+    pub fn jump_to_undo_tree_node(
+        &mut self,
+        ix: usize,
+        cx: &mut ModelContext<Self>,
+    ) -> Option<TransactionId> {
+        let Some(edit) = self.text.start_undo_tree_jump(ix) else {
+            cx.notify();
+            return None;
+        };
+
+        self.finalize_last_transaction();
+        self.start_transaction();
+        self.edit([(edit.range, edit.new_text)], None, cx);
+        let transaction_id = self.end_transaction(cx);
+        self.finalize_last_transaction();
+        transaction_id
+    }
+
+    /// Restores an undo tree that was persisted for this buffer's contents.
+    pub fn restore_undo_tree(
+        &mut self,
+        undo_tree: SerializedUndoTree,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let was_dirty = self.is_dirty();
+        let old_version = self.version.clone();
+
+        let operations = self.text.restore_undo_tree(undo_tree)?;
+        let edited = !operations.is_empty();
+        for operation in operations {
+            self.send_operation(Operation::Buffer(operation), cx);
+        }
+        if edited {
+            self.did_edit(&old_version, was_dirty, cx);
+        } else {
+            cx.notify();
+        }
+        Ok(())
+    }
+
     /// Override current completion triggers with the user-provided completion triggers.
     pub fn set_completion_triggers(&mut self, triggers: Vec<String>, cx: &mut ModelContext<Self>) {
         self.completion_triggers = triggers.clone();
@@ -2664,6 +2746,51 @@ impl BufferSnapshot {
         result
     }
 
+    /// Returns the range of the statement that begins on the given row: the outermost
+    /// syntax node starting at the row's first non-whitespace character that isn't an
+    /// item of a list of statements, such as a block or the whole file.
+    ///
+    /// Returns `None` for blank rows and rows that continue a node begun on an earlier
+    /// row, such as the lines of a multi-line string.
+    pub fn statement_range_for_row(&self, row: u32) -> Option<Range<usize>> {
+        let indent = self.indent_size_for_line(row).len;
+        if self.line_len(row) <= indent {
+            return None;
+        }
+        let start = Point::new(row, indent).to_offset(self);
+        let layer = self.syntax_layer_at(start)?;
+        let mut node = layer.node().descendant_for_byte_range(start, start)?;
+        if node.start_byte() != start {
+            return None;
+        }
+
+        while let Some(parent) = node.parent() {
+            if parent.start_byte() != start || parent.parent().is_none() {
+                break;
+            }
+            // Anonymous tokens like `def` or `if` always belong to the construct they
+            // begin, while a named node may be one statement among many in a block.
+            if node.is_named() && self.is_statement_list(parent) {
+                break;
+            }
+            node = parent;
+        }
+
+        Some(node.byte_range())
+    }
+
+    /// Whether more than one of the node's named children begin their own rows.
+    fn is_statement_list(&self, node: tree_sitter::Node) -> bool {
+        let mut cursor = node.walk();
+        node.named_children(&mut cursor)
+            .filter(|child| {
+                let position = child.start_position();
+                position.column as u32 == self.indent_size_for_line(position.row as u32).len
+            })
+            .nth(1)
+            .is_some()
+    }
+
     /// Returns the outline for the buffer.
     ///
     /// This method allows passing an optional [SyntaxTheme] to
@@ -2838,6 +2965,70 @@ impl BufferSnapshot {
         self.syntax.matches(range, self, query)
     }
 
+    /// Returns the runnables whose names intersect the given range.
+    pub fn runnables_in_range<T: ToOffset>(&self, range: Range<T>) -> Vec<Runnable> {
+        let range = range.start.to_offset(self)..range.end.to_offset(self);
+        let mut matches = self.syntax.matches(range.clone(), &self.text, |grammar| {
+            grammar.runnable_config.as_ref().map(|c| &c.query)
+        });
+        let configs = matches
+            .grammars()
+            .iter()
+            .map(|grammar| grammar.runnable_config.as_ref().unwrap())
+            .collect::<Vec<_>>();
+
+        let mut runnables = Vec::<Runnable>::new();
+        while let Some(mat) = matches.peek() {
+            let config = configs[mat.grammar_index];
+            let name_range = mat
+                .captures
+                .iter()
+                .find(|capture| capture.index == config.run_capture_ix)
+                .map(|capture| capture.node.byte_range());
+            if let Some(name_range) = name_range {
+                let is_duplicate = runnables
+                    .iter()
+                    .any(|runnable| runnable.range == name_range);
+                if name_range.end >= range.start && name_range.start <= range.end && !is_duplicate {
+                    if let Some(language) = self.language_at(name_range.start) {
+                        runnables.push(Runnable {
+                            kind: config.pattern_kinds[mat.pattern_index],
+                            range: name_range,
+                            language,
+                        });
+                    }
+                }
+            }
+            matches.advance();
+        }
+        runnables.sort_by_key(|runnable| runnable.range.start);
+        runnables
+    }
+
+    /// Returns the names of the outline items enclosing the given runnable, from the
+    /// outermost one down to the runnable itself, such as `["tests", "test_foo"]`.
+    pub fn runnable_symbol_path(&self, runnable: &Runnable) -> Vec<String> {
+        let name = self
+            .text_for_range(runnable.range.clone())
+            .collect::<String>();
+        let mut path = self
+            .symbols_containing(runnable.range.start, None)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| {
+                item.name_ranges
+                    .iter()
+                    .map(|range| item.text[range.clone()].trim())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>();
+        if path.last() != Some(&name) {
+            path.push(name);
+        }
+        path
+    }
+
     /// Returns bracket range pairs overlapping or adjacent to `range`
     pub fn bracket_ranges<'a, T: ToOffset>(
         &'a self,
@@ -662,6 +662,80 @@ async fn test_outline_with_extra_context(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+fn test_runnables(cx: &mut AppContext) {
+    let language = rust_lang()
+        .with_runnable_query(
+            r#"
+            (
+                (attribute_item) @_attribute
+                .
+                [(attribute_item) (line_comment)]*
+                .
+                (function_item
+                    name: (_) @run)
+                (#match? @_attribute "^#\\[(\\w+::)*test\\b")
+                (#set! "kind" "test")
+            )
+            (source_file
+                (function_item
+                    name: (_) @run
+                    (#eq? @run "main")
+                    (#set! "kind" "main")))
+            "#,
+        )
+        .unwrap();
+
+    let text = r#"
+        fn main() {}
+
+        #[cfg(test)]
+        mod tests {
+            #[test]
+            fn test_a() {}
+
+            #[gpui::test(iterations = 10)]
+            #[should_panic]
+            async fn test_b() {}
+
+            fn helper() {}
+        }
+    "#
+    .unindent();
+
+    let buffer = cx.new_model(|cx| {
+        Buffer::new(0, BufferId::new(cx.entity_id().as_u64()).unwrap(), text)
+            .with_language(Arc::new(language), cx)
+    });
+    let snapshot = buffer.read(cx).snapshot();
+    let runnables = snapshot.runnables_in_range(0..snapshot.len());
+    assert_eq!(
+        runnables
+            .iter()
+            .map(|runnable| (runnable.kind, snapshot.runnable_symbol_path(runnable)))
+            .collect::<Vec<_>>(),
+        &[
+            (RunnableKind::Main, vec!["main".to_string()]),
+            (
+                RunnableKind::Test,
+                vec!["tests".to_string(), "test_a".to_string()]
+            ),
+            (
+                RunnableKind::Test,
+                vec!["tests".to_string(), "test_b".to_string()]
+            ),
+        ]
+    );
+
+    let test_b_offset = snapshot.text().find("test_b").unwrap();
+    let runnables = snapshot.runnables_in_range(test_b_offset..test_b_offset);
+    assert_eq!(runnables.len(), 1);
+    assert_eq!(
+        runnables[0].range,
+        test_b_offset..test_b_offset + "test_b".len()
+    );
+}
+
 #[gpui::test]
 async fn test_symbols_containing(cx: &mut gpui::TestAppContext) {
     let text = r#"
@@ -938,6 +1012,37 @@ fn test_range_for_syntax_ancestor(cx: &mut AppContext) {
     }
 }
 
+#[gpui::test]
+fn test_statement_range_for_row(cx: &mut AppContext) {
+    cx.new_model(|cx| {
+        let text = indoc! {r#"
+            fn a() {
+                let b = 1;
+
+                c(
+                    b,
+                );
+            }
+        "#};
+        let buffer = Buffer::new(0, BufferId::new(cx.entity_id().as_u64()).unwrap(), text)
+            .with_language(Arc::new(rust_lang()), cx);
+        let snapshot = buffer.snapshot();
+        let statement = |row| {
+            snapshot
+                .statement_range_for_row(row)
+                .map(|range| snapshot.text_for_range(range).collect::<String>())
+        };
+
+        assert_eq!(statement(0).as_deref(), Some(text.trim_end()));
+        assert_eq!(statement(1).as_deref(), Some("let b = 1;"));
+        assert_eq!(statement(2), None);
+        assert_eq!(statement(3).as_deref(), Some("c(\n        b,\n    );"));
+        assert_eq!(statement(4).as_deref(), Some("b"));
+
+        buffer
+    });
+}
+
 #[gpui::test]
 fn test_autoindent_with_soft_tabs(cx: &mut AppContext) {
     init_settings(cx, |_| {});
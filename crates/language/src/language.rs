@@ -654,6 +654,7 @@ pub struct Grammar {
     pub(crate) indents_config: Option<IndentConfig>,
     pub outline_config: Option<OutlineConfig>,
     pub embedding_config: Option<EmbeddingConfig>,
+    pub runnable_config: Option<RunnableConfig>,
     pub(crate) injection_config: Option<InjectionConfig>,
     pub(crate) override_config: Option<OverrideConfig>,
     pub(crate) highlight_map: Mutex<HighlightMap>,
@@ -685,6 +686,26 @@ pub struct EmbeddingConfig {
     pub keep_capture_ix: Option<u32>,
 }
 
+/// Detects the tests, entry points and benchmarks of a language, which can be run
+/// from the gutter.
+#[derive(Debug)]
+pub struct RunnableConfig {
+    pub query: Query,
+    /// The capture of the runnable's name, on whose row the run button is shown.
+    pub run_capture_ix: u32,
+    /// The kind of runnable matched by each pattern, set with `(#set! kind "...")`.
+    pub pattern_kinds: Vec<RunnableKind>,
+}
+
+/// What a runnable detected by a `runnables.scm` query is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RunnableKind {
+    #[default]
+    Test,
+    Main,
+    Benchmark,
+}
+
 struct InjectionConfig {
     query: Query,
     content_capture_ix: u32,
@@ -738,6 +759,7 @@ impl Language {
                     brackets_config: None,
                     outline_config: None,
                     embedding_config: None,
+                    runnable_config: None,
                     indents_config: None,
                     injection_config: None,
                     override_config: None,
@@ -784,6 +806,11 @@ impl Language {
                 .with_embedding_query(query.as_ref())
                 .context("Error loading embedding query")?;
         }
+        if let Some(query) = queries.runnables {
+            self = self
+                .with_runnable_query(query.as_ref())
+                .context("Error loading runnables query")?;
+        }
         if let Some(query) = queries.injections {
             self = self
                 .with_injection_query(query.as_ref())
@@ -867,6 +894,37 @@ impl Language {
         Ok(self)
     }
 
+    pub fn with_runnable_query(mut self, source: &str) -> Result<Self> {
+        let grammar = self.grammar_mut();
+        let query = Query::new(&grammar.ts_language, source)?;
+        let mut run_capture_ix = None;
+        get_capture_indices(&query, &mut [("run", &mut run_capture_ix)]);
+        let pattern_kinds = (0..query.pattern_count())
+            .map(|ix| {
+                let mut kind = RunnableKind::default();
+                for setting in query.property_settings(ix) {
+                    if setting.key.as_ref() == "kind" {
+                        match setting.value.as_deref() {
+                            Some("test") => kind = RunnableKind::Test,
+                            Some("main") => kind = RunnableKind::Main,
+                            Some("benchmark") => kind = RunnableKind::Benchmark,
+                            value => log::warn!("unknown runnable kind {value:?}"),
+                        }
+                    }
+                }
+                kind
+            })
+            .collect();
+        if let Some(run_capture_ix) = run_capture_ix {
+            grammar.runnable_config = Some(RunnableConfig {
+                query,
+                run_capture_ix,
+                pattern_kinds,
+            });
+        }
+        Ok(self)
+    }
+
     pub fn with_brackets_query(mut self, source: &str) -> Result<Self> {
         let grammar = self.grammar_mut();
         let query = Query::new(&grammar.ts_language, source)?;
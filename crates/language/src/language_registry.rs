@@ -92,6 +92,7 @@ pub const QUERY_FILENAME_PREFIXES: &[(
     ("outline", |q| &mut q.outline),
     ("indents", |q| &mut q.indents),
     ("embedding", |q| &mut q.embedding),
+    ("runnables", |q| &mut q.runnables),
     ("injections", |q| &mut q.injections),
     ("overrides", |q| &mut q.overrides),
     ("redactions", |q| &mut q.redactions),
@@ -105,6 +106,7 @@ pub struct LanguageQueries {
     pub indents: Option<Cow<'static, str>>,
     pub outline: Option<Cow<'static, str>>,
     pub embedding: Option<Cow<'static, str>>,
+    pub runnables: Option<Cow<'static, str>>,
     pub injections: Option<Cow<'static, str>>,
     pub overrides: Option<Cow<'static, str>>,
     pub redactions: Option<Cow<'static, str>>,
@@ -0,0 +1,481 @@
+use std::{
+    any::{Any, TypeId},
+    ops::Range,
+    path::PathBuf,
+};
+
+use anyhow::Result;
+use editor::{Editor, EditorEvent, MultiBuffer};
+use futures::future::try_join_all;
+use gpui::{
+    actions, AnyElement, AnyView, AppContext, EventEmitter, FocusHandle, FocusableView, Model,
+    Render, SharedString, Subscription, Task, View, ViewContext, VisualContext, WeakView,
+    WindowContext,
+};
+use language::{Point, ToPoint};
+use project::{repository::GitFileStatus, worktree::Snapshot, Project, ProjectPath};
+use ui::prelude::*;
+use workspace::{
+    item::{BreadcrumbText, Item, ItemEvent},
+    ItemNavHistory, Pane, ToolbarItemLocation, Workspace,
+};
+
+actions!(merge_conflicts, [Deploy]);
+
+const CONTEXT_LINE_COUNT: u32 = 1;
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(ProjectConflictsEditor::register)
+        .detach();
+}
+
+/// A multibuffer with the conflicts of every file that git reports as conflicted, which
+/// can be resolved in place.
+pub struct ProjectConflictsEditor {
+    project: Model<Project>,
+    workspace: WeakView<Workspace>,
+    focus_handle: FocusHandle,
+    editor: View<Editor>,
+    excerpts: Model<MultiBuffer>,
+    conflicted_paths: Vec<ProjectPath>,
+    update_excerpts_task: Task<Result<()>>,
+    worktree_subscriptions: Vec<Subscription>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl ProjectConflictsEditor {
+    fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(Self::deploy);
+    }
+
+    fn deploy(workspace: &mut Workspace, _: &Deploy, cx: &mut ViewContext<Workspace>) {
+        if let Some(existing) = workspace.item_of_type::<ProjectConflictsEditor>(cx) {
+            workspace.activate_item(&existing, cx);
+        } else {
+            let workspace_handle = cx.view().downgrade();
+            let conflicts = cx.new_view(|cx| {
+                ProjectConflictsEditor::new(workspace.project().clone(), workspace_handle, cx)
+            });
+            workspace.add_item(Box::new(conflicts), cx);
+        }
+    }
+
+    fn new(
+        project: Model<Project>,
+        workspace: WeakView<Workspace>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let focus_handle = cx.focus_handle();
+        let excerpts = cx.new_model(|cx| {
+            MultiBuffer::new(project.read(cx).replica_id(), project.read(cx).capability())
+        });
+        let editor = cx.new_view(|cx| {
+            let mut editor = Editor::for_multibuffer(excerpts.clone(), Some(project.clone()), cx);
+            editor.set_vertical_scroll_margin(5, cx);
+            editor
+        });
+        let subscriptions = vec![
+            cx.subscribe(&project, |this, _, event, cx| match event {
+                project::Event::WorktreeAdded | project::Event::WorktreeRemoved(_) => {
+                    this.subscribe_to_worktrees(cx);
+                    this.update_excerpts(cx);
+                }
+                _ => {}
+            }),
+            cx.on_focus_in(&focus_handle, |this, cx| {
+                if this.focus_handle.is_focused(cx) && !this.conflicted_paths.is_empty() {
+                    this.editor.focus_handle(cx).focus(cx)
+                }
+            }),
+            cx.subscribe(&editor, |_, _, event: &EditorEvent, cx| {
+                cx.emit(event.clone())
+            }),
+        ];
+
+        let mut this = Self {
+            project,
+            workspace,
+            focus_handle,
+            editor,
+            excerpts,
+            conflicted_paths: Vec::new(),
+            update_excerpts_task: Task::ready(Ok(())),
+            worktree_subscriptions: Vec::new(),
+            _subscriptions: subscriptions,
+        };
+        this.subscribe_to_worktrees(cx);
+        this.update_excerpts(cx);
+        this
+    }
+
+    /// Files become conflicted or get resolved as the worktrees' repositories change.
+    fn subscribe_to_worktrees(&mut self, cx: &mut ViewContext<Self>) {
+        let worktrees = self
+            .project
+            .read(cx)
+            .visible_worktrees(cx)
+            .collect::<Vec<_>>();
+        self.worktree_subscriptions = worktrees
+            .iter()
+            .map(|worktree| {
+                cx.subscribe(worktree, |this, _, event, cx| {
+                    if let project::worktree::Event::UpdatedGitRepositories(_) = event {
+                        this.update_excerpts(cx);
+                    }
+                })
+            })
+            .collect();
+    }
+
+    /// Rebuilds the excerpts when files become conflicted or get resolved. Resolving
+    /// conflicts within a file leaves it in place until it's staged.
+    ///
+    /// The worktrees and the conflicted files are scanned in the background.
+    fn update_excerpts(&mut self, cx: &mut ViewContext<Self>) {
+        let worktrees = self
+            .project
+            .read(cx)
+            .visible_worktrees(cx)
+            .map(|worktree| worktree.read(cx).snapshot())
+            .collect::<Vec<_>>();
+        self.update_excerpts_task = cx.spawn(|this, mut cx| async move {
+            let conflicted_paths = cx
+                .background_executor()
+                .spawn(async move { find_conflicted_paths(&worktrees) })
+                .await;
+            let open_buffers = this.update(&mut cx, |this, cx| {
+                if conflicted_paths == this.conflicted_paths {
+                    return None;
+                }
+                Some(
+                    conflicted_paths
+                        .iter()
+                        .map(|path| {
+                            this.project
+                                .update(cx, |project, cx| project.open_buffer(path.clone(), cx))
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })?;
+            let Some(open_buffers) = open_buffers else {
+                return Ok(());
+            };
+
+            let buffers = try_join_all(open_buffers).await?;
+            let snapshots = cx.update(|cx| {
+                buffers
+                    .iter()
+                    .map(|buffer| buffer.read(cx).text_snapshot())
+                    .collect::<Vec<_>>()
+            })?;
+            let conflict_ranges = cx
+                .background_executor()
+                .spawn(async move {
+                    snapshots
+                        .iter()
+                        .map(|snapshot| {
+                            git::conflict::parse_conflicts(snapshot)
+                                .into_iter()
+                                .map(|conflict| {
+                                    conflict.range.start.to_point(snapshot)
+                                        ..conflict.range.end.to_point(snapshot)
+                                })
+                                .collect::<Vec<Range<Point>>>()
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .await;
+
+            this.update(&mut cx, |this, cx| {
+                this.conflicted_paths = conflicted_paths;
+                this.excerpts.update(cx, |excerpts, cx| {
+                    excerpts.clear(cx);
+                    for (buffer, ranges) in buffers.into_iter().zip(conflict_ranges) {
+                        if !ranges.is_empty() {
+                            excerpts.push_excerpts_with_context_lines(
+                                buffer,
+                                ranges,
+                                CONTEXT_LINE_COUNT,
+                                cx,
+                            );
+                        }
+                    }
+                });
+                cx.notify();
+            })
+        });
+    }
+
+    fn conflict_count(&self, cx: &AppContext) -> usize {
+        self.editor.read(cx).conflicts().len()
+    }
+}
+
+/// The files of the given worktrees that have conflicts, in the order they're shown in.
+fn find_conflicted_paths(worktrees: &[Snapshot]) -> Vec<ProjectPath> {
+    worktrees
+        .iter()
+        .flat_map(|worktree| {
+            let worktree_id = worktree.id();
+            worktree
+                .files(false, 0)
+                .filter(|entry| entry.git_status == Some(GitFileStatus::Conflict))
+                .map(move |entry| ProjectPath {
+                    worktree_id,
+                    path: entry.path.clone(),
+                })
+        })
+        .collect()
+}
+
+impl EventEmitter<EditorEvent> for ProjectConflictsEditor {}
+
+impl FocusableView for ProjectConflictsEditor {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for ProjectConflictsEditor {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let child = if self.conflicted_paths.is_empty() {
+            div()
+                .bg(cx.theme().colors().editor_background)
+                .flex()
+                .items_center()
+                .justify_center()
+                .size_full()
+                .child(Label::new("No merge conflicts in workspace"))
+        } else {
+            div().size_full().child(self.editor.clone())
+        };
+
+        div()
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .child(child)
+    }
+}
+
+impl Item for ProjectConflictsEditor {
+    type Event = EditorEvent;
+
+    fn to_item_events(event: &EditorEvent, f: impl FnMut(ItemEvent)) {
+        Editor::to_item_events(event, f)
+    }
+
+    fn deactivated(&mut self, cx: &mut ViewContext<Self>) {
+        self.editor.update(cx, |editor, cx| editor.deactivated(cx));
+    }
+
+    fn navigate(&mut self, data: Box<dyn Any>, cx: &mut ViewContext<Self>) -> bool {
+        self.editor
+            .update(cx, |editor, cx| editor.navigate(data, cx))
+    }
+
+    fn tab_tooltip_text(&self, _: &AppContext) -> Option<SharedString> {
+        Some("Merge Conflicts".into())
+    }
+
+    fn tab_content(
+        &self,
+        _detail: Option<usize>,
+        selected: bool,
+        cx: &WindowContext,
+    ) -> AnyElement {
+        let color = if selected {
+            Color::Default
+        } else {
+            Color::Muted
+        };
+        let conflict_count = self.conflict_count(cx);
+        if conflict_count == 0 {
+            Label::new("No conflicts").color(color).into_any_element()
+        } else {
+            h_flex()
+                .gap_1()
+                .child(Icon::new(IconName::ExclamationTriangle).color(Color::Conflict))
+                .child(Label::new(conflict_count.to_string()).color(color))
+                .into_any_element()
+        }
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("merge conflicts")
+    }
+
+    fn for_each_project_item(
+        &self,
+        cx: &AppContext,
+        f: &mut dyn FnMut(gpui::EntityId, &dyn project::Item),
+    ) {
+        self.editor.for_each_project_item(cx, f)
+    }
+
+    fn is_singleton(&self, _: &AppContext) -> bool {
+        false
+    }
+
+    fn set_nav_history(&mut self, nav_history: ItemNavHistory, cx: &mut ViewContext<Self>) {
+        self.editor.update(cx, |editor, _| {
+            editor.set_nav_history(Some(nav_history));
+        });
+    }
+
+    fn clone_on_split(
+        &self,
+        _workspace_id: workspace::WorkspaceId,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<View<Self>>
+    where
+        Self: Sized,
+    {
+        Some(cx.new_view(|cx| {
+            ProjectConflictsEditor::new(self.project.clone(), self.workspace.clone(), cx)
+        }))
+    }
+
+    fn is_dirty(&self, cx: &AppContext) -> bool {
+        self.excerpts.read(cx).is_dirty(cx)
+    }
+
+    fn has_conflict(&self, cx: &AppContext) -> bool {
+        self.excerpts.read(cx).has_conflict(cx)
+    }
+
+    fn can_save(&self, _: &AppContext) -> bool {
+        true
+    }
+
+    fn save(&mut self, project: Model<Project>, cx: &mut ViewContext<Self>) -> Task<Result<()>> {
+        self.editor.save(project, cx)
+    }
+
+    fn save_as(
+        &mut self,
+        _: Model<Project>,
+        _: PathBuf,
+        _: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        unreachable!()
+    }
+
+    fn reload(&mut self, project: Model<Project>, cx: &mut ViewContext<Self>) -> Task<Result<()>> {
+        self.editor.reload(project, cx)
+    }
+
+    fn act_as_type<'a>(
+        &'a self,
+        type_id: TypeId,
+        self_handle: &'a View<Self>,
+        _: &'a AppContext,
+    ) -> Option<AnyView> {
+        if type_id == TypeId::of::<Self>() {
+            Some(self_handle.to_any())
+        } else if type_id == TypeId::of::<Editor>() {
+            Some(self.editor.to_any())
+        } else {
+            None
+        }
+    }
+
+    fn breadcrumb_location(&self) -> ToolbarItemLocation {
+        ToolbarItemLocation::PrimaryLeft
+    }
+
+    fn breadcrumbs(&self, theme: &theme::Theme, cx: &AppContext) -> Option<Vec<BreadcrumbText>> {
+        self.editor.breadcrumbs(theme, cx)
+    }
+
+    fn added_to_workspace(&mut self, workspace: &mut Workspace, cx: &mut ViewContext<Self>) {
+        self.editor
+            .update(cx, |editor, cx| editor.added_to_workspace(workspace, cx));
+    }
+
+    fn serialized_item_kind() -> Option<&'static str> {
+        Some("merge_conflicts")
+    }
+
+    fn deserialize(
+        project: Model<Project>,
+        workspace: WeakView<Workspace>,
+        _workspace_id: workspace::WorkspaceId,
+        _item_id: workspace::ItemId,
+        cx: &mut ViewContext<Pane>,
+    ) -> Task<Result<View<Self>>> {
+        Task::ready(Ok(cx.new_view(|cx| Self::new(project, workspace, cx))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+    use std::path::Path;
+
+    #[gpui::test]
+    async fn test_project_conflicts(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/root",
+            json!({
+                ".git": {},
+                "a.txt": "one\n<<<<<<< HEAD\ntwo\n=======\n2\n>>>>>>> feature\nthree\n",
+                "b.txt": "clean\n",
+            }),
+        )
+        .await;
+        fs.set_status_for_repo_via_git_operation(
+            Path::new("/root/.git"),
+            &[(Path::new("a.txt"), GitFileStatus::Conflict)],
+        );
+        let project = Project::test(fs.clone(), ["/root".as_ref()], cx).await;
+        cx.executor().run_until_parked();
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+
+        let conflicts = workspace.update(cx, |workspace, cx| {
+            let workspace_handle = cx.view().downgrade();
+            cx.new_view(|cx| ProjectConflictsEditor::new(project.clone(), workspace_handle, cx))
+        });
+        cx.run_until_parked();
+        cx.executor()
+            .advance_clock(editor::CONFLICTS_DEBOUNCE_TIMEOUT);
+        cx.run_until_parked();
+
+        conflicts.update(cx, |conflicts, cx| {
+            assert_eq!(conflicts.conflicted_paths.len(), 1);
+            assert_eq!(
+                conflicts.editor.update(cx, |editor, cx| editor.text(cx)),
+                "one\n<<<<<<< HEAD\ntwo\n=======\n2\n>>>>>>> feature\nthree\n"
+            );
+            assert_eq!(conflicts.conflict_count(cx), 1);
+        });
+
+        // Files are removed once git no longer reports them as conflicted.
+        fs.set_status_for_repo_via_git_operation(Path::new("/root/.git"), &[]);
+        cx.run_until_parked();
+        conflicts.update(cx, |conflicts, cx| {
+            assert!(conflicts.conflicted_paths.is_empty());
+            assert_eq!(
+                conflicts.editor.update(cx, |editor, cx| editor.text(cx)),
+                ""
+            );
+        });
+    }
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            language::init(cx);
+            Project::init_settings(cx);
+            workspace::init_settings(cx);
+            editor::init(cx);
+        });
+    }
+}
@@ -0,0 +1,48 @@
+use crate::{file_content::FileContent, Item, Project, ProjectEntryId, ProjectPath, Worktree};
+use gpui::{AppContext, Model, ModelContext, WeakModel};
+use std::{path::Path, sync::Arc};
+
+/// A project file that isn't valid UTF-8, opened as raw bytes rather than as a
+/// buffer, and reloaded whenever the file's worktree entry changes on disk.
+pub struct BinaryItem {
+    file: FileContent,
+}
+
+impl BinaryItem {
+    pub(crate) fn new(
+        project: WeakModel<Project>,
+        project_path: ProjectPath,
+        worktree: &Model<Worktree>,
+        content: Vec<u8>,
+        cx: &mut ModelContext<Self>,
+    ) -> Self {
+        Self {
+            file: FileContent::new(
+                project,
+                project_path,
+                worktree,
+                content,
+                |this: &mut Self| &mut this.file,
+                cx,
+            ),
+        }
+    }
+
+    pub fn path(&self) -> &Arc<Path> {
+        &self.file.project_path.path
+    }
+
+    pub fn content(&self) -> &Arc<[u8]> {
+        &self.file.content
+    }
+}
+
+impl Item for BinaryItem {
+    fn entry_id(&self, _: &AppContext) -> Option<ProjectEntryId> {
+        self.file.entry_id
+    }
+
+    fn project_path(&self, _: &AppContext) -> Option<ProjectPath> {
+        Some(self.file.project_path.clone())
+    }
+}
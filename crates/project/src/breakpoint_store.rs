@@ -0,0 +1,237 @@
+//! Keeps track of the breakpoints set in a project.
+
+use std::{path::Path, sync::Arc};
+
+use collections::{BTreeMap, BTreeSet, HashMap};
+use gpui::{AppContext, Context, EventEmitter, Model, ModelContext, WeakModel};
+use language::{Anchor, Buffer, Point, ToPoint};
+
+use crate::Project;
+
+/// The breakpoints of a project, keyed by the absolute path of their file.
+///
+/// The breakpoints of files that are open in a buffer are anchored in it, so that they
+/// follow the edits made to the file. They're only turned into rows when they're read.
+pub struct BreakpointStore {
+    breakpoints: BTreeMap<Arc<Path>, Breakpoints>,
+    open_buffers: HashMap<Arc<Path>, WeakModel<Buffer>>,
+}
+
+enum Breakpoints {
+    /// Zero-based rows, in a file that isn't open.
+    Rows(BTreeSet<u32>),
+    /// The starts of the breakpoints' lines, in the file's open buffer.
+    Anchors(Vec<Anchor>),
+}
+
+pub enum Event {
+    /// The breakpoints of the file at the given path changed, or their file was saved
+    /// with edits that may have moved them.
+    BreakpointsChanged { abs_path: Arc<Path> },
+}
+
+impl EventEmitter<Event> for BreakpointStore {}
+
+impl BreakpointStore {
+    pub(crate) fn new(cx: &mut AppContext) -> Model<Self> {
+        cx.new_model(|_| Self {
+            breakpoints: BTreeMap::default(),
+            open_buffers: HashMap::default(),
+        })
+    }
+
+    /// Starts anchoring the breakpoints of a local file in the buffer it was opened in,
+    /// until the buffer is released.
+    pub(crate) fn buffer_opened(&mut self, buffer: &Model<Buffer>, cx: &mut ModelContext<Self>) {
+        let Some(file) = buffer.read(cx).file().and_then(|file| file.as_local()) else {
+            return;
+        };
+        let abs_path: Arc<Path> = file.abs_path(cx).into();
+        self.open_buffers
+            .insert(abs_path.clone(), buffer.downgrade());
+        if let Some(Breakpoints::Rows(rows)) = self.breakpoints.get(&abs_path) {
+            let anchors = anchors_for_rows(buffer, rows.iter().copied(), cx);
+            self.breakpoints
+                .insert(abs_path.clone(), Breakpoints::Anchors(anchors));
+        }
+
+        cx.subscribe(buffer, {
+            let abs_path = abs_path.clone();
+            move |this, _, event, cx| {
+                if let language::Event::Saved = event {
+                    if this.breakpoints.contains_key(&abs_path) {
+                        cx.emit(Event::BreakpointsChanged {
+                            abs_path: abs_path.clone(),
+                        });
+                        cx.notify();
+                    }
+                }
+            }
+        })
+        .detach();
+        let buffer_id = buffer.entity_id();
+        cx.observe_release(buffer, move |this, buffer, _| {
+            if this
+                .open_buffers
+                .get(&abs_path)
+                .map_or(false, |open_buffer| open_buffer.entity_id() == buffer_id)
+            {
+                this.open_buffers.remove(&abs_path);
+            }
+            if let Some(Breakpoints::Anchors(anchors)) = this.breakpoints.get(&abs_path) {
+                let snapshot = buffer.text_snapshot();
+                let rows = anchors
+                    .iter()
+                    .map(|anchor| anchor.to_point(&snapshot).row)
+                    .collect();
+                this.breakpoints.insert(abs_path, Breakpoints::Rows(rows));
+            }
+        })
+        .detach();
+    }
+
+    fn open_buffer(&self, abs_path: &Path) -> Option<Model<Buffer>> {
+        self.open_buffers.get(abs_path)?.upgrade()
+    }
+
+    /// Sets a breakpoint on the given row of a file, or removes it when there is one.
+    pub fn toggle(&mut self, abs_path: Arc<Path>, row: u32, cx: &mut ModelContext<Self>) {
+        let mut rows = self
+            .breakpoints_for_path(&abs_path, cx)
+            .collect::<BTreeSet<_>>();
+        if !rows.remove(&row) {
+            rows.insert(row);
+        }
+        self.set_rows(abs_path.clone(), rows, cx);
+        cx.emit(Event::BreakpointsChanged { abs_path });
+        cx.notify();
+    }
+
+    fn set_rows(&mut self, abs_path: Arc<Path>, rows: BTreeSet<u32>, cx: &AppContext) {
+        if rows.is_empty() {
+            self.breakpoints.remove(&abs_path);
+            return;
+        }
+        let breakpoints = match self.open_buffer(&abs_path) {
+            Some(buffer) => Breakpoints::Anchors(anchors_for_rows(&buffer, rows, cx)),
+            None => Breakpoints::Rows(rows),
+        };
+        self.breakpoints.insert(abs_path, breakpoints);
+    }
+
+    /// The zero-based rows of the breakpoints in the given file, in order.
+    pub fn breakpoints_for_path<'a>(
+        &'a self,
+        abs_path: &Path,
+        cx: &'a AppContext,
+    ) -> impl 'a + Iterator<Item = u32> {
+        let rows = match self.breakpoints.get(abs_path) {
+            Some(Breakpoints::Rows(rows)) => Some(rows.clone()),
+            Some(Breakpoints::Anchors(anchors)) => self.open_buffer(abs_path).map(|buffer| {
+                let snapshot = buffer.read(cx).text_snapshot();
+                // Breakpoints end up on the same row when the lines between them are deleted.
+                anchors
+                    .iter()
+                    .map(|anchor| anchor.to_point(&snapshot).row)
+                    .collect::<BTreeSet<_>>()
+            }),
+            None => None,
+        };
+        rows.into_iter().flatten()
+    }
+
+    /// The paths of the files containing breakpoints.
+    pub fn paths(&self) -> impl '_ + Iterator<Item = &Arc<Path>> {
+        self.breakpoints.keys()
+    }
+
+    /// All the breakpoints, ordered by path and row.
+    pub fn all<'a>(&'a self, cx: &'a AppContext) -> impl 'a + Iterator<Item = (&Arc<Path>, u32)> {
+        self.breakpoints.keys().flat_map(move |path| {
+            self.breakpoints_for_path(path, cx)
+                .map(move |row| (path, row))
+        })
+    }
+
+    /// Replaces all the breakpoints, as when restoring them from a previous session.
+    pub fn restore(
+        &mut self,
+        breakpoints: impl IntoIterator<Item = (Arc<Path>, u32)>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let old_paths = std::mem::take(&mut self.breakpoints).into_keys();
+        let mut rows = BTreeMap::<Arc<Path>, BTreeSet<u32>>::default();
+        for (abs_path, row) in breakpoints {
+            rows.entry(abs_path).or_default().insert(row);
+        }
+        for (abs_path, rows) in rows {
+            self.set_rows(abs_path, rows, cx);
+        }
+        let changed_paths = old_paths
+            .chain(self.breakpoints.keys().cloned())
+            .collect::<BTreeSet<_>>();
+        for abs_path in changed_paths {
+            cx.emit(Event::BreakpointsChanged { abs_path });
+        }
+        cx.notify();
+    }
+}
+
+fn anchors_for_rows(
+    buffer: &Model<Buffer>,
+    rows: impl IntoIterator<Item = u32>,
+    cx: &AppContext,
+) -> Vec<Anchor> {
+    let snapshot = buffer.read(cx).text_snapshot();
+    let max_row = snapshot.max_point().row;
+    rows.into_iter()
+        .map(|row| snapshot.anchor_before(Point::new(row.min(max_row), 0)))
+        .collect()
+}
+
+impl Project {
+    pub fn breakpoint_store(&self) -> &Model<BreakpointStore> {
+        &self.breakpoint_store
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+
+    #[gpui::test]
+    fn test_toggling_breakpoints(cx: &mut TestAppContext) {
+        let store = cx.update(BreakpointStore::new);
+        let main_rs: Arc<Path> = Path::new("/project/src/main.rs").into();
+        let lib_rs: Arc<Path> = Path::new("/project/src/lib.rs").into();
+        store.update(cx, |store, cx| {
+            store.toggle(main_rs.clone(), 4, cx);
+            store.toggle(main_rs.clone(), 1, cx);
+            store.toggle(lib_rs.clone(), 7, cx);
+            store.toggle(main_rs.clone(), 4, cx);
+        });
+        store.read_with(cx, |store, cx| {
+            assert_eq!(
+                store.breakpoints_for_path(&main_rs, cx).collect::<Vec<_>>(),
+                [1]
+            );
+            assert_eq!(
+                store
+                    .all(cx)
+                    .map(|(path, row)| (path.clone(), row))
+                    .collect::<Vec<_>>(),
+                [(lib_rs.clone(), 7), (main_rs.clone(), 1)]
+            );
+        });
+
+        store.update(cx, |store, cx| store.restore([(main_rs.clone(), 2)], cx));
+        store.read_with(cx, |store, cx| {
+            assert_eq!(store.breakpoints_for_path(&lib_rs, cx).count(), 0);
+            assert_eq!(
+                store.breakpoints_for_path(&main_rs, cx).collect::<Vec<_>>(),
+                [2]
+            );
+        });
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{Project, ProjectEntryId, ProjectPath, Worktree};
+use gpui::{Model, ModelContext, Subscription, Task, WeakModel};
+use std::{sync::Arc, time::SystemTime};
+
+/// The bytes of a project file that isn't opened as text, reloaded whenever the
+/// file's worktree entry changes on disk.
+pub(crate) struct FileContent {
+    project: WeakModel<Project>,
+    pub(crate) project_path: ProjectPath,
+    pub(crate) entry_id: Option<ProjectEntryId>,
+    mtime: Option<SystemTime>,
+    pub(crate) content: Arc<[u8]>,
+    reload_task: Option<Task<()>>,
+    _observe_worktree: Subscription,
+}
+
+impl FileContent {
+    /// Creates the content of a model, `file` returning it from the model.
+    pub(crate) fn new<T: 'static>(
+        project: WeakModel<Project>,
+        project_path: ProjectPath,
+        worktree: &Model<Worktree>,
+        content: Vec<u8>,
+        file: fn(&mut T) -> &mut FileContent,
+        cx: &mut ModelContext<T>,
+    ) -> Self {
+        let entry = worktree.read(cx).entry_for_path(&project_path.path);
+        Self {
+            project,
+            entry_id: entry.map(|entry| entry.id),
+            mtime: entry.map(|entry| entry.mtime),
+            project_path,
+            content: content.into(),
+            reload_task: None,
+            _observe_worktree: cx.observe(worktree, move |this, worktree, cx| {
+                let Some(load) = file(this).reload_if_changed(&worktree, cx) else {
+                    return;
+                };
+                let path = file(this).project_path.path.clone();
+                file(this).reload_task = Some(cx.spawn(|this, mut cx| async move {
+                    match load.await {
+                        Ok(content) => {
+                            this.update(&mut cx, |this, cx| {
+                                file(this).content = content.into();
+                                cx.notify();
+                            })
+                            .ok();
+                        }
+                        Err(error) => log::error!("failed to reload {path:?}: {error:#}"),
+                    }
+                }));
+            }),
+        }
+    }
+
+    fn reload_if_changed<T>(
+        &mut self,
+        worktree: &Model<Worktree>,
+        cx: &mut ModelContext<T>,
+    ) -> Option<Task<anyhow::Result<Vec<u8>>>> {
+        let entry = worktree.read(cx).entry_for_path(&self.project_path.path)?;
+        let (entry_id, mtime) = (entry.id, entry.mtime);
+        self.entry_id = Some(entry_id);
+        if self.mtime == Some(mtime) {
+            return None;
+        }
+        self.mtime = Some(mtime);
+
+        let project = self.project.upgrade()?;
+        Some(project.update(cx, |project, cx| {
+            project.load_file_bytes(&self.project_path, cx)
+        }))
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{file_content::FileContent, Item, Project, ProjectEntryId, ProjectPath, Worktree};
+use gpui::{AppContext, Model, ModelContext, WeakModel};
+use std::{path::Path, sync::Arc};
+
+/// The extensions of the files opened as images rather than as text.
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "bmp", "gif", "ico", "jpeg", "jpg", "png", "svg", "tif", "tiff", "webp",
+];
+
+/// Whether the file at the given path is opened as an image.
+pub fn is_image_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map_or(false, |extension| {
+            IMAGE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        })
+}
+
+/// The contents of an image file in the project, reloaded whenever the file's
+/// worktree entry changes on disk.
+pub struct ImageItem {
+    file: FileContent,
+}
+
+impl ImageItem {
+    pub(crate) fn new(
+        project: WeakModel<Project>,
+        project_path: ProjectPath,
+        worktree: &Model<Worktree>,
+        content: Vec<u8>,
+        cx: &mut ModelContext<Self>,
+    ) -> Self {
+        Self {
+            file: FileContent::new(
+                project,
+                project_path,
+                worktree,
+                content,
+                |this: &mut Self| &mut this.file,
+                cx,
+            ),
+        }
+    }
+
+    pub fn path(&self) -> &Arc<Path> {
+        &self.file.project_path.path
+    }
+
+    /// The encoded bytes of the image, as stored on disk.
+    pub fn content(&self) -> &Arc<[u8]> {
+        &self.file.content
+    }
+
+    pub fn is_svg(&self) -> bool {
+        self.path()
+            .extension()
+            .map_or(false, |extension| extension.eq_ignore_ascii_case("svg"))
+    }
+}
+
+impl Item for ImageItem {
+    fn entry_id(&self, _: &AppContext) -> Option<ProjectEntryId> {
+        self.file.entry_id
+    }
+
+    fn project_path(&self, _: &AppContext) -> Option<ProjectPath> {
+        Some(self.file.project_path.clone())
+    }
+}
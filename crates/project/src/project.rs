@@ -1,10 +1,15 @@
+pub mod binary_item;
+pub mod breakpoint_store;
 pub mod debounced_delay;
+mod file_content;
 mod ignore;
+pub mod image_item;
 pub mod lsp_command;
 pub mod lsp_ext_command;
 mod prettier_support;
 pub mod project_settings;
 pub mod search;
+pub mod task_inventory;
 pub mod terminals;
 pub mod worktree;
 
@@ -14,6 +19,7 @@ mod project_tests;
 mod worktree_tests;
 
 use anyhow::{anyhow, bail, Context as _, Result};
+use breakpoint_store::BreakpointStore;
 use client::{proto, Client, Collaborator, TypedEnvelope, UserStore};
 use clock::ReplicaId;
 use collections::{hash_map, BTreeMap, HashMap, HashSet, VecDeque};
@@ -57,7 +63,8 @@ use postage::watch;
 use prettier_support::{DefaultPrettier, PrettierInstance};
 use project_settings::{LspSettings, ProjectSettings};
 use rand::prelude::*;
-use rpc::{ErrorCode, ErrorExt};
+use remote::{SshSession, HEADLESS_PROJECT_ID};
+use rpc::{Connection, ErrorCode, ErrorExt};
 use search::SearchQuery;
 use serde::Serialize;
 use settings::{Settings, SettingsStore};
@@ -81,19 +88,27 @@ use std::{
     },
     time::{Duration, Instant},
 };
+use task_inventory::Inventory;
 use terminals::Terminals;
 use text::{Anchor, BufferId};
 use util::{
-    debug_panic, defer, http::HttpClient, merge_json_value_into,
-    paths::LOCAL_SETTINGS_RELATIVE_PATH, post_inc, ResultExt, TryFutureExt as _,
+    debug_panic, defer,
+    http::{HttpClient, ZedHttpClient},
+    merge_json_value_into,
+    paths::{LOCAL_SETTINGS_RELATIVE_PATH, LOCAL_TASKS_RELATIVE_PATH},
+    post_inc, ResultExt, TryFutureExt as _,
 };
 
+pub use binary_item::BinaryItem;
 pub use fs::*;
+pub use image_item::ImageItem;
 #[cfg(any(test, feature = "test-support"))]
 pub use prettier::FORMAT_SUFFIX as TEST_PRETTIER_FORMAT_SUFFIX;
 pub use worktree::*;
 
 const MAX_SERVER_REINSTALL_ATTEMPT_COUNT: u64 = 4;
+/// Files opened as raw bytes are kept in memory whole, so larger ones aren't opened.
+const MAX_FILE_BYTES_LEN: u64 = 64 * 1024 * 1024;
 
 pub trait Item {
     fn entry_id(&self, cx: &AppContext) -> Option<ProjectEntryId>;
@@ -146,6 +161,11 @@ pub struct Project {
     _maintain_buffer_languages: Task<()>,
     _maintain_workspace_config: Task<Result<()>>,
     terminals: Terminals,
+    /// The SSH session of the headless server hosting the project, if any, which
+    /// lives as long as the project.
+    _ssh_session: Option<Arc<SshSession>>,
+    task_inventory: Model<Inventory>,
+    breakpoint_store: Model<BreakpointStore>,
     copilot_lsp_subscription: Option<gpui::Subscription>,
     copilot_log_subscription: Option<lsp::Subscription>,
     current_lsp_settings: HashMap<Arc<str>, LspSettings>,
@@ -596,8 +616,11 @@ impl Project {
         client.add_model_request_handler(Self::handle_open_buffer_for_symbol);
         client.add_model_request_handler(Self::handle_open_buffer_by_id);
         client.add_model_request_handler(Self::handle_open_buffer_by_path);
+        client.add_model_request_handler(Self::handle_load_file_bytes);
         client.add_model_request_handler(Self::handle_save_buffer);
         client.add_model_message_handler(Self::handle_update_diff_base);
+        client.add_model_message_handler(Self::handle_terminal_output);
+        client.add_model_message_handler(Self::handle_terminal_exited);
         client.add_model_request_handler(Self::handle_lsp_command::<lsp_ext_command::ExpandMacro>);
     }
 
@@ -615,6 +638,7 @@ impl Project {
                 .detach();
             let copilot_lsp_subscription =
                 Copilot::global(cx).map(|copilot| subscribe_for_copilot_events(&copilot, cx));
+            let task_inventory = Inventory::new(languages.next_language_server_id(), cx);
             Self {
                 worktrees: Vec::new(),
                 buffer_ordered_messages_tx: tx,
@@ -657,7 +681,11 @@ impl Project {
                 nonce: StdRng::from_entropy().gen(),
                 terminals: Terminals {
                     local_handles: Vec::new(),
+                    remote: None,
                 },
+                _ssh_session: None,
+                task_inventory,
+                breakpoint_store: BreakpointStore::new(cx),
                 copilot_lsp_subscription,
                 copilot_log_subscription: None,
                 current_lsp_settings: ProjectSettings::get_global(cx).lsp.clone(),
@@ -701,6 +729,7 @@ impl Project {
                 .detach();
             let copilot_lsp_subscription =
                 Copilot::global(cx).map(|copilot| subscribe_for_copilot_events(&copilot, cx));
+            let task_inventory = Inventory::new(languages.next_language_server_id(), cx);
             let mut this = Self {
                 worktrees: Vec::new(),
                 buffer_ordered_messages_tx: tx,
@@ -762,7 +791,11 @@ impl Project {
                 nonce: StdRng::from_entropy().gen(),
                 terminals: Terminals {
                     local_handles: Vec::new(),
+                    remote: None,
                 },
+                _ssh_session: None,
+                task_inventory,
+                breakpoint_store: BreakpointStore::new(cx),
                 copilot_lsp_subscription,
                 copilot_log_subscription: None,
                 current_lsp_settings: ProjectSettings::get_global(cx).lsp.clone(),
@@ -798,6 +831,53 @@ impl Project {
         Ok(this)
     }
 
+    /// Opens the project served by a headless project server, which is presented like a
+    /// local project, except that it's hosted on the server's machine.
+    pub async fn ssh(
+        ssh_session: Option<Arc<SshSession>>,
+        connection: Connection,
+        http: Arc<ZedHttpClient>,
+        languages: Arc<LanguageRegistry>,
+        fs: Arc<dyn Fs>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Model<Self>> {
+        // The server isn't the collaboration server, so the project talks to it with a
+        // client of its own.
+        let client = cx.update(|cx| {
+            let client = Client::new(http, cx);
+            Self::init(&client, cx);
+            client
+        })?;
+        client.connect_over(connection, &cx).await?;
+        let user_store = cx.new_model(|cx| UserStore::new(client.clone(), cx))?;
+        let this = Self::remote(
+            HEADLESS_PROJECT_ID,
+            client.clone(),
+            user_store,
+            languages,
+            fs,
+            proto::ChannelRole::Member,
+            cx.clone(),
+        )
+        .await?;
+        this.update(&mut cx, |this, cx| {
+            this._ssh_session = ssh_session;
+            this.terminals.remote = Some(Default::default());
+            let mut status = client.status();
+            cx.spawn(|this, mut cx| async move {
+                while let Some(status) = status.next().await {
+                    if !status.is_connected() {
+                        this.update(&mut cx, |this, cx| this.disconnected_from_host(cx))
+                            .ok();
+                        break;
+                    }
+                }
+            })
+            .detach();
+        })?;
+        Ok(this)
+    }
+
     fn release(&mut self, cx: &mut AppContext) {
         match &self.client_state {
             ProjectClientState::Local => {}
@@ -1624,6 +1704,8 @@ impl Project {
 
             self.collaborators.clear();
 
+            self.terminals.disconnected_from_host();
+
             for worktree in &self.worktrees {
                 if let Some(worktree) = worktree.upgrade() {
                     worktree.update(cx, |worktree, _| {
@@ -1710,9 +1792,33 @@ impl Project {
         path: ProjectPath,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<(Option<ProjectEntryId>, AnyModel)>> {
+        if image_item::is_image_path(&path.path) {
+            let task = self.open_image(path, cx);
+            return cx.spawn(move |_, cx| async move {
+                let image = task.await?;
+                let project_entry_id = image.read_with(&cx, |image, cx| image.entry_id(cx))?;
+
+                let image: &AnyModel = &image;
+                Ok((project_entry_id, image.clone()))
+            });
+        }
+
         let task = self.open_buffer(path.clone(), cx);
-        cx.spawn(move |_, cx| async move {
-            let buffer = task.await?;
+        cx.spawn(move |this, mut cx| async move {
+            let buffer = match task.await {
+                Ok(buffer) => buffer,
+                Err(error) if error.error_code() == ErrorCode::BinaryFile => {
+                    let binary = this
+                        .update(&mut cx, |this, cx| this.open_binary(path, cx))?
+                        .await?;
+                    let project_entry_id =
+                        binary.read_with(&cx, |binary, cx| binary.entry_id(cx))?;
+
+                    let binary: &AnyModel = &binary;
+                    return Ok((project_entry_id, binary.clone()));
+                }
+                Err(error) => return Err(error),
+            };
             let project_entry_id = buffer.read_with(&cx, |buffer, cx| {
                 File::from_dyn(buffer.file()).and_then(|file| file.project_entry_id(cx))
             })?;
@@ -1836,6 +1942,76 @@ impl Project {
         })
     }
 
+    pub fn open_image(
+        &mut self,
+        project_path: ProjectPath,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Model<ImageItem>>> {
+        let Some(worktree) = self.worktree_for_id(project_path.worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("no such worktree")));
+        };
+        let load = self.load_file_bytes(&project_path, cx);
+        cx.spawn(move |this, mut cx| async move {
+            let content = load.await?;
+            cx.new_model(|cx| ImageItem::new(this, project_path, &worktree, content, cx))
+        })
+    }
+
+    pub fn open_binary(
+        &mut self,
+        project_path: ProjectPath,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Model<BinaryItem>>> {
+        let Some(worktree) = self.worktree_for_id(project_path.worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("no such worktree")));
+        };
+        let load = self.load_file_bytes(&project_path, cx);
+        cx.spawn(move |this, mut cx| async move {
+            let content = load.await?;
+            cx.new_model(|cx| BinaryItem::new(this, project_path, &worktree, content, cx))
+        })
+    }
+
+    /// Reads the bytes of a file, from disk or from the host of a remote project.
+    pub(crate) fn load_file_bytes(
+        &self,
+        project_path: &ProjectPath,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<u8>>> {
+        let Some(worktree) = self.worktree_for_id(project_path.worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("no such worktree")));
+        };
+        let worktree = worktree.read(cx);
+        if worktree.is_local() {
+            let abs_path = worktree.absolutize(&project_path.path);
+            let fs = self.fs.clone();
+            cx.background_executor().spawn(async move {
+                let abs_path = abs_path?;
+                let len = fs
+                    .metadata(&abs_path)
+                    .await?
+                    .map_or(0, |metadata| metadata.len);
+                if len > MAX_FILE_BYTES_LEN {
+                    bail!(
+                        "{} is too large to open ({len} bytes, at most {MAX_FILE_BYTES_LEN})",
+                        abs_path.display()
+                    );
+                }
+                fs.load_bytes(&abs_path).await
+            })
+        } else if let Some(project_id) = self.remote_id() {
+            let request = self.client.request(proto::LoadFileBytes {
+                project_id,
+                worktree_id: project_path.worktree_id.to_proto(),
+                path: project_path.path.to_string_lossy().to_string(),
+            });
+            cx.background_executor()
+                .spawn(async move { Ok(request.await?.content) })
+        } else {
+            Task::ready(Err(anyhow!("cannot open file while disconnected")))
+        }
+    }
+
     /// LanguageServerName is owned, because it is inserted into a map
     pub fn open_local_buffer_via_lsp(
         &mut self,
@@ -1934,6 +2110,25 @@ impl Project {
         })
     }
 
+    /// Loads the contents of a file at a git revision, such as `HEAD`, or `None` if the
+    /// file doesn't exist at that revision.
+    pub fn load_revision_text(
+        &self,
+        project_path: &ProjectPath,
+        revision: String,
+        cx: &AppContext,
+    ) -> Task<Result<Option<String>>> {
+        let Some(worktree) = self.worktree_for_id(project_path.worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("no such worktree")));
+        };
+        match worktree.read(cx).as_local() {
+            Some(worktree) => worktree.load_revision_text(&project_path.path, revision, cx),
+            None => Task::ready(Err(anyhow!(
+                "loading revisions isn't supported in remote projects"
+            ))),
+        }
+    }
+
     pub fn save_buffer_as(
         &mut self,
         buffer: Model<Buffer>,
@@ -2043,6 +2238,8 @@ impl Project {
             }
         }
 
+        self.breakpoint_store
+            .update(cx, |store, cx| store.buffer_opened(buffer, cx));
         self.detect_language_for_buffer(buffer, cx);
         self.register_buffer_with_language_servers(buffer, cx);
         self.register_buffer_with_copilot(buffer, cx);
@@ -2076,6 +2273,10 @@ impl Project {
     ) {
         let buffer = buffer_handle.read(cx);
         let buffer_id = buffer.remote_id();
+        // Syncing a large file with language servers would stall both sides.
+        if buffer.is_large_file() {
+            return;
+        }
 
         if let Some(file) = File::from_dyn(buffer.file()) {
             if !file.is_local() {
@@ -2758,7 +2959,7 @@ impl Project {
         };
         if let Some(file) = buffer_file {
             let worktree = file.worktree.clone();
-            if worktree.read(cx).is_local() {
+            if worktree.read(cx).is_local() && !buffer.read(cx).is_large_file() {
                 self.start_language_servers(&worktree, new_language, cx);
             }
         }
@@ -3267,6 +3468,9 @@ impl Project {
         for buffer in self.opened_buffers.values() {
             if let Some(buffer_handle) = buffer.upgrade() {
                 let buffer = buffer_handle.read(cx);
+                if buffer.is_large_file() {
+                    continue;
+                }
                 let file = match File::from_dyn(buffer.file()) {
                     Some(file) => file,
                     None => continue,
@@ -3833,6 +4037,31 @@ impl Project {
         })
     }
 
+    /// The project files above the large file threshold that are still being
+    /// read, along with how much of each has been read so far.
+    pub fn loading_files<'a>(
+        &'a self,
+        cx: &'a AppContext,
+    ) -> impl Iterator<Item = (ProjectPath, FileLoadProgress)> + 'a {
+        self.worktrees().flat_map(move |worktree| {
+            let worktree = worktree.read(cx);
+            let worktree_id = worktree.id();
+            worktree
+                .as_local()
+                .into_iter()
+                .flat_map(|worktree| worktree.loading_files())
+                .map(move |(path, progress)| {
+                    (
+                        ProjectPath {
+                            worktree_id,
+                            path: path.clone(),
+                        },
+                        *progress,
+                    )
+                })
+        })
+    }
+
     pub fn language_server_statuses(
         &self,
     ) -> impl DoubleEndedIterator<Item = &LanguageServerStatus> {
@@ -6650,6 +6879,10 @@ impl Project {
     }
 
     pub fn remove_worktree(&mut self, id_to_remove: WorktreeId, cx: &mut ModelContext<Self>) {
+        self.task_inventory.update(cx, |inventory, cx| {
+            inventory.remove_worktree(id_to_remove, cx);
+        });
+
         let mut servers_to_remove = HashMap::default();
         let mut servers_to_preserve = HashSet::default();
         for ((worktree_id, server_name), &server_id) in &self.language_server_ids {
@@ -7062,6 +7295,7 @@ impl Project {
         let remote_worktree_id = worktree.id();
 
         let mut settings_contents = Vec::new();
+        let mut tasks_contents = Vec::new();
         for (path, _, change) in changes.iter() {
             if path.ends_with(&*LOCAL_SETTINGS_RELATIVE_PATH) {
                 let settings_dir = Arc::from(
@@ -7082,9 +7316,52 @@ impl Project {
                         },
                     )
                 });
+            } else if path.ends_with(&*LOCAL_TASKS_RELATIVE_PATH) {
+                let tasks_dir = Arc::from(
+                    path.ancestors()
+                        .nth(LOCAL_TASKS_RELATIVE_PATH.components().count())
+                        .unwrap(),
+                );
+                let fs = self.fs.clone();
+                let removed = *change == PathChange::Removed;
+                let abs_path = worktree.absolutize(path);
+                tasks_contents.push(async move {
+                    (
+                        tasks_dir,
+                        if removed {
+                            None
+                        } else {
+                            Some(async move { fs.load(&abs_path?).await }.await)
+                        },
+                    )
+                });
             }
         }
 
+        if !tasks_contents.is_empty() {
+            let task_inventory = self.task_inventory.clone();
+            let worktree_abs_path = worktree.abs_path().clone();
+            cx.spawn(move |_, mut cx| async move {
+                let tasks_contents: Vec<(Arc<Path>, _)> =
+                    futures::future::join_all(tasks_contents).await;
+                task_inventory
+                    .update(&mut cx, |inventory, cx| {
+                        for (directory, file_content) in tasks_contents {
+                            let file_content = file_content.and_then(|content| content.log_err());
+                            inventory.set_worktree_templates(
+                                remote_worktree_id,
+                                directory.clone(),
+                                worktree_abs_path.join(&directory),
+                                file_content.as_deref(),
+                                cx,
+                            );
+                        }
+                    })
+                    .ok();
+            })
+            .detach();
+        }
+
         if settings_contents.is_empty() {
             return;
         }
@@ -8417,6 +8694,31 @@ impl Project {
         Project::respond_to_open_buffer_request(this, buffer, peer_id, &mut cx)
     }
 
+    async fn handle_load_file_bytes(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::LoadFileBytes>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::LoadFileBytesResponse> {
+        let project_path = ProjectPath {
+            worktree_id: WorktreeId::from_proto(envelope.payload.worktree_id),
+            path: PathBuf::from(envelope.payload.path).into(),
+        };
+        let load = this.update(&mut cx, |this, cx| {
+            let is_private = this
+                .entry_for_path(&project_path, cx)
+                .map_or(false, |entry| entry.is_private);
+            if is_private {
+                Task::ready(Err(anyhow!(ErrorCode::UnsharedItem)))
+            } else {
+                this.load_file_bytes(&project_path, cx)
+            }
+        })?;
+        Ok(proto::LoadFileBytesResponse {
+            content: load.await?,
+        })
+    }
+
     fn respond_to_open_buffer_request(
         this: Model<Self>,
         buffer: Model<Buffer>,
@@ -39,6 +39,13 @@ pub struct ProjectSettings {
     /// Treat the files matching these globs as `.env` files.
     /// Default: [ "**/.env*" ]
     pub private_files: Option<Vec<String>>,
+
+    /// The size in bytes above which files are opened in large file mode,
+    /// without syntax highlighting, language servers, soft wrap, inlay hints
+    /// or git diffs.
+    ///
+    /// Default: 20000000
+    pub large_file_threshold: Option<u64>,
 }
 
 #[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
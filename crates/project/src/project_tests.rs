@@ -142,6 +142,170 @@ async fn test_managing_project_specific_settings(cx: &mut gpui::TestAppContext)
     });
 }
 
+#[gpui::test]
+async fn test_managing_project_specific_tasks(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/the-root",
+        json!({
+            ".zed": {
+                "tasks.json": r#"[{ "label": "cargo check", "command": "cargo", "args": ["check"] }]"#
+            },
+            "a": {
+                "a.rs": "fn a() {\n    A\n}"
+            },
+            "b": {
+                ".zed": {
+                    "tasks.json": r#"[{ "label": "echo file", "command": "echo", "args": ["$ZED_FILE"] }]"#
+                },
+                "b.rs": "fn b() {\n  B\n}"
+            }
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/the-root".as_ref()], cx).await;
+    let worktree_id = project.update(cx, |project, cx| {
+        project.worktrees().next().unwrap().read(cx).id()
+    });
+    cx.executor().run_until_parked();
+
+    let task_context = task::TaskContext {
+        cwd: None,
+        task_variables: task::TaskVariables::from_iter([(
+            task::VariableName::File,
+            "/the-root/b/b.rs".to_string(),
+        )]),
+    };
+    let resolved_tasks = |cx: &mut gpui::TestAppContext| {
+        project.update(cx, |project, cx| {
+            project
+                .task_inventory()
+                .read(cx)
+                .resolve_tasks(Some(worktree_id), &task_context, cx)
+                .into_iter()
+                .map(|(_, task)| {
+                    let task = task.resolved;
+                    (task.label, task.args, task.cwd.unwrap())
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    // Tasks of the innermost directories come first.
+    assert_eq!(
+        resolved_tasks(cx),
+        [
+            (
+                "echo file".to_string(),
+                vec!["/the-root/b/b.rs".to_string()],
+                PathBuf::from("/the-root/b")
+            ),
+            (
+                "cargo check".to_string(),
+                vec!["check".to_string()],
+                PathBuf::from("/the-root/")
+            ),
+        ]
+    );
+
+    fs.remove_file("/the-root/b/.zed/tasks.json".as_ref(), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(
+        resolved_tasks(cx),
+        [(
+            "cargo check".to_string(),
+            vec!["check".to_string()],
+            PathBuf::from("/the-root/")
+        )]
+    );
+
+    // Problems found in the output of tasks are reported as diagnostics, replacing the
+    // ones of the task's previous run.
+    let check = task::TaskId("cargo check".into());
+    let clippy = task::TaskId("cargo clippy".into());
+    project.update(cx, |project, cx| {
+        project.update_task_diagnostics(
+            &check,
+            "cargo check",
+            Some("/the-root".as_ref()),
+            vec![
+                task::Problem {
+                    path: "a/a.rs".into(),
+                    row: 1,
+                    column: 4,
+                    severity: task::ProblemSeverity::Error,
+                    message: "unknown value `A`".into(),
+                },
+                task::Problem {
+                    path: "/the-root/b/b.rs".into(),
+                    row: 0,
+                    column: 3,
+                    severity: task::ProblemSeverity::Warning,
+                    message: "unused function `b`".into(),
+                },
+            ],
+            cx,
+        );
+        assert_eq!(
+            project.diagnostic_summary(false, cx),
+            DiagnosticSummary {
+                error_count: 1,
+                warning_count: 1
+            }
+        );
+        let server_id = project.task_inventory().read(cx).diagnostics_server_id;
+        assert_eq!(
+            project
+                .language_server_statuses
+                .get(&server_id)
+                .map(|status| status.name.as_str()),
+            Some("tasks")
+        );
+
+        // Other tasks don't replace the problems found in the same files.
+        project.update_task_diagnostics(
+            &clippy,
+            "cargo clippy",
+            Some("/the-root".as_ref()),
+            vec![task::Problem {
+                path: "b/b.rs".into(),
+                row: 1,
+                column: 0,
+                severity: task::ProblemSeverity::Warning,
+                message: "needless return".into(),
+            }],
+            cx,
+        );
+        assert_eq!(
+            project.diagnostic_summary(false, cx),
+            DiagnosticSummary {
+                error_count: 1,
+                warning_count: 2
+            }
+        );
+
+        project.update_task_diagnostics(
+            &check,
+            "cargo check",
+            Some("/the-root".as_ref()),
+            vec![],
+            cx,
+        );
+        assert_eq!(
+            project.diagnostic_summary(false, cx),
+            DiagnosticSummary {
+                error_count: 0,
+                warning_count: 1
+            }
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_managing_language_servers(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -2669,6 +2833,243 @@ async fn test_apply_code_actions_with_commands(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_terminal_profiles(cx: &mut gpui::TestAppContext) {
+    use terminal::terminal_settings::{Shell, TerminalProfile, TerminalSettings, VenvSettings};
+
+    init_test(cx);
+    cx.update(|cx| {
+        TerminalSettings::register(cx);
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings::<TerminalSettings>(cx, |settings| {
+                settings.env = Some(HashMap::from_iter([
+                    ("EDITOR".to_string(), "zed".to_string()),
+                    ("LANG".to_string(), "C".to_string()),
+                ]));
+                settings.detect_venv = Some(VenvSettings::Off);
+                settings.profiles = Some(HashMap::from_iter([
+                    (
+                        "login".to_string(),
+                        TerminalProfile {
+                            shell: Some(Shell::Program("bash".to_string())),
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "python".to_string(),
+                        TerminalProfile {
+                            env: HashMap::from_iter([(
+                                "LANG".to_string(),
+                                "en_US.UTF-8".to_string(),
+                            )]),
+                            detect_venv: Some(VenvSettings::On {
+                                directories: Some(vec![PathBuf::from(".venv")]),
+                                activate_script: None,
+                            }),
+                            ..Default::default()
+                        },
+                    ),
+                ]));
+                settings.default_profile = Some("login".to_string());
+            });
+        });
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/the-root",
+        json!({
+            ".zed": {
+                "settings.json": r#"{ "terminal": { "default_profile": "python" } }"#
+            },
+            "main.py": "print(1)"
+        }),
+    )
+    .await;
+    let project = Project::test(fs, ["/the-root".as_ref()], cx).await;
+    cx.executor().run_until_parked();
+
+    project.read_with(cx, |project, cx| {
+        // The project's default profile overrides the global one.
+        assert_eq!(
+            project.terminal_settings(cx).profile(None).name.as_deref(),
+            Some("python")
+        );
+
+        // The profile's environment is merged into the global one, and its
+        // virtual environment is activated.
+        let launch = project.terminal_launch(Some("/the-root".into()), None, None, cx);
+        assert_eq!(launch.profile.as_deref(), Some("python"));
+        assert_eq!(launch.env.get("EDITOR").map(String::as_str), Some("zed"));
+        assert_eq!(
+            launch.env.get("LANG").map(String::as_str),
+            Some("en_US.UTF-8")
+        );
+        assert_eq!(launch.working_directory, Some(PathBuf::from("/the-root")));
+        assert!(matches!(launch.python_settings, VenvSettings::On { .. }));
+
+        let launch = project.terminal_launch(None, Some("login"), None, cx);
+        assert_eq!(launch.shell, Shell::Program("bash".to_string()));
+        assert_eq!(launch.env.get("LANG").map(String::as_str), Some("C"));
+        assert!(matches!(launch.python_settings, VenvSettings::Off));
+
+        // Tasks run their own command in their own directory, with the profile's
+        // environment.
+        let launch = project.terminal_launch(
+            Some("/the-root".into()),
+            None,
+            Some(task::SpawnInTerminal {
+                id: task::TaskId("test".to_string()),
+                label: "test".to_string(),
+                command: "pytest".to_string(),
+                args: Vec::new(),
+                cwd: Some("/the-root/tests".into()),
+                env: HashMap::from_iter([("CI".to_string(), "1".to_string())]),
+                use_new_terminal: false,
+                allow_concurrent_runs: false,
+                problem_matchers: Vec::new(),
+            }),
+            cx,
+        );
+        assert_eq!(
+            launch.env.get("LANG").map(String::as_str),
+            Some("en_US.UTF-8")
+        );
+        assert_eq!(launch.env.get("CI").map(String::as_str), Some("1"));
+        assert_eq!(
+            launch.working_directory,
+            Some(PathBuf::from("/the-root/tests"))
+        );
+        assert!(matches!(launch.python_settings, VenvSettings::Off));
+        assert!(launch.task_state.is_some());
+    });
+}
+
+#[gpui::test]
+async fn test_opening_large_files(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings::<ProjectSettings>(cx, |project_settings| {
+                project_settings.large_file_threshold = Some(1024);
+            });
+        });
+    });
+
+    let mut rust = Language::new(
+        LanguageConfig {
+            name: Arc::from("Rust"),
+            matcher: LanguageMatcher {
+                path_suffixes: vec!["rs".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Some(tree_sitter_rust::language()),
+    );
+    let mut fake_rust_servers = rust
+        .set_fake_lsp_adapter(Arc::new(FakeLspAdapter {
+            name: "rust-lsp",
+            ..Default::default()
+        }))
+        .await;
+
+    // The files span several chunks, which split a character in the first file and
+    // a line ending in the second.
+    let large_text = |prefix: &str| format!("{prefix}{}", "é\r\n".repeat(300_000));
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": large_text("xxx"),
+            "b.rs": large_text("x"),
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    project.update(cx, |project, _| project.languages.add(Arc::new(rust)));
+
+    let mut buffers = Vec::new();
+    for (path, prefix) in [("/dir/a.rs", "xxx"), ("/dir/b.rs", "x")] {
+        let buffer = project
+            .update(cx, |project, cx| project.open_local_buffer(path, cx))
+            .await
+            .unwrap();
+        cx.executor().run_until_parked();
+        buffer.read_with(cx, |buffer, _| {
+            assert!(buffer.is_large_file());
+            assert_eq!(buffer.line_ending(), LineEnding::Windows);
+            assert_eq!(buffer.text(), format!("{prefix}{}", "é\n".repeat(300_000)));
+            assert!(buffer.snapshot().syntax_layers().next().is_none());
+        });
+        buffers.push(buffer);
+    }
+
+    // Large files aren't reloaded when they change on disk, but editing them
+    // then is a conflict.
+    fs.save("/dir/a.rs".as_ref(), &"changed".into(), LineEnding::Unix)
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    buffers[0].update(cx, |buffer, cx| {
+        assert!(buffer.text().starts_with("xxxé\n"));
+        assert!(!buffer.has_conflict());
+        buffer.edit([(0..0, "y")], None, cx);
+        assert!(buffer.has_conflict());
+    });
+
+    project.read_with(cx, |project, cx| {
+        assert_eq!(project.loading_files(cx).count(), 0)
+    });
+    assert!(fake_rust_servers.try_next().is_err());
+}
+
+#[gpui::test]
+async fn test_breakpoints_follow_edits(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "main.rs": "one\ntwo\nthree\n" }))
+        .await;
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let breakpoint_store = project.read_with(cx, |project, _| project.breakpoint_store().clone());
+    let main_rs: Arc<Path> = Path::new("/dir/main.rs").into();
+    breakpoint_store.update(cx, |store, cx| store.restore([(main_rs.clone(), 1)], cx));
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/main.rs", cx)
+        })
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "zero\n")], None, cx));
+    breakpoint_store.update(cx, |store, cx| {
+        assert_eq!(
+            store.breakpoints_for_path(&main_rs, cx).collect::<Vec<_>>(),
+            [2]
+        );
+        store.toggle(main_rs.clone(), 3, cx);
+    });
+    buffer.update(cx, |buffer, cx| buffer.edit([(0..5, "")], None, cx));
+    breakpoint_store.read_with(cx, |store, cx| {
+        assert_eq!(
+            store.breakpoints_for_path(&main_rs, cx).collect::<Vec<_>>(),
+            [1, 2]
+        );
+    });
+
+    // Once the buffer is released, the breakpoints are kept as the rows they were on.
+    drop(buffer);
+    cx.executor().run_until_parked();
+    breakpoint_store.read_with(cx, |store, cx| {
+        assert_eq!(
+            store.all(cx).map(|(_, row)| row).collect::<Vec<_>>(),
+            [1, 2]
+        );
+    });
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_save_file(cx: &mut gpui::TestAppContext) {
     init_test(cx);
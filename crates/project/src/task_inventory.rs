@@ -0,0 +1,309 @@
+//! Keeps track of the tasks that can be spawned in a project.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use collections::{BTreeMap, HashMap, HashSet};
+use futures::{channel::mpsc::UnboundedReceiver, StreamExt};
+use gpui::{AppContext, Context, Global, Model, ModelContext};
+use language::{
+    Diagnostic, DiagnosticEntry, DiagnosticSeverity, LanguageServerId, PointUtf16, Unclipped,
+};
+use task::{
+    Problem, ProblemSeverity, ResolvedTask, TaskContext, TaskId, TaskTemplate, TaskTemplates,
+};
+use util::{post_inc, ResultExt};
+
+use crate::{LanguageServerStatus, Project, WorktreeId};
+
+/// The tasks defined in `~/.config/zed/tasks.json`, available in every project.
+struct GlobalTaskTemplates(TaskTemplates);
+
+impl Global for GlobalTaskTemplates {}
+
+/// Keeps the global tasks up to date with the contents of the user's tasks file.
+pub fn handle_tasks_file_changes(
+    mut user_tasks_file_rx: UnboundedReceiver<String>,
+    cx: &mut AppContext,
+) {
+    cx.spawn(move |cx| async move {
+        while let Some(content) = user_tasks_file_rx.next().await {
+            let Some(templates) = parse_tasks(&content).log_err() else {
+                continue;
+            };
+            if cx
+                .update(|cx| cx.set_global(GlobalTaskTemplates(templates)))
+                .is_err()
+            {
+                break;
+            }
+        }
+    })
+    .detach();
+}
+
+fn parse_tasks(content: &str) -> Result<TaskTemplates> {
+    if content.trim().is_empty() {
+        Ok(TaskTemplates::default())
+    } else {
+        settings::parse_json_with_comments(content)
+    }
+}
+
+/// Where a task is defined.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TaskSourceKind {
+    /// The user's `~/.config/zed/tasks.json` file.
+    Global,
+    /// A `.zed/tasks.json` file inside of a worktree.
+    Worktree {
+        id: WorktreeId,
+        /// The directory containing the `.zed` directory, relative to the worktree root.
+        directory: Arc<Path>,
+        abs_directory: PathBuf,
+    },
+    /// A test or entry point detected in the source code of the given language.
+    Language { name: Arc<str> },
+}
+
+impl TaskSourceKind {
+    /// The prefix of the ids of the tasks defined in this source.
+    pub fn id_base(&self) -> String {
+        match self {
+            Self::Global => "global".to_string(),
+            Self::Worktree { id, directory, .. } => {
+                format!("worktree_{}_{}", id.to_usize(), directory.display())
+            }
+            Self::Language { name } => format!("language_{name}"),
+        }
+    }
+
+    /// A short description of the source, to display next to its tasks.
+    pub fn label(&self) -> String {
+        match self {
+            Self::Global => "global".to_string(),
+            Self::Worktree { abs_directory, .. } => abs_directory
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            Self::Language { name } => name.to_string(),
+        }
+    }
+}
+
+/// The tasks of a project, along with the last one that was spawned.
+pub struct Inventory {
+    worktree_templates: BTreeMap<(WorktreeId, Arc<Path>), (PathBuf, TaskTemplates)>,
+    last_scheduled_task: Option<(TaskSourceKind, ResolvedTask)>,
+    /// The id under which the problems found in the tasks' output are reported.
+    diagnostics_server_id: LanguageServerId,
+    /// The problems found in the output of the last run of each task, by path.
+    task_diagnostics:
+        HashMap<TaskId, HashMap<PathBuf, Vec<DiagnosticEntry<Unclipped<PointUtf16>>>>>,
+}
+
+impl Inventory {
+    pub(crate) fn new(diagnostics_server_id: LanguageServerId, cx: &mut AppContext) -> Model<Self> {
+        cx.new_model(|_| Self {
+            worktree_templates: BTreeMap::default(),
+            last_scheduled_task: None,
+            diagnostics_server_id,
+            task_diagnostics: HashMap::default(),
+        })
+    }
+
+    pub(crate) fn set_worktree_templates(
+        &mut self,
+        worktree_id: WorktreeId,
+        directory: Arc<Path>,
+        abs_directory: PathBuf,
+        content: Option<&str>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let key = (worktree_id, directory);
+        match content.map(parse_tasks) {
+            Some(Ok(templates)) => {
+                self.worktree_templates
+                    .insert(key, (abs_directory, templates));
+            }
+            Some(Err(error)) => {
+                log::error!("failed to parse tasks in {abs_directory:?}: {error:?}");
+                return;
+            }
+            None => {
+                self.worktree_templates.remove(&key);
+            }
+        }
+        cx.notify();
+    }
+
+    pub(crate) fn remove_worktree(&mut self, worktree_id: WorktreeId, cx: &mut ModelContext<Self>) {
+        self.worktree_templates
+            .retain(|(id, _), _| *id != worktree_id);
+        cx.notify();
+    }
+
+    /// Lists the tasks that can be spawned from a file of the given worktree, or from
+    /// any worktree when none is given: the tasks defined in the worktree, from the
+    /// innermost directory to the outermost, followed by the global tasks.
+    pub fn list_templates(
+        &self,
+        worktree: Option<WorktreeId>,
+        cx: &AppContext,
+    ) -> Vec<(TaskSourceKind, TaskTemplate)> {
+        let worktree_templates = self
+            .worktree_templates
+            .iter()
+            .rev()
+            .filter(|((id, _), _)| worktree.map_or(true, |worktree| worktree == *id))
+            .flat_map(|((id, directory), (abs_directory, templates))| {
+                let source = TaskSourceKind::Worktree {
+                    id: *id,
+                    directory: directory.clone(),
+                    abs_directory: abs_directory.clone(),
+                };
+                templates
+                    .0
+                    .iter()
+                    .map(move |template| (source.clone(), template.clone()))
+            });
+        let global_templates = cx
+            .try_global::<GlobalTaskTemplates>()
+            .into_iter()
+            .flat_map(|templates| templates.0 .0.iter())
+            .map(|template| (TaskSourceKind::Global, template.clone()));
+        worktree_templates.chain(global_templates).collect()
+    }
+
+    /// Resolves the tasks listed by [`Self::list_templates`] in the given context, skipping
+    /// those referring to unavailable variables. Tasks defined in a worktree are spawned
+    /// in the directory of their tasks file by default.
+    pub fn resolve_tasks(
+        &self,
+        worktree: Option<WorktreeId>,
+        task_context: &TaskContext,
+        cx: &AppContext,
+    ) -> Vec<(TaskSourceKind, ResolvedTask)> {
+        self.list_templates(worktree, cx)
+            .into_iter()
+            .filter_map(|(source, template)| {
+                let resolved = match &source {
+                    TaskSourceKind::Global | TaskSourceKind::Language { .. } => {
+                        template.resolve_task(&source.id_base(), task_context)
+                    }
+                    TaskSourceKind::Worktree { abs_directory, .. } => {
+                        let task_context = TaskContext {
+                            cwd: Some(abs_directory.clone()),
+                            task_variables: task_context.task_variables.clone(),
+                        };
+                        template.resolve_task(&source.id_base(), &task_context)
+                    }
+                }?;
+                Some((source, resolved))
+            })
+            .collect()
+    }
+
+    pub fn task_scheduled(&mut self, source: TaskSourceKind, task: ResolvedTask) {
+        self.last_scheduled_task = Some((source, task));
+    }
+
+    /// The task spawned last, which can be spawned again with the same variables.
+    pub fn last_scheduled_task(&self) -> Option<&(TaskSourceKind, ResolvedTask)> {
+        self.last_scheduled_task.as_ref()
+    }
+}
+
+impl Project {
+    pub fn task_inventory(&self) -> &Model<Inventory> {
+        &self.task_inventory
+    }
+
+    /// Replaces the diagnostics reported for the previous run of a task with the problems
+    /// found in its output. Relative paths are resolved against the task's working
+    /// directory.
+    pub fn update_task_diagnostics(
+        &mut self,
+        task_id: &TaskId,
+        task_label: &str,
+        cwd: Option<&Path>,
+        problems: Vec<Problem>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let mut diagnostics_by_path = HashMap::<PathBuf, Vec<_>>::default();
+        for problem in problems {
+            let path = Path::new(&problem.path);
+            let abs_path = match cwd {
+                Some(cwd) if path.is_relative() => cwd.join(path),
+                _ => path.to_path_buf(),
+            };
+            let point = Unclipped(PointUtf16::new(problem.row, problem.column));
+            diagnostics_by_path
+                .entry(abs_path)
+                .or_default()
+                .push(DiagnosticEntry {
+                    range: point..point,
+                    diagnostic: Diagnostic {
+                        source: Some(task_label.to_string()),
+                        severity: match problem.severity {
+                            ProblemSeverity::Error => DiagnosticSeverity::ERROR,
+                            ProblemSeverity::Warning => DiagnosticSeverity::WARNING,
+                            ProblemSeverity::Info => DiagnosticSeverity::INFORMATION,
+                            ProblemSeverity::Hint => DiagnosticSeverity::HINT,
+                        },
+                        message: problem.message,
+                        group_id: post_inc(&mut self.next_diagnostic_group_id),
+                        is_primary: true,
+                        is_disk_based: true,
+                        ..Default::default()
+                    },
+                });
+        }
+
+        let (server_id, updates) = self.task_inventory.update(cx, |inventory, _| {
+            let new_paths = diagnostics_by_path.keys().cloned().collect::<Vec<_>>();
+            let old_paths = inventory
+                .task_diagnostics
+                .insert(task_id.clone(), diagnostics_by_path)
+                .unwrap_or_default()
+                .into_keys();
+            // All the tasks report their problems under the same server, so the
+            // diagnostics of a path are the problems every task found in it.
+            let updates = old_paths
+                .chain(new_paths)
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .map(|path| {
+                    let diagnostics = inventory
+                        .task_diagnostics
+                        .values()
+                        .filter_map(|diagnostics_by_path| diagnostics_by_path.get(&path))
+                        .flatten()
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    (path, diagnostics)
+                })
+                .collect::<Vec<_>>();
+            inventory
+                .task_diagnostics
+                .retain(|_, diagnostics_by_path| !diagnostics_by_path.is_empty());
+            (inventory.diagnostics_server_id, updates)
+        });
+
+        self.language_server_statuses
+            .entry(server_id)
+            .or_insert_with(|| LanguageServerStatus {
+                name: "tasks".to_string(),
+                pending_work: Default::default(),
+                has_pending_diagnostic_updates: false,
+                progress_tokens: Default::default(),
+            });
+        for (abs_path, diagnostics) in updates {
+            self.update_diagnostic_entries(server_id, abs_path, None, diagnostics, cx)
+                .log_err();
+        }
+    }
+}
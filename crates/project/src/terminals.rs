@@ -1,80 +1,363 @@
 use crate::Project;
-use gpui::{AnyWindowHandle, Context, Entity, Model, ModelContext, WeakModel};
+use client::{proto, Client, TypedEnvelope};
+use collections::HashMap;
+use futures::StreamExt;
+use gpui::{
+    AnyWindowHandle, AppContext, AsyncAppContext, Context, Entity, Model, ModelContext, WeakModel,
+};
 use settings::Settings;
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use task::SpawnInTerminal;
 use terminal::{
-    terminal_settings::{self, TerminalSettings, VenvSettingsContent},
-    Terminal, TerminalBuilder,
+    terminal_settings::{self, Shell, TerminalSettings, VenvSettingsContent},
+    RemotePty, RemotePtyInput, TaskState, TaskStatus, Terminal, TerminalBuilder,
 };
+use util::{post_inc, ResultExt};
 
 // #[cfg(target_os = "macos")]
 // use std::os::unix::ffi::OsStrExt;
 
 pub struct Terminals {
     pub(crate) local_handles: Vec<WeakModel<terminal::Terminal>>,
+    /// The terminals of a project served by a headless project server, whose
+    /// shells run on the server's machine.
+    pub(crate) remote: Option<RemoteTerminals>,
+}
+
+#[derive(Default)]
+pub(crate) struct RemoteTerminals {
+    next_id: u64,
+    ptys: HashMap<u64, RemotePty>,
+}
+
+impl Terminals {
+    /// Ends the terminals whose shells ran on the host, now that it's gone.
+    pub(crate) fn disconnected_from_host(&mut self) {
+        if let Some(remote) = &mut self.remote {
+            remote.ptys.clear();
+        }
+    }
+}
+
+/// How a terminal runs, once its profile and task are applied.
+#[derive(Debug)]
+pub(crate) struct TerminalLaunch {
+    pub(crate) profile: Option<String>,
+    pub(crate) shell: Shell,
+    pub(crate) env: HashMap<String, String>,
+    pub(crate) working_directory: Option<PathBuf>,
+    pub(crate) python_settings: terminal_settings::VenvSettings,
+    pub(crate) task_state: Option<TaskState>,
 }
 
 impl Project {
+    /// The terminal settings of the project, which its `.zed/settings.json` can
+    /// change, for example to pick the default profile.
+    pub fn terminal_settings<'a>(&self, cx: &'a AppContext) -> &'a TerminalSettings {
+        let location = self
+            .visible_worktrees(cx)
+            .next()
+            .map(|worktree| (worktree.read(cx).id().to_usize(), Path::new("")));
+        TerminalSettings::get(location, cx)
+    }
+
+    /// How a terminal started with the named profile, or the default profile when no
+    /// name is given, runs. A task replaces the profile's shell with its command.
+    pub(crate) fn terminal_launch(
+        &self,
+        working_directory: Option<PathBuf>,
+        profile: Option<&str>,
+        spawn_task: Option<SpawnInTerminal>,
+        cx: &AppContext,
+    ) -> TerminalLaunch {
+        let profile = self.terminal_settings(cx).profile(profile);
+        let mut launch = TerminalLaunch {
+            profile: profile.name,
+            shell: profile.shell,
+            env: profile.env,
+            working_directory,
+            python_settings: profile.detect_venv,
+            task_state: None,
+        };
+
+        if let Some(spawn_task) = spawn_task {
+            // Tasks run their command directly, so there's no shell to activate
+            // the virtual environment in.
+            launch.python_settings = terminal_settings::VenvSettings::Off;
+            launch.shell = Shell::WithArguments {
+                program: spawn_task.command,
+                args: spawn_task.args,
+            };
+            launch.env.extend(spawn_task.env);
+            if spawn_task.cwd.is_some() {
+                launch.working_directory = spawn_task.cwd;
+            }
+            launch.task_state = Some(TaskState {
+                id: spawn_task.id,
+                label: spawn_task.label,
+                status: TaskStatus::Running,
+            });
+        }
+        launch
+    }
+
+    /// Starts a terminal with the named profile, or the default profile when no
+    /// name is given.
     pub fn create_terminal(
         &mut self,
         working_directory: Option<PathBuf>,
+        profile: Option<&str>,
+        spawn_task: Option<SpawnInTerminal>,
         window: AnyWindowHandle,
         cx: &mut ModelContext<Self>,
     ) -> anyhow::Result<Model<Terminal>> {
-        if self.is_remote() {
+        let launch = self.terminal_launch(working_directory, profile, spawn_task, cx);
+        if self.terminals.remote.is_some() {
+            return self.create_remote_terminal(launch, cx);
+        } else if self.is_remote() {
             return Err(anyhow::anyhow!(
                 "creating terminals as a guest is not supported yet"
             ));
-        } else {
-            let settings = TerminalSettings::get_global(cx);
-            let python_settings = settings.detect_venv.clone();
-            let shell = settings.shell.clone();
-
-            let terminal = TerminalBuilder::new(
-                working_directory.clone(),
-                shell.clone(),
-                settings.env.clone(),
-                Some(settings.blinking.clone()),
-                settings.alternate_scroll,
-                window,
-            )
-            .map(|builder| {
-                let terminal_handle = cx.new_model(|cx| builder.subscribe(cx));
-
-                self.terminals
-                    .local_handles
-                    .push(terminal_handle.downgrade());
-
-                let id = terminal_handle.entity_id();
-                cx.observe_release(&terminal_handle, move |project, _terminal, cx| {
-                    let handles = &mut project.terminals.local_handles;
-
-                    if let Some(index) = handles
-                        .iter()
-                        .position(|terminal| terminal.entity_id() == id)
-                    {
-                        handles.remove(index);
-                        cx.notify();
+        }
+
+        let TerminalLaunch {
+            profile,
+            shell,
+            env,
+            working_directory,
+            python_settings,
+            task_state,
+        } = launch;
+        let settings = self.terminal_settings(cx);
+
+        // Tasks end with their command, so they don't outlive Zed.
+        let persistent = settings.persistent_sessions && task_state.is_none();
+
+        let terminal = TerminalBuilder::new(
+            working_directory.clone(),
+            shell,
+            env,
+            Some(settings.blinking.clone()),
+            settings.alternate_scroll,
+            settings.shell_integration,
+            persistent,
+            profile,
+            task_state,
+            window,
+        )
+        .map(|builder| {
+            let terminal_handle = self.add_terminal(builder, cx);
+
+            if let Some(python_settings) = &python_settings.as_option() {
+                let activate_command = Project::get_activate_command(python_settings);
+                let activate_script_path =
+                    self.find_activate_script_path(python_settings, working_directory);
+                self.activate_python_virtual_environment(
+                    activate_command,
+                    activate_script_path,
+                    &terminal_handle,
+                    cx,
+                );
+            }
+            terminal_handle
+        });
+
+        terminal
+    }
+
+    /// Starts a terminal whose shell runs on the host of a project served by a
+    /// headless project server, carrying its input and output over the project's
+    /// connection to the server.
+    fn create_remote_terminal(
+        &mut self,
+        launch: TerminalLaunch,
+        cx: &mut ModelContext<Self>,
+    ) -> anyhow::Result<Model<Terminal>> {
+        let project_id = self
+            .remote_id()
+            .ok_or_else(|| anyhow::anyhow!("the project has no remote id"))?;
+        let TerminalLaunch {
+            profile,
+            shell,
+            env,
+            working_directory,
+            task_state,
+            ..
+        } = launch;
+        // Without a shell of its own, the terminal runs the server user's shell.
+        let (program, args) = match shell {
+            Shell::System => (None, Vec::new()),
+            Shell::Program(program) => (Some(program), Vec::new()),
+            Shell::WithArguments { program, args } => (Some(program), args),
+        };
+
+        let settings = self.terminal_settings(cx);
+        let (builder, pty, mut input_rx) = TerminalBuilder::remote(
+            Some(settings.blinking.clone()),
+            settings.alternate_scroll,
+            profile,
+            task_state,
+        )?;
+        let remote = self
+            .terminals
+            .remote
+            .as_mut()
+            .expect("remote terminals are only created for headless projects");
+        let terminal_id = post_inc(&mut remote.next_id);
+        remote.ptys.insert(terminal_id, pty);
+        let terminal_handle = self.add_terminal(builder, cx);
+
+        let client = self.client.clone();
+        let open_terminal = client.request(proto::OpenTerminal {
+            project_id,
+            terminal_id,
+            working_directory: working_directory
+                .map(|directory| directory.to_string_lossy().into_owned()),
+            program,
+            args,
+            env: env
+                .into_iter()
+                .map(|(name, value)| proto::TerminalEnvVar { name, value })
+                .collect(),
+        });
+        cx.spawn(|this, mut cx| async move {
+            if let Err(error) = open_terminal.await {
+                log::error!("failed to open a terminal on the remote host: {error:#}");
+                this.update(&mut cx, |this, _| {
+                    if let Some(pty) = this.remote_terminal_pty(terminal_id) {
+                        pty.exited(None).log_err();
                     }
                 })
-                .detach();
-
-                if let Some(python_settings) = &python_settings.as_option() {
-                    let activate_command = Project::get_activate_command(python_settings);
-                    let activate_script_path =
-                        self.find_activate_script_path(python_settings, working_directory);
-                    self.activate_python_virtual_environment(
-                        activate_command,
-                        activate_script_path,
-                        &terminal_handle,
-                        cx,
-                    );
+                .ok();
+            } else {
+                // The input ends once the terminal is dropped.
+                while let Some(input) = input_rx.next().await {
+                    let sent = match input {
+                        RemotePtyInput::Input(input) => client.send(proto::TerminalInput {
+                            project_id,
+                            terminal_id,
+                            input,
+                        }),
+                        RemotePtyInput::Resize {
+                            rows,
+                            columns,
+                            cell_width,
+                            cell_height,
+                        } => client.send(proto::ResizeTerminal {
+                            project_id,
+                            terminal_id,
+                            rows: rows as u32,
+                            columns: columns as u32,
+                            cell_width: cell_width as u32,
+                            cell_height: cell_height as u32,
+                        }),
+                    };
+                    sent.log_err();
                 }
-                terminal_handle
-            });
+                client
+                    .send(proto::CloseTerminal {
+                        project_id,
+                        terminal_id,
+                    })
+                    .log_err();
+            }
+
+            this.update(&mut cx, |this, _| {
+                if let Some(remote) = &mut this.terminals.remote {
+                    remote.ptys.remove(&terminal_id);
+                }
+            })
+            .ok();
+        })
+        .detach();
+
+        Ok(terminal_handle)
+    }
+
+    fn remote_terminal_pty(&mut self, terminal_id: u64) -> Option<&mut RemotePty> {
+        self.terminals.remote.as_mut()?.ptys.get_mut(&terminal_id)
+    }
+
+    pub(crate) async fn handle_terminal_output(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::TerminalOutput>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> anyhow::Result<()> {
+        this.update(&mut cx, |this, _| {
+            if let Some(pty) = this.remote_terminal_pty(envelope.payload.terminal_id) {
+                pty.output(&envelope.payload.output)?;
+            }
+            Ok(())
+        })?
+    }
+
+    pub(crate) async fn handle_terminal_exited(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::TerminalExited>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> anyhow::Result<()> {
+        this.update(&mut cx, |this, _| {
+            if let Some(pty) = this.remote_terminal_pty(envelope.payload.terminal_id) {
+                pty.exited(envelope.payload.exit_code)?;
+            }
+            Ok(())
+        })?
+    }
 
-            terminal
+    /// Reattaches to a persistent terminal session, in which the shell kept
+    /// running after its terminal, started with the named profile, was dropped.
+    pub fn attach_terminal_session(
+        &mut self,
+        session_id: String,
+        profile: Option<String>,
+        cx: &mut ModelContext<Self>,
+    ) -> anyhow::Result<Model<Terminal>> {
+        if self.is_remote() {
+            return Err(anyhow::anyhow!(
+                "persistent terminal sessions are only kept for local projects"
+            ));
         }
+        let settings = TerminalSettings::get_global(cx);
+        let builder = TerminalBuilder::attach(
+            session_id,
+            Some(settings.blinking.clone()),
+            settings.alternate_scroll,
+            profile,
+        )?;
+        Ok(self.add_terminal(builder, cx))
+    }
+
+    fn add_terminal(
+        &mut self,
+        builder: TerminalBuilder,
+        cx: &mut ModelContext<Self>,
+    ) -> Model<Terminal> {
+        let terminal_handle = cx.new_model(|cx| builder.subscribe(cx));
+
+        self.terminals
+            .local_handles
+            .push(terminal_handle.downgrade());
+
+        let id = terminal_handle.entity_id();
+        cx.observe_release(&terminal_handle, move |project, _terminal, cx| {
+            let handles = &mut project.terminals.local_handles;
+
+            if let Some(index) = handles
+                .iter()
+                .position(|terminal| terminal.entity_id() == id)
+            {
+                handles.remove(index);
+                cx.notify();
+            }
+        })
+        .detach();
+
+        terminal_handle
     }
 
     pub fn find_activate_script_path(
@@ -24,7 +24,7 @@ use fuzzy::CharBag;
 use git::{DOT_GIT, GITIGNORE};
 use gpui::{
     AppContext, AsyncAppContext, BackgroundExecutor, Context, EventEmitter, Model, ModelContext,
-    Task,
+    Task, WeakModel,
 };
 use itertools::Itertools;
 use language::{
@@ -42,6 +42,7 @@ use postage::{
     prelude::{Sink as _, Stream as _},
     watch,
 };
+use rpc::{ErrorCode, ErrorCodeExt as _};
 use settings::{Settings, SettingsStore};
 use smol::channel::{self, Sender};
 use std::{
@@ -95,8 +96,26 @@ pub struct LocalWorktree {
     fs: Arc<dyn Fs>,
     fs_case_sensitive: bool,
     visible: bool,
+    loading_files: HashMap<Arc<Path>, FileLoadProgress>,
 }
 
+/// How much of a large file has been streamed into its buffer so far.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileLoadProgress {
+    pub loaded: u64,
+    pub total: u64,
+}
+
+/// The contents of a file loaded from disk, read whole unless the file is
+/// above the large file threshold.
+enum LoadedText {
+    Text(String),
+    Large { text: Rope, line_ending: LineEnding },
+}
+
+/// How many bytes of a large file are read and decoded at a time.
+const LARGE_FILE_CHUNK_SIZE: usize = 1024 * 1024;
+
 struct ScanRequest {
     relative_paths: Vec<Arc<Path>>,
     done: barrier::Sender,
@@ -445,6 +464,7 @@ impl Worktree {
                 fs,
                 fs_case_sensitive,
                 visible,
+                loading_files: Default::default(),
             })
         })
     }
@@ -729,21 +749,36 @@ impl LocalWorktree {
             let (file, contents, diff_base) = this
                 .update(&mut cx, |t, cx| t.as_local().unwrap().load(&path, cx))?
                 .await?;
+            let is_large_file = matches!(contents, LoadedText::Large { .. });
             let text_buffer = cx
                 .background_executor()
-                .spawn(async move { text::Buffer::new(0, id, contents) })
+                .spawn(async move {
+                    match contents {
+                        LoadedText::Text(text) => text::Buffer::new(0, id, text),
+                        LoadedText::Large { text, line_ending } => {
+                            text::Buffer::new_normalized(0, id, line_ending, text)
+                        }
+                    }
+                })
                 .await;
             cx.new_model(|_| {
-                Buffer::build(
+                let mut buffer = Buffer::build(
                     text_buffer,
                     diff_base,
                     Some(Arc::new(file)),
                     Capability::ReadWrite,
-                )
+                );
+                buffer.set_large_file(is_large_file);
+                buffer
             })
         })
     }
 
+    /// The files above the large file threshold that are still being read.
+    pub fn loading_files(&self) -> impl Iterator<Item = (&Arc<Path>, &FileLoadProgress)> {
+        self.loading_files.iter()
+    }
+
     pub fn diagnostics_for_path(
         &self,
         path: &Path,
@@ -1004,22 +1039,72 @@ impl LocalWorktree {
         }
     }
 
+    /// Loads the contents of a file at a revision of the git repository containing it,
+    /// or `None` if the file doesn't exist at that revision.
+    pub fn load_revision_text(
+        &self,
+        path: &Path,
+        revision: String,
+        cx: &AppContext,
+    ) -> Task<Result<Option<String>>> {
+        let snapshot = self.snapshot();
+        let Some(repo) = snapshot.repository_for_path(path) else {
+            return Task::ready(Err(anyhow!("{path:?} isn't in a git repository")));
+        };
+        let repo_path = match repo.work_directory.relativize(&snapshot, path) {
+            Ok(repo_path) => repo_path,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let Some(git_repo) = snapshot.git_repositories.get(&*repo.work_directory) else {
+            return Task::ready(Err(anyhow!("{path:?} isn't in a git repository")));
+        };
+        let git_repo = git_repo.repo_ptr.clone();
+        cx.background_executor()
+            .spawn(async move { git_repo.lock().load_revision_text(&repo_path, &revision) })
+    }
+
     fn load(
         &self,
         path: &Path,
         cx: &mut ModelContext<Worktree>,
-    ) -> Task<Result<(File, String, Option<String>)>> {
+    ) -> Task<Result<(File, LoadedText, Option<String>)>> {
         let path = Arc::from(path);
         let abs_path = self.absolutize(&path);
         let fs = self.fs.clone();
         let entry = self.refresh_entry(path.clone(), None, cx);
+        let large_file_threshold = ProjectSettings::get_global(cx).large_file_threshold;
 
         cx.spawn(|this, mut cx| async move {
             let abs_path = abs_path?;
-            let text = fs.load(&abs_path).await?;
+            let len = fs
+                .metadata(&abs_path)
+                .await?
+                .map_or(0, |metadata| metadata.len);
+            let text = if large_file_threshold.map_or(false, |threshold| len > threshold) {
+                Self::load_large_file(
+                    &this,
+                    fs.clone(),
+                    abs_path.clone(),
+                    path.clone(),
+                    len,
+                    &mut cx,
+                )
+                .await?
+            } else {
+                // Files that aren't valid UTF-8 are opened as raw bytes instead of as buffers.
+                LoadedText::Text(
+                    String::from_utf8(fs.load_bytes(&abs_path).await?)
+                        .map_err(|_| ErrorCode::BinaryFile.anyhow())?,
+                )
+            };
             let mut index_task = None;
             let snapshot = this.update(&mut cx, |this, _| this.as_local().unwrap().snapshot())?;
-            if let Some(repo) = snapshot.repository_for_path(&path) {
+            let repository = match text {
+                // Large files aren't diffed, so there's no need to load their index text.
+                LoadedText::Large { .. } => None,
+                LoadedText::Text(_) => snapshot.repository_for_path(&path),
+            };
+            if let Some(repo) = repository {
                 if let Some(repo_path) = repo.work_directory.relativize(&snapshot, &path).log_err()
                 {
                     if let Some(git_repo) = snapshot.git_repositories.get(&*repo.work_directory) {
@@ -1084,6 +1169,76 @@ impl LocalWorktree {
         })
     }
 
+    /// Streams a large file into a rope in the background, reporting how much
+    /// of it has been read through the worktree's loading files.
+    async fn load_large_file(
+        this: &WeakModel<Worktree>,
+        fs: Arc<dyn Fs>,
+        abs_path: PathBuf,
+        path: Arc<Path>,
+        total: u64,
+        cx: &mut AsyncAppContext,
+    ) -> Result<LoadedText> {
+        let set_progress = |progress: Option<FileLoadProgress>, cx: &mut AsyncAppContext| {
+            this.update(cx, |this, cx| {
+                let loading_files = &mut this.as_local_mut().unwrap().loading_files;
+                match progress {
+                    Some(progress) => loading_files.insert(path.clone(), progress),
+                    None => loading_files.remove(&path),
+                };
+                cx.notify();
+            })
+        };
+
+        set_progress(Some(FileLoadProgress { loaded: 0, total }), cx)?;
+        let (progress_tx, mut progress_rx) = mpsc::unbounded();
+        let load = cx.background_executor().spawn(async move {
+            let mut chunks = fs.load_chunks(&abs_path, LARGE_FILE_CHUNK_SIZE).await?;
+            let mut text = Rope::new();
+            let mut line_ending = None;
+            let mut pending = Vec::new();
+            let mut loaded = 0;
+            while let Some(chunk) = chunks.next().await {
+                let chunk = chunk?;
+                loaded += chunk.len() as u64;
+                pending.extend_from_slice(&chunk);
+
+                // Hold back a character split across chunks, as well as a trailing
+                // carriage return that may be followed by a line feed.
+                let mut decoded_len = match std::str::from_utf8(&pending) {
+                    Ok(_) => pending.len(),
+                    Err(error) if error.error_len().is_none() => error.valid_up_to(),
+                    Err(_) => return Err(ErrorCode::BinaryFile.anyhow()),
+                };
+                if pending[..decoded_len].ends_with(b"\r") {
+                    decoded_len -= 1;
+                }
+                let rest = pending.split_off(decoded_len);
+                let mut decoded = String::from_utf8(mem::replace(&mut pending, rest))?;
+                line_ending.get_or_insert_with(|| LineEnding::detect(&decoded));
+                LineEnding::normalize(&mut decoded);
+                text.push(&decoded);
+                progress_tx.unbounded_send(loaded).ok();
+            }
+
+            let mut decoded =
+                String::from_utf8(pending).map_err(|_| ErrorCode::BinaryFile.anyhow())?;
+            LineEnding::normalize(&mut decoded);
+            text.push(&decoded);
+            Ok(LoadedText::Large {
+                text,
+                line_ending: line_ending.unwrap_or_default(),
+            })
+        });
+
+        while let Some(loaded) = progress_rx.next().await {
+            set_progress(Some(FileLoadProgress { loaded, total }), cx)?;
+        }
+        let text = load.await;
+        set_progress(None, cx)?;
+        text
+    }
+
     pub fn save_buffer(
         &self,
         buffer_handle: Model<Buffer>,
@@ -8,7 +8,7 @@ use editor::{actions::Cancel, scroll::Autoscroll, Editor};
 use file_associations::FileAssociations;
 
 use anyhow::{anyhow, Result};
-use collections::{hash_map, HashMap};
+use collections::{hash_map, BTreeSet, HashMap};
 use gpui::{
     actions, div, overlay, px, uniform_list, Action, AppContext, AssetSource, AsyncWindowContext,
     ClipboardItem, DismissEvent, Div, EventEmitter, FocusHandle, FocusableView, InteractiveElement,
@@ -23,15 +23,21 @@ use project::{
 };
 use project_panel_settings::{ProjectPanelDockPosition, ProjectPanelSettings};
 use serde::{Deserialize, Serialize};
-use std::{cmp::Ordering, ffi::OsStr, ops::Range, path::Path, sync::Arc};
+use std::{
+    cmp::Ordering,
+    ffi::OsStr,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use theme::ThemeSettings;
-use ui::{prelude::*, v_flex, ContextMenu, Icon, KeyBinding, Label, ListItem};
+use ui::{prelude::*, v_flex, ContextMenu, Icon, KeyBinding, Label, ListItem, Tooltip};
 use unicase::UniCase;
 use util::{maybe, ResultExt, TryFutureExt};
 use workspace::{
     dock::{DockPosition, Panel, PanelEvent},
     notifications::DetachAndPromptErr,
-    Workspace,
+    DraggedSelection, SelectedEntry, Workspace,
 };
 
 const PROJECT_PANEL_KEY: &'static str = "ProjectPanel";
@@ -45,7 +51,8 @@ pub struct ProjectPanel {
     visible_entries: Vec<(WorktreeId, Vec<Entry>)>,
     last_worktree_root_id: Option<ProjectEntryId>,
     expanded_dir_ids: HashMap<WorktreeId, Vec<ProjectEntryId>>,
-    selection: Option<Selection>,
+    selection: Option<SelectedEntry>,
+    marked_entries: BTreeSet<SelectedEntry>,
     context_menu: Option<(View<ContextMenu>, Point<Pixels>, Subscription)>,
     edit_state: Option<EditState>,
     filename_editor: View<Editor>,
@@ -56,12 +63,6 @@ pub struct ProjectPanel {
     pending_serialization: Task<Option<()>>,
 }
 
-#[derive(Copy, Clone, Debug)]
-struct Selection {
-    worktree_id: WorktreeId,
-    entry_id: ProjectEntryId,
-}
-
 #[derive(Clone, Debug)]
 struct EditState {
     worktree_id: WorktreeId,
@@ -71,20 +72,15 @@ struct EditState {
     processing_filename: Option<String>,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Clone, Debug)]
 pub enum ClipboardEntry {
-    Copied {
-        worktree_id: WorktreeId,
-        entry_id: ProjectEntryId,
-    },
-    Cut {
-        worktree_id: WorktreeId,
-        entry_id: ProjectEntryId,
-    },
+    Copied(BTreeSet<SelectedEntry>),
+    Cut(BTreeSet<SelectedEntry>),
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct EntryDetails {
+    worktree_id: WorktreeId,
     filename: String,
     icon: Option<Arc<str>>,
     path: Arc<Path>,
@@ -93,6 +89,7 @@ pub struct EntryDetails {
     is_ignored: bool,
     is_expanded: bool,
     is_selected: bool,
+    is_marked: bool,
     is_editing: bool,
     is_processing: bool,
     is_cut: bool,
@@ -144,6 +141,7 @@ pub enum Event {
     OpenedEntry {
         entry_id: ProjectEntryId,
         focus_opened_item: bool,
+        allow_preview: bool,
     },
     SplitEntry {
         entry_id: ProjectEntryId,
@@ -157,9 +155,10 @@ struct SerializedProjectPanel {
 }
 
 struct DraggedProjectEntryView {
-    entry_id: ProjectEntryId,
+    selection: SelectedEntry,
     details: EntryDetails,
     width: Pixels,
+    selections: Arc<BTreeSet<SelectedEntry>>,
 }
 
 impl ProjectPanel {
@@ -232,6 +231,7 @@ impl ProjectPanel {
                 last_worktree_root_id: Default::default(),
                 expanded_dir_ids: Default::default(),
                 selection: None,
+                marked_entries: Default::default(),
                 edit_state: None,
                 context_menu: None,
                 filename_editor,
@@ -252,6 +252,7 @@ impl ProjectPanel {
                 &Event::OpenedEntry {
                     entry_id,
                     focus_opened_item,
+                    allow_preview,
                 } => {
                     if let Some(worktree) = project.read(cx).worktree_for_entry(entry_id, cx) {
                         if let Some(entry) = worktree.read(cx).entry_for_id(entry_id) {
@@ -260,13 +261,14 @@ impl ProjectPanel {
                             let entry_id = entry.id;
 
                             workspace
-                                .open_path(
+                                .open_path_preview(
                                     ProjectPath {
                                         worktree_id,
                                         path: file_path.clone(),
                                     },
                                     None,
                                     focus_opened_item,
+                                    allow_preview,
                                     cx,
                                 )
                                 .detach_and_prompt_err("Failed to open file", cx, move |e, _| {
@@ -282,7 +284,7 @@ impl ProjectPanel {
                             if let Some(project_panel) = project_panel.upgrade() {
                                 // Always select the entry, regardless of whether it is opened or not.
                                 project_panel.update(cx, |project_panel, _| {
-                                    project_panel.selection = Some(Selection {
+                                    project_panel.selection = Some(SelectedEntry {
                                         worktree_id,
                                         entry_id
                                     });
@@ -383,10 +385,14 @@ impl ProjectPanel {
             return;
         };
 
-        self.selection = Some(Selection {
+        let selection = SelectedEntry {
             worktree_id,
             entry_id,
-        });
+        };
+        if !self.marked_entries.contains(&selection) {
+            self.marked_entries.clear();
+        }
+        self.selection = Some(selection);
 
         if let Some((worktree, entry)) = self.selected_entry(cx) {
             let is_root = Some(entry) == worktree.root_entry();
@@ -427,10 +433,13 @@ impl ProjectPanel {
                         .separator()
                         .action("Cut", Box::new(Cut))
                         .action("Copy", Box::new(Copy))
-                        .when_some(self.clipboard_entry, |menu, entry| {
-                            menu.when(entry.worktree_id() == worktree_id, |menu| {
-                                menu.action("Paste", Box::new(Paste))
-                            })
+                        .when_some(self.clipboard_entry.as_ref(), |menu, entry| {
+                            menu.when(
+                                entry
+                                    .items()
+                                    .any(|selection| selection.worktree_id == worktree_id),
+                                |menu| menu.action("Paste", Box::new(Paste)),
+                            )
                         })
                         .separator()
                         .action("Copy Path", Box::new(CopyPath))
@@ -548,6 +557,7 @@ impl ProjectPanel {
     }
 
     fn select_prev(&mut self, _: &SelectPrev, cx: &mut ViewContext<Self>) {
+        self.marked_entries.clear();
         if let Some(selection) = self.selection {
             let (mut worktree_ix, mut entry_ix, _) =
                 self.index_for_selection(selection).unwrap_or_default();
@@ -561,7 +571,7 @@ impl ProjectPanel {
             }
 
             let (worktree_id, worktree_entries) = &self.visible_entries[worktree_ix];
-            self.selection = Some(Selection {
+            self.selection = Some(SelectedEntry {
                 worktree_id: *worktree_id,
                 entry_id: worktree_entries[entry_ix].id,
             });
@@ -581,7 +591,7 @@ impl ProjectPanel {
     fn open(&mut self, _: &Open, cx: &mut ViewContext<Self>) {
         if let Some((_, entry)) = self.selected_entry(cx) {
             if entry.is_file() {
-                self.open_entry(entry.id, true, cx);
+                self.open_entry(entry.id, true, false, cx);
             } else {
                 self.toggle_expanded(entry.id, cx);
             }
@@ -603,7 +613,7 @@ impl ProjectPanel {
         let edit_task;
         let edited_entry_id;
         if is_new_entry {
-            self.selection = Some(Selection {
+            self.selection = Some(SelectedEntry {
                 worktree_id,
                 entry_id: NEW_ENTRY_ID,
             });
@@ -653,7 +663,7 @@ impl ProjectPanel {
                     }
                     this.update_visible_entries(None, cx);
                     if is_new_entry && !is_dir {
-                        this.open_entry(new_entry.id, true, cx);
+                        this.open_entry(new_entry.id, true, false, cx);
                     }
                     cx.notify();
                 })?;
@@ -664,6 +674,7 @@ impl ProjectPanel {
 
     fn cancel(&mut self, _: &Cancel, cx: &mut ViewContext<Self>) {
         self.edit_state = None;
+        self.marked_entries.clear();
         self.update_visible_entries(None, cx);
         cx.focus(&self.focus_handle);
         cx.notify();
@@ -673,11 +684,13 @@ impl ProjectPanel {
         &mut self,
         entry_id: ProjectEntryId,
         focus_opened_item: bool,
+        allow_preview: bool,
         cx: &mut ViewContext<Self>,
     ) {
         cx.emit(Event::OpenedEntry {
             entry_id,
             focus_opened_item,
+            allow_preview,
         });
     }
 
@@ -694,7 +707,7 @@ impl ProjectPanel {
     }
 
     fn add_entry(&mut self, is_dir: bool, cx: &mut ViewContext<Self>) {
-        if let Some(Selection {
+        if let Some(SelectedEntry {
             worktree_id,
             entry_id,
         }) = self.selection
@@ -750,7 +763,7 @@ impl ProjectPanel {
     }
 
     fn rename(&mut self, _: &Rename, cx: &mut ViewContext<Self>) {
-        if let Some(Selection {
+        if let Some(SelectedEntry {
             worktree_id,
             entry_id,
         }) = self.selection
@@ -790,27 +803,58 @@ impl ProjectPanel {
 
     fn delete(&mut self, _: &Delete, cx: &mut ViewContext<Self>) {
         maybe!({
-            let Selection { entry_id, .. } = self.selection?;
-            let path = self.project.read(cx).path_for_entry(entry_id, cx)?.path;
-            let file_name = path.file_name()?;
-
-            let answer = cx.prompt(
-                PromptLevel::Info,
-                &format!("Delete {file_name:?}?"),
-                None,
-                &["Delete", "Cancel"],
-            );
+            let project = self.project.read(cx);
+            let entries_to_delete = self
+                .disjoint_entries(self.effective_entries(), cx)
+                .into_iter()
+                .filter_map(|selection| {
+                    let path = project.path_for_entry(selection.entry_id, cx)?.path;
+                    Some((selection.entry_id, path.file_name()?.to_os_string()))
+                })
+                .collect::<Vec<_>>();
+
+            let prompt = match entries_to_delete.as_slice() {
+                [] => return None,
+                [(_, file_name)] => format!("Delete {file_name:?}?"),
+                entries => {
+                    let file_names = entries
+                        .iter()
+                        .map(|(_, file_name)| format!("{file_name:?}"))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    format!(
+                        "Delete the following {} items?\n{file_names}",
+                        entries.len()
+                    )
+                }
+            };
+            let answer = cx.prompt(PromptLevel::Info, &prompt, None, &["Delete", "Cancel"]);
 
             cx.spawn(|this, mut cx| async move {
                 if answer.await != Ok(0) {
                     return Ok(());
                 }
                 this.update(&mut cx, |this, cx| {
-                    this.project
-                        .update(cx, |project, cx| project.delete_entry(entry_id, cx))
-                        .ok_or_else(|| anyhow!("no such entry"))
-                })??
-                .await
+                    file_history::snapshot_deleted_entries(
+                        &this.project,
+                        entries_to_delete.iter().map(|(entry_id, _)| *entry_id),
+                        cx,
+                    )
+                })?
+                .await;
+                let delete_tasks = this.update(&mut cx, |this, cx| {
+                    this.marked_entries.clear();
+                    entries_to_delete
+                        .into_iter()
+                        .map(|(entry_id, _)| {
+                            this.project
+                                .update(cx, |project, cx| project.delete_entry(entry_id, cx))
+                                .ok_or_else(|| anyhow!("no such entry"))
+                        })
+                        .collect::<Result<Vec<_>>>()
+                })??;
+                futures::future::try_join_all(delete_tasks).await?;
+                Ok(())
             })
             .detach_and_log_err(cx);
             Some(())
@@ -818,6 +862,7 @@ impl ProjectPanel {
     }
 
     fn select_next(&mut self, _: &SelectNext, cx: &mut ViewContext<Self>) {
+        self.marked_entries.clear();
         if let Some(selection) = self.selection {
             let (mut worktree_ix, mut entry_ix, _) =
                 self.index_for_selection(selection).unwrap_or_default();
@@ -832,7 +877,7 @@ impl ProjectPanel {
 
             if let Some((worktree_id, worktree_entries)) = self.visible_entries.get(worktree_ix) {
                 if let Some(entry) = worktree_entries.get(entry_ix) {
-                    self.selection = Some(Selection {
+                    self.selection = Some(SelectedEntry {
                         worktree_id: *worktree_id,
                         entry_id: entry.id,
                     });
@@ -854,7 +899,7 @@ impl ProjectPanel {
             let worktree = worktree.read(cx);
             let worktree_id = worktree.id();
             if let Some(root_entry) = worktree.root_entry() {
-                self.selection = Some(Selection {
+                self.selection = Some(SelectedEntry {
                     worktree_id,
                     entry_id: root_entry.id,
                 });
@@ -872,21 +917,18 @@ impl ProjectPanel {
     }
 
     fn cut(&mut self, _: &Cut, cx: &mut ViewContext<Self>) {
-        if let Some((worktree, entry)) = self.selected_entry(cx) {
-            self.clipboard_entry = Some(ClipboardEntry::Cut {
-                worktree_id: worktree.id(),
-                entry_id: entry.id,
-            });
+        let entries = self.effective_entries();
+        if !entries.is_empty() {
+            self.clipboard_entry = Some(ClipboardEntry::Cut(entries));
+            self.marked_entries.clear();
             cx.notify();
         }
     }
 
     fn copy(&mut self, _: &Copy, cx: &mut ViewContext<Self>) {
-        if let Some((worktree, entry)) = self.selected_entry(cx) {
-            self.clipboard_entry = Some(ClipboardEntry::Copied {
-                worktree_id: worktree.id(),
-                entry_id: entry.id,
-            });
+        let entries = self.effective_entries();
+        if !entries.is_empty() {
+            self.clipboard_entry = Some(ClipboardEntry::Copied(entries));
             cx.notify();
         }
     }
@@ -894,63 +936,100 @@ impl ProjectPanel {
     fn paste(&mut self, _: &Paste, cx: &mut ViewContext<Self>) {
         maybe!({
             let (worktree, entry) = self.selected_entry(cx)?;
-            let clipboard_entry = self.clipboard_entry?;
-            if clipboard_entry.worktree_id() != worktree.id() {
+            let clipboard_entry = self.clipboard_entry.as_ref()?;
+            let clipboard_entries = self.disjoint_entries(
+                clipboard_entry
+                    .items()
+                    .filter(|selection| selection.worktree_id == worktree.id())
+                    .copied(),
+                cx,
+            );
+            if clipboard_entries.is_empty() {
                 return None;
             }
 
-            let clipboard_entry_file_name = self
-                .project
-                .read(cx)
-                .path_for_entry(clipboard_entry.entry_id(), cx)?
-                .path
-                .file_name()?
-                .to_os_string();
-
-            let mut new_path = entry.path.to_path_buf();
+            let mut destination_dir = entry.path.to_path_buf();
             if entry.is_file() {
-                new_path.pop();
+                destination_dir.pop();
             }
 
-            new_path.push(&clipboard_entry_file_name);
-            let extension = new_path.extension().map(|e| e.to_os_string());
-            let file_name_without_extension = Path::new(&clipboard_entry_file_name).file_stem()?;
-            let mut ix = 0;
-            while worktree.entry_for_path(&new_path).is_some() {
-                new_path.pop();
-
-                let mut new_file_name = file_name_without_extension.to_os_string();
-                new_file_name.push(" copy");
-                if ix > 0 {
-                    new_file_name.push(format!(" {}", ix));
+            let is_cut = clipboard_entry.is_cut();
+            let mut new_paths = BTreeSet::new();
+            let mut paste_entries = Vec::new();
+            for clipboard_entry in clipboard_entries {
+                if let Some(new_path) = self.paste_path(
+                    worktree,
+                    clipboard_entry.entry_id,
+                    &destination_dir,
+                    &new_paths,
+                    cx,
+                ) {
+                    new_paths.insert(new_path.clone());
+                    paste_entries.push((clipboard_entry.entry_id, new_path));
                 }
-                if let Some(extension) = extension.as_ref() {
-                    new_file_name.push(".");
-                    new_file_name.push(extension);
-                }
-
-                new_path.push(new_file_name);
-                ix += 1;
             }
 
-            if clipboard_entry.is_cut() {
-                self.project
-                    .update(cx, |project, cx| {
-                        project.rename_entry(clipboard_entry.entry_id(), new_path, cx)
-                    })
-                    .detach_and_log_err(cx)
-            } else {
-                self.project
-                    .update(cx, |project, cx| {
-                        project.copy_entry(clipboard_entry.entry_id(), new_path, cx)
-                    })
-                    .detach_and_log_err(cx)
+            for (entry_id, new_path) in paste_entries {
+                if is_cut {
+                    self.project
+                        .update(cx, |project, cx| {
+                            project.rename_entry(entry_id, new_path, cx)
+                        })
+                        .detach_and_log_err(cx)
+                } else {
+                    self.project
+                        .update(cx, |project, cx| project.copy_entry(entry_id, new_path, cx))
+                        .detach_and_log_err(cx)
+                }
             }
 
             Some(())
         });
     }
 
+    /// Picks a path in `destination_dir` for the pasted entry, appending " copy" to its
+    /// name if the path is already taken.
+    fn paste_path(
+        &self,
+        worktree: &Worktree,
+        entry_id: ProjectEntryId,
+        destination_dir: &Path,
+        pending_paths: &BTreeSet<PathBuf>,
+        cx: &AppContext,
+    ) -> Option<PathBuf> {
+        let clipboard_entry_file_name = self
+            .project
+            .read(cx)
+            .path_for_entry(entry_id, cx)?
+            .path
+            .file_name()?
+            .to_os_string();
+
+        let mut new_path = destination_dir.to_path_buf();
+        new_path.push(&clipboard_entry_file_name);
+        let extension = new_path.extension().map(|e| e.to_os_string());
+        let file_name_without_extension = Path::new(&clipboard_entry_file_name).file_stem()?;
+        let mut ix = 0;
+        while worktree.entry_for_path(&new_path).is_some() || pending_paths.contains(&new_path) {
+            new_path.pop();
+
+            let mut new_file_name = file_name_without_extension.to_os_string();
+            new_file_name.push(" copy");
+            if ix > 0 {
+                new_file_name.push(format!(" {}", ix));
+            }
+            if let Some(extension) = extension.as_ref() {
+                new_file_name.push(".");
+                new_file_name.push(extension);
+            }
+
+            new_path.push(new_file_name);
+            ix += 1;
+        }
+
+        Some(new_path)
+    }
+
     fn copy_path(&mut self, _: &CopyPath, cx: &mut ViewContext<Self>) {
         if let Some((worktree, entry)) = self.selected_entry(cx) {
             cx.write_to_clipboard(ClipboardItem::new(
@@ -1004,6 +1083,74 @@ impl ProjectPanel {
         }
     }
 
+    fn toggle_marked(&mut self, selection: SelectedEntry, cx: &mut ViewContext<Self>) {
+        if self.marked_entries.is_empty() {
+            // Keep the entry that was selected before the first toggle as a part of the batch.
+            self.marked_entries.extend(self.selection);
+        }
+        if !self.marked_entries.insert(selection) {
+            self.marked_entries.remove(&selection);
+        }
+        self.selection = Some(selection);
+        cx.notify();
+    }
+
+    fn mark_range_to(&mut self, selection: SelectedEntry, cx: &mut ViewContext<Self>) {
+        let anchor_ix = self
+            .selection
+            .and_then(|anchor| self.index_for_selection(anchor))
+            .map(|(_, _, ix)| ix);
+        let target_ix = self.index_for_selection(selection).map(|(_, _, ix)| ix);
+        if let Some((anchor_ix, target_ix)) = anchor_ix.zip(target_ix) {
+            let range = anchor_ix.min(target_ix)..=anchor_ix.max(target_ix);
+            let visible_entries = self
+                .visible_entries
+                .iter()
+                .flat_map(|(worktree_id, entries)| {
+                    entries.iter().map(|entry| SelectedEntry {
+                        worktree_id: *worktree_id,
+                        entry_id: entry.id,
+                    })
+                })
+                .enumerate()
+                .filter(|(ix, entry)| range.contains(ix) && entry.entry_id != NEW_ENTRY_ID)
+                .map(|(_, entry)| entry)
+                .collect::<Vec<_>>();
+            self.marked_entries.extend(visible_entries);
+        }
+        self.selection = Some(selection);
+        cx.notify();
+    }
+
+    fn drag_onto(
+        &mut self,
+        selections: &DraggedSelection,
+        target_entry_id: ProjectEntryId,
+        is_file: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let entries_to_move = self.disjoint_entries(selections.items().copied(), cx);
+        let target_worktree_id = self
+            .project
+            .read(cx)
+            .worktree_id_for_entry(target_entry_id, cx);
+        if entries_to_move
+            .iter()
+            .any(|selection| Some(selection.worktree_id) != target_worktree_id)
+        {
+            drop(cx.prompt(
+                PromptLevel::Critical,
+                "Can't move entries to another project folder",
+                Some("Entries can only be moved within the folder they belong to."),
+                &["Ok"],
+            ));
+            return;
+        }
+        for selection in entries_to_move {
+            self.move_entry(selection.entry_id, target_entry_id, is_file, cx);
+        }
+    }
+
     fn move_entry(
         &mut self,
         entry_to_move: ProjectEntryId,
@@ -1013,12 +1160,20 @@ impl ProjectPanel {
     ) {
         let destination_worktree = self.project.update(cx, |project, cx| {
             let entry_path = project.path_for_entry(entry_to_move, cx)?;
-            let destination_entry_path = project.path_for_entry(destination, cx)?.path.clone();
+            let destination_entry_path = project.path_for_entry(destination, cx)?;
+            // Entries can only be moved within their worktree.
+            if entry_path.worktree_id != destination_entry_path.worktree_id {
+                return None;
+            }
 
-            let mut destination_path = destination_entry_path.as_ref();
+            let mut destination_path = destination_entry_path.path.as_ref();
             if destination_is_file {
                 destination_path = destination_path.parent()?;
             }
+            // A directory can't be moved into itself or one of its descendants.
+            if destination_path.starts_with(&entry_path.path) {
+                return None;
+            }
 
             let mut new_path = destination_path.to_path_buf();
             new_path.push(entry_path.path.file_name()?);
@@ -1027,7 +1182,7 @@ impl ProjectPanel {
                 cx.foreground_executor().spawn(task).detach_and_log_err(cx);
             }
 
-            Some(project.worktree_id_for_entry(destination, cx)?)
+            Some(destination_entry_path.worktree_id)
         });
 
         if let Some(destination_worktree) = destination_worktree {
@@ -1035,7 +1190,47 @@ impl ProjectPanel {
         }
     }
 
-    fn index_for_selection(&self, selection: Selection) -> Option<(usize, usize, usize)> {
+    /// Returns the entries an action applies to: the marked entries if there are any,
+    /// otherwise the selected one.
+    fn effective_entries(&self) -> BTreeSet<SelectedEntry> {
+        if self.marked_entries.is_empty() {
+            self.selection.into_iter().collect()
+        } else {
+            self.marked_entries.clone()
+        }
+    }
+
+    /// Drops the entries that are nested in other entries of the given set, so that batch
+    /// operations don't act on the same path twice.
+    fn disjoint_entries(
+        &self,
+        entries: impl IntoIterator<Item = SelectedEntry>,
+        cx: &AppContext,
+    ) -> Vec<SelectedEntry> {
+        let project = self.project.read(cx);
+        let entries = entries
+            .into_iter()
+            .filter_map(|selection| {
+                let path = project.path_for_entry(selection.entry_id, cx)?;
+                Some((selection, path))
+            })
+            .collect::<Vec<_>>();
+
+        entries
+            .iter()
+            .filter(|(_, path)| {
+                !entries.iter().any(|(_, other_path)| {
+                    other_path.worktree_id == path.worktree_id
+                        && !other_path.path.as_os_str().is_empty()
+                        && other_path.path != path.path
+                        && path.path.starts_with(&other_path.path)
+                })
+            })
+            .map(|(selection, _)| *selection)
+            .collect()
+    }
+
+    fn index_for_selection(&self, selection: SelectedEntry) -> Option<(usize, usize, usize)> {
         let mut entry_index = 0;
         let mut visible_entries_index = 0;
         for (worktree_index, (worktree_id, worktree_entries)) in
@@ -1198,7 +1393,7 @@ impl ProjectPanel {
         }
 
         if let Some((worktree_id, entry_id)) = new_selected_entry {
-            self.selection = Some(Selection {
+            self.selection = Some(SelectedEntry {
                 worktree_id,
                 entry_id,
             });
@@ -1295,6 +1490,7 @@ impl ProjectPanel {
                     };
 
                     let mut details = EntryDetails {
+                        worktree_id: snapshot.id(),
                         filename: entry
                             .path
                             .file_name()
@@ -1310,11 +1506,15 @@ impl ProjectPanel {
                         is_selected: self.selection.map_or(false, |e| {
                             e.worktree_id == snapshot.id() && e.entry_id == entry.id
                         }),
+                        is_marked: self.marked_entries.contains(&SelectedEntry {
+                            worktree_id: snapshot.id(),
+                            entry_id: entry.id,
+                        }),
                         is_editing: false,
                         is_processing: false,
-                        is_cut: self
-                            .clipboard_entry
-                            .map_or(false, |e| e.is_cut() && e.entry_id() == entry.id),
+                        is_cut: self.clipboard_entry.as_ref().map_or(false, |e| {
+                            e.is_cut() && e.items().any(|selection| selection.entry_id == entry.id)
+                        }),
                         git_status: status,
                         is_dotenv: entry.is_private,
                     };
@@ -1356,9 +1556,14 @@ impl ProjectPanel {
         let kind = details.kind;
         let settings = ProjectPanelSettings::get_global(cx);
         let show_editor = details.is_editing && !details.is_processing;
+        let selection = SelectedEntry {
+            worktree_id: details.worktree_id,
+            entry_id,
+        };
         let is_selected = self
             .selection
             .map_or(false, |selection| selection.entry_id == entry_id);
+        let is_marked = details.is_marked;
         let width = self.width.unwrap_or(px(0.));
 
         let filename_text_color = details
@@ -1369,7 +1574,7 @@ impl ProjectPanel {
                 GitFileStatus::Modified => Color::Modified,
                 GitFileStatus::Conflict => Color::Conflict,
             })
-            .unwrap_or(if is_selected {
+            .unwrap_or(if is_selected || is_marked {
                 Color::Default
             } else if details.is_ignored {
                 Color::Disabled
@@ -1377,29 +1582,37 @@ impl ProjectPanel {
                 Color::Muted
             });
 
+        let is_conflicted = kind.is_file() && details.git_status == Some(GitFileStatus::Conflict);
         let file_name = details.filename.clone();
         let icon = details.icon.clone();
         let depth = details.depth;
         div()
             .id(entry_id.to_proto() as usize)
-            .on_drag(entry_id, move |entry_id, cx| {
-                cx.new_view(|_| DraggedProjectEntryView {
-                    details: details.clone(),
-                    width,
-                    entry_id: *entry_id,
-                })
-            })
-            .drag_over::<ProjectEntryId>(|style, _, cx| {
+            .on_drag(
+                DraggedSelection {
+                    active_selection: selection,
+                    marked_selections: Arc::new(self.marked_entries.clone()),
+                },
+                move |selections, cx| {
+                    cx.new_view(|_| DraggedProjectEntryView {
+                        details: details.clone(),
+                        width,
+                        selection: selections.active_selection,
+                        selections: selections.marked_selections.clone(),
+                    })
+                },
+            )
+            .drag_over::<DraggedSelection>(|style, _, cx| {
                 style.bg(cx.theme().colors().drop_target_background)
             })
-            .on_drop(cx.listener(move |this, dragged_id: &ProjectEntryId, cx| {
-                this.move_entry(*dragged_id, entry_id, kind.is_file(), cx);
+            .on_drop(cx.listener(move |this, selections: &DraggedSelection, cx| {
+                this.drag_onto(selections, entry_id, kind.is_file(), cx);
             }))
             .child(
                 ListItem::new(entry_id.to_proto() as usize)
                     .indent_level(depth)
                     .indent_step_size(px(settings.indent_size))
-                    .selected(is_selected)
+                    .selected(is_selected || is_marked)
                     .child(if let Some(icon) = &icon {
                         div().child(Icon::from_path(icon.to_string()).color(filename_text_color))
                     } else {
@@ -1413,18 +1626,42 @@ impl ProjectPanel {
                         }
                         .ml_1(),
                     )
+                    .when(is_conflicted, |item| {
+                        item.end_slot(
+                            div()
+                                .id("conflict-indicator")
+                                .child(
+                                    Icon::new(IconName::ExclamationTriangle)
+                                        .size(IconSize::Small)
+                                        .color(Color::Conflict),
+                                )
+                                .tooltip(|cx| Tooltip::text("Unresolved merge conflicts", cx)),
+                        )
+                    })
                     .on_click(cx.listener(move |this, event: &gpui::ClickEvent, cx| {
                         if event.down.button == MouseButton::Right {
                             return;
                         }
                         if !show_editor {
-                            if kind.is_dir() {
+                            if event.down.modifiers.shift {
+                                this.mark_range_to(selection, cx);
+                            } else if event.down.modifiers.command {
+                                this.toggle_marked(selection, cx);
+                            } else if kind.is_dir() {
+                                this.marked_entries.clear();
                                 this.toggle_expanded(entry_id, cx);
                             } else {
-                                if event.down.modifiers.command {
+                                this.marked_entries.clear();
+                                if event.down.modifiers.alt {
                                     this.split_entry(entry_id, cx);
                                 } else {
-                                    this.open_entry(entry_id, event.up.click_count > 1, cx);
+                                    let click_count = event.up.click_count;
+                                    this.open_entry(
+                                        entry_id,
+                                        click_count > 1,
+                                        click_count == 1,
+                                        cx,
+                                    );
                                 }
                             }
                         }
@@ -1579,12 +1816,17 @@ impl Render for DraggedProjectEntryView {
     fn render(&mut self, cx: &mut ViewContext<Self>) -> impl Element {
         let settings = ProjectPanelSettings::get_global(cx);
         let ui_font = ThemeSettings::get_global(cx).ui_font.family.clone();
+        let dragged_entries_count = if self.selections.contains(&self.selection) {
+            self.selections.len()
+        } else {
+            1
+        };
         h_flex()
             .font(ui_font)
             .bg(cx.theme().colors().background)
             .w(self.width)
             .child(
-                ListItem::new(self.entry_id.to_proto() as usize)
+                ListItem::new(self.selection.entry_id.to_proto() as usize)
                     .indent_level(self.details.depth)
                     .indent_step_size(px(settings.indent_size))
                     .child(if let Some(icon) = &self.details.icon {
@@ -1592,7 +1834,11 @@ impl Render for DraggedProjectEntryView {
                     } else {
                         div()
                     })
-                    .child(Label::new(self.details.filename.clone())),
+                    .child(if dragged_entries_count > 1 {
+                        Label::new(format!("{dragged_entries_count} entries"))
+                    } else {
+                        Label::new(self.details.filename.clone())
+                    }),
             )
     }
 }
@@ -1671,21 +1917,12 @@ impl FocusableView for ProjectPanel {
 
 impl ClipboardEntry {
     fn is_cut(&self) -> bool {
-        matches!(self, Self::Cut { .. })
+        matches!(self, Self::Cut(_))
     }
 
-    fn entry_id(&self) -> ProjectEntryId {
+    fn items(&self) -> impl Iterator<Item = &SelectedEntry> {
         match self {
-            ClipboardEntry::Copied { entry_id, .. } | ClipboardEntry::Cut { entry_id, .. } => {
-                *entry_id
-            }
-        }
-    }
-
-    fn worktree_id(&self) -> WorktreeId {
-        match self {
-            ClipboardEntry::Copied { worktree_id, .. }
-            | ClipboardEntry::Cut { worktree_id, .. } => *worktree_id,
+            ClipboardEntry::Copied(entries) | ClipboardEntry::Cut(entries) => entries.iter(),
         }
     }
 }
@@ -2474,6 +2711,179 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_multiple_marked_entries(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor().clone());
+        fs.insert_tree(
+            "/root",
+            json!({
+                "a": {
+                    "one.txt": "",
+                    "three.txt": "",
+                    "two.txt": "",
+                },
+                "b": {}
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs.clone(), ["/root".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace
+            .update(cx, |workspace, cx| ProjectPanel::new(workspace, cx))
+            .unwrap();
+
+        toggle_expand_dir(&panel, "root/a", cx);
+        select_path(&panel, "root/a/two.txt", cx);
+        let two = panel.update(cx, |panel, _| panel.selection.unwrap());
+        select_path(&panel, "root/a/three.txt", cx);
+        let three = panel.update(cx, |panel, _| panel.selection.unwrap());
+        select_path(&panel, "root/a/one.txt", cx);
+
+        panel.update(cx, |panel, cx| {
+            panel.mark_range_to(two, cx);
+            panel.toggle_marked(three, cx);
+        });
+        assert_eq!(
+            visible_entries_as_strings(&panel, 0..50, cx),
+            &[
+                //
+                "v root",
+                "    v a",
+                "          one.txt  <== marked",
+                "          three.txt  <== selected",
+                "          two.txt  <== marked",
+                "    > b",
+            ]
+        );
+
+        panel.update(cx, |panel, cx| panel.cut(&Default::default(), cx));
+        select_path(&panel, "root/b", cx);
+        panel.update(cx, |panel, cx| panel.paste(&Default::default(), cx));
+        cx.executor().run_until_parked();
+
+        assert_eq!(find_project_entry(&panel, "root/a/one.txt", cx), None);
+        assert_eq!(find_project_entry(&panel, "root/a/two.txt", cx), None);
+        assert_ne!(find_project_entry(&panel, "root/a/three.txt", cx), None);
+        assert_ne!(find_project_entry(&panel, "root/b/one.txt", cx), None);
+        assert_ne!(find_project_entry(&panel, "root/b/two.txt", cx), None);
+
+        toggle_expand_dir(&panel, "root/b", cx);
+        select_path(&panel, "root/b/one.txt", cx);
+        let one = panel.update(cx, |panel, _| panel.selection.unwrap());
+        select_path(&panel, "root/b/two.txt", cx);
+        panel.update(cx, |panel, cx| panel.toggle_marked(one, cx));
+        submit_deletion(&panel, cx);
+
+        assert_eq!(find_project_entry(&panel, "root/b/one.txt", cx), None);
+        assert_eq!(find_project_entry(&panel, "root/b/two.txt", cx), None);
+        assert_ne!(find_project_entry(&panel, "root/a/three.txt", cx), None);
+    }
+
+    #[gpui::test]
+    async fn test_drag_marked_entries(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor().clone());
+        fs.insert_tree(
+            "/root",
+            json!({
+                "a": {
+                    "inner": {
+                        "one.txt": "",
+                    },
+                    "two.txt": "",
+                },
+                "b": {}
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs.clone(), ["/root".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace
+            .update(cx, |workspace, cx| ProjectPanel::new(workspace, cx))
+            .unwrap();
+
+        toggle_expand_dir(&panel, "root/a", cx);
+        select_path(&panel, "root/a/inner", cx);
+        let inner = panel.update(cx, |panel, _| panel.selection.unwrap());
+        select_path(&panel, "root/a/two.txt", cx);
+        let two = panel.update(cx, |panel, _| panel.selection.unwrap());
+        let b = find_project_entry(&panel, "root/b", cx).unwrap();
+
+        // Dragging one of the marked entries moves all of them, skipping nested ones.
+        panel.update(cx, |panel, cx| {
+            panel.toggle_marked(inner, cx);
+            let selections = DraggedSelection {
+                active_selection: two,
+                marked_selections: Arc::new(panel.marked_entries.clone()),
+            };
+            panel.drag_onto(&selections, b, false, cx);
+        });
+        cx.executor().run_until_parked();
+
+        assert_eq!(find_project_entry(&panel, "root/a/inner", cx), None);
+        assert_eq!(find_project_entry(&panel, "root/a/two.txt", cx), None);
+        assert_ne!(find_project_entry(&panel, "root/b/inner/one.txt", cx), None);
+        assert_ne!(find_project_entry(&panel, "root/b/two.txt", cx), None);
+
+        // A directory can't be dropped into itself.
+        let b_inner = find_project_entry(&panel, "root/b/inner", cx).unwrap();
+        panel.update(cx, |panel, cx| {
+            let worktree_id = panel.selection.unwrap().worktree_id;
+            let selections = DraggedSelection {
+                active_selection: SelectedEntry {
+                    worktree_id,
+                    entry_id: b,
+                },
+                marked_selections: Default::default(),
+            };
+            panel.drag_onto(&selections, b_inner, false, cx);
+        });
+        cx.executor().run_until_parked();
+        assert_ne!(find_project_entry(&panel, "root/b/inner/one.txt", cx), None);
+    }
+
+    #[gpui::test]
+    async fn test_drag_entries_between_worktrees(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor().clone());
+        fs.insert_tree("/root1", json!({ "one.txt": "" })).await;
+        fs.insert_tree("/root2", json!({ "two.txt": "" })).await;
+
+        let project = Project::test(fs.clone(), ["/root1".as_ref(), "/root2".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace
+            .update(cx, |workspace, cx| ProjectPanel::new(workspace, cx))
+            .unwrap();
+
+        select_path(&panel, "root1/one.txt", cx);
+        let one = panel.update(cx, |panel, _| panel.selection.unwrap());
+        let root2 = find_project_entry(&panel, "root2", cx).unwrap();
+
+        // Moving an entry to another worktree is refused with an error.
+        panel.update(cx, |panel, cx| {
+            let selections = DraggedSelection {
+                active_selection: one,
+                marked_selections: Default::default(),
+            };
+            panel.drag_onto(&selections, root2, false, cx);
+        });
+        cx.executor().run_until_parked();
+
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(0);
+        assert_ne!(find_project_entry(&panel, "root1/one.txt", cx), None);
+        assert_eq!(find_project_entry(&panel, "root2/one.txt", cx), None);
+    }
+
     #[gpui::test]
     async fn test_copy_paste_directory(cx: &mut gpui::TestAppContext) {
         init_test(cx);
@@ -3467,7 +3877,7 @@ mod tests {
                 let worktree = worktree.read(cx);
                 if let Ok(relative_path) = path.strip_prefix(worktree.root_name()) {
                     let entry_id = worktree.entry_for_path(relative_path).unwrap().id;
-                    panel.selection = Some(crate::Selection {
+                    panel.selection = Some(SelectedEntry {
                         worktree_id: worktree.id(),
                         entry_id,
                     });
@@ -3537,6 +3947,8 @@ mod tests {
                 };
                 let selected = if details.is_selected {
                     "  <== selected"
+                } else if details.is_marked {
+                    "  <== marked"
                 } else {
                     ""
                 };
@@ -3552,6 +3964,7 @@ mod tests {
             let settings_store = SettingsStore::test(cx);
             cx.set_global(settings_store);
             init_settings(cx);
+            file_history::init_settings(cx);
             theme::init(theme::LoadThemes::JustBase, cx);
             language::init(cx);
             editor::init_settings(cx);
@@ -3573,6 +3986,7 @@ mod tests {
             let app_state = AppState::test(cx);
             theme::init(theme::LoadThemes::JustBase, cx);
             init_settings(cx);
+            file_history::init_settings(cx);
             language::init(cx);
             editor::init(cx);
             crate::init((), cx);
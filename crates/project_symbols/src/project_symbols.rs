@@ -131,7 +131,7 @@ impl PickerDelegate for ProjectSymbolsDelegate {
                     let editor = if secondary {
                         workspace.split_project_item::<Editor>(buffer, cx)
                     } else {
-                        workspace.open_project_item::<Editor>(buffer, cx)
+                        workspace.open_project_item::<Editor>(buffer, false, cx)
                     };
 
                     editor.update(cx, |editor, cx| {
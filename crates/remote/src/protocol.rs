@@ -0,0 +1,180 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use anyhow::{anyhow, Result};
+use async_tungstenite::tungstenite::Message as WebSocketMessage;
+use futures::{
+    channel::mpsc, io::BufReader, AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _,
+    Sink, SinkExt as _, Stream, StreamExt as _,
+};
+use rpc::Connection;
+
+const BINARY: u8 = 0;
+const PING: u8 = 1;
+const PONG: u8 = 2;
+const CLOSE: u8 = 3;
+
+/// Carries the messages of a connection over a byte stream, such as the standard
+/// input and output of a process. Each message is preceded by its kind and length.
+pub fn stream_connection<R, W>(reader: R, writer: W) -> Connection
+where
+    R: 'static + Send + Unpin + AsyncRead,
+    W: 'static + Send + Unpin + AsyncWrite,
+{
+    let incoming = futures::stream::unfold(Some(BufReader::new(reader)), |reader| async move {
+        let mut reader = reader?;
+        match read_message(&mut reader).await {
+            Ok(Some(message)) => Some((Ok(message), Some(reader))),
+            Ok(None) => None,
+            // The stream can't be read past a malformed message.
+            Err(error) => Some((Err(error), None)),
+        }
+    });
+    let outgoing = futures::sink::unfold(writer, |mut writer, message| async move {
+        write_message(&mut writer, &message).await?;
+        anyhow::Ok(writer)
+    });
+    Connection::new(Duplex::new(outgoing, incoming))
+}
+
+/// Creates the two ends of a connection within this process.
+pub fn connection_pair() -> (Connection, Connection) {
+    let (a_tx, a_rx) = mpsc::unbounded();
+    let (b_tx, b_rx) = mpsc::unbounded();
+    (
+        Connection::new(Duplex::new(
+            a_tx.sink_map_err(|error| anyhow!(error)),
+            b_rx.map(Ok),
+        )),
+        Connection::new(Duplex::new(
+            b_tx.sink_map_err(|error| anyhow!(error)),
+            a_rx.map(Ok),
+        )),
+    )
+}
+
+async fn read_message(reader: &mut (impl Unpin + AsyncRead)) -> Result<Option<WebSocketMessage>> {
+    let mut header = [0; 5];
+    match reader.read_exact(&mut header).await {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.into()),
+    }
+    let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload).await?;
+    match header[0] {
+        BINARY => Ok(Some(WebSocketMessage::Binary(payload))),
+        PING => Ok(Some(WebSocketMessage::Ping(payload))),
+        PONG => Ok(Some(WebSocketMessage::Pong(payload))),
+        CLOSE => Ok(None),
+        kind => Err(anyhow!("unknown message kind {kind}")),
+    }
+}
+
+async fn write_message(
+    writer: &mut (impl Unpin + AsyncWrite),
+    message: &WebSocketMessage,
+) -> Result<()> {
+    let (kind, payload) = match message {
+        WebSocketMessage::Binary(payload) => (BINARY, payload.as_slice()),
+        WebSocketMessage::Ping(payload) => (PING, payload.as_slice()),
+        WebSocketMessage::Pong(payload) => (PONG, payload.as_slice()),
+        WebSocketMessage::Close(_) => (CLOSE, [].as_slice()),
+        message => return Err(anyhow!("unsupported message {message:?}")),
+    };
+    let len = u32::try_from(payload.len())?.to_le_bytes();
+    writer
+        .write_all(&[kind, len[0], len[1], len[2], len[3]])
+        .await?;
+    writer.write_all(payload).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Joins the halves of a connection into the single value that [`Connection::new`]
+/// splits again.
+struct Duplex {
+    outgoing: Pin<Box<dyn Send + Sink<WebSocketMessage, Error = anyhow::Error>>>,
+    incoming: Pin<Box<dyn Send + Stream<Item = Result<WebSocketMessage>>>>,
+}
+
+impl Duplex {
+    fn new(
+        outgoing: impl 'static + Send + Sink<WebSocketMessage, Error = anyhow::Error>,
+        incoming: impl 'static + Send + Stream<Item = Result<WebSocketMessage>>,
+    ) -> Self {
+        Self {
+            outgoing: Box::pin(outgoing),
+            incoming: Box::pin(incoming),
+        }
+    }
+}
+
+impl Sink<WebSocketMessage> for Duplex {
+    type Error = anyhow::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<()>> {
+        self.outgoing.as_mut().poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, message: WebSocketMessage) -> Result<()> {
+        self.outgoing.as_mut().start_send(message)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<()>> {
+        self.outgoing.as_mut().poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<()>> {
+        self.outgoing.as_mut().poll_close(cx)
+    }
+}
+
+impl Stream for Duplex {
+    type Item = Result<WebSocketMessage>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.incoming.as_mut().poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::io::Cursor;
+
+    #[test]
+    fn test_message_framing() {
+        smol::block_on(async {
+            let messages = [
+                WebSocketMessage::Binary(b"envelope".to_vec()),
+                WebSocketMessage::Ping(Vec::new()),
+                WebSocketMessage::Pong(Vec::new()),
+                WebSocketMessage::Binary(Vec::new()),
+            ];
+            let mut bytes = Cursor::new(Vec::new());
+            for message in &messages {
+                write_message(&mut bytes, message).await.unwrap();
+            }
+            write_message(&mut bytes, &WebSocketMessage::Close(None))
+                .await
+                .unwrap();
+            write_message(&mut bytes, &WebSocketMessage::Binary(b"ignored".to_vec()))
+                .await
+                .unwrap();
+
+            let mut reader = Cursor::new(bytes.into_inner());
+            for message in messages {
+                assert_eq!(read_message(&mut reader).await.unwrap(), Some(message));
+            }
+            assert_eq!(read_message(&mut reader).await.unwrap(), None);
+
+            let mut truncated = Cursor::new(vec![BINARY, 4, 0, 0, 0, 1]);
+            assert!(read_message(&mut truncated).await.is_err());
+        });
+    }
+}
@@ -0,0 +1,14 @@
+//! Connects to projects hosted by a headless project server on another machine.
+//!
+//! The server speaks the same protocol as the collaboration server, carried over the
+//! standard input and output of the process started by SSH.
+
+mod protocol;
+mod ssh;
+
+pub use protocol::{connection_pair, stream_connection};
+pub use ssh::{SshConnectionOptions, SshSession, REMOTE_SERVER_BINARY};
+
+/// The id of the project served by a headless project server, which serves a single
+/// project to a single client.
+pub const HEADLESS_PROJECT_ID: u64 = 1;
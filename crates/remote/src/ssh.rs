@@ -0,0 +1,193 @@
+use std::process::Stdio;
+
+use anyhow::{anyhow, Context as _, Result};
+use futures::{io::BufReader, AsyncBufReadExt as _, StreamExt as _};
+use gpui::{BackgroundExecutor, Task};
+use rpc::Connection;
+use smol::process;
+
+use crate::stream_connection;
+
+/// The headless project server started on the remote host, which must be on its `PATH`.
+pub const REMOTE_SERVER_BINARY: &str = "zed-remote-server";
+
+/// How to reach a remote host over SSH, as in `ssh://user@host:22/path`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SshConnectionOptions {
+    pub host: String,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+}
+
+impl SshConnectionOptions {
+    /// Parses an `ssh://[user@]host[:port][/path]` URL into the options to reach the
+    /// host and the path on it, which is the home directory when the URL doesn't have one.
+    pub fn parse_url(url: &str) -> Result<(Self, String)> {
+        let rest = url
+            .strip_prefix("ssh://")
+            .with_context(|| format!("{url:?} isn't an ssh:// URL"))?;
+        let (authority, path) = match rest.find('/') {
+            Some(ix) => rest.split_at(ix),
+            None => (rest, ""),
+        };
+        let (username, host_and_port) = match authority.rsplit_once('@') {
+            Some((username, host_and_port)) => (Some(username.to_string()), host_and_port),
+            None => (None, authority),
+        };
+        let (host, port) = match host_and_port.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port
+                    .parse()
+                    .with_context(|| format!("invalid port in {url:?}"))?;
+                (host, Some(port))
+            }
+            None => (host_and_port, None),
+        };
+        if host.is_empty() {
+            return Err(anyhow!("missing host in {url:?}"));
+        }
+
+        // `ssh://host/~/project` refers to a path relative to the home directory, in
+        // which the server starts.
+        let path = path
+            .strip_prefix("/~")
+            .map_or(path, |path| path.strip_prefix('/').unwrap_or(path));
+        let path = if path.is_empty() { "." } else { path };
+        let options = Self {
+            host: host.to_string(),
+            port,
+            username,
+        };
+        Ok((options, path.to_string()))
+    }
+
+    /// The host as passed to `ssh`, along with the user to log in as.
+    pub fn destination(&self) -> String {
+        match &self.username {
+            Some(username) => format!("{username}@{}", self.host),
+            None => self.host.clone(),
+        }
+    }
+
+    fn ssh_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(port) = self.port {
+            args.push("-p".to_string());
+            args.push(port.to_string());
+        }
+        args.push(self.destination());
+        args
+    }
+}
+
+/// A headless project server started over SSH, which lives as long as the session.
+pub struct SshSession {
+    options: SshConnectionOptions,
+    _process: process::Child,
+    _log_stderr: Task<()>,
+}
+
+impl SshSession {
+    /// Starts a headless project server for the given paths on a remote host, returning
+    /// the connection to it over the standard input and output of `ssh`.
+    pub fn start(
+        options: SshConnectionOptions,
+        paths: &[String],
+        executor: &BackgroundExecutor,
+    ) -> Result<(Self, Connection)> {
+        let remote_command = [REMOTE_SERVER_BINARY, "run"]
+            .into_iter()
+            .chain(paths.iter().map(String::as_str))
+            .map(shell_quote)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut process = process::Command::new("ssh")
+            // There's no terminal to prompt for passwords, and the output carries
+            // the messages of the protocol.
+            .args(["-T", "-o", "BatchMode=yes"])
+            .args(options.ssh_args())
+            .arg(remote_command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context("failed to start ssh")?;
+
+        let stdin = process.stdin.take().unwrap();
+        let stdout = process.stdout.take().unwrap();
+        let stderr = process.stderr.take().unwrap();
+        let host = options.host.clone();
+        let log_stderr = executor.spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Some(Ok(line)) = lines.next().await {
+                log::info!("{host}: {line}");
+            }
+        });
+
+        let session = Self {
+            options,
+            _process: process,
+            _log_stderr: log_stderr,
+        };
+        Ok((session, stream_connection(stdout, stdin)))
+    }
+
+    pub fn options(&self) -> &SshConnectionOptions {
+        &self.options
+    }
+}
+
+/// Quotes an argument of a command run by the remote shell.
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@%+,".contains(c))
+    {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url() {
+        let (options, path) =
+            SshConnectionOptions::parse_url("ssh://alice@build-box:2222/home/alice/code").unwrap();
+        assert_eq!(
+            options,
+            SshConnectionOptions {
+                host: "build-box".into(),
+                port: Some(2222),
+                username: Some("alice".into()),
+            }
+        );
+        assert_eq!(path, "/home/alice/code");
+        assert_eq!(options.destination(), "alice@build-box");
+
+        let (options, path) = SshConnectionOptions::parse_url("ssh://build-box/~/code").unwrap();
+        assert_eq!(options.destination(), "build-box");
+        assert_eq!(options.port, None);
+        assert_eq!(path, "code");
+
+        let (_, path) = SshConnectionOptions::parse_url("ssh://build-box").unwrap();
+        assert_eq!(path, ".");
+
+        assert!(SshConnectionOptions::parse_url("ssh://build-box:ssh/code").is_err());
+        assert!(SshConnectionOptions::parse_url("ssh:///code").is_err());
+        assert!(SshConnectionOptions::parse_url("file:///code").is_err());
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("src/main.rs"), "src/main.rs");
+        assert_eq!(shell_quote("my project"), "'my project'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+}
@@ -0,0 +1,768 @@
+use std::{any::TypeId, path::PathBuf, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use client::{Client, UserStore};
+use collections::HashMap;
+use fs::Fs;
+use futures::StreamExt as _;
+use gpui::{AppContext, Context as _, EventEmitter, Model, ModelContext, Task};
+use language::LanguageRegistry;
+use node_runtime::NodeRuntime;
+use project::Project;
+use remote::{connection_pair, HEADLESS_PROJECT_ID};
+use rpc::{
+    proto::{self, AnyTypedEnvelope, EnvelopedMessage, RequestMessage},
+    Connection, ConnectionId, ErrorExt as _, Peer, TypedEnvelope,
+};
+use util::{http::ZedHttpClient, ResultExt as _};
+
+use crate::terminals::TerminalShell;
+
+/// The replica id of the client, the host of the project being replica 0.
+const CLIENT_REPLICA_ID: u32 = 1;
+/// How often a shell whose PTY was closed is checked for having exited.
+const SHELL_EXIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+type MessageHandler = fn(
+    &mut HeadlessProject,
+    Box<dyn AnyTypedEnvelope>,
+    &mut ModelContext<HeadlessProject>,
+) -> Result<()>;
+
+/// A local project shared with a single client, standing in for the collaboration
+/// server between them.
+///
+/// The project is hosted by a client of its own, connected to this server within the
+/// process, so that it's shared exactly as it would be with collaborators. Messages
+/// are relayed between the project and the client, and the requests that the
+/// collaboration server answers itself are answered here. So are those of the
+/// client's terminals, whose shells run on this machine.
+pub struct HeadlessProject {
+    project: Model<Project>,
+    peer: Arc<Peer>,
+    project_connection_id: ConnectionId,
+    client_connection_id: ConnectionId,
+    message_handlers: HashMap<TypeId, MessageHandler>,
+    joined: bool,
+    shells: HashMap<u64, TerminalShell>,
+    _project_io: Task<Result<()>>,
+    _handle_messages: Task<()>,
+    _wait_for_disconnection: Task<()>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The client left the project or its connection was lost.
+    Disconnected,
+}
+
+impl EventEmitter<Event> for HeadlessProject {}
+
+macro_rules! message_handlers {
+    ($handlers:ident, $handler:ident, [$($message:ty),* $(,)?]) => {
+        $(
+            $handlers.insert(
+                TypeId::of::<$message>(),
+                HeadlessProject::$handler::<$message> as MessageHandler,
+            );
+        )*
+    };
+}
+
+impl HeadlessProject {
+    /// Opens a project with the given directories and serves it to the client at the
+    /// other end of the connection.
+    pub fn serve(
+        paths: Vec<PathBuf>,
+        connection: Connection,
+        http: Arc<ZedHttpClient>,
+        languages: Arc<LanguageRegistry>,
+        node_runtime: Arc<dyn NodeRuntime>,
+        fs: Arc<dyn Fs>,
+        cx: &mut AppContext,
+    ) -> Task<Result<Model<Self>>> {
+        let client = Client::new(http, cx);
+        Project::init(&client, cx);
+        let user_store = cx.new_model(|cx| UserStore::new(client.clone(), cx));
+        let project = Project::local(client.clone(), node_runtime, user_store, languages, fs, cx);
+
+        cx.spawn(|mut cx| async move {
+            for path in paths {
+                project
+                    .update(&mut cx, |project, cx| {
+                        project.find_or_create_local_worktree(&path, true, cx)
+                    })?
+                    .await?;
+            }
+
+            let executor = cx.background_executor().clone();
+            let peer = Peer::new(0);
+            let (project_connection, connection_to_project) = connection_pair();
+            let (project_connection_id, project_io, project_messages) =
+                peer.add_connection(connection_to_project, {
+                    let executor = executor.clone();
+                    move |duration| executor.timer(duration)
+                });
+            let (client_connection_id, client_io, client_messages) =
+                peer.add_connection(connection, {
+                    let executor = executor.clone();
+                    move |duration| executor.timer(duration)
+                });
+            let project_io = executor.spawn(project_io);
+            let client_io = executor.spawn(client_io);
+
+            // Both clients wait for the server to say hello before sending anything.
+            peer.send(
+                project_connection_id,
+                proto::Hello {
+                    peer_id: Some(project_connection_id.into()),
+                },
+            )?;
+            client.connect_over(project_connection, &cx).await?;
+            peer.send(
+                client_connection_id,
+                proto::Hello {
+                    peer_id: Some(client_connection_id.into()),
+                },
+            )?;
+
+            cx.new_model(|cx| {
+                let mut messages = futures::stream::select(project_messages, client_messages);
+                let handle_messages = cx.spawn(|this, mut cx| async move {
+                    while let Some(message) = messages.next().await {
+                        if this
+                            .update(&mut cx, |this, cx| this.handle_message(message, cx))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                });
+                let wait_for_disconnection = cx.spawn(|this, mut cx| async move {
+                    if let Err(error) = client_io.await {
+                        log::info!("connection to the client closed: {error:?}");
+                    }
+                    this.update(&mut cx, |_, cx| cx.emit(Event::Disconnected))
+                        .ok();
+                });
+
+                Self {
+                    project,
+                    peer,
+                    project_connection_id,
+                    client_connection_id,
+                    message_handlers: Self::message_handlers(),
+                    joined: false,
+                    shells: HashMap::default(),
+                    _project_io: project_io,
+                    _handle_messages: handle_messages,
+                    _wait_for_disconnection: wait_for_disconnection,
+                }
+            })
+        })
+    }
+
+    pub fn project(&self) -> &Model<Project> {
+        &self.project
+    }
+
+    fn message_handlers() -> HashMap<TypeId, MessageHandler> {
+        let mut handlers = HashMap::default();
+        handlers.insert(
+            TypeId::of::<proto::JoinProject>(),
+            Self::handle_join_project as MessageHandler,
+        );
+        handlers.insert(
+            TypeId::of::<proto::LeaveProject>(),
+            Self::handle_leave_project as MessageHandler,
+        );
+        handlers.insert(
+            TypeId::of::<proto::OpenTerminal>(),
+            Self::handle_open_terminal as MessageHandler,
+        );
+        handlers.insert(
+            TypeId::of::<proto::TerminalInput>(),
+            Self::handle_terminal_input as MessageHandler,
+        );
+        handlers.insert(
+            TypeId::of::<proto::ResizeTerminal>(),
+            Self::handle_resize_terminal as MessageHandler,
+        );
+        handlers.insert(
+            TypeId::of::<proto::CloseTerminal>(),
+            Self::handle_close_terminal as MessageHandler,
+        );
+        message_handlers!(
+            handlers,
+            forward_request,
+            [
+                proto::ApplyCodeAction,
+                proto::ApplyCompletionAdditionalEdits,
+                proto::CopyProjectEntry,
+                proto::CreateProjectEntry,
+                proto::DeleteProjectEntry,
+                proto::ExpandProjectEntry,
+                proto::FormatBuffers,
+                proto::GetCodeActions,
+                proto::GetCompletions,
+                proto::GetDefinition,
+                proto::GetDocumentHighlights,
+                proto::GetHover,
+                proto::GetProjectSymbols,
+                proto::GetReferences,
+                proto::GetTypeDefinition,
+                proto::InlayHints,
+                proto::LoadFileBytes,
+                proto::OnTypeFormatting,
+                proto::OpenBufferById,
+                proto::OpenBufferByPath,
+                proto::OpenBufferForSymbol,
+                proto::PerformRename,
+                proto::PrepareRename,
+                proto::ReloadBuffers,
+                proto::RenameProjectEntry,
+                proto::ResolveCompletionDocumentation,
+                proto::ResolveInlayHint,
+                proto::SaveBuffer,
+                proto::SearchProject,
+                proto::SynchronizeBuffers,
+                proto::UpdateBuffer,
+            ]
+        );
+        message_handlers!(
+            handlers,
+            forward_update,
+            [proto::UpdateProject, proto::UpdateWorktree]
+        );
+        message_handlers!(
+            handlers,
+            forward_message,
+            [
+                proto::BufferReloaded,
+                proto::BufferSaved,
+                proto::CreateBufferForPeer,
+                proto::RefreshInlayHints,
+                proto::StartLanguageServer,
+                proto::UnshareProject,
+                proto::UpdateBufferFile,
+                proto::UpdateDiagnosticSummary,
+                proto::UpdateDiffBase,
+                proto::UpdateLanguageServer,
+                proto::UpdateWorktreeSettings,
+            ]
+        );
+        handlers
+    }
+
+    fn handle_message(&mut self, message: Box<dyn AnyTypedEnvelope>, cx: &mut ModelContext<Self>) {
+        let type_name = message.payload_type_name();
+        let result = match self
+            .message_handlers
+            .get(&message.payload_type_id())
+            .copied()
+        {
+            Some(handler) => handler(self, message, cx),
+            None => {
+                log::warn!("unhandled message {type_name}");
+                self.peer.respond_with_unhandled_message(message)
+            }
+        };
+        if let Err(error) = result {
+            log::error!("error handling message {type_name}: {error:?}");
+        }
+    }
+
+    fn handle_join_project(
+        &mut self,
+        envelope: Box<dyn AnyTypedEnvelope>,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let envelope = downcast::<proto::JoinProject>(envelope)?;
+        let receipt = envelope.receipt();
+        if envelope.sender_id != self.client_connection_id
+            || envelope.payload.project_id != HEADLESS_PROJECT_ID
+            || self.joined
+        {
+            return self
+                .peer
+                .respond_with_error(receipt, anyhow!("no such project").to_proto());
+        }
+        self.joined = true;
+
+        self.project
+            .update(cx, |project, cx| project.shared(HEADLESS_PROJECT_ID, cx))?;
+        // The project only sends its buffers to collaborators it knows about.
+        self.peer.send(
+            self.project_connection_id,
+            proto::AddProjectCollaborator {
+                project_id: HEADLESS_PROJECT_ID,
+                collaborator: Some(proto::Collaborator {
+                    peer_id: Some(self.client_connection_id.into()),
+                    replica_id: CLIENT_REPLICA_ID,
+                    user_id: 0,
+                }),
+            },
+        )?;
+        let worktrees = self.project.read(cx).worktree_metadata_protos(cx);
+        self.peer.respond(
+            receipt,
+            proto::JoinProjectResponse {
+                replica_id: CLIENT_REPLICA_ID,
+                worktrees,
+                collaborators: Vec::new(),
+                // The project announces its language servers once it's shared.
+                language_servers: Vec::new(),
+            },
+        )
+    }
+
+    fn handle_leave_project(
+        &mut self,
+        _: Box<dyn AnyTypedEnvelope>,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        cx.emit(Event::Disconnected);
+        Ok(())
+    }
+
+    fn handle_open_terminal(
+        &mut self,
+        envelope: Box<dyn AnyTypedEnvelope>,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let envelope = downcast::<proto::OpenTerminal>(envelope)?;
+        let receipt = envelope.receipt();
+        let terminal_id = envelope.payload.terminal_id;
+        if envelope.sender_id != self.client_connection_id || self.shells.contains_key(&terminal_id)
+        {
+            return self
+                .peer
+                .respond_with_error(receipt, anyhow!("can't open terminal").to_proto());
+        }
+        let (shell, mut output) = match TerminalShell::spawn(envelope.payload) {
+            Ok(shell) => shell,
+            Err(error) => return self.peer.respond_with_error(receipt, error.to_proto()),
+        };
+        self.shells.insert(terminal_id, shell);
+        self.peer.respond(receipt, proto::Ack {})?;
+
+        let peer = self.peer.clone();
+        let client_connection_id = self.client_connection_id;
+        cx.spawn(|this, mut cx| async move {
+            while let Some(output) = output.next().await {
+                peer.send(
+                    client_connection_id,
+                    proto::TerminalOutput {
+                        project_id: HEADLESS_PROJECT_ID,
+                        terminal_id,
+                        output,
+                    },
+                )
+                .log_err();
+            }
+
+            // The PTY can close slightly before the shell exits. The client may
+            // also have closed the terminal, which hangs up the shell.
+            loop {
+                let exit_status = this.update(&mut cx, |this, _| {
+                    let exit_status = this.shells.get_mut(&terminal_id)?.exit_status();
+                    if exit_status.is_some() {
+                        this.shells.remove(&terminal_id);
+                    }
+                    Some(exit_status)
+                });
+                match exit_status {
+                    Ok(Some(Some(exit_code))) => {
+                        peer.send(
+                            client_connection_id,
+                            proto::TerminalExited {
+                                project_id: HEADLESS_PROJECT_ID,
+                                terminal_id,
+                                exit_code,
+                            },
+                        )
+                        .log_err();
+                        break;
+                    }
+                    Ok(Some(None)) => {
+                        cx.background_executor()
+                            .timer(SHELL_EXIT_POLL_INTERVAL)
+                            .await
+                    }
+                    Ok(None) | Err(_) => break,
+                }
+            }
+        })
+        .detach();
+        Ok(())
+    }
+
+    fn handle_terminal_input(
+        &mut self,
+        envelope: Box<dyn AnyTypedEnvelope>,
+        _: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let envelope = downcast::<proto::TerminalInput>(envelope)?;
+        if let Some(shell) = self.client_shell(envelope.sender_id, envelope.payload.terminal_id) {
+            shell.input(envelope.payload.input);
+        }
+        Ok(())
+    }
+
+    fn handle_resize_terminal(
+        &mut self,
+        envelope: Box<dyn AnyTypedEnvelope>,
+        _: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let envelope = downcast::<proto::ResizeTerminal>(envelope)?;
+        if let Some(shell) = self.client_shell(envelope.sender_id, envelope.payload.terminal_id) {
+            shell.resize(&envelope.payload);
+        }
+        Ok(())
+    }
+
+    fn handle_close_terminal(
+        &mut self,
+        envelope: Box<dyn AnyTypedEnvelope>,
+        _: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let envelope = downcast::<proto::CloseTerminal>(envelope)?;
+        if envelope.sender_id == self.client_connection_id {
+            self.shells.remove(&envelope.payload.terminal_id);
+        }
+        Ok(())
+    }
+
+    fn client_shell(
+        &mut self,
+        sender_id: ConnectionId,
+        terminal_id: u64,
+    ) -> Option<&mut TerminalShell> {
+        if sender_id == self.client_connection_id {
+            self.shells.get_mut(&terminal_id)
+        } else {
+            None
+        }
+    }
+
+    fn forward_request<T: RequestMessage>(
+        &mut self,
+        envelope: Box<dyn AnyTypedEnvelope>,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let envelope = downcast::<T>(envelope)?;
+        let receipt = envelope.receipt();
+        let receiver_id = self.receiver_of(envelope.sender_id);
+        let response = self
+            .peer
+            .forward_request(envelope.sender_id, receiver_id, envelope.payload);
+        let peer = self.peer.clone();
+        cx.background_executor()
+            .spawn(async move {
+                match response.await {
+                    Ok(response) => peer.respond(receipt, response),
+                    Err(error) => peer.respond_with_error(receipt, error.to_proto()),
+                }
+                .log_err();
+            })
+            .detach();
+        Ok(())
+    }
+
+    /// Forwards an update of the project to the client, acknowledging it on its
+    /// behalf as the collaboration server does.
+    fn forward_update<T: RequestMessage<Response = proto::Ack>>(
+        &mut self,
+        envelope: Box<dyn AnyTypedEnvelope>,
+        _: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let envelope = downcast::<T>(envelope)?;
+        let receipt = envelope.receipt();
+        if envelope.sender_id != self.project_connection_id {
+            return self.peer.respond_with_error(
+                receipt,
+                anyhow!("only the host can update the project").to_proto(),
+            );
+        }
+        self.peer.forward_send(
+            envelope.sender_id,
+            self.client_connection_id,
+            envelope.payload,
+        )?;
+        self.peer.respond(receipt, proto::Ack {})
+    }
+
+    fn forward_message<T: EnvelopedMessage>(
+        &mut self,
+        envelope: Box<dyn AnyTypedEnvelope>,
+        _: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let envelope = downcast::<T>(envelope)?;
+        let receiver_id = self.receiver_of(envelope.sender_id);
+        self.peer
+            .forward_send(envelope.sender_id, receiver_id, envelope.payload)
+    }
+
+    fn receiver_of(&self, sender_id: ConnectionId) -> ConnectionId {
+        if sender_id == self.project_connection_id {
+            self.client_connection_id
+        } else {
+            self.project_connection_id
+        }
+    }
+}
+
+fn downcast<T: EnvelopedMessage>(envelope: Box<dyn AnyTypedEnvelope>) -> Result<TypedEnvelope<T>> {
+    envelope
+        .into_any()
+        .downcast::<TypedEnvelope<T>>()
+        .map(|envelope| *envelope)
+        .map_err(|_| anyhow!("received message of the wrong type"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+    use node_runtime::FakeNodeRuntime;
+    use remote::stream_connection;
+    use serde_json::json;
+    use settings::{Settings as _, SettingsStore};
+    use std::path::Path;
+    use task::{SpawnInTerminal, TaskId};
+    use terminal::{terminal_settings::TerminalSettings, TaskStatus, Terminal};
+    use util::http::FakeHttpClient;
+
+    #[gpui::test]
+    async fn test_remote_project(cx: &mut TestAppContext, server_cx: &mut TestAppContext) {
+        init_test(cx);
+        init_test(server_cx);
+
+        let server_fs = FakeFs::new(server_cx.executor());
+        server_fs
+            .insert_tree(
+                "/code/project",
+                json!({
+                    "README.md": "# project",
+                    "src": {
+                        "lib.rs": "fn one() -> usize { 1 }",
+                    },
+                }),
+            )
+            .await;
+
+        let (project, headless) = connect(server_fs.clone(), "/code/project", cx, server_cx).await;
+
+        let worktree = project.read_with(cx, |project, cx| {
+            assert!(project.is_remote());
+            let worktrees = project.worktrees().collect::<Vec<_>>();
+            assert_eq!(worktrees.len(), 1);
+            assert_eq!(worktrees[0].read(cx).root_name(), "project");
+            worktrees[0].clone()
+        });
+        worktree.read_with(cx, |worktree, _| {
+            assert_eq!(
+                worktree
+                    .paths()
+                    .map(|path| path.to_string_lossy().to_string())
+                    .collect::<Vec<_>>(),
+                ["README.md", "src", "src/lib.rs"]
+            );
+        });
+
+        let worktree_id = worktree.read_with(cx, |worktree, _| worktree.id());
+        let buffer = project
+            .update(cx, |project, cx| {
+                project.open_buffer((worktree_id, Path::new("src/lib.rs")), cx)
+            })
+            .await
+            .unwrap();
+        buffer.update(cx, |buffer, cx| {
+            assert_eq!(buffer.text(), "fn one() -> usize { 1 }");
+            buffer.edit([(0..0, "pub ")], None, cx);
+        });
+        project
+            .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+            .await
+            .unwrap();
+        assert_eq!(
+            server_fs
+                .load(Path::new("/code/project/src/lib.rs"))
+                .await
+                .unwrap(),
+            "pub fn one() -> usize { 1 }"
+        );
+        buffer.read_with(cx, |buffer, _| assert!(!buffer.is_dirty()));
+
+        // Files created on the server show up in the client's project.
+        server_fs
+            .insert_file("/code/project/src/two.rs", "fn two() {}".into())
+            .await;
+        cx.executor().run_until_parked();
+        worktree.read_with(cx, |worktree, _| {
+            assert!(worktree.entry_for_path("src/two.rs").is_some());
+        });
+
+        // The client leaving disconnects it from the server.
+        let disconnected = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        server_cx.update(|cx| {
+            let disconnected = disconnected.clone();
+            cx.subscribe(&headless, move |_, event, _| {
+                assert_eq!(*event, Event::Disconnected);
+                disconnected.store(true, std::sync::atomic::Ordering::SeqCst);
+            })
+            .detach();
+        });
+        cx.update(|_| drop(project));
+        cx.executor().run_until_parked();
+        assert!(disconnected.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[gpui::test]
+    async fn test_remote_terminal(cx: &mut TestAppContext, server_cx: &mut TestAppContext) {
+        init_test(cx);
+        init_test(server_cx);
+        cx.update(TerminalSettings::register);
+        // The shell runs on a real PTY, read and written by threads.
+        cx.executor().allow_parking();
+        server_cx.executor().allow_parking();
+
+        let directory = tempfile::tempdir().unwrap();
+        std::fs::write(directory.path().join("remote-file.txt"), "").unwrap();
+        let root = directory.path().to_string_lossy().to_string();
+        // The project's files are beside the point, unlike the shell's directory.
+        let server_fs = FakeFs::new(server_cx.executor());
+        server_fs.insert_tree(&root, json!({})).await;
+        let (project, headless) = connect(server_fs, &root, cx, server_cx).await;
+
+        cx.add_empty_window();
+        let window = cx.windows()[0];
+        let terminal = project
+            .update(cx, |project, cx| {
+                let spawn_task = SpawnInTerminal {
+                    id: TaskId("remote".to_string()),
+                    label: "Remote".to_string(),
+                    command: "sh".to_string(),
+                    args: vec![
+                        "-c".to_string(),
+                        r#"ls; echo "$GREETING"; read line; echo "got $line"; exit 3"#.to_string(),
+                    ],
+                    cwd: Some(directory.path().to_path_buf()),
+                    env: [("GREETING".to_string(), "hello from the host".to_string())]
+                        .into_iter()
+                        .collect(),
+                    use_new_terminal: false,
+                    allow_concurrent_runs: false,
+                    problem_matchers: Vec::new(),
+                };
+                project.create_terminal(None, None, Some(spawn_task), window, cx)
+            })
+            .unwrap();
+
+        // The shell runs in the directory and with the environment of the task.
+        wait_until(cx, server_cx, |cx| {
+            let text = screen_text(&terminal, cx);
+            text.contains("remote-file.txt") && text.contains("hello from the host")
+        });
+        terminal.update(cx, |terminal, _| terminal.input("typed\r".to_string()));
+        wait_until(cx, server_cx, |cx| {
+            screen_text(&terminal, cx).contains("got typed")
+        });
+
+        // The task ends with its shell, which the server forgets.
+        wait_until(cx, server_cx, |cx| {
+            terminal.read_with(cx, |terminal, _| {
+                terminal.task().unwrap().status == TaskStatus::Completed
+            })
+        });
+        headless.read_with(server_cx, |headless, _| assert!(headless.shells.is_empty()));
+    }
+
+    /// Serves the directory from one context to a project opened in the other.
+    async fn connect(
+        server_fs: Arc<dyn Fs>,
+        path: &str,
+        cx: &mut TestAppContext,
+        server_cx: &mut TestAppContext,
+    ) -> (Model<Project>, Model<HeadlessProject>) {
+        // Pipes stand in for the standard input and output of `ssh`.
+        let (client_writer, server_reader) = async_pipe::pipe();
+        let (server_writer, client_reader) = async_pipe::pipe();
+        let headless = server_cx.update(|cx| {
+            HeadlessProject::serve(
+                vec![path.into()],
+                stream_connection(server_reader, server_writer),
+                FakeHttpClient::with_404_response(),
+                Arc::new(LanguageRegistry::test()),
+                FakeNodeRuntime::new(),
+                server_fs,
+                cx,
+            )
+        });
+        let project = cx
+            .spawn(|cx| {
+                Project::ssh(
+                    None,
+                    stream_connection(client_reader, client_writer),
+                    FakeHttpClient::with_404_response(),
+                    Arc::new(LanguageRegistry::test()),
+                    FakeFs::new(cx.background_executor().clone()),
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+        let headless = headless.await.unwrap();
+        cx.executor().run_until_parked();
+        (project, headless)
+    }
+
+    /// Lets both sides and the shell's IO threads run until the condition holds.
+    fn wait_until(
+        cx: &mut TestAppContext,
+        server_cx: &mut TestAppContext,
+        mut condition: impl FnMut(&mut TestAppContext) -> bool,
+    ) {
+        for _ in 0..100 {
+            server_cx.executor().advance_clock(SHELL_EXIT_POLL_INTERVAL);
+            server_cx.run_until_parked();
+            cx.run_until_parked();
+            if condition(cx) {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        panic!("timed out waiting for the terminal");
+    }
+
+    fn screen_text(terminal: &Model<Terminal>, cx: &mut TestAppContext) -> String {
+        terminal.update(cx, |terminal, cx| {
+            terminal.sync(cx);
+            let mut text = String::new();
+            let mut line = None;
+            for cell in &terminal.last_content().cells {
+                if line.is_some_and(|line| line != cell.point.line) {
+                    text.push('\n');
+                }
+                line = Some(cell.point.line);
+                text.push(cell.c);
+            }
+            text
+        })
+    }
+
+    fn init_test(cx: &mut TestAppContext) {
+        if std::env::var("RUST_LOG").is_ok() {
+            env_logger::try_init().ok();
+        }
+
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            release_channel::init("0.0.0", cx);
+            language::init(cx);
+            Project::init_settings(cx);
+        });
+    }
+}
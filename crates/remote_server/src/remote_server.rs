@@ -0,0 +1,10 @@
+//! Hosts a project on the machine it runs on for a client on another machine.
+//!
+//! The server is started over SSH by the client, which it talks to over its standard
+//! input and output with the messages that collaborators exchange through the
+//! collaboration server.
+
+mod headless_project;
+mod terminals;
+
+pub use headless_project::{Event, HeadlessProject};
@@ -0,0 +1,136 @@
+//! Shells started on this machine for the client's terminals, whose input and
+//! output are carried over the client's connection.
+
+use anyhow::{Context as _, Result};
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use rpc::proto;
+use std::{
+    env,
+    fs::File,
+    io::{self, Read, Write},
+    os::unix::io::AsRawFd,
+    path::PathBuf,
+    sync::mpsc,
+    thread,
+};
+use terminal::alacritty_terminal::{
+    event::{OnResize, WindowSize},
+    tty::{self, setup_env, ChildEvent, EventedPty, Pty},
+};
+
+const READ_BUFFER_LEN: usize = 64 * 1024;
+
+/// A shell running in a PTY for one of the client's terminals. Dropping it hangs
+/// up the shell.
+pub(crate) struct TerminalShell {
+    pty: Pty,
+    input_tx: mpsc::Sender<Vec<u8>>,
+}
+
+impl TerminalShell {
+    /// Starts the shell, or the program, that the client asked for, returning it
+    /// along with its output, which ends once the PTY is closed.
+    pub(crate) fn spawn(
+        request: proto::OpenTerminal,
+    ) -> Result<(Self, UnboundedReceiver<Vec<u8>>)> {
+        let program = match request.program {
+            Some(program) => Some((program, request.args)),
+            // The environment is set by running the user's shell through `env`.
+            None if !request.env.is_empty() => Some((
+                env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string()),
+                Vec::new(),
+            )),
+            None => None,
+        };
+        let shell = program.map(|(program, args)| {
+            if request.env.is_empty() {
+                tty::Shell::new(program, args)
+            } else {
+                let args = request
+                    .env
+                    .into_iter()
+                    .map(|var| format!("{}={}", var.name, var.value))
+                    .chain([program])
+                    .chain(args)
+                    .collect();
+                tty::Shell::new("env".to_string(), args)
+            }
+        });
+
+        setup_env();
+        env::set_var("ZED_TERM", "true");
+        let options = tty::Options {
+            shell,
+            working_directory: request.working_directory.map(PathBuf::from),
+            hold: false,
+        };
+        let window_size = WindowSize {
+            num_lines: 24,
+            num_cols: 80,
+            cell_width: 1,
+            cell_height: 1,
+        };
+        let pty = tty::new(&options, window_size, 0).context("failed to start a shell")?;
+
+        // The PTY's file is non-blocking for alacritty's event loop, but here it's
+        // read and written by threads of its own.
+        let reader = pty.file().try_clone()?;
+        unsafe {
+            let fd = reader.as_raw_fd();
+            let flags = libc::fcntl(fd, libc::F_GETFL);
+            libc::fcntl(fd, libc::F_SETFL, flags & !libc::O_NONBLOCK);
+        }
+        let writer = pty.file().try_clone()?;
+        let (output_tx, output_rx) = unbounded();
+        thread::spawn(move || read_output(reader, output_tx));
+        let (input_tx, input_rx) = mpsc::channel::<Vec<u8>>();
+        thread::spawn(move || {
+            let mut writer = writer;
+            for input in input_rx {
+                if writer.write_all(&input).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok((Self { pty, input_tx }, output_rx))
+    }
+
+    pub(crate) fn input(&mut self, input: Vec<u8>) {
+        self.input_tx.send(input).ok();
+    }
+
+    pub(crate) fn resize(&mut self, request: &proto::ResizeTerminal) {
+        self.pty.on_resize(WindowSize {
+            num_lines: request.rows as u16,
+            num_cols: request.columns as u16,
+            cell_width: request.cell_width as u16,
+            cell_height: request.cell_height as u16,
+        });
+    }
+
+    /// Whether the shell has exited, with its exit code unless it was killed by a
+    /// signal.
+    pub(crate) fn exit_status(&mut self) -> Option<Option<i32>> {
+        match self.pty.next_child_event()? {
+            ChildEvent::Exited(code) => Some(code),
+        }
+    }
+}
+
+fn read_output(mut reader: File, output_tx: UnboundedSender<Vec<u8>>) {
+    let mut buffer = vec![0; READ_BUFFER_LEN];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(len) => {
+                if output_tx.unbounded_send(buffer[..len].to_vec()).is_err() {
+                    break;
+                }
+            }
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            // Reading fails once the shell, and whatever it started, closed the PTY.
+            Err(_) => break,
+        }
+    }
+}
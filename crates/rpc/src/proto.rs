@@ -148,6 +148,7 @@ messages!(
     (CallCanceled, Foreground),
     (CancelCall, Foreground),
     (ChannelMessageSent, Foreground),
+    (CloseTerminal, Foreground),
     (CopyProjectEntry, Foreground),
     (CreateBufferForPeer, Foreground),
     (CreateChannel, Foreground),
@@ -211,6 +212,8 @@ messages!(
     (LeaveChannelChat, Foreground),
     (LeaveProject, Foreground),
     (LeaveRoom, Foreground),
+    (LoadFileBytes, Background),
+    (LoadFileBytesResponse, Background),
     (MarkNotificationRead, Foreground),
     (MoveChannel, Foreground),
     (OnTypeFormatting, Background),
@@ -220,6 +223,7 @@ messages!(
     (OpenBufferForSymbol, Background),
     (OpenBufferForSymbolResponse, Background),
     (OpenBufferResponse, Background),
+    (OpenTerminal, Foreground),
     (PerformRename, Background),
     (PerformRenameResponse, Background),
     (Ping, Foreground),
@@ -241,6 +245,7 @@ messages!(
     (RenameChannelResponse, Foreground),
     (RenameProjectEntry, Foreground),
     (RequestContact, Foreground),
+    (ResizeTerminal, Foreground),
     (ResolveCompletionDocumentation, Background),
     (ResolveCompletionDocumentationResponse, Background),
     (ResolveInlayHint, Background),
@@ -261,6 +266,9 @@ messages!(
     (StartLanguageServer, Foreground),
     (SynchronizeBuffers, Foreground),
     (SynchronizeBuffersResponse, Foreground),
+    (TerminalExited, Foreground),
+    (TerminalInput, Foreground),
+    (TerminalOutput, Foreground),
     (Test, Foreground),
     (Unfollow, Foreground),
     (UnshareProject, Foreground),
@@ -330,12 +338,14 @@ request_messages!(
     (JoinRoom, JoinRoomResponse),
     (LeaveChannelBuffer, Ack),
     (LeaveRoom, Ack),
+    (LoadFileBytes, LoadFileBytesResponse),
     (MarkNotificationRead, Ack),
     (MoveChannel, Ack),
     (OnTypeFormatting, OnTypeFormattingResponse),
     (OpenBufferById, OpenBufferResponse),
     (OpenBufferByPath, OpenBufferResponse),
     (OpenBufferForSymbol, OpenBufferForSymbolResponse),
+    (OpenTerminal, Ack),
     (PerformRename, PerformRenameResponse),
     (Ping, Ack),
     (PrepareRename, PrepareRenameResponse),
@@ -379,6 +389,7 @@ entity_messages!(
     ApplyCompletionAdditionalEdits,
     BufferReloaded,
     BufferSaved,
+    CloseTerminal,
     CopyProjectEntry,
     CreateBufferForPeer,
     CreateProjectEntry,
@@ -396,22 +407,28 @@ entity_messages!(
     InlayHints,
     JoinProject,
     LeaveProject,
+    LoadFileBytes,
     OnTypeFormatting,
     OpenBufferById,
     OpenBufferByPath,
     OpenBufferForSymbol,
+    OpenTerminal,
     PerformRename,
     PrepareRename,
     RefreshInlayHints,
     ReloadBuffers,
     RemoveProjectCollaborator,
     RenameProjectEntry,
+    ResizeTerminal,
     ResolveCompletionDocumentation,
     ResolveInlayHint,
     SaveBuffer,
     SearchProject,
     StartLanguageServer,
     SynchronizeBuffers,
+    TerminalExited,
+    TerminalInput,
+    TerminalOutput,
     UnshareProject,
     UpdateBuffer,
     UpdateBufferFile,
@@ -6,7 +6,7 @@ use crate::{
     search_bar::render_nav_button,
     ActivateRegexMode, ActivateTextMode, CycleMode, NextHistoryQuery, PreviousHistoryQuery,
     ReplaceAll, ReplaceNext, SearchOptions, SelectAllMatches, SelectNextMatch, SelectPrevMatch,
-    ToggleCaseSensitive, ToggleReplace, ToggleWholeWord,
+    ToggleCaseSensitive, ToggleReplace, ToggleSelection, ToggleWholeWord,
 };
 use collections::HashMap;
 use editor::{
@@ -237,6 +237,12 @@ impl Render for BufferSearchBar {
                             SearchOptions::WHOLE_WORD,
                             cx.listener(|this, _, cx| this.toggle_whole_word(&ToggleWholeWord, cx)),
                         )
+                    }))
+                    .children(supported_options.selection.then(|| {
+                        self.render_search_option_button(
+                            SearchOptions::SELECTION,
+                            cx.listener(|this, _, cx| this.toggle_selection(&ToggleSelection, cx)),
+                        )
                     })),
             )
             .child(
@@ -400,6 +406,9 @@ impl Render for BufferSearchBar {
             .when(self.supported_options().word, |this| {
                 this.on_action(cx.listener(Self::toggle_whole_word))
             })
+            .when(self.supported_options().selection, |this| {
+                this.on_action(cx.listener(Self::toggle_selection))
+            })
             .gap_2()
             .child(
                 h_flex().child(search_line.w_full()).child(
@@ -427,6 +436,7 @@ impl ToolbarItemView for BufferSearchBar {
         cx: &mut ViewContext<Self>,
     ) -> ToolbarItemLocation {
         cx.notify();
+        self.release_search_ranges(cx);
         self.active_searchable_item_subscription.take();
         self.active_searchable_item.take();
 
@@ -475,6 +485,11 @@ impl BufferSearchBar {
                 this.toggle_whole_word(action, cx);
             }
         }));
+        registrar.register_handler(ForDeployed(|this, action: &ToggleSelection, cx| {
+            if this.supported_options().selection {
+                this.toggle_selection(action, cx);
+            }
+        }));
         registrar.register_handler(ForDeployed(|this, action: &ToggleReplace, cx| {
             if this.supported_options().replacement {
                 this.toggle_replace(action, cx);
@@ -552,6 +567,7 @@ impl BufferSearchBar {
 
     pub fn dismiss(&mut self, _: &Dismiss, cx: &mut ViewContext<Self>) {
         self.dismissed = true;
+        self.release_search_ranges(cx);
         for searchable_item in self.searchable_items_with_matches.keys() {
             if let Some(searchable_item) =
                 WeakSearchableItemHandle::upgrade(searchable_item.as_ref(), cx)
@@ -688,7 +704,9 @@ impl BufferSearchBar {
                     query_buffer.edit([(0..len, query)], None, cx);
                 });
             });
-            self.search_options = options;
+            self.search_options = (options - SearchOptions::SELECTION)
+                | (self.search_options & SearchOptions::SELECTION);
+            self.set_selection_search(options.contains(SearchOptions::SELECTION), cx);
             self.clear_matches(cx);
             cx.notify();
         }
@@ -726,7 +744,7 @@ impl BufferSearchBar {
 
     fn toggle_search_option(&mut self, search_option: SearchOptions, cx: &mut ViewContext<Self>) {
         self.search_options.toggle(search_option);
-        self.default_options = self.search_options;
+        self.default_options = self.search_options - SearchOptions::SELECTION;
         let _ = self.update_matches(cx);
         cx.notify();
     }
@@ -736,10 +754,46 @@ impl BufferSearchBar {
         search_options: SearchOptions,
         cx: &mut ViewContext<Self>,
     ) {
-        self.search_options = search_options;
+        self.search_options = (search_options - SearchOptions::SELECTION)
+            | (self.search_options & SearchOptions::SELECTION);
+        self.set_selection_search(search_options.contains(SearchOptions::SELECTION), cx);
         cx.notify();
     }
 
+    /// Restricts the search to the active item's current selections, or lifts that restriction.
+    ///
+    /// The selections are captured when the restriction is enabled, so moving the cursor
+    /// afterwards does not change the searched ranges. Without any selected text there's
+    /// nothing to restrict the search to, and the option stays off.
+    fn set_selection_search(&mut self, enabled: bool, cx: &mut ViewContext<Self>) {
+        if self.search_options.contains(SearchOptions::SELECTION) == enabled {
+            return;
+        }
+        if let Some(active_searchable_item) = self.active_searchable_item.as_ref() {
+            if active_searchable_item.supported_options().selection {
+                active_searchable_item.toggle_filtered_search_ranges(enabled, cx);
+                let enabled = enabled && active_searchable_item.has_filtered_search_ranges(cx);
+                self.search_options.set(SearchOptions::SELECTION, enabled);
+            }
+        }
+    }
+
+    /// Lifts the restriction to the captured ranges once edits have left no text in them.
+    fn release_emptied_search_ranges(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(active_searchable_item) = self.active_searchable_item.as_ref() {
+            if self.search_options.contains(SearchOptions::SELECTION)
+                && !active_searchable_item.has_filtered_search_ranges(cx)
+            {
+                self.release_search_ranges(cx);
+                cx.notify();
+            }
+        }
+    }
+
+    fn release_search_ranges(&mut self, cx: &mut ViewContext<Self>) {
+        self.set_selection_search(false, cx);
+    }
+
     fn select_next_match(&mut self, _: &SelectNextMatch, cx: &mut ViewContext<Self>) {
         self.select_match(Direction::Next, 1, cx);
     }
@@ -833,6 +887,7 @@ impl BufferSearchBar {
     fn on_active_searchable_item_event(&mut self, event: &SearchEvent, cx: &mut ViewContext<Self>) {
         match event {
             SearchEvent::MatchesInvalidated => {
+                self.release_emptied_search_ranges(cx);
                 let _ = self.update_matches(cx);
             }
             SearchEvent::ActiveMatchChanged => self.update_match_index(cx),
@@ -845,6 +900,12 @@ impl BufferSearchBar {
     fn toggle_whole_word(&mut self, _: &ToggleWholeWord, cx: &mut ViewContext<Self>) {
         self.toggle_search_option(SearchOptions::WHOLE_WORD, cx)
     }
+    fn toggle_selection(&mut self, _: &ToggleSelection, cx: &mut ViewContext<Self>) {
+        let enabled = !self.search_options.contains(SearchOptions::SELECTION);
+        self.set_selection_search(enabled, cx);
+        let _ = self.update_matches(cx);
+        cx.notify();
+    }
 
     fn clear_active_searchable_item_matches(&mut self, cx: &mut WindowContext) {
         if let Some(active_searchable_item) = self.active_searchable_item.as_ref() {
@@ -1091,7 +1152,7 @@ mod tests {
     use super::*;
     use editor::{DisplayPoint, Editor};
     use gpui::{Context, Hsla, TestAppContext, VisualTestContext};
-    use language::{Buffer, BufferId};
+    use language::{Buffer, BufferId, Point};
     use smol::stream::StreamExt as _;
     use unindent::Unindent as _;
 
@@ -1922,6 +1983,112 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_search_within_selection(cx: &mut TestAppContext) {
+        let (editor, search_bar, cx) = init_test(cx);
+        let match_count = |search_bar: &BufferSearchBar, editor: &View<Editor>| {
+            search_bar
+                .searchable_items_with_matches
+                .get(&SearchableItemHandle::downgrade(editor))
+                .map_or(0, Vec::len)
+        };
+
+        editor.update(cx, |editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.select_display_ranges([DisplayPoint::new(1, 0)..DisplayPoint::new(3, 0)])
+            });
+        });
+        search_bar
+            .update(cx, |search_bar, cx| {
+                search_bar.search("or", Some(SearchOptions::SELECTION), cx)
+            })
+            .await
+            .unwrap();
+        search_bar.update(cx, |search_bar, _| {
+            assert_eq!(match_count(search_bar, &editor), 1);
+        });
+
+        // Moving the cursor does not change the ranges being searched.
+        editor.update(cx, |editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.select_display_ranges([DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0)])
+            });
+        });
+        search_bar.update(cx, |search_bar, cx| {
+            search_bar.replacement_editor.update(cx, |editor, cx| {
+                editor.set_text("OR", cx);
+            });
+            search_bar.replace_all(&ReplaceAll, cx)
+        });
+        assert_eq!(
+            editor.update(cx, |this, cx| { this.text(cx) }),
+            r#"
+        A regular expression (shortened as regex or regexp;[1] also referred to as
+        rational expression[2][3]) is a sequence of characters that specifies a search
+        pattern in text. Usually such patterns are used by string-searching algORithms
+        for "find" or "find and replace" operations on strings, or for input validation.
+        "#
+            .unindent()
+        );
+
+        // Turning the option off searches the whole buffer again.
+        search_bar.update(cx, |search_bar, cx| {
+            search_bar.toggle_selection(&ToggleSelection, cx);
+        });
+        cx.run_until_parked();
+        search_bar.update(cx, |search_bar, _| {
+            assert!(!search_bar.search_options.contains(SearchOptions::SELECTION));
+            assert_eq!(match_count(search_bar, &editor), 7);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_search_within_selection_without_selected_text(cx: &mut TestAppContext) {
+        let (editor, search_bar, cx) = init_test(cx);
+        let match_count = |search_bar: &BufferSearchBar, editor: &View<Editor>| {
+            search_bar
+                .searchable_items_with_matches
+                .get(&SearchableItemHandle::downgrade(editor))
+                .map_or(0, Vec::len)
+        };
+
+        // With only a cursor there's nothing to search within.
+        search_bar
+            .update(cx, |search_bar, cx| {
+                search_bar.search("or", Some(SearchOptions::SELECTION), cx)
+            })
+            .await
+            .unwrap();
+        search_bar.update(cx, |search_bar, _| {
+            assert!(!search_bar.search_options.contains(SearchOptions::SELECTION));
+            assert_eq!(match_count(search_bar, &editor), 7);
+        });
+
+        editor.update(cx, |editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.select_display_ranges([DisplayPoint::new(1, 0)..DisplayPoint::new(3, 0)])
+            });
+        });
+        search_bar.update(cx, |search_bar, cx| {
+            search_bar.toggle_selection(&ToggleSelection, cx);
+        });
+        cx.run_until_parked();
+        search_bar.update(cx, |search_bar, _| {
+            assert!(search_bar.search_options.contains(SearchOptions::SELECTION));
+            assert_eq!(match_count(search_bar, &editor), 1);
+        });
+
+        // Deleting the text of the captured ranges turns the option off along with them.
+        editor.update(cx, |editor, cx| {
+            editor.edit([(Point::new(1, 0)..Point::new(3, 0), "")], cx);
+        });
+        cx.run_until_parked();
+        search_bar.update(cx, |search_bar, _| {
+            assert!(!search_bar.search_options.contains(SearchOptions::SELECTION));
+            assert_eq!(match_count(search_bar, &editor), 6);
+        });
+    }
+
     #[gpui::test]
     async fn test_invalid_regexp_search_after_valid(cx: &mut TestAppContext) {
         let (editor, search_bar, cx) = init_test(cx);
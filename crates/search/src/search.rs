@@ -27,6 +27,7 @@ actions!(
         ToggleCaseSensitive,
         ToggleIncludeIgnored,
         ToggleReplace,
+        ToggleSelection,
         SelectNextMatch,
         SelectPrevMatch,
         SelectAllMatches,
@@ -43,10 +44,11 @@ actions!(
 bitflags! {
     #[derive(Default)]
     pub struct SearchOptions: u8 {
-        const NONE = 0b000;
-        const WHOLE_WORD = 0b001;
-        const CASE_SENSITIVE = 0b010;
-        const INCLUDE_IGNORED = 0b100;
+        const NONE = 0b0000;
+        const WHOLE_WORD = 0b0001;
+        const CASE_SENSITIVE = 0b0010;
+        const INCLUDE_IGNORED = 0b0100;
+        const SELECTION = 0b1000;
     }
 }
 
@@ -56,6 +58,7 @@ impl SearchOptions {
             SearchOptions::WHOLE_WORD => "Match Whole Word",
             SearchOptions::CASE_SENSITIVE => "Match Case",
             SearchOptions::INCLUDE_IGNORED => "Include ignored",
+            SearchOptions::SELECTION => "Search Selection",
             _ => panic!("{:?} is not a named SearchOption", self),
         }
     }
@@ -65,6 +68,7 @@ impl SearchOptions {
             SearchOptions::WHOLE_WORD => ui::IconName::WholeWord,
             SearchOptions::CASE_SENSITIVE => ui::IconName::CaseSensitive,
             SearchOptions::INCLUDE_IGNORED => ui::IconName::FileGit,
+            SearchOptions::SELECTION => ui::IconName::TextSelect,
             _ => panic!("{:?} is not a named SearchOption", self),
         }
     }
@@ -74,6 +78,7 @@ impl SearchOptions {
             SearchOptions::WHOLE_WORD => Box::new(ToggleWholeWord),
             SearchOptions::CASE_SENSITIVE => Box::new(ToggleCaseSensitive),
             SearchOptions::INCLUDE_IGNORED => Box::new(ToggleIncludeIgnored),
+            SearchOptions::SELECTION => Box::new(ToggleSelection),
             _ => panic!("{:?} is not a named SearchOption", self),
         }
     }
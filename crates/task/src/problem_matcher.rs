@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+
+/// A pattern that extracts problems, such as compiler errors, from the lines of a
+/// task's output.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ProblemMatcher {
+    /// Regular expression matched against each line of the output.
+    pub pattern: String,
+    /// Index of the capture group containing the path of the file, relative to the
+    /// directory of the task.
+    #[serde(default = "default_file_group")]
+    pub file: usize,
+    /// Index of the capture group containing the one-based line of the problem.
+    #[serde(default = "default_line_group")]
+    pub line: usize,
+    /// Index of the capture group containing the one-based column of the problem.
+    #[serde(default)]
+    pub column: Option<usize>,
+    /// Index of the capture group containing the severity of the problem, such as
+    /// `error` or `warning`.
+    #[serde(default)]
+    pub severity: Option<usize>,
+    /// Index of the capture group containing the message. Defaults to the whole line.
+    #[serde(default)]
+    pub message: Option<usize>,
+    /// The severity of the problems whose severity isn't captured or recognized.
+    #[serde(default)]
+    pub default_severity: ProblemSeverity,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemSeverity {
+    #[default]
+    Error,
+    Warning,
+    Info,
+    Hint,
+}
+
+/// A problem found in the output of a task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Problem {
+    pub path: String,
+    /// Zero-based row of the problem.
+    pub row: u32,
+    /// Zero-based column of the problem.
+    pub column: u32,
+    pub severity: ProblemSeverity,
+    pub message: String,
+}
+
+fn default_file_group() -> usize {
+    1
+}
+
+fn default_line_group() -> usize {
+    2
+}
+
+impl ProblemSeverity {
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().to_lowercase();
+        if text.starts_with("err") || text == "fatal" {
+            Some(Self::Error)
+        } else if text.starts_with("warn") {
+            Some(Self::Warning)
+        } else if text.starts_with("info") || text == "note" {
+            Some(Self::Info)
+        } else if text == "hint" || text == "help" {
+            Some(Self::Hint)
+        } else {
+            None
+        }
+    }
+}
+
+impl ProblemMatcher {
+    /// Returns the problems found in the lines of the given output.
+    pub fn match_output(&self, output: &str) -> Result<Vec<Problem>> {
+        let regex = Regex::new(&self.pattern)
+            .with_context(|| format!("invalid problem matcher pattern {:?}", self.pattern))?;
+        Ok(output
+            .lines()
+            .filter_map(|line| self.match_line(&regex, line))
+            .collect())
+    }
+
+    fn match_line(&self, regex: &Regex, line: &str) -> Option<Problem> {
+        let captures = regex.captures(line)?;
+        let group = |ix: usize| captures.get(ix).map(|group| group.as_str());
+        let number = |ix: Option<usize>| -> Option<u32> {
+            let number = group(ix?)?.trim().parse::<u32>().ok()?;
+            Some(number.saturating_sub(1))
+        };
+
+        let path = group(self.file)?.trim();
+        if path.is_empty() {
+            return None;
+        }
+        let row = number(Some(self.line))?;
+        let column = number(self.column).unwrap_or(0);
+        let severity = self
+            .severity
+            .and_then(group)
+            .and_then(ProblemSeverity::parse)
+            .unwrap_or(self.default_severity);
+        let message = match self.message {
+            Some(ix) => group(ix).unwrap_or_default(),
+            None => line,
+        };
+
+        Some(Problem {
+            path: path.to_string(),
+            row,
+            column,
+            severity,
+            message: message.trim().to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_output() {
+        let matcher = ProblemMatcher {
+            pattern: r"^(.+?):(\d+):(\d+): (error|warning|note): (.*)$".into(),
+            file: 1,
+            line: 2,
+            column: Some(3),
+            severity: Some(4),
+            message: Some(5),
+            default_severity: ProblemSeverity::Error,
+        };
+        let output = "\
+            compiling...\n\
+            src/main.c:10:5: error: expected ';'\n\
+            src/lib.c:3:1: warning: unused variable 'x'\n\
+            src/lib.c:0:0: note: declared here\n\
+            done";
+        assert_eq!(
+            matcher.match_output(output).unwrap(),
+            [
+                Problem {
+                    path: "src/main.c".into(),
+                    row: 9,
+                    column: 4,
+                    severity: ProblemSeverity::Error,
+                    message: "expected ';'".into(),
+                },
+                Problem {
+                    path: "src/lib.c".into(),
+                    row: 2,
+                    column: 0,
+                    severity: ProblemSeverity::Warning,
+                    message: "unused variable 'x'".into(),
+                },
+                Problem {
+                    path: "src/lib.c".into(),
+                    row: 0,
+                    column: 0,
+                    severity: ProblemSeverity::Info,
+                    message: "declared here".into(),
+                },
+            ]
+        );
+
+        // Without severity and message groups, the whole line is reported with the
+        // default severity.
+        let matcher = ProblemMatcher {
+            pattern: r"^(\S+)\((\d+)\)".into(),
+            file: 1,
+            line: 2,
+            column: None,
+            severity: None,
+            message: None,
+            default_severity: ProblemSeverity::Warning,
+        };
+        assert_eq!(
+            matcher
+                .match_output("a.ts(7) is deprecated\nb.ts(x)")
+                .unwrap(),
+            [Problem {
+                path: "a.ts".into(),
+                row: 6,
+                column: 0,
+                severity: ProblemSeverity::Warning,
+                message: "a.ts(7) is deprecated".into(),
+            }]
+        );
+
+        let matcher = ProblemMatcher {
+            pattern: "(".into(),
+            ..matcher
+        };
+        assert!(matcher.match_output("").is_err());
+    }
+}
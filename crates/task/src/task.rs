@@ -0,0 +1,326 @@
+//! Tasks are commands defined in `tasks.json` files that can be spawned in the terminal,
+//! with details about the current editing context substituted into them.
+
+mod problem_matcher;
+
+use collections::HashMap;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Display},
+    path::PathBuf,
+};
+
+pub use problem_matcher::{Problem, ProblemMatcher, ProblemSeverity};
+
+/// Prefix of the variables substituted into the tasks.
+const VARIABLE_PREFIX: &str = "ZED_";
+
+/// A unique identifier of a task, which stays the same across its runs so that the
+/// terminal it last ran in can be found again.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaskId(pub String);
+
+/// A task, as defined in a `tasks.json` file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TaskTemplate {
+    /// Human readable name of the task to display in the UI.
+    pub label: String,
+    /// Executable command to spawn.
+    pub command: String,
+    /// Arguments to the command.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Environment variables to set for the command, on top of the terminal's environment.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Directory to spawn the command in, relative to the directory of the tasks file.
+    /// Defaults to the directory of the tasks file.
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Whether to spawn the task in a new terminal tab, rather than reusing the tab
+    /// of its previous run.
+    #[serde(default)]
+    pub use_new_terminal: bool,
+    /// Whether to allow the task to run while a previous run of it is still in progress.
+    #[serde(default)]
+    pub allow_concurrent_runs: bool,
+    /// Patterns turning lines of the task's output into diagnostics.
+    #[serde(default)]
+    pub problem_matchers: Vec<ProblemMatcher>,
+}
+
+/// The contents of a `tasks.json` file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct TaskTemplates(pub Vec<TaskTemplate>);
+
+/// A variable describing the editing context a task is spawned from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum VariableName {
+    /// Absolute path of the file open in the active editor.
+    File,
+    /// One-based row of the cursor in the active editor.
+    Row,
+    /// One-based column of the cursor in the active editor.
+    Column,
+    /// Text selected in the active editor.
+    SelectedText,
+    /// Absolute path of the root of the worktree the active file belongs to.
+    WorktreeRoot,
+}
+
+impl VariableName {
+    pub const ALL: [Self; 5] = [
+        Self::File,
+        Self::Row,
+        Self::Column,
+        Self::SelectedText,
+        Self::WorktreeRoot,
+    ];
+
+    /// The name of the variable, as referenced in the tasks and set in their environment.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::File => "ZED_FILE",
+            Self::Row => "ZED_ROW",
+            Self::Column => "ZED_COLUMN",
+            Self::SelectedText => "ZED_SELECTED_TEXT",
+            Self::WorktreeRoot => "ZED_WORKTREE_ROOT",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|variable| variable.name() == name)
+    }
+}
+
+impl Display for VariableName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${}", self.name())
+    }
+}
+
+/// The values of the variables available when a task is spawned.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TaskVariables(HashMap<VariableName, String>);
+
+impl TaskVariables {
+    pub fn insert(&mut self, variable: VariableName, value: String) {
+        self.0.insert(variable, value);
+    }
+
+    pub fn get(&self, variable: VariableName) -> Option<&str> {
+        self.0.get(&variable).map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (VariableName, &str)> {
+        self.0
+            .iter()
+            .map(|(variable, value)| (*variable, value.as_str()))
+    }
+}
+
+impl FromIterator<(VariableName, String)> for TaskVariables {
+    fn from_iter<T: IntoIterator<Item = (VariableName, String)>>(iter: T) -> Self {
+        Self(HashMap::from_iter(iter))
+    }
+}
+
+/// The context a task is spawned in.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TaskContext {
+    /// Directory to spawn the task in when it doesn't specify one.
+    pub cwd: Option<PathBuf>,
+    pub task_variables: TaskVariables,
+}
+
+/// A task with all the variables substituted, ready to be spawned.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedTask {
+    pub original_task: TaskTemplate,
+    pub resolved: SpawnInTerminal,
+}
+
+/// Everything needed to spawn a task in the terminal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpawnInTerminal {
+    pub id: TaskId,
+    pub label: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    pub env: HashMap<String, String>,
+    pub use_new_terminal: bool,
+    pub allow_concurrent_runs: bool,
+    pub problem_matchers: Vec<ProblemMatcher>,
+}
+
+impl TaskTemplate {
+    /// Substitutes the variables of the context into the task.
+    ///
+    /// Returns `None` when the task references a variable that isn't available in the
+    /// context, such as the selected text when nothing is selected.
+    pub fn resolve_task(&self, id_base: &str, cx: &TaskContext) -> Option<ResolvedTask> {
+        let variables = &cx.task_variables;
+        let label = substitute_variables(&self.label, variables)?;
+        let command = substitute_variables(&self.command, variables)?;
+        let args = self
+            .args
+            .iter()
+            .map(|arg| substitute_variables(arg, variables))
+            .collect::<Option<Vec<_>>>()?;
+
+        let cwd = match &self.cwd {
+            Some(cwd) => {
+                let cwd = PathBuf::from(substitute_variables(cwd, variables)?);
+                match &cx.cwd {
+                    Some(context_cwd) if cwd.is_relative() => Some(context_cwd.join(cwd)),
+                    _ => Some(cwd),
+                }
+            }
+            None => cx.cwd.clone(),
+        };
+
+        let mut env = variables
+            .iter()
+            .map(|(variable, value)| (variable.name().to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        for (key, value) in &self.env {
+            env.insert(key.clone(), substitute_variables(value, variables)?);
+        }
+
+        Some(ResolvedTask {
+            original_task: self.clone(),
+            resolved: SpawnInTerminal {
+                id: TaskId(format!("{id_base}_{}", self.label)),
+                label,
+                command,
+                args,
+                cwd,
+                env,
+                use_new_terminal: self.use_new_terminal,
+                allow_concurrent_runs: self.allow_concurrent_runs,
+                problem_matchers: self.problem_matchers.clone(),
+            },
+        })
+    }
+}
+
+/// Replaces the `$ZED_*` and `${ZED_*}` variables in the given text with their values,
+/// leaving any other variable untouched.
+fn substitute_variables(text: &str, variables: &TaskVariables) -> Option<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(dollar_ix) = rest.find('$') {
+        result.push_str(&rest[..dollar_ix]);
+        let after_dollar = &rest[dollar_ix + 1..];
+        let (name, reference_len) = if let Some(braced) = after_dollar.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let end = after_dollar
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(after_dollar.len());
+            (&after_dollar[..end], end)
+        };
+
+        if name.starts_with(VARIABLE_PREFIX) {
+            let value = variables.get(VariableName::from_name(name)?)?;
+            result.push_str(value);
+        } else {
+            result.push('$');
+            result.push_str(&after_dollar[..reference_len]);
+        }
+        rest = &after_dollar[reference_len..];
+    }
+    result.push_str(rest);
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolving_tasks() {
+        let template = TaskTemplate {
+            label: "test ${ZED_FILE}".into(),
+            command: "cargo".into(),
+            args: vec!["test".into(), "$ZED_FILE:$ZED_ROW".into(), "$HOME".into()],
+            env: HashMap::from_iter([("SELECTION".into(), "${ZED_SELECTED_TEXT}".into())]),
+            cwd: Some("crates".into()),
+            use_new_terminal: false,
+            allow_concurrent_runs: false,
+            problem_matchers: Vec::new(),
+        };
+
+        let mut cx = TaskContext {
+            cwd: Some(PathBuf::from("/project")),
+            task_variables: TaskVariables::from_iter([
+                (VariableName::File, "/project/src/main.rs".into()),
+                (VariableName::Row, "12".into()),
+                (VariableName::Column, "4".into()),
+            ]),
+        };
+        // The task refers to the selected text, which isn't available.
+        assert_eq!(template.resolve_task("global", &cx), None);
+
+        cx.task_variables
+            .insert(VariableName::SelectedText, "foo".into());
+        let resolved = template.resolve_task("global", &cx).unwrap().resolved;
+        assert_eq!(resolved.id, TaskId("global_test ${ZED_FILE}".into()));
+        assert_eq!(resolved.label, "test /project/src/main.rs");
+        assert_eq!(resolved.command, "cargo");
+        assert_eq!(resolved.args, ["test", "/project/src/main.rs:12", "$HOME"]);
+        assert_eq!(resolved.cwd, Some(PathBuf::from("/project/crates")));
+        assert_eq!(resolved.env.get("SELECTION").unwrap(), "foo");
+        assert_eq!(resolved.env.get("ZED_ROW").unwrap(), "12");
+        assert_eq!(resolved.env.get("ZED_COLUMN").unwrap(), "4");
+    }
+
+    #[test]
+    fn test_substitute_variables() {
+        let variables = TaskVariables::from_iter([(VariableName::Row, "3".into())]);
+        assert_eq!(
+            substitute_variables("a$ZED_ROW-${ZED_ROW}b", &variables).as_deref(),
+            Some("a3-3b")
+        );
+        assert_eq!(
+            substitute_variables("$ $1 ${PATH} $ZED_ROW$", &variables).as_deref(),
+            Some("$ $1 ${PATH} 3$")
+        );
+        assert_eq!(
+            substitute_variables("${ZED_ROW", &variables).as_deref(),
+            Some("${ZED_ROW")
+        );
+        assert_eq!(substitute_variables("$ZED_FILE", &variables), None);
+        assert_eq!(substitute_variables("$ZED_UNKNOWN", &variables), None);
+    }
+
+    #[test]
+    fn test_parsing_templates() {
+        let templates: TaskTemplates = serde_json::from_str(
+            r#"[
+                {
+                    "label": "clippy",
+                    "command": "cargo",
+                    "args": ["clippy"],
+                    "problem_matchers": [{ "pattern": "^(.+):(\\d+):(\\d+): (.*)$", "column": 3, "message": 4 }]
+                }
+            ]"#,
+        )
+        .unwrap();
+        let template = &templates.0[0];
+        assert_eq!(template.label, "clippy");
+        assert!(!template.use_new_terminal);
+        assert_eq!(template.problem_matchers[0].file, 1);
+        assert_eq!(template.problem_matchers[0].line, 2);
+        assert_eq!(template.problem_matchers[0].column, Some(3));
+    }
+}
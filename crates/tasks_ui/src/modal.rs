@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    rems, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, ParentElement,
+    Render, Styled, Task, View, ViewContext, VisualContext, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use project::task_inventory::TaskSourceKind;
+use task::ResolvedTask;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+use crate::{schedule_task, task_context, Spawn};
+
+/// Lists the tasks that can be spawned from the active editor.
+pub struct TasksModal {
+    picker: View<Picker<TasksModalDelegate>>,
+}
+
+impl TasksModal {
+    pub fn toggle(workspace: &mut Workspace, _: &Spawn, cx: &mut ViewContext<Workspace>) {
+        let (worktree_id, task_context) = task_context(workspace, cx);
+        let candidates = workspace
+            .project()
+            .read(cx)
+            .task_inventory()
+            .read(cx)
+            .resolve_tasks(worktree_id, &task_context, cx);
+        let workspace_handle = cx.view().downgrade();
+        workspace.toggle_modal(cx, |cx| Self::new(workspace_handle, candidates, cx));
+    }
+
+    fn new(
+        workspace: WeakView<Workspace>,
+        candidates: Vec<(TaskSourceKind, ResolvedTask)>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let delegate = TasksModalDelegate {
+            tasks_modal: cx.view().downgrade(),
+            workspace,
+            candidates,
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        let picker = cx.new_view(|cx| Picker::new(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for TasksModal {
+    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for TasksModal {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for TasksModal {}
+impl ModalView for TasksModal {}
+
+pub struct TasksModalDelegate {
+    tasks_modal: WeakView<TasksModal>,
+    workspace: WeakView<Workspace>,
+    candidates: Vec<(TaskSourceKind, ResolvedTask)>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl PickerDelegate for TasksModalDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self) -> Arc<str> {
+        "Select a task to spawn...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self
+            .candidates
+            .iter()
+            .enumerate()
+            .map(|(ix, (_, task))| StringMatchCandidate::new(ix, task.resolved.label.clone()))
+            .collect::<Vec<_>>();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let (source, task) = self.candidates[mat.candidate_id].clone();
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    schedule_task(workspace, source, task, cx);
+                })
+                .log_err();
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.tasks_modal
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let (source, _) = &self.candidates[mat.candidate_id];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                ))
+                .end_slot(
+                    Label::new(source.label())
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                ),
+        )
+    }
+}
@@ -0,0 +1,392 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use editor::{actions::SpawnRunnable, Editor};
+use gpui::{ViewContext, WindowContext};
+use language::{Language, Point, RunnableKind};
+use project::task_inventory::TaskSourceKind;
+use task::TaskTemplate;
+use workspace::Workspace;
+
+use crate::{schedule_task, task_context};
+
+/// Spawns the runnable on the given row of the active editor, or on the row of its
+/// newest selection.
+pub(crate) fn spawn_runnable(
+    workspace: &mut Workspace,
+    action: &SpawnRunnable,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some((language, template)) = runnable_task_at_row(editor.read(cx), action.row, cx) else {
+        return;
+    };
+    let (_, task_context) = task_context(workspace, cx);
+    let source = TaskSourceKind::Language {
+        name: language.name(),
+    };
+    if let Some(task) = template.resolve_task(&source.id_base(), &task_context) {
+        schedule_task(workspace, source, task, cx);
+    }
+}
+
+fn runnable_task_at_row(
+    editor: &Editor,
+    row: Option<u32>,
+    cx: &WindowContext,
+) -> Option<(Arc<Language>, TaskTemplate)> {
+    let row = row.unwrap_or_else(|| editor.selections.newest::<Point>(cx).head().row);
+    let multi_buffer = editor.buffer().read(cx);
+    let (buffer, offset, _) = multi_buffer.point_to_buffer_offset(Point::new(row, 0), cx)?;
+    let buffer = buffer.read(cx);
+    let abs_path = buffer.file()?.as_local()?.abs_path(cx);
+    let crate_root = project::File::from_dyn(buffer.file()).and_then(|file| crate_root(file, cx));
+
+    let snapshot = buffer.snapshot();
+    let buffer_row = snapshot.offset_to_point(offset).row;
+    let runnable = snapshot
+        .runnables_in_range(
+            Point::new(buffer_row, 0)..Point::new(buffer_row, snapshot.line_len(buffer_row)),
+        )
+        .into_iter()
+        .find(|runnable| snapshot.offset_to_point(runnable.range.start).row == buffer_row)?;
+    let symbol_path = snapshot.runnable_symbol_path(&runnable);
+    let template = runnable_task(
+        &runnable.language.name(),
+        runnable.kind,
+        &symbol_path,
+        &abs_path,
+        crate_root.as_deref(),
+    )?;
+    Some((runnable.language, template))
+}
+
+/// The directory of the nearest `Cargo.toml` above the given file in its worktree.
+fn crate_root(file: &project::File, cx: &WindowContext) -> Option<PathBuf> {
+    let worktree = file.worktree.read(cx);
+    let root_dir = file
+        .path
+        .ancestors()
+        .skip(1)
+        .find(|dir| worktree.entry_for_path(dir.join("Cargo.toml")).is_some())?;
+    Some(worktree.abs_path().join(root_dir))
+}
+
+/// Builds the task running a runnable of the given language, defined in the file at
+/// `abs_path`. The task is spawned in the directory of the file.
+///
+/// Rust runnables need the `crate_root` directory containing the file's `Cargo.toml`.
+fn runnable_task(
+    language: &str,
+    kind: RunnableKind,
+    symbol_path: &[String],
+    abs_path: &Path,
+    crate_root: Option<&Path>,
+) -> Option<TaskTemplate> {
+    let directory = abs_path.parent()?;
+    let file_name = abs_path.file_name()?.to_string_lossy().to_string();
+    let name = symbol_path.last()?;
+    let (command, args) = match (language, kind) {
+        ("Rust", RunnableKind::Test | RunnableKind::Benchmark) => {
+            let (target, module_path) = rust_target(abs_path, crate_root?)?;
+            let subcommand = if kind == RunnableKind::Test {
+                "test"
+            } else {
+                "bench"
+            };
+            let test_path = module_path
+                .iter()
+                .chain(symbol_path)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("::");
+            let mut args = vec![subcommand.to_string()];
+            args.extend(target);
+            args.extend(["--".into(), test_path, "--exact".into()]);
+            ("cargo", args)
+        }
+        ("Rust", RunnableKind::Main) => {
+            let (target, _) = rust_target(abs_path, crate_root?)?;
+            let mut args = vec!["run".to_string()];
+            args.extend(target);
+            ("cargo", args)
+        }
+        ("Go", RunnableKind::Test) => (
+            "go",
+            vec!["test".into(), "-run".into(), format!("^{name}$")],
+        ),
+        ("Go", RunnableKind::Benchmark) => (
+            "go",
+            vec![
+                "test".into(),
+                "-run".into(),
+                "^$".into(),
+                "-bench".into(),
+                format!("^{name}$"),
+            ],
+        ),
+        ("Go", RunnableKind::Main) => ("go", vec!["run".into(), ".".into()]),
+        ("Python", RunnableKind::Test) => (
+            "pytest",
+            vec![format!("{file_name}::{}", symbol_path.join("::"))],
+        ),
+        ("Python", RunnableKind::Main) => ("python3", vec![file_name]),
+        ("JavaScript" | "TypeScript" | "TSX", RunnableKind::Test) => {
+            let test_name = name.trim_matches(|c| matches!(c, '"' | '\'' | '`'));
+            (
+                "npx",
+                vec![
+                    "jest".into(),
+                    file_name,
+                    "-t".into(),
+                    escape_regex(test_name),
+                ],
+            )
+        }
+        _ => return None,
+    };
+
+    Some(TaskTemplate {
+        label: format!("{command} {}", args.join(" ")),
+        command: command.to_string(),
+        args,
+        cwd: Some(directory.to_string_lossy().to_string()),
+        ..TaskTemplate::default()
+    })
+}
+
+/// The Cargo target flags selecting the target a Rust file belongs to, and the path of
+/// the file's module in that target, from the file's location below its crate's root.
+fn rust_target(abs_path: &Path, crate_root: &Path) -> Option<(Vec<String>, Vec<String>)> {
+    let components = abs_path
+        .strip_prefix(crate_root)
+        .ok()?
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    let (target_dir, files) = components.split_first()?;
+    let (target, files) = match (target_dir.as_str(), files) {
+        ("src", [bin, target, files @ ..]) if bin == "bin" => (Some(("--bin", target)), files),
+        ("src", files) => (None, files),
+        ("tests", [target, files @ ..]) => (Some(("--test", target)), files),
+        ("benches", [target, files @ ..]) => (Some(("--bench", target)), files),
+        ("examples", [target, files @ ..]) => (Some(("--example", target)), files),
+        _ => return None,
+    };
+    let target = target
+        .map(|(flag, name)| {
+            let name = name.strip_suffix(".rs").unwrap_or(name);
+            vec![flag.to_string(), name.to_string()]
+        })
+        .unwrap_or_default();
+
+    let mut module_path = files
+        .iter()
+        .map(|name| name.strip_suffix(".rs").unwrap_or(name).to_string())
+        .collect::<Vec<_>>();
+    // `mod.rs` files and the roots of the targets don't add a module of their own.
+    let is_root = |name: &str| match module_path.len() {
+        1 => matches!(name, "mod" | "lib" | "main"),
+        _ => name == "mod",
+    };
+    if module_path.last().map_or(false, |name| is_root(name)) {
+        module_path.pop();
+    }
+    Some((target, module_path))
+}
+
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runnable_task() {
+        let path = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let task = runnable_task(
+            "Rust",
+            RunnableKind::Test,
+            &path(&["tests", "test_a"]),
+            Path::new("/project/src/lib.rs"),
+            Some(Path::new("/project")),
+        )
+        .unwrap();
+        assert_eq!(task.label, "cargo test -- tests::test_a --exact");
+        assert_eq!(task.cwd.as_deref(), Some("/project/src"));
+
+        let task = runnable_task(
+            "Rust",
+            RunnableKind::Test,
+            &path(&["tests", "test_a"]),
+            Path::new("/project/src/parser/lexer.rs"),
+            Some(Path::new("/project")),
+        )
+        .unwrap();
+        assert_eq!(
+            task.args,
+            ["test", "--", "parser::lexer::tests::test_a", "--exact"]
+        );
+
+        let task = runnable_task(
+            "Rust",
+            RunnableKind::Test,
+            &path(&["test_a"]),
+            Path::new("/project/src/parser/mod.rs"),
+            Some(Path::new("/project")),
+        )
+        .unwrap();
+        assert_eq!(task.args, ["test", "--", "parser::test_a", "--exact"]);
+
+        let task = runnable_task(
+            "Rust",
+            RunnableKind::Test,
+            &path(&["test_a"]),
+            Path::new("/project/tests/integration/main.rs"),
+            Some(Path::new("/project")),
+        )
+        .unwrap();
+        assert_eq!(
+            task.args,
+            ["test", "--test", "integration", "--", "test_a", "--exact"]
+        );
+
+        let task = runnable_task(
+            "Rust",
+            RunnableKind::Benchmark,
+            &path(&["bench_a"]),
+            Path::new("/project/src/bin/tool.rs"),
+            Some(Path::new("/project")),
+        )
+        .unwrap();
+        assert_eq!(
+            task.args,
+            ["bench", "--bin", "tool", "--", "bench_a", "--exact"]
+        );
+
+        let task = runnable_task(
+            "Rust",
+            RunnableKind::Main,
+            &path(&["main"]),
+            Path::new("/project/src/bin/tool.rs"),
+            Some(Path::new("/project")),
+        )
+        .unwrap();
+        assert_eq!(task.args, ["run", "--bin", "tool"]);
+
+        let task = runnable_task(
+            "Go",
+            RunnableKind::Test,
+            &path(&["TestParse"]),
+            Path::new("/project/parse_test.go"),
+            Some(Path::new("/project")),
+        )
+        .unwrap();
+        assert_eq!(task.label, "go test -run ^TestParse$");
+
+        let task = runnable_task(
+            "Python",
+            RunnableKind::Test,
+            &path(&["TestParser", "test_parse"]),
+            Path::new("/project/tests/test_parser.py"),
+            Some(Path::new("/project")),
+        )
+        .unwrap();
+        assert_eq!(task.args, ["test_parser.py::TestParser::test_parse"]);
+
+        let task = runnable_task(
+            "TypeScript",
+            RunnableKind::Test,
+            &path(&["\"parses (nested) input\""]),
+            Path::new("/project/parser.test.ts"),
+            Some(Path::new("/project")),
+        )
+        .unwrap();
+        assert_eq!(
+            task.args,
+            ["jest", "parser.test.ts", "-t", "parses \\(nested\\) input"]
+        );
+
+        // Targets are found from the crate root, even in nested `src` or `tests` directories.
+        let task = runnable_task(
+            "Rust",
+            RunnableKind::Test,
+            &path(&["test_a"]),
+            Path::new("/project/src/tests/mod.rs"),
+            Some(Path::new("/project")),
+        )
+        .unwrap();
+        assert_eq!(task.args, ["test", "--", "tests::test_a", "--exact"]);
+
+        let task = runnable_task(
+            "Rust",
+            RunnableKind::Test,
+            &path(&["test_a"]),
+            Path::new("/project/examples/demo/src/util.rs"),
+            Some(Path::new("/project")),
+        )
+        .unwrap();
+        assert_eq!(
+            task.args,
+            [
+                "test",
+                "--example",
+                "demo",
+                "--",
+                "src::util::test_a",
+                "--exact"
+            ]
+        );
+
+        let task = runnable_task(
+            "Rust",
+            RunnableKind::Test,
+            &path(&["test_a"]),
+            Path::new("/project/crates/tests/src/lib.rs"),
+            Some(Path::new("/project/crates/tests")),
+        )
+        .unwrap();
+        assert_eq!(task.args, ["test", "--", "test_a", "--exact"]);
+
+        assert_eq!(
+            runnable_task(
+                "Rust",
+                RunnableKind::Test,
+                &path(&["test_a"]),
+                Path::new("/project/src/lib.rs"),
+                None,
+            ),
+            None
+        );
+
+        assert_eq!(
+            runnable_task(
+                "Ruby",
+                RunnableKind::Test,
+                &path(&["test_a"]),
+                Path::new("/project/test.rb"),
+                Some(Path::new("/project")),
+            ),
+            None
+        );
+    }
+}
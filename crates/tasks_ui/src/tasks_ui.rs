@@ -0,0 +1,115 @@
+mod modal;
+mod runnables;
+
+use editor::Editor;
+use gpui::{actions, AppContext, ViewContext, WindowContext};
+use project::{task_inventory::TaskSourceKind, WorktreeId};
+use task::{ResolvedTask, TaskContext, TaskVariables, VariableName};
+use terminal_view::terminal_panel::TerminalPanel;
+use workspace::Workspace;
+
+pub use modal::TasksModal;
+
+actions!(task, [Spawn, Rerun]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(
+        |workspace: &mut Workspace, _: &mut ViewContext<Workspace>| {
+            workspace
+                .register_action(TasksModal::toggle)
+                .register_action(runnables::spawn_runnable)
+                .register_action(|workspace, _: &Rerun, cx| {
+                    let last_scheduled_task = workspace
+                        .project()
+                        .read(cx)
+                        .task_inventory()
+                        .read(cx)
+                        .last_scheduled_task()
+                        .cloned();
+                    match last_scheduled_task {
+                        Some((source, task)) => schedule_task(workspace, source, task, cx),
+                        None => TasksModal::toggle(workspace, &Spawn, cx),
+                    }
+                });
+        },
+    )
+    .detach();
+}
+
+/// Spawns the task in the terminal panel, remembering it as the last scheduled task.
+fn schedule_task(
+    workspace: &Workspace,
+    source: TaskSourceKind,
+    task: ResolvedTask,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let spawn_in_terminal = task.resolved.clone();
+    workspace.project().update(cx, |project, cx| {
+        project.task_inventory().update(cx, |inventory, _| {
+            inventory.task_scheduled(source, task);
+        })
+    });
+    if let Some(terminal_panel) = workspace.panel::<TerminalPanel>(cx) {
+        terminal_panel.update(cx, |terminal_panel, cx| {
+            terminal_panel
+                .spawn_task(spawn_in_terminal, cx)
+                .detach_and_log_err(cx)
+        });
+    }
+}
+
+/// Describes the active editor with the variables available to tasks, returning the
+/// worktree of the edited file, if any.
+fn task_context(workspace: &Workspace, cx: &WindowContext) -> (Option<WorktreeId>, TaskContext) {
+    let project = workspace.project().read(cx);
+    let mut task_variables = TaskVariables::default();
+    let mut worktree_id = None;
+
+    if let Some(editor) = workspace.active_item_as::<Editor>(cx) {
+        let editor = editor.read(cx);
+        let selection = editor.selections.newest::<usize>(cx);
+        let multi_buffer = editor.buffer().read(cx);
+        let selected_text = multi_buffer
+            .snapshot(cx)
+            .text_for_range(selection.range())
+            .collect::<String>();
+        if !selected_text.is_empty() {
+            task_variables.insert(VariableName::SelectedText, selected_text);
+        }
+
+        if let Some((buffer, offset, _)) = multi_buffer.point_to_buffer_offset(selection.head(), cx)
+        {
+            let buffer = buffer.read(cx);
+            let point = buffer.offset_to_point(offset);
+            task_variables.insert(VariableName::Row, (point.row + 1).to_string());
+            task_variables.insert(VariableName::Column, (point.column + 1).to_string());
+            if let Some(file) = buffer.file().and_then(|file| file.as_local()) {
+                task_variables.insert(
+                    VariableName::File,
+                    file.abs_path(cx).to_string_lossy().to_string(),
+                );
+                worktree_id = Some(WorktreeId::from_usize(file.worktree_id()));
+            }
+        }
+    }
+
+    let worktree = match worktree_id {
+        Some(worktree_id) => project.worktree_for_id(worktree_id, cx),
+        None => project.visible_worktrees(cx).next(),
+    };
+    let cwd = worktree.map(|worktree| worktree.read(cx).abs_path().to_path_buf());
+    if let Some(cwd) = &cwd {
+        task_variables.insert(
+            VariableName::WorktreeRoot,
+            cwd.to_string_lossy().to_string(),
+        );
+    }
+
+    (
+        worktree_id,
+        TaskContext {
+            cwd,
+            task_variables,
+        },
+    )
+}
@@ -0,0 +1,1124 @@
+//! Inline images, which programs print with the kitty graphics protocol, with
+//! sixel, or with iTerm2's inline image protocol.
+//!
+//! Alacritty ignores these sequences, so they're cut out of the shell's output
+//! before alacritty parses it. Each image is replaced with a hyperlink to
+//! `zed-image:<id>` on the cell where the image starts, followed by enough line
+//! feeds to make room for the image. The image is anchored to that cell, so it
+//! scrolls and reflows with the text, and is dropped from the scrollback along
+//! with it.
+
+use alacritty_terminal::{
+    event::{Event as AlacTermEvent, EventListener, WindowSize},
+    grid::Dimensions,
+    index::{Column, Line, Point as AlacPoint},
+    term::cell::Hyperlink,
+    Term,
+};
+use anyhow::{anyhow, bail, Context as _, Result};
+use collections::{BTreeMap, HashMap, HashSet};
+use flate2::read::ZlibDecoder;
+use gpui::ImageData;
+use image::{Bgra, ImageBuffer};
+use std::{
+    fs,
+    io::{Cursor, Read},
+    sync::{Arc, Mutex},
+};
+
+use crate::{TerminalSize, ZedListener};
+
+const ANCHOR_SCHEME: &str = "zed-image:";
+/// The longest image sequence kept, base64 encoded. Longer ones are dropped.
+const MAX_SEQUENCE_LEN: usize = 64 * 1024 * 1024;
+/// How many bytes of decoded images a terminal keeps.
+const MAX_IMAGES_LEN: usize = 256 * 1024 * 1024;
+const MAX_TRANSMITTED_IMAGES: usize = 64;
+const MAX_IMAGE_DIMENSION: u32 = 8192;
+const MAX_IMAGE_ROWS: usize = 500;
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+const CAN: u8 = 0x18;
+const SUB: u8 = 0x1a;
+
+/// An image in the visible part of the terminal.
+#[derive(Clone, Debug)]
+pub struct ImagePlacement {
+    pub image: Arc<ImageData>,
+    /// The cell the image's top left corner is on, which is above the visible
+    /// lines if the image is scrolled partly out of view.
+    pub point: AlacPoint,
+    pub columns: usize,
+    pub rows: usize,
+}
+
+/// Returns whether a hyperlink anchors an image, rather than linking somewhere.
+pub(crate) fn is_image_anchor(hyperlink: &Hyperlink) -> bool {
+    hyperlink.uri().starts_with(ANCHOR_SCHEME)
+}
+
+struct TerminalImage {
+    image: Arc<ImageData>,
+    columns: usize,
+    rows: usize,
+    kitty_id: Option<u32>,
+}
+
+/// The images printed in a terminal, shared between its IO thread and the
+/// terminal.
+#[derive(Default)]
+pub(crate) struct TerminalImages {
+    /// The images anchored in the grid, oldest first.
+    images: BTreeMap<usize, TerminalImage>,
+    next_id: usize,
+    len: usize,
+    /// Images sent with the kitty protocol to be displayed later.
+    transmitted: HashMap<u32, Arc<ImageData>>,
+    /// How many images were left after the last pruning.
+    pruned_count: usize,
+}
+
+impl TerminalImages {
+    fn insert(&mut self, image: TerminalImage) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.len += image.image.as_bytes().len();
+        self.images.insert(id, image);
+        while self.len > MAX_IMAGES_LEN {
+            let Some((_, oldest)) = self.images.pop_first() else {
+                break;
+            };
+            self.len -= oldest.image.as_bytes().len();
+        }
+        id
+    }
+
+    fn transmit(&mut self, kitty_id: u32, image: Arc<ImageData>) {
+        if self.transmitted.len() >= MAX_TRANSMITTED_IMAGES {
+            self.transmitted.clear();
+        }
+        self.transmitted.insert(kitty_id, image);
+    }
+
+    /// Removes the images with the given kitty ID, or all of them.
+    fn delete(&mut self, kitty_id: Option<u32>) {
+        self.images
+            .retain(|_, image| kitty_id.is_some() && image.kitty_id != kitty_id);
+        match kitty_id {
+            Some(kitty_id) => {
+                self.transmitted.remove(&kitty_id);
+            }
+            None => self.transmitted.clear(),
+        }
+        self.len = self
+            .images
+            .values()
+            .map(|image| image.image.as_bytes().len())
+            .sum();
+    }
+
+    /// Returns the images anchored on the visible lines, or on lines above
+    /// them that images reach down from.
+    pub(crate) fn visible(&self, term: &Term<ZedListener>) -> Vec<ImagePlacement> {
+        let Some(max_rows) = self.images.values().map(|image| image.rows).max() else {
+            return Vec::new();
+        };
+        let grid = term.grid();
+        let top_line = Line(-(grid.display_offset() as i32));
+        let bottom_line = top_line + term.screen_lines() - 1;
+        let first_line = (top_line - (max_rows - 1)).max(grid.topmost_line());
+
+        let mut placements = Vec::new();
+        for line in first_line.0..=bottom_line.0 {
+            let line = Line(line);
+            for column in 0..grid.columns() {
+                let point = AlacPoint::new(line, Column(column));
+                let Some(image) =
+                    anchored_image(&grid[point].hyperlink()).and_then(|id| self.images.get(&id))
+                else {
+                    continue;
+                };
+                if line + image.rows > top_line {
+                    placements.push(ImagePlacement {
+                        image: image.image.clone(),
+                        point,
+                        columns: image.columns,
+                        rows: image.rows,
+                    });
+                }
+            }
+        }
+        placements
+    }
+
+    /// Drops the images that are no longer anchored anywhere in the grid,
+    /// once enough images were added since this was last done.
+    pub(crate) fn prune(&mut self, term: &Term<ZedListener>) {
+        if self.images.len() <= (2 * self.pruned_count).max(16) {
+            return;
+        }
+        let grid = term.grid();
+        let mut anchored = HashSet::default();
+        for line in grid.topmost_line().0..=grid.bottommost_line().0 {
+            for column in 0..grid.columns() {
+                let point = AlacPoint::new(Line(line), Column(column));
+                anchored.extend(anchored_image(&grid[point].hyperlink()));
+            }
+        }
+        self.images.retain(|id, _| anchored.contains(id));
+        self.len = self
+            .images
+            .values()
+            .map(|image| image.image.as_bytes().len())
+            .sum();
+        self.pruned_count = self.images.len();
+    }
+}
+
+fn anchored_image(hyperlink: &Option<Hyperlink>) -> Option<usize> {
+    hyperlink
+        .as_ref()?
+        .uri()
+        .strip_prefix(ANCHOR_SCHEME)?
+        .parse()
+        .ok()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Protocol {
+    /// `ESC _ G <control data> ; <payload> ESC \`
+    Kitty,
+    /// `ESC P <parameters> q <sixel data> ESC \`
+    Sixel,
+    /// `ESC ] 1337 ; File = <arguments> : <payload> BEL`
+    ITerm,
+}
+
+impl Protocol {
+    /// What comes after the escape sequence's introducer.
+    fn prefix(self) -> &'static [u8] {
+        match self {
+            Protocol::Kitty => b"G",
+            Protocol::Sixel => b"",
+            Protocol::ITerm => b"1337;File=",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum FilterState {
+    #[default]
+    Ground,
+    Escape,
+    /// In an introducer that might start an image.
+    Prefix(Protocol),
+    SixelParameters,
+    Payload(Protocol),
+    PayloadEscape(Protocol),
+    /// In an image sequence that is too long to keep.
+    Discard,
+    DiscardEscape,
+}
+
+/// Cuts the images out of a shell's output, replacing each with its anchor.
+pub(crate) struct ImageFilter {
+    state: FilterState,
+    /// Output held back until it's known whether it starts an image.
+    held: Vec<u8>,
+    payload: Vec<u8>,
+    /// The first command of a kitty image sent in chunks, and the data so far.
+    kitty_chunks: Option<(KittyCommand, Vec<u8>)>,
+    images: Arc<Mutex<TerminalImages>>,
+    listener: ZedListener,
+    window_size: WindowSize,
+}
+
+impl ImageFilter {
+    pub(crate) fn new(images: Arc<Mutex<TerminalImages>>, listener: ZedListener) -> Self {
+        Self {
+            state: FilterState::Ground,
+            held: Vec::new(),
+            payload: Vec::new(),
+            kitty_chunks: None,
+            images,
+            listener,
+            window_size: TerminalSize::default().into(),
+        }
+    }
+
+    pub(crate) fn resize(&mut self, window_size: WindowSize) {
+        self.window_size = window_size;
+    }
+
+    /// Appends the output to `filtered`, with images replaced by anchors.
+    pub(crate) fn filter(&mut self, output: &[u8], filtered: &mut Vec<u8>) {
+        for byte in output {
+            self.advance(*byte, filtered);
+        }
+    }
+
+    fn advance(&mut self, byte: u8, filtered: &mut Vec<u8>) {
+        match self.state {
+            FilterState::Ground if byte == ESC => {
+                self.held.push(byte);
+                self.state = FilterState::Escape;
+            }
+            FilterState::Ground => filtered.push(byte),
+            FilterState::Escape => {
+                self.state = match byte {
+                    b'_' => FilterState::Prefix(Protocol::Kitty),
+                    b']' => FilterState::Prefix(Protocol::ITerm),
+                    b'P' => FilterState::SixelParameters,
+                    _ => return self.flush(byte, filtered),
+                };
+                self.held.push(byte);
+            }
+            FilterState::Prefix(protocol) => {
+                let prefix = protocol.prefix();
+                let matched = self.held.len() - 2;
+                if prefix.get(matched) != Some(&byte) {
+                    return self.flush(byte, filtered);
+                }
+                self.held.push(byte);
+                if matched + 1 == prefix.len() {
+                    self.held.clear();
+                    self.payload.clear();
+                    self.state = FilterState::Payload(protocol);
+                }
+            }
+            FilterState::SixelParameters => match byte {
+                b'0'..=b'9' | b';' if self.held.len() < 32 => self.held.push(byte),
+                b'q' => {
+                    // The parameters only choose the background and the
+                    // pixels' aspect ratio, which is always 1:1 nowadays.
+                    self.held.clear();
+                    self.payload.clear();
+                    self.state = FilterState::Payload(Protocol::Sixel);
+                }
+                _ => self.flush(byte, filtered),
+            },
+            FilterState::Payload(protocol) => match byte {
+                ESC => self.state = FilterState::PayloadEscape(protocol),
+                BEL if protocol == Protocol::ITerm => {
+                    self.state = FilterState::Ground;
+                    self.finish(protocol, filtered);
+                }
+                CAN | SUB => self.state = FilterState::Ground,
+                _ if self.payload.len() >= MAX_SEQUENCE_LEN => {
+                    self.payload = Vec::new();
+                    self.state = FilterState::Discard;
+                }
+                _ => self.payload.push(byte),
+            },
+            FilterState::PayloadEscape(protocol) => {
+                if byte == b'\\' {
+                    self.state = FilterState::Ground;
+                    self.finish(protocol, filtered);
+                } else {
+                    // The escape cancels the image and starts another sequence.
+                    self.payload.clear();
+                    self.held.push(ESC);
+                    self.state = FilterState::Escape;
+                    self.advance(byte, filtered);
+                }
+            }
+            FilterState::Discard => match byte {
+                ESC => self.state = FilterState::DiscardEscape,
+                BEL | CAN | SUB => self.state = FilterState::Ground,
+                _ => {}
+            },
+            FilterState::DiscardEscape => {
+                if byte == b'\\' {
+                    self.state = FilterState::Ground;
+                } else {
+                    self.held.push(ESC);
+                    self.state = FilterState::Escape;
+                    self.advance(byte, filtered);
+                }
+            }
+        }
+    }
+
+    /// Passes on the output held back, which turned out not to be an image,
+    /// followed by the byte that showed it.
+    fn flush(&mut self, byte: u8, filtered: &mut Vec<u8>) {
+        filtered.append(&mut self.held);
+        self.state = FilterState::Ground;
+        self.advance(byte, filtered);
+    }
+
+    fn finish(&mut self, protocol: Protocol, filtered: &mut Vec<u8>) {
+        let payload = std::mem::take(&mut self.payload);
+        let image = match protocol {
+            Protocol::Kitty => self.handle_kitty(&payload),
+            Protocol::Sixel => decode_sixel(&payload).map(|image| {
+                let size = image.size();
+                let (columns, rows) = self.cells(size.width.into(), size.height.into());
+                Some((Arc::new(image), columns, rows, None))
+            }),
+            Protocol::ITerm => self.decode_iterm(&payload),
+        };
+        match image {
+            Ok(Some((image, columns, rows, kitty_id))) => {
+                let rows = rows.min(MAX_IMAGE_ROWS);
+                let id = self.images.lock().unwrap().insert(TerminalImage {
+                    image,
+                    columns,
+                    rows,
+                    kitty_id,
+                });
+                write_anchor(id, columns, rows, filtered);
+            }
+            Ok(None) => {}
+            Err(error) => log::debug!("failed to display an image in the terminal: {error:?}"),
+        }
+    }
+
+    /// The cells an image takes up, drawn at its size in pixels but scaled
+    /// down to fit the terminal's width.
+    fn cells(&self, width: u32, height: u32) -> (usize, usize) {
+        self.cells_for_size(width as f32, height as f32)
+    }
+
+    fn cells_for_size(&self, width: f32, height: f32) -> (usize, usize) {
+        let cell_width = self.window_size.cell_width.max(1) as f32;
+        let cell_height = self.window_size.cell_height.max(1) as f32;
+        let max_width = self.window_size.num_cols.max(1) as f32 * cell_width;
+        let scale = (max_width / width.max(1.)).min(1.);
+        (
+            ((width * scale / cell_width).ceil() as usize).max(1),
+            ((height * scale / cell_height).ceil() as usize).max(1),
+        )
+    }
+
+    fn handle_kitty(
+        &mut self,
+        payload: &[u8],
+    ) -> Result<Option<(Arc<ImageData>, usize, usize, Option<u32>)>> {
+        let (control, data) = match payload.iter().position(|byte| *byte == b';') {
+            Some(ix) => (&payload[..ix], &payload[ix + 1..]),
+            None => (payload, &[][..]),
+        };
+        let command = KittyCommand::parse(control);
+
+        // Every chunk but the last is sent with `m=1`, and only the first has
+        // the image's keys.
+        let (command, data) = match self.kitty_chunks.take() {
+            Some((first, mut chunks)) => {
+                chunks.extend_from_slice(data);
+                if command.more {
+                    self.kitty_chunks = Some((first, chunks));
+                    return Ok(None);
+                }
+                (first, chunks)
+            }
+            None if command.more => {
+                self.kitty_chunks = Some((command, data.to_vec()));
+                return Ok(None);
+            }
+            None => (command, data.to_vec()),
+        };
+
+        let result = match command.action {
+            b'q' => command.decode(&data).map(|_| None),
+            b't' => command.decode(&data).map(|image| {
+                if let Some(image_id) = command.image_id {
+                    self.images
+                        .lock()
+                        .unwrap()
+                        .transmit(image_id, Arc::new(image));
+                }
+                None
+            }),
+            b'T' => command.decode(&data).map(|image| Some(Arc::new(image))),
+            b'p' => command
+                .image_id
+                .and_then(|image_id| {
+                    self.images
+                        .lock()
+                        .unwrap()
+                        .transmitted
+                        .get(&image_id)
+                        .cloned()
+                })
+                .map(Some)
+                .ok_or_else(|| anyhow!("ENOENT:no such image")),
+            b'd' => {
+                let kitty_id = match command.delete {
+                    b'i' | b'I' => command.image_id,
+                    _ => None,
+                };
+                self.images.lock().unwrap().delete(kitty_id);
+                return Ok(None);
+            }
+            _ => return Ok(None),
+        };
+
+        self.respond_to_kitty(&command, &result);
+        let Some(image) = result? else {
+            return Ok(None);
+        };
+        let size = image.size();
+        let (width, height) = (u32::from(size.width), u32::from(size.height));
+        let (columns, rows) = match (command.columns, command.rows) {
+            (Some(columns), Some(rows)) => (columns as usize, rows as usize),
+            (Some(columns), None) => {
+                let width_px = columns as f32 * self.window_size.cell_width as f32;
+                self.cells_for_size(width_px, width_px * height as f32 / width.max(1) as f32)
+            }
+            (None, Some(rows)) => {
+                let height_px = rows as f32 * self.window_size.cell_height as f32;
+                self.cells_for_size(height_px * width as f32 / height.max(1) as f32, height_px)
+            }
+            (None, None) => self.cells(width, height),
+        };
+        Ok(Some((image, columns.max(1), rows.max(1), command.image_id)))
+    }
+
+    /// Tells the program whether its command worked, unless it asked not to
+    /// be told or didn't give the image an ID to refer to it by.
+    fn respond_to_kitty<T>(&self, command: &KittyCommand, result: &Result<T>) {
+        let Some(image_id) = command.image_id else {
+            return;
+        };
+        let message = match result {
+            Ok(_) if command.quiet == 0 => "OK".to_string(),
+            Err(error) if command.quiet < 2 => {
+                let error = error.to_string();
+                if error.contains(':') {
+                    error
+                } else {
+                    format!("EINVAL:{error}")
+                }
+            }
+            _ => return,
+        };
+        self.listener.send_event(AlacTermEvent::PtyWrite(format!(
+            "\x1b_Gi={image_id};{message}\x1b\\"
+        )));
+    }
+
+    fn decode_iterm(
+        &self,
+        payload: &[u8],
+    ) -> Result<Option<(Arc<ImageData>, usize, usize, Option<u32>)>> {
+        let ix = payload
+            .iter()
+            .position(|byte| *byte == b':')
+            .context("missing image data")?;
+        let arguments = String::from_utf8_lossy(&payload[..ix]);
+        let mut width = None;
+        let mut height = None;
+        let mut inline = false;
+        let mut preserve_aspect_ratio = true;
+        for argument in arguments.split(';') {
+            match argument.split_once('=') {
+                Some(("width", value)) => width = Some(value.to_string()),
+                Some(("height", value)) => height = Some(value.to_string()),
+                Some(("inline", value)) => inline = value == "1",
+                Some(("preserveAspectRatio", value)) => preserve_aspect_ratio = value != "0",
+                _ => {}
+            }
+        }
+        // Files that aren't inline are downloads, which the terminal doesn't do.
+        if !inline {
+            return Ok(None);
+        }
+
+        let data = base64::decode(&payload[ix + 1..])?;
+        let image = decode_encoded(&data)?;
+        let size = image.size();
+        let natural_width = u32::from(size.width) as f32;
+        let natural_height = u32::from(size.height) as f32;
+        let cell_width = self.window_size.cell_width as f32;
+        let cell_height = self.window_size.cell_height as f32;
+        let terminal_width = self.window_size.num_cols as f32 * cell_width;
+        let terminal_height = self.window_size.num_lines as f32 * cell_height;
+        let width = width.and_then(|width| iterm_dimension(&width, cell_width, terminal_width));
+        let height =
+            height.and_then(|height| iterm_dimension(&height, cell_height, terminal_height));
+
+        let aspect_ratio = natural_width / natural_height.max(1.);
+        let (width, height) = match (width, height) {
+            (None, None) => (natural_width, natural_height),
+            (Some(width), None) => (width, width / aspect_ratio),
+            (None, Some(height)) => (height * aspect_ratio, height),
+            (Some(width), Some(height)) if preserve_aspect_ratio => {
+                let scale = (width / natural_width).min(height / natural_height.max(1.));
+                (natural_width * scale, natural_height * scale)
+            }
+            (Some(width), Some(height)) => (width, height),
+        };
+        let (columns, rows) = self.cells_for_size(width, height);
+        Ok(Some((Arc::new(image), columns, rows, None)))
+    }
+}
+
+/// Converts an iTerm2 width or height to pixels: `N` cells, `Npx`, `N%` of
+/// the terminal, or `auto`.
+fn iterm_dimension(value: &str, cell: f32, terminal: f32) -> Option<f32> {
+    if let Some(pixels) = value.strip_suffix("px") {
+        pixels.parse().ok()
+    } else if let Some(percent) = value.strip_suffix('%') {
+        Some(percent.parse::<f32>().ok()? / 100. * terminal)
+    } else {
+        Some(value.parse::<f32>().ok()? * cell)
+    }
+}
+
+/// Replaces an image with a hyperlinked cell for it to be anchored to, and
+/// moves the cursor past the image like kitty does: to the column after the
+/// image, on its last line.
+fn write_anchor(id: usize, columns: usize, rows: usize, filtered: &mut Vec<u8>) {
+    filtered.extend_from_slice(
+        format!("\x1b]8;id=zed-image-{id};{ANCHOR_SCHEME}{id}\x1b\\ \x1b]8;;\x1b\\\x08").as_bytes(),
+    );
+    filtered.extend(std::iter::repeat(b'\n').take(rows - 1));
+    filtered.extend_from_slice(format!("\x1b[{columns}C").as_bytes());
+}
+
+/// A kitty graphics command's keys.
+#[derive(Debug)]
+struct KittyCommand {
+    action: u8,
+    format: u32,
+    medium: u8,
+    width: u32,
+    height: u32,
+    compressed: bool,
+    more: bool,
+    image_id: Option<u32>,
+    columns: Option<u32>,
+    rows: Option<u32>,
+    quiet: u32,
+    delete: u8,
+}
+
+impl KittyCommand {
+    fn parse(control: &[u8]) -> Self {
+        let mut command = Self {
+            action: b't',
+            format: 32,
+            medium: b'd',
+            width: 0,
+            height: 0,
+            compressed: false,
+            more: false,
+            image_id: None,
+            columns: None,
+            rows: None,
+            quiet: 0,
+            delete: b'a',
+        };
+        for key_value in control.split(|byte| *byte == b',') {
+            let &[key, b'=', ref value @ ..] = key_value else {
+                continue;
+            };
+            let number = std::str::from_utf8(value)
+                .ok()
+                .and_then(|value| value.parse::<u32>().ok());
+            let character = value.first().copied();
+            match key {
+                b'a' => command.action = character.unwrap_or(b't'),
+                b'f' => command.format = number.unwrap_or(32),
+                b't' => command.medium = character.unwrap_or(b'd'),
+                b's' => command.width = number.unwrap_or(0),
+                b'v' => command.height = number.unwrap_or(0),
+                b'o' => command.compressed = character == Some(b'z'),
+                b'm' => command.more = number == Some(1),
+                b'i' => command.image_id = number,
+                b'c' => command.columns = number.filter(|columns| *columns > 0),
+                b'r' => command.rows = number.filter(|rows| *rows > 0),
+                b'q' => command.quiet = number.unwrap_or(0),
+                b'd' => command.delete = character.unwrap_or(b'a'),
+                _ => {}
+            }
+        }
+        command
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<ImageData> {
+        let mut data = base64::decode(data)?;
+        match self.medium {
+            b'd' => {}
+            b't' => data = read_temporary_file(&String::from_utf8(data)?)?,
+            // Any output, including a file printed from a remote host, could
+            // otherwise read local files, so only temporary files are accepted.
+            _ => bail!("EINVAL:unsupported transmission medium"),
+        }
+
+        let bytes_per_pixel = match self.format {
+            100 => None,
+            24 => Some(3),
+            32 => Some(4),
+            format => bail!("EINVAL:unsupported format {format}"),
+        };
+        let (width, height) = (self.width, self.height);
+        if bytes_per_pixel.is_some()
+            && (width == 0
+                || height == 0
+                || width > MAX_IMAGE_DIMENSION
+                || height > MAX_IMAGE_DIMENSION)
+        {
+            bail!("EINVAL:invalid image size {width}x{height}");
+        }
+        if self.compressed {
+            // Raw pixels past the image's size are ignored, so don't inflate them.
+            let max_len = bytes_per_pixel.map_or(MAX_SEQUENCE_LEN, |bytes_per_pixel| {
+                (width * height) as usize * bytes_per_pixel
+            });
+            let mut decompressed = Vec::new();
+            ZlibDecoder::new(data.as_slice())
+                .take(max_len as u64 + 1)
+                .read_to_end(&mut decompressed)?;
+            if decompressed.len() > max_len && bytes_per_pixel.is_none() {
+                bail!("EFBIG:decompressed image is too large");
+            }
+            data = decompressed;
+        }
+
+        let Some(bytes_per_pixel) = bytes_per_pixel else {
+            return decode_encoded(&data);
+        };
+        let pixels = data
+            .get(..(width * height) as usize * bytes_per_pixel)
+            .ok_or_else(|| anyhow!("ENODATA:insufficient image data"))?;
+        let bgra = pixels
+            .chunks_exact(bytes_per_pixel)
+            .flat_map(|pixel| {
+                [
+                    pixel[2],
+                    pixel[1],
+                    pixel[0],
+                    pixel.get(3).copied().unwrap_or(255),
+                ]
+            })
+            .collect();
+        Ok(ImageData::new(
+            ImageBuffer::<Bgra<u8>, _>::from_raw(width, height, bgra)
+                .context("invalid image data")?,
+        ))
+    }
+}
+
+/// Reads a file that a program wrote an image to for the terminal, and deletes
+/// it. Like kitty, only files in the temporary directory whose name marks them
+/// as meant for the terminal are read.
+fn read_temporary_file(path: &str) -> Result<Vec<u8>> {
+    let path = fs::canonicalize(path).context("EBADF:no such file")?;
+    let temp_dir = fs::canonicalize(std::env::temp_dir())?;
+    let is_temporary = path.starts_with(&temp_dir)
+        && path.file_name().map_or(false, |name| {
+            name.to_string_lossy().contains("tty-graphics-protocol")
+        });
+    if !is_temporary || !path.is_file() {
+        bail!("EPERM:not a temporary file");
+    }
+
+    let mut data = Vec::new();
+    fs::File::open(&path)?
+        .take(MAX_SEQUENCE_LEN as u64 + 1)
+        .read_to_end(&mut data)?;
+    fs::remove_file(&path).ok();
+    if data.len() > MAX_SEQUENCE_LEN {
+        bail!("EFBIG:image file is too large");
+    }
+    Ok(data)
+}
+
+/// Decodes an image file's contents, checking its size before its pixels are
+/// allocated.
+fn decode_encoded(data: &[u8]) -> Result<ImageData> {
+    let (width, height) = image::io::Reader::new(Cursor::new(data))
+        .with_guessed_format()?
+        .into_dimensions()?;
+    if width == 0 || height == 0 || width > MAX_IMAGE_DIMENSION || height > MAX_IMAGE_DIMENSION {
+        bail!("EINVAL:invalid image size {width}x{height}");
+    }
+    ImageData::from_encoded(data)
+}
+
+/// The colors sixel images start out with, as VT340 terminals had them, in
+/// percent.
+const SIXEL_PALETTE: [[u32; 3]; 16] = [
+    [0, 0, 0],
+    [20, 20, 80],
+    [80, 13, 13],
+    [20, 80, 20],
+    [80, 20, 80],
+    [20, 80, 80],
+    [80, 80, 20],
+    [53, 53, 53],
+    [26, 26, 26],
+    [33, 33, 60],
+    [60, 26, 26],
+    [33, 60, 33],
+    [60, 33, 60],
+    [33, 60, 60],
+    [60, 60, 33],
+    [80, 80, 80],
+];
+
+/// Decodes sixel data, leaving the pixels no sixel sets transparent.
+fn decode_sixel(data: &[u8]) -> Result<ImageData> {
+    let mut palette = [[0u8, 0, 0, 255]; 256];
+    for (color, [red, green, blue]) in palette.iter_mut().zip(SIXEL_PALETTE) {
+        *color = [percent(red), percent(green), percent(blue), 255];
+    }
+    let mut color = palette[0];
+
+    // Pixels as BGRA, with rows as wide as the widest sixel so far.
+    let mut pixels = Vec::<u8>::new();
+    let mut width = 0;
+    let mut height = 0;
+    let (mut x, mut y) = (0, 0);
+    let mut ix = 0;
+    while ix < data.len() {
+        let byte = data[ix];
+        ix += 1;
+        match byte {
+            // Raster attributes, declaring the image's size.
+            b'"' => {
+                let parameters = parse_numbers(data, &mut ix);
+                if let [_, _, declared_width, declared_height, ..] = parameters[..] {
+                    let declared_width = declared_width.min(MAX_IMAGE_DIMENSION) as usize;
+                    let declared_height = declared_height.min(MAX_IMAGE_DIMENSION) as usize;
+                    grow(
+                        &mut pixels,
+                        &mut width,
+                        &mut height,
+                        declared_width,
+                        declared_height,
+                    );
+                }
+            }
+            // Selecting a color, and defining it if it comes with a color space.
+            b'#' => {
+                let parameters = parse_numbers(data, &mut ix);
+                let Some(&index) = parameters.first() else {
+                    continue;
+                };
+                let index = index as usize % palette.len();
+                if let [_, space, a, b, c] = parameters[..] {
+                    palette[index] = match space {
+                        1 => hls_to_rgb(a, b, c),
+                        _ => [percent(a), percent(b), percent(c), 255],
+                    };
+                }
+                color = palette[index];
+            }
+            b'!' => {
+                let count = parse_numbers(data, &mut ix).first().copied().unwrap_or(1);
+                if let Some(&sixel @ 0x3f..=0x7e) = data.get(ix) {
+                    ix += 1;
+                    for _ in 0..count.min(MAX_IMAGE_DIMENSION) {
+                        put_sixel(&mut pixels, &mut width, &mut height, x, y, sixel, color);
+                        x += 1;
+                    }
+                }
+            }
+            b'$' => x = 0,
+            b'-' => {
+                x = 0;
+                y += 6;
+            }
+            0x3f..=0x7e => {
+                put_sixel(&mut pixels, &mut width, &mut height, x, y, byte, color);
+                x += 1;
+            }
+            _ => {}
+        }
+    }
+
+    if width == 0 || height == 0 {
+        bail!("empty sixel image");
+    }
+    let image = ImageBuffer::<Bgra<u8>, _>::from_raw(width as u32, height as u32, pixels)
+        .context("invalid sixel image")?;
+    Ok(ImageData::new(image))
+}
+
+fn percent(value: u32) -> u8 {
+    (value.min(100) * 255 / 100) as u8
+}
+
+/// Converts a sixel HLS color, in which hue 0 is blue, to RGBA.
+fn hls_to_rgb(hue: u32, lightness: u32, saturation: u32) -> [u8; 4] {
+    let hue = ((hue + 240) % 360) as f32 / 360.;
+    let lightness = lightness.min(100) as f32 / 100.;
+    let saturation = saturation.min(100) as f32 / 100.;
+    let q = if lightness < 0.5 {
+        lightness * (1. + saturation)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let p = 2. * lightness - q;
+    let channel = |t: f32| {
+        let t = t.rem_euclid(1.);
+        let value = if t < 1. / 6. {
+            p + (q - p) * 6. * t
+        } else if t < 1. / 2. {
+            q
+        } else if t < 2. / 3. {
+            p + (q - p) * (2. / 3. - t) * 6.
+        } else {
+            p
+        };
+        (value * 255.).round() as u8
+    };
+    [
+        channel(hue + 1. / 3.),
+        channel(hue),
+        channel(hue - 1. / 3.),
+        255,
+    ]
+}
+
+fn parse_numbers(data: &[u8], ix: &mut usize) -> Vec<u32> {
+    let mut numbers = vec![0u32];
+    while let Some(byte) = data.get(*ix) {
+        match byte {
+            b'0'..=b'9' => {
+                let number = numbers.last_mut().unwrap();
+                *number = number
+                    .saturating_mul(10)
+                    .saturating_add((byte - b'0') as u32);
+            }
+            b';' => numbers.push(0),
+            _ => break,
+        }
+        *ix += 1;
+    }
+    numbers
+}
+
+/// Sets the pixels of a sixel: a column of six pixels, one per bit.
+fn put_sixel(
+    pixels: &mut Vec<u8>,
+    width: &mut usize,
+    height: &mut usize,
+    x: usize,
+    y: usize,
+    sixel: u8,
+    color: [u8; 4],
+) {
+    let bits = sixel - 0x3f;
+    if bits == 0 || x >= MAX_IMAGE_DIMENSION as usize || y + 6 > MAX_IMAGE_DIMENSION as usize {
+        return;
+    }
+    grow(pixels, width, height, x + 1, y + 6);
+    let [red, green, blue, alpha] = color;
+    for bit in 0..6 {
+        if bits & (1 << bit) != 0 {
+            let offset = ((y + bit) * *width + x) * 4;
+            pixels[offset..offset + 4].copy_from_slice(&[blue, green, red, alpha]);
+        }
+    }
+}
+
+/// Makes the image at least the given size, keeping its pixels where they are.
+fn grow(
+    pixels: &mut Vec<u8>,
+    width: &mut usize,
+    height: &mut usize,
+    min_width: usize,
+    min_height: usize,
+) {
+    if min_width > *width {
+        let new_width = min_width.max(*width * 2).min(MAX_IMAGE_DIMENSION as usize);
+        let mut grown = vec![0; new_width * *height * 4];
+        if *width > 0 {
+            for (row, new_row) in pixels
+                .chunks_exact(*width * 4)
+                .zip(grown.chunks_exact_mut(new_width * 4))
+            {
+                new_row[..row.len()].copy_from_slice(row);
+            }
+        }
+        *pixels = grown;
+        *width = new_width;
+    }
+    if min_height > *height {
+        *height = min_height;
+        pixels.resize(*width * *height * 4, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::channel::mpsc::unbounded;
+    use gpui::{size, DevicePixels};
+
+    fn pixels(value: u32) -> DevicePixels {
+        DevicePixels::from(value)
+    }
+
+    fn filter(output: &[&[u8]]) -> (Vec<u8>, ImageFilter) {
+        let (events_tx, _) = unbounded();
+        let mut filter = ImageFilter::new(Default::default(), ZedListener(events_tx));
+        filter.resize(WindowSize {
+            num_lines: 24,
+            num_cols: 80,
+            cell_width: 10,
+            cell_height: 20,
+        });
+        let mut filtered = Vec::new();
+        for chunk in output {
+            filter.filter(chunk, &mut filtered);
+        }
+        (filtered, filter)
+    }
+
+    fn image_sizes(filter: &ImageFilter) -> Vec<(Size, usize, usize)> {
+        filter
+            .images
+            .lock()
+            .unwrap()
+            .images
+            .values()
+            .map(|image| (image.image.size(), image.columns, image.rows))
+            .collect()
+    }
+
+    type Size = gpui::Size<DevicePixels>;
+
+    #[test]
+    fn test_filter_passes_other_output() {
+        let output: &[u8] =
+            b"\x1b[31mred\x1b]133;A\x07\x1b_Xapc\x1b\\\x1bP1$r\x1b\\\x1b]1337;SetMark\x07";
+        let (filtered, _) = filter(&[output]);
+        assert_eq!(filtered, output);
+    }
+
+    #[test]
+    fn test_kitty_images() {
+        // A 2x1 RGB image, sent in two chunks split within the escape sequence.
+        let data = base64::encode([255, 0, 0, 0, 255, 0]);
+        let (first, last) = data.split_at(4);
+        let (filtered, filter) = filter(&[
+            format!("a\x1b_Ga=T,f=24,s=2,v=1,m=1;{first}\x1b\\\x1b_").as_bytes(),
+            format!("Gm=0;{last}\x1b\\b").as_bytes(),
+        ]);
+        assert_eq!(
+            String::from_utf8(filtered).unwrap(),
+            "a\x1b]8;id=zed-image-0;zed-image:0\x1b\\ \x1b]8;;\x1b\\\x08\x1b[1Cb"
+        );
+        assert_eq!(image_sizes(&filter), [(size(pixels(2), pixels(1)), 1, 1)]);
+        let images = filter.images.lock().unwrap();
+        let pixels = images.images[&0].image.as_bytes();
+        assert_eq!(pixels, [0, 0, 255, 255, 0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn test_kitty_file_media() {
+        let pixel = [255, 0, 0, 255];
+        let decode = |control: &str, data: &[u8]| {
+            KittyCommand::parse(control.as_bytes()).decode(base64::encode(data).as_bytes())
+        };
+
+        // Temporary files meant for the terminal are read, then deleted.
+        let path = std::env::temp_dir().join("tty-graphics-protocol-zed-test");
+        fs::write(&path, pixel).unwrap();
+        let image = decode("t=t,s=1,v=1", path.to_string_lossy().as_bytes()).unwrap();
+        assert_eq!(image.as_bytes(), [0, 0, 255, 255]);
+        assert!(!path.exists());
+
+        // Other files are neither read nor deleted.
+        let path = std::env::temp_dir().join("zed-terminal-image-test");
+        fs::write(&path, pixel).unwrap();
+        let error = decode("t=t,s=1,v=1", path.to_string_lossy().as_bytes()).unwrap_err();
+        assert!(error.to_string().starts_with("EPERM:"));
+        assert!(path.exists());
+        let error = decode("t=f,s=1,v=1", path.to_string_lossy().as_bytes()).unwrap_err();
+        assert!(error.to_string().starts_with("EINVAL:"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_kitty_oversized_images() {
+        use flate2::{write::ZlibEncoder, Compression};
+        use std::io::Write;
+
+        // Compressed pixels are only inflated up to the image's size.
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&vec![0; 1024 * 1024]).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let image = KittyCommand::parse(b"o=z,s=2,v=2")
+            .decode(base64::encode(&compressed).as_bytes())
+            .unwrap();
+        assert_eq!(image.as_bytes().len(), 16);
+
+        // PNGs are rejected by the size in their header, before being decoded.
+        let mut png = Vec::new();
+        image::png::PngEncoder::new(&mut png)
+            .encode(&[0; 4], 1, 1, image::ColorType::Rgba8)
+            .unwrap();
+        // Patch the width in the IHDR chunk, and its checksum.
+        png[16..20].copy_from_slice(&100_000u32.to_be_bytes());
+        let mut crc = flate2::Crc::new();
+        crc.update(&png[12..29]);
+        png[29..33].copy_from_slice(&crc.sum().to_be_bytes());
+        let error = KittyCommand::parse(b"f=100")
+            .decode(base64::encode(&png).as_bytes())
+            .unwrap_err();
+        assert!(error.to_string().starts_with("EINVAL:invalid image size"));
+    }
+
+    #[test]
+    fn test_kitty_responses() {
+        let (events_tx, mut events_rx) = unbounded();
+        let mut filter = ImageFilter::new(Default::default(), ZedListener(events_tx));
+        let mut filtered = Vec::new();
+        let pixel = base64::encode([0, 0, 0, 0]);
+        filter.filter(
+            format!("\x1b_Gi=31,s=1,v=1,a=q;{pixel}\x1b\\\x1b_Gi=32,a=p\x1b\\").as_bytes(),
+            &mut filtered,
+        );
+        assert!(filtered.is_empty());
+
+        let mut responses = Vec::new();
+        while let Ok(Some(AlacTermEvent::PtyWrite(response))) = events_rx.try_next() {
+            responses.push(response);
+        }
+        assert_eq!(
+            responses,
+            [
+                "\x1b_Gi=31;OK\x1b\\",
+                "\x1b_Gi=32;ENOENT:no such image\x1b\\"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sixel_images() {
+        // A red 3x6 column, then a 2x6 column in a new color below it.
+        let (filtered, filter) =
+            filter(&[b"\x1bP0;1;0q\"1;1;3;6#1;2;100;0;0#1!3~-#2;2;0;0;100~~\x1b\\"]);
+        assert_eq!(
+            String::from_utf8(filtered).unwrap(),
+            "\x1b]8;id=zed-image-0;zed-image:0\x1b\\ \x1b]8;;\x1b\\\x08\x1b[1C"
+        );
+        assert_eq!(image_sizes(&filter), [(size(pixels(3), pixels(12)), 1, 1)]);
+        let images = filter.images.lock().unwrap();
+        let pixels = images.images[&0].image.as_bytes();
+        assert_eq!(&pixels[..4], [0, 0, 255, 255]);
+        assert_eq!(&pixels[(6 * 3 + 1) * 4..(6 * 3 + 2) * 4], [255, 0, 0, 255]);
+        // Pixels no sixel set are transparent.
+        assert_eq!(&pixels[(6 * 3 + 2) * 4..(6 * 3 + 3) * 4], [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_iterm_sizes() {
+        let (_, filter) = filter(&[]);
+        assert_eq!(iterm_dimension("3", 10., 800.), Some(30.));
+        assert_eq!(iterm_dimension("25px", 10., 800.), Some(25.));
+        assert_eq!(iterm_dimension("50%", 10., 800.), Some(400.));
+        assert_eq!(iterm_dimension("auto", 10., 800.), None);
+
+        // Images are scaled down to fit the terminal's width.
+        assert_eq!(filter.cells_for_size(1600., 400.), (80, 10));
+        assert_eq!(filter.cells_for_size(95., 41.), (10, 3));
+    }
+}
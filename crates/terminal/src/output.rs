@@ -0,0 +1,278 @@
+//! The terminal's output as plain text lines, for reading it programmatically.
+//!
+//! The PTY wrapper feeds the shell's output through an [`OutputCapture`]
+//! before alacritty parses it, which strips escape sequences and streams the
+//! lines, along with the command boundaries the shell reports, to subscribers.
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use std::mem;
+
+use crate::shell_integration::ShellMark;
+
+/// A line of a terminal's output or a command boundary, in output order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TerminalOutput {
+    /// A line of output, without its line ending and escape sequences.
+    Line(String),
+    /// The shell started printing a prompt.
+    Prompt,
+    /// A command started running, and the lines that follow are its output.
+    /// The command line is known if the shell reports it.
+    CommandStarted { command: Option<String> },
+    /// The running command finished.
+    CommandFinished { exit_code: Option<i32> },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum State {
+    #[default]
+    Ground,
+    Escape,
+    /// A control sequence, which ends with a byte in `0x40..=0x7e`.
+    Csi,
+    /// A string that ends with BEL or ST, like an OSC or DCS sequence.
+    String,
+    StringEscape,
+}
+
+/// Turns raw terminal output into lines of text.
+#[derive(Default)]
+pub(crate) struct LineSanitizer {
+    state: State,
+    line: Vec<u8>,
+    carriage_return: bool,
+}
+
+impl LineSanitizer {
+    /// Consumes output, returning the lines it completes.
+    pub(crate) fn push(&mut self, output: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+        for &byte in output {
+            match self.state {
+                State::Ground => self.push_text(byte, &mut lines),
+                State::Escape => {
+                    self.state = match byte {
+                        b'[' => State::Csi,
+                        b']' | b'P' | b'X' | b'^' | b'_' => State::String,
+                        // Intermediate bytes, as in `ESC ( B`, precede the final one.
+                        0x20..=0x2f => State::Escape,
+                        _ => State::Ground,
+                    }
+                }
+                State::Csi => {
+                    if (0x40..=0x7e).contains(&byte) {
+                        self.state = State::Ground;
+                    }
+                }
+                State::String => match byte {
+                    0x07 => self.state = State::Ground,
+                    0x1b => self.state = State::StringEscape,
+                    _ => {}
+                },
+                State::StringEscape => {
+                    self.state = match byte {
+                        b'\\' => State::Ground,
+                        0x1b => State::StringEscape,
+                        _ => State::String,
+                    }
+                }
+            }
+        }
+        lines
+    }
+
+    fn push_text(&mut self, byte: u8, lines: &mut Vec<String>) {
+        // A carriage return that doesn't end the line starts overwriting it,
+        // as progress bars do.
+        if mem::take(&mut self.carriage_return) && byte != b'\n' {
+            self.line.clear();
+        }
+        match byte {
+            0x1b => self.state = State::Escape,
+            b'\n' => lines.push(self.take_line()),
+            b'\r' => self.carriage_return = true,
+            b'\t' => self.line.push(byte),
+            0x08 => {
+                // Erase the whole character, not just its last UTF-8 byte.
+                while let Some(byte) = self.line.pop() {
+                    if byte & 0xc0 != 0x80 {
+                        break;
+                    }
+                }
+            }
+            0x00..=0x1f | 0x7f => {}
+            _ => self.line.push(byte),
+        }
+    }
+
+    /// Ends the line being written, returning it unless it's empty.
+    pub(crate) fn flush(&mut self) -> Option<String> {
+        self.carriage_return = false;
+        if self.line.is_empty() {
+            None
+        } else {
+            Some(self.take_line())
+        }
+    }
+
+    fn take_line(&mut self) -> String {
+        String::from_utf8_lossy(&mem::take(&mut self.line)).into_owned()
+    }
+}
+
+/// Streams a terminal's output to its subscribers. Output is only sanitized
+/// while someone is subscribed.
+#[derive(Default)]
+pub(crate) struct OutputCapture {
+    sanitizer: LineSanitizer,
+    subscribers: Vec<UnboundedSender<TerminalOutput>>,
+    /// The command line reported for the command about to run.
+    command: Option<String>,
+}
+
+impl OutputCapture {
+    pub(crate) fn subscribe(&mut self) -> UnboundedReceiver<TerminalOutput> {
+        // Without subscribers the sanitizer skipped the output, so it starts over
+        // rather than finishing a line or an escape sequence from long ago.
+        self.subscribers
+            .retain(|subscriber| !subscriber.is_closed());
+        if self.subscribers.is_empty() {
+            self.sanitizer = LineSanitizer::default();
+            self.command = None;
+        }
+        let (tx, rx) = unbounded();
+        self.subscribers.push(tx);
+        rx
+    }
+
+    pub(crate) fn push(&mut self, output: &[u8]) {
+        if self.subscribers.is_empty() {
+            return;
+        }
+        for line in self.sanitizer.push(output) {
+            self.send(TerminalOutput::Line(line));
+        }
+    }
+
+    /// Reports a mark found right after the output pushed so far.
+    pub(crate) fn push_mark(&mut self, mark: &ShellMark) {
+        if self.subscribers.is_empty() {
+            return;
+        }
+        let event = match mark {
+            ShellMark::PromptStart => TerminalOutput::Prompt,
+            ShellMark::CommandLine(command) => {
+                self.command = Some(command.clone());
+                return;
+            }
+            ShellMark::CommandExecuted => TerminalOutput::CommandStarted {
+                command: self.command.take(),
+            },
+            ShellMark::CommandFinished { exit_code } => TerminalOutput::CommandFinished {
+                exit_code: *exit_code,
+            },
+            ShellMark::CommandStart | ShellMark::WorkingDirectory(_) => return,
+        };
+        if let Some(line) = self.sanitizer.flush() {
+            self.send(TerminalOutput::Line(line));
+        }
+        self.send(event);
+    }
+
+    fn send(&mut self, output: TerminalOutput) {
+        self.subscribers
+            .retain(|subscriber| subscriber.unbounded_send(output.clone()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_lines() {
+        let mut sanitizer = LineSanitizer::default();
+        assert_eq!(
+            sanitizer.push(b"\x1b[1;31merror\x1b[0m: bad\r\n\x1b]0;title\x07ok\n"),
+            ["error: bad", "ok"]
+        );
+        // Lines are completed across writes, and escapes split between them are
+        // still removed.
+        assert_eq!(sanitizer.push(b"a\x1b["), Vec::<String>::new());
+        assert_eq!(sanitizer.push(b"2Kb\x1b(Bc\n"), ["abc"]);
+        // Carriage returns overwrite the line, and backspaces erase characters.
+        assert_eq!(
+            sanitizer.push(b"10%\r50%\r100%\nab\x08\x08\xc3\xa9\x08x\n"),
+            ["100%", "x"]
+        );
+        assert_eq!(
+            sanitizer.push(b"\x1bP1$r0m\x1b\\\tdone"),
+            Vec::<String>::new()
+        );
+        assert_eq!(sanitizer.flush().as_deref(), Some("\tdone"));
+        assert_eq!(sanitizer.flush(), None);
+    }
+
+    #[test]
+    fn test_capture_command_boundaries() {
+        let mut capture = OutputCapture::default();
+        capture.push(b"ignored\n");
+        let mut rx = capture.subscribe();
+        capture.push_mark(&ShellMark::PromptStart);
+        capture.push(b"$ ls");
+        capture.push_mark(&ShellMark::CommandLine("ls".into()));
+        capture.push(b"\r\n");
+        capture.push_mark(&ShellMark::CommandExecuted);
+        capture.push(b"a.txt\r\nb");
+        capture.push_mark(&ShellMark::CommandFinished { exit_code: Some(0) });
+
+        let mut events = Vec::new();
+        while let Ok(Some(event)) = rx.try_next() {
+            events.push(event);
+        }
+        assert_eq!(
+            events,
+            [
+                TerminalOutput::Prompt,
+                TerminalOutput::Line("$ ls".into()),
+                TerminalOutput::CommandStarted {
+                    command: Some("ls".into())
+                },
+                TerminalOutput::Line("a.txt".into()),
+                TerminalOutput::Line("b".into()),
+                TerminalOutput::CommandFinished { exit_code: Some(0) },
+            ]
+        );
+
+        drop(rx);
+        capture.push(b"more\n");
+        assert!(capture.subscribers.is_empty());
+    }
+
+    #[test]
+    fn test_subscribe_after_unsanitized_output() {
+        let mut capture = OutputCapture::default();
+        let mut rx = capture.subscribe();
+        capture.push(b"old");
+        capture.push_mark(&ShellMark::CommandLine("old".into()));
+        drop(rx);
+        capture.push(b" line\n\x1b]0;unfinished title");
+
+        // Neither the line nor the escape sequence left unfinished is carried
+        // over to the next subscriber.
+        rx = capture.subscribe();
+        capture.push(b"new\n");
+        capture.push_mark(&ShellMark::CommandExecuted);
+        let mut events = Vec::new();
+        while let Ok(Some(event)) = rx.try_next() {
+            events.push(event);
+        }
+        assert_eq!(
+            events,
+            [
+                TerminalOutput::Line("new".into()),
+                TerminalOutput::CommandStarted { command: None },
+            ]
+        );
+    }
+}
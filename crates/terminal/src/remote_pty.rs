@@ -0,0 +1,168 @@
+//! The PTY of a terminal whose shell runs on another machine, such as the host
+//! of a remote project, with the caller carrying the shell's input and output.
+//!
+//! The terminal reads and writes the same frames as over a persistent session's
+//! socket, through one end of a socket pair. [`RemotePty`] is the other end.
+
+use crate::session::{Frame, FrameDecoder, SessionPty, MAX_FRAME_LEN};
+use anyhow::Result;
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use std::{
+    io::{Read, Write},
+    net::Shutdown,
+    os::unix::net::UnixStream,
+    thread,
+};
+
+/// What a terminal whose shell runs on another machine sends to the shell.
+#[derive(Debug, PartialEq)]
+pub enum RemotePtyInput {
+    Input(Vec<u8>),
+    Resize {
+        rows: u16,
+        columns: u16,
+        cell_width: u16,
+        cell_height: u16,
+    },
+}
+
+/// The other end of the PTY of a terminal whose shell runs on another machine.
+/// Dropping it ends the terminal as if the shell had been killed.
+pub struct RemotePty {
+    stream: UnixStream,
+}
+
+impl RemotePty {
+    /// Creates the PTY of a terminal along with its other end, and a channel of
+    /// what the terminal sends, which closes once the terminal is dropped.
+    pub(crate) fn new() -> Result<(Self, SessionPty, UnboundedReceiver<RemotePtyInput>)> {
+        let (stream, terminal_stream) = UnixStream::pair()?;
+        // The shell isn't a process of this machine, so its pid is never used.
+        let pty = SessionPty::from_stream(terminal_stream, FrameDecoder::default(), 0)?;
+        let (input_tx, input_rx) = unbounded();
+        let reader = stream.try_clone()?;
+        thread::spawn(move || read_input(reader, input_tx));
+        Ok((Self { stream }, pty, input_rx))
+    }
+
+    /// Passes output of the shell to the terminal.
+    pub fn output(&mut self, output: &[u8]) -> Result<()> {
+        for chunk in output.chunks(MAX_FRAME_LEN) {
+            self.stream
+                .write_all(&Frame::Output(chunk.to_vec()).encode())?;
+        }
+        Ok(())
+    }
+
+    /// Tells the terminal that the shell exited, with its exit code unless it
+    /// was killed by a signal.
+    pub fn exited(&mut self, exit_code: Option<i32>) -> Result<()> {
+        self.stream.write_all(&Frame::Exited(exit_code).encode())?;
+        Ok(())
+    }
+}
+
+impl Drop for RemotePty {
+    fn drop(&mut self) {
+        // The input reader holds a clone of the stream, which would otherwise
+        // keep the terminal from seeing it close.
+        self.stream.shutdown(Shutdown::Both).ok();
+    }
+}
+
+fn read_input(mut stream: UnixStream, input_tx: UnboundedSender<RemotePtyInput>) {
+    let mut decoder = FrameDecoder::default();
+    let mut buffer = [0; 4096];
+    loop {
+        let input = match decoder.next_frame() {
+            Ok(Some(Frame::Input(input))) => RemotePtyInput::Input(input),
+            Ok(Some(Frame::Resize {
+                rows,
+                columns,
+                cell_width,
+                cell_height,
+            })) => RemotePtyInput::Resize {
+                rows,
+                columns,
+                cell_width,
+                cell_height,
+            },
+            Ok(Some(_)) => continue,
+            Ok(None) => {
+                match stream.read(&mut buffer) {
+                    Ok(0) | Err(_) => return,
+                    Ok(len) => decoder.push(&buffer[..len]),
+                }
+                continue;
+            }
+            Err(_) => return,
+        };
+        if input_tx.unbounded_send(input).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alacritty_terminal::{
+        event::{OnResize, WindowSize},
+        tty::{ChildEvent, EventedPty, EventedReadWrite},
+    };
+    use futures::StreamExt;
+    use std::{io, time::Duration};
+
+    #[test]
+    fn test_remote_pty() {
+        let (mut remote_pty, mut pty, mut input_rx) = RemotePty::new().unwrap();
+
+        pty.writer().write_all(b"ls\r").unwrap();
+        pty.on_resize(WindowSize {
+            num_lines: 24,
+            num_cols: 80,
+            cell_width: 8,
+            cell_height: 16,
+        });
+        let input = futures::executor::block_on(async {
+            vec![
+                input_rx.next().await.unwrap(),
+                input_rx.next().await.unwrap(),
+            ]
+        });
+        assert_eq!(
+            input,
+            [
+                RemotePtyInput::Input(b"ls\r".to_vec()),
+                RemotePtyInput::Resize {
+                    rows: 24,
+                    columns: 80,
+                    cell_width: 8,
+                    cell_height: 16,
+                },
+            ]
+        );
+
+        remote_pty.output(b"file.txt\r\n").unwrap();
+        remote_pty.exited(Some(3)).unwrap();
+        let mut output = Vec::new();
+        let mut buffer = [0; 64];
+        for _ in 0..100 {
+            match pty.reader().read(&mut buffer) {
+                Ok(len) => output.extend_from_slice(&buffer[..len]),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                    if let Some(event) = pty.next_child_event() {
+                        assert_eq!(output, b"file.txt\r\n");
+                        assert!(matches!(event, ChildEvent::Exited(Some(3))));
+                        drop(pty);
+                        assert_eq!(futures::executor::block_on(input_rx.next()), None);
+                        return;
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                }
+                Err(error) => panic!("failed to read from the pty: {error}"),
+            }
+        }
+        panic!("the terminal never saw the shell exit");
+    }
+}
@@ -0,0 +1,975 @@
+//! Persistent terminal sessions: shells that run under a small detached daemon
+//! instead of as children of Zed, so that they outlive Zed's windows.
+//!
+//! The daemon is a copy of Zed started with [`SESSION_DAEMON_ENV_VAR`] set. It
+//! owns the PTY, keeps the shell's recent output, and serves one client at a
+//! time over a Unix socket in [`TERMINAL_SESSIONS_DIR`]. A client that attaches
+//! is first sent that output, which its terminal replays to rebuild the screen
+//! and the scrollback. Detaching leaves the shell running; the session ends
+//! when the shell exits or a client kills it.
+
+use alacritty_terminal::{
+    event::{OnResize, WindowSize},
+    tty::{self, ChildEvent, EventedPty, EventedReadWrite, Pty},
+};
+use anyhow::{anyhow, Context as _, Result};
+use polling::{Event as PollEvent, Events, PollMode, Poller};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    env,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read, Write},
+    os::unix::{
+        fs::PermissionsExt,
+        io::AsRawFd,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    process::{self, Command, Stdio},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use util::paths::TERMINAL_SESSIONS_DIR;
+
+/// Set to the path of the socket to serve when Zed is started as a session daemon.
+pub const SESSION_DAEMON_ENV_VAR: &str = "ZED_TERMINAL_SESSION_SOCKET";
+
+/// How much of the shell's output the daemon keeps to replay.
+const MAX_SCROLLBACK_LEN: usize = 4 * 1024 * 1024;
+pub(crate) const MAX_FRAME_LEN: usize = 1024 * 1024;
+const READ_BUFFER_LEN: usize = 64 * 1024;
+const ATTACH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The keys alacritty's PTY registers itself under.
+const PTY_KEY: usize = 0;
+const CHILD_EVENT_KEY: usize = 1;
+const LISTENER_KEY: usize = 2;
+const CLIENT_KEY: usize = 3;
+
+const ATTACHED: u8 = 0;
+const OUTPUT: u8 = 1;
+const EXITED: u8 = 2;
+const INPUT: u8 = 3;
+const RESIZE: u8 = 4;
+const KILL: u8 = 5;
+
+/// The messages exchanged over a session's socket, each sent as a tag byte and
+/// the big-endian length of the payload that follows.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Frame {
+    /// Sent by the daemon to a client that attaches, before the replayed output.
+    Attached {
+        shell_pid: u32,
+    },
+    Output(Vec<u8>),
+    /// The shell exited, with its exit code unless it was killed by a signal.
+    Exited(Option<i32>),
+    Input(Vec<u8>),
+    Resize {
+        rows: u16,
+        columns: u16,
+        cell_width: u16,
+        cell_height: u16,
+    },
+    Kill,
+}
+
+impl Frame {
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let (tag, payload) = match self {
+            Frame::Attached { shell_pid } => (ATTACHED, shell_pid.to_be_bytes().to_vec()),
+            Frame::Output(output) => (OUTPUT, output.clone()),
+            Frame::Exited(code) => (
+                EXITED,
+                code.map_or_else(Vec::new, |code| code.to_be_bytes().to_vec()),
+            ),
+            Frame::Input(input) => (INPUT, input.clone()),
+            Frame::Resize {
+                rows,
+                columns,
+                cell_width,
+                cell_height,
+            } => (
+                RESIZE,
+                [rows, columns, cell_width, cell_height]
+                    .iter()
+                    .flat_map(|value| value.to_be_bytes())
+                    .collect(),
+            ),
+            Frame::Kill => (KILL, Vec::new()),
+        };
+        let mut frame = Vec::with_capacity(5 + payload.len());
+        frame.push(tag);
+        frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&payload);
+        frame
+    }
+
+    fn decode(tag: u8, payload: Vec<u8>) -> Option<Self> {
+        let u16_at = |ix: usize| {
+            let bytes = payload.get(ix..ix + 2)?;
+            Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+        };
+        let u32_payload = || <[u8; 4]>::try_from(payload.as_slice()).ok();
+        Some(match tag {
+            ATTACHED => Frame::Attached {
+                shell_pid: u32::from_be_bytes(u32_payload()?),
+            },
+            OUTPUT => Frame::Output(payload),
+            EXITED if payload.is_empty() => Frame::Exited(None),
+            EXITED => Frame::Exited(Some(i32::from_be_bytes(u32_payload()?))),
+            INPUT => Frame::Input(payload),
+            RESIZE => Frame::Resize {
+                rows: u16_at(0)?,
+                columns: u16_at(2)?,
+                cell_width: u16_at(4)?,
+                cell_height: u16_at(6)?,
+            },
+            KILL => Frame::Kill,
+            _ => return None,
+        })
+    }
+}
+
+impl From<WindowSize> for Frame {
+    fn from(size: WindowSize) -> Self {
+        Frame::Resize {
+            rows: size.num_lines,
+            columns: size.num_cols,
+            cell_width: size.cell_width,
+            cell_height: size.cell_height,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct FrameDecoder {
+    buffer: Vec<u8>,
+}
+
+impl FrameDecoder {
+    pub(crate) fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    pub(crate) fn next_frame(&mut self) -> io::Result<Option<Frame>> {
+        let Some(header) = self.buffer.get(..5) else {
+            return Ok(None);
+        };
+        let tag = header[0];
+        let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+        if len > MAX_FRAME_LEN {
+            return Err(invalid_frame());
+        }
+        if self.buffer.len() < 5 + len {
+            return Ok(None);
+        }
+        let payload = self.buffer[5..5 + len].to_vec();
+        self.buffer.drain(..5 + len);
+        Frame::decode(tag, payload)
+            .map(Some)
+            .ok_or_else(invalid_frame)
+    }
+}
+
+fn invalid_frame() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid terminal session frame")
+}
+
+/// What Zed asks a new daemon to run, written to the daemon's standard input.
+#[derive(Serialize, Deserialize)]
+struct SpawnRequest {
+    shell: Option<(String, Vec<String>)>,
+    working_directory: Option<PathBuf>,
+    rows: u16,
+    columns: u16,
+    cell_width: u16,
+    cell_height: u16,
+}
+
+fn socket_path(session_id: &str) -> Result<PathBuf> {
+    if session_id.is_empty() || !session_id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(anyhow!("invalid terminal session id {session_id:?}"));
+    }
+    Ok(TERMINAL_SESSIONS_DIR.join(format!("{session_id}.sock")))
+}
+
+/// Starts a session running the given shell, or the user's default shell, and
+/// attaches to it. Returns the id of the session along with its PTY.
+pub(crate) fn spawn(
+    shell: Option<(String, Vec<String>)>,
+    working_directory: Option<PathBuf>,
+    window_size: WindowSize,
+) -> Result<(String, SessionPty)> {
+    fs::create_dir_all(&*TERMINAL_SESSIONS_DIR)?;
+    fs::set_permissions(&*TERMINAL_SESSIONS_DIR, fs::Permissions::from_mode(0o700))?;
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+    let session_id = format!("{timestamp:x}{:x}", process::id());
+    let socket_path = socket_path(&session_id)?;
+    let log = File::create(socket_path.with_extension("log"))?;
+
+    let mut daemon = Command::new(env::current_exe()?)
+        .env(SESSION_DAEMON_ENV_VAR, &socket_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(log)
+        .spawn()
+        .context("failed to start the terminal session daemon")?;
+    let request = SpawnRequest {
+        shell,
+        working_directory,
+        rows: window_size.num_lines,
+        columns: window_size.num_cols,
+        cell_width: window_size.cell_width,
+        cell_height: window_size.cell_height,
+    };
+    if let Some(mut stdin) = daemon.stdin.take() {
+        stdin.write_all(&serde_json::to_vec(&request)?)?;
+    }
+    let mut status = String::new();
+    if let Some(stdout) = daemon.stdout.take() {
+        BufReader::new(stdout).read_line(&mut status)?;
+    }
+    // The daemon forks away before serving, so this returns right away.
+    daemon.wait()?;
+    match status.trim_end() {
+        "ready" => {}
+        "" => return Err(anyhow!("the terminal session daemon exited")),
+        error => return Err(anyhow!("failed to start a terminal session: {error}")),
+    }
+
+    let pty = SessionPty::attach(&session_id)?;
+    Ok((session_id, pty))
+}
+
+/// Ends a session, hanging up on its shell. Any client attached to it is detached.
+pub fn kill(session_id: &str) -> Result<()> {
+    let mut stream = UnixStream::connect(socket_path(session_id)?)?;
+    stream.write_all(&Frame::Kill.encode())?;
+    Ok(())
+}
+
+/// Serves a session if Zed was started as a session daemon, returning whether it was.
+pub fn run_daemon_if_requested() -> bool {
+    let Some(socket_path) = env::var_os(SESSION_DAEMON_ENV_VAR) else {
+        return false;
+    };
+    env::remove_var(SESSION_DAEMON_ENV_VAR);
+
+    // Fork so that Zed doesn't have to reap the daemon, and leave Zed's session
+    // so that the daemon isn't hung up on along with it.
+    unsafe {
+        match libc::fork() {
+            -1 => process::exit(1),
+            0 => {
+                libc::setsid();
+            }
+            _ => libc::_exit(0),
+        }
+    }
+
+    if let Err(error) = run_daemon(Path::new(&socket_path)) {
+        // No logger is set up this early in Zed's startup, but the daemon's stderr
+        // goes to the session's log file.
+        eprintln!("terminal session failed: {error:?}");
+    }
+    true
+}
+
+fn run_daemon(socket_path: &Path) -> Result<()> {
+    let mut request = String::new();
+    io::stdin().read_to_string(&mut request)?;
+    let request = serde_json::from_str::<SpawnRequest>(&request)?;
+    let options = tty::Options {
+        shell: request
+            .shell
+            .map(|(program, args)| tty::Shell::new(program, args)),
+        working_directory: request.working_directory,
+        hold: false,
+    };
+    let window_size = WindowSize {
+        num_lines: request.rows,
+        num_cols: request.columns,
+        cell_width: request.cell_width,
+        cell_height: request.cell_height,
+    };
+
+    let daemon = Daemon::new(socket_path, &options, window_size);
+    let mut stdout = io::stdout();
+    match &daemon {
+        Ok(_) => writeln!(stdout, "ready")?,
+        Err(error) => writeln!(stdout, "{error}")?,
+    }
+    stdout.flush()?;
+    daemon?.run()
+}
+
+struct Daemon {
+    socket_path: PathBuf,
+    listener: UnixListener,
+    pty: Pty,
+    poller: Arc<Poller>,
+    scrollback: VecDeque<u8>,
+    /// Input from the client that the shell hasn't read yet.
+    pending_input: Vec<u8>,
+    client: Option<Client>,
+}
+
+struct Client {
+    stream: UnixStream,
+    decoder: FrameDecoder,
+    /// Frames the client hasn't read yet.
+    pending_output: Vec<u8>,
+}
+
+impl Client {
+    fn send(&mut self, frame: &Frame) {
+        self.pending_output.extend_from_slice(&frame.encode());
+    }
+}
+
+impl Daemon {
+    fn new(socket_path: &Path, options: &tty::Options, window_size: WindowSize) -> Result<Self> {
+        let pty = tty::new(options, window_size, 0)?;
+        fs::remove_file(socket_path).ok();
+        let listener = UnixListener::bind(socket_path)?;
+        listener.set_nonblocking(true)?;
+
+        let mut daemon = Self {
+            socket_path: socket_path.to_path_buf(),
+            listener,
+            pty,
+            poller: Arc::new(Poller::new()?),
+            scrollback: VecDeque::new(),
+            pending_input: Vec::new(),
+            client: None,
+        };
+        unsafe {
+            daemon.pty.register(
+                &daemon.poller,
+                PollEvent::readable(PTY_KEY),
+                PollMode::Level,
+            )?;
+            daemon.poller.add_with_mode(
+                &daemon.listener,
+                PollEvent::readable(LISTENER_KEY),
+                PollMode::Level,
+            )?;
+        }
+        Ok(daemon)
+    }
+
+    fn run(mut self) -> Result<()> {
+        let mut events = Events::new();
+        let mut buffer = vec![0; READ_BUFFER_LEN];
+        loop {
+            events.clear();
+            match self.poller.wait(&mut events, None) {
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                result => result?,
+            };
+
+            for event in events.iter() {
+                match event.key {
+                    PTY_KEY => {
+                        if event.readable {
+                            self.read_output(&mut buffer)?;
+                        }
+                        if event.writable {
+                            self.write_input()?;
+                        }
+                    }
+                    CHILD_EVENT_KEY => {
+                        if let Some(ChildEvent::Exited(code)) = self.pty.next_child_event() {
+                            self.read_output(&mut buffer).ok();
+                            if let Some(mut client) = self.client.take() {
+                                client.send(&Frame::Exited(code));
+                                client.stream.set_nonblocking(false).ok();
+                                client.stream.write_all(&client.pending_output).ok();
+                            }
+                            return Ok(());
+                        }
+                    }
+                    LISTENER_KEY => self.accept()?,
+                    CLIENT_KEY => {
+                        if event.readable && self.read_client(&mut buffer) {
+                            return Ok(());
+                        }
+                        if event.writable {
+                            self.write_client();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            let pty_interest = if self.pending_input.is_empty() {
+                PollEvent::readable(PTY_KEY)
+            } else {
+                PollEvent::all(PTY_KEY)
+            };
+            self.pty
+                .reregister(&self.poller, pty_interest, PollMode::Level)?;
+            if let Some(client) = &self.client {
+                let client_interest = if client.pending_output.is_empty() {
+                    PollEvent::readable(CLIENT_KEY)
+                } else {
+                    PollEvent::all(CLIENT_KEY)
+                };
+                self.poller
+                    .modify_with_mode(&client.stream, client_interest, PollMode::Level)?;
+            }
+        }
+    }
+
+    /// Reads the shell's output until there is nothing more to read.
+    fn read_output(&mut self, buffer: &mut [u8]) -> Result<()> {
+        loop {
+            match self.pty.reader().read(buffer) {
+                Ok(0) => return Ok(()),
+                Ok(len) => {
+                    let output = &buffer[..len];
+                    self.scrollback.extend(output);
+                    trim_scrollback(&mut self.scrollback);
+                    if let Some(client) = &mut self.client {
+                        client.send(&Frame::Output(output.to_vec()));
+                        // A client that stopped reading catches up when it attaches again.
+                        if client.pending_output.len() > MAX_SCROLLBACK_LEN {
+                            self.detach();
+                        }
+                    }
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                // Linux fails reads once the shell has exited, before the child event.
+                Err(error) if error.raw_os_error() == Some(libc::EIO) => return Ok(()),
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    fn write_input(&mut self) -> Result<()> {
+        while !self.pending_input.is_empty() {
+            match self.pty.writer().write(&self.pending_input) {
+                Ok(len) => {
+                    self.pending_input.drain(..len);
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) => return Err(error.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Attaches a new client in place of the current one, replaying the output kept so far.
+    fn accept(&mut self) -> Result<()> {
+        let stream = match self.listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
+        stream.set_nonblocking(true)?;
+        self.detach();
+
+        let mut client = Client {
+            stream,
+            decoder: FrameDecoder::default(),
+            pending_output: Vec::new(),
+        };
+        client.send(&Frame::Attached {
+            shell_pid: self.pty.child().id(),
+        });
+        let replay = strip_queries(self.scrollback.make_contiguous());
+        for chunk in replay.chunks(READ_BUFFER_LEN) {
+            client.send(&Frame::Output(chunk.to_vec()));
+        }
+        unsafe {
+            self.poller.add_with_mode(
+                &client.stream,
+                PollEvent::all(CLIENT_KEY),
+                PollMode::Level,
+            )?;
+        }
+        self.client = Some(client);
+        Ok(())
+    }
+
+    fn detach(&mut self) {
+        if let Some(client) = self.client.take() {
+            self.poller.delete(&client.stream).ok();
+        }
+    }
+
+    /// Handles what the client sent, returning whether it killed the session.
+    fn read_client(&mut self, buffer: &mut [u8]) -> bool {
+        let Some(client) = &mut self.client else {
+            return false;
+        };
+        loop {
+            match client.stream.read(buffer) {
+                Ok(0) => break self.detach(),
+                Ok(len) => client.decoder.push(&buffer[..len]),
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(_) => break self.detach(),
+            }
+        }
+
+        while let Some(client) = &mut self.client {
+            match client.decoder.next_frame() {
+                Ok(Some(Frame::Input(input))) => self.pending_input.extend(input),
+                Ok(Some(Frame::Resize {
+                    rows,
+                    columns,
+                    cell_width,
+                    cell_height,
+                })) => self.pty.on_resize(WindowSize {
+                    num_lines: rows,
+                    num_cols: columns,
+                    cell_width,
+                    cell_height,
+                }),
+                Ok(Some(Frame::Kill)) => return true,
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(_) => self.detach(),
+            }
+        }
+        false
+    }
+
+    fn write_client(&mut self) {
+        let Some(client) = &mut self.client else {
+            return;
+        };
+        while !client.pending_output.is_empty() {
+            match client.stream.write(&client.pending_output) {
+                Ok(len) => {
+                    client.pending_output.drain(..len);
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return,
+                Err(_) => return self.detach(),
+            }
+        }
+    }
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        fs::remove_file(&self.socket_path).ok();
+        fs::remove_file(self.socket_path.with_extension("log")).ok();
+    }
+}
+
+/// Drops the oldest output beyond [`MAX_SCROLLBACK_LEN`], up to the end of a
+/// line so that the replay doesn't start in the middle of an escape sequence.
+fn trim_scrollback(scrollback: &mut VecDeque<u8>) {
+    if scrollback.len() <= MAX_SCROLLBACK_LEN {
+        return;
+    }
+    let excess = scrollback.len() - MAX_SCROLLBACK_LEN;
+    let trimmed_len = scrollback
+        .iter()
+        .skip(excess)
+        .position(|byte| *byte == b'\n')
+        .map_or(excess, |ix| excess + ix + 1);
+    scrollback.drain(..trimmed_len);
+}
+
+/// Removes the queries from output to replay. The attaching terminal would
+/// answer them again, and the shell would read the answers as typed input.
+fn strip_queries(output: &[u8]) -> Vec<u8> {
+    let mut stripped = Vec::with_capacity(output.len());
+    let mut ix = 0;
+    while ix < output.len() {
+        let len = match output[ix..] {
+            [0x1b, b'[', ..] => control_sequence_query_len(&output[ix..]),
+            [0x1b, b']', ..] => osc_query_len(&output[ix..]),
+            _ => None,
+        };
+        match len {
+            Some(len) => ix += len,
+            None => {
+                stripped.push(output[ix]);
+                ix += 1;
+            }
+        }
+    }
+    stripped
+}
+
+/// Returns the length of the control sequence starting the output if it is a query.
+fn control_sequence_query_len(output: &[u8]) -> Option<usize> {
+    let end = 2 + output[2..]
+        .iter()
+        .position(|byte| (0x40..=0x7e).contains(byte))?;
+    let (parameters, last) = (&output[2..end], output[end]);
+    let is_query = match last {
+        // Device attributes and status reports.
+        b'c' | b'n' => true,
+        // Mode reports.
+        b'p' => parameters.ends_with(b"$"),
+        // Terminal version.
+        b'q' => parameters.starts_with(b">"),
+        // Keyboard protocol flags.
+        b'u' => parameters == b"?",
+        // Window size reports.
+        b't' => matches!(parameters, b"14" | b"16" | b"18"),
+        _ => false,
+    };
+    is_query.then_some(end + 1)
+}
+
+/// Returns the length of the OSC sequence starting the output if it is a query,
+/// like those for colors and the clipboard, which end their payload with `?`.
+fn osc_query_len(output: &[u8]) -> Option<usize> {
+    let (payload_len, terminator_len) =
+        output[2..]
+            .iter()
+            .enumerate()
+            .find_map(|(ix, byte)| match byte {
+                0x07 => Some((ix, 1)),
+                0x1b if output.get(2 + ix + 1) == Some(&b'\\') => Some((ix, 2)),
+                _ => None,
+            })?;
+    let payload = &output[2..2 + payload_len];
+    payload
+        .ends_with(b"?")
+        .then_some(2 + payload_len + terminator_len)
+}
+
+/// The PTY of a session, read and written through its daemon's socket.
+pub(crate) struct SessionPty {
+    reader: SessionReader,
+    writer: SessionWriter,
+    /// Readable once the session has ended, to report it to alacritty's event loop.
+    child_events: UnixStream,
+    shell_pid: u32,
+}
+
+impl SessionPty {
+    /// Attaches to a running session.
+    pub(crate) fn attach(session_id: &str) -> Result<Self> {
+        Self::connect(&socket_path(session_id)?)
+            .with_context(|| format!("failed to attach to terminal session {session_id}"))
+    }
+
+    fn connect(socket_path: &Path) -> Result<Self> {
+        let mut stream = UnixStream::connect(socket_path)?;
+        stream.set_read_timeout(Some(ATTACH_TIMEOUT))?;
+        let mut decoder = FrameDecoder::default();
+        let mut buffer = [0; 256];
+        let shell_pid = loop {
+            match decoder.next_frame()? {
+                Some(Frame::Attached { shell_pid }) => break shell_pid,
+                Some(_) => return Err(invalid_frame().into()),
+                None => {}
+            }
+            let len = stream.read(&mut buffer)?;
+            if len == 0 {
+                return Err(anyhow!("the session has ended"));
+            }
+            decoder.push(&buffer[..len]);
+        };
+        stream.set_read_timeout(None)?;
+        Self::from_stream(stream, decoder, shell_pid)
+    }
+
+    /// Reads and writes the frames of a PTY through the stream, starting with
+    /// those the decoder has already received.
+    pub(crate) fn from_stream(
+        stream: UnixStream,
+        decoder: FrameDecoder,
+        shell_pid: u32,
+    ) -> Result<Self> {
+        let (child_events, exit_tx) = UnixStream::pair()?;
+        child_events.set_nonblocking(true)?;
+        Ok(Self {
+            reader: SessionReader {
+                stream: stream.try_clone()?,
+                decoder,
+                buffer: vec![0; READ_BUFFER_LEN],
+                output: Vec::new(),
+                exit_tx,
+                exited: false,
+            },
+            writer: SessionWriter { stream },
+            child_events,
+            shell_pid,
+        })
+    }
+
+    pub(crate) fn shell_pid(&self) -> u32 {
+        self.shell_pid
+    }
+}
+
+impl EventedReadWrite for SessionPty {
+    type Reader = SessionReader;
+    type Writer = SessionWriter;
+
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: PollEvent,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        poll.add_with_mode(&self.reader.stream, interest, mode)?;
+        poll.add_with_mode(
+            &self.child_events,
+            PollEvent::readable(CHILD_EVENT_KEY),
+            PollMode::Level,
+        )
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: PollEvent,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        poll.modify_with_mode(&self.reader.stream, interest, mode)
+    }
+
+    fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+        poll.delete(&self.reader.stream)?;
+        poll.delete(&self.child_events)
+    }
+
+    fn reader(&mut self) -> &mut SessionReader {
+        &mut self.reader
+    }
+
+    fn writer(&mut self) -> &mut SessionWriter {
+        &mut self.writer
+    }
+}
+
+impl EventedPty for SessionPty {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        let mut event = [0; 5];
+        self.child_events.read_exact(&mut event).ok()?;
+        let code =
+            (event[0] == 1).then(|| i32::from_be_bytes([event[1], event[2], event[3], event[4]]));
+        Some(ChildEvent::Exited(code))
+    }
+}
+
+impl OnResize for SessionPty {
+    fn on_resize(&mut self, window_size: WindowSize) {
+        self.writer.send(&window_size.into()).ok();
+    }
+}
+
+/// Reads the shell's output out of the frames the daemon sends.
+///
+/// The socket is shared with the writer, which blocks, so reads are made
+/// non-blocking per call instead.
+pub(crate) struct SessionReader {
+    stream: UnixStream,
+    decoder: FrameDecoder,
+    buffer: Vec<u8>,
+    /// Output received but not read yet.
+    output: Vec<u8>,
+    exit_tx: UnixStream,
+    exited: bool,
+}
+
+impl SessionReader {
+    fn exit(&mut self, code: Option<i32>) {
+        self.exited = true;
+        let mut event = [0; 5];
+        if let Some(code) = code {
+            event[0] = 1;
+            event[1..].copy_from_slice(&code.to_be_bytes());
+        }
+        self.exit_tx.write_all(&event).ok();
+    }
+}
+
+impl Read for SessionReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if !self.output.is_empty() {
+                let len = self.output.len().min(buf.len());
+                buf[..len].copy_from_slice(&self.output[..len]);
+                self.output.drain(..len);
+                return Ok(len);
+            }
+            if self.exited {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+
+            match self.decoder.next_frame()? {
+                Some(Frame::Output(output)) => self.output = output,
+                Some(Frame::Exited(code)) => self.exit(code),
+                Some(_) => {}
+                None => {
+                    let len = unsafe {
+                        libc::recv(
+                            self.stream.as_raw_fd(),
+                            self.buffer.as_mut_ptr().cast(),
+                            self.buffer.len(),
+                            libc::MSG_DONTWAIT,
+                        )
+                    };
+                    match len {
+                        ..=-1 => return Err(io::Error::last_os_error()),
+                        // The daemon went away without the shell exiting.
+                        0 => self.exit(None),
+                        len => {
+                            let len = len as usize;
+                            self.decoder.push(&self.buffer[..len]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub(crate) struct SessionWriter {
+    stream: UnixStream,
+}
+
+impl SessionWriter {
+    fn send(&mut self, frame: &Frame) -> io::Result<()> {
+        self.stream.write_all(&frame.encode())
+    }
+}
+
+impl Write for SessionWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(MAX_FRAME_LEN);
+        self.send(&Frame::Input(buf[..len].to_vec()))?;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{thread, time::Instant};
+
+    #[test]
+    fn test_frames() {
+        let frames = [
+            Frame::Attached { shell_pid: 4321 },
+            Frame::Output(b"$ ls\r\n".to_vec()),
+            Frame::Exited(Some(-1)),
+            Frame::Exited(None),
+            Frame::Input(Vec::new()),
+            Frame::Resize {
+                rows: 24,
+                columns: 80,
+                cell_width: 8,
+                cell_height: 16,
+            },
+            Frame::Kill,
+        ];
+        let encoded = frames.iter().flat_map(Frame::encode).collect::<Vec<_>>();
+
+        // Frames are decoded however the bytes are split up.
+        let mut decoder = FrameDecoder::default();
+        let mut decoded = Vec::new();
+        for chunk in encoded.chunks(3) {
+            decoder.push(chunk);
+            while let Some(frame) = decoder.next_frame().unwrap() {
+                decoded.push(frame);
+            }
+        }
+        assert_eq!(decoded, frames);
+
+        let mut decoder = FrameDecoder::default();
+        decoder.push(&[42, 0, 0, 0, 0]);
+        assert!(decoder.next_frame().is_err());
+    }
+
+    #[test]
+    fn test_strip_queries() {
+        assert_eq!(
+            strip_queries(b"a\x1b[cb\x1b[>0c\x1b[6nc\x1b[?2004$pd\x1b[>qe\x1b[?uf\x1b[18tg"),
+            b"abcdefg"
+        );
+        assert_eq!(
+            strip_queries(b"\x1b]11;?\x07bg\x1b]52;c;?\x1b\\clip\x1b]4;1;?\x07"),
+            b"bgclip"
+        );
+        // Everything else is replayed, down to incomplete sequences.
+        let output = b"\x1b[1;31mred\x1b[0m\x1b]0;title\x07\x1b[2J\x1b]133;A\x07\x1b[";
+        assert_eq!(strip_queries(output), output);
+    }
+
+    fn read_until(pty: &mut SessionPty, text: &str) -> String {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut output = Vec::new();
+        let mut buffer = [0; 1024];
+        while !String::from_utf8_lossy(&output).contains(text) {
+            assert!(Instant::now() < deadline, "timed out waiting for {text:?}");
+            match pty.reader().read(&mut buffer) {
+                Ok(len) => output.extend_from_slice(&buffer[..len]),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(10))
+                }
+                Err(error) => panic!("failed to read the session: {error}"),
+            }
+        }
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    #[test]
+    fn test_session_outlives_client() {
+        let socket_path = env::temp_dir().join(format!("zed-session-{}.sock", process::id()));
+        let options = tty::Options {
+            shell: Some(tty::Shell::new(
+                "/bin/sh".into(),
+                vec!["-c".into(), "echo started; cat".into()],
+            )),
+            working_directory: None,
+            hold: false,
+        };
+        let window_size = WindowSize {
+            num_lines: 24,
+            num_cols: 80,
+            cell_width: 8,
+            cell_height: 16,
+        };
+        let daemon = Daemon::new(&socket_path, &options, window_size).unwrap();
+        let daemon = thread::spawn(move || daemon.run());
+
+        let mut pty = SessionPty::connect(&socket_path).unwrap();
+        read_until(&mut pty, "started");
+        pty.writer().write_all(b"hello\n").unwrap();
+        read_until(&mut pty, "hello");
+        drop(pty);
+
+        // The shell keeps running, and a new client is sent what it printed.
+        let mut pty = SessionPty::connect(&socket_path).unwrap();
+        let replay = read_until(&mut pty, "hello");
+        assert!(replay.contains("started"));
+        pty.writer().write_all(b"again\n").unwrap();
+        read_until(&mut pty, "again");
+
+        pty.writer.send(&Frame::Kill).unwrap();
+        daemon.join().unwrap().unwrap();
+        assert!(!socket_path.exists());
+        assert!(SessionPty::connect(&socket_path).is_err());
+    }
+
+    #[test]
+    fn test_trim_scrollback() {
+        let mut scrollback = VecDeque::from(vec![b'x'; MAX_SCROLLBACK_LEN - 2]);
+        scrollback.extend(b"\nfirst\nsecond\n");
+        trim_scrollback(&mut scrollback);
+        // The partial line left at the start is dropped with the excess.
+        assert_eq!(scrollback, b"first\nsecond\n");
+    }
+}
@@ -0,0 +1,914 @@
+//! Shell integration: the scripts that make bash, zsh and fish report where
+//! prompts and commands begin and end, and the PTY wrapper that picks those
+//! reports out of the shell's output before alacritty parses (and ignores) them.
+//!
+//! Shells report with the OSC 133 sequences of the FinalTerm protocol, the
+//! OSC 633 superset VS Code uses, and OSC 7 for the working directory.
+
+use alacritty_terminal::{
+    event::{Event as AlacTermEvent, EventListener, OnResize, WindowSize},
+    grid::Dimensions,
+    index::{Column, Line, Point as AlacPoint},
+    sync::FairMutex,
+    term::cell::Cell,
+    tty::{ChildEvent, EventedPty, EventedReadWrite},
+    Term,
+};
+use anyhow::Result;
+use collections::HashMap;
+use polling::{Event as PollEvent, PollMode, Poller};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    mem,
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use util::{paths::SHELL_INTEGRATION_DIR, ResultExt};
+
+use crate::{
+    images::{ImageFilter, TerminalImages},
+    output::OutputCapture,
+    terminal_settings::Shell,
+    ZedListener,
+};
+
+const BASH_SCRIPT: &str = include_str!("shell_integration/zed.bash");
+const ZSHENV_SCRIPT: &str = include_str!("shell_integration/zshenv.zsh");
+const ZPROFILE_SCRIPT: &str = include_str!("shell_integration/zprofile.zsh");
+const ZSHRC_SCRIPT: &str = include_str!("shell_integration/zshrc.zsh");
+const FISH_SCRIPT: &str = include_str!("shell_integration/zed.fish");
+
+/// Longer OSC sequences can't be marks, so they aren't buffered.
+const MAX_OSC_LEN: usize = 4096;
+/// As many commands as there are lines of scrollback, at most.
+const MAX_SHELL_COMMANDS: usize = 10000;
+
+/// Sets up the given shell to load Zed's shell integration, adding the
+/// variables it needs to `env`. Returns the shell to launch instead, which is
+/// the same shell unless it's bash, or the shell given if it isn't one Zed has
+/// integration for.
+pub(crate) fn inject(shell: Shell, env: &mut HashMap<String, String>) -> Shell {
+    let program = match &shell {
+        Shell::System => std::env::var("SHELL").ok(),
+        Shell::Program(program) => Some(program.clone()),
+        // The arguments may already decide which startup files are loaded.
+        Shell::WithArguments { .. } => None,
+    };
+    let Some(program) = program else {
+        return shell;
+    };
+    let name = Path::new(&program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+
+    let injected = match name {
+        "bash" => inject_bash(&program, matches!(shell, Shell::System), env),
+        "zsh" => inject_zsh(env).map(|()| None),
+        "fish" => inject_fish(env).map(|()| None),
+        _ => Ok(None),
+    };
+    match injected.log_err() {
+        Some(Some(injected_shell)) => injected_shell,
+        _ => shell,
+    }
+}
+
+fn inject_bash(
+    program: &str,
+    system_shell: bool,
+    env: &mut HashMap<String, String>,
+) -> Result<Option<Shell>> {
+    let script_path = SHELL_INTEGRATION_DIR.join("zed.bash");
+    write_script(&script_path, BASH_SCRIPT)?;
+    // The system shell is a login shell on macOS, whose startup files the
+    // script has to load itself, as bash ignores `--init-file` for them.
+    let login = system_shell && cfg!(target_os = "macos");
+    env.insert(
+        "ZED_BASH_LOGIN".to_string(),
+        if login { "1" } else { "" }.to_string(),
+    );
+    Ok(Some(Shell::WithArguments {
+        program: program.to_string(),
+        args: vec![
+            "--init-file".to_string(),
+            script_path.to_string_lossy().to_string(),
+        ],
+    }))
+}
+
+fn inject_zsh(env: &mut HashMap<String, String>) -> Result<()> {
+    let zdotdir = SHELL_INTEGRATION_DIR.join("zsh");
+    write_script(&zdotdir.join(".zshenv"), ZSHENV_SCRIPT)?;
+    write_script(&zdotdir.join(".zprofile"), ZPROFILE_SCRIPT)?;
+    write_script(&zdotdir.join(".zshrc"), ZSHRC_SCRIPT)?;
+    let original_zdotdir = original_var(env, "ZDOTDIR")
+        .unwrap_or_else(|| util::paths::HOME.to_string_lossy().to_string());
+    env.insert("ZED_ORIGINAL_ZDOTDIR".to_string(), original_zdotdir);
+    env.insert("ZDOTDIR".to_string(), zdotdir.to_string_lossy().to_string());
+    Ok(())
+}
+
+fn inject_fish(env: &mut HashMap<String, String>) -> Result<()> {
+    let script_path = SHELL_INTEGRATION_DIR
+        .join("fish")
+        .join("vendor_conf.d")
+        .join("zed.fish");
+    write_script(&script_path, FISH_SCRIPT)?;
+    let original_data_dirs = original_var(env, "XDG_DATA_DIRS");
+    env.insert(
+        "XDG_DATA_DIRS".to_string(),
+        format!(
+            "{}:{}",
+            SHELL_INTEGRATION_DIR.to_string_lossy(),
+            original_data_dirs
+                .as_deref()
+                .unwrap_or("/usr/local/share:/usr/share")
+        ),
+    );
+    env.insert(
+        "ZED_ORIGINAL_XDG_DATA_DIRS".to_string(),
+        original_data_dirs.unwrap_or_default(),
+    );
+    Ok(())
+}
+
+/// The value an environment variable had before the shell integration
+/// replaced it. Terminals set their variables in Zed's own environment, so
+/// another terminal may have replaced it already.
+fn original_var(env: &HashMap<String, String>, key: &str) -> Option<String> {
+    if let Some(value) = env.get(key) {
+        return Some(value.clone());
+    }
+    match std::env::var(format!("ZED_ORIGINAL_{key}")) {
+        Ok(original) => Some(original).filter(|original| !original.is_empty()),
+        Err(_) => std::env::var(key).ok(),
+    }
+}
+
+fn write_script(path: &Path, script: &str) -> Result<()> {
+    if fs::read_to_string(path).ok().as_deref() == Some(script) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, script)?;
+    Ok(())
+}
+
+/// What a shell reported about the command at its prompt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ShellMark {
+    /// The shell is about to print its prompt.
+    PromptStart,
+    /// The prompt was printed, and what the user types next is the command.
+    CommandStart,
+    /// The command line, as the user entered it.
+    CommandLine(String),
+    /// The command is running, and what's printed next is its output.
+    CommandExecuted,
+    /// The command finished.
+    CommandFinished { exit_code: Option<i32> },
+    /// The shell's working directory changed.
+    WorkingDirectory(PathBuf),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum OscState {
+    #[default]
+    Ground,
+    Escape,
+    Osc,
+    OscEscape,
+}
+
+/// Finds the marks in a shell's output, one byte at a time, so that the
+/// output can be cut right after each mark.
+#[derive(Default)]
+pub(crate) struct OscParser {
+    state: OscState,
+    payload: Vec<u8>,
+}
+
+impl OscParser {
+    /// Consumes the next byte of output, returning the mark it ends, if any.
+    pub(crate) fn advance(&mut self, byte: u8) -> Option<ShellMark> {
+        match (self.state, byte) {
+            (OscState::Ground, 0x1b) => self.state = OscState::Escape,
+            (OscState::Ground, _) => {}
+            (OscState::Escape, b']') | (OscState::OscEscape, b']') => {
+                self.payload.clear();
+                self.state = OscState::Osc;
+            }
+            (OscState::Escape, 0x1b) => {}
+            (OscState::Escape, _) => self.state = OscState::Ground,
+            (OscState::Osc, 0x07) => {
+                self.state = OscState::Ground;
+                return parse_mark(&self.payload);
+            }
+            (OscState::Osc, 0x1b) => self.state = OscState::OscEscape,
+            (OscState::Osc, _) => {
+                if self.payload.len() < MAX_OSC_LEN {
+                    self.payload.push(byte);
+                } else {
+                    self.state = OscState::Ground;
+                }
+            }
+            (OscState::OscEscape, b'\\') => {
+                self.state = OscState::Ground;
+                return parse_mark(&self.payload);
+            }
+            (OscState::OscEscape, 0x1b) => self.state = OscState::Escape,
+            (OscState::OscEscape, _) => self.state = OscState::Ground,
+        }
+        None
+    }
+}
+
+fn parse_mark(payload: &[u8]) -> Option<ShellMark> {
+    let payload = std::str::from_utf8(payload).ok()?;
+    let (code, rest) = payload.split_once(';')?;
+    match code {
+        "133" | "633" => {
+            let mut params = rest.split(';');
+            match params.next()? {
+                "A" => Some(ShellMark::PromptStart),
+                "B" => Some(ShellMark::CommandStart),
+                "C" => Some(ShellMark::CommandExecuted),
+                "D" => Some(ShellMark::CommandFinished {
+                    exit_code: params.next().and_then(|code| code.parse().ok()),
+                }),
+                "E" if code == "633" => Some(ShellMark::CommandLine(unescape(
+                    params.next().unwrap_or_default(),
+                    b'\\',
+                ))),
+                "P" if code == "633" => {
+                    let cwd = params.next()?.strip_prefix("Cwd=")?;
+                    Some(ShellMark::WorkingDirectory(unescape(cwd, b'\\').into()))
+                }
+                _ => None,
+            }
+        }
+        "7" => {
+            let url = rest.strip_prefix("file://")?;
+            let path = &url[url.find('/')?..];
+            Some(ShellMark::WorkingDirectory(unescape(path, b'%').into()))
+        }
+        _ => None,
+    }
+}
+
+/// Decodes the hex escapes in a mark's parameter: `\xAB` and `\\` in OSC 633
+/// parameters, or `%AB` in OSC 7 URLs.
+fn unescape(text: &str, escape: u8) -> String {
+    let prefix: &[u8] = if escape == b'\\' { b"\\x" } else { b"%" };
+    let bytes = text.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut ix = 0;
+    while ix < bytes.len() {
+        if escape == b'\\' && bytes[ix..].starts_with(b"\\\\") {
+            unescaped.push(b'\\');
+            ix += 2;
+            continue;
+        }
+        if bytes[ix..].starts_with(prefix) {
+            let digits = ix + prefix.len()..ix + prefix.len() + 2;
+            let byte = bytes
+                .get(digits.clone())
+                .and_then(|digits| std::str::from_utf8(digits).ok())
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok());
+            if let Some(byte) = byte {
+                unescaped.push(byte);
+                ix = digits.end;
+                continue;
+            }
+        }
+        unescaped.push(bytes[ix]);
+        ix += 1;
+    }
+    String::from_utf8_lossy(&unescaped).into_owned()
+}
+
+/// A position in the terminal's grid, with lines counted from the first line
+/// the terminal printed, so that it stays put as output scrolls and as old
+/// lines are evicted from the scrollback.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ScrollbackPoint {
+    pub line: usize,
+    pub column: Column,
+}
+
+impl ScrollbackPoint {
+    /// `lines_above` is how many lines the terminal printed above its screen,
+    /// as returned by [`ShellMarks::lines_above_screen`].
+    pub(crate) fn from_grid(point: AlacPoint, lines_above: usize) -> Self {
+        Self {
+            line: (lines_above as i64 + point.line.0 as i64).max(0) as usize,
+            column: point.column,
+        }
+    }
+
+    pub fn to_grid(self, lines_above: usize) -> AlacPoint {
+        AlacPoint::new(
+            Line((self.line as i64 - lines_above as i64) as i32),
+            self.column,
+        )
+    }
+}
+
+/// Counts the lines alacritty drops from the top of the scrollback once it's
+/// full, which it doesn't report.
+///
+/// Rows keep the allocation of their cells as they scroll up, so the newest
+/// line of the scrollback is recognized later by the address of its cells,
+/// without putting anything in the grid. How far that line moved up since,
+/// less how much the scrollback grew, is how many lines were evicted.
+#[derive(Default)]
+struct Evictions {
+    count: usize,
+    newest_line: Option<NewestLine>,
+}
+
+/// The newest line of the scrollback, at `Line(-1)`, when [`Evictions`] were
+/// last updated.
+struct NewestLine {
+    cells_address: usize,
+    history_size: usize,
+    columns: usize,
+    screen_lines: usize,
+}
+
+impl Evictions {
+    fn update(&mut self, term: &Term<ZedListener>) {
+        let grid = term.grid();
+        let history_size = grid.history_size();
+        if let Some(newest_line) = self.newest_line.take() {
+            // Resizing reflows the scrollback rather than evicting from it.
+            let resized = newest_line.columns != grid.columns()
+                || newest_line.screen_lines != grid.screen_lines();
+            if !resized {
+                // The rows of a cleared scrollback are freed, so their address
+                // can't be looked for anymore.
+                let found = if history_size < newest_line.history_size {
+                    None
+                } else {
+                    // Lines only move up, until they're evicted.
+                    (grid.topmost_line().0..0)
+                        .rev()
+                        .map(Line)
+                        .find(|line| cells_address(term, *line) == newest_line.cells_address)
+                };
+                let scrolled = match found {
+                    Some(line) => (-1 - line.0) as usize,
+                    // The line was evicted, or the scrollback was cleared, so
+                    // at least every line up to it is gone.
+                    None => newest_line.history_size,
+                };
+                let growth = history_size.saturating_sub(newest_line.history_size);
+                self.count += scrolled.saturating_sub(growth);
+            }
+        }
+
+        if history_size > 0 {
+            self.newest_line = Some(NewestLine {
+                cells_address: cells_address(term, Line(-1)),
+                history_size,
+                columns: grid.columns(),
+                screen_lines: grid.screen_lines(),
+            });
+        }
+    }
+}
+
+fn cells_address(term: &Term<ZedListener>, line: Line) -> usize {
+    &term.grid()[AlacPoint::new(line, Column(0))] as *const Cell as usize
+}
+
+/// How the command at a prompt went.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandStatus {
+    /// The prompt is waiting for a command, or the command is still running.
+    Pending,
+    Succeeded,
+    Failed(i32),
+}
+
+/// A command run at a prompt of a shell with shell integration.
+#[derive(Clone, Debug)]
+pub struct ShellCommand {
+    /// Where the prompt starts.
+    pub prompt: ScrollbackPoint,
+    /// Where the command line the user typed starts.
+    pub command_start: Option<ScrollbackPoint>,
+    /// The command line, if the shell reports it.
+    pub command: Option<String>,
+    /// Where the command's output starts, once the command runs.
+    pub output_start: Option<ScrollbackPoint>,
+    /// Where the command's output ends, once the command finishes.
+    pub output_end: Option<ScrollbackPoint>,
+    pub exit_code: Option<i32>,
+    pub started_at: Option<Instant>,
+    pub finished_at: Option<Instant>,
+    /// The shell's working directory at the prompt.
+    pub working_directory: Option<PathBuf>,
+}
+
+impl ShellCommand {
+    pub fn status(&self) -> CommandStatus {
+        match (self.output_end, self.exit_code) {
+            (None, _) => CommandStatus::Pending,
+            (Some(_), None | Some(0)) => CommandStatus::Succeeded,
+            (Some(_), Some(exit_code)) => CommandStatus::Failed(exit_code),
+        }
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        Some(self.finished_at?.duration_since(self.started_at?))
+    }
+}
+
+/// The commands run in a terminal, as reported by its shell.
+#[derive(Default)]
+pub(crate) struct ShellCommands {
+    commands: Vec<ShellCommand>,
+    working_directory: Option<PathBuf>,
+}
+
+impl ShellCommands {
+    pub(crate) fn commands(&self) -> &[ShellCommand] {
+        &self.commands
+    }
+
+    /// Forgets the commands whose prompts were evicted from the scrollback,
+    /// given the number of lines evicted.
+    pub(crate) fn forget_evicted(&mut self, evicted_lines: usize) {
+        let evicted = self
+            .commands
+            .partition_point(|command| command.prompt.line < evicted_lines);
+        self.commands.drain(..evicted);
+    }
+
+    pub(crate) fn apply(&mut self, mark: ResolvedMark) {
+        let ResolvedMark {
+            mark,
+            position,
+            time,
+        } = mark;
+        if let ShellMark::PromptStart = mark {
+            if self.commands.len() == MAX_SHELL_COMMANDS {
+                self.commands.remove(0);
+            }
+            self.commands.push(ShellCommand {
+                prompt: position,
+                command_start: None,
+                command: None,
+                output_start: None,
+                output_end: None,
+                exit_code: None,
+                started_at: None,
+                finished_at: None,
+                working_directory: self.working_directory.clone(),
+            });
+            return;
+        }
+        if let ShellMark::WorkingDirectory(path) = mark {
+            self.working_directory = Some(path);
+            return;
+        }
+
+        let Some(command) = self.commands.last_mut() else {
+            return;
+        };
+        match mark {
+            ShellMark::CommandStart => command.command_start = Some(position),
+            ShellMark::CommandLine(line) => command.command = Some(line),
+            ShellMark::CommandExecuted => {
+                command.output_start = Some(position);
+                command.started_at = Some(time);
+            }
+            ShellMark::CommandFinished { exit_code } => {
+                if command.output_start.is_some() && command.output_end.is_none() {
+                    command.output_end = Some(position);
+                    command.exit_code = exit_code;
+                    command.finished_at = Some(time);
+                }
+            }
+            ShellMark::PromptStart | ShellMark::WorkingDirectory(_) => unreachable!(),
+        }
+    }
+
+    /// Forgets the commands cleared from the terminal, keeping the prompt
+    /// that was moved to the given line if no command was run at it yet.
+    pub(crate) fn clear(&mut self, prompt_line: usize) {
+        let prompt = self
+            .commands
+            .pop()
+            .filter(|command| command.output_start.is_none());
+        self.commands.clear();
+        if let Some(mut prompt) = prompt {
+            let offset = prompt.prompt.line;
+            prompt.prompt.line = prompt_line;
+            if let Some(command_start) = &mut prompt.command_start {
+                command_start.line = command_start.line.saturating_sub(offset) + prompt_line;
+            }
+            self.commands.push(prompt);
+        }
+    }
+}
+
+/// A mark, and where the cursor was when alacritty got to it.
+pub(crate) struct ResolvedMark {
+    mark: ShellMark,
+    position: ScrollbackPoint,
+    time: Instant,
+}
+
+struct PendingMark {
+    mark: ShellMark,
+    time: Instant,
+    /// Whether alacritty has parsed the output up to the mark.
+    parsed: bool,
+}
+
+/// The marks found in a terminal's output, shared between its IO thread and
+/// the terminal.
+#[derive(Default)]
+pub(crate) struct ShellMarks {
+    pending: Option<PendingMark>,
+    resolved: Vec<ResolvedMark>,
+    evictions: Evictions,
+}
+
+impl ShellMarks {
+    pub(crate) fn take_resolved(&mut self) -> Vec<ResolvedMark> {
+        mem::take(&mut self.resolved)
+    }
+
+    /// How many lines were evicted from the scrollback, as of the last time
+    /// [`Self::lines_above_screen`] was called.
+    pub(crate) fn evicted_lines(&self) -> usize {
+        self.evictions.count
+    }
+
+    /// How many lines the terminal printed above its screen, counting the
+    /// ones evicted from the scrollback, for converting between grid points
+    /// and [`ScrollbackPoint`]s.
+    pub(crate) fn lines_above_screen(&mut self, term: &Term<ZedListener>) -> usize {
+        self.evictions.update(term);
+        self.evictions.count + term.grid().history_size()
+    }
+}
+
+/// A PTY with the shell's output read through a [`MarkReader`].
+pub(crate) struct ShellIntegrationPty<P> {
+    pty: P,
+    reader: MarkReader,
+}
+
+impl<P: EventedReadWrite> ShellIntegrationPty<P> {
+    pub(crate) fn new(
+        pty: P,
+        term: Arc<FairMutex<Term<ZedListener>>>,
+        listener: ZedListener,
+        marks: Arc<Mutex<ShellMarks>>,
+        images: Arc<Mutex<TerminalImages>>,
+        output: Arc<Mutex<OutputCapture>>,
+    ) -> io::Result<Self> {
+        let (wake_rx, wake_tx) = UnixStream::pair()?;
+        wake_rx.set_nonblocking(true)?;
+        wake_tx.set_nonblocking(true)?;
+        let reader = MarkReader {
+            images: ImageFilter::new(images, listener.clone()),
+            parser: OscParser::default(),
+            unread: Vec::new(),
+            wake_rx,
+            wake_tx,
+            term,
+            listener,
+            marks,
+            output,
+        };
+        Ok(Self { pty, reader })
+    }
+}
+
+impl<P: EventedReadWrite> EventedReadWrite for ShellIntegrationPty<P> {
+    type Reader = Self;
+    type Writer = P::Writer;
+
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: PollEvent,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.register(poll, interest, mode)?;
+        poll.add_with_mode(
+            &self.reader.wake_rx,
+            PollEvent::readable(interest.key),
+            mode,
+        )
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: PollEvent,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.reregister(poll, interest, mode)?;
+        poll.modify_with_mode(
+            &self.reader.wake_rx,
+            PollEvent::readable(interest.key),
+            mode,
+        )
+    }
+
+    fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+        self.pty.deregister(poll)?;
+        poll.delete(&self.reader.wake_rx)
+    }
+
+    fn reader(&mut self) -> &mut Self {
+        self
+    }
+
+    fn writer(&mut self) -> &mut P::Writer {
+        self.pty.writer()
+    }
+}
+
+impl<P: EventedReadWrite> Read for ShellIntegrationPty<P> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read_from(self.pty.reader(), buf)
+    }
+}
+
+impl<P: EventedPty> EventedPty for ShellIntegrationPty<P> {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        self.pty.next_child_event()
+    }
+}
+
+impl<P: EventedReadWrite + OnResize> OnResize for ShellIntegrationPty<P> {
+    fn on_resize(&mut self, window_size: WindowSize) {
+        self.reader.images.resize(window_size);
+        self.pty.on_resize(window_size)
+    }
+}
+
+/// Reads the shell's output, stopping after each mark so that it can record
+/// where alacritty's cursor is once alacritty has parsed everything before it.
+///
+/// Alacritty's event loop parses each read before reading again, but holds the
+/// terminal's lock until the shell has nothing more to read, so the reader
+/// reports that it would block to have the lock released, and wakes the event
+/// loop up through a socket to carry on reading.
+pub(crate) struct MarkReader {
+    images: ImageFilter,
+    parser: OscParser,
+    /// Output with images cut out, returned by the next reads.
+    unread: Vec<u8>,
+    wake_rx: UnixStream,
+    wake_tx: UnixStream,
+    term: Arc<FairMutex<Term<ZedListener>>>,
+    listener: ZedListener,
+    marks: Arc<Mutex<ShellMarks>>,
+    output: Arc<Mutex<OutputCapture>>,
+}
+
+impl MarkReader {
+    /// Records where the pending mark is, if there is one and alacritty has
+    /// parsed the output before it. Returns whether reading can go on.
+    fn resolve_pending_mark(&mut self) -> bool {
+        let parsed = match &mut self.marks.lock().unwrap().pending {
+            Some(pending) => mem::replace(&mut pending.parsed, true),
+            None => return true,
+        };
+        if parsed {
+            if let Some(term) = self.term.try_lock_unfair() {
+                let mut marks = self.marks.lock().unwrap();
+                let lines_above = marks.lines_above_screen(&term);
+                let position = ScrollbackPoint::from_grid(term.grid().cursor.point, lines_above);
+                drop(term);
+
+                if let Some(pending) = marks.pending.take() {
+                    marks.resolved.push(ResolvedMark {
+                        mark: pending.mark,
+                        position,
+                        time: pending.time,
+                    });
+                }
+                drop(marks);
+                self.listener.send_event(AlacTermEvent::Wakeup);
+                return true;
+            }
+        }
+        self.wake_tx.write_all(&[0]).ok();
+        false
+    }
+
+    fn read_from(&mut self, source: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+        let mut wake_bytes = [0; 64];
+        while matches!(self.wake_rx.read(&mut wake_bytes), Ok(len) if len > 0) {}
+
+        if !self.resolve_pending_mark() {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+
+        // Images are cut out of the output first, so that the marks are
+        // found in what alacritty parses.
+        while self.unread.is_empty() {
+            let len = source.read(buf)?;
+            if len == 0 {
+                return Ok(0);
+            }
+            self.images.filter(&buf[..len], &mut self.unread);
+        }
+        let len = self.unread.len().min(buf.len());
+        buf[..len].copy_from_slice(&self.unread[..len]);
+        self.unread.drain(..len);
+        for (ix, byte) in buf[..len].iter().enumerate() {
+            if let Some(mark) = self.parser.advance(*byte) {
+                let mut output = self.output.lock().unwrap();
+                output.push(&buf[..ix + 1]);
+                output.push_mark(&mark);
+                drop(output);
+
+                self.marks.lock().unwrap().pending = Some(PendingMark {
+                    mark,
+                    time: Instant::now(),
+                    parsed: false,
+                });
+                self.unread.splice(0..0, buf[ix + 1..len].iter().copied());
+                return Ok(ix + 1);
+            }
+        }
+        self.output.lock().unwrap().push(&buf[..len]);
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TerminalSize;
+    use alacritty_terminal::{
+        term::Config,
+        vte::ansi::{ClearMode, Handler},
+    };
+    use futures::channel::mpsc::unbounded;
+
+    fn parse(output: &str) -> Vec<ShellMark> {
+        let mut parser = OscParser::default();
+        output
+            .bytes()
+            .filter_map(|byte| parser.advance(byte))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_marks() {
+        assert_eq!(
+            parse(concat!(
+                "\x1b]7;file://host/home/me/my%20project\x07",
+                "\x1b]133;A\x07$ \x1b]133;B\x1b\\",
+                "\x1b]633;E;echo a\\x3bb \\\\\x07",
+                "\x1b]133;C\x07",
+                "a;b \\\r\n",
+                "\x1b]133;D;1\x07",
+                "\x1b]633;P;Cwd=/tmp\x07",
+                "\x1b]0;title\x07\x1b[31m\x1b]133;D\x07",
+            )),
+            [
+                ShellMark::WorkingDirectory("/home/me/my project".into()),
+                ShellMark::PromptStart,
+                ShellMark::CommandStart,
+                ShellMark::CommandLine("echo a;b \\".into()),
+                ShellMark::CommandExecuted,
+                ShellMark::CommandFinished { exit_code: Some(1) },
+                ShellMark::WorkingDirectory("/tmp".into()),
+                ShellMark::CommandFinished { exit_code: None },
+            ]
+        );
+
+        // An escape sequence interrupting the mark cancels it.
+        assert_eq!(
+            parse("\x1b]133;A\x1b[m\x1b]133;B\x07"),
+            [ShellMark::CommandStart]
+        );
+    }
+
+    #[test]
+    fn test_shell_commands() {
+        let start = Instant::now();
+        let point = |line| ScrollbackPoint {
+            line,
+            column: Column(0),
+        };
+        let mark = |mark, line, elapsed| ResolvedMark {
+            mark,
+            position: point(line),
+            time: start + Duration::from_millis(elapsed),
+        };
+
+        let mut commands = ShellCommands::default();
+        commands.apply(mark(ShellMark::WorkingDirectory("/a".into()), 0, 0));
+        commands.apply(mark(ShellMark::PromptStart, 0, 0));
+        commands.apply(mark(ShellMark::CommandStart, 0, 0));
+        commands.apply(mark(ShellMark::CommandLine("false".into()), 0, 10));
+        commands.apply(mark(ShellMark::CommandExecuted, 1, 10));
+        commands.apply(mark(
+            ShellMark::CommandFinished { exit_code: Some(2) },
+            3,
+            30,
+        ));
+        commands.apply(mark(ShellMark::WorkingDirectory("/b".into()), 3, 30));
+        commands.apply(mark(ShellMark::PromptStart, 3, 30));
+        // A prompt that's left without running anything doesn't finish.
+        commands.apply(mark(
+            ShellMark::CommandFinished { exit_code: Some(0) },
+            4,
+            40,
+        ));
+        commands.apply(mark(ShellMark::PromptStart, 4, 40));
+
+        let [failed, empty, current] = commands.commands() else {
+            panic!("unexpected commands {:?}", commands.commands());
+        };
+        assert_eq!(failed.command.as_deref(), Some("false"));
+        assert_eq!(failed.status(), CommandStatus::Failed(2));
+        assert_eq!(failed.output_start, Some(point(1)));
+        assert_eq!(failed.output_end, Some(point(3)));
+        assert_eq!(failed.duration(), Some(Duration::from_millis(20)));
+        assert_eq!(failed.working_directory, Some("/a".into()));
+        assert_eq!(empty.status(), CommandStatus::Pending);
+        assert_eq!(empty.working_directory, Some("/b".into()));
+        assert_eq!(current.prompt, point(4));
+
+        commands.forget_evicted(2);
+        assert_eq!(commands.commands().len(), 2);
+        assert_eq!(commands.commands()[0].prompt, point(3));
+
+        commands.clear(0);
+        assert_eq!(commands.commands().len(), 1);
+        assert_eq!(commands.commands()[0].prompt, point(0));
+    }
+
+    #[test]
+    fn test_evictions() {
+        let (events_tx, _events_rx) = unbounded();
+        let config = Config {
+            scrolling_history: 4,
+            ..Default::default()
+        };
+        let mut term = Term::new(config, &TerminalSize::default(), ZedListener(events_tx));
+        let mut marks = ShellMarks::default();
+        let print_lines = |term: &mut Term<ZedListener>, count: usize| {
+            for _ in 0..count {
+                term.input('x');
+                term.carriage_return();
+                term.linefeed();
+            }
+        };
+
+        // Fill the screen, and then print a line more each time.
+        let screen_lines = term.screen_lines();
+        print_lines(&mut term, screen_lines - 1);
+        assert_eq!(marks.lines_above_screen(&term), 0);
+        for lines_above in 1..=12 {
+            print_lines(&mut term, 1);
+            assert_eq!(marks.lines_above_screen(&term), lines_above);
+        }
+        assert_eq!(term.grid().history_size(), 4);
+        assert_eq!(marks.evicted_lines(), 8);
+
+        // Several lines can scroll by between updates.
+        print_lines(&mut term, 3);
+        assert_eq!(marks.lines_above_screen(&term), 15);
+        assert_eq!(marks.evicted_lines(), 11);
+
+        // Nothing is put in the grid to keep track of the scrollback.
+        let topmost_line = term.grid().topmost_line().0;
+        let links = (topmost_line..term.screen_lines() as i32)
+            .flat_map(|line| (0..term.columns()).map(move |column| (line, column)))
+            .filter(|(line, column)| {
+                term.grid()[AlacPoint::new(Line(*line), Column(*column))]
+                    .hyperlink()
+                    .is_some()
+            })
+            .count();
+        assert_eq!(links, 0);
+
+        // Clearing the scrollback doesn't change how many lines were printed.
+        term.clear_screen(ClearMode::Saved);
+        assert_eq!(term.grid().history_size(), 0);
+        assert_eq!(marks.lines_above_screen(&term), 15);
+        print_lines(&mut term, 2);
+        assert_eq!(marks.lines_above_screen(&term), 17);
+    }
+}
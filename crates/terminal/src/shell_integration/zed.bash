@@ -0,0 +1,62 @@
+# Zed's shell integration for bash, loaded through `--init-file`. It reports
+# prompts, commands and their exit codes with OSC 133 and OSC 633 sequences,
+# and the working directory with OSC 7.
+
+# `--init-file` replaces the user's startup files, so load them first.
+if [ -n "$ZED_BASH_LOGIN" ]; then
+    [ -r /etc/profile ] && . /etc/profile
+    if [ -r ~/.bash_profile ]; then
+        . ~/.bash_profile
+    elif [ -r ~/.bash_login ]; then
+        . ~/.bash_login
+    elif [ -r ~/.profile ]; then
+        . ~/.profile
+    fi
+else
+    [ -r /etc/bash.bashrc ] && . /etc/bash.bashrc
+    [ -r ~/.bashrc ] && . ~/.bashrc
+fi
+unset ZED_BASH_LOGIN
+
+__zed_escape() {
+    local value=${1//\\/\\\\}
+    value=${value//;/\\x3b}
+    printf '%s' "${value//$'\n'/\\x0a}"
+}
+
+__zed_precmd() {
+    local exit_code=$?
+    if [ -n "$__zed_command_running" ]; then
+        printf '\e]133;D;%s\a' "$exit_code"
+        unset __zed_command_running
+    fi
+    printf '\e]7;file://%s%s\a' "$HOSTNAME" "$PWD"
+    if [[ "$PS1" != *'133;A'* ]]; then
+        PS1='\[\e]133;A\a\]'"$PS1"'\[\e]133;B\a\]'
+    fi
+}
+
+# Runs after everything else in PROMPT_COMMAND, so that the DEBUG trap only
+# treats what the user runs at the prompt as a command.
+__zed_prompt_ready() {
+    __zed_at_prompt=1
+}
+
+__zed_preexec() {
+    [ -n "$__zed_at_prompt" ] || return
+    [ -n "$COMP_LINE" ] && return
+    # An empty command line runs PROMPT_COMMAND straight away.
+    [[ "$BASH_COMMAND" == __zed_precmd* ]] && return
+    unset __zed_at_prompt
+    __zed_command_running=1
+
+    local command=$BASH_COMMAND
+    if [[ "$(HISTTIMEFORMAT= builtin history 1)" =~ ^\ *[0-9]+\*?\ +(.*)$ ]]; then
+        command=${BASH_REMATCH[1]}
+    fi
+    printf '\e]633;E;%s\a' "$(__zed_escape "$command")"
+    printf '\e]133;C\a'
+}
+
+PROMPT_COMMAND="__zed_precmd${PROMPT_COMMAND:+;$PROMPT_COMMAND};__zed_prompt_ready"
+trap '__zed_preexec' DEBUG
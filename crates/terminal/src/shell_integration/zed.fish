@@ -0,0 +1,47 @@
+# Zed's shell integration for fish, loaded from vendor_conf.d through
+# XDG_DATA_DIRS. It reports prompts, commands and their exit codes with
+# OSC 133 and OSC 633 sequences, and the working directory with OSC 7.
+
+if test -n "$ZED_ORIGINAL_XDG_DATA_DIRS"
+    set -gx --path XDG_DATA_DIRS $ZED_ORIGINAL_XDG_DATA_DIRS
+else
+    set -e XDG_DATA_DIRS
+end
+set -e ZED_ORIGINAL_XDG_DATA_DIRS
+
+status is-interactive; or exit
+
+function __zed_escape
+    string replace -a '\\' '\\\\' -- $argv | string replace -a ';' '\\x3b' | string join '\\x0a'
+end
+
+function __zed_prompt_start --on-event fish_prompt
+    if set -q __zed_command_running
+        printf '\e]133;D;%s\a' $__zed_last_status
+        set -e __zed_command_running
+    end
+    printf '\e]7;file://%s%s\a' $hostname $PWD
+    printf '\e]133;A\a'
+end
+
+# vendor_conf.d is loaded before config.fish, so wait for the user's prompt
+# to be defined before marking where it ends.
+function __zed_wrap_prompt --on-event fish_prompt
+    functions -e __zed_wrap_prompt
+    functions -q fish_prompt; or return
+    functions -c fish_prompt __zed_user_fish_prompt
+    function fish_prompt
+        __zed_user_fish_prompt
+        printf '\e]133;B\a'
+    end
+end
+
+function __zed_preexec --on-event fish_preexec
+    set -g __zed_command_running 1
+    printf '\e]633;E;%s\a' (__zed_escape $argv)
+    printf '\e]133;C\a'
+end
+
+function __zed_postexec --on-event fish_postexec
+    set -g __zed_last_status $status
+end
@@ -1,20 +1,25 @@
 pub mod mappings;
 pub use alacritty_terminal;
+mod images;
+mod output;
+mod remote_pty;
+pub mod session;
+mod shell_integration;
 pub mod terminal_settings;
 
 use alacritty_terminal::{
-    event::{Event as AlacTermEvent, EventListener, Notify, WindowSize},
+    event::{Event as AlacTermEvent, EventListener, Notify, OnResize, WindowSize},
     event_loop::{EventLoop, Msg, Notifier},
     grid::{Dimensions, Scroll as AlacScroll},
     index::{Boundary, Column, Direction as AlacDirection, Line, Point as AlacPoint},
     selection::{Selection, SelectionRange, SelectionType},
     sync::FairMutex,
     term::{
-        cell::Cell,
+        cell::{Cell, Flags, Hyperlink},
         search::{Match, RegexIter, RegexSearch},
         Config, RenderableCursor, TermMode,
     },
-    tty::{self, setup_env},
+    tty::{self, setup_env, EventedPty},
     vte::ansi::{ClearMode, Handler, NamedPrivateMode, PrivateMode, Rgb},
     Term,
 };
@@ -31,12 +36,16 @@ use mappings::mouse::{
 };
 
 use collections::{HashMap, VecDeque};
+use images::{is_image_anchor, TerminalImages};
+use output::OutputCapture;
 use procinfo::LocalProcessInfo;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
+use shell_integration::{ShellCommands, ShellIntegrationPty, ShellMarks};
+use task::TaskId;
 use terminal_settings::{AlternateScroll, Shell, TerminalBlink, TerminalSettings};
 use theme::{ActiveTheme, Theme};
-use util::truncate_and_trailoff;
+use util::{truncate_and_trailoff, ResultExt};
 
 use std::{
     cmp::{self, min},
@@ -44,7 +53,7 @@ use std::{
     ops::{Deref, Index, RangeInclusive},
     os::unix::prelude::AsRawFd,
     path::PathBuf,
-    sync::Arc,
+    sync::{Arc, Mutex},
     time::Duration,
 };
 use thiserror::Error;
@@ -57,9 +66,25 @@ use gpui::{
 
 use crate::mappings::{colors::to_alac_rgb, keys::to_esc_str};
 
+pub use images::ImagePlacement;
+pub use output::TerminalOutput;
+pub use remote_pty::{RemotePty, RemotePtyInput};
+pub use shell_integration::{CommandStatus, ScrollbackPoint, ShellCommand};
+
 actions!(
     terminal,
-    [Clear, Copy, Paste, ShowCharacterPalette, SearchTest,]
+    [
+        Clear,
+        Copy,
+        Paste,
+        ShowCharacterPalette,
+        SearchTest,
+        ScrollToPreviousPrompt,
+        ScrollToNextPrompt,
+        CopyLastCommandOutput,
+        ToggleOutputFilter,
+        CopyMatchingLines,
+    ]
 );
 
 ///Scrolling is unbearably sluggish by default. Alacritty supports a configurable
@@ -67,6 +92,8 @@ actions!(
 ///Implement scroll bars.
 const SCROLL_MULTIPLIER: f32 = 4.;
 const MAX_SEARCH_LINES: usize = 100;
+/// How many lines of the scrollback are searched while holding the terminal's lock.
+const SEARCH_CHUNK_LINES: usize = 1000;
 const DEBUG_TERMINAL_WIDTH: Pixels = px(500.);
 const DEBUG_TERMINAL_HEIGHT: Pixels = px(30.);
 const DEBUG_CELL_WIDTH: Pixels = px(5.);
@@ -84,6 +111,7 @@ pub enum Event {
     SelectionsChanged,
     NewNavigationTarget(Option<MaybeNavigationTarget>),
     Open(MaybeNavigationTarget),
+    TaskFinished,
 }
 
 #[derive(Clone, Debug)]
@@ -113,6 +141,8 @@ enum InternalEvent {
     // FocusNextMatch,
     Scroll(AlacScroll),
     ScrollToAlacPoint(AlacPoint),
+    ScrollToPreviousPrompt,
+    ScrollToNextPrompt,
     SetSelection(Option<(Selection, AlacPoint)>),
     UpdateSelection(Point<Pixels>),
     // Adjusted mouse position, should open
@@ -289,24 +319,23 @@ impl TerminalBuilder {
         env: HashMap<String, String>,
         blink_settings: Option<TerminalBlink>,
         alternate_scroll: AlternateScroll,
+        shell_integration: bool,
+        persistent: bool,
+        profile: Option<String>,
+        task: Option<TaskState>,
         window: AnyWindowHandle,
     ) -> Result<TerminalBuilder> {
-        let pty_options = {
-            let alac_shell = match shell.clone() {
-                Shell::System => None,
-                Shell::Program(program) => {
-                    Some(alacritty_terminal::tty::Shell::new(program, Vec::new()))
-                }
-                Shell::WithArguments { program, args } => {
-                    Some(alacritty_terminal::tty::Shell::new(program, args))
-                }
-            };
+        let mut env = env;
+        let shell = if shell_integration {
+            shell_integration::inject(shell, &mut env)
+        } else {
+            shell
+        };
 
-            alacritty_terminal::tty::Options {
-                shell: alac_shell,
-                working_directory: working_directory.clone(),
-                hold: false,
-            }
+        let shell_command = match shell.clone() {
+            Shell::System => None,
+            Shell::Program(program) => Some((program, Vec::new())),
+            Shell::WithArguments { program, args } => Some((program, args)),
         };
 
         // First, setup Alacritty's env
@@ -320,32 +349,39 @@ impl TerminalBuilder {
         std::env::set_var("LC_ALL", "en_US.UTF-8");
         std::env::set_var("ZED_TERM", "true");
 
-        let config = Config {
-            scrolling_history: 10000,
-            ..Default::default()
-        };
-
         //Spawn a task so the Alacritty EventLoop can communicate with us in a view context
         //TODO: Remove with a bounded sender which can be dispatched on &self
         let (events_tx, events_rx) = unbounded();
-        //Set up the terminal...
-        let mut term = Term::new(
-            config,
-            &TerminalSize::default(),
-            ZedListener(events_tx.clone()),
-        );
-
-        //Start off blinking if we need to
-        if let Some(TerminalBlink::On) = blink_settings {
-            term.set_private_mode(PrivateMode::Named(NamedPrivateMode::BlinkingCursor));
-        }
-
-        //Alacritty defaults to alternate scrolling being on, so we just need to turn it off.
-        if let AlternateScroll::Off = alternate_scroll {
-            term.unset_private_mode(PrivateMode::Named(NamedPrivateMode::AlternateScroll));
+        let term = Self::new_term(blink_settings, alternate_scroll, &events_tx);
+
+        // Persistent sessions run the shell under a daemon, which inherits the
+        // environment set up above.
+        if persistent {
+            let (session_id, pty) = session::spawn(
+                shell_command,
+                working_directory,
+                TerminalSize::default().into(),
+            )?;
+            let shell_pid = pty.shell_pid();
+            return Self::start(
+                term,
+                pty,
+                events_tx,
+                events_rx,
+                None,
+                Some(shell_pid),
+                Some(session_id),
+                profile,
+                task,
+            );
         }
 
-        let term = Arc::new(FairMutex::new(term));
+        let pty_options = alacritty_terminal::tty::Options {
+            shell: shell_command
+                .map(|(program, args)| alacritty_terminal::tty::Shell::new(program, args)),
+            working_directory: working_directory.clone(),
+            hold: false,
+        };
 
         //Setup the pty...
         let pty = match tty::new(
@@ -365,13 +401,128 @@ impl TerminalBuilder {
 
         let fd = pty.file().as_raw_fd();
         let shell_pid = pty.child().id();
+        Self::start(
+            term,
+            pty,
+            events_tx,
+            events_rx,
+            Some(fd as u32),
+            Some(shell_pid),
+            None,
+            profile,
+            task,
+        )
+    }
+
+    /// Attaches to a persistent session started by an earlier terminal, which
+    /// replays the shell's recent output, keeping the profile it was started with.
+    pub fn attach(
+        session_id: String,
+        blink_settings: Option<TerminalBlink>,
+        alternate_scroll: AlternateScroll,
+        profile: Option<String>,
+    ) -> Result<TerminalBuilder> {
+        let pty = session::SessionPty::attach(&session_id)?;
+        let (events_tx, events_rx) = unbounded();
+        let term = Self::new_term(blink_settings, alternate_scroll, &events_tx);
+        let shell_pid = pty.shell_pid();
+        Self::start(
+            term,
+            pty,
+            events_tx,
+            events_rx,
+            None,
+            Some(shell_pid),
+            Some(session_id),
+            profile,
+            None,
+        )
+    }
+
+    /// Starts a terminal whose shell runs on another machine. Returns the other
+    /// end of its PTY, through which the shell's output is passed to it, along
+    /// with what the terminal sends to the shell.
+    pub fn remote(
+        blink_settings: Option<TerminalBlink>,
+        alternate_scroll: AlternateScroll,
+        profile: Option<String>,
+        task: Option<TaskState>,
+    ) -> Result<(
+        TerminalBuilder,
+        RemotePty,
+        UnboundedReceiver<RemotePtyInput>,
+    )> {
+        let (remote_pty, pty, input_rx) = RemotePty::new()?;
+        let (events_tx, events_rx) = unbounded();
+        let term = Self::new_term(blink_settings, alternate_scroll, &events_tx);
+        let builder = Self::start(
+            term, pty, events_tx, events_rx, None, None, None, profile, task,
+        )?;
+        Ok((builder, remote_pty, input_rx))
+    }
+
+    fn new_term(
+        blink_settings: Option<TerminalBlink>,
+        alternate_scroll: AlternateScroll,
+        events_tx: &UnboundedSender<AlacTermEvent>,
+    ) -> Arc<FairMutex<Term<ZedListener>>> {
+        let config = Config {
+            scrolling_history: 10000,
+            ..Default::default()
+        };
+
+        //Set up the terminal...
+        let mut term = Term::new(
+            config,
+            &TerminalSize::default(),
+            ZedListener(events_tx.clone()),
+        );
+
+        //Start off blinking if we need to
+        if let Some(TerminalBlink::On) = blink_settings {
+            term.set_private_mode(PrivateMode::Named(NamedPrivateMode::BlinkingCursor));
+        }
+
+        //Alacritty defaults to alternate scrolling being on, so we just need to turn it off.
+        if let AlternateScroll::Off = alternate_scroll {
+            term.unset_private_mode(PrivateMode::Named(NamedPrivateMode::AlternateScroll));
+        }
+
+        Arc::new(FairMutex::new(term))
+    }
+
+    fn start<P>(
+        term: Arc<FairMutex<Term<ZedListener>>>,
+        pty: P,
+        events_tx: UnboundedSender<AlacTermEvent>,
+        events_rx: UnboundedReceiver<AlacTermEvent>,
+        shell_fd: Option<u32>,
+        shell_pid: Option<u32>,
+        session_id: Option<String>,
+        profile: Option<String>,
+        task: Option<TaskState>,
+    ) -> Result<TerminalBuilder>
+    where
+        P: EventedPty + OnResize + Send + 'static,
+    {
+        let shell_marks = Arc::new(Mutex::new(ShellMarks::default()));
+        let images = Arc::new(Mutex::new(TerminalImages::default()));
+        let output = Arc::new(Mutex::new(OutputCapture::default()));
+        let pty = ShellIntegrationPty::new(
+            pty,
+            term.clone(),
+            ZedListener(events_tx.clone()),
+            shell_marks.clone(),
+            images.clone(),
+            output.clone(),
+        )?;
 
         //And connect them together
         let event_loop = EventLoop::new(
             term.clone(),
             ZedListener(events_tx.clone()),
             pty,
-            pty_options.hold,
+            false,
             false,
         )?;
 
@@ -390,8 +541,9 @@ impl TerminalBuilder {
             last_mouse: None,
             matches: Vec::new(),
             selection_head: None,
-            shell_fd: fd as u32,
+            shell_fd,
             shell_pid,
+            session_id,
             foreground_process_info: None,
             breadcrumb_text: String::new(),
             scroll_px: px(0.),
@@ -402,6 +554,12 @@ impl TerminalBuilder {
             hovered_word: false,
             url_regex,
             word_regex,
+            profile,
+            task,
+            shell_marks,
+            shell_commands: ShellCommands::default(),
+            images,
+            output,
         };
 
         Ok(TerminalBuilder {
@@ -481,6 +639,13 @@ pub struct IndexedCell {
     pub cell: Cell,
 }
 
+impl IndexedCell {
+    /// The cell's hyperlink, unless it's where an image is anchored.
+    pub fn hyperlink(&self) -> Option<Hyperlink> {
+        self.cell.hyperlink().filter(|link| !is_image_anchor(link))
+    }
+}
+
 impl Deref for IndexedCell {
     type Target = Cell;
 
@@ -502,6 +667,16 @@ pub struct TerminalContent {
     pub cursor_char: char,
     pub size: TerminalSize,
     pub last_hovered_word: Option<HoveredWord>,
+    pub command_marks: Vec<CommandMark>,
+    /// The images printed in the visible part of the terminal.
+    pub images: Vec<ImagePlacement>,
+}
+
+/// A prompt the shell reported, in the visible part of the terminal.
+#[derive(Clone, Copy, Debug)]
+pub struct CommandMark {
+    pub line: Line,
+    pub status: CommandStatus,
 }
 
 #[derive(Clone)]
@@ -526,6 +701,8 @@ impl Default for TerminalContent {
             cursor_char: Default::default(),
             size: Default::default(),
             last_hovered_word: None,
+            command_marks: Vec::new(),
+            images: Vec::new(),
         }
     }
 }
@@ -548,8 +725,11 @@ pub struct Terminal {
     pub last_content: TerminalContent,
     pub selection_head: Option<AlacPoint>,
     pub breadcrumb_text: String,
-    shell_pid: u32,
-    shell_fd: u32,
+    /// The shell's process, unless it runs on another machine.
+    shell_pid: Option<u32>,
+    /// The PTY's file, unless the shell runs in a persistent session.
+    shell_fd: Option<u32>,
+    session_id: Option<String>,
     pub foreground_process_info: Option<LocalProcessInfo>,
     scroll_px: Pixels,
     next_link_id: usize,
@@ -558,6 +738,26 @@ pub struct Terminal {
     hovered_word: bool,
     url_regex: RegexSearch,
     word_regex: RegexSearch,
+    profile: Option<String>,
+    task: Option<TaskState>,
+    shell_marks: Arc<Mutex<ShellMarks>>,
+    shell_commands: ShellCommands,
+    images: Arc<Mutex<TerminalImages>>,
+    output: Arc<Mutex<OutputCapture>>,
+}
+
+/// A task spawned in the terminal, which keeps the terminal open once it exits.
+#[derive(Clone, Debug)]
+pub struct TaskState {
+    pub id: TaskId,
+    pub label: String,
+    pub status: TaskStatus,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskStatus {
+    Running,
+    Completed,
 }
 
 impl Terminal {
@@ -589,11 +789,29 @@ impl Terminal {
             AlacTermEvent::Bell => {
                 cx.emit(Event::Bell);
             }
-            AlacTermEvent::Exit => cx.emit(Event::CloseTerminal),
+            AlacTermEvent::Exit => {
+                // A session ends along with its shell.
+                self.session_id = None;
+                match &mut self.task {
+                    Some(task) => {
+                        task.status = TaskStatus::Completed;
+                        cx.emit(Event::TaskFinished);
+                    }
+                    None => cx.emit(Event::CloseTerminal),
+                }
+            }
             AlacTermEvent::MouseCursorDirty => {
                 //NOOP, Handled in render
             }
             AlacTermEvent::Wakeup => {
+                let mut shell_marks = self.shell_marks.lock().unwrap();
+                let marks = shell_marks.take_resolved();
+                let evicted_lines = shell_marks.evicted_lines();
+                drop(shell_marks);
+                for mark in marks {
+                    self.shell_commands.apply(mark);
+                }
+                self.shell_commands.forget_evicted(evicted_lines);
                 cx.emit(Event::Wakeup);
 
                 if self.update_process_info() {
@@ -613,9 +831,14 @@ impl Terminal {
 
     /// Updates the cached process info, returns whether the Zed-relevant info has changed
     fn update_process_info(&mut self) -> bool {
-        let mut pid = unsafe { libc::tcgetpgrp(self.shell_fd as i32) };
+        let mut pid = self
+            .shell_fd
+            .map_or(-1, |fd| unsafe { libc::tcgetpgrp(fd as i32) });
         if pid < 0 {
-            pid = self.shell_pid as i32;
+            let Some(shell_pid) = self.shell_pid else {
+                return false;
+            };
+            pid = shell_pid as i32;
         }
 
         if let Some(process_info) = LocalProcessInfo::with_root_pid(pid as u32) {
@@ -695,6 +918,9 @@ impl Terminal {
                     term.grid_mut().reset_region((new_cursor.line + 1)..);
                 }
 
+                let lines_above = self.shell_marks.lock().unwrap().lines_above_screen(term);
+                self.shell_commands.clear(lines_above);
+
                 cx.emit(Event::Wakeup);
             }
             InternalEvent::Scroll(scroll) => {
@@ -734,6 +960,31 @@ impl Terminal {
                 term.scroll_to_point(*point);
                 self.refresh_hovered_word();
             }
+            InternalEvent::ScrollToPreviousPrompt | InternalEvent::ScrollToNextPrompt => {
+                let lines_above = self.shell_marks.lock().unwrap().lines_above_screen(term);
+                let top_line = lines_above - term.grid().display_offset();
+                let mut prompt_lines = self
+                    .shell_commands
+                    .commands()
+                    .iter()
+                    .map(|command| command.prompt.line);
+                let prompt_line = if let InternalEvent::ScrollToPreviousPrompt = event {
+                    prompt_lines.filter(|line| *line < top_line).last()
+                } else {
+                    prompt_lines.find(|line| *line > top_line)
+                };
+
+                match prompt_line {
+                    Some(line) => {
+                        term.scroll_display(AlacScroll::Delta(top_line as i32 - line as i32))
+                    }
+                    None if matches!(event, InternalEvent::ScrollToNextPrompt) => {
+                        term.scroll_display(AlacScroll::Bottom)
+                    }
+                    None => {}
+                }
+                self.refresh_hovered_word();
+            }
             InternalEvent::FindHyperlink(position, open) => {
                 let prev_hovered_word = self.last_content.last_hovered_word.take();
 
@@ -744,7 +995,11 @@ impl Terminal {
                 )
                 .grid_clamp(term, Boundary::Grid);
 
-                let link = term.grid().index(point).hyperlink();
+                let link = term
+                    .grid()
+                    .index(point)
+                    .hyperlink()
+                    .filter(|link| !is_image_anchor(link));
                 let found_word = if link.is_some() {
                     let mut min_index = point;
                     loop {
@@ -937,6 +1192,16 @@ impl Terminal {
         self.events.push_back(InternalEvent::Clear)
     }
 
+    /// Scrolls the previous prompt reported by the shell to the top of the terminal.
+    pub fn scroll_to_previous_prompt(&mut self) {
+        self.events.push_back(InternalEvent::ScrollToPreviousPrompt)
+    }
+
+    /// Scrolls the next prompt reported by the shell to the top of the terminal.
+    pub fn scroll_to_next_prompt(&mut self) {
+        self.events.push_back(InternalEvent::ScrollToNextPrompt)
+    }
+
     ///Resize the terminal and the PTY.
     pub fn set_size(&mut self, new_size: TerminalSize) {
         self.events.push_back(InternalEvent::Resize(new_size))
@@ -1005,11 +1270,33 @@ impl Terminal {
             self.process_terminal_event(&e, &mut terminal, cx)
         }
 
-        self.last_content = Self::make_content(&terminal, &self.last_content);
+        let mut shell_marks = self.shell_marks.lock().unwrap();
+        let lines_above = shell_marks.lines_above_screen(&terminal);
+        self.shell_commands
+            .forget_evicted(shell_marks.evicted_lines());
+        drop(shell_marks);
+
+        let mut images = self.images.lock().unwrap();
+        images.prune(&terminal);
+        self.last_content = Self::make_content(
+            &terminal,
+            &self.last_content,
+            &self.shell_commands,
+            lines_above,
+            &images,
+        );
     }
 
-    fn make_content(term: &Term<ZedListener>, last_content: &TerminalContent) -> TerminalContent {
+    fn make_content(
+        term: &Term<ZedListener>,
+        last_content: &TerminalContent,
+        shell_commands: &ShellCommands,
+        lines_above: usize,
+        images: &TerminalImages,
+    ) -> TerminalContent {
         let content = term.renderable_content();
+        let top_line = Line(-(content.display_offset as i32));
+        let bottom_line = top_line + term.screen_lines() - 1;
         TerminalContent {
             cells: content
                 .display_iter
@@ -1033,6 +1320,20 @@ impl Terminal {
             cursor_char: term.grid()[content.cursor.point].c,
             size: last_content.size,
             last_hovered_word: last_content.last_hovered_word.clone(),
+            command_marks: shell_commands
+                .commands()
+                .iter()
+                .filter_map(|command| {
+                    let line = command.prompt.to_grid(lines_above).line;
+                    (top_line..=bottom_line)
+                        .contains(&line)
+                        .then(|| CommandMark {
+                            line,
+                            status: command.status(),
+                        })
+                })
+                .collect(),
+            images: images.visible(term),
         }
     }
 
@@ -1293,20 +1594,92 @@ impl Terminal {
         }
     }
 
+    /// Searches the whole scrollback, from the newest lines to the oldest, a chunk
+    /// at a time so that output isn't blocked on long searches. The matches found
+    /// so far are highlighted while the search runs.
+    ///
+    /// Output printed between chunks moves the lines of the grid up, so the search
+    /// keeps track of its matches as [`ScrollbackPoint`]s.
     pub fn find_matches(
         &mut self,
         mut searcher: RegexSearch,
         cx: &mut ModelContext<Self>,
     ) -> Task<Vec<RangeInclusive<AlacPoint>>> {
         let term = self.term.clone();
-        cx.background_executor().spawn(async move {
-            let term = term.lock();
-
-            all_search_matches(&term, &mut searcher).collect()
+        let shell_marks = self.shell_marks.clone();
+        cx.spawn(|this, mut cx| async move {
+            let mut matches = Vec::new();
+            let mut end_line = None;
+            loop {
+                let (mut chunk_matches, start_line, chunk_searcher) = cx
+                    .background_executor()
+                    .spawn({
+                        let term = term.clone();
+                        let shell_marks = shell_marks.clone();
+                        async move {
+                            let term = term.lock();
+                            let (chunk_matches, start_line) = search_scrollback_chunk(
+                                &term,
+                                &mut shell_marks.lock().unwrap(),
+                                &mut searcher,
+                                end_line,
+                            );
+                            (chunk_matches, start_line, searcher)
+                        }
+                    })
+                    .await;
+                searcher = chunk_searcher;
+                chunk_matches.append(&mut matches);
+                matches = chunk_matches;
+
+                let Some(start_line) = start_line.filter(|line| *line > 0) else {
+                    break;
+                };
+                end_line = Some(start_line - 1);
+                this.update(&mut cx, |this, cx| {
+                    this.matches = this.scrollback_matches_to_grid(&matches);
+                    cx.notify();
+                })
+                .ok();
+            }
+            this.update(&mut cx, |this, _| this.scrollback_matches_to_grid(&matches))
+                .unwrap_or_default()
         })
     }
 
+    /// Converts search matches to the current points of the grid, dropping the ones
+    /// evicted from the scrollback.
+    fn scrollback_matches_to_grid(
+        &self,
+        matches: &[RangeInclusive<ScrollbackPoint>],
+    ) -> Vec<RangeInclusive<AlacPoint>> {
+        let term = self.term.lock();
+        let lines_above = self.shell_marks.lock().unwrap().lines_above_screen(&term);
+        let topmost_line = term.grid().topmost_line();
+        matches
+            .iter()
+            .map(|search_match| {
+                search_match.start().to_grid(lines_above)..=search_match.end().to_grid(lines_above)
+            })
+            .filter(|search_match| search_match.start().line >= topmost_line)
+            .collect()
+    }
+
+    /// The lines that contain the current search matches, each once. A line
+    /// wrapped across rows of the grid is returned as a whole.
+    pub fn matching_lines(&self) -> Vec<String> {
+        lines_of_matches(&self.term.lock(), &self.matches)
+    }
+
     pub fn title(&self, truncate: bool) -> String {
+        if let Some(task) = &self.task {
+            return if truncate {
+                truncate_and_trailoff(&task.label, 25)
+            } else {
+                task.label.clone()
+            };
+        }
+
         self.foreground_process_info
             .as_ref()
             .map(|fpi| {
@@ -1340,6 +1713,104 @@ impl Terminal {
     pub fn can_navigate_to_selected_word(&self) -> bool {
         self.cmd_pressed && self.hovered_word
     }
+
+    pub fn task(&self) -> Option<&TaskState> {
+        self.task.as_ref()
+    }
+
+    /// The name of the profile the terminal was started with, if any.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// The persistent session the shell runs in, if it runs in one.
+    pub fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    /// Ends the persistent session the shell runs in, which otherwise keeps
+    /// running once the terminal is dropped.
+    pub fn kill_session(&mut self) {
+        if let Some(session_id) = self.session_id.take() {
+            session::kill(&session_id).log_err();
+        }
+    }
+
+    /// The commands run in the terminal, if its shell reports them.
+    pub fn shell_commands(&self) -> &[ShellCommand] {
+        self.shell_commands.commands()
+    }
+
+    /// Returns the output of the last command that finished, if the shell
+    /// reports where commands' output starts and ends.
+    pub fn last_command_output(&self) -> Option<String> {
+        let command = self
+            .shell_commands
+            .commands()
+            .iter()
+            .rev()
+            .find(|command| command.output_end.is_some())?;
+        let term = self.term.lock();
+        let lines_above = self.shell_marks.lock().unwrap().lines_above_screen(&term);
+        let start = command
+            .output_start?
+            .to_grid(lines_above)
+            .grid_clamp(&*term, Boundary::Grid);
+        let end = command
+            .output_end?
+            .to_grid(lines_above)
+            .grid_clamp(&*term, Boundary::Grid);
+        if end <= start {
+            return Some(String::new());
+        }
+        let end = end.sub(&*term, Boundary::Grid, 1);
+        Some(term.bounds_to_string(start, end))
+    }
+
+    /// Streams the terminal's output from now on, as lines without escape
+    /// sequences, along with the boundaries of the commands run, if the shell
+    /// reports them. The stream ends when the terminal is dropped.
+    pub fn subscribe_to_output(&self) -> UnboundedReceiver<TerminalOutput> {
+        self.output.lock().unwrap().subscribe()
+    }
+
+    /// Returns the text of the whole terminal, including its scrollback.
+    pub fn output_text(&self) -> String {
+        let term = self.term.lock();
+        let start = AlacPoint::new(term.topmost_line(), Column(0));
+        let end = AlacPoint::new(term.bottommost_line(), term.last_column());
+        term.bounds_to_string(start, end)
+    }
+
+    /// Returns the text of the terminal up to its cursor, as the lines finished
+    /// before the cursor's line and what's been printed on the cursor's line so far.
+    pub fn output_text_until_cursor(&self) -> (String, String) {
+        let term = self.term.lock();
+        let cursor = term.grid().cursor.point;
+        let line_start = term.line_search_left(AlacPoint::new(cursor.line, Column(0)));
+        let finished_lines = if line_start.line > term.topmost_line() {
+            let start = AlacPoint::new(term.topmost_line(), Column(0));
+            let end = AlacPoint::new(line_start.line - 1, term.last_column());
+            term.bounds_to_string(start, end)
+        } else {
+            String::new()
+        };
+        // Read the cells themselves, as the line's text would lose the blanks
+        // the cursor moved past.
+        let mut current_line = String::new();
+        let mut point = line_start;
+        while point < cursor {
+            let cell = &term.grid()[point];
+            if !cell
+                .flags
+                .intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+            {
+                current_line.push(cell.c);
+            }
+            point = point.add(&*term, Boundary::Grid, 1);
+        }
+        (finished_lines, current_line)
+    }
 }
 
 impl Drop for Terminal {
@@ -1380,13 +1851,83 @@ fn make_selection(range: &RangeInclusive<AlacPoint>) -> Selection {
     selection
 }
 
-fn all_search_matches<'a, T>(
-    term: &'a Term<T>,
-    regex: &'a mut RegexSearch,
-) -> impl Iterator<Item = Match> + 'a {
-    let start = AlacPoint::new(term.grid().topmost_line(), Column(0));
-    let end = AlacPoint::new(term.grid().bottommost_line(), term.grid().last_column());
-    RegexIter::new(start, end, AlacDirection::Right, term, regex)
+/// Searches up to [`SEARCH_CHUNK_LINES`] lines ending at `end_line`, or at the
+/// bottom of the grid, without splitting wrapped lines. Returns the matches and
+/// the first line searched, unless the chunk reached the top of the scrollback.
+fn search_matches_chunk<T>(
+    term: &Term<T>,
+    regex: &mut RegexSearch,
+    end_line: Option<Line>,
+) -> (Vec<Match>, Option<Line>) {
+    let grid = term.grid();
+    let topmost_line = grid.topmost_line();
+    let end_line = end_line.unwrap_or_else(|| grid.bottommost_line());
+    let mut start_line = (end_line - (SEARCH_CHUNK_LINES - 1)).max(topmost_line);
+    while start_line > topmost_line
+        && grid[start_line - 1][grid.last_column()]
+            .flags
+            .contains(Flags::WRAPLINE)
+    {
+        start_line = start_line - 1;
+    }
+
+    let start = AlacPoint::new(start_line, Column(0));
+    let end = AlacPoint::new(end_line, grid.last_column());
+    let matches = RegexIter::new(start, end, AlacDirection::Right, term, regex).collect();
+    (matches, (start_line > topmost_line).then_some(start_line))
+}
+
+/// Searches the chunk of the scrollback ending at `end_line` with
+/// [`search_matches_chunk`], with lines counted as [`ScrollbackPoint`]s so they
+/// stay valid while output is printed between chunks. Returns the matches and the
+/// first line searched, unless the chunk reached the top of the scrollback or the
+/// rest of it was evicted.
+fn search_scrollback_chunk(
+    term: &Term<ZedListener>,
+    shell_marks: &mut ShellMarks,
+    searcher: &mut RegexSearch,
+    end_line: Option<usize>,
+) -> (Vec<RangeInclusive<ScrollbackPoint>>, Option<usize>) {
+    let lines_above = shell_marks.lines_above_screen(term);
+    let end_line = end_line.map(|line| {
+        ScrollbackPoint {
+            line,
+            column: Column(0),
+        }
+        .to_grid(lines_above)
+        .line
+    });
+    if end_line.map_or(false, |line| line < term.grid().topmost_line()) {
+        return (Vec::new(), None);
+    }
+
+    let (matches, start_line) = search_matches_chunk(term, searcher, end_line);
+    let matches = matches
+        .into_iter()
+        .map(|search_match| {
+            ScrollbackPoint::from_grid(*search_match.start(), lines_above)
+                ..=ScrollbackPoint::from_grid(*search_match.end(), lines_above)
+        })
+        .collect();
+    let start_line = start_line
+        .map(|line| ScrollbackPoint::from_grid(AlacPoint::new(line, Column(0)), lines_above).line);
+    (matches, start_line)
+}
+
+/// The lines containing the matches, sorted from the top of the grid, each once.
+fn lines_of_matches<T>(term: &Term<T>, matches: &[RangeInclusive<AlacPoint>]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut last_line = None;
+    for search_match in matches {
+        if last_line.map_or(false, |line| search_match.start().line <= line) {
+            continue;
+        }
+        let start = term.line_search_left(*search_match.start());
+        let end = term.line_search_right(*search_match.end());
+        lines.push(term.bounds_to_string(AlacPoint::new(start.line, Column(0)), end));
+        last_line = Some(end.line);
+    }
+    lines
 }
 
 fn content_index_for_mouse(pos: Point<Pixels>, size: &TerminalSize) -> usize {
@@ -1485,13 +2026,17 @@ pub fn rgba_color(r: u8, g: u8, b: u8) -> Hsla {
 mod tests {
     use alacritty_terminal::{
         index::{Column, Line, Point as AlacPoint},
-        term::cell::Cell,
+        term::{cell::Cell, search::RegexSearch, Config, Term},
+        vte::ansi::Handler,
     };
+    use futures::channel::mpsc::unbounded;
     use gpui::{point, size, Pixels};
     use rand::{distributions::Alphanumeric, rngs::ThreadRng, thread_rng, Rng};
 
     use crate::{
-        content_index_for_mouse, rgb_for_index, IndexedCell, TerminalContent, TerminalSize,
+        content_index_for_mouse, lines_of_matches, rgb_for_index, search_matches_chunk,
+        search_scrollback_chunk, shell_integration::ShellMarks, IndexedCell, TerminalContent,
+        TerminalSize, ZedListener, SEARCH_CHUNK_LINES,
     };
 
     #[test]
@@ -1503,6 +2048,168 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_search_chunks_keep_wrapped_lines_whole() {
+        let mut term = new_term(10000);
+        let columns = term.columns();
+        print_line(&mut term, "first");
+        print_line(&mut term, &format!("{}needle", "a".repeat(columns - 3)));
+        print_line(&mut term, &format!("needle{}", "b".repeat(2 * columns)));
+        for i in 0..SEARCH_CHUNK_LINES + 10 {
+            print_line(&mut term, &format!("line {i}"));
+        }
+        let wrapped_line = line_containing(&term, "nee");
+        let long_line = wrapped_line + 2;
+        let mut regex = RegexSearch::new("needle").unwrap();
+
+        // A chunk starting in the middle of a wrapped line starts with it instead,
+        // so a match across the rows is found whole, and only once.
+        let (matches, start_line) = search_matches_chunk(
+            &term,
+            &mut regex,
+            Some(wrapped_line + 1 + (SEARCH_CHUNK_LINES - 1)),
+        );
+        assert_eq!(start_line, Some(wrapped_line));
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].start().line, wrapped_line);
+        assert_eq!(matches[0].end().line, wrapped_line + 1);
+        assert_eq!(matches[1].start().line, long_line);
+        let (matches, start_line) = search_matches_chunk(&term, &mut regex, Some(wrapped_line - 1));
+        assert_eq!(matches, Vec::new());
+        assert_eq!(start_line, None);
+
+        // A match in the first row of a line wrapped into the chunk is found too.
+        let (matches, start_line) = search_matches_chunk(
+            &term,
+            &mut regex,
+            Some(long_line + 2 + (SEARCH_CHUNK_LINES - 1)),
+        );
+        assert_eq!(start_line, Some(long_line));
+        assert_eq!(matches.len(), 1);
+        assert_eq!(*matches[0].start(), AlacPoint::new(long_line, Column(0)));
+    }
+
+    #[test]
+    fn test_search_scrollback_with_output_between_chunks() {
+        let mut term = new_term(10000);
+        let mut shell_marks = ShellMarks::default();
+        for i in 0..2500 {
+            if i % 100 == 0 {
+                print_line(&mut term, &format!("needle {i}"));
+            } else {
+                print_line(&mut term, &format!("line {i}"));
+            }
+        }
+        let mut regex = RegexSearch::new("needle").unwrap();
+
+        // Output printed while the scrollback is searched moves the lines up, and
+        // the chunks still cover the lines in between.
+        let mut matches = Vec::new();
+        let mut end_line = None;
+        loop {
+            let (mut chunk_matches, start_line) =
+                search_scrollback_chunk(&term, &mut shell_marks, &mut regex, end_line);
+            chunk_matches.append(&mut matches);
+            matches = chunk_matches;
+            for i in 0..150 {
+                print_line(&mut term, &format!("later {i}"));
+            }
+            let Some(start_line) = start_line.filter(|line| *line > 0) else {
+                break;
+            };
+            end_line = Some(start_line - 1);
+        }
+
+        let lines_above = shell_marks.lines_above_screen(&term);
+        let matching_lines = matches
+            .iter()
+            .map(|search_match| {
+                let start = search_match.start().to_grid(lines_above);
+                let end = term.line_search_right(start);
+                term.bounds_to_string(start, end).trim_end().to_string()
+            })
+            .collect::<Vec<_>>();
+        let needles = (0..2500)
+            .step_by(100)
+            .map(|i| format!("needle {i}"))
+            .collect::<Vec<_>>();
+        assert_eq!(matching_lines, needles);
+
+        // Nothing is left to search once the rest of the scrollback was evicted.
+        let mut term = new_term(1500);
+        let mut shell_marks = ShellMarks::default();
+        for i in 0..2500 {
+            print_line(&mut term, &format!("needle {i}"));
+        }
+        let (_, start_line) = search_scrollback_chunk(&term, &mut shell_marks, &mut regex, None);
+        for i in 0..1200 {
+            print_line(&mut term, &format!("later {i}"));
+        }
+        let (matches, start_line) = search_scrollback_chunk(
+            &term,
+            &mut shell_marks,
+            &mut regex,
+            Some(start_line.unwrap() - 1),
+        );
+        assert_eq!(matches, Vec::new());
+        assert_eq!(start_line, None);
+    }
+
+    #[test]
+    fn test_lines_of_matches() {
+        let mut term = new_term(10000);
+        let wrapped_line = format!("{} needle", "c".repeat(term.columns()));
+        print_line(&mut term, "one needle, two needle");
+        print_line(&mut term, &wrapped_line);
+        print_line(&mut term, "no match");
+        print_line(&mut term, "needle");
+        let mut regex = RegexSearch::new("needle").unwrap();
+        let (matches, _) = search_matches_chunk(&term, &mut regex, None);
+        assert_eq!(matches.len(), 4);
+
+        // Lines with several matches are listed once, and wrapped lines whole.
+        assert_eq!(
+            lines_of_matches(&term, &matches),
+            [
+                "one needle, two needle".to_string(),
+                wrapped_line,
+                "needle".to_string()
+            ]
+        );
+    }
+
+    fn new_term(scrolling_history: usize) -> Term<ZedListener> {
+        let (events_tx, _events_rx) = unbounded();
+        let config = Config {
+            scrolling_history,
+            ..Default::default()
+        };
+        Term::new(config, &TerminalSize::default(), ZedListener(events_tx))
+    }
+
+    fn print_line(term: &mut Term<ZedListener>, text: &str) {
+        for c in text.chars() {
+            term.input(c);
+        }
+        term.carriage_return();
+        term.linefeed();
+    }
+
+    /// The first line of the grid whose first row contains the text.
+    fn line_containing(term: &Term<ZedListener>, text: &str) -> Line {
+        let grid = term.grid();
+        (grid.topmost_line().0..=grid.bottommost_line().0)
+            .map(Line)
+            .find(|line| {
+                term.bounds_to_string(
+                    AlacPoint::new(*line, Column(0)),
+                    AlacPoint::new(*line, grid.last_column()),
+                )
+                .contains(text)
+            })
+            .unwrap()
+    }
+
     #[test]
     fn test_mouse_to_cell_test() {
         let mut rng = thread_rng();
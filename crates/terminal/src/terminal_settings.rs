@@ -31,6 +31,11 @@ pub struct TerminalSettings {
     pub alternate_scroll: AlternateScroll,
     pub option_as_meta: bool,
     pub copy_on_select: bool,
+    pub shell_integration: bool,
+    pub persistent_sessions: bool,
+    pub repl: HashMap<String, ReplSettings>,
+    pub profiles: HashMap<String, TerminalProfile>,
+    pub default_profile: Option<String>,
     pub dock: TerminalDockPosition,
     pub default_width: Pixels,
     pub default_height: Pixels,
@@ -71,6 +76,82 @@ impl VenvSettings {
     }
 }
 
+/// A named way of starting terminals, overriding the settings outside of profiles.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TerminalProfile {
+    /// The shell to run.
+    pub shell: Option<Shell>,
+    /// Variables added to the `env` setting's.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    pub working_directory: Option<WorkingDirectory>,
+    pub font_family: Option<String>,
+    pub font_size: Option<f32>,
+    pub detect_venv: Option<VenvSettings>,
+}
+
+/// The settings a terminal starts with, once its profile is applied.
+#[derive(Clone, Debug)]
+pub struct ResolvedTerminalProfile {
+    /// The profile's name, unless no profile applies.
+    pub name: Option<String>,
+    pub shell: Shell,
+    pub env: HashMap<String, String>,
+    pub working_directory: WorkingDirectory,
+    pub font_family: Option<String>,
+    pub font_size: Option<Pixels>,
+    pub detect_venv: VenvSettings,
+}
+
+impl TerminalSettings {
+    /// Applies the named profile, or the default one when no name is given. A name
+    /// that isn't defined leaves the settings outside of profiles as they are.
+    pub fn profile(&self, name: Option<&str>) -> ResolvedTerminalProfile {
+        let name = name.or(self.default_profile.as_deref());
+        let profile = name.and_then(|name| self.profiles.get(name));
+        let Some(profile) = profile else {
+            return ResolvedTerminalProfile {
+                name: None,
+                shell: self.shell.clone(),
+                env: self.env.clone(),
+                working_directory: self.working_directory.clone(),
+                font_family: self.font_family.clone(),
+                font_size: self.font_size,
+                detect_venv: self.detect_venv.clone(),
+            };
+        };
+
+        let mut env = self.env.clone();
+        env.extend(profile.env.clone());
+        ResolvedTerminalProfile {
+            name: name.map(str::to_string),
+            shell: profile.shell.clone().unwrap_or_else(|| self.shell.clone()),
+            env,
+            working_directory: profile
+                .working_directory
+                .clone()
+                .unwrap_or_else(|| self.working_directory.clone()),
+            font_family: profile
+                .font_family
+                .clone()
+                .or_else(|| self.font_family.clone()),
+            font_size: profile.font_size.map(px).or(self.font_size),
+            detect_venv: profile
+                .detect_venv
+                .clone()
+                .unwrap_or_else(|| self.detect_venv.clone()),
+        }
+    }
+}
+
+/// The command that starts a language's interactive interpreter.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct ReplSettings {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
 #[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ActivateScript {
@@ -131,6 +212,32 @@ pub struct TerminalSettingsContent {
     ///
     /// Default: false
     pub copy_on_select: Option<bool>,
+    /// Whether to load Zed's shell integration into bash, zsh and fish, so
+    /// that the terminal knows where each command's prompt and output are.
+    ///
+    /// Default: true
+    pub shell_integration: Option<bool>,
+    /// Whether to run terminals' shells in sessions that outlive Zed, so that
+    /// terminals restored on restart reattach to their shells.
+    ///
+    /// Default: false
+    pub persistent_sessions: Option<bool>,
+    /// The interactive interpreters that code sent from the editor with
+    /// `terminal_panel::SendToTerminal` runs in, keyed by language name.
+    /// Code in other languages is sent to the active terminal.
+    ///
+    /// Default: {"Python": {"command": "python3"}, "Elixir": {"command": "iex"}}
+    pub repl: Option<HashMap<String, ReplSettings>>,
+    /// Named ways of starting terminals, each with its own shell, environment,
+    /// working directory, font and virtual environment activation.
+    ///
+    /// Default: {}
+    pub profiles: Option<HashMap<String, TerminalProfile>>,
+    /// The profile new terminals start with, unless another one is picked. Set
+    /// this in a project's `.zed/settings.json` to change it for the project.
+    ///
+    /// Default: null
+    pub default_profile: Option<String>,
     pub dock: Option<TerminalDockPosition>,
     /// Default width when the terminal is docked to the left or right.
     ///
@@ -0,0 +1,413 @@
+use std::collections::VecDeque;
+
+use editor::{Editor, EditorEvent};
+use futures::StreamExt;
+use gpui::{
+    uniform_list, AppContext, FocusHandle, FocusableView, Model, Render, SharedString, Task,
+    UniformListScrollHandle, View, ViewContext, VisualContext,
+};
+use regex::Regex;
+use settings::Settings;
+use terminal::{Terminal, TerminalOutput};
+use theme::ThemeSettings;
+use ui::prelude::*;
+
+/// As many lines as the terminal keeps in its scrollback.
+const MAX_LINES: usize = 10000;
+const MAX_BATCH_SIZE: usize = 256;
+
+/// Shows the lines of a terminal's output that match a regex, following the output
+/// as it's printed, without changing the terminal's grid.
+pub struct OutputFilter {
+    query_editor: View<Editor>,
+    scroll_handle: UniformListScrollHandle,
+    regex: Option<Regex>,
+    error: Option<SharedString>,
+    lines: VecDeque<String>,
+    /// The number of lines dropped from the front of `lines`, so that matches can
+    /// refer to lines by a number that doesn't change.
+    dropped_lines: usize,
+    matches: VecDeque<usize>,
+    /// What was printed on the cursor's line when the filter was opened, until
+    /// the rest of the line is streamed.
+    seam: Option<String>,
+    _read_output: Task<()>,
+}
+
+impl OutputFilter {
+    pub fn new(terminal: &Model<Terminal>, cx: &mut ViewContext<Self>) -> Self {
+        let query_editor = cx.new_view(|cx| {
+            let mut editor = Editor::single_line(cx);
+            editor.set_placeholder_text("Filter output by regex…", cx);
+            editor
+        });
+        cx.subscribe(&query_editor, Self::on_query_change).detach();
+
+        // Subscribe before reading the scrollback so that no line printed in
+        // between is missed. Lines printed in between are streamed too, which
+        // `push_streamed_lines` accounts for.
+        let terminal = terminal.read(cx);
+        let mut output = terminal.subscribe_to_output().ready_chunks(MAX_BATCH_SIZE);
+        let (scrollback, current_line) = terminal.output_text_until_cursor();
+        let read_output = cx.spawn(|this, mut cx| async move {
+            while let Some(outputs) = output.next().await {
+                let lines = outputs
+                    .into_iter()
+                    .filter_map(|output| match output {
+                        TerminalOutput::Line(line) => Some(line),
+                        _ => None,
+                    })
+                    .collect();
+                if this
+                    .update(&mut cx, |this, cx| this.push_streamed_lines(lines, cx))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let mut this = Self {
+            query_editor,
+            scroll_handle: UniformListScrollHandle::new(),
+            regex: None,
+            error: None,
+            lines: VecDeque::new(),
+            dropped_lines: 0,
+            matches: VecDeque::new(),
+            seam: Some(current_line),
+            _read_output: read_output,
+        };
+        let scrollback = scrollback.lines().map(|line| line.to_string());
+        this.push_lines(scrollback, cx);
+        this
+    }
+
+    /// The lines that match the filter, in the order they were printed.
+    pub fn matching_lines(&self) -> impl Iterator<Item = &str> {
+        self.matches
+            .iter()
+            .map(|line| self.lines[line - self.dropped_lines].as_str())
+    }
+
+    fn on_query_change(
+        &mut self,
+        _: View<Editor>,
+        event: &EditorEvent,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let EditorEvent::Edited = event {
+            let query = self.query_editor.read(cx).text(cx);
+            self.set_query(&query, cx);
+        }
+    }
+
+    /// Filters the output by the given regex, showing every line when it's empty.
+    pub fn set_query(&mut self, query: &str, cx: &mut ViewContext<Self>) {
+        if query.is_empty() {
+            self.regex = None;
+            self.error = None;
+        } else {
+            match Regex::new(query) {
+                Ok(regex) => {
+                    self.regex = Some(regex);
+                    self.error = None;
+                }
+                // Keep showing the last valid filter's matches while the regex is
+                // being typed.
+                Err(_) => {
+                    self.error = Some("Invalid regex".into());
+                    cx.notify();
+                    return;
+                }
+            }
+        }
+
+        self.matches = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| self.is_match(line))
+            .map(|(ix, _)| ix + self.dropped_lines)
+            .collect();
+        self.scroll_to_end();
+        cx.notify();
+    }
+
+    fn is_match(&self, line: &str) -> bool {
+        self.regex
+            .as_ref()
+            .map_or(true, |regex| regex.is_match(line))
+    }
+
+    fn push_streamed_lines(&mut self, mut lines: Vec<String>, cx: &mut ViewContext<Self>) {
+        if lines.is_empty() {
+            return;
+        }
+        if let Some(current_line) = self.seam.take() {
+            // Skip the lines that were printed after subscribing, but in time to
+            // be read from the scrollback. The first of them may be the rest of a
+            // line that was being printed when subscribing.
+            let overlap = (1..=lines.len().min(self.lines.len()))
+                .rev()
+                .find(|&len| {
+                    let read_lines = self.lines.range(self.lines.len() - len..);
+                    read_lines
+                        .zip(&lines)
+                        .enumerate()
+                        .all(|(ix, (read, streamed))| {
+                            let (read, streamed) = (read.trim_end(), streamed.trim_end());
+                            read == streamed
+                                || (ix == 0 && !streamed.is_empty() && read.ends_with(streamed))
+                        })
+                })
+                .unwrap_or(0);
+            lines.drain(..overlap);
+            match lines.first_mut() {
+                Some(line) => *line = join_at_seam(&current_line, line),
+                None => self.seam = Some(current_line),
+            }
+        }
+        self.push_lines(lines, cx);
+    }
+
+    fn push_lines(&mut self, lines: impl IntoIterator<Item = String>, cx: &mut ViewContext<Self>) {
+        let match_count = self.matches.len();
+        for line in lines {
+            if self.lines.len() == MAX_LINES {
+                self.lines.pop_front();
+                if self.matches.front() == Some(&self.dropped_lines) {
+                    self.matches.pop_front();
+                }
+                self.dropped_lines += 1;
+            }
+            if self.is_match(&line) {
+                self.matches
+                    .push_back(self.dropped_lines + self.lines.len());
+            }
+            self.lines.push_back(line);
+        }
+        if self.matches.len() != match_count {
+            self.scroll_to_end();
+            cx.notify();
+        }
+    }
+
+    fn scroll_to_end(&self) {
+        if let Some(last_ix) = self.matches.len().checked_sub(1) {
+            self.scroll_handle.scroll_to_item(last_ix);
+        }
+    }
+
+    fn render_header(&self, cx: &ViewContext<Self>) -> impl IntoElement {
+        h_flex()
+            .gap_2()
+            .p_2()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(
+                div()
+                    .w(rems(24.))
+                    .px_2()
+                    .py_1()
+                    .border_1()
+                    .border_color(cx.theme().colors().border)
+                    .rounded_md()
+                    .child(self.query_editor.clone()),
+            )
+            .child(
+                Label::new(format!(
+                    "{} of {} lines",
+                    self.matches.len(),
+                    self.lines.len()
+                ))
+                .color(Color::Muted),
+            )
+            .children(
+                self.error
+                    .clone()
+                    .map(|error| Label::new(error).color(Color::Error)),
+            )
+    }
+}
+
+/// Joins what was read of a line to the rest of it that was streamed, which
+/// starts with whatever was printed after subscribing and before reading.
+fn join_at_seam(read: &str, streamed: &str) -> String {
+    let overlap = (0..=read.len().min(streamed.len()))
+        .rev()
+        .find(|&len| streamed.is_char_boundary(len) && read.ends_with(&streamed[..len]))
+        .unwrap_or(0);
+    format!("{read}{}", &streamed[overlap..])
+}
+
+impl FocusableView for OutputFilter {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.query_editor.focus_handle(cx)
+    }
+}
+
+impl Render for OutputFilter {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let settings = ThemeSettings::get_global(cx);
+        let font_family = settings.buffer_font.family.clone();
+        let font_size = settings.buffer_font_size(cx);
+
+        v_flex()
+            .key_context("TerminalOutputFilter")
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(self.render_header(cx))
+            .child(
+                div().flex_1().font(font_family).text_size(font_size).child(
+                    uniform_list(
+                        cx.view().clone(),
+                        "terminal-output-filter",
+                        self.matches.len(),
+                        |this, range, _| {
+                            range
+                                .map(|ix| {
+                                    let line = this.matches[ix] - this.dropped_lines;
+                                    div()
+                                        .id(ix)
+                                        .px_2()
+                                        .whitespace_nowrap()
+                                        .overflow_hidden()
+                                        .child(this.lines[line].clone())
+                                })
+                                .collect::<Vec<_>>()
+                        },
+                    )
+                    .size_full()
+                    .track_scroll(self.scroll_handle.clone()),
+                ),
+            )
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::tests::{init_test, spawn_script, wait_until};
+    use gpui::{TestAppContext, VisualTestContext};
+    use terminal::terminal_settings::TerminalSettings;
+
+    #[gpui::test]
+    async fn test_filtering_lines(cx: &mut TestAppContext) {
+        let (filter, cx) = open_filter(r"printf 'apple\nbanana\ncherry'", "cherry", cx).await;
+
+        // The line the cursor is on isn't shown until it's finished.
+        let matching_lines = |cx: &mut VisualTestContext| {
+            filter.read_with(cx, |filter, _| {
+                filter
+                    .matching_lines()
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(matching_lines(cx), ["apple", "banana"]);
+
+        filter.update(cx, |filter, cx| filter.set_query("an+a", cx));
+        assert_eq!(matching_lines(cx), ["banana"]);
+
+        // An invalid regex keeps the last valid one's matches.
+        filter.update(cx, |filter, cx| filter.set_query("an+a(", cx));
+        assert_eq!(matching_lines(cx), ["banana"]);
+        filter.read_with(cx, |filter, _| assert!(filter.error.is_some()));
+
+        filter.update(cx, |filter, cx| {
+            filter.set_query("^.a", cx);
+            filter.push_streamed_lines(vec!["cherry".into(), "date".into()], cx);
+        });
+        filter.read_with(cx, |filter, _| assert!(filter.error.is_none()));
+        assert_eq!(matching_lines(cx), ["banana", "date"]);
+
+        filter.update(cx, |filter, cx| filter.set_query("", cx));
+        assert_eq!(matching_lines(cx), ["apple", "banana", "cherry", "date"]);
+    }
+
+    #[gpui::test]
+    async fn test_lines_streamed_after_reading_scrollback(cx: &mut TestAppContext) {
+        let (filter, cx) = open_filter(r"printf 'apple\nbanana\ncher'", "cher", cx).await;
+
+        // Everything from the end of "banana" on was printed after subscribing,
+        // and read from the scrollback up to "cher".
+        filter.update(cx, |filter, cx| {
+            filter.push_streamed_lines(vec!["nana".into(), "cherry".into()], cx);
+            filter.push_streamed_lines(vec!["banana".into()], cx);
+        });
+        filter.read_with(cx, |filter, _| {
+            assert_eq!(
+                filter.matching_lines().collect::<Vec<_>>(),
+                ["apple", "banana", "cherry", "banana"]
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_rest_of_line_streamed_after_reading_scrollback(cx: &mut TestAppContext) {
+        let (filter, cx) = open_filter(r"printf 'apple\n$ '", "$", cx).await;
+
+        // The prompt was printed before subscribing, and the command typed at it
+        // after.
+        filter.update(cx, |filter, cx| {
+            filter.push_streamed_lines(Vec::new(), cx);
+            filter.push_streamed_lines(vec!["ls".into(), "a.txt".into()], cx);
+        });
+        filter.read_with(cx, |filter, _| {
+            assert_eq!(
+                filter.matching_lines().collect::<Vec<_>>(),
+                ["apple", "$ ls", "a.txt"]
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_dropping_lines(cx: &mut TestAppContext) {
+        let (filter, cx) = open_filter("true", "", cx).await;
+
+        filter.update(cx, |filter, cx| {
+            filter.set_query("0$", cx);
+            filter.push_lines((0..MAX_LINES + 5).map(|ix| format!("line {ix}")), cx);
+        });
+        filter.read_with(cx, |filter, _| {
+            assert_eq!(filter.lines.len(), MAX_LINES);
+            assert_eq!(filter.dropped_lines, 5);
+            let matching_lines = filter.matching_lines().collect::<Vec<_>>();
+            assert_eq!(matching_lines.len(), MAX_LINES / 10);
+            assert_eq!(matching_lines.first(), Some(&"line 10"));
+            assert_eq!(matching_lines.last(), Some(&"line 10000"));
+        });
+
+        // Matches are found again among the lines that are kept.
+        filter.update(cx, |filter, cx| filter.set_query("1$", cx));
+        filter.read_with(cx, |filter, _| {
+            let matching_lines = filter.matching_lines().collect::<Vec<_>>();
+            assert_eq!(matching_lines.len(), MAX_LINES / 10);
+            assert_eq!(matching_lines.first(), Some(&"line 11"));
+            assert_eq!(matching_lines.last(), Some(&"line 10001"));
+        });
+    }
+
+    /// Opens a filter on the output of a script, once it's printed the given text.
+    async fn open_filter<'a>(
+        script: &str,
+        printed_text: &str,
+        cx: &'a mut TestAppContext,
+    ) -> (View<OutputFilter>, &'a mut VisualTestContext) {
+        let (project, _workspace) = init_test(cx).await;
+        cx.update(|cx| {
+            TerminalSettings::register(cx);
+            editor::init_settings(cx);
+        });
+        cx.executor().allow_parking();
+
+        let terminal = spawn_script(&project, script, cx);
+        wait_until(cx, |cx| {
+            terminal.read_with(cx, |terminal, _| {
+                terminal.output_text().contains(printed_text)
+            })
+        });
+        cx.add_window_view(|cx| OutputFilter::new(&terminal, cx))
+    }
+}
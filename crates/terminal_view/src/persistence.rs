@@ -34,6 +34,12 @@ define_connection! {
             DROP TABLE terminals;
 
             ALTER TABLE terminals2 RENAME TO terminals;
+        ),
+        sql!(
+            ALTER TABLE terminals ADD COLUMN session_id TEXT;
+        ),
+        sql!(
+            ALTER TABLE terminals ADD COLUMN profile TEXT;
         )];
 }
 
@@ -56,8 +62,36 @@ impl TerminalDb {
             workspace_id: WorkspaceId,
             working_directory: PathBuf
         ) -> Result<()> {
-            INSERT OR REPLACE INTO terminals(item_id, workspace_id, working_directory)
+            INSERT INTO terminals(item_id, workspace_id, working_directory)
+            VALUES (?, ?, ?)
+            ON CONFLICT(workspace_id, item_id) DO UPDATE SET
+                working_directory = excluded.working_directory
+        }
+    }
+
+    query! {
+        pub async fn save_session_id(
+            item_id: ItemId,
+            workspace_id: WorkspaceId,
+            session_id: String
+        ) -> Result<()> {
+            INSERT INTO terminals(item_id, workspace_id, session_id)
+            VALUES (?, ?, ?)
+            ON CONFLICT(workspace_id, item_id) DO UPDATE SET
+                session_id = excluded.session_id
+        }
+    }
+
+    query! {
+        pub async fn save_profile(
+            item_id: ItemId,
+            workspace_id: WorkspaceId,
+            profile: String
+        ) -> Result<()> {
+            INSERT INTO terminals(item_id, workspace_id, profile)
             VALUES (?, ?, ?)
+            ON CONFLICT(workspace_id, item_id) DO UPDATE SET
+                profile = excluded.profile
         }
     }
 
@@ -68,4 +102,20 @@ impl TerminalDb {
             WHERE item_id = ? AND workspace_id = ?
         }
     }
+
+    query! {
+        pub fn get_session_id(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<String>> {
+            SELECT session_id
+            FROM terminals
+            WHERE item_id = ? AND workspace_id = ? AND session_id IS NOT NULL
+        }
+    }
+
+    query! {
+        pub fn get_profile(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<String>> {
+            SELECT profile
+            FROM terminals
+            WHERE item_id = ? AND workspace_id = ? AND profile IS NOT NULL
+        }
+    }
 }
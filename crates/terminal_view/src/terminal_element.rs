@@ -1,11 +1,12 @@
 use editor::{Cursor, HighlightedRange, HighlightedRangeLine};
 use gpui::{
-    div, fill, point, px, relative, AnyElement, AvailableSpace, Bounds, DispatchPhase, Element,
-    ElementContext, ElementId, FocusHandle, Font, FontStyle, FontWeight, HighlightStyle, Hsla,
-    InputHandler, InteractiveBounds, InteractiveElement, InteractiveElementState, Interactivity,
-    IntoElement, LayoutId, Model, ModelContext, ModifiersChangedEvent, MouseButton, MouseMoveEvent,
-    Pixels, Point, ShapedLine, StatefulInteractiveElement, StrikethroughStyle, Styled, TextRun,
-    TextStyle, UnderlineStyle, WeakView, WhiteSpace, WindowContext, WindowTextSystem,
+    div, fill, point, px, relative, AnyElement, AvailableSpace, Bounds, ContentMask, Corners,
+    DispatchPhase, Element, ElementContext, ElementId, FocusHandle, Font, FontStyle, FontWeight,
+    HighlightStyle, Hsla, ImageData, InputHandler, InteractiveBounds, InteractiveElement,
+    InteractiveElementState, Interactivity, IntoElement, LayoutId, Model, ModelContext,
+    ModifiersChangedEvent, MouseButton, MouseMoveEvent, Pixels, Point, ShapedLine,
+    StatefulInteractiveElement, StrikethroughStyle, Styled, TextRun, TextStyle, UnderlineStyle,
+    WeakView, WhiteSpace, WindowContext, WindowTextSystem,
 };
 use itertools::Itertools;
 use language::CursorShape;
@@ -18,10 +19,11 @@ use terminal::{
         vte::ansi::{Color as AnsiColor, Color::Named, CursorShape as AlacCursorShape, NamedColor},
     },
     terminal_settings::TerminalSettings,
-    IndexedCell, Terminal, TerminalContent, TerminalSize,
+    CommandStatus, ImagePlacement, IndexedCell, Terminal, TerminalContent, TerminalSize,
 };
 use theme::{ActiveTheme, Theme, ThemeSettings};
 use ui::Tooltip;
+use util::ResultExt;
 use workspace::Workspace;
 
 use std::mem;
@@ -39,6 +41,8 @@ pub struct LayoutState {
     display_offset: usize,
     hyperlink_tooltip: Option<AnyElement>,
     gutter: Pixels,
+    command_marks: Vec<(i32, Hsla)>,
+    images: Vec<ImagePlacement>,
 }
 
 /// Helper struct for converting data between Alacritty's cursor points, and displayed cursor points.
@@ -337,13 +341,12 @@ impl TerminalElement {
             fg.a *= 0.7;
         }
 
-        let underline = (flags.intersects(Flags::ALL_UNDERLINES)
-            || indexed.cell.hyperlink().is_some())
-        .then(|| UnderlineStyle {
-            color: Some(fg),
-            thickness: Pixels::from(1.0),
-            wavy: flags.contains(Flags::UNDERCURL),
-        });
+        let underline = (flags.intersects(Flags::ALL_UNDERLINES) || indexed.hyperlink().is_some())
+            .then(|| UnderlineStyle {
+                color: Some(fg),
+                thickness: Pixels::from(1.0),
+                wavy: flags.contains(Flags::UNDERCURL),
+            });
 
         let strikethrough = flags
             .intersects(Flags::STRIKEOUT)
@@ -398,10 +401,26 @@ impl TerminalElement {
         let buffer_font_size = settings.buffer_font_size(cx);
 
         let terminal_settings = TerminalSettings::get_global(cx);
-        let font_family = terminal_settings
-            .font_family
-            .as_ref()
-            .map(|string| string.clone().into())
+        // The terminal's profile may come from the project's settings, which may
+        // also change its font.
+        let (font_family, font_size) = self
+            .terminal
+            .read(cx)
+            .profile()
+            .and_then(|profile| {
+                let workspace = self.workspace.upgrade()?;
+                let project = workspace.read(cx).project().read(cx);
+                let profile = project.terminal_settings(cx).profile(Some(profile));
+                Some((profile.font_family, profile.font_size))
+            })
+            .unwrap_or_else(|| {
+                (
+                    terminal_settings.font_family.clone(),
+                    terminal_settings.font_size,
+                )
+            });
+        let font_family = font_family
+            .map(|string| string.into())
             .unwrap_or(settings.buffer_font.family);
 
         let font_features = terminal_settings
@@ -410,8 +429,6 @@ impl TerminalElement {
             .unwrap_or(settings.buffer_font.features.clone());
 
         let line_height = terminal_settings.line_height.value();
-        let font_size = terminal_settings.font_size.clone();
-
         let font_size =
             font_size.map_or(buffer_font_size, |size| theme::adjusted_font_size(size, cx));
 
@@ -516,9 +533,23 @@ impl TerminalElement {
             cursor_char,
             selection,
             cursor,
+            command_marks,
+            images,
             ..
         } = &self.terminal.read(cx).last_content;
 
+        let command_marks = command_marks
+            .iter()
+            .map(|mark| {
+                let color = match mark.status {
+                    CommandStatus::Pending => theme.colors().text_muted,
+                    CommandStatus::Succeeded => theme.status().success,
+                    CommandStatus::Failed(_) => theme.status().error,
+                };
+                (mark.line.0 + *display_offset as i32, color)
+            })
+            .collect();
+
         // searches, highlights to a single range representations
         let mut relative_highlighted_ranges = Vec::new();
         for search_match in search_matches {
@@ -603,6 +634,8 @@ impl TerminalElement {
             display_offset: *display_offset,
             hyperlink_tooltip,
             gutter,
+            command_marks,
+            images: images.clone(),
         }
     }
 
@@ -820,6 +853,19 @@ impl Element for TerminalElement {
                     rect.paint(origin, &layout, cx);
                 }
 
+                // Mark the prompts the shell reported in the gutter, colored by
+                // how their command exited.
+                for (line, color) in &layout.command_marks {
+                    let mark_bounds = Bounds::new(
+                        point(
+                            bounds.origin.x + layout.gutter / 4.,
+                            origin.y + *line as f32 * layout.dimensions.line_height,
+                        ),
+                        gpui::size(layout.gutter / 4., layout.dimensions.line_height),
+                    );
+                    cx.paint_quad(fill(mark_bounds, *color));
+                }
+
                 cx.with_z_index(1, |cx| {
                     for (relative_highlighted_range, color) in
                         layout.relative_highlighted_ranges.iter()
@@ -846,6 +892,38 @@ impl Element for TerminalElement {
                     }
                 });
 
+                // Images are drawn over the text, scaled to fit the cells they
+                // take up and clipped to the terminal.
+                cx.with_z_index(2, |cx| {
+                    cx.with_content_mask(Some(ContentMask { bounds }), |cx| {
+                        for placement in &layout.images {
+                            let line = placement.point.line.0 + layout.display_offset as i32;
+                            let cell_origin = origin
+                                + point(
+                                    placement.point.column.0 as f32 * layout.dimensions.cell_width,
+                                    line as f32 * layout.dimensions.line_height,
+                                );
+                            let image_bounds = fit_image(
+                                &placement.image,
+                                Bounds::new(
+                                    cell_origin,
+                                    gpui::size(
+                                        placement.columns as f32 * layout.dimensions.cell_width,
+                                        placement.rows as f32 * layout.dimensions.line_height,
+                                    ),
+                                ),
+                            );
+                            cx.paint_image(
+                                image_bounds,
+                                Corners::default(),
+                                placement.image.clone(),
+                                false,
+                            )
+                            .log_err();
+                        }
+                    });
+                });
+
                 if self.cursor_visible {
                     cx.with_z_index(3, |cx| {
                         if let Some(cursor) = &layout.cursor {
@@ -944,6 +1022,19 @@ impl InputHandler for TerminalInputHandler {
     }
 }
 
+/// Scales an image down to fit the given bounds, keeping its aspect ratio and
+/// its top left corner where it is.
+fn fit_image(image: &ImageData, bounds: Bounds<Pixels>) -> Bounds<Pixels> {
+    let image_size = image.size();
+    let width = u32::from(image_size.width).max(1) as f32;
+    let height = u32::from(image_size.height).max(1) as f32;
+    let scale = (f32::from(bounds.size.width) / width).min(f32::from(bounds.size.height) / height);
+    Bounds::new(
+        bounds.origin,
+        gpui::size(px(width * scale), px(height * scale)),
+    )
+}
+
 fn is_blank(cell: &IndexedCell) -> bool {
     if cell.c != ' ' {
         return false;
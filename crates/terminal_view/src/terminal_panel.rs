@@ -1,39 +1,62 @@
-use std::{ops::ControlFlow, path::PathBuf, sync::Arc};
+use std::{cmp, ops::ControlFlow, path::PathBuf, sync::Arc, time::Duration};
 
 use crate::TerminalView;
+use collections::HashMap;
 use db::kvp::KEY_VALUE_STORE;
+use editor::Editor;
+use futures::FutureExt;
 use gpui::{
-    actions, AppContext, AsyncWindowContext, Entity, EventEmitter, ExternalPaths, FocusHandle,
-    FocusableView, IntoElement, ParentElement, Pixels, Render, Styled, Subscription, Task, View,
-    ViewContext, VisualContext, WeakView, WindowContext,
+    actions, impl_actions, AppContext, AsyncWindowContext, Axis, Entity, EntityId, EventEmitter,
+    ExternalPaths, FocusHandle, FocusableView, InteractiveElement, IntoElement, Model,
+    ModelContext, ParentElement, Pixels, Render, Styled, Subscription, Task, View, ViewContext,
+    VisualContext, WeakView, WindowContext,
 };
 use itertools::Itertools;
-use project::{Fs, ProjectEntryId};
+use language::Point;
+use project::{Fs, Project};
 use search::{buffer_search::DivRegistrar, BufferSearchBar};
 use serde::{Deserialize, Serialize};
 use settings::Settings;
-use terminal::terminal_settings::{TerminalDockPosition, TerminalSettings};
-use ui::{h_flex, ButtonCommon, Clickable, IconButton, IconSize, Selectable, Tooltip};
+use task::{SpawnInTerminal, TaskId};
+use terminal::{
+    alacritty_terminal::term::TermMode,
+    terminal_settings::{TerminalDockPosition, TerminalSettings},
+    TaskStatus, Terminal,
+};
+use ui::{
+    h_flex, popover_menu, ButtonCommon, Clickable, ContextMenu, IconButton, IconSize, Selectable,
+    Tooltip,
+};
 use util::{ResultExt, TryFutureExt};
 use workspace::{
     dock::{DockPosition, Panel, PanelEvent},
-    item::Item,
+    item::{Item, ItemHandle},
     pane,
     ui::IconName,
-    DraggedTab, Pane, Workspace,
+    DraggedSelection, DraggedTab, Member, Pane, PaneAxis, PaneGroup, SplitDirection, Workspace,
 };
 
 use anyhow::Result;
 
 const TERMINAL_PANEL_KEY: &'static str = "TerminalPanel";
 
-actions!(terminal_panel, [ToggleFocus]);
+actions!(terminal_panel, [ToggleFocus, SendToTerminal]);
+
+/// Opens a new terminal with the named profile from the `terminal.profiles` setting.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct NewTerminalWithProfile {
+    pub profile: String,
+}
+
+impl_actions!(terminal_panel, [NewTerminalWithProfile]);
 
 pub fn init(cx: &mut AppContext) {
     cx.observe_new_views(
         |workspace: &mut Workspace, _: &mut ViewContext<Workspace>| {
             workspace.register_action(TerminalPanel::new_terminal);
+            workspace.register_action(TerminalPanel::new_terminal_with_profile);
             workspace.register_action(TerminalPanel::open_terminal);
+            workspace.register_action(TerminalPanel::send_to_terminal);
             workspace.register_action(|workspace, _: &ToggleFocus, cx| {
                 workspace.toggle_panel_focus::<TerminalPanel>(cx);
             });
@@ -43,125 +66,42 @@ pub fn init(cx: &mut AppContext) {
 }
 
 pub struct TerminalPanel {
-    pane: View<Pane>,
+    center: PaneGroup,
+    active_pane: View<Pane>,
     fs: Arc<dyn Fs>,
     workspace: WeakView<Workspace>,
     width: Option<Pixels>,
     height: Option<Pixels>,
     pending_serialization: Task<Option<()>>,
-    _subscriptions: Vec<Subscription>,
+    pane_subscriptions: HashMap<EntityId, Vec<Subscription>>,
 }
 
 impl TerminalPanel {
     fn new(workspace: &Workspace, cx: &mut ViewContext<Self>) -> Self {
-        let terminal_panel = cx.view().downgrade();
-        let pane = cx.new_view(|cx| {
-            let mut pane = Pane::new(
-                workspace.weak_handle(),
-                workspace.project().clone(),
-                Default::default(),
-                None,
-                cx,
-            );
-            pane.set_can_split(false, cx);
-            pane.set_can_navigate(false, cx);
-            pane.display_nav_history_buttons(false);
-            pane.set_render_tab_bar_buttons(cx, move |pane, cx| {
-                let terminal_panel = terminal_panel.clone();
-                h_flex()
-                    .gap_2()
-                    .child(
-                        IconButton::new("plus", IconName::Plus)
-                            .icon_size(IconSize::Small)
-                            .on_click(move |_, cx| {
-                                terminal_panel
-                                    .update(cx, |panel, cx| panel.add_terminal(None, cx))
-                                    .log_err();
-                            })
-                            .tooltip(|cx| Tooltip::text("New Terminal", cx)),
-                    )
-                    .child({
-                        let zoomed = pane.is_zoomed();
-                        IconButton::new("toggle_zoom", IconName::Maximize)
-                            .icon_size(IconSize::Small)
-                            .selected(zoomed)
-                            .selected_icon(IconName::Minimize)
-                            .on_click(cx.listener(|pane, _, cx| {
-                                pane.toggle_zoom(&workspace::ToggleZoom, cx);
-                            }))
-                            .tooltip(move |cx| {
-                                Tooltip::text(if zoomed { "Zoom Out" } else { "Zoom In" }, cx)
-                            })
-                    })
-                    .into_any_element()
-            });
-
-            let workspace = workspace.weak_handle();
-            pane.set_custom_drop_handle(cx, move |pane, dropped_item, cx| {
-                if let Some(tab) = dropped_item.downcast_ref::<DraggedTab>() {
-                    let item = if &tab.pane == cx.view() {
-                        pane.item_for_index(tab.ix)
-                    } else {
-                        tab.pane.read(cx).item_for_index(tab.ix)
-                    };
-                    if let Some(item) = item {
-                        if item.downcast::<TerminalView>().is_some() {
-                            return ControlFlow::Continue(());
-                        } else if let Some(project_path) = item.project_path(cx) {
-                            if let Some(entry_path) = workspace
-                                .update(cx, |workspace, cx| {
-                                    workspace
-                                        .project()
-                                        .read(cx)
-                                        .absolute_path(&project_path, cx)
-                                })
-                                .log_err()
-                                .flatten()
-                            {
-                                add_paths_to_terminal(pane, &[entry_path], cx);
-                            }
-                        }
-                    }
-                } else if let Some(&entry_id) = dropped_item.downcast_ref::<ProjectEntryId>() {
-                    if let Some(entry_path) = workspace
-                        .update(cx, |workspace, cx| {
-                            let project = workspace.project().read(cx);
-                            project
-                                .path_for_entry(entry_id, cx)
-                                .and_then(|project_path| project.absolute_path(&project_path, cx))
-                        })
-                        .log_err()
-                        .flatten()
-                    {
-                        add_paths_to_terminal(pane, &[entry_path], cx);
-                    }
-                } else if let Some(paths) = dropped_item.downcast_ref::<ExternalPaths>() {
-                    add_paths_to_terminal(pane, paths.paths(), cx);
-                }
-
-                ControlFlow::Break(())
-            });
-            let buffer_search_bar = cx.new_view(search::BufferSearchBar::new);
-            pane.toolbar()
-                .update(cx, |toolbar, cx| toolbar.add_item(buffer_search_bar, cx));
-            pane
-        });
-        let subscriptions = vec![
-            cx.observe(&pane, |_, _, cx| cx.notify()),
-            cx.subscribe(&pane, Self::handle_pane_event),
-        ];
-        let this = Self {
-            pane,
+        let pane = new_terminal_pane(workspace.weak_handle(), workspace.project().clone(), cx);
+        let mut this = Self {
+            center: PaneGroup::new(pane.clone()),
+            active_pane: pane.clone(),
             fs: workspace.app_state().fs.clone(),
             workspace: workspace.weak_handle(),
             pending_serialization: Task::ready(None),
             width: None,
             height: None,
-            _subscriptions: subscriptions,
+            pane_subscriptions: HashMap::default(),
         };
+        this.subscribe_to_pane(&pane, cx);
         this
     }
 
+    fn subscribe_to_pane(&mut self, pane: &View<Pane>, cx: &mut ViewContext<Self>) {
+        let subscriptions = vec![
+            cx.observe(pane, |_, _, cx| cx.notify()),
+            cx.subscribe(pane, Self::handle_pane_event),
+        ];
+        self.pane_subscriptions
+            .insert(pane.entity_id(), subscriptions);
+    }
+
     pub async fn load(
         workspace: WeakView<Workspace>,
         mut cx: AsyncWindowContext,
@@ -177,77 +117,163 @@ impl TerminalPanel {
             .log_err()
             .flatten();
 
-        let (panel, pane, items) = workspace.update(&mut cx, |workspace, cx| {
+        let (panel, panes) = workspace.update(&mut cx, |workspace, cx| {
             let panel = cx.new_view(|cx| TerminalPanel::new(workspace, cx));
-            let items = if let Some(serialized_panel) = serialized_panel.as_ref() {
+            let mut panes = Vec::new();
+            if let Some(serialized_panel) = serialized_panel.as_ref() {
                 panel.update(cx, |panel, cx| {
                     cx.notify();
                     panel.height = serialized_panel.height;
                     panel.width = serialized_panel.width;
-                    panel.pane.update(cx, |_, cx| {
-                        serialized_panel
-                            .items
-                            .iter()
-                            .map(|item_id| {
-                                TerminalView::deserialize(
-                                    workspace.project().clone(),
-                                    workspace.weak_handle(),
-                                    workspace.database_id(),
-                                    *item_id,
-                                    cx,
-                                )
-                            })
-                            .collect::<Vec<_>>()
-                    })
-                })
-            } else {
-                Default::default()
-            };
-            let pane = panel.read(cx).pane.clone();
-            (panel, pane, items)
+                    let serialized_panes = serialized_panel.panes.clone().unwrap_or_else(|| {
+                        SerializedTerminalPanes::Pane {
+                            items: serialized_panel.items.clone(),
+                            active_item_id: serialized_panel.active_item_id,
+                            active: true,
+                        }
+                    });
+                    let mut initial_pane = Some(panel.active_pane.clone());
+                    let root = panel.deserialize_panes(
+                        serialized_panes,
+                        workspace,
+                        &mut initial_pane,
+                        &mut panes,
+                        cx,
+                    );
+                    panel.center = PaneGroup::with_root(root);
+                });
+            }
+            (panel, panes)
         })?;
 
-        let pane = pane.downgrade();
-        let items = futures::future::join_all(items).await;
-        pane.update(&mut cx, |pane, cx| {
-            let active_item_id = serialized_panel
-                .as_ref()
-                .and_then(|panel| panel.active_item_id);
-            let mut active_ix = None;
-            for item in items {
-                if let Some(item) = item.log_err() {
-                    let item_id = item.entity_id().as_u64();
-                    pane.add_item(Box::new(item), false, false, None, cx);
-                    if Some(item_id) == active_item_id {
-                        active_ix = Some(pane.items_len() - 1);
+        for (pane, items, active_item_id) in panes {
+            let items = futures::future::join_all(items).await;
+            pane.update(&mut cx, |pane, cx| {
+                let mut active_ix = None;
+                for item in items {
+                    if let Some(item) = item.log_err() {
+                        let item_id = item.entity_id().as_u64();
+                        pane.add_item(Box::new(item), false, false, None, cx);
+                        if Some(item_id) == active_item_id {
+                            active_ix = Some(pane.items_len() - 1);
+                        }
                     }
                 }
-            }
 
-            if let Some(active_ix) = active_ix {
-                pane.activate_item(active_ix, false, false, cx)
+                if let Some(active_ix) = active_ix {
+                    pane.activate_item(active_ix, false, false, cx)
+                }
+            })?;
+        }
+
+        panel.update(&mut cx, |panel, cx| {
+            // Panes whose terminals could not be restored are dropped from the layout.
+            for pane in panel
+                .center
+                .panes()
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>()
+            {
+                if pane.read(cx).items_len() == 0 {
+                    panel.remove_pane(&pane, cx);
+                }
             }
         })?;
 
         Ok(panel)
     }
 
+    /// Recreates the panes of a serialized layout, collecting the terminals that have
+    /// to be loaded into each of them.
+    fn deserialize_panes(
+        &mut self,
+        serialized: SerializedTerminalPanes,
+        workspace: &Workspace,
+        initial_pane: &mut Option<View<Pane>>,
+        panes: &mut Vec<(
+            View<Pane>,
+            Vec<Task<Result<View<TerminalView>>>>,
+            Option<u64>,
+        )>,
+        cx: &mut ViewContext<Self>,
+    ) -> Member {
+        match serialized {
+            SerializedTerminalPanes::Pane {
+                items,
+                active_item_id,
+                active,
+            } => {
+                let pane = initial_pane.take().unwrap_or_else(|| {
+                    let pane =
+                        new_terminal_pane(workspace.weak_handle(), workspace.project().clone(), cx);
+                    self.subscribe_to_pane(&pane, cx);
+                    pane
+                });
+                if active {
+                    self.active_pane = pane.clone();
+                }
+                let items = pane.update(cx, |_, cx| {
+                    items
+                        .into_iter()
+                        .map(|item_id| {
+                            TerminalView::deserialize(
+                                workspace.project().clone(),
+                                workspace.weak_handle(),
+                                workspace.database_id(),
+                                item_id,
+                                cx,
+                            )
+                        })
+                        .collect()
+                });
+                panes.push((pane.clone(), items, active_item_id));
+                Member::Pane(pane)
+            }
+            SerializedTerminalPanes::Axis {
+                axis,
+                members,
+                flexes,
+            } => {
+                let members = members
+                    .into_iter()
+                    .map(|member| {
+                        self.deserialize_panes(member, workspace, initial_pane, panes, cx)
+                    })
+                    .collect::<Vec<_>>();
+                let flexes = (flexes.len() == members.len()).then_some(flexes);
+                Member::Axis(PaneAxis::load(axis.into(), members, flexes))
+            }
+        }
+    }
+
     fn handle_pane_event(
         &mut self,
-        _pane: View<Pane>,
+        pane: View<Pane>,
         event: &pane::Event,
         cx: &mut ViewContext<Self>,
     ) {
         match event {
             pane::Event::ActivateItem { .. } => self.serialize(cx),
             pane::Event::RemoveItem { .. } => self.serialize(cx),
-            pane::Event::Remove => cx.emit(PanelEvent::Close),
+            pane::Event::Remove => {
+                if !self.remove_pane(&pane, cx) {
+                    cx.emit(PanelEvent::Close)
+                }
+            }
             pane::Event::ZoomIn => cx.emit(PanelEvent::ZoomIn),
             pane::Event::ZoomOut => cx.emit(PanelEvent::ZoomOut),
+            pane::Event::Split(direction) => self.split_pane(pane, *direction, cx),
+            pane::Event::Focus => {
+                if self.active_pane != pane {
+                    self.active_pane = pane;
+                    self.serialize(cx);
+                    cx.notify();
+                }
+            }
 
             pane::Event::AddItem { item } => {
                 if let Some(workspace) = self.workspace.upgrade() {
-                    let pane = self.pane.clone();
                     workspace.update(cx, |workspace, cx| item.added_to_pane(workspace, pane, cx))
                 }
             }
@@ -256,6 +282,104 @@ impl TerminalPanel {
         }
     }
 
+    /// Splits the pane, opening a terminal in the working directory of its active one,
+    /// with the same profile.
+    fn split_pane(
+        &mut self,
+        pane: View<Pane>,
+        direction: SplitDirection,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let (working_directory, profile) = pane
+            .read(cx)
+            .active_item()
+            .and_then(|item| item.downcast::<TerminalView>())
+            .map(|terminal_view| {
+                let terminal = terminal_view.read(cx).terminal().read(cx);
+                let working_directory = terminal
+                    .foreground_process_info
+                    .as_ref()
+                    .map(|info| info.cwd.clone());
+                (working_directory, terminal.profile().map(str::to_string))
+            })
+            .unwrap_or_default();
+
+        let project = workspace.read(cx).project().clone();
+        let new_pane = new_terminal_pane(self.workspace.clone(), project, cx);
+        self.subscribe_to_pane(&new_pane, cx);
+        self.center.split(&pane, &new_pane, direction).log_err();
+        self.active_pane = new_pane.clone();
+        cx.focus_view(&new_pane);
+        self.add_terminal(working_directory, profile, None, None, cx)
+            .detach_and_log_err(cx);
+        cx.notify();
+    }
+
+    /// Removes the pane from the layout, returning false if it is the last one left.
+    fn remove_pane(&mut self, pane: &View<Pane>, cx: &mut ViewContext<Self>) -> bool {
+        if !self.center.remove(pane).unwrap_or(false) {
+            return false;
+        }
+
+        self.pane_subscriptions.remove(&pane.entity_id());
+        if &self.active_pane == pane {
+            self.active_pane = self.center.first_pane();
+            if pane.focus_handle(cx).contains_focused(cx) {
+                cx.focus_view(&self.active_pane);
+            }
+        }
+        self.serialize(cx);
+        cx.notify();
+        true
+    }
+
+    fn activate_pane_in_direction(
+        &mut self,
+        action: &workspace::ActivatePaneInDirection,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let Some(pane) = self
+            .center
+            .find_pane_in_direction(&self.active_pane, action.0, cx)
+            .cloned()
+        {
+            cx.focus_view(&pane);
+        } else {
+            cx.propagate();
+        }
+    }
+
+    fn activate_next_pane(&mut self, _: &workspace::ActivateNextPane, cx: &mut ViewContext<Self>) {
+        let panes = self.center.panes();
+        if panes.len() < 2 {
+            cx.propagate();
+            return;
+        }
+        if let Some(ix) = panes.iter().position(|pane| **pane == self.active_pane) {
+            let next_pane = panes[(ix + 1) % panes.len()].clone();
+            cx.focus_view(&next_pane);
+        }
+    }
+
+    fn activate_previous_pane(
+        &mut self,
+        _: &workspace::ActivatePreviousPane,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let panes = self.center.panes();
+        if panes.len() < 2 {
+            cx.propagate();
+            return;
+        }
+        if let Some(ix) = panes.iter().position(|pane| **pane == self.active_pane) {
+            let prev_pane = panes[cmp::min(ix.wrapping_sub(1), panes.len() - 1)].clone();
+            cx.focus_view(&prev_pane);
+        }
+    }
+
     pub fn open_terminal(
         workspace: &mut Workspace,
         action: &workspace::OpenTerminal,
@@ -266,7 +390,8 @@ impl TerminalPanel {
         };
 
         this.update(cx, |this, cx| {
-            this.add_terminal(Some(action.working_directory.clone()), cx)
+            this.add_terminal(Some(action.working_directory.clone()), None, None, None, cx)
+                .detach_and_log_err(cx)
         })
     }
 
@@ -280,57 +405,297 @@ impl TerminalPanel {
             return;
         };
 
-        this.update(cx, |this, cx| this.add_terminal(None, cx))
+        this.update(cx, |this, cx| {
+            this.add_terminal(None, None, None, None, cx)
+                .detach_and_log_err(cx)
+        })
+    }
+
+    fn new_terminal_with_profile(
+        workspace: &mut Workspace,
+        action: &NewTerminalWithProfile,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let Some(this) = workspace.focus_panel::<Self>(cx) else {
+            return;
+        };
+
+        this.update(cx, |this, cx| {
+            this.add_terminal(None, Some(action.profile.clone()), None, None, cx)
+                .detach_and_log_err(cx)
+        })
+    }
+
+    /// Spawns a task, reusing the terminal of its previous run unless the task asks for
+    /// a new terminal. A task that doesn't allow concurrent runs is not spawned while
+    /// a previous run of it is in progress; its terminal gets activated instead.
+    pub fn spawn_task(
+        &mut self,
+        spawn_in_terminal: SpawnInTerminal,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<Model<Terminal>>> {
+        let task_terminals = self.terminals_for_task(&spawn_in_terminal.id, cx);
+        if !spawn_in_terminal.allow_concurrent_runs {
+            if let Some((pane, running_ix, _)) = task_terminals
+                .iter()
+                .find(|(_, _, status)| *status == TaskStatus::Running)
+            {
+                let running_ix = *running_ix;
+                let terminal = pane.update(cx, |pane, cx| {
+                    pane.activate_item(running_ix, false, false, cx);
+                    pane.item_for_index(running_ix)
+                        .and_then(|item| item.downcast::<TerminalView>())
+                        .map(|terminal_view| terminal_view.read(cx).terminal().clone())
+                });
+                self.active_pane = pane.clone();
+                let workspace = self.workspace.clone();
+                cx.defer(move |_, cx| {
+                    workspace
+                        .update(cx, |workspace, cx| workspace.open_panel::<Self>(cx))
+                        .log_err();
+                });
+                return Task::ready(
+                    terminal.ok_or_else(|| anyhow::anyhow!("task terminal not found")),
+                );
+            }
+        }
+
+        let completed_terminal = task_terminals
+            .into_iter()
+            .find(|(_, _, status)| *status == TaskStatus::Completed);
+        match completed_terminal {
+            Some((pane, completed_ix, _)) if !spawn_in_terminal.use_new_terminal => {
+                let completed_item = pane
+                    .read(cx)
+                    .item_for_index(completed_ix)
+                    .map(|item| item.boxed_clone());
+                self.active_pane = pane;
+                self.add_terminal(None, None, Some(spawn_in_terminal), completed_item, cx)
+            }
+            _ => self.add_terminal(None, None, Some(spawn_in_terminal), None, cx),
+        }
+    }
+
+    /// Sends code from the active editor to a terminal: the selected text or, without
+    /// a selection, the statement or line at the cursor. Code in a language with a
+    /// configured REPL goes to that REPL's terminal, which the first send starts.
+    fn send_to_terminal(
+        workspace: &mut Workspace,
+        _: &SendToTerminal,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+            return;
+        };
+        let Some((code, language)) = code_to_send(editor.read(cx), cx) else {
+            return;
+        };
+        let Some(this) = workspace.panel::<Self>(cx) else {
+            return;
+        };
+        workspace.open_panel::<Self>(cx);
+
+        let repl = language.and_then(|language| {
+            let repl = TerminalSettings::get_global(cx)
+                .repl
+                .get(language.as_ref())?;
+            Some(SpawnInTerminal {
+                id: TaskId(format!("repl_{language}")),
+                label: format!("{language} REPL"),
+                command: repl.command.clone(),
+                args: repl.args.clone(),
+                cwd: None,
+                env: HashMap::default(),
+                use_new_terminal: false,
+                allow_concurrent_runs: false,
+                problem_matchers: Vec::new(),
+            })
+        });
+
+        this.update(cx, |this, cx| {
+            let terminal = match repl {
+                Some(repl) => {
+                    let started = this
+                        .terminals_for_task(&repl.id, cx)
+                        .iter()
+                        .any(|(_, _, status)| *status == TaskStatus::Running);
+                    let terminal = this.spawn_task(repl, cx);
+                    if started {
+                        terminal
+                    } else {
+                        cx.spawn(|_, cx| async move {
+                            let terminal = terminal.await?;
+                            wait_for_output(&terminal, cx).await?;
+                            Ok(terminal)
+                        })
+                    }
+                }
+                None => match this
+                    .active_pane
+                    .read(cx)
+                    .active_item()
+                    .and_then(|item| item.downcast::<TerminalView>())
+                {
+                    Some(terminal_view) => {
+                        Task::ready(Ok(terminal_view.read(cx).terminal().clone()))
+                    }
+                    None => this.add_terminal(None, None, None, None, cx),
+                },
+            };
+
+            cx.spawn(|_, mut cx| async move {
+                let terminal = terminal.await?;
+                terminal.update(&mut cx, |terminal, cx| send_code(terminal, &code, cx))
+            })
+            .detach_and_log_err(cx);
+        });
+    }
+
+    fn terminals_for_task(
+        &self,
+        id: &TaskId,
+        cx: &AppContext,
+    ) -> Vec<(View<Pane>, usize, TaskStatus)> {
+        self.center
+            .panes()
+            .into_iter()
+            .flat_map(|pane| {
+                pane.read(cx)
+                    .items()
+                    .enumerate()
+                    .filter_map(|(ix, item)| {
+                        let terminal_view = item.downcast::<TerminalView>()?;
+                        let terminal = terminal_view.read(cx).terminal().read(cx);
+                        let task = terminal.task()?;
+                        (&task.id == id).then(|| (pane.clone(), ix, task.status))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
     }
 
-    fn add_terminal(&mut self, working_directory: Option<PathBuf>, cx: &mut ViewContext<Self>) {
+    /// Opens a new terminal in the active pane. When `replaced_item` is given, the
+    /// terminal takes its place, and the replaced item is removed only after the
+    /// new terminal is added so that the pane never becomes empty.
+    fn add_terminal(
+        &mut self,
+        working_directory: Option<PathBuf>,
+        profile: Option<String>,
+        mut spawn_task: Option<SpawnInTerminal>,
+        replaced_item: Option<Box<dyn ItemHandle>>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<Model<Terminal>>> {
         let workspace = self.workspace.clone();
+        let pane = self.active_pane.clone();
         cx.spawn(|this, mut cx| async move {
-            let pane = this.update(&mut cx, |this, _| this.pane.clone())?;
-            workspace.update(&mut cx, |workspace, cx| {
+            let terminal = workspace.update(&mut cx, |workspace, cx| {
                 let working_directory = if let Some(working_directory) = working_directory {
                     Some(working_directory)
                 } else {
-                    let working_directory_strategy =
-                        TerminalSettings::get_global(cx).working_directory.clone();
-                    crate::get_working_directory(workspace, cx, working_directory_strategy)
+                    crate::profile_working_directory(workspace, profile.as_deref(), cx)
                 };
+                if let Some(spawn_task) = spawn_task.as_mut() {
+                    if spawn_task.cwd.is_none() {
+                        spawn_task.cwd = working_directory.clone();
+                    }
+                }
 
                 let window = cx.window_handle();
-                if let Some(terminal) = workspace.project().update(cx, |project, cx| {
-                    project
-                        .create_terminal(working_directory, window, cx)
-                        .log_err()
-                }) {
-                    let terminal = Box::new(cx.new_view(|cx| {
-                        TerminalView::new(
-                            terminal,
-                            workspace.weak_handle(),
-                            workspace.database_id(),
-                            cx,
-                        )
-                    }));
-                    pane.update(cx, |pane, cx| {
-                        let focus = pane.has_focus(cx);
-                        pane.add_item(terminal, true, focus, None, cx);
-                    });
+                let terminal = workspace.project().update(cx, |project, cx| {
+                    project.create_terminal(
+                        working_directory,
+                        profile.as_deref(),
+                        spawn_task.clone(),
+                        window,
+                        cx,
+                    )
+                })?;
+                let terminal_view = Box::new(cx.new_view(|cx| {
+                    TerminalView::new(
+                        terminal.clone(),
+                        workspace.weak_handle(),
+                        workspace.database_id(),
+                        cx,
+                    )
+                }));
+                pane.update(cx, |pane, cx| {
+                    let focus = pane.has_focus(cx);
+                    let destination_index = replaced_item
+                        .as_ref()
+                        .and_then(|item| pane.index_for_item(item.as_ref()));
+                    pane.add_item(terminal_view, true, focus, destination_index, cx);
+                    if let Some(replaced_ix) = replaced_item
+                        .as_ref()
+                        .and_then(|item| pane.index_for_item(item.as_ref()))
+                    {
+                        pane.remove_item(replaced_ix, false, cx);
+                    }
+                });
+                if spawn_task.is_some() {
+                    workspace.open_panel::<Self>(cx);
+                }
+                anyhow::Ok(terminal)
+            })??;
+            this.update(&mut cx, |this, cx| {
+                if let Some(spawn_task) = spawn_task {
+                    this.report_task_problems(&terminal, spawn_task, cx);
                 }
+                this.serialize(cx)
             })?;
-            this.update(&mut cx, |this, cx| this.serialize(cx))?;
-            anyhow::Ok(())
+            Ok(terminal)
+        })
+    }
+
+    /// Reports the problems found in the output of a task as diagnostics once it finishes.
+    fn report_task_problems(
+        &mut self,
+        terminal: &Model<Terminal>,
+        spawn_task: SpawnInTerminal,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if spawn_task.problem_matchers.is_empty() {
+            return;
+        }
+
+        let workspace = self.workspace.clone();
+        cx.subscribe(terminal, move |_, terminal, event, cx| {
+            if let terminal::Event::TaskFinished = event {
+                let output = terminal.read(cx).output_text();
+                let problems = spawn_task
+                    .problem_matchers
+                    .iter()
+                    .filter_map(|matcher| matcher.match_output(&output).log_err())
+                    .flatten()
+                    .collect::<Vec<_>>();
+                workspace
+                    .update(cx, |workspace, cx| {
+                        workspace.project().update(cx, |project, cx| {
+                            project.update_task_diagnostics(
+                                &spawn_task.id,
+                                &spawn_task.label,
+                                spawn_task.cwd.as_deref(),
+                                problems,
+                                cx,
+                            )
+                        })
+                    })
+                    .log_err();
+            }
         })
-        .detach_and_log_err(cx);
+        .detach();
     }
 
     fn serialize(&mut self, cx: &mut ViewContext<Self>) {
+        let panes = serialize_panes(&self.center.root, &self.active_pane, cx);
+        // The flat list of items and the active one are kept for older versions.
         let items = self
-            .pane
-            .read(cx)
-            .items()
-            .map(|item| item.item_id().as_u64())
+            .center
+            .panes()
+            .into_iter()
+            .flat_map(|pane| serializable_items(pane, cx))
             .collect::<Vec<_>>();
         let active_item_id = self
-            .pane
+            .active_pane
             .read(cx)
             .active_item()
             .map(|item| item.item_id().as_u64());
@@ -344,6 +709,7 @@ impl TerminalPanel {
                         serde_json::to_string(&SerializedTerminalPanel {
                             items,
                             active_item_id,
+                            panes: Some(panes),
                             height,
                             width,
                         })?,
@@ -356,6 +722,299 @@ impl TerminalPanel {
     }
 }
 
+/// Returns the ids of the terminals in the pane that get restored on restart. Tasks are
+/// not restored, as they would be spawned again.
+fn serializable_items(pane: &View<Pane>, cx: &AppContext) -> Vec<u64> {
+    pane.read(cx)
+        .items()
+        .filter(|item| {
+            item.downcast::<TerminalView>()
+                .map_or(true, |terminal_view| {
+                    terminal_view.read(cx).terminal().read(cx).task().is_none()
+                })
+        })
+        .map(|item| item.item_id().as_u64())
+        .collect()
+}
+
+fn serialize_panes(
+    member: &Member,
+    active_pane: &View<Pane>,
+    cx: &AppContext,
+) -> SerializedTerminalPanes {
+    match member {
+        Member::Pane(pane) => SerializedTerminalPanes::Pane {
+            items: serializable_items(pane, cx),
+            active_item_id: pane
+                .read(cx)
+                .active_item()
+                .map(|item| item.item_id().as_u64()),
+            active: pane == active_pane,
+        },
+        Member::Axis(axis) => SerializedTerminalPanes::Axis {
+            axis: axis.axis.into(),
+            members: axis
+                .members
+                .iter()
+                .map(|member| serialize_panes(member, active_pane, cx))
+                .collect(),
+            flexes: axis.flexes.lock().clone(),
+        },
+    }
+}
+
+fn new_terminal_pane(
+    workspace: WeakView<Workspace>,
+    project: Model<Project>,
+    cx: &mut ViewContext<TerminalPanel>,
+) -> View<Pane> {
+    let terminal_panel = cx.view().downgrade();
+    cx.new_view(|cx| {
+        let mut pane = Pane::new(
+            workspace.clone(),
+            project.clone(),
+            Default::default(),
+            None,
+            cx,
+        );
+        pane.set_can_split(false, cx);
+        pane.set_can_navigate(false, cx);
+        pane.display_nav_history_buttons(false);
+        pane.set_render_tab_bar_buttons(cx, move |pane, cx| {
+            let terminal_panel = terminal_panel.clone();
+            let this = cx.view().clone();
+            let profiles = project
+                .read(cx)
+                .terminal_settings(cx)
+                .profiles
+                .keys()
+                .cloned()
+                .sorted()
+                .collect::<Vec<_>>();
+            let profile_menu = (!profiles.is_empty()).then(|| {
+                let terminal_panel = terminal_panel.clone();
+                let this = this.clone();
+                popover_menu("terminal-profiles")
+                    .trigger(
+                        IconButton::new("terminal-profiles", IconName::ChevronDown)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| Tooltip::text("New Terminal with Profile", cx)),
+                    )
+                    .menu(move |cx| {
+                        ContextMenu::build(cx, |mut menu, _| {
+                            for profile in &profiles {
+                                let terminal_panel = terminal_panel.clone();
+                                let this = this.clone();
+                                let action = NewTerminalWithProfile {
+                                    profile: profile.clone(),
+                                };
+                                menu = menu.entry(
+                                    profile.clone(),
+                                    Some(Box::new(action.clone())),
+                                    move |cx| {
+                                        terminal_panel
+                                            .update(cx, |panel, cx| {
+                                                panel.active_pane = this.clone();
+                                                panel
+                                                    .add_terminal(
+                                                        None,
+                                                        Some(action.profile.clone()),
+                                                        None,
+                                                        None,
+                                                        cx,
+                                                    )
+                                                    .detach_and_log_err(cx)
+                                            })
+                                            .log_err();
+                                    },
+                                );
+                            }
+                            menu
+                        })
+                        .into()
+                    })
+                    .anchor(gpui::AnchorCorner::TopRight)
+            });
+            h_flex()
+                .gap_2()
+                .child(
+                    IconButton::new("plus", IconName::Plus)
+                        .icon_size(IconSize::Small)
+                        .on_click(move |_, cx| {
+                            terminal_panel
+                                .update(cx, |panel, cx| {
+                                    panel.active_pane = this.clone();
+                                    panel
+                                        .add_terminal(None, None, None, None, cx)
+                                        .detach_and_log_err(cx)
+                                })
+                                .log_err();
+                        })
+                        .tooltip(|cx| Tooltip::text("New Terminal", cx)),
+                )
+                .children(profile_menu)
+                .child(
+                    IconButton::new("split", IconName::Split)
+                        .icon_size(IconSize::Small)
+                        .on_click(cx.listener(|pane, _, cx| {
+                            pane.split(SplitDirection::Right, cx);
+                        }))
+                        .tooltip(|cx| Tooltip::text("Split Terminal", cx)),
+                )
+                .child({
+                    let zoomed = pane.is_zoomed();
+                    IconButton::new("toggle_zoom", IconName::Maximize)
+                        .icon_size(IconSize::Small)
+                        .selected(zoomed)
+                        .selected_icon(IconName::Minimize)
+                        .on_click(cx.listener(|pane, _, cx| {
+                            pane.toggle_zoom(&workspace::ToggleZoom, cx);
+                        }))
+                        .tooltip(move |cx| {
+                            Tooltip::text(if zoomed { "Zoom Out" } else { "Zoom In" }, cx)
+                        })
+                })
+                .into_any_element()
+        });
+
+        pane.set_custom_drop_handle(cx, move |pane, dropped_item, cx| {
+            if let Some(tab) = dropped_item.downcast_ref::<DraggedTab>() {
+                let item = if &tab.pane == cx.view() {
+                    pane.item_for_index(tab.ix)
+                } else {
+                    tab.pane.read(cx).item_for_index(tab.ix)
+                };
+                if let Some(item) = item {
+                    if item.downcast::<TerminalView>().is_some() {
+                        return ControlFlow::Continue(());
+                    } else if let Some(project_path) = item.project_path(cx) {
+                        if let Some(entry_path) = workspace
+                            .update(cx, |workspace, cx| {
+                                workspace
+                                    .project()
+                                    .read(cx)
+                                    .absolute_path(&project_path, cx)
+                            })
+                            .log_err()
+                            .flatten()
+                        {
+                            add_paths_to_terminal(pane, &[entry_path], cx);
+                        }
+                    }
+                }
+            } else if let Some(selection) = dropped_item.downcast_ref::<DraggedSelection>() {
+                let entry_paths = workspace
+                    .update(cx, |workspace, cx| {
+                        let project = workspace.project().read(cx);
+                        selection
+                            .items()
+                            .filter_map(|selected_entry| {
+                                let project_path =
+                                    project.path_for_entry(selected_entry.entry_id, cx)?;
+                                project.absolute_path(&project_path, cx)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .log_err()
+                    .unwrap_or_default();
+                if !entry_paths.is_empty() {
+                    add_paths_to_terminal(pane, &entry_paths, cx);
+                }
+            } else if let Some(paths) = dropped_item.downcast_ref::<ExternalPaths>() {
+                add_paths_to_terminal(pane, paths.paths(), cx);
+            }
+
+            ControlFlow::Break(())
+        });
+        let buffer_search_bar = cx.new_view(search::BufferSearchBar::new);
+        pane.toolbar()
+            .update(cx, |toolbar, cx| toolbar.add_item(buffer_search_bar, cx));
+        pane
+    })
+}
+
+/// Returns the code to send from the editor along with the name of its language.
+fn code_to_send(editor: &Editor, cx: &AppContext) -> Option<(String, Option<Arc<str>>)> {
+    let selection = editor.selections.newest::<Point>(cx);
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let language = snapshot
+        .language_at(selection.head())
+        .map(|language| language.name());
+    if !selection.is_empty() {
+        let code = snapshot.text_for_range(selection.range()).collect();
+        return Some((code, language));
+    }
+
+    let (buffer, line) = snapshot.buffer_line_for_row(selection.head().row)?;
+    let code = match buffer.statement_range_for_row(line.start.row) {
+        Some(statement) => buffer.text_for_range(statement).collect::<String>(),
+        None => buffer.text_for_range(line).collect::<String>(),
+    };
+    if code.trim().is_empty() {
+        return None;
+    }
+    Some((code, language))
+}
+
+/// Writes code to the program in the terminal, followed by the key press running it.
+fn send_code(terminal: &mut Terminal, code: &str, cx: &mut ModelContext<Terminal>) {
+    // Bracketed paste depends on the mode the program in the terminal asked for,
+    // which may have changed since the terminal last drew.
+    terminal.sync(cx);
+    if terminal
+        .last_content()
+        .mode
+        .contains(TermMode::BRACKETED_PASTE)
+    {
+        terminal.paste(code);
+        terminal.input("\r".to_string());
+    } else {
+        terminal.input(typed_code(code));
+    }
+}
+
+/// The input typing the code line by line. REPLs reading typed lines end an indented
+/// block at the first blank line, so the blank lines in the code are left out, and
+/// multi-line code is ended by one to close the block it may have opened.
+fn typed_code(code: &str) -> String {
+    let lines = code
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>();
+    let mut input = lines.join("\r");
+    input.push('\r');
+    if lines.len() > 1 {
+        input.push('\r');
+    }
+    input
+}
+
+/// How long to wait for a newly started REPL to print something before sending it code.
+const REPL_STARTUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Waits for a newly started terminal to print its first output, so that the program
+/// in it is likely ready to read input. Programs that print nothing until they get
+/// input are only waited for up to [`REPL_STARTUP_TIMEOUT`].
+async fn wait_for_output(terminal: &Model<Terminal>, mut cx: AsyncWindowContext) -> Result<()> {
+    let (tx, rx) = futures::channel::oneshot::channel();
+    let mut tx = Some(tx);
+    let _subscription = cx.update(|cx| {
+        cx.subscribe(terminal, move |_, event, _| {
+            if let terminal::Event::Wakeup = event {
+                if let Some(tx) = tx.take() {
+                    tx.send(()).ok();
+                }
+            }
+        })
+    })?;
+    let mut timeout = cx.background_executor().timer(REPL_STARTUP_TIMEOUT).fuse();
+    futures::select_biased! {
+        _ = rx => {}
+        _ = timeout => {}
+    }
+    Ok(())
+}
+
 fn add_paths_to_terminal(pane: &mut Pane, paths: &[PathBuf], cx: &mut ViewContext<'_, Pane>) {
     if let Some(terminal_view) = pane
         .active_item()
@@ -379,7 +1038,7 @@ impl Render for TerminalPanel {
         let mut registrar = DivRegistrar::new(
             |panel, cx| {
                 panel
-                    .pane
+                    .active_pane
                     .read(cx)
                     .toolbar()
                     .read(cx)
@@ -388,13 +1047,34 @@ impl Render for TerminalPanel {
             cx,
         );
         BufferSearchBar::register(&mut registrar);
-        registrar.into_div().size_full().child(self.pane.clone())
+        let panes = self.workspace.upgrade().map(|workspace| {
+            let workspace = workspace.read(cx);
+            let project = workspace.project().clone();
+            let app_state = workspace.app_state().clone();
+            self.center.render(
+                &project,
+                &HashMap::default(),
+                None,
+                &self.active_pane,
+                None,
+                &app_state,
+                &self.workspace,
+                cx,
+            )
+        });
+        registrar
+            .into_div()
+            .size_full()
+            .on_action(cx.listener(Self::activate_pane_in_direction))
+            .on_action(cx.listener(Self::activate_next_pane))
+            .on_action(cx.listener(Self::activate_previous_pane))
+            .children(panes)
     }
 }
 
 impl FocusableView for TerminalPanel {
     fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
-        self.pane.focus_handle(cx)
+        self.active_pane.focus_handle(cx)
     }
 }
 
@@ -442,21 +1122,29 @@ impl Panel for TerminalPanel {
     }
 
     fn is_zoomed(&self, cx: &WindowContext) -> bool {
-        self.pane.read(cx).is_zoomed()
+        self.active_pane.read(cx).is_zoomed()
     }
 
     fn set_zoomed(&mut self, zoomed: bool, cx: &mut ViewContext<Self>) {
-        self.pane.update(cx, |pane, cx| pane.set_zoomed(zoomed, cx));
+        for pane in self.center.panes() {
+            pane.update(cx, |pane, cx| pane.set_zoomed(zoomed, cx));
+        }
     }
 
     fn set_active(&mut self, active: bool, cx: &mut ViewContext<Self>) {
-        if active && self.pane.read(cx).items_len() == 0 {
-            self.add_terminal(None, cx)
+        if active && self.active_pane.read(cx).items_len() == 0 {
+            self.add_terminal(None, None, None, None, cx)
+                .detach_and_log_err(cx)
         }
     }
 
     fn icon_label(&self, cx: &WindowContext) -> Option<String> {
-        let count = self.pane.read(cx).items_len();
+        let count = self
+            .center
+            .panes()
+            .into_iter()
+            .map(|pane| pane.read(cx).items_len())
+            .sum::<usize>();
         if count == 0 {
             None
         } else {
@@ -483,8 +1171,363 @@ impl Panel for TerminalPanel {
 
 #[derive(Serialize, Deserialize)]
 struct SerializedTerminalPanel {
+    #[serde(default)]
     items: Vec<u64>,
+    #[serde(default)]
     active_item_id: Option<u64>,
+    #[serde(default)]
+    panes: Option<SerializedTerminalPanes>,
     width: Option<Pixels>,
     height: Option<Pixels>,
 }
+
+/// The layout of the panes in the terminal panel.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum SerializedTerminalPanes {
+    Pane {
+        items: Vec<u64>,
+        active_item_id: Option<u64>,
+        active: bool,
+    },
+    Axis {
+        axis: SerializedAxis,
+        members: Vec<SerializedTerminalPanes>,
+        flexes: Vec<f32>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SerializedAxis {
+    Horizontal,
+    Vertical,
+}
+
+impl From<Axis> for SerializedAxis {
+    fn from(axis: Axis) -> Self {
+        match axis {
+            Axis::Horizontal => Self::Horizontal,
+            Axis::Vertical => Self::Vertical,
+        }
+    }
+}
+
+impl From<SerializedAxis> for Axis {
+    fn from(axis: SerializedAxis) -> Self {
+        match axis {
+            SerializedAxis::Horizontal => Self::Horizontal,
+            SerializedAxis::Vertical => Self::Vertical,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::TERMINAL_DB;
+    use gpui::{TestAppContext, VisualTestContext};
+    use settings::SettingsStore;
+    use std::path::Path;
+    use terminal::terminal_settings::{Shell, TerminalProfile};
+    use workspace::ActivatePaneInDirection;
+
+    #[test]
+    fn test_serialized_panel_layout() {
+        let panel = SerializedTerminalPanel {
+            items: vec![1, 2, 3],
+            active_item_id: Some(3),
+            panes: Some(SerializedTerminalPanes::Axis {
+                axis: SerializedAxis::Horizontal,
+                members: vec![
+                    SerializedTerminalPanes::Pane {
+                        items: vec![1, 2],
+                        active_item_id: Some(1),
+                        active: false,
+                    },
+                    SerializedTerminalPanes::Pane {
+                        items: vec![3],
+                        active_item_id: Some(3),
+                        active: true,
+                    },
+                ],
+                flexes: vec![0.5, 1.5],
+            }),
+            width: None,
+            height: None,
+        };
+        let json = serde_json::to_string(&panel).unwrap();
+        let deserialized = serde_json::from_str::<SerializedTerminalPanel>(&json).unwrap();
+        assert_eq!(deserialized.panes, panel.panes);
+
+        // Panels saved before splits were supported have no layout.
+        let legacy = serde_json::from_str::<SerializedTerminalPanel>(
+            r#"{"items":[4,5],"active_item_id":5,"width":null,"height":null}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.items, vec![4, 5]);
+        assert_eq!(legacy.active_item_id, Some(5));
+        assert_eq!(legacy.panes, None);
+    }
+
+    #[cfg(unix)]
+    #[gpui::test]
+    async fn test_sending_code_without_bracketed_paste(cx: &mut TestAppContext) {
+        let (project, _workspace) = crate::tests::init_test(cx).await;
+        cx.update(TerminalSettings::register);
+        cx.executor().allow_parking();
+
+        // The terminal runs a program recording its raw input, which like most REPLs
+        // doesn't ask for bracketed paste.
+        let dir = tempfile::tempdir().unwrap();
+        let ready = dir.path().join("ready");
+        let input = dir.path().join("input");
+        let script = format!(
+            "stty raw -echo && touch '{}' && exec cat > '{}'",
+            ready.display(),
+            input.display()
+        );
+        let window = cx.windows()[0];
+        let terminal = project
+            .update(cx, |project, cx| {
+                let record_input = SpawnInTerminal {
+                    id: TaskId("record_input".to_string()),
+                    label: "Record input".to_string(),
+                    command: "sh".to_string(),
+                    args: vec!["-c".to_string(), script],
+                    cwd: Some(dir.path().to_path_buf()),
+                    env: HashMap::default(),
+                    use_new_terminal: false,
+                    allow_concurrent_runs: false,
+                    problem_matchers: Vec::new(),
+                };
+                project.create_terminal(None, None, Some(record_input), window, cx)
+            })
+            .unwrap();
+        wait_for_file(&ready, |_| true);
+
+        terminal.update(cx, |terminal, cx| send_code(terminal, "x = 1", cx));
+        wait_for_file(&input, |text| text == "x = 1\r");
+
+        // Blank lines would end the block early, and the block needs one to end.
+        terminal.update(cx, |terminal, cx| {
+            send_code(terminal, "def f():\n    a = 1\n\n    return a\n", cx)
+        });
+        wait_for_file(&input, |text| {
+            text == "x = 1\rdef f():\r    a = 1\r    return a\r\r"
+        });
+    }
+
+    #[cfg(unix)]
+    #[gpui::test]
+    async fn test_splitting_and_removing_panes(cx: &mut TestAppContext) {
+        let (_project, workspace) = crate::tests::init_test(cx).await;
+        cx.update(|cx| {
+            TerminalSettings::register(cx);
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings::<TerminalSettings>(cx, |settings| {
+                    settings.profiles = Some(HashMap::from_iter([(
+                        "dev".to_string(),
+                        TerminalProfile {
+                            shell: Some(Shell::Program("sh".to_string())),
+                            ..Default::default()
+                        },
+                    )]));
+                });
+            });
+        });
+        cx.executor().allow_parking();
+        let window = cx.windows()[0];
+        let cx = &mut VisualTestContext::from_window(window, cx);
+        let panel = open_panel(&workspace, cx);
+        let first_pane = panel.update(cx, |panel, _| panel.active_pane.clone());
+        panel
+            .update(cx, |panel, cx| {
+                panel.add_terminal(None, Some("dev".to_string()), None, None, cx)
+            })
+            .await
+            .unwrap();
+
+        // The new pane becomes the active one, with a terminal started the same way.
+        panel.update(cx, |panel, cx| {
+            panel.split_pane(first_pane.clone(), SplitDirection::Right, cx)
+        });
+        cx.run_until_parked();
+        let second_pane = panel.update(cx, |panel, cx| {
+            assert_eq!(panel.center.panes().len(), 2);
+            assert_ne!(panel.active_pane, first_pane);
+            let terminal_view = panel
+                .active_pane
+                .read(cx)
+                .active_item()
+                .and_then(|item| item.downcast::<TerminalView>())
+                .unwrap();
+            assert_eq!(
+                terminal_view.read(cx).terminal().read(cx).profile(),
+                Some("dev")
+            );
+            panel.active_pane.clone()
+        });
+
+        // Removing it collapses the layout back to the first pane, which can't be removed.
+        panel.update(cx, |panel, cx| {
+            assert!(panel.remove_pane(&second_pane, cx));
+            assert_eq!(panel.center.panes(), vec![&first_pane]);
+            assert!(matches!(&panel.center.root, Member::Pane(pane) if *pane == first_pane));
+            assert_eq!(panel.active_pane, first_pane);
+            assert!(!panel.remove_pane(&first_pane, cx));
+        });
+    }
+
+    #[cfg(unix)]
+    #[gpui::test]
+    async fn test_activating_pane_in_direction(cx: &mut TestAppContext) {
+        let (_project, workspace) = crate::tests::init_test(cx).await;
+        cx.update(TerminalSettings::register);
+        cx.executor().allow_parking();
+        let window = cx.windows()[0];
+        let cx = &mut VisualTestContext::from_window(window, cx);
+        let panel = open_panel(&workspace, cx);
+        let left_pane = panel.update(cx, |panel, _| panel.active_pane.clone());
+        panel
+            .update(cx, |panel, cx| {
+                panel.add_terminal(None, None, None, None, cx)
+            })
+            .await
+            .unwrap();
+        panel.update(cx, |panel, cx| {
+            panel.split_pane(left_pane.clone(), SplitDirection::Right, cx)
+        });
+        cx.run_until_parked();
+        let right_pane = panel.update(cx, |panel, _| panel.active_pane.clone());
+        assert_ne!(right_pane, left_pane);
+
+        cx.dispatch_action(ActivatePaneInDirection(SplitDirection::Left));
+        cx.run_until_parked();
+        panel.update(cx, |panel, cx| {
+            assert_eq!(panel.active_pane, left_pane);
+            assert!(left_pane.focus_handle(cx).contains_focused(cx));
+        });
+
+        cx.dispatch_action(ActivatePaneInDirection(SplitDirection::Right));
+        cx.run_until_parked();
+        panel.update(cx, |panel, cx| {
+            assert_eq!(panel.active_pane, right_pane);
+            assert!(right_pane.focus_handle(cx).contains_focused(cx));
+        });
+    }
+
+    #[cfg(unix)]
+    #[gpui::test]
+    async fn test_restoring_split_layout(cx: &mut TestAppContext) {
+        let (project, workspace) = crate::tests::init_test(cx).await;
+        // Terminals mark the directory they were started in.
+        cx.update(|cx| {
+            TerminalSettings::register(cx);
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings::<TerminalSettings>(cx, |settings| {
+                    settings.shell = Some(Shell::WithArguments {
+                        program: "sh".to_string(),
+                        args: vec!["-c".to_string(), "touch started && exec cat".to_string()],
+                    });
+                });
+            });
+        });
+        cx.executor().allow_parking();
+
+        // Saved terminals belong to a workspace in the database.
+        let workspace_id = workspace::WORKSPACE_DB.next_id().await.unwrap();
+        let app_state = workspace.update(cx, |workspace, _| workspace.app_state().clone());
+        let window = cx.add_window(|cx| Workspace::new(workspace_id, project, app_state, cx));
+        let workspace = window.root_view(cx).unwrap();
+        let left_dir = tempfile::tempdir().unwrap();
+        let right_dir = tempfile::tempdir().unwrap();
+        TERMINAL_DB
+            .save_working_directory(1, workspace_id, left_dir.path().to_path_buf())
+            .await
+            .unwrap();
+        TERMINAL_DB
+            .save_working_directory(2, workspace_id, right_dir.path().to_path_buf())
+            .await
+            .unwrap();
+
+        let cx = &mut VisualTestContext::from_window(window.into(), cx);
+        let serialized = SerializedTerminalPanes::Axis {
+            axis: SerializedAxis::Horizontal,
+            members: vec![
+                SerializedTerminalPanes::Pane {
+                    items: vec![1],
+                    active_item_id: Some(1),
+                    active: false,
+                },
+                SerializedTerminalPanes::Pane {
+                    items: vec![2],
+                    active_item_id: Some(2),
+                    active: true,
+                },
+            ],
+            flexes: vec![0.5, 1.5],
+        };
+        let (panel, panes) = workspace.update(cx, |workspace, cx| {
+            let panel = cx.new_view(|cx| TerminalPanel::new(workspace, cx));
+            let panes = panel.update(cx, |panel, cx| {
+                let mut initial_pane = Some(panel.active_pane.clone());
+                let mut panes = Vec::new();
+                let root = panel.deserialize_panes(
+                    serialized,
+                    workspace,
+                    &mut initial_pane,
+                    &mut panes,
+                    cx,
+                );
+                panel.center = PaneGroup::with_root(root);
+                panes
+            });
+            (panel, panes)
+        });
+        assert_eq!(panes.len(), 2);
+        for (_, items, _) in panes {
+            for item in items {
+                item.await.unwrap();
+            }
+        }
+
+        panel.update(cx, |panel, _| {
+            let Member::Axis(axis) = &panel.center.root else {
+                panic!("expected the panes to be split");
+            };
+            assert_eq!(axis.axis, Axis::Horizontal);
+            assert_eq!(*axis.flexes.lock(), vec![0.5, 1.5]);
+            assert_eq!(panel.center.panes().len(), 2);
+            assert_eq!(&panel.active_pane, panel.center.panes()[1]);
+        });
+        wait_for_file(&left_dir.path().join("started"), |_| true);
+        wait_for_file(&right_dir.path().join("started"), |_| true);
+    }
+
+    fn open_panel(workspace: &View<Workspace>, cx: &mut VisualTestContext) -> View<TerminalPanel> {
+        let panel = workspace.update(cx, |workspace, cx| {
+            let panel = cx.new_view(|cx| TerminalPanel::new(workspace, cx));
+            workspace.add_panel(panel.clone(), cx);
+            workspace.open_panel::<TerminalPanel>(cx);
+            panel
+        });
+        cx.run_until_parked();
+        panel
+    }
+
+    fn wait_for_file(path: &Path, done: impl Fn(&str) -> bool) {
+        for _ in 0..100 {
+            if let Ok(text) = std::fs::read_to_string(path) {
+                if done(&text) {
+                    return;
+                }
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        panic!(
+            "unexpected contents of {path:?}: {:?}",
+            std::fs::read_to_string(path)
+        );
+    }
+}
@@ -1,16 +1,20 @@
+mod output_filter;
 mod persistence;
 pub mod terminal_element;
 pub mod terminal_panel;
 
+pub use terminal::session::run_daemon_if_requested as run_session_daemon_if_requested;
+
 use collections::HashSet;
 use editor::{scroll::Autoscroll, Editor};
 use futures::{stream::FuturesUnordered, StreamExt};
 use gpui::{
-    div, impl_actions, overlay, AnyElement, AppContext, DismissEvent, EventEmitter, FocusHandle,
-    FocusableView, KeyContext, KeyDownEvent, Keystroke, Model, MouseButton, MouseDownEvent, Pixels,
-    Render, Styled, Subscription, Task, View, VisualContext, WeakView,
+    div, impl_actions, overlay, AnyElement, AppContext, ClipboardItem, DismissEvent, EventEmitter,
+    FocusHandle, FocusableView, KeyContext, KeyDownEvent, Keystroke, Model, MouseButton,
+    MouseDownEvent, Pixels, Render, Styled, Subscription, Task, View, VisualContext, WeakView,
 };
 use language::Bias;
+use output_filter::OutputFilter;
 use persistence::TERMINAL_DB;
 use project::{search::SearchQuery, Fs, LocalWorktree, Metadata, Project};
 use terminal::{
@@ -19,7 +23,9 @@ use terminal::{
         term::{search::RegexSearch, TermMode},
     },
     terminal_settings::{TerminalBlink, TerminalSettings, WorkingDirectory},
-    Clear, Copy, Event, MaybeNavigationTarget, Paste, ShowCharacterPalette, Terminal,
+    Clear, Copy, CopyLastCommandOutput, CopyMatchingLines, Event, MaybeNavigationTarget, Paste,
+    ScrollToNextPrompt, ScrollToPreviousPrompt, ShowCharacterPalette, TaskStatus, Terminal,
+    ToggleOutputFilter,
 };
 use terminal_element::TerminalElement;
 use ui::{h_flex, prelude::*, ContextMenu, Icon, IconName, Label};
@@ -89,6 +95,7 @@ pub struct TerminalView {
     blinking_paused: bool,
     blink_epoch: usize,
     can_navigate_to_selected_word: bool,
+    output_filter: Option<View<OutputFilter>>,
     workspace_id: WorkspaceId,
     _subscriptions: Vec<Subscription>,
 }
@@ -110,15 +117,13 @@ impl TerminalView {
         _: &NewCenterTerminal,
         cx: &mut ViewContext<Workspace>,
     ) {
-        let strategy = TerminalSettings::get_global(cx);
-        let working_directory =
-            get_working_directory(workspace, cx, strategy.working_directory.clone());
+        let working_directory = profile_working_directory(workspace, None, cx);
 
         let window = cx.window_handle();
         let terminal = workspace
             .project()
             .update(cx, |project, cx| {
-                project.create_terminal(working_directory, window, cx)
+                project.create_terminal(working_directory, None, None, window, cx)
             })
             .notify_err(workspace, cx);
 
@@ -295,6 +300,7 @@ impl TerminalView {
             Event::BreadcrumbsChanged => cx.emit(ItemEvent::UpdateBreadcrumbs),
             Event::CloseTerminal => cx.emit(ItemEvent::CloseItem),
             Event::SelectionsChanged => cx.emit(SearchEvent::ActiveMatchChanged),
+            Event::TaskFinished => cx.emit(ItemEvent::UpdateTab),
         })
         .detach();
 
@@ -306,6 +312,31 @@ impl TerminalView {
             terminal_view.focus_out(cx);
         });
 
+        if let Some(session_id) = terminal.read(cx).session_id() {
+            let session_id = session_id.to_string();
+            let item_id = cx.entity_id();
+            cx.background_executor()
+                .spawn(async move {
+                    TERMINAL_DB
+                        .save_session_id(item_id.as_u64(), workspace_id, session_id)
+                        .await
+                        .log_err();
+                })
+                .detach();
+        }
+        if let Some(profile) = terminal.read(cx).profile() {
+            let profile = profile.to_string();
+            let item_id = cx.entity_id();
+            cx.background_executor()
+                .spawn(async move {
+                    TERMINAL_DB
+                        .save_profile(item_id.as_u64(), workspace_id, profile)
+                        .await
+                        .log_err();
+                })
+                .detach();
+        }
+
         Self {
             terminal,
             workspace: workspace_handle,
@@ -317,6 +348,7 @@ impl TerminalView {
             blinking_paused: false,
             blink_epoch: 0,
             can_navigate_to_selected_word: false,
+            output_filter: None,
             workspace_id,
             _subscriptions: vec![focus_in, focus_out],
         }
@@ -342,6 +374,9 @@ impl TerminalView {
     ) {
         let context_menu = ContextMenu::build(cx, |menu, _| {
             menu.action("Clear", Box::new(Clear))
+                .action("Copy Last Command Output", Box::new(CopyLastCommandOutput))
+                .action("Filter Output", Box::new(ToggleOutputFilter))
+                .action("Copy Matching Lines", Box::new(CopyMatchingLines))
                 .action("Close", Box::new(CloseActiveItem { save_intent: None }))
         });
 
@@ -389,6 +424,64 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn scroll_to_previous_prompt(
+        &mut self,
+        _: &ScrollToPreviousPrompt,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_previous_prompt());
+        cx.notify();
+    }
+
+    fn scroll_to_next_prompt(&mut self, _: &ScrollToNextPrompt, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_next_prompt());
+        cx.notify();
+    }
+
+    fn copy_last_command_output(&mut self, _: &CopyLastCommandOutput, cx: &mut ViewContext<Self>) {
+        if let Some(output) = self.terminal.read(cx).last_command_output() {
+            cx.write_to_clipboard(ClipboardItem::new(output));
+        }
+    }
+
+    /// Opens the lines that contain the current search matches in a new buffer.
+    fn copy_matching_lines(&mut self, _: &CopyMatchingLines, cx: &mut ViewContext<Self>) {
+        let lines = self.terminal.read(cx).matching_lines();
+        if lines.is_empty() {
+            return;
+        }
+        let mut text = lines.join("\n");
+        text.push('\n');
+        self.workspace
+            .update(cx, |workspace, cx| {
+                let project = workspace.project().clone();
+                let Some(buffer) = project
+                    .update(cx, |project, cx| project.create_buffer(&text, None, cx))
+                    .log_err()
+                else {
+                    return;
+                };
+                let editor = cx.new_view(|cx| Editor::for_buffer(buffer, Some(project), cx));
+                workspace.add_item(Box::new(editor), cx);
+            })
+            .ok();
+    }
+
+    /// Shows only the lines of output that match a regex in place of the terminal, or
+    /// shows the terminal again.
+    fn toggle_output_filter(&mut self, _: &ToggleOutputFilter, cx: &mut ViewContext<Self>) {
+        if self.output_filter.take().is_some() {
+            cx.focus_self();
+        } else {
+            let output_filter = cx.new_view(|cx| OutputFilter::new(&self.terminal, cx));
+            cx.focus_view(&output_filter);
+            self.output_filter = Some(output_filter);
+        }
+        cx.notify();
+    }
+
     pub fn should_show_cursor(&self, focused: bool, cx: &mut gpui::ViewContext<Self>) -> bool {
         //Don't blink the cursor when not focused, blinking is disabled, or paused
         if !focused
@@ -659,14 +752,34 @@ fn regex_to_literal(regex: &str) -> String {
 }
 
 pub fn regex_search_for_query(query: &project::search::SearchQuery) -> Option<RegexSearch> {
-    let query = query.as_str();
-    if query == "." {
+    if query.as_str() == "." {
         return None;
     }
-    let searcher = RegexSearch::new(&query);
+    let searcher = RegexSearch::new(&search_pattern(query));
     searcher.ok()
 }
 
+/// Builds the regex alacritty searches with, applying the query's options.
+fn search_pattern(query: &SearchQuery) -> String {
+    let mut pattern = if query.is_regex() {
+        query.as_str().to_string()
+    } else {
+        regex_to_literal(query.as_str())
+    };
+    if query.whole_word() {
+        // Alacritty's DFAs can't match Unicode word boundaries.
+        pattern = format!(r"(?-u:\b)(?:{pattern})(?-u:\b)");
+    }
+    // Alacritty ignores case unless the pattern has an uppercase letter, so the
+    // flag is always set.
+    let case_flag = if query.case_sensitive() {
+        "(?-i)"
+    } else {
+        "(?i)"
+    };
+    format!("{case_flag}{pattern}")
+}
+
 impl TerminalView {
     fn key_down(&mut self, event: &KeyDownEvent, cx: &mut ViewContext<Self>) {
         self.clear_bel(cx);
@@ -704,35 +817,45 @@ impl Render for TerminalView {
             .size_full()
             .relative()
             .track_focus(&self.focus_handle)
-            .key_context(self.dispatch_context(cx))
-            .on_action(cx.listener(TerminalView::send_text))
-            .on_action(cx.listener(TerminalView::send_keystroke))
-            .on_action(cx.listener(TerminalView::copy))
-            .on_action(cx.listener(TerminalView::paste))
-            .on_action(cx.listener(TerminalView::clear))
-            .on_action(cx.listener(TerminalView::show_character_palette))
-            .on_action(cx.listener(TerminalView::select_all))
-            .on_key_down(cx.listener(Self::key_down))
-            .on_mouse_down(
-                MouseButton::Right,
-                cx.listener(|this, event: &MouseDownEvent, cx| {
-                    if !this.terminal.read(cx).mouse_mode(event.modifiers.shift) {
-                        this.deploy_context_menu(event.position, cx);
-                        cx.notify();
-                    }
-                }),
-            )
-            .child(
-                // TODO: Oddly this wrapper div is needed for TerminalElement to not steal events from the context menu
-                div().size_full().child(TerminalElement::new(
-                    terminal_handle,
-                    self.workspace.clone(),
-                    self.focus_handle.clone(),
-                    focused,
-                    self.should_show_cursor(focused, cx),
-                    self.can_navigate_to_selected_word,
-                )),
-            )
+            .on_action(cx.listener(TerminalView::toggle_output_filter))
+            .map(|this| match &self.output_filter {
+                // The filter gets the keystrokes that would go to the terminal.
+                Some(output_filter) => this.child(output_filter.clone()),
+                None => this
+                    .key_context(self.dispatch_context(cx))
+                    .on_action(cx.listener(TerminalView::send_text))
+                    .on_action(cx.listener(TerminalView::send_keystroke))
+                    .on_action(cx.listener(TerminalView::copy))
+                    .on_action(cx.listener(TerminalView::paste))
+                    .on_action(cx.listener(TerminalView::clear))
+                    .on_action(cx.listener(TerminalView::scroll_to_previous_prompt))
+                    .on_action(cx.listener(TerminalView::scroll_to_next_prompt))
+                    .on_action(cx.listener(TerminalView::copy_last_command_output))
+                    .on_action(cx.listener(TerminalView::copy_matching_lines))
+                    .on_action(cx.listener(TerminalView::show_character_palette))
+                    .on_action(cx.listener(TerminalView::select_all))
+                    .on_key_down(cx.listener(Self::key_down))
+                    .on_mouse_down(
+                        MouseButton::Right,
+                        cx.listener(|this, event: &MouseDownEvent, cx| {
+                            if !this.terminal.read(cx).mouse_mode(event.modifiers.shift) {
+                                this.deploy_context_menu(event.position, cx);
+                                cx.notify();
+                            }
+                        }),
+                    )
+                    .child(
+                        // TODO: Oddly this wrapper div is needed for TerminalElement to not steal events from the context menu
+                        div().size_full().child(TerminalElement::new(
+                            terminal_handle,
+                            self.workspace.clone(),
+                            self.focus_handle.clone(),
+                            focused,
+                            self.should_show_cursor(focused, cx),
+                            self.can_navigate_to_selected_word,
+                        )),
+                    ),
+            })
             .children(self.context_menu.as_ref().map(|(menu, position, _)| {
                 overlay()
                     .position(*position)
@@ -755,10 +878,15 @@ impl Item for TerminalView {
         selected: bool,
         cx: &WindowContext,
     ) -> AnyElement {
-        let title = self.terminal().read(cx).title(true);
+        let terminal = self.terminal().read(cx);
+        let title = terminal.title(true);
+        let icon = match terminal.task() {
+            Some(task) if task.status == TaskStatus::Completed => IconName::Check,
+            _ => IconName::Terminal,
+        };
         h_flex()
             .gap_2()
-            .child(Icon::new(IconName::Terminal))
+            .child(Icon::new(icon))
             .child(Label::new(title).color(if selected {
                 Color::Default
             } else {
@@ -794,6 +922,11 @@ impl Item for TerminalView {
         self.has_bell()
     }
 
+    fn closed(&mut self, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |terminal, _| terminal.kill_session());
+    }
+
     fn has_conflict(&self, _cx: &AppContext) -> bool {
         false
     }
@@ -826,26 +959,44 @@ impl Item for TerminalView {
     ) -> Task<anyhow::Result<View<Self>>> {
         let window = cx.window_handle();
         cx.spawn(|pane, mut cx| async move {
+            let profile = TERMINAL_DB
+                .get_profile(item_id, workspace_id)
+                .log_err()
+                .flatten();
+
+            // Reattach to the session the shell kept running in, if it still is.
+            let session_id = TERMINAL_DB
+                .get_session_id(item_id, workspace_id)
+                .log_err()
+                .flatten();
+            if let Some(session_id) = session_id {
+                let terminal = project
+                    .update(&mut cx, |project, cx| {
+                        project.attach_terminal_session(session_id, profile.clone(), cx)
+                    })?
+                    .ok();
+                if let Some(terminal) = terminal {
+                    return pane.update(&mut cx, |_, cx| {
+                        cx.new_view(|cx| TerminalView::new(terminal, workspace, workspace_id, cx))
+                    });
+                }
+            }
+
             let cwd = TERMINAL_DB
                 .get_working_directory(item_id, workspace_id)
                 .log_err()
                 .flatten()
                 .or_else(|| {
                     cx.update(|cx| {
-                        let strategy = TerminalSettings::get_global(cx).working_directory.clone();
-                        workspace
-                            .upgrade()
-                            .map(|workspace| {
-                                get_working_directory(workspace.read(cx), cx, strategy)
-                            })
-                            .flatten()
+                        let workspace = workspace.upgrade()?;
+                        profile_working_directory(workspace.read(cx), profile.as_deref(), cx)
                     })
                     .ok()
                     .flatten()
                 });
 
             let terminal = project.update(&mut cx, |project, cx| {
-                project.create_terminal(cwd, window, cx)
+                project.create_terminal(cwd, profile.as_deref(), None, window, cx)
             })??;
             pane.update(&mut cx, |_, cx| {
                 cx.new_view(|cx| TerminalView::new(terminal, workspace, workspace_id, cx))
@@ -874,10 +1025,11 @@ impl SearchableItem for TerminalView {
 
     fn supported_options() -> SearchOptions {
         SearchOptions {
-            case: false,
-            word: false,
+            case: true,
+            word: true,
             regex: true,
             replacement: false,
+            selection: false,
         }
     }
 
@@ -921,22 +1073,7 @@ impl SearchableItem for TerminalView {
         query: Arc<SearchQuery>,
         cx: &mut ViewContext<Self>,
     ) -> Task<Vec<Self::Match>> {
-        let searcher = match &*query {
-            SearchQuery::Text { .. } => regex_search_for_query(
-                &(SearchQuery::text(
-                    regex_to_literal(&query.as_str()),
-                    query.whole_word(),
-                    query.case_sensitive(),
-                    query.include_ignored(),
-                    query.files_to_include().to_vec(),
-                    query.files_to_exclude().to_vec(),
-                )
-                .unwrap()),
-            ),
-            SearchQuery::Regex { .. } => regex_search_for_query(&query),
-        };
-
-        if let Some(s) = searcher {
+        if let Some(s) = regex_search_for_query(&query) {
             self.terminal()
                 .update(cx, |term, cx| term.find_matches(s, cx))
         } else {
@@ -985,6 +1122,21 @@ impl SearchableItem for TerminalView {
     }
 }
 
+///Gets the working directory of a new terminal with the given profile, or the default one.
+pub fn profile_working_directory(
+    workspace: &Workspace,
+    profile: Option<&str>,
+    cx: &AppContext,
+) -> Option<PathBuf> {
+    let strategy = workspace
+        .project()
+        .read(cx)
+        .terminal_settings(cx)
+        .profile(profile)
+        .working_directory;
+    get_working_directory(workspace, cx, strategy)
+}
+
 ///Gets the working directory for the given workspace, respecting the user's settings.
 pub fn get_working_directory(
     workspace: &Workspace,
@@ -1040,9 +1192,16 @@ fn get_path_from_wt(wt: &LocalWorktree) -> Option<PathBuf> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use gpui::TestAppContext;
+    use collections::HashMap;
+    use gpui::{TestAppContext, VisualTestContext};
     use project::{Entry, Project, ProjectPath, Worktree};
+    use settings::SettingsStore;
     use std::path::Path;
+    use task::{SpawnInTerminal, TaskId};
+    use terminal::{
+        terminal_settings::{Shell, TerminalProfile},
+        TerminalSize,
+    };
     use workspace::AppState;
 
     // Working directory calculation tests
@@ -1151,6 +1310,205 @@ mod tests {
         });
     }
 
+    // A profile's working directory overrides the default one
+    #[gpui::test]
+    async fn profile_working_directory_overrides_default(cx: &mut TestAppContext) {
+        let (project, workspace) = init_test(cx).await;
+        cx.update(|cx| {
+            TerminalSettings::register(cx);
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings::<TerminalSettings>(cx, |settings| {
+                    settings.working_directory = Some(WorkingDirectory::CurrentProjectDirectory);
+                    settings.profiles = Some(HashMap::from_iter([(
+                        "first".to_string(),
+                        TerminalProfile {
+                            working_directory: Some(WorkingDirectory::FirstProjectDirectory),
+                            ..Default::default()
+                        },
+                    )]));
+                });
+            });
+        });
+
+        let (_wt, _entry) = create_folder_wt(project.clone(), "/root1/", cx).await;
+        let (wt2, entry2) = create_folder_wt(project.clone(), "/root2/", cx).await;
+        insert_active_entry_for(wt2, entry2, project.clone(), cx);
+
+        cx.update(|cx| {
+            let workspace = workspace.read(cx);
+
+            let res = profile_working_directory(workspace, None, cx);
+            assert_eq!(res, Some((Path::new("/root2/")).to_path_buf()));
+            let res = profile_working_directory(workspace, Some("first"), cx);
+            assert_eq!(res, Some((Path::new("/root1/")).to_path_buf()));
+        });
+    }
+
+    // A restored terminal starts with the profile it was started with
+    #[cfg(unix)]
+    #[gpui::test]
+    async fn restored_terminal_keeps_profile(cx: &mut TestAppContext) {
+        let (project, workspace) = init_test(cx).await;
+        cx.update(|cx| {
+            TerminalSettings::register(cx);
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings::<TerminalSettings>(cx, |settings| {
+                    settings.profiles = Some(HashMap::from_iter([(
+                        "dev".to_string(),
+                        TerminalProfile {
+                            shell: Some(Shell::Program("sh".to_string())),
+                            ..Default::default()
+                        },
+                    )]));
+                });
+            });
+        });
+        cx.executor().allow_parking();
+        let workspace_id = workspace::WORKSPACE_DB.next_id().await.unwrap();
+
+        let window = cx.windows()[0];
+        let terminal = project
+            .update(cx, |project, cx| {
+                project.create_terminal(None, Some("dev"), None, window, cx)
+            })
+            .unwrap();
+        let cx = &mut VisualTestContext::from_window(window, cx);
+        let terminal_view = workspace.update(cx, |workspace, cx| {
+            let weak_workspace = workspace.weak_handle();
+            cx.new_view(|cx| TerminalView::new(terminal, weak_workspace, workspace_id, cx))
+        });
+        let item_id = terminal_view.entity_id().as_u64();
+        wait_until(cx, |_| {
+            TERMINAL_DB
+                .get_profile(item_id, workspace_id)
+                .unwrap()
+                .is_some()
+        });
+
+        let weak_workspace = workspace.downgrade();
+        let restored = workspace
+            .update(cx, |workspace, cx| {
+                workspace.active_pane().update(cx, |_, cx| {
+                    TerminalView::deserialize(project, weak_workspace, workspace_id, item_id, cx)
+                })
+            })
+            .await
+            .unwrap();
+        restored.update(cx, |restored, cx| {
+            assert_eq!(restored.terminal().read(cx).profile(), Some("dev"));
+        });
+    }
+
+    // Marks printed by a program in a real PTY are resolved to where they were
+    // printed, and commands can be navigated and copied
+    #[cfg(unix)]
+    #[gpui::test]
+    async fn shell_marks_from_pty_output(cx: &mut TestAppContext) {
+        let (project, workspace) = init_test(cx).await;
+        cx.update(TerminalSettings::register);
+        cx.executor().allow_parking();
+
+        let terminal = spawn_script(
+            &project,
+            r#"mark() { printf '\033]133;%s\007' "$1"; }
+            for n in 1 2; do
+                mark A; printf '$ '; mark B; printf 'seq 30\n'; mark C
+                seq $((n * 30 - 29)) $((n * 30)); mark 'D;0'
+            done
+            mark A; printf '$ '
+            exec sleep 60"#,
+            cx,
+        );
+        wait_until(cx, |cx| {
+            terminal.read_with(cx, |terminal, _| terminal.shell_commands().len() == 3)
+        });
+
+        // Each prompt starts its line: the first at the top of the output, and
+        // the others after the 30 lines of output of the command before them.
+        let prompt_lines = terminal.read_with(cx, |terminal, _| {
+            terminal
+                .shell_commands()
+                .iter()
+                .map(|command| command.prompt.line)
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(prompt_lines, [0, 31, 62]);
+        let expected_output = (31..=60).map(|n| n.to_string()).collect::<Vec<_>>();
+        let output = terminal.read_with(cx, |terminal, _| terminal.last_command_output());
+        assert_eq!(output.unwrap().trim_end(), expected_output.join("\n"));
+
+        let mut scroll = |previous: bool| {
+            terminal.update(cx, |terminal, cx| {
+                if previous {
+                    terminal.scroll_to_previous_prompt();
+                } else {
+                    terminal.scroll_to_next_prompt();
+                }
+                terminal.sync(cx);
+                terminal.last_content().display_offset
+            })
+        };
+        // The 63 lines printed fill the screen, and the rest is scrollback.
+        let lines_above = 63 - TerminalSize::default().num_lines();
+        assert_eq!(scroll(true), lines_above - 31);
+        assert_eq!(scroll(true), lines_above);
+        assert_eq!(scroll(true), lines_above);
+        assert_eq!(scroll(false), lines_above - 31);
+        assert_eq!(scroll(false), 0);
+
+        let cx = &mut VisualTestContext::from_window(cx.windows()[0], cx);
+        let terminal_view = workspace.update(cx, |workspace, cx| {
+            let workspace_id = workspace.database_id();
+            let weak_workspace = workspace.weak_handle();
+            cx.new_view(|cx| TerminalView::new(terminal.clone(), weak_workspace, workspace_id, cx))
+        });
+        terminal_view.update(cx, |terminal_view, cx| {
+            terminal_view.copy_last_command_output(&CopyLastCommandOutput, cx)
+        });
+        let clipboard = cx.read_from_clipboard().unwrap();
+        assert_eq!(clipboard.text().trim_end(), expected_output.join("\n"));
+    }
+
+    /// Runs a shell script in a new terminal.
+    pub fn spawn_script(
+        project: &Model<Project>,
+        script: &str,
+        cx: &mut TestAppContext,
+    ) -> Model<Terminal> {
+        let window = cx.windows()[0];
+        project
+            .update(cx, |project, cx| {
+                let spawn_task = SpawnInTerminal {
+                    id: TaskId("script".to_string()),
+                    label: "Script".to_string(),
+                    command: "sh".to_string(),
+                    args: vec!["-c".to_string(), script.to_string()],
+                    cwd: None,
+                    env: HashMap::default(),
+                    use_new_terminal: false,
+                    allow_concurrent_runs: false,
+                    problem_matchers: Vec::new(),
+                };
+                project.create_terminal(None, None, Some(spawn_task), window, cx)
+            })
+            .unwrap()
+    }
+
+    /// Lets the terminals' IO threads run until the condition holds.
+    pub fn wait_until(
+        cx: &mut TestAppContext,
+        mut condition: impl FnMut(&mut TestAppContext) -> bool,
+    ) {
+        for _ in 0..100 {
+            cx.run_until_parked();
+            if condition(cx) {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        panic!("timed out waiting for the terminal");
+    }
+
     /// Creates a worktree with 1 file: /root.txt
     pub async fn init_test(cx: &mut TestAppContext) -> (Model<Project>, View<Workspace>) {
         let params = cx.update(AppState::test);
@@ -1235,6 +1593,17 @@ mod tests {
         assert_eq!(regex_to_literal(r"test(\w)"), r"test\(\\w\)".to_string());
     }
 
+    #[test]
+    fn search_pattern_applies_options() {
+        let query = SearchQuery::text("a.b", false, false, false, Vec::new(), Vec::new()).unwrap();
+        assert_eq!(search_pattern(&query), r"(?i)a\.b");
+        let query = SearchQuery::text("Ab", true, true, false, Vec::new(), Vec::new()).unwrap();
+        assert_eq!(search_pattern(&query), r"(?-i)(?-u:\b)(?:Ab)(?-u:\b)");
+        let query =
+            SearchQuery::regex(r"\d+|x", false, true, false, Vec::new(), Vec::new()).unwrap();
+        assert_eq!(search_pattern(&query), r"(?-i)\d+|x");
+    }
+
     #[test]
     fn empty_string_stays_empty() {
         assert_eq!(regex_to_literal(""), "".to_string());
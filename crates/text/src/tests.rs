@@ -626,6 +626,75 @@ fn test_finalize_last_transaction() {
     assert_eq!(buffer.text(), "ab2cde6");
 }
 
+#[test]
+fn test_undo_tree() {
+    let mut buffer = Buffer::new(0, BufferId::new(1).unwrap(), "abc".into());
+    buffer.set_group_interval(Duration::from_secs(0));
+
+    buffer.edit([(3..3, "1")]);
+    buffer.edit([(4..4, "2")]);
+    assert_eq!(buffer.text(), "abc12");
+
+    // Editing after an undo starts a new branch instead of discarding the undone state.
+    buffer.undo();
+    buffer.edit([(4..4, "3")]);
+    assert_eq!(buffer.text(), "abc13");
+    assert_eq!(undo_tree_texts(&buffer), ["abc", "abc1", "abc12", "abc13"]);
+    assert_eq!(buffer.undo_tree().current(), 3);
+    assert_eq!(buffer.undo_tree().path_to(3), [0, 1, 3]);
+
+    // Jumping to another branch is an undoable transaction.
+    let edit = buffer.start_undo_tree_jump(2).unwrap();
+    buffer.start_transaction();
+    buffer.edit([(edit.range, edit.new_text)]);
+    buffer.end_transaction();
+    assert_eq!(buffer.text(), "abc12");
+    assert_eq!(buffer.undo_tree().current(), 2);
+    assert_eq!(buffer.undo_tree().nodes().len(), 4);
+
+    buffer.undo();
+    assert_eq!(buffer.text(), "abc13");
+    assert_eq!(buffer.undo_tree().current(), 3);
+
+    // Jumping to the current state is a no-op.
+    assert_eq!(buffer.start_undo_tree_jump(3), None);
+
+    // Restoring a serialized tree replays the path to the current state.
+    let serialized = buffer.undo_tree().serialize();
+    let mut restored = Buffer::new(0, BufferId::new(2).unwrap(), "abc13".into());
+    restored.set_group_interval(Duration::from_secs(0));
+    restored.restore_undo_tree(serialized.clone()).unwrap();
+    assert_eq!(restored.text(), "abc13");
+    assert_eq!(
+        undo_tree_texts(&restored),
+        ["abc", "abc1", "abc13", "abc12"]
+    );
+    assert_eq!(restored.undo_tree().current(), 2);
+
+    restored.undo();
+    assert_eq!(restored.text(), "abc1");
+    restored.undo();
+    assert_eq!(restored.text(), "abc");
+    assert!(restored.undo().is_none());
+    restored.redo();
+    restored.redo();
+    assert_eq!(restored.text(), "abc13");
+
+    // A tree that doesn't match the buffer's text is rejected.
+    let mut other = Buffer::new(0, BufferId::new(3).unwrap(), "xyz".into());
+    assert!(other.restore_undo_tree(serialized).is_err());
+    assert_eq!(other.text(), "xyz");
+
+    fn undo_tree_texts(buffer: &Buffer) -> Vec<String> {
+        buffer
+            .undo_tree()
+            .nodes()
+            .iter()
+            .map(|node| node.text.to_string())
+            .collect()
+    }
+}
+
 #[test]
 fn test_edited_ranges_for_transaction() {
     let now = Instant::now();
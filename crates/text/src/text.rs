@@ -9,6 +9,7 @@ pub mod subscription;
 #[cfg(test)]
 mod tests;
 mod undo_map;
+mod undo_tree;
 
 pub use anchor::*;
 use anyhow::{anyhow, Context as _, Result};
@@ -39,6 +40,7 @@ pub use subscription::*;
 pub use sum_tree::Bias;
 use sum_tree::{FilterCursor, SumTree, TreeMap};
 use undo_map::UndoMap;
+pub use undo_tree::*;
 use util::ResultExt;
 
 #[cfg(any(test, feature = "test-support"))]
@@ -131,6 +133,7 @@ struct History {
     insertion_slices: HashMap<clock::Lamport, Vec<InsertionSlice>>,
     undo_stack: Vec<HistoryEntry>,
     redo_stack: Vec<HistoryEntry>,
+    undo_tree: UndoTree,
     transaction_depth: usize,
     group_interval: Duration,
 }
@@ -144,6 +147,7 @@ struct InsertionSlice {
 impl History {
     pub fn new(base_text: Rope) -> Self {
         Self {
+            undo_tree: UndoTree::new(base_text.clone()),
             base_text,
             operations: Default::default(),
             insertion_slices: Default::default(),
@@ -513,8 +517,23 @@ impl Buffer {
     pub fn new(replica_id: u16, remote_id: BufferId, mut base_text: String) -> Buffer {
         let line_ending = LineEnding::detect(&base_text);
         LineEnding::normalize(&mut base_text);
+        Self::new_normalized(
+            replica_id,
+            remote_id,
+            line_ending,
+            Rope::from(base_text.as_ref()),
+        )
+    }
 
-        let history = History::new(Rope::from(base_text.as_ref()));
+    /// Creates a buffer from text whose line endings were already normalized,
+    /// such as a rope that was built incrementally.
+    pub fn new_normalized(
+        replica_id: u16,
+        remote_id: BufferId,
+        line_ending: LineEnding,
+        base_text: Rope,
+    ) -> Buffer {
+        let history = History::new(base_text);
         let mut fragments = SumTree::new();
         let mut insertions = SumTree::new();
 
@@ -1179,6 +1198,7 @@ impl Buffer {
         if let Some(entry) = self.history.end_transaction(now) {
             let since = entry.transaction.start.clone();
             let id = self.history.group().unwrap();
+            self.record_undo_tree_state();
             Some((id, since))
         } else {
             None
@@ -1191,6 +1211,7 @@ impl Buffer {
 
     pub fn group_until_transaction(&mut self, transaction_id: TransactionId) {
         self.history.group_until(transaction_id);
+        self.record_undo_tree_state();
     }
 
     pub fn base_text(&self) -> &Rope {
@@ -1206,6 +1227,7 @@ impl Buffer {
             let transaction = entry.transaction.clone();
             let transaction_id = transaction.id;
             let op = self.undo_or_redo(transaction).unwrap();
+            self.record_undo_tree_state();
             Some((transaction_id, op))
         } else {
             None
@@ -1218,7 +1240,9 @@ impl Buffer {
             .remove_from_undo(transaction_id)?
             .transaction
             .clone();
-        self.undo_or_redo(transaction).log_err()
+        let operation = self.undo_or_redo(transaction).log_err();
+        self.record_undo_tree_state();
+        operation
     }
 
     pub fn undo_to_transaction(&mut self, transaction_id: TransactionId) -> Vec<Operation> {
@@ -1229,18 +1253,22 @@ impl Buffer {
             .map(|entry| entry.transaction.clone())
             .collect::<Vec<_>>();
 
-        transactions
+        let operations = transactions
             .into_iter()
             .map(|transaction| self.undo_or_redo(transaction).unwrap())
-            .collect()
+            .collect();
+        self.record_undo_tree_state();
+        operations
     }
 
     pub fn forget_transaction(&mut self, transaction_id: TransactionId) {
         self.history.forget(transaction_id);
+        self.record_undo_tree_state();
     }
 
     pub fn merge_transactions(&mut self, transaction: TransactionId, destination: TransactionId) {
         self.history.merge_transactions(transaction, destination);
+        self.record_undo_tree_state();
     }
 
     pub fn redo(&mut self) -> Option<(TransactionId, Operation)> {
@@ -1248,6 +1276,7 @@ impl Buffer {
             let transaction = entry.transaction.clone();
             let transaction_id = transaction.id;
             let op = self.undo_or_redo(transaction).unwrap();
+            self.record_undo_tree_state();
             Some((transaction_id, op))
         } else {
             None
@@ -1262,10 +1291,12 @@ impl Buffer {
             .map(|entry| entry.transaction.clone())
             .collect::<Vec<_>>();
 
-        transactions
+        let operations = transactions
             .into_iter()
             .map(|transaction| self.undo_or_redo(transaction).unwrap())
-            .collect()
+            .collect();
+        self.record_undo_tree_state();
+        operations
     }
 
     fn undo_or_redo(&mut self, transaction: Transaction) -> Result<Operation> {
@@ -1289,6 +1320,108 @@ impl Buffer {
     pub fn push_transaction(&mut self, transaction: Transaction, now: Instant) {
         self.history.push_transaction(transaction, now);
         self.history.finalize_last_transaction();
+        self.record_undo_tree_state();
+    }
+
+    pub fn undo_tree(&self) -> &UndoTree {
+        &self.history.undo_tree
+    }
+
+    fn record_undo_tree_state(&mut self) {
+        let top_transaction_id = self
+            .history
+            .undo_stack
+            .last()
+            .map(|entry| entry.transaction.id);
+        self.history
+            .undo_tree
+            .record(top_transaction_id, &self.snapshot.visible_text);
+    }
+
+    /// Prepares a jump to the given state of the undo tree, returning the edit that brings
+    /// the buffer's text to that state.
+    ///
+    /// The next transaction is recorded as leading to that state, so undoing it goes back
+    /// to where the jump started.
+    pub fn start_undo_tree_jump(&mut self, ix: usize) -> Option<UndoTreeEdit> {
+        let target = self.history.undo_tree.nodes().get(ix)?;
+        let edit = UndoTreeEdit::between(&self.snapshot.visible_text, &target.text);
+        if edit.is_empty() {
+            self.history.undo_tree.set_current(ix);
+            None
+        } else {
+            self.history.undo_tree.set_pending_jump(ix);
+            Some(edit)
+        }
+    }
+
+    /// Rebuilds the undo history from a serialized undo tree, whose current node must
+    /// match the buffer's text.
+    ///
+    /// The states leading to the current node are replayed as transactions, so that they
+    /// can be undone, while the other branches are only kept in the undo tree.
+    pub fn restore_undo_tree(&mut self, serialized: SerializedUndoTree) -> Result<Vec<Operation>> {
+        if self.history.transaction_depth > 0
+            || !self.history.undo_stack.is_empty()
+            || !self.history.redo_stack.is_empty()
+        {
+            return Err(anyhow!("can't restore the undo tree of an edited buffer"));
+        }
+
+        let texts = serialized.node_texts(&self.snapshot.visible_text)?;
+        let path = {
+            let mut path = vec![serialized.current];
+            while let Some(parent) = serialized.nodes[*path.last().unwrap()].parent {
+                path.push(parent);
+            }
+            path.reverse();
+            path
+        };
+
+        let now = Instant::now();
+        let mut operations = Vec::new();
+
+        // Rewind to the root of the tree without recording it in the history.
+        let rewind = UndoTreeEdit::between(&self.snapshot.visible_text, &texts[path[0]]);
+        if !rewind.is_empty() {
+            self.start_transaction_at(now);
+            operations.push(self.edit([(rewind.range, rewind.new_text)]));
+            if let Some((transaction_id, _)) = self.end_transaction_at(now) {
+                self.history.forget(transaction_id);
+            }
+        }
+
+        let mut undo_tree =
+            UndoTree::with_timestamp(texts[path[0]].clone(), serialized.nodes[path[0]].timestamp);
+        let mut tree_ixs = vec![None; serialized.nodes.len()];
+        tree_ixs[path[0]] = Some(0);
+        for &ix in &path[1..] {
+            let node = &serialized.nodes[ix];
+            self.start_transaction_at(now);
+            operations.push(self.edit([(node.edit.range.clone(), node.edit.new_text.as_str())]));
+            let transaction_id = self.end_transaction_at(now).map(|(id, _)| id);
+            self.history.finalize_last_transaction();
+            tree_ixs[ix] = Some(undo_tree.push(
+                node.parent.and_then(|parent| tree_ixs[parent]),
+                transaction_id,
+                texts[ix].clone(),
+                node.timestamp,
+            ));
+        }
+        for (ix, node) in serialized.nodes.iter().enumerate() {
+            if tree_ixs[ix].is_none() {
+                tree_ixs[ix] = Some(undo_tree.push(
+                    node.parent.and_then(|parent| tree_ixs[parent]),
+                    None,
+                    texts[ix].clone(),
+                    node.timestamp,
+                ));
+            }
+        }
+        undo_tree.set_current(tree_ixs[serialized.current].unwrap());
+        self.history.undo_tree = undo_tree;
+
+        Ok(operations)
     }
 
     pub fn edited_ranges_for_transaction<'a, D>(
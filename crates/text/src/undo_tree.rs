@@ -0,0 +1,269 @@
+use crate::{Rope, TransactionId};
+use anyhow::{anyhow, Result};
+use collections::HashMap;
+use smallvec::SmallVec;
+use std::{ops::Range, time::SystemTime};
+
+/// A branching record of the states a buffer went through.
+///
+/// Every node holds the buffer's text after one or more transactions. Unlike the undo
+/// stack, editing after an undo doesn't discard the undone states: they stay reachable
+/// in a separate branch of the tree.
+#[derive(Clone, Debug)]
+pub struct UndoTree {
+    nodes: Vec<UndoTreeNode>,
+    current: usize,
+    pending_jump: Option<usize>,
+    /// The node each transaction produced, for looking them up as they're undone.
+    transaction_nodes: HashMap<TransactionId, usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct UndoTreeNode {
+    pub parent: Option<usize>,
+    /// The transactions that produced this state, if they are still in the buffer's history.
+    pub transaction_ids: SmallVec<[TransactionId; 1]>,
+    pub text: Rope,
+    pub timestamp: SystemTime,
+}
+
+/// A single replacement turning one text into another.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UndoTreeEdit {
+    /// The replaced range, in the old text.
+    pub range: Range<usize>,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SerializedUndoTreeNode {
+    pub parent: Option<usize>,
+    /// The edit turning the parent's text into this node's text.
+    pub edit: UndoTreeEdit,
+    pub timestamp: SystemTime,
+}
+
+/// An undo tree stored as a list of edits, which can be restored on top of the text
+/// of its current node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SerializedUndoTree {
+    /// The nodes of the tree, where parents always precede their children.
+    pub nodes: Vec<SerializedUndoTreeNode>,
+    pub current: usize,
+}
+
+impl UndoTree {
+    pub fn new(text: Rope) -> Self {
+        Self::with_timestamp(text, SystemTime::now())
+    }
+
+    pub(crate) fn with_timestamp(text: Rope, timestamp: SystemTime) -> Self {
+        Self {
+            nodes: vec![UndoTreeNode {
+                parent: None,
+                transaction_ids: Default::default(),
+                text,
+                timestamp,
+            }],
+            current: 0,
+            pending_jump: None,
+            transaction_nodes: HashMap::default(),
+        }
+    }
+
+    pub fn nodes(&self) -> &[UndoTreeNode] {
+        &self.nodes
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() == 1
+    }
+
+    /// Returns the nodes from the root of the tree to the given node.
+    pub fn path_to(&self, mut ix: usize) -> Vec<usize> {
+        let mut path = vec![ix];
+        while let Some(parent) = self.nodes[ix].parent {
+            path.push(parent);
+            ix = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    pub(crate) fn set_pending_jump(&mut self, ix: usize) {
+        self.pending_jump = Some(ix);
+    }
+
+    pub(crate) fn set_current(&mut self, ix: usize) {
+        self.current = ix;
+    }
+
+    /// Updates the tree after the buffer's history changed, given the transaction at the
+    /// top of the undo stack and the buffer's text.
+    pub(crate) fn record(&mut self, top_transaction_id: Option<TransactionId>, text: &Rope) {
+        let Some(transaction_id) = top_transaction_id else {
+            // Everything was undone, which brings the buffer back to the root of the tree.
+            self.pending_jump = None;
+            self.current = 0;
+            self.nodes[0].text = text.clone();
+            return;
+        };
+
+        if let Some(ix) = self.node_for_transaction(transaction_id) {
+            // An undo, a redo or a transaction that was grouped with an earlier one.
+            self.pending_jump = None;
+            self.current = ix;
+            self.nodes[ix].text = text.clone();
+        } else if let Some(ix) = self.pending_jump.take() {
+            self.current = ix;
+            let node = &mut self.nodes[ix];
+            node.transaction_ids.push(transaction_id);
+            node.text = text.clone();
+            self.transaction_nodes.insert(transaction_id, ix);
+        } else {
+            self.current = self.push(
+                Some(self.current),
+                Some(transaction_id),
+                text.clone(),
+                SystemTime::now(),
+            );
+        }
+    }
+
+    pub(crate) fn push(
+        &mut self,
+        parent: Option<usize>,
+        transaction_id: Option<TransactionId>,
+        text: Rope,
+        timestamp: SystemTime,
+    ) -> usize {
+        let ix = self.nodes.len();
+        self.nodes.push(UndoTreeNode {
+            parent,
+            transaction_ids: transaction_id.into_iter().collect(),
+            text,
+            timestamp,
+        });
+        if let Some(transaction_id) = transaction_id {
+            self.transaction_nodes.insert(transaction_id, ix);
+        }
+        ix
+    }
+
+    fn node_for_transaction(&self, transaction_id: TransactionId) -> Option<usize> {
+        self.transaction_nodes.get(&transaction_id).copied()
+    }
+
+    pub fn serialize(&self) -> SerializedUndoTree {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|node| SerializedUndoTreeNode {
+                parent: node.parent,
+                edit: node
+                    .parent
+                    .map(|parent| UndoTreeEdit::between(&self.nodes[parent].text, &node.text))
+                    .unwrap_or_default(),
+                timestamp: node.timestamp,
+            })
+            .collect();
+        SerializedUndoTree {
+            nodes,
+            current: self.current,
+        }
+    }
+}
+
+impl SerializedUndoTree {
+    /// Reconstructs the text of every node, given the text of the current node.
+    pub fn node_texts(&self, current_text: &Rope) -> Result<Vec<Rope>> {
+        if self.current >= self.nodes.len() {
+            return Err(anyhow!("invalid current undo tree node"));
+        }
+
+        let mut texts = vec![None; self.nodes.len()];
+        texts[self.current] = Some(current_text.clone());
+
+        // Walk up from the current node, undoing each edit.
+        let mut ix = self.current;
+        while let Some(parent) = self.nodes[ix].parent {
+            if parent >= ix {
+                return Err(anyhow!("undo tree nodes are not in order"));
+            }
+            let edit = &self.nodes[ix].edit;
+            let mut text = texts[ix].clone().unwrap();
+            let new_range = edit.range.start..edit.range.start + edit.new_text.len();
+            if new_range.end > text.len()
+                || text.chunks_in_range(new_range.clone()).collect::<String>() != edit.new_text
+            {
+                return Err(anyhow!("undo tree doesn't match the buffer's text"));
+            }
+            text.replace(new_range, &edit.old_text);
+            texts[parent] = Some(text);
+            ix = parent;
+        }
+
+        // Then replay the edits of the remaining branches.
+        for ix in 0..self.nodes.len() {
+            if texts[ix].is_some() {
+                continue;
+            }
+            let node = &self.nodes[ix];
+            let parent = node
+                .parent
+                .filter(|parent| *parent < ix)
+                .ok_or_else(|| anyhow!("undo tree nodes are not in order"))?;
+            let mut text = texts[parent].clone().unwrap();
+            let edit = &node.edit;
+            if edit.range.end > text.len()
+                || text.chunks_in_range(edit.range.clone()).collect::<String>() != edit.old_text
+            {
+                return Err(anyhow!("undo tree edit doesn't match its parent"));
+            }
+            text.replace(edit.range.clone(), &edit.new_text);
+            texts[ix] = Some(text);
+        }
+
+        Ok(texts.into_iter().map(Option::unwrap).collect())
+    }
+}
+
+impl UndoTreeEdit {
+    /// Computes the smallest single replacement turning `old` into `new`.
+    pub fn between(old: &Rope, new: &Rope) -> Self {
+        let prefix_len = old
+            .chars()
+            .zip(new.chars())
+            .take_while(|(old, new)| old == new)
+            .map(|(c, _)| c.len_utf8())
+            .sum::<usize>();
+        let max_suffix_len = old.len().min(new.len()) - prefix_len;
+        let mut suffix_len = 0;
+        for (old_char, new_char) in old
+            .reversed_chars_at(old.len())
+            .zip(new.reversed_chars_at(new.len()))
+        {
+            if old_char != new_char || suffix_len + old_char.len_utf8() > max_suffix_len {
+                break;
+            }
+            suffix_len += old_char.len_utf8();
+        }
+
+        let old_range = prefix_len..old.len() - suffix_len;
+        let new_range = prefix_len..new.len() - suffix_len;
+        Self {
+            old_text: old.chunks_in_range(old_range.clone()).collect(),
+            new_text: new.chunks_in_range(new_range).collect(),
+            range: old_range,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.old_text.is_empty() && self.new_text.is_empty()
+    }
+}
@@ -69,6 +69,7 @@ impl ThemeColors {
             editor_subheader_background: neutral().light().step_2(),
             editor_active_line_background: neutral().light_alpha().step_3(),
             editor_highlighted_line_background: neutral().light_alpha().step_3(),
+            editor_search_scope_background: blue().light_alpha().step_2(),
             editor_line_number: neutral().light().step_10(),
             editor_active_line_number: neutral().light().step_11(),
             editor_invisible: neutral().light().step_10(),
@@ -163,6 +164,7 @@ impl ThemeColors {
             editor_subheader_background: neutral().dark().step_3(),
             editor_active_line_background: neutral().dark_alpha().step_3(),
             editor_highlighted_line_background: neutral().dark_alpha().step_4(),
+            editor_search_scope_background: blue().dark_alpha().step_2(),
             editor_line_number: neutral().dark_alpha().step_10(),
             editor_active_line_number: neutral().dark_alpha().step_12(),
             editor_invisible: neutral().dark_alpha().step_4(),
@@ -327,6 +327,10 @@ pub struct ThemeColorsContent {
     #[serde(rename = "editor.highlighted_line.background")]
     pub editor_highlighted_line_background: Option<String>,
 
+    /// Background Color. Used for the ranges a buffer search is restricted to.
+    #[serde(rename = "editor.search_scope.background")]
+    pub editor_search_scope_background: Option<String>,
+
     /// Text Color. Used for the text of the line number in the editor gutter.
     #[serde(rename = "editor.line_number")]
     pub editor_line_number: Option<String>,
@@ -687,6 +691,10 @@ impl ThemeColorsContent {
                 .editor_highlighted_line_background
                 .as_ref()
                 .and_then(|color| try_parse_color(&color).ok()),
+            editor_search_scope_background: self
+                .editor_search_scope_background
+                .as_ref()
+                .and_then(|color| try_parse_color(&color).ok()),
             editor_line_number: self
                 .editor_line_number
                 .as_ref()
@@ -57,6 +57,12 @@ pub enum IconName {
     CopilotInit,
     Copy,
     Dash,
+    Debug,
+    DebugPause,
+    DebugStepInto,
+    DebugStepOut,
+    DebugStepOver,
+    DebugStop,
     Delete,
     Disconnected,
     Ellipsis,
@@ -93,6 +99,7 @@ pub enum IconName {
     Option,
     PageDown,
     PageUp,
+    Play,
     Plus,
     Public,
     Quote,
@@ -108,6 +115,7 @@ pub enum IconName {
     Split,
     Tab,
     Terminal,
+    TextSelect,
     Update,
     WholeWord,
     XCircle,
@@ -149,6 +157,12 @@ impl IconName {
             IconName::CopilotInit => "icons/copilot_init.svg",
             IconName::Copy => "icons/copy.svg",
             IconName::Dash => "icons/dash.svg",
+            IconName::Debug => "icons/debug.svg",
+            IconName::DebugPause => "icons/debug_pause.svg",
+            IconName::DebugStepInto => "icons/debug_step_into.svg",
+            IconName::DebugStepOut => "icons/debug_step_out.svg",
+            IconName::DebugStepOver => "icons/debug_step_over.svg",
+            IconName::DebugStop => "icons/debug_stop.svg",
             IconName::Delete => "icons/delete.svg",
             IconName::Disconnected => "icons/disconnected.svg",
             IconName::Ellipsis => "icons/ellipsis.svg",
@@ -185,6 +199,7 @@ impl IconName {
             IconName::Option => "icons/option.svg",
             IconName::PageDown => "icons/page_down.svg",
             IconName::PageUp => "icons/page_up.svg",
+            IconName::Play => "icons/play.svg",
             IconName::Plus => "icons/plus.svg",
             IconName::Public => "icons/public.svg",
             IconName::Quote => "icons/quote.svg",
@@ -200,6 +215,7 @@ impl IconName {
             IconName::Split => "icons/split.svg",
             IconName::Tab => "icons/tab.svg",
             IconName::Terminal => "icons/terminal.svg",
+            IconName::TextSelect => "icons/text_select.svg",
             IconName::Update => "icons/update.svg",
             IconName::WholeWord => "icons/word_search.svg",
             IconName::XCircle => "icons/error.svg",
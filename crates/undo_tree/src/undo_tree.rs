@@ -0,0 +1,315 @@
+use editor::{Editor, EditorMode};
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, rems, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model,
+    ParentElement, Render, Styled, Task, View, ViewContext, VisualContext, WeakView, WindowContext,
+};
+use language::Buffer;
+use picker::{Picker, PickerDelegate};
+use std::{sync::Arc, time::SystemTime};
+use text::{UndoTree, UndoTreeEdit};
+use ui::{
+    prelude::*,
+    utils::{DateTimeType, FormatDistance},
+    HighlightedLabel, ListItem, ListItemSpacing,
+};
+use util::ResultExt;
+use workspace::ModalView;
+
+actions!(undo_tree, [Toggle]);
+
+const MAX_LABEL_LEN: usize = 48;
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(UndoTreeView::register).detach();
+}
+
+pub fn toggle(editor: View<Editor>, _: &Toggle, cx: &mut WindowContext) {
+    let Some(buffer) = editor.read(cx).buffer().read(cx).as_singleton() else {
+        return;
+    };
+
+    if let Some(workspace) = editor.read(cx).workspace() {
+        workspace.update(cx, |workspace, cx| {
+            workspace.toggle_modal(cx, |cx| UndoTreeView::new(buffer, editor, cx));
+        })
+    }
+}
+
+pub struct UndoTreeView {
+    picker: View<Picker<UndoTreeViewDelegate>>,
+    _load_entries: Task<()>,
+}
+
+impl FocusableView for UndoTreeView {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for UndoTreeView {}
+impl ModalView for UndoTreeView {}
+
+impl Render for UndoTreeView {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl UndoTreeView {
+    fn register(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+        if editor.mode() == EditorMode::Full {
+            let handle = cx.view().downgrade();
+            editor.register_action(move |action, cx| {
+                if let Some(editor) = handle.upgrade() {
+                    toggle(editor, action, cx);
+                }
+            });
+        }
+    }
+
+    fn new(buffer: Model<Buffer>, editor: View<Editor>, cx: &mut ViewContext<Self>) -> Self {
+        // Labelling the nodes diffs each of them against its parent, so it's
+        // done in the background.
+        let undo_tree = buffer.read(cx).undo_tree().clone();
+        let entries = cx
+            .background_executor()
+            .spawn(async move { UndoTreeEntry::for_tree(&undo_tree) });
+        let delegate = UndoTreeViewDelegate {
+            undo_tree_view: cx.view().downgrade(),
+            buffer,
+            editor,
+            entries: Vec::new(),
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        let picker = cx.new_view(|cx| Picker::new(delegate, cx).max_height(vh(0.75, cx)));
+        let _load_entries = cx.spawn({
+            let picker = picker.downgrade();
+            |_, mut cx| async move {
+                let entries = entries.await;
+                picker
+                    .update(&mut cx, |picker, cx| {
+                        let delegate = &mut picker.delegate;
+                        delegate.selected_index = entries
+                            .iter()
+                            .position(|entry| entry.is_current)
+                            .unwrap_or(0);
+                        delegate.entries = entries;
+                        picker.refresh(cx);
+                    })
+                    .log_err();
+            }
+        });
+        Self {
+            picker,
+            _load_entries,
+        }
+    }
+}
+
+/// A node of the undo tree, laid out as a row of the picker.
+struct UndoTreeEntry {
+    node_ix: usize,
+    /// How many branches the node is nested in.
+    depth: usize,
+    label: String,
+    timestamp: SystemTime,
+    is_current: bool,
+}
+
+impl UndoTreeEntry {
+    /// Lists the nodes of the tree depth-first, so that every branch directly follows
+    /// the state it started from.
+    fn for_tree(undo_tree: &UndoTree) -> Vec<Self> {
+        let nodes = undo_tree.nodes();
+        let mut children = vec![Vec::new(); nodes.len()];
+        for (ix, node) in nodes.iter().enumerate() {
+            if let Some(parent) = node.parent {
+                children[parent].push(ix);
+            }
+        }
+
+        let mut entries = Vec::with_capacity(nodes.len());
+        let mut stack = vec![(0, 0)];
+        while let Some((ix, depth)) = stack.pop() {
+            let node = &nodes[ix];
+            let label = match node.parent {
+                Some(parent) => {
+                    describe_edit(&UndoTreeEdit::between(&nodes[parent].text, &node.text))
+                }
+                None => "Original".to_string(),
+            };
+            entries.push(Self {
+                node_ix: ix,
+                depth,
+                label,
+                timestamp: node.timestamp,
+                is_current: ix == undo_tree.current(),
+            });
+
+            // The oldest child continues the current branch, newer ones start new branches.
+            for (child_ix, child) in children[ix].iter().enumerate().rev() {
+                let child_depth = if child_ix == 0 { depth } else { depth + 1 };
+                stack.push((*child, child_depth));
+            }
+        }
+        entries
+    }
+}
+
+fn describe_edit(edit: &UndoTreeEdit) -> String {
+    let old_text = summarize(&edit.old_text);
+    let new_text = summarize(&edit.new_text);
+    match (edit.old_text.is_empty(), edit.new_text.is_empty()) {
+        (true, true) => "No changes".to_string(),
+        (true, false) => format!("Inserted {new_text}"),
+        (false, true) => format!("Deleted {old_text}"),
+        (false, false) => format!("Replaced {old_text} with {new_text}"),
+    }
+}
+
+fn summarize(text: &str) -> String {
+    let text = text.trim();
+    let first_line = text.lines().next().unwrap_or_default();
+    let mut summary = first_line.chars().take(MAX_LABEL_LEN).collect::<String>();
+    if summary.len() < text.len() {
+        summary.push('…');
+    }
+    format!("{summary:?}")
+}
+
+pub struct UndoTreeViewDelegate {
+    undo_tree_view: WeakView<UndoTreeView>,
+    buffer: Model<Buffer>,
+    editor: View<Editor>,
+    entries: Vec<UndoTreeEntry>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl PickerDelegate for UndoTreeViewDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self) -> Arc<str> {
+        "Search undo history...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> gpui::Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(ix, entry)| StringMatchCandidate::new(ix, entry.label.clone()))
+            .collect::<Vec<_>>();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                let mut matches = match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await;
+                // Keep the tree order rather than sorting by score.
+                matches.sort_unstable_by_key(|mat| mat.candidate_id);
+                matches
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let node_ix = self.entries[mat.candidate_id].node_ix;
+            self.buffer.update(cx, |buffer, cx| {
+                buffer.jump_to_undo_tree_node(node_ix, cx);
+            });
+            self.editor.update(cx, |editor, cx| editor.focus(cx));
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.undo_tree_view
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let entry = &self.entries[mat.candidate_id];
+        let age = FormatDistance::from_now(DateTimeType::Local(entry.timestamp.into()))
+            .hide_prefix(true)
+            .add_suffix(true)
+            .to_string();
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .indent_level(entry.depth)
+                .start_slot(
+                    Icon::new(if entry.is_current {
+                        IconName::Check
+                    } else {
+                        IconName::Dash
+                    })
+                    .color(if entry.is_current {
+                        Color::Accent
+                    } else {
+                        Color::Muted
+                    }),
+                )
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                ))
+                .end_slot(Label::new(age).size(LabelSize::Small).color(Color::Muted)),
+        )
+    }
+}
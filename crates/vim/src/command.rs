@@ -1,6 +1,6 @@
-use command_palette::CommandInterceptResult;
+use command_palette::{CommandInterceptResult, CommandPalette};
 use editor::actions::{SortLinesCaseInsensitive, SortLinesCaseSensitive};
-use gpui::{impl_actions, Action, AppContext, ViewContext};
+use gpui::{actions, impl_actions, Action, AppContext, ViewContext};
 use serde_derive::Deserialize;
 use workspace::{SaveIntent, Workspace};
 
@@ -22,6 +22,11 @@ pub struct GoToLine {
 
 impl_actions!(vim, [GoToLine]);
 
+actions!(vim, [VisualCommand]);
+
+/// The range vim inserts when `:` is pressed in visual mode.
+pub(crate) const VISUAL_RANGE: &str = "'<,'>";
+
 pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(|_: &mut Workspace, action: &GoToLine, cx| {
         Vim::update(cx, |vim, cx| {
@@ -29,6 +34,9 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
             move_cursor(vim, Motion::StartOfDocument, Some(action.line as usize), cx);
         });
     });
+    workspace.register_action(|workspace: &mut Workspace, _: &VisualCommand, cx| {
+        CommandPalette::toggle(workspace, VISUAL_RANGE, cx);
+    });
 }
 
 pub fn command_interceptor(mut query: &str, cx: &AppContext) -> Option<CommandInterceptResult> {
@@ -39,12 +47,20 @@ pub fn command_interceptor(mut query: &str, cx: &AppContext) -> Option<CommandIn
     // We also need to support passing arguments to commands like :w
     // (ideally with filename autocompletion).
     //
-    // For now, you can only do a replace on the % range, and you can
+    // For now, you can only do a replace on the % and '<,'> ranges, and you can
     // only use a specific line number range to "go to line"
     while query.starts_with(":") {
         query = &query[1..];
     }
 
+    let full_query = query;
+    let visual_range = if let Some(rest) = query.strip_prefix(VISUAL_RANGE) {
+        query = rest;
+        true
+    } else {
+        false
+    };
+
     let (name, action) = match query {
         // save and quit
         "w" | "wr" | "wri" | "writ" | "write" => (
@@ -302,11 +318,11 @@ pub fn command_interceptor(mut query: &str, cx: &AppContext) -> Option<CommandIn
                     }
                     .boxed_clone(),
                 )
-            } else if query.starts_with("%") {
+            } else if query.starts_with("%") || (visual_range && query.starts_with("s")) {
                 (
-                    query,
+                    full_query,
                     ReplaceCommand {
-                        query: query.to_string(),
+                        query: full_query.to_string(),
                     }
                     .boxed_clone(),
                 )
@@ -4,6 +4,7 @@ use serde_derive::Deserialize;
 use workspace::{searchable::Direction, Workspace};
 
 use crate::{
+    command::VISUAL_RANGE,
     motion::Motion,
     normal::move_cursor,
     state::{Mode, SearchState},
@@ -47,6 +48,7 @@ struct Replacement {
     replacement: String,
     should_replace_all: bool,
     is_case_sensitive: bool,
+    within_selection: bool,
 }
 
 actions!(vim, [SearchSubmit]);
@@ -232,6 +234,12 @@ fn replace_command(
     cx: &mut ViewContext<Workspace>,
 ) {
     let replacement = parse_replace_all(&action.query);
+    if replacement.within_selection {
+        // '<,'> always covers whole lines, even when the visual selection was characterwise.
+        Vim::update(cx, |vim, cx| {
+            vim.update_active_editor(cx, |editor, _| editor.selections.line_mode = true);
+        });
+    }
     let pane = workspace.active_pane().clone();
     pane.update(cx, |pane, cx| {
         let Some(search_bar) = pane.toolbar().read(cx).item_of_type::<BufferSearchBar>() else {
@@ -246,6 +254,9 @@ fn replace_command(
             if replacement.is_case_sensitive {
                 options.set(SearchOptions::CASE_SENSITIVE, true)
             }
+            if replacement.within_selection {
+                options.set(SearchOptions::SELECTION, true)
+            }
             let search = if replacement.search == "" {
                 search_bar.query(cx)
             } else {
@@ -254,9 +265,11 @@ fn replace_command(
 
             search_bar.set_replacement(Some(&replacement.replacement), cx);
             search_bar.activate_search_mode(SearchMode::Regex, cx);
-            Some(search_bar.search(&search, Some(options), cx))
+            Some((search_bar.search(&search, Some(options), cx), options))
         });
-        let Some(search) = search else { return };
+        let Some((search, options)) = search else {
+            return;
+        };
         let search_bar = search_bar.downgrade();
         cx.spawn(|_, mut cx| async move {
             search.await?;
@@ -264,7 +277,13 @@ fn replace_command(
                 if replacement.should_replace_all {
                     search_bar.select_last_match(cx);
                     search_bar.replace_all(&Default::default(), cx);
+                    if replacement.within_selection {
+                        search_bar.set_search_options(options - SearchOptions::SELECTION, cx);
+                    }
                     Vim::update(cx, |vim, cx| {
+                        if replacement.within_selection {
+                            vim.switch_mode(Mode::Normal, true, cx);
+                        }
                         move_cursor(
                             vim,
                             Motion::StartOfLine {
@@ -287,8 +306,16 @@ fn replace_command(
 // but we do flip \( and \) to ( and ) (and vice-versa) in the pattern,
 // and convert \0..\9 to $0..$9 in the replacement so that common idioms work.
 fn parse_replace_all(query: &str) -> Replacement {
+    let (query, within_selection) = if let Some(query) = query.strip_prefix(VISUAL_RANGE) {
+        (query, true)
+    } else if let Some(query) = query.strip_prefix('%') {
+        (query, false)
+    } else {
+        return Replacement::default();
+    };
+
     let mut chars = query.chars();
-    if Some('%') != chars.next() || Some('s') != chars.next() {
+    if Some('s') != chars.next() {
         return Replacement::default();
     }
 
@@ -345,6 +372,7 @@ fn parse_replace_all(query: &str) -> Replacement {
         replacement,
         should_replace_all: true,
         is_case_sensitive: true,
+        within_selection,
     };
 
     for c in flags.chars() {
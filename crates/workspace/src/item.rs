@@ -84,6 +84,46 @@ impl Settings for ItemSettings {
     }
 }
 
+#[derive(Deserialize)]
+pub struct PreviewTabsSettings {
+    pub enabled: bool,
+    pub enable_preview_from_file_finder: bool,
+    pub enable_preview_from_code_navigation: bool,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct PreviewTabsSettingsContent {
+    /// Whether to show opened editors as preview tabs.
+    /// Preview tabs do not stay open, are reused until explicitly set to be kept open,
+    /// and show file names in italic.
+    ///
+    /// Default: true
+    enabled: Option<bool>,
+    /// Whether to open files from the file finder as preview tabs.
+    ///
+    /// Default: false
+    enable_preview_from_file_finder: Option<bool>,
+    /// Whether to open files reached through code navigation (e.g. go to definition)
+    /// as preview tabs.
+    ///
+    /// Default: false
+    enable_preview_from_code_navigation: Option<bool>,
+}
+
+impl Settings for PreviewTabsSettings {
+    const KEY: Option<&'static str> = Some("preview_tabs");
+
+    type FileContent = PreviewTabsSettingsContent;
+
+    fn load(
+        default_value: &Self::FileContent,
+        user_values: &[&Self::FileContent],
+        _: &mut AppContext,
+    ) -> Result<Self> {
+        Self::load_via_json_merge(default_value, user_values)
+    }
+}
+
 #[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
 pub enum ItemEvent {
     CloseItem,
@@ -103,6 +143,9 @@ pub trait Item: FocusableView + EventEmitter<Self::Event> {
 
     fn deactivated(&mut self, _: &mut ViewContext<Self>) {}
     fn workspace_deactivated(&mut self, _: &mut ViewContext<Self>) {}
+    /// Called when the user closes the item, as opposed to the item being
+    /// dropped because its pane or window went away.
+    fn closed(&mut self, _: &mut ViewContext<Self>) {}
     fn navigate(&mut self, _: Box<dyn Any>, _: &mut ViewContext<Self>) -> bool {
         false
     }
@@ -164,6 +207,11 @@ pub trait Item: FocusableView + EventEmitter<Self::Event> {
     ) -> Task<Result<()>> {
         unimplemented!("reload() must be implemented if can_save() returns true")
     }
+    /// Writes the item's unsaved changes to the database, so that it restores
+    /// them when deserialized. Returns `None` for items that can't do so.
+    fn backup_unsaved_changes(&mut self, _cx: &mut ViewContext<Self>) -> Option<Task<Result<()>>> {
+        None
+    }
 
     fn to_item_events(event: &Self::Event, f: impl FnMut(ItemEvent));
 
@@ -252,6 +300,7 @@ pub trait ItemHandle: 'static + Send {
     );
     fn deactivated(&self, cx: &mut WindowContext);
     fn workspace_deactivated(&self, cx: &mut WindowContext);
+    fn closed(&self, cx: &mut WindowContext);
     fn navigate(&self, data: Box<dyn Any>, cx: &mut WindowContext) -> bool;
     fn item_id(&self) -> EntityId;
     fn to_any(&self) -> AnyView;
@@ -266,6 +315,7 @@ pub trait ItemHandle: 'static + Send {
         cx: &mut WindowContext,
     ) -> Task<Result<()>>;
     fn reload(&self, project: Model<Project>, cx: &mut WindowContext) -> Task<Result<()>>;
+    fn backup_unsaved_changes(&self, cx: &mut WindowContext) -> Option<Task<Result<()>>>;
     fn act_as_type(&self, type_id: TypeId, cx: &AppContext) -> Option<AnyView>;
     fn to_followable_item_handle(&self, cx: &AppContext) -> Option<Box<dyn FollowableItemHandle>>;
     fn on_release(
@@ -497,6 +547,10 @@ impl<T: Item> ItemHandle for View<T> {
                         }
 
                         ItemEvent::Edit => {
+                            pane.update(cx, |pane, cx| {
+                                pane.handle_item_edit(item.item_id(), cx);
+                            });
+
                             let autosave = WorkspaceSettings::get_global(cx).autosave;
                             if let AutosaveSetting::AfterDelay { milliseconds } = autosave {
                                 let delay = Duration::from_millis(milliseconds);
@@ -542,6 +596,10 @@ impl<T: Item> ItemHandle for View<T> {
         self.update(cx, |this, cx| this.workspace_deactivated(cx));
     }
 
+    fn closed(&self, cx: &mut WindowContext) {
+        self.update(cx, |this, cx| this.closed(cx));
+    }
+
     fn navigate(&self, data: Box<dyn Any>, cx: &mut WindowContext) -> bool {
         self.update(cx, |this, cx| this.navigate(data, cx))
     }
@@ -583,6 +641,10 @@ impl<T: Item> ItemHandle for View<T> {
         self.update(cx, |item, cx| item.reload(project, cx))
     }
 
+    fn backup_unsaved_changes(&self, cx: &mut WindowContext) -> Option<Task<Result<()>>> {
+        self.update(cx, |item, cx| item.backup_unsaved_changes(cx))
+    }
+
     fn act_as_type<'a>(&'a self, type_id: TypeId, cx: &'a AppContext) -> Option<AnyView> {
         self.read(cx).act_as_type(type_id, self, cx)
     }
@@ -791,6 +853,8 @@ pub mod test {
         pub save_count: usize,
         pub save_as_count: usize,
         pub reload_count: usize,
+        pub backup_count: usize,
+        pub can_backup: bool,
         pub is_dirty: bool,
         pub is_singleton: bool,
         pub has_conflict: bool,
@@ -844,6 +908,8 @@ pub mod test {
                 save_count: 0,
                 save_as_count: 0,
                 reload_count: 0,
+                backup_count: 0,
+                can_backup: false,
                 is_dirty: false,
                 has_conflict: false,
                 project_items: Vec::new(),
@@ -877,6 +943,11 @@ pub mod test {
             self
         }
 
+        pub fn with_backup(mut self, can_backup: bool) -> Self {
+            self.can_backup = can_backup;
+            self
+        }
+
         pub fn with_conflict(mut self, has_conflict: bool) -> Self {
             self.has_conflict = has_conflict;
             self
@@ -1047,6 +1118,16 @@ pub mod test {
             Task::ready(Ok(()))
         }
 
+        fn backup_unsaved_changes(
+            &mut self,
+            _: &mut ViewContext<Self>,
+        ) -> Option<Task<anyhow::Result<()>>> {
+            self.can_backup.then(|| {
+                self.backup_count += 1;
+                Task::ready(Ok(()))
+            })
+        }
+
         fn serialized_item_kind() -> Option<&'static str> {
             Some("TestItem")
         }
@@ -1,5 +1,5 @@
 use crate::{
-    item::{ClosePosition, Item, ItemHandle, ItemSettings, WeakItemHandle},
+    item::{ClosePosition, Item, ItemHandle, ItemSettings, PreviewTabsSettings, WeakItemHandle},
     toolbar::Toolbar,
     workspace_settings::{AutosaveSetting, WorkspaceSettings},
     NewCenterTerminal, NewFile, NewSearch, OpenVisible, SplitDirection, ToggleZoom, Workspace,
@@ -9,18 +9,20 @@ use collections::{HashMap, HashSet, VecDeque};
 use futures::{stream::FuturesUnordered, StreamExt};
 use gpui::{
     actions, impl_actions, overlay, prelude::*, Action, AnchorCorner, AnyElement, AppContext,
-    AsyncWindowContext, DismissEvent, Div, DragMoveEvent, EntityId, EventEmitter, ExternalPaths,
-    FocusHandle, FocusableView, Model, MouseButton, NavigationDirection, Pixels, Point,
-    PromptLevel, Render, ScrollHandle, Subscription, Task, View, ViewContext, VisualContext,
+    AsyncWindowContext, ClickEvent, DismissEvent, Div, DragMoveEvent, EntityId, EventEmitter,
+    ExternalPaths, FocusHandle, FocusableView, Model, MouseButton, NavigationDirection, Pixels,
+    Point, PromptLevel, Render, ScrollHandle, Subscription, Task, View, ViewContext, VisualContext,
     WeakView, WindowContext,
 };
 use parking_lot::Mutex;
-use project::{Project, ProjectEntryId, ProjectPath};
+use project::{Project, ProjectEntryId, ProjectPath, WorktreeId};
 use serde::Deserialize;
 use settings::Settings;
 use std::{
     any::Any,
-    cmp, fmt, mem,
+    cmp,
+    collections::BTreeSet,
+    fmt, mem,
     ops::ControlFlow,
     path::{Path, PathBuf},
     rc::Rc,
@@ -102,6 +104,7 @@ actions!(
         DeploySearch,
         GoForward,
         ReopenClosedItem,
+        PinPreviewTab,
         SplitLeft,
         SplitUp,
         SplitRight,
@@ -158,6 +161,7 @@ pub struct Pane {
     zoomed: bool,
     was_focused: bool,
     active_item_index: usize,
+    preview_item_id: Option<EntityId>,
     last_focused_view_by_item: HashMap<EntityId, FocusHandle>,
     nav_history: NavHistory,
     toolbar: View<Toolbar>,
@@ -226,6 +230,33 @@ pub struct DraggedTab {
     pub is_active: bool,
 }
 
+/// A project entry selected in the project panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SelectedEntry {
+    pub worktree_id: WorktreeId,
+    pub entry_id: ProjectEntryId,
+}
+
+/// One or more project entries being dragged out of the project panel.
+#[derive(Debug, Clone)]
+pub struct DraggedSelection {
+    /// The entry the drag was started from.
+    pub active_selection: SelectedEntry,
+    pub marked_selections: Arc<BTreeSet<SelectedEntry>>,
+}
+
+impl DraggedSelection {
+    /// Returns the dragged entries: all marked entries if the drag was started from one
+    /// of them, otherwise only the entry the drag was started from.
+    pub fn items<'a>(&'a self) -> Box<dyn Iterator<Item = &'a SelectedEntry> + 'a> {
+        if self.marked_selections.contains(&self.active_selection) {
+            Box::new(self.marked_selections.iter())
+        } else {
+            Box::new(std::iter::once(&self.active_selection))
+        }
+    }
+}
+
 impl EventEmitter<Event> for Pane {}
 
 impl Pane {
@@ -252,6 +283,7 @@ impl Pane {
             was_focused: false,
             zoomed: false,
             active_item_index: 0,
+            preview_item_id: None,
             last_focused_view_by_item: Default::default(),
             nav_history: NavHistory(Arc::new(Mutex::new(NavHistoryState {
                 mode: NavigationMode::Normal,
@@ -405,6 +437,11 @@ impl Pane {
         self.active_item_index
     }
 
+    /// The ids of the pane's items, from the least to the most recently activated.
+    pub fn activation_history(&self) -> &[EntityId] {
+        &self.activation_history
+    }
+
     pub fn set_can_split(&mut self, can_split: bool, cx: &mut ViewContext<Self>) {
         self.can_split = can_split;
         cx.notify();
@@ -490,10 +527,72 @@ impl Pane {
         self.toolbar.update(cx, |_, cx| cx.notify());
     }
 
+    pub fn preview_item_id(&self) -> Option<EntityId> {
+        self.preview_item_id
+    }
+
+    fn preview_item_idx(&self) -> Option<usize> {
+        let preview_item_id = self.preview_item_id?;
+        self.items
+            .iter()
+            .position(|item| item.item_id() == preview_item_id)
+    }
+
+    pub fn is_active_preview_item(&self, item_id: EntityId) -> bool {
+        self.preview_item_id == Some(item_id)
+    }
+
+    /// Marks the item with the given ID as the preview item, or makes the current
+    /// preview item permanent when `None` is passed.
+    /// This is a no-op if preview tabs are disabled.
+    pub fn set_preview_item_id(&mut self, item_id: Option<EntityId>, cx: &mut ViewContext<Self>) {
+        if !PreviewTabsSettings::get_global(cx).enabled || self.preview_item_id == item_id {
+            return;
+        }
+        self.preview_item_id = item_id;
+        cx.emit(Event::ChangeItemTitle);
+        cx.notify();
+    }
+
+    /// Makes the preview item permanent once it's been edited.
+    pub fn handle_item_edit(&mut self, item_id: EntityId, cx: &mut ViewContext<Self>) {
+        if self.is_active_preview_item(item_id) {
+            self.set_preview_item_id(None, cx);
+        }
+    }
+
+    fn pin_preview_tab(&mut self, _: &PinPreviewTab, cx: &mut ViewContext<Self>) {
+        if let Some(active_item) = self.active_item() {
+            self.handle_item_edit(active_item.item_id(), cx);
+        }
+    }
+
+    /// Removes the current preview item, returning the index it occupied so that its
+    /// replacement can be opened in the same slot.
+    /// A preview item with unsaved changes is kept and made permanent instead.
+    pub fn close_current_preview_item(&mut self, cx: &mut ViewContext<Self>) -> Option<usize> {
+        let item_ix = self.preview_item_idx()?;
+        if self.items[item_ix].is_dirty(cx) {
+            self.set_preview_item_id(None, cx);
+            return None;
+        }
+
+        let prev_active_item_index = self.active_item_index;
+        self.remove_item(item_ix, false, cx);
+        self.active_item_index = prev_active_item_index;
+
+        if item_ix < self.items.len() {
+            Some(item_ix)
+        } else {
+            None
+        }
+    }
+
     pub(crate) fn open_item(
         &mut self,
         project_entry_id: Option<ProjectEntryId>,
         focus_item: bool,
+        allow_preview: bool,
         cx: &mut ViewContext<Self>,
         build_item: impl FnOnce(&mut ViewContext<Pane>) -> Box<dyn ItemHandle>,
     ) -> Box<dyn ItemHandle> {
@@ -511,11 +610,24 @@ impl Pane {
         }
 
         if let Some((index, existing_item)) = existing_item {
+            // Opening the preview item again without asking for a preview keeps it open.
+            if !allow_preview {
+                self.handle_item_edit(existing_item.item_id(), cx);
+            }
             self.activate_item(index, focus_item, focus_item, cx);
             existing_item
         } else {
+            let destination_index = if allow_preview {
+                self.close_current_preview_item(cx)
+            } else {
+                None
+            };
+
             let new_item = build_item(cx);
-            self.add_item(new_item.clone(), true, focus_item, None, cx);
+            if allow_preview {
+                self.set_preview_item_id(Some(new_item.item_id()), cx);
+            }
+            self.add_item(new_item.clone(), true, focus_item, destination_index, cx);
             new_item
         }
     }
@@ -1000,6 +1112,7 @@ impl Pane {
                         .iter()
                         .position(|i| i.item_id() == item.item_id())
                     {
+                        item.closed(cx);
                         pane.remove_item(item_ix, false, cx);
                     }
                 })
@@ -1042,6 +1155,9 @@ impl Pane {
         }
 
         let item = self.items.remove(item_index);
+        if self.is_active_preview_item(item.item_id()) {
+            self.preview_item_id = None;
+        }
 
         cx.emit(Event::RemoveItem {
             item_id: item.item_id(),
@@ -1281,8 +1397,11 @@ impl Pane {
         cx: &mut ViewContext<'_, Pane>,
     ) -> impl IntoElement {
         let is_active = ix == self.active_item_index;
+        let is_preview = self.is_active_preview_item(item.item_id());
 
-        let label = item.tab_content(Some(detail), is_active, cx);
+        let label = div()
+            .when(is_preview, |label| label.italic())
+            .child(item.tab_content(Some(detail), is_active, cx));
         let close_side = &ItemSettings::get_global(cx).close_position;
 
         let indicator = maybe!({
@@ -1313,9 +1432,12 @@ impl Pane {
                 ClosePosition::Right => ui::TabCloseSide::End,
             })
             .selected(is_active)
-            .on_click(
-                cx.listener(move |pane: &mut Self, _, cx| pane.activate_item(ix, true, true, cx)),
-            )
+            .on_click(cx.listener(move |pane: &mut Self, event: &ClickEvent, cx| {
+                if event.up.click_count > 1 {
+                    pane.handle_item_edit(item_id, cx);
+                }
+                pane.activate_item(ix, true, true, cx)
+            }))
             // TODO: This should be a click listener with the middle mouse button instead of a mouse down listener.
             .on_mouse_down(
                 MouseButton::Middle,
@@ -1337,7 +1459,7 @@ impl Pane {
             .drag_over::<DraggedTab>(|tab, _, cx| {
                 tab.bg(cx.theme().colors().drop_target_background)
             })
-            .drag_over::<ProjectEntryId>(|tab, _, cx| {
+            .drag_over::<DraggedSelection>(|tab, _, cx| {
                 tab.bg(cx.theme().colors().drop_target_background)
             })
             .when_some(self.can_drop_predicate.clone(), |this, p| {
@@ -1347,9 +1469,9 @@ impl Pane {
                 this.drag_split_direction = None;
                 this.handle_tab_drop(dragged_tab, ix, cx)
             }))
-            .on_drop(cx.listener(move |this, entry_id: &ProjectEntryId, cx| {
+            .on_drop(cx.listener(move |this, selection: &DraggedSelection, cx| {
                 this.drag_split_direction = None;
-                this.handle_project_entry_drop(entry_id, cx)
+                this.handle_dragged_selection_drop(selection, cx)
             }))
             .on_drop(cx.listener(move |this, paths, cx| {
                 this.drag_split_direction = None;
@@ -1514,16 +1636,16 @@ impl Pane {
                     .drag_over::<DraggedTab>(|bar, _, cx| {
                         bar.bg(cx.theme().colors().drop_target_background)
                     })
-                    .drag_over::<ProjectEntryId>(|bar, _, cx| {
+                    .drag_over::<DraggedSelection>(|bar, _, cx| {
                         bar.bg(cx.theme().colors().drop_target_background)
                     })
                     .on_drop(cx.listener(move |this, dragged_tab: &DraggedTab, cx| {
                         this.drag_split_direction = None;
                         this.handle_tab_drop(dragged_tab, this.items.len(), cx)
                     }))
-                    .on_drop(cx.listener(move |this, entry_id: &ProjectEntryId, cx| {
+                    .on_drop(cx.listener(move |this, selection: &DraggedSelection, cx| {
                         this.drag_split_direction = None;
-                        this.handle_project_entry_drop(entry_id, cx)
+                        this.handle_dragged_selection_drop(selection, cx)
                     }))
                     .on_drop(cx.listener(move |this, paths, cx| {
                         this.drag_split_direction = None;
@@ -1639,30 +1761,46 @@ impl Pane {
             .log_err();
     }
 
-    fn handle_project_entry_drop(
+    fn handle_dragged_selection_drop(
         &mut self,
-        project_entry_id: &ProjectEntryId,
+        selection: &DraggedSelection,
         cx: &mut ViewContext<'_, Self>,
     ) {
         if let Some(custom_drop_handle) = self.custom_drop_handle.clone() {
-            if let ControlFlow::Break(()) = custom_drop_handle(self, project_entry_id, cx) {
+            if let ControlFlow::Break(()) = custom_drop_handle(self, selection, cx) {
                 return;
             }
         }
         let mut to_pane = cx.view().clone();
         let split_direction = self.drag_split_direction;
-        let project_entry_id = *project_entry_id;
+        let selected_entries = selection.items().copied().collect::<Vec<_>>();
         self.workspace
             .update(cx, |_, cx| {
                 cx.defer(move |workspace, cx| {
-                    if let Some(path) = workspace
-                        .project()
-                        .read(cx)
-                        .path_for_entry(project_entry_id, cx)
-                    {
-                        if let Some(split_direction) = split_direction {
-                            to_pane = workspace.split_pane(to_pane, split_direction, cx);
-                        }
+                    let paths = {
+                        let project = workspace.project().read(cx);
+                        selected_entries
+                            .into_iter()
+                            .filter_map(|selected_entry| {
+                                let worktree =
+                                    project.worktree_for_id(selected_entry.worktree_id, cx)?;
+                                let entry =
+                                    worktree.read(cx).entry_for_id(selected_entry.entry_id)?;
+                                entry.is_file().then(|| ProjectPath {
+                                    worktree_id: selected_entry.worktree_id,
+                                    path: entry.path.clone(),
+                                })
+                            })
+                            .collect::<Vec<_>>()
+                    };
+                    if paths.is_empty() {
+                        return;
+                    }
+
+                    if let Some(split_direction) = split_direction {
+                        to_pane = workspace.split_pane(to_pane, split_direction, cx);
+                    }
+                    for path in paths {
                         workspace
                             .open_path(path, Some(to_pane.downgrade()), true, cx)
                             .detach_and_log_err(cx);
@@ -1755,6 +1893,7 @@ impl Render for Pane {
             .on_action(cx.listener(|pane, _: &GoBack, cx| pane.navigate_backward(cx)))
             .on_action(cx.listener(|pane, _: &GoForward, cx| pane.navigate_forward(cx)))
             .on_action(cx.listener(Pane::toggle_zoom))
+            .on_action(cx.listener(Pane::pin_preview_tab))
             .on_action(cx.listener(|pane: &mut Pane, action: &ActivateItem, cx| {
                 pane.activate_item(action.0, true, true, cx);
             }))
@@ -1831,7 +1970,7 @@ impl Render for Pane {
                     .relative()
                     .group("")
                     .on_drag_move::<DraggedTab>(cx.listener(Self::handle_drag_move))
-                    .on_drag_move::<ProjectEntryId>(cx.listener(Self::handle_drag_move))
+                    .on_drag_move::<DraggedSelection>(cx.listener(Self::handle_drag_move))
                     .on_drag_move::<ExternalPaths>(cx.listener(Self::handle_drag_move))
                     .map(|div| {
                         if let Some(item) = self.active_item() {
@@ -1861,7 +2000,7 @@ impl Render for Pane {
                                 0.75,
                             ))
                             .group_drag_over::<DraggedTab>("", |style| style.visible())
-                            .group_drag_over::<ProjectEntryId>("", |style| style.visible())
+                            .group_drag_over::<DraggedSelection>("", |style| style.visible())
                             .group_drag_over::<ExternalPaths>("", |style| style.visible())
                             .when_some(self.can_drop_predicate.clone(), |this, p| {
                                 this.can_drop(move |a, cx| p(a, cx))
@@ -1869,8 +2008,8 @@ impl Render for Pane {
                             .on_drop(cx.listener(move |this, dragged_tab, cx| {
                                 this.handle_tab_drop(dragged_tab, this.active_item_index(), cx)
                             }))
-                            .on_drop(cx.listener(move |this, entry_id, cx| {
-                                this.handle_project_entry_drop(entry_id, cx)
+                            .on_drop(cx.listener(move |this, selection, cx| {
+                                this.handle_dragged_selection_drop(selection, cx)
                             }))
                             .on_drop(cx.listener(move |this, paths, cx| {
                                 this.handle_external_paths_drop(paths, cx)
@@ -2536,6 +2675,46 @@ mod tests {
         assert_item_labels(&pane, [], cx);
     }
 
+    #[gpui::test]
+    async fn test_preview_items(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let pane = workspace.update(cx, |workspace, _| workspace.active_pane().clone());
+
+        add_labeled_item(&pane, "A", false, cx);
+        let open_preview = |label: &'static str, cx: &mut VisualTestContext| {
+            pane.update(cx, |pane, cx| {
+                pane.open_item(None, true, true, cx, |cx| {
+                    Box::new(cx.new_view(|cx| TestItem::new(cx).with_label(label)))
+                })
+            })
+        };
+
+        // Opening a preview item replaces the previous preview item in place.
+        let b = open_preview("B", cx);
+        assert_item_labels(&pane, ["A", "B*"], cx);
+        pane.update(cx, |pane, cx| pane.activate_item(0, false, false, cx));
+        open_preview("C", cx);
+        assert_item_labels(&pane, ["A", "C*"], cx);
+        pane.read_with(cx, |pane, _| {
+            assert!(!pane.is_active_preview_item(b.item_id()))
+        });
+
+        // Pinning the preview item keeps it open when the next preview is opened.
+        pane.update(cx, |pane, cx| pane.pin_preview_tab(&PinPreviewTab, cx));
+        pane.read_with(cx, |pane, _| assert_eq!(pane.preview_item_id(), None));
+        let d = open_preview("D", cx);
+        assert_item_labels(&pane, ["A", "C", "D*"], cx);
+
+        // Editing the preview item makes it permanent too.
+        pane.update(cx, |pane, cx| pane.handle_item_edit(d.item_id(), cx));
+        open_preview("E", cx);
+        assert_item_labels(&pane, ["A", "C", "D", "E*"], cx);
+    }
+
     fn init_test(cx: &mut TestAppContext) {
         cx.update(|cx| {
             let settings_store = SettingsStore::test(cx);
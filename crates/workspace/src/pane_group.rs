@@ -3,8 +3,8 @@ use anyhow::{anyhow, Result};
 use call::{ActiveCall, ParticipantLocation};
 use collections::HashMap;
 use gpui::{
-    point, size, AnyView, AnyWeakView, Axis, Bounds, IntoElement, Model, MouseButton, Pixels,
-    Point, View, ViewContext,
+    point, size, AnyView, AnyWeakView, AppContext, Axis, Bounds, IntoElement, Model, MouseButton,
+    Pixels, Point, View, WeakView,
 };
 use parking_lot::Mutex;
 use project::Project;
@@ -18,11 +18,11 @@ const VERTICAL_MIN_SIZE: f32 = 100.;
 
 #[derive(Clone)]
 pub struct PaneGroup {
-    pub(crate) root: Member,
+    pub root: Member,
 }
 
 impl PaneGroup {
-    pub(crate) fn with_root(root: Member) -> Self {
+    pub fn with_root(root: Member) -> Self {
         Self { root }
     }
 
@@ -88,7 +88,40 @@ impl PaneGroup {
         };
     }
 
-    pub(crate) fn render(
+    /// Returns the pane adjacent to `active_pane` in the given direction, if any.
+    pub fn find_pane_in_direction(
+        &self,
+        active_pane: &View<Pane>,
+        direction: SplitDirection,
+        cx: &AppContext,
+    ) -> Option<&View<Pane>> {
+        let bounding_box = self.bounding_box_for_pane(active_pane)?;
+        let cursor = active_pane.read(cx).pixel_position_of_cursor(cx);
+        let center = match cursor {
+            Some(cursor) if bounding_box.contains(&cursor) => cursor,
+            _ => bounding_box.center(),
+        };
+
+        let distance_to_next = HANDLE_HITBOX_SIZE;
+
+        let target = match direction {
+            SplitDirection::Left => {
+                Point::new(bounding_box.left() - distance_to_next.into(), center.y)
+            }
+            SplitDirection::Right => {
+                Point::new(bounding_box.right() + distance_to_next.into(), center.y)
+            }
+            SplitDirection::Up => {
+                Point::new(center.x, bounding_box.top() - distance_to_next.into())
+            }
+            SplitDirection::Down => {
+                Point::new(center.x, bounding_box.bottom() + distance_to_next.into())
+            }
+        };
+        self.pane_at_pixel_position(target)
+    }
+
+    pub fn render(
         &self,
         project: &Model<Project>,
         follower_states: &HashMap<View<Pane>, FollowerState>,
@@ -96,7 +129,8 @@ impl PaneGroup {
         active_pane: &View<Pane>,
         zoomed: Option<&AnyWeakView>,
         app_state: &Arc<AppState>,
-        cx: &mut ViewContext<Workspace>,
+        workspace: &WeakView<Workspace>,
+        cx: &mut WindowContext,
     ) -> impl IntoElement {
         self.root.render(
             project,
@@ -106,23 +140,24 @@ impl PaneGroup {
             active_pane,
             zoomed,
             app_state,
+            workspace,
             cx,
         )
     }
 
-    pub(crate) fn panes(&self) -> Vec<&View<Pane>> {
+    pub fn panes(&self) -> Vec<&View<Pane>> {
         let mut panes = Vec::new();
         self.root.collect_panes(&mut panes);
         panes
     }
 
-    pub(crate) fn first_pane(&self) -> View<Pane> {
+    pub fn first_pane(&self) -> View<Pane> {
         self.root.first_pane()
     }
 }
 
 #[derive(Clone)]
-pub(crate) enum Member {
+pub enum Member {
     Axis(PaneAxis),
     Pane(View<Pane>),
 }
@@ -168,7 +203,8 @@ impl Member {
         active_pane: &View<Pane>,
         zoomed: Option<&AnyWeakView>,
         app_state: &Arc<AppState>,
-        cx: &mut ViewContext<Workspace>,
+        workspace: &WeakView<Workspace>,
+        cx: &mut WindowContext,
     ) -> impl IntoElement {
         match self {
             Member::Pane(pane) => {
@@ -263,18 +299,18 @@ impl Member {
                                 .when_some(
                                     leader_join_data,
                                     |this, (leader_project_id, leader_user_id)| {
-                                        this.cursor_pointer().on_mouse_down(
-                                            MouseButton::Left,
-                                            cx.listener(move |this, _, cx| {
+                                        this.cursor_pointer().on_mouse_down(MouseButton::Left, {
+                                            let app_state = app_state.clone();
+                                            move |_, cx| {
                                                 crate::join_remote_project(
                                                     leader_project_id,
                                                     leader_user_id,
-                                                    this.app_state().clone(),
+                                                    app_state.clone(),
                                                     cx,
                                                 )
                                                 .detach_and_log_err(cx);
-                                            }),
-                                        )
+                                            }
+                                        })
                                     },
                                 ),
                         )
@@ -290,6 +326,7 @@ impl Member {
                     active_pane,
                     zoomed,
                     app_state,
+                    workspace,
                     cx,
                 )
                 .into_any(),
@@ -309,7 +346,7 @@ impl Member {
 }
 
 #[derive(Clone)]
-pub(crate) struct PaneAxis {
+pub struct PaneAxis {
     pub axis: Axis,
     pub members: Vec<Member>,
     pub flexes: Arc<Mutex<Vec<f32>>>,
@@ -480,7 +517,8 @@ impl PaneAxis {
         active_pane: &View<Pane>,
         zoomed: Option<&AnyWeakView>,
         app_state: &Arc<AppState>,
-        cx: &mut ViewContext<Workspace>,
+        workspace: &WeakView<Workspace>,
+        cx: &mut WindowContext,
     ) -> gpui::AnyElement {
         debug_assert!(self.members.len() == self.flexes.lock().len());
         let mut active_pane_ix = None;
@@ -490,7 +528,7 @@ impl PaneAxis {
             basis,
             self.flexes.clone(),
             self.bounding_boxes.clone(),
-            cx.view().downgrade(),
+            workspace.clone(),
         )
         .children(self.members.iter().enumerate().map(|(ix, member)| {
             if member.contains(active_pane) {
@@ -505,6 +543,7 @@ impl PaneAxis {
                     active_pane,
                     zoomed,
                     app_state,
+                    workspace,
                     cx,
                 )
                 .into_any_element()
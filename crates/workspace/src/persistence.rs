@@ -1,6 +1,6 @@
 pub mod model;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, bail, Context, Result};
 use db::{define_connection, query, sqlez::connection::Connection, sqlez_macros::sql};
@@ -185,6 +185,13 @@ define_connection! {
     //     kind: String, // Indicates which view this connects to. This is the key in the item_deserializers global
     //     position: usize, // Position of the item in the parent pane. This is equivalent to panes' position column
     //     active: bool, // Indicates if this item is the active one in the pane
+    //     preview: bool // Indicates if this item is a preview item
+    // )
+    //
+    // breakpoints(
+    //     workspace_id: usize, // References workspaces table
+    //     path: PathBuf, // Absolute path of the file containing the breakpoint
+    //     row: u32, // Zero-based row of the breakpoint
     // )
     pub static ref DB: WorkspaceDb<()> =
     &[sql!(
@@ -291,6 +298,22 @@ define_connection! {
     // Add pane group flex data
     sql!(
         ALTER TABLE pane_groups ADD COLUMN flexes TEXT;
+    ),
+    // Add preview flag to items
+    sql!(
+        ALTER TABLE items ADD COLUMN preview INTEGER; //bool
+    ),
+    // Add breakpoints
+    sql!(
+        CREATE TABLE breakpoints(
+            workspace_id INTEGER NOT NULL,
+            path BLOB NOT NULL,
+            row INTEGER NOT NULL,
+            FOREIGN KEY(workspace_id) REFERENCES workspaces(workspace_id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE,
+            PRIMARY KEY(workspace_id, path, row)
+        ) STRICT;
     )
     ];
 }
@@ -351,6 +374,10 @@ impl WorkspaceDb {
             bounds: bounds.map(|bounds| bounds.0),
             display,
             docks,
+            breakpoints: self
+                .get_breakpoints(workspace_id)
+                .context("Getting breakpoints")
+                .log_err()?,
         })
     }
 
@@ -407,6 +434,9 @@ impl WorkspaceDb {
                 Self::save_pane_group(conn, workspace.id, &workspace.center_group, None)
                     .context("save pane group in save workspace")?;
 
+                Self::save_breakpoints(conn, workspace.id, &workspace.breakpoints)
+                    .context("save breakpoints in save workspace")?;
+
                 Ok(())
             })
             .log_err();
@@ -623,7 +653,7 @@ impl WorkspaceDb {
 
     fn get_items(&self, pane_id: PaneId) -> Result<Vec<SerializedItem>> {
         Ok(self.select_bound(sql!(
-            SELECT kind, item_id, active FROM items
+            SELECT kind, item_id, active, preview FROM items
             WHERE pane_id = ?
                 ORDER BY position
         ))?(pane_id)?)
@@ -636,7 +666,7 @@ impl WorkspaceDb {
         items: &[SerializedItem],
     ) -> Result<()> {
         let mut insert = conn.exec_bound(sql!(
-            INSERT INTO items(workspace_id, pane_id, position, kind, item_id, active) VALUES (?, ?, ?, ?, ?, ?)
+            INSERT INTO items(workspace_id, pane_id, position, kind, item_id, active, preview) VALUES (?, ?, ?, ?, ?, ?, ?)
         )).context("Preparing insertion")?;
         for (position, item) in items.iter().enumerate() {
             insert((workspace_id, pane_id, position, item))?;
@@ -645,6 +675,36 @@ impl WorkspaceDb {
         Ok(())
     }
 
+    fn get_breakpoints(&self, workspace_id: WorkspaceId) -> Result<Vec<(PathBuf, u32)>> {
+        Ok(self.select_bound(sql!(
+            SELECT path, row FROM breakpoints
+            WHERE workspace_id = ?
+            ORDER BY path, row
+        ))?(workspace_id)?)
+    }
+
+    fn save_breakpoints(
+        conn: &Connection,
+        workspace_id: WorkspaceId,
+        breakpoints: &[(PathBuf, u32)],
+    ) -> Result<()> {
+        conn.exec_bound(sql!(
+            DELETE FROM breakpoints WHERE workspace_id = ?
+        ))?(workspace_id)
+        .context("Clearing old breakpoints")?;
+
+        let mut insert = conn
+            .exec_bound(sql!(
+                INSERT INTO breakpoints(workspace_id, path, row) VALUES (?, ?, ?)
+            ))
+            .context("Preparing insertion")?;
+        for (path, row) in breakpoints {
+            insert((workspace_id, path.as_path(), *row))?;
+        }
+
+        Ok(())
+    }
+
     query! {
         pub async fn update_timestamp(workspace_id: WorkspaceId) -> Result<()> {
             UPDATE workspaces
@@ -750,6 +810,7 @@ mod tests {
             bounds: Default::default(),
             display: Default::default(),
             docks: Default::default(),
+            breakpoints: Default::default(),
         };
 
         let workspace_2 = SerializedWorkspace {
@@ -759,6 +820,7 @@ mod tests {
             bounds: Default::default(),
             display: Default::default(),
             docks: Default::default(),
+            breakpoints: Default::default(),
         };
 
         db.save_workspace(workspace_1.clone()).await;
@@ -826,15 +888,15 @@ mod tests {
                     vec![
                         SerializedPaneGroup::Pane(SerializedPane::new(
                             vec![
-                                SerializedItem::new("Terminal", 5, false),
-                                SerializedItem::new("Terminal", 6, true),
+                                SerializedItem::new("Terminal", 5, false, false),
+                                SerializedItem::new("Terminal", 6, true, false),
                             ],
                             false,
                         )),
                         SerializedPaneGroup::Pane(SerializedPane::new(
                             vec![
-                                SerializedItem::new("Terminal", 7, true),
-                                SerializedItem::new("Terminal", 8, false),
+                                SerializedItem::new("Terminal", 7, true, false),
+                                SerializedItem::new("Terminal", 8, false, false),
                             ],
                             false,
                         )),
@@ -842,8 +904,8 @@ mod tests {
                 ),
                 SerializedPaneGroup::Pane(SerializedPane::new(
                     vec![
-                        SerializedItem::new("Terminal", 9, false),
-                        SerializedItem::new("Terminal", 10, true),
+                        SerializedItem::new("Terminal", 9, false, false),
+                        SerializedItem::new("Terminal", 10, true, false),
                     ],
                     false,
                 )),
@@ -857,6 +919,7 @@ mod tests {
             bounds: Default::default(),
             display: Default::default(),
             docks: Default::default(),
+            breakpoints: vec![("/tmp/a.rs".into(), 3), ("/tmp2/b.rs".into(), 0)],
         };
 
         db.save_workspace(workspace.clone()).await;
@@ -885,6 +948,7 @@ mod tests {
             bounds: Default::default(),
             display: Default::default(),
             docks: Default::default(),
+            breakpoints: Default::default(),
         };
 
         let mut workspace_2 = SerializedWorkspace {
@@ -894,6 +958,7 @@ mod tests {
             bounds: Default::default(),
             display: Default::default(),
             docks: Default::default(),
+            breakpoints: Default::default(),
         };
 
         db.save_workspace(workspace_1.clone()).await;
@@ -930,6 +995,7 @@ mod tests {
             bounds: Default::default(),
             display: Default::default(),
             docks: Default::default(),
+            breakpoints: Default::default(),
         };
 
         db.save_workspace(workspace_3.clone()).await;
@@ -963,6 +1029,7 @@ mod tests {
             bounds: Default::default(),
             display: Default::default(),
             docks: Default::default(),
+            breakpoints: Default::default(),
         }
     }
 
@@ -985,15 +1052,15 @@ mod tests {
                     vec![
                         SerializedPaneGroup::Pane(SerializedPane::new(
                             vec![
-                                SerializedItem::new("Terminal", 1, false),
-                                SerializedItem::new("Terminal", 2, true),
+                                SerializedItem::new("Terminal", 1, false, false),
+                                SerializedItem::new("Terminal", 2, true, false),
                             ],
                             false,
                         )),
                         SerializedPaneGroup::Pane(SerializedPane::new(
                             vec![
-                                SerializedItem::new("Terminal", 4, false),
-                                SerializedItem::new("Terminal", 3, true),
+                                SerializedItem::new("Terminal", 4, false, false),
+                                SerializedItem::new("Terminal", 3, true, false),
                             ],
                             true,
                         )),
@@ -1001,8 +1068,8 @@ mod tests {
                 ),
                 SerializedPaneGroup::Pane(SerializedPane::new(
                     vec![
-                        SerializedItem::new("Terminal", 5, true),
-                        SerializedItem::new("Terminal", 6, false),
+                        SerializedItem::new("Terminal", 5, true, false),
+                        SerializedItem::new("Terminal", 6, false, false),
                     ],
                     false,
                 )),
@@ -1032,15 +1099,15 @@ mod tests {
                     vec![
                         SerializedPaneGroup::Pane(SerializedPane::new(
                             vec![
-                                SerializedItem::new("Terminal", 1, false),
-                                SerializedItem::new("Terminal", 2, true),
+                                SerializedItem::new("Terminal", 1, false, false),
+                                SerializedItem::new("Terminal", 2, true, false),
                             ],
                             false,
                         )),
                         SerializedPaneGroup::Pane(SerializedPane::new(
                             vec![
-                                SerializedItem::new("Terminal", 4, false),
-                                SerializedItem::new("Terminal", 3, true),
+                                SerializedItem::new("Terminal", 4, false, false),
+                                SerializedItem::new("Terminal", 3, true, false),
                             ],
                             true,
                         )),
@@ -1048,8 +1115,8 @@ mod tests {
                 ),
                 SerializedPaneGroup::Pane(SerializedPane::new(
                     vec![
-                        SerializedItem::new("Terminal", 5, false),
-                        SerializedItem::new("Terminal", 6, true),
+                        SerializedItem::new("Terminal", 5, false, false),
+                        SerializedItem::new("Terminal", 6, true, false),
                     ],
                     false,
                 )),
@@ -1067,15 +1134,15 @@ mod tests {
             vec![
                 SerializedPaneGroup::Pane(SerializedPane::new(
                     vec![
-                        SerializedItem::new("Terminal", 1, false),
-                        SerializedItem::new("Terminal", 2, true),
+                        SerializedItem::new("Terminal", 1, false, false),
+                        SerializedItem::new("Terminal", 2, true, false),
                     ],
                     false,
                 )),
                 SerializedPaneGroup::Pane(SerializedPane::new(
                     vec![
-                        SerializedItem::new("Terminal", 4, true),
-                        SerializedItem::new("Terminal", 3, false),
+                        SerializedItem::new("Terminal", 4, true, false),
+                        SerializedItem::new("Terminal", 3, false, false),
                     ],
                     true,
                 )),
@@ -62,6 +62,8 @@ pub(crate) struct SerializedWorkspace {
     pub(crate) bounds: Option<WindowBounds>,
     pub(crate) display: Option<Uuid>,
     pub(crate) docks: DockStructure,
+    /// The breakpoints of the project, as zero-based rows in the files at the given paths.
+    pub(crate) breakpoints: Vec<(PathBuf, u32)>,
 }
 
 #[derive(Debug, PartialEq, Clone, Default)]
@@ -253,13 +255,20 @@ impl SerializedPane {
         }
 
         let mut items = Vec::new();
-        for item_handle in futures::future::join_all(item_tasks).await {
+        for (item, item_handle) in self
+            .children
+            .iter()
+            .zip(futures::future::join_all(item_tasks).await)
+        {
             let item_handle = item_handle.log_err();
             items.push(item_handle.clone());
 
             if let Some(item_handle) = item_handle {
                 pane.update(cx, |pane, cx| {
                     pane.add_item(item_handle.clone(), true, true, None, cx);
+                    if item.preview {
+                        pane.set_preview_item_id(Some(item_handle.item_id()), cx);
+                    }
                 })?;
             }
         }
@@ -283,14 +292,16 @@ pub struct SerializedItem {
     pub kind: Arc<str>,
     pub item_id: ItemId,
     pub active: bool,
+    pub preview: bool,
 }
 
 impl SerializedItem {
-    pub fn new(kind: impl AsRef<str>, item_id: ItemId, active: bool) -> Self {
+    pub fn new(kind: impl AsRef<str>, item_id: ItemId, active: bool, preview: bool) -> Self {
         Self {
             kind: Arc::from(kind.as_ref()),
             item_id,
             active,
+            preview,
         }
     }
 }
@@ -302,20 +313,22 @@ impl Default for SerializedItem {
             kind: Arc::from("Terminal"),
             item_id: 100000,
             active: false,
+            preview: false,
         }
     }
 }
 
 impl StaticColumnCount for SerializedItem {
     fn column_count() -> usize {
-        3
+        4
     }
 }
 impl Bind for &SerializedItem {
     fn bind(&self, statement: &Statement, start_index: i32) -> Result<i32> {
         let next_index = statement.bind(&self.kind, start_index)?;
         let next_index = statement.bind(&self.item_id, next_index)?;
-        statement.bind(&self.active, next_index)
+        let next_index = statement.bind(&self.active, next_index)?;
+        statement.bind(&self.preview, next_index)
     }
 }
 
@@ -324,11 +337,13 @@ impl Column for SerializedItem {
         let (kind, next_index) = Arc::<str>::column(statement, start_index)?;
         let (item_id, next_index) = ItemId::column(statement, next_index)?;
         let (active, next_index) = bool::column(statement, next_index)?;
+        let (preview, next_index) = Option::<bool>::column(statement, next_index)?;
         Ok((
             SerializedItem {
                 kind,
                 item_id,
                 active,
+                preview: preview.unwrap_or(false),
             },
             next_index,
         ))
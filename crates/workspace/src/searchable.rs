@@ -30,6 +30,8 @@ pub struct SearchOptions {
     pub regex: bool,
     /// Specifies whether the item supports search & replace.
     pub replacement: bool,
+    /// Specifies whether the item supports restricting the search to its current selections.
+    pub selection: bool,
 }
 
 pub trait SearchableItem: Item + EventEmitter<SearchEvent> {
@@ -41,10 +43,18 @@ pub trait SearchableItem: Item + EventEmitter<SearchEvent> {
             word: true,
             regex: true,
             replacement: true,
+            selection: false,
         }
     }
 
     fn clear_matches(&mut self, cx: &mut ViewContext<Self>);
+    /// Captures (or releases) the ranges that subsequent calls to `find_matches` are restricted to.
+    fn toggle_filtered_search_ranges(&mut self, _enabled: bool, _cx: &mut ViewContext<Self>) {}
+    /// Whether `find_matches` is restricted to captured ranges, which stops being the case
+    /// once there's no text left in any of them.
+    fn has_filtered_search_ranges(&mut self, _cx: &mut ViewContext<Self>) -> bool {
+        false
+    }
     fn update_matches(&mut self, matches: Vec<Self::Match>, cx: &mut ViewContext<Self>);
     fn query_suggestion(&mut self, cx: &mut ViewContext<Self>) -> String;
     fn activate_match(
@@ -97,6 +107,8 @@ pub trait SearchableItemHandle: ItemHandle {
         handler: Box<dyn Fn(&SearchEvent, &mut WindowContext) + Send>,
     ) -> Subscription;
     fn clear_matches(&self, cx: &mut WindowContext);
+    fn toggle_filtered_search_ranges(&self, enabled: bool, cx: &mut WindowContext);
+    fn has_filtered_search_ranges(&self, cx: &mut WindowContext) -> bool;
     fn update_matches(&self, matches: &Vec<Box<dyn Any + Send>>, cx: &mut WindowContext);
     fn query_suggestion(&self, cx: &mut WindowContext) -> String;
     fn activate_match(
@@ -151,6 +163,14 @@ impl<T: SearchableItem> SearchableItemHandle for View<T> {
     fn clear_matches(&self, cx: &mut WindowContext) {
         self.update(cx, |this, cx| this.clear_matches(cx));
     }
+    fn toggle_filtered_search_ranges(&self, enabled: bool, cx: &mut WindowContext) {
+        self.update(cx, |this, cx| {
+            this.toggle_filtered_search_ranges(enabled, cx)
+        });
+    }
+    fn has_filtered_search_ranges(&self, cx: &mut WindowContext) -> bool {
+        self.update(cx, |this, cx| this.has_filtered_search_ranges(cx))
+    }
     fn update_matches(&self, matches: &Vec<Box<dyn Any + Send>>, cx: &mut WindowContext) {
         let matches = downcast_matches(matches);
         self.update(cx, |this, cx| this.update_matches(matches, cx));
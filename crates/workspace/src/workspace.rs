@@ -34,7 +34,10 @@ use gpui::{
     Render, SharedString, Size, Styled, Subscription, Task, View, ViewContext, VisualContext,
     WeakView, WindowBounds, WindowContext, WindowHandle, WindowOptions,
 };
-use item::{FollowableItem, FollowableItemHandle, Item, ItemHandle, ItemSettings, ProjectItem};
+use item::{
+    FollowableItem, FollowableItemHandle, Item, ItemHandle, ItemSettings, PreviewTabsSettings,
+    ProjectItem,
+};
 use itertools::Itertools;
 use language::{LanguageRegistry, Rope};
 use lazy_static::lazy_static;
@@ -50,6 +53,7 @@ pub use persistence::{
 };
 use postage::stream::Stream;
 use project::{Project, ProjectEntryId, ProjectPath, Worktree, WorktreeId};
+use remote::{SshConnectionOptions, SshSession};
 use serde::Deserialize;
 use settings::Settings;
 use shared_screen::SharedScreen;
@@ -226,6 +230,7 @@ pub type WorkspaceId = i64;
 pub fn init_settings(cx: &mut AppContext) {
     WorkspaceSettings::register(cx);
     ItemSettings::register(cx);
+    PreviewTabsSettings::register(cx);
 }
 
 pub fn init(app_state: Arc<AppState>, cx: &mut AppContext) {
@@ -516,7 +521,7 @@ pub struct ViewId {
 }
 
 #[derive(Default)]
-struct FollowerState {
+pub struct FollowerState {
     leader_id: PeerId,
     active_view_id: Option<ViewId>,
     items_by_leader_view_id: HashMap<ViewId, Box<dyn FollowableItemHandle>>,
@@ -680,6 +685,7 @@ impl Workspace {
             active_call = Some((call, subscriptions));
         }
 
+        let breakpoint_store = project.read(cx).breakpoint_store().clone();
         let subscriptions = vec![
             cx.observe_window_activation(Self::on_window_activation_changed),
             cx.observe_window_bounds(move |_, cx| {
@@ -723,6 +729,9 @@ impl Workspace {
                 this.serialize_workspace(cx);
                 cx.notify();
             }),
+            cx.observe(&breakpoint_store, |this, _, cx| {
+                this.serialize_workspace(cx);
+            }),
             cx.on_release(|this, window, cx| {
                 this.app_state.workspace_store.update(cx, |store, _| {
                     let window = window.downcast::<Self>().unwrap();
@@ -1056,7 +1065,7 @@ impl Workspace {
                     })?;
 
                     pane.update(&mut cx, |pane, cx| {
-                        let item = pane.open_item(project_entry_id, true, cx, build_item);
+                        let item = pane.open_item(project_entry_id, true, false, cx, build_item);
                         navigated |= Some(item.item_id()) != prev_active_item_id;
                         pane.nav_history_mut().set_mode(NavigationMode::Normal);
                         if let Some(data) = entry.data {
@@ -1239,22 +1248,63 @@ impl Workspace {
                 }
             }
 
+            // Items whose unsaved changes are restored on the next launch don't need saving.
+            let backed_up_items = if quitting {
+                this.update(&mut cx, |this, cx| this.backup_unsaved_changes(cx))?
+                    .await
+            } else {
+                HashSet::default()
+            };
+
             Ok(this
                 .update(&mut cx, |this, cx| {
-                    this.save_all_internal(SaveIntent::Close, cx)
+                    this.save_all_internal(SaveIntent::Close, &backed_up_items, cx)
                 })?
                 .await?)
         })
     }
 
+    /// Backs up the unsaved changes of every dirty item that can restore them,
+    /// returning the ids of the items that were backed up.
+    fn backup_unsaved_changes(&mut self, cx: &mut ViewContext<Self>) -> Task<HashSet<EntityId>> {
+        if !WorkspaceSettings::get_global(cx).restore_unsaved_buffers {
+            return Task::ready(HashSet::default());
+        }
+        let dirty_items = self
+            .panes
+            .iter()
+            .flat_map(|pane| pane.read(cx).items())
+            .filter(|item| item.is_dirty(cx))
+            .map(|item| item.boxed_clone())
+            .collect::<Vec<_>>();
+        let backups = dirty_items
+            .into_iter()
+            .filter_map(|item| Some((item.item_id(), item.backup_unsaved_changes(cx)?)))
+            .collect::<Vec<_>>();
+        cx.background_executor().spawn(async move {
+            let mut backed_up_items = HashSet::default();
+            for (item_id, backup) in backups {
+                if backup.await.log_err().is_some() {
+                    backed_up_items.insert(item_id);
+                }
+            }
+            backed_up_items
+        })
+    }
+
     fn save_all(&mut self, action: &SaveAll, cx: &mut ViewContext<Self>) {
-        self.save_all_internal(action.save_intent.unwrap_or(SaveIntent::SaveAll), cx)
-            .detach_and_log_err(cx);
+        self.save_all_internal(
+            action.save_intent.unwrap_or(SaveIntent::SaveAll),
+            &HashSet::default(),
+            cx,
+        )
+        .detach_and_log_err(cx);
     }
 
     fn save_all_internal(
         &mut self,
         mut save_intent: SaveIntent,
+        skipped_items: &HashSet<EntityId>,
         cx: &mut ViewContext<Self>,
     ) -> Task<Result<bool>> {
         if self.project.read(cx).is_disconnected() {
@@ -1265,7 +1315,7 @@ impl Workspace {
             .iter()
             .flat_map(|pane| {
                 pane.read(cx).items().filter_map(|item| {
-                    if item.is_dirty(cx) {
+                    if item.is_dirty(cx) && !skipped_items.contains(&item.item_id()) {
                         Some((pane.downgrade(), item.boxed_clone()))
                     } else {
                         None
@@ -1835,14 +1885,24 @@ impl Workspace {
     }
 
     pub fn add_item(&mut self, item: Box<dyn ItemHandle>, cx: &mut WindowContext) {
+        self.add_item_at(item, None, cx);
+    }
+
+    fn add_item_at(
+        &mut self,
+        item: Box<dyn ItemHandle>,
+        destination_index: Option<usize>,
+        cx: &mut WindowContext,
+    ) {
         if let Some(text) = item.telemetry_event_text(cx) {
             self.client()
                 .telemetry()
                 .report_app_event(format!("{}: open", text));
         }
 
-        self.active_pane
-            .update(cx, |pane, cx| pane.add_item(item, true, true, None, cx));
+        self.active_pane.update(cx, |pane, cx| {
+            pane.add_item(item, true, true, destination_index, cx)
+        });
     }
 
     pub fn split_item(
@@ -1917,6 +1977,19 @@ impl Workspace {
         pane: Option<WeakView<Pane>>,
         focus_item: bool,
         cx: &mut WindowContext,
+    ) -> Task<Result<Box<dyn ItemHandle>, anyhow::Error>> {
+        self.open_path_preview(path, pane, focus_item, false, cx)
+    }
+
+    /// Opens the given path, allowing it to replace the pane's current preview item
+    /// when `allow_preview` is set and preview tabs are enabled.
+    pub fn open_path_preview(
+        &mut self,
+        path: impl Into<ProjectPath>,
+        pane: Option<WeakView<Pane>>,
+        focus_item: bool,
+        allow_preview: bool,
+        cx: &mut WindowContext,
     ) -> Task<Result<Box<dyn ItemHandle>, anyhow::Error>> {
         let pane = pane.unwrap_or_else(|| {
             self.last_active_center_pane.clone().unwrap_or_else(|| {
@@ -1931,7 +2004,7 @@ impl Workspace {
         cx.spawn(move |mut cx| async move {
             let (project_entry_id, build_item) = task.await?;
             pane.update(&mut cx, |pane, cx| {
-                pane.open_item(project_entry_id, focus_item, cx, build_item)
+                pane.open_item(project_entry_id, focus_item, allow_preview, cx, build_item)
             })
         })
     }
@@ -1961,7 +2034,7 @@ impl Workspace {
                 let pane = pane.upgrade()?;
                 let new_pane = this.split_pane(pane, SplitDirection::Right, cx);
                 new_pane.update(cx, |new_pane, cx| {
-                    Some(new_pane.open_item(project_entry_id, true, cx, build_item))
+                    Some(new_pane.open_item(project_entry_id, true, false, cx, build_item))
                 })
             })
             .map(|option| option.ok_or_else(|| anyhow!("pane was dropped")))?
@@ -1997,6 +2070,7 @@ impl Workspace {
     pub fn open_project_item<T>(
         &mut self,
         project_item: Model<T::Item>,
+        allow_preview: bool,
         cx: &mut ViewContext<Self>,
     ) -> View<T>
     where
@@ -2009,12 +2083,26 @@ impl Workspace {
             .and_then(|entry_id| self.active_pane().read(cx).item_for_entry(entry_id, cx))
             .and_then(|item| item.downcast())
         {
+            if !allow_preview {
+                self.active_pane.update(cx, |pane, cx| {
+                    pane.handle_item_edit(item.item_id(), cx);
+                });
+            }
             self.activate_item(&item, cx);
             return item;
         }
 
         let item = cx.new_view(|cx| T::for_project_item(self.project().clone(), project_item, cx));
-        self.add_item(Box::new(item.clone()), cx);
+        let destination_index = if allow_preview {
+            self.active_pane.update(cx, |pane, cx| {
+                let destination_index = pane.close_current_preview_item(cx);
+                pane.set_preview_item_id(Some(item.item_id()), cx);
+                destination_index
+            })
+        } else {
+            None
+        };
+        self.add_item_at(Box::new(item.clone()), destination_index, cx);
         item
     }
 
@@ -2189,32 +2277,9 @@ impl Workspace {
         direction: SplitDirection,
         cx: &WindowContext,
     ) -> Option<View<Pane>> {
-        let Some(bounding_box) = self.center.bounding_box_for_pane(&self.active_pane) else {
-            return None;
-        };
-        let cursor = self.active_pane.read(cx).pixel_position_of_cursor(cx);
-        let center = match cursor {
-            Some(cursor) if bounding_box.contains(&cursor) => cursor,
-            _ => bounding_box.center(),
-        };
-
-        let distance_to_next = pane_group::HANDLE_HITBOX_SIZE;
-
-        let target = match direction {
-            SplitDirection::Left => {
-                Point::new(bounding_box.left() - distance_to_next.into(), center.y)
-            }
-            SplitDirection::Right => {
-                Point::new(bounding_box.right() + distance_to_next.into(), center.y)
-            }
-            SplitDirection::Up => {
-                Point::new(center.x, bounding_box.top() - distance_to_next.into())
-            }
-            SplitDirection::Down => {
-                Point::new(center.x, bounding_box.bottom() + distance_to_next.into())
-            }
-        };
-        self.center.pane_at_pixel_position(target).cloned()
+        self.center
+            .find_pane_in_direction(&self.active_pane, direction, cx)
+            .cloned()
     }
 
     pub fn swap_pane_in_direction(
@@ -3160,6 +3225,7 @@ impl Workspace {
                                 kind: Arc::from(item_handle.serialized_item_kind()?),
                                 item_id: item_handle.item_id().as_u64(),
                                 active: Some(item_handle.item_id()) == active_item_id,
+                                preview: pane.is_active_preview_item(item_handle.item_id()),
                             })
                         })
                         .collect::<Vec<_>>(),
@@ -3251,6 +3317,14 @@ impl Workspace {
             if !location.paths().is_empty() {
                 let center_group = build_serialized_pane_group(&self.center.root, cx);
                 let docks = build_serialized_docks(self, cx);
+                let breakpoints = self
+                    .project
+                    .read(cx)
+                    .breakpoint_store()
+                    .read(cx)
+                    .all(cx)
+                    .map(|(path, row)| (path.to_path_buf(), row))
+                    .collect();
 
                 let serialized_workspace = SerializedWorkspace {
                     id: self.database_id,
@@ -3259,6 +3333,7 @@ impl Workspace {
                     bounds: Default::default(),
                     display: Default::default(),
                     docks,
+                    breakpoints,
                 };
 
                 cx.spawn(|_| persistence::DB.save_workspace(serialized_workspace))
@@ -3344,6 +3419,18 @@ impl Workspace {
                     .bottom_dock
                     .update(cx, |dock, _| dock.serialized_dock = Some(bottom));
 
+                let breakpoints = serialized_workspace.breakpoints;
+                workspace.project.update(cx, |project, cx| {
+                    project.breakpoint_store().update(cx, |store, cx| {
+                        store.restore(
+                            breakpoints
+                                .into_iter()
+                                .map(|(path, row)| (path.into(), row)),
+                            cx,
+                        )
+                    })
+                });
+
                 cx.notify();
             })?;
 
@@ -3735,6 +3822,7 @@ impl Render for Workspace {
                                         &self.active_pane,
                                         self.zoomed.as_ref(),
                                         &self.app_state,
+                                        &cx.view().downgrade(),
                                         cx,
                                     ))
                                     .children(
@@ -4412,6 +4500,42 @@ pub fn join_remote_project(
     })
 }
 
+/// Opens a window with a project hosted on another machine by a headless project
+/// server, started there over SSH.
+pub fn open_ssh_project(
+    options: SshConnectionOptions,
+    paths: Vec<String>,
+    app_state: Arc<AppState>,
+    cx: &mut AppContext,
+) -> Task<Result<()>> {
+    cx.spawn(|mut cx| async move {
+        let (session, connection) = SshSession::start(options, &paths, cx.background_executor())?;
+        let project = Project::ssh(
+            Some(Arc::new(session)),
+            connection,
+            app_state.client.http_client(),
+            app_state.languages.clone(),
+            app_state.fs.clone(),
+            cx.clone(),
+        )
+        .await?;
+
+        let window_bounds_override = window_bounds_env_override(&cx);
+        let workspace = cx.update(|cx| {
+            let options = (app_state.build_window_options)(window_bounds_override, None, cx);
+            cx.open_window(options, |cx| {
+                cx.new_view(|cx| Workspace::new(0, project, app_state.clone(), cx))
+            })
+        })?;
+        workspace.update(&mut cx, |_, cx| {
+            cx.activate(true);
+            cx.activate_window();
+        })?;
+
+        anyhow::Ok(())
+    })
+}
+
 pub fn restart(_: &Restart, cx: &mut AppContext) {
     let should_confirm = WorkspaceSettings::get_global(cx).confirm_quit;
     let mut workspace_windows = cx
@@ -4728,6 +4852,71 @@ mod tests {
         assert!(!task.await.unwrap());
     }
 
+    #[gpui::test]
+    async fn test_quit_with_backed_up_items(cx: &mut TestAppContext) {
+        init_test(cx);
+        cx.update(|cx| {
+            cx.update_global(|settings: &mut SettingsStore, cx| {
+                settings.update_user_settings::<WorkspaceSettings>(cx, |settings| {
+                    settings.restore_unsaved_buffers = Some(true);
+                })
+            });
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+
+        let item1 = cx.new_view(|cx| TestItem::new(cx).with_dirty(true).with_backup(true));
+        let item2 = cx.new_view(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_project_items(&[TestProjectItem::new(1, "1.txt", cx)])
+        });
+        workspace.update(cx, |w, cx| {
+            w.add_item(Box::new(item1.clone()), cx);
+            w.add_item(Box::new(item2.clone()), cx);
+        });
+
+        // When quitting, the items that back up their changes aren't prompted for.
+        let task = workspace.update(cx, |w, cx| w.prepare_to_close(true, cx));
+        cx.executor().run_until_parked();
+        cx.simulate_prompt_answer(1); // don't save the item that can't be backed up
+        cx.executor().run_until_parked();
+        assert!(!cx.has_pending_prompt());
+        assert!(task.await.unwrap());
+        item1.update(cx, |item, _| {
+            assert_eq!(item.backup_count, 1);
+            assert_eq!(item.save_count + item.save_as_count, 0);
+        });
+
+        // When closing the window, every dirty item is prompted for.
+        let task = workspace.update(cx, |w, cx| w.prepare_to_close(false, cx));
+        cx.executor().run_until_parked();
+        cx.simulate_prompt_answer(2); // cancel save all
+        cx.executor().run_until_parked();
+        cx.simulate_prompt_answer(2); // cancel saving the first item
+        cx.executor().run_until_parked();
+        assert!(!task.await.unwrap());
+
+        // Items aren't backed up once restoring unsaved buffers is turned off.
+        cx.update(|cx| {
+            cx.update_global(|settings: &mut SettingsStore, cx| {
+                settings.update_user_settings::<WorkspaceSettings>(cx, |settings| {
+                    settings.restore_unsaved_buffers = Some(false);
+                })
+            });
+        });
+        let task = workspace.update(cx, |w, cx| w.prepare_to_close(true, cx));
+        cx.executor().run_until_parked();
+        cx.simulate_prompt_answer(2); // cancel save all
+        cx.executor().run_until_parked();
+        cx.simulate_prompt_answer(2); // cancel saving the first item
+        cx.executor().run_until_parked();
+        assert!(!task.await.unwrap());
+        item1.update(cx, |item, _| assert_eq!(item.backup_count, 1));
+    }
+
     #[gpui::test]
     async fn test_close_pane_items(cx: &mut TestAppContext) {
         init_test(cx);
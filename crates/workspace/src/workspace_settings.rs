@@ -8,6 +8,7 @@ pub struct WorkspaceSettings {
     pub confirm_quit: bool,
     pub show_call_status_icon: bool,
     pub autosave: AutosaveSetting,
+    pub restore_unsaved_buffers: bool,
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
@@ -30,6 +31,12 @@ pub struct WorkspaceSettingsContent {
     ///
     /// Default: off
     pub autosave: Option<AutosaveSetting>,
+    /// Whether to keep the unsaved changes of buffers, including untitled ones,
+    /// across restarts instead of prompting to save them when quitting. The
+    /// changes are also backed up periodically, so that they survive a crash.
+    ///
+    /// Default: false
+    pub restore_unsaved_buffers: Option<bool>,
 }
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
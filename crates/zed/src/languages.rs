@@ -52,6 +52,7 @@ mod zig;
 // 5. Add indents.scm, outline.scm, and brackets.scm to implement indent on newline, outline/breadcrumbs,
 //    and autoclosing brackets respectively
 // 6. If the language has injections add an injections.scm query file
+// 7. Add runnables.scm to show run buttons next to the tests and entry points of the language
 
 #[derive(RustEmbed)]
 #[folder = "src/languages"]
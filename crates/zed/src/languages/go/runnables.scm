@@ -0,0 +1,20 @@
+(
+    (function_declaration
+        name: (_) @run)
+    (#match? @run "^Test")
+    (#set! "kind" "test")
+)
+
+(
+    (function_declaration
+        name: (_) @run)
+    (#match? @run "^Benchmark")
+    (#set! "kind" "benchmark")
+)
+
+(
+    (function_declaration
+        name: (_) @run)
+    (#eq? @run "main")
+    (#set! "kind" "main")
+)
@@ -0,0 +1,10 @@
+; Test suites and test cases of frameworks such as Jest and Mocha.
+(
+    (call_expression
+        function: (identifier) @_function
+        arguments: (arguments
+            .
+            [(string) (template_string)] @run))
+    (#match? @_function "^(describe|it|test)$")
+    (#set! "kind" "test")
+)
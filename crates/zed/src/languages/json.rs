@@ -70,6 +70,13 @@ impl JsonLspAdapter {
                     {
                         "fileMatch": [schema_file_match(&paths::KEYMAP)],
                         "schema": KeymapFile::generate_json_schema(&action_names),
+                    },
+                    {
+                        "fileMatch": [
+                            schema_file_match(&paths::TASKS),
+                            &*paths::LOCAL_TASKS_RELATIVE_PATH,
+                        ],
+                        "schema": schemars::schema_for!(task::TaskTemplates),
                     }
                 ]
             }
@@ -0,0 +1,19 @@
+; Test functions, either at the top level or inside of a test class.
+(
+    (function_definition
+        name: (_) @run)
+    (#match? @run "^test")
+    (#set! "kind" "test")
+)
+
+(
+    (if_statement
+        "if" @run
+        condition: (comparison_operator
+            (identifier) @_name
+            (string) @_main)
+        consequence: (_))
+    (#eq? @_name "__name__")
+    (#match? @_main "^[\"']__main__[\"']$")
+    (#set! "kind" "main")
+)
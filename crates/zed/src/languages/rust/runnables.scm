@@ -0,0 +1,29 @@
+; Functions annotated with `#[test]`, or with the test attribute of a crate such as
+; `#[tokio::test]`.
+(
+    (attribute_item) @_attribute
+    .
+    [(attribute_item) (line_comment)]*
+    .
+    (function_item
+        name: (_) @run)
+    (#match? @_attribute "^#\\[(\\w+::)*test\\b")
+    (#set! "kind" "test")
+)
+
+(
+    (attribute_item) @_attribute
+    .
+    [(attribute_item) (line_comment)]*
+    .
+    (function_item
+        name: (_) @run)
+    (#match? @_attribute "^#\\[bench\\]")
+    (#set! "kind" "benchmark")
+)
+
+(source_file
+    (function_item
+        name: (_) @run
+        (#eq? @run "main")
+        (#set! "kind" "main")))
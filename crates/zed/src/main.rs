@@ -63,6 +63,11 @@ use zed::{
 static GLOBAL: MiMalloc = MiMalloc;
 
 fn main() {
+    // Zed runs persistent terminal sessions as copies of itself.
+    if terminal_view::run_session_daemon_if_requested() {
+        return;
+    }
+
     menu::init();
     zed_actions::init();
 
@@ -95,6 +100,8 @@ fn main() {
         fs.clone(),
         paths::KEYMAP.clone(),
     );
+    let user_tasks_file_rx =
+        watch_config_file(&app.background_executor(), fs.clone(), paths::TASKS.clone());
 
     let login_shell_env_loaded = if stdout_is_a_pty() {
         Task::ready(())
@@ -138,6 +145,7 @@ fn main() {
         cx.set_global(store);
         handle_settings_file_changes(user_settings_file_rx, cx);
         handle_keymap_file_changes(user_keymap_file_rx, cx);
+        project::task_inventory::handle_tasks_file_changes(user_tasks_file_rx, cx);
         client::init_settings(cx);
 
         let http = http::zed_client(&client::ClientSettings::get_global(cx).server_url);
@@ -241,7 +249,13 @@ fn main() {
 
         go_to_line::init(cx);
         file_finder::init(cx);
+        file_history::init(cx);
+        diff_view::init(cx);
+        hex_viewer::init(cx);
+        image_viewer::init(cx);
+        merge_conflicts::init(cx);
         outline::init(cx);
+        undo_tree::init(cx);
         project_symbols::init(cx);
         project_panel::init(Assets, cx);
         channel::init(&client, user_store.clone(), cx);
@@ -249,6 +263,8 @@ fn main() {
         semantic_index::init(fs.clone(), http.clone(), languages.clone(), cx);
         vim::init(cx);
         terminal_view::init(cx);
+        tasks_ui::init(cx);
+        debugger_ui::init(cx);
 
         journal::init(app_state.clone(), cx);
         language_selector::init(cx);
@@ -348,6 +364,10 @@ fn main() {
                 })
                 .detach_and_log_err(cx);
             }
+            Ok(Some(OpenRequest::OpenSshProject { options, paths })) => {
+                workspace::open_ssh_project(options, paths, app_state.clone(), cx)
+                    .detach_and_log_err(cx);
+            }
             Ok(None) | Err(_) => cx
                 .spawn({
                     let app_state = app_state.clone();
@@ -403,6 +423,14 @@ fn main() {
                         cx.update(|cx| open_notes_task.detach_and_log_err(cx))
                             .log_err();
                     }
+                    OpenRequest::OpenSshProject { options, paths } => {
+                        let app_state = app_state.clone();
+                        cx.update(|cx| {
+                            workspace::open_ssh_project(options, paths, app_state, cx)
+                                .detach_and_log_err(cx)
+                        })
+                        .log_err();
+                    }
                 }
             }
         })
@@ -863,7 +891,7 @@ fn collect_url_args() -> Vec<String> {
         .filter_map(|arg| match std::fs::canonicalize(Path::new(&arg)) {
             Ok(path) => Some(format!("file://{}", path.to_string_lossy())),
             Err(error) => {
-                if let Some(_) = parse_zed_link(&arg) {
+                if parse_zed_link(&arg).is_some() || arg.starts_with("ssh://") {
                     Some(arg)
                 } else {
                     log::error!("error parsing path argument: {}", error);
@@ -2,15 +2,18 @@ use anyhow::{anyhow, Context, Result};
 use cli::{ipc, IpcHandshake};
 use cli::{ipc::IpcSender, CliRequest, CliResponse};
 use collections::HashMap;
+use diff_view::DiffView;
 use editor::scroll::Autoscroll;
 use editor::Editor;
 use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender};
 use futures::channel::{mpsc, oneshot};
+use futures::future::FusedFuture;
 use futures::{FutureExt, SinkExt, StreamExt};
-use gpui::{AppContext, AsyncAppContext, Global};
+use gpui::{AppContext, AsyncAppContext, BackgroundExecutor, Global};
 use itertools::Itertools;
 use language::{Bias, Point};
 use release_channel::parse_zed_link;
+use remote::SshConnectionOptions;
 use std::path::Path;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
@@ -35,6 +38,10 @@ pub enum OpenRequest {
         channel_id: u64,
         heading: Option<String>,
     },
+    OpenSshProject {
+        options: SshConnectionOptions,
+        paths: Vec<String>,
+    },
 }
 
 pub struct OpenListener {
@@ -74,6 +81,8 @@ impl OpenListener {
             self.handle_cli_connection(server_name)
         } else if let Some(request_path) = urls.first().and_then(|url| parse_zed_link(url)) {
             self.handle_zed_url_scheme(request_path)
+        } else if urls.first().map_or(false, |url| url.starts_with("ssh://")) {
+            self.handle_ssh_urls(urls)
         } else {
             self.handle_file_urls(urls)
         };
@@ -94,6 +103,23 @@ impl OpenListener {
         None
     }
 
+    fn handle_ssh_urls(&self, urls: &[String]) -> Option<OpenRequest> {
+        let mut request: Option<(SshConnectionOptions, Vec<String>)> = None;
+        for url in urls {
+            let Some((options, path)) = SshConnectionOptions::parse_url(url).log_err() else {
+                continue;
+            };
+            match &mut request {
+                Some((first_options, paths)) if *first_options == options => paths.push(path),
+                Some(_) => log::error!("can't open paths on several hosts at once: {url}"),
+                None => request = Some((options, vec![path])),
+            }
+        }
+
+        let (options, paths) = request?;
+        Some(OpenRequest::OpenSshProject { options, paths })
+    }
+
     fn handle_zed_url_scheme(&self, request_path: &str) -> Option<OpenRequest> {
         let mut parts = request_path.split("/");
         if parts.next() == Some("channel") {
@@ -285,21 +311,7 @@ pub async fn handle_cli_connection(
                                     };
                                 }
                                 .fuse();
-                                futures::pin_mut!(wait);
-
-                                loop {
-                                    // Repeatedly check if CLI is still open to avoid wasting resources
-                                    // waiting for files or workspaces to close.
-                                    let mut timer = background.timer(Duration::from_secs(1)).fuse();
-                                    futures::select_biased! {
-                                        _ = wait => break,
-                                        _ = timer => {
-                                            if responses.send(CliResponse::Ping).is_err() {
-                                                break;
-                                            }
-                                        }
-                                    }
-                                }
+                                wait_while_cli_is_open(wait, &responses, &background).await;
                             }
                         }
                         Err(error) => {
@@ -320,6 +332,76 @@ pub async fn handle_cli_connection(
                     })
                     .log_err();
             }
+            CliRequest::Diff {
+                old_path,
+                new_path,
+                wait,
+            } => {
+                let result = async {
+                    let workspace =
+                        workspace::get_any_active_workspace(app_state, cx.clone()).await?;
+                    let diff_view = workspace
+                        .update(&mut cx, |workspace, cx| {
+                            DiffView::open_files(
+                                workspace,
+                                PathBuf::from(old_path),
+                                PathBuf::from(new_path),
+                                cx,
+                            )
+                        })?
+                        .await?;
+
+                    if wait {
+                        let (released_tx, released_rx) = oneshot::channel();
+                        cx.update(|cx| {
+                            cx.observe_release(&diff_view, move |_, _| {
+                                let _ = released_tx.send(());
+                            })
+                            .detach();
+                        })?;
+                        // Holding on to the view would keep it from being released.
+                        drop(diff_view);
+                        let background = cx.background_executor().clone();
+                        wait_while_cli_is_open(released_rx.fuse(), &responses, &background).await;
+                    }
+                    anyhow::Ok(())
+                }
+                .await;
+
+                if let Err(error) = &result {
+                    responses
+                        .send(CliResponse::Stderr {
+                            message: format!("error comparing files: {error:#}"),
+                        })
+                        .log_err();
+                }
+                responses
+                    .send(CliResponse::Exit {
+                        status: i32::from(result.is_err()),
+                    })
+                    .log_err();
+            }
+        }
+    }
+}
+
+/// Waits for the given future, unless the CLI exits first, which is checked every second
+/// to avoid wasting resources waiting for files or workspaces to close.
+async fn wait_while_cli_is_open(
+    wait: impl FusedFuture,
+    responses: &IpcSender<CliResponse>,
+    background: &BackgroundExecutor,
+) {
+    futures::pin_mut!(wait);
+    loop {
+        let mut timer = background.timer(Duration::from_secs(1)).fuse();
+        futures::select_biased! {
+            _ = wait => break,
+            _ = timer => {
+                if responses.send(CliResponse::Ping).is_err() {
+                    break;
+                }
+            }
         }
     }
 }
@@ -0,0 +1,116 @@
+//! The headless project server started on remote hosts over SSH.
+//!
+//! Usage: `zed-remote-server run [PATH...]`, which serves a project made of the given
+//! directories over standard input and output until the client disconnects. Logs are
+//! written to standard error, which the client shows in its own log.
+
+use std::{env, path::PathBuf, sync::Arc};
+
+use anyhow::{anyhow, Context as _, Result};
+use fs::RealFs;
+use futures::{channel::mpsc, StreamExt as _};
+use gpui::{App, AppContext, Task};
+use language::LanguageRegistry;
+use log::LevelFilter;
+use node_runtime::RealNodeRuntime;
+use remote_server::{Event, HeadlessProject};
+use settings::{default_settings, Settings, SettingsStore};
+use util::{http, paths, ResultExt as _};
+
+fn main() {
+    env_logger::Builder::new()
+        .filter_level(LevelFilter::Info)
+        .parse_default_env()
+        .target(env_logger::Target::Stderr)
+        .init();
+
+    let paths = match parse_args(env::args().skip(1)) {
+        Ok(paths) => paths,
+        Err(error) => {
+            eprintln!("{error:#}");
+            eprintln!("usage: {} run [PATH...]", remote::REMOTE_SERVER_BINARY);
+            std::process::exit(1);
+        }
+    };
+
+    log::info!("========== starting zed remote server ==========");
+    App::headless().run(move |cx| {
+        release_channel::init(env!("CARGO_PKG_VERSION"), cx);
+        let mut store = SettingsStore::default();
+        store
+            .set_default_settings(default_settings().as_ref(), cx)
+            .unwrap();
+        cx.set_global(store);
+        client::init_settings(cx);
+
+        if let Err(error) = serve(paths, cx) {
+            log::error!("failed to start the project server: {error:?}");
+            cx.quit();
+        }
+    });
+}
+
+fn serve(paths: Vec<PathBuf>, cx: &mut AppContext) -> Result<()> {
+    std::fs::create_dir_all(&*paths::LANGUAGES_DIR).context("could not create languages path")?;
+
+    let http = http::zed_client(&client::ClientSettings::get_global(cx).server_url);
+    let mut languages = LanguageRegistry::new(Task::ready(()));
+    languages.set_executor(cx.background_executor().clone());
+    languages.set_language_server_download_dir(paths::LANGUAGES_DIR.clone());
+    let languages = Arc::new(languages);
+    let node_runtime = RealNodeRuntime::new(http.clone());
+    language::init(cx);
+    zed::languages::init(languages.clone(), node_runtime.clone(), cx);
+
+    let connection = remote::stream_connection(
+        smol::Unblock::new(std::io::stdin()),
+        smol::Unblock::new(std::io::stdout()),
+    );
+    let headless = HeadlessProject::serve(
+        paths,
+        connection,
+        http,
+        languages,
+        node_runtime,
+        Arc::new(RealFs),
+        cx,
+    );
+    cx.spawn(|cx| async move {
+        let result = async {
+            let headless = headless.await?;
+            let (disconnected_tx, mut disconnected_rx) = mpsc::unbounded();
+            let _subscription = cx.update(|cx| {
+                cx.subscribe(&headless, move |_, event, _| match event {
+                    Event::Disconnected => {
+                        disconnected_tx.unbounded_send(()).ok();
+                    }
+                })
+            })?;
+            disconnected_rx.next().await;
+            log::info!("client disconnected");
+            anyhow::Ok(())
+        }
+        .await;
+        if let Err(error) = result {
+            log::error!("failed to serve the project: {error:?}");
+        }
+        cx.update(|cx| cx.quit()).log_err();
+    })
+    .detach();
+    Ok(())
+}
+
+/// Resolves the paths to serve, relative ones being relative to the directory the
+/// server was started in, which is the home directory when started over SSH.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Vec<PathBuf>> {
+    match args.next().as_deref() {
+        Some("run") => {}
+        Some(command) => return Err(anyhow!("unknown command {command:?}")),
+        None => return Err(anyhow!("missing command")),
+    }
+    let cwd = env::current_dir().context("failed to get the current directory")?;
+    args.map(|path| {
+        std::fs::canonicalize(cwd.join(&path)).with_context(|| format!("no such path {path:?}"))
+    })
+    .collect()
+}
@@ -7,6 +7,7 @@ pub use app_menus::*;
 use assistant::AssistantPanel;
 use breadcrumbs::Breadcrumbs;
 use collections::VecDeque;
+use debugger_ui::DebuggerPanel;
 use editor::{Editor, MultiBuffer};
 use gpui::{
     actions, point, px, AppContext, Context, FocusableView, PromptLevel, TitlebarOptions, View,
@@ -57,6 +58,7 @@ actions!(
         OpenLicenses,
         OpenLocalSettings,
         OpenLog,
+        OpenTasks,
         OpenTelemetryLog,
         ResetBufferFontSize,
         ResetDatabase,
@@ -126,6 +128,7 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
         let feedback_button =
             cx.new_view(|_| feedback::deploy_feedback_button::DeployFeedbackButton::new(workspace));
         let cursor_position = cx.new_view(|_| editor::items::CursorPosition::new());
+        let image_info = cx.new_view(|_| image_viewer::ImageInfo::new());
         workspace.status_bar().update(cx, |status_bar, cx| {
             status_bar.add_left_item(diagnostic_summary, cx);
             status_bar.add_left_item(activity_indicator, cx);
@@ -134,6 +137,7 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
             status_bar.add_right_item(active_buffer_language, cx);
             status_bar.add_right_item(vim_mode_indicator, cx);
             status_bar.add_right_item(cursor_position, cx);
+            status_bar.add_right_item(image_info, cx);
         });
 
         auto_update::notify_of_any_new_update(cx);
@@ -155,6 +159,7 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
             let project_panel = ProjectPanel::load(workspace_handle.clone(), cx.clone());
             let terminal_panel = TerminalPanel::load(workspace_handle.clone(), cx.clone());
             let assistant_panel = AssistantPanel::load(workspace_handle.clone(), cx.clone());
+            let debugger_panel = DebuggerPanel::load(workspace_handle.clone(), cx.clone());
             let channels_panel =
                 collab_ui::collab_panel::CollabPanel::load(workspace_handle.clone(), cx.clone());
             let chat_panel =
@@ -167,6 +172,7 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
                 project_panel,
                 terminal_panel,
                 assistant_panel,
+                debugger_panel,
                 channels_panel,
                 chat_panel,
                 notification_panel,
@@ -174,6 +180,7 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
                 project_panel,
                 terminal_panel,
                 assistant_panel,
+                debugger_panel,
                 channels_panel,
                 chat_panel,
                 notification_panel,
@@ -183,6 +190,7 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
                 workspace.add_panel(project_panel, cx);
                 workspace.add_panel(terminal_panel, cx);
                 workspace.add_panel(assistant_panel, cx);
+                workspace.add_panel(debugger_panel, cx);
                 workspace.add_panel(channels_panel, cx);
                 workspace.add_panel(chat_panel, cx);
                 workspace.add_panel(notification_panel, cx);
@@ -254,6 +262,15 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
                     );
                 },
             )
+            .register_action(
+                move |_: &mut Workspace, _: &OpenTasks, cx: &mut ViewContext<Workspace>| {
+                    open_settings_file(
+                        &paths::TASKS,
+                        || settings::initial_tasks_content().as_ref().into(),
+                        cx,
+                    );
+                },
+            )
             .register_action(open_local_settings_file)
             .register_action(
                 move |workspace: &mut Workspace,
@@ -2739,8 +2756,14 @@ mod tests {
             project_panel::init_settings(cx);
             collab_ui::init(&app_state, cx);
             project_panel::init((), cx);
+            file_history::init(cx);
             terminal_view::init(cx);
             assistant::init(cx);
+            debugger_ui::init(cx);
+            diff_view::init(cx);
+            hex_viewer::init(cx);
+            image_viewer::init(cx);
+            merge_conflicts::init(cx);
             initialize_workspace(app_state.clone(), cx);
             app_state
         })